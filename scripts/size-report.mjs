@@ -0,0 +1,239 @@
+// Reports what makes up the size of a release build: the largest crates in
+// the app binary, its sections, the bundles, and the frontend chunks.
+//
+//   npm run size-report                      # after `npm run tauri build`
+//   npm run size-report -- --baseline old.json
+//
+// Needs cargo-bloat (`cargo install cargo-bloat`) for the crate breakdown and
+// llvm-size (`rustup component add llvm-tools`) for sections; either part is
+// skipped with a note when its tool is missing. Writes size-report.json and
+// size-report.md to src-tauri/target/release/bundle/size-report, next to the
+// bundles they describe.
+
+import { execFileSync } from "node:child_process";
+import fs from "node:fs";
+import path from "node:path";
+import { fileURLToPath } from "node:url";
+
+const root = path.resolve(path.dirname(fileURLToPath(import.meta.url)), "..");
+const tauriDir = path.join(root, "src-tauri");
+const releaseDir = path.join(tauriDir, "target", "release");
+const outDir = path.join(releaseDir, "bundle", "size-report");
+const binaryName = process.platform === "win32" ? "Smudge.exe" : "Smudge";
+
+const TOP_CRATES = 30;
+const TOP_CHUNKS = 15;
+
+function formatBytes(bytes) {
+  const sign = bytes < 0 ? "-" : "";
+  let value = Math.abs(bytes);
+  for (const unit of ["B", "KiB", "MiB"]) {
+    if (value < 1024 || unit === "MiB") {
+      return `${sign}${unit === "B" ? value : value.toFixed(1)} ${unit}`;
+    }
+    value /= 1024;
+  }
+}
+
+function formatDelta(bytes, baseline) {
+  if (baseline === undefined) return "";
+  const delta = bytes - baseline;
+  return delta === 0 ? "±0" : `${delta > 0 ? "+" : ""}${formatBytes(delta)}`;
+}
+
+function sizeOf(target) {
+  const stat = fs.statSync(target);
+  if (!stat.isDirectory()) return stat.size;
+  return fs
+    .readdirSync(target)
+    .reduce((total, entry) => total + sizeOf(path.join(target, entry)), 0);
+}
+
+function run(command, args, options = {}) {
+  try {
+    return execFileSync(command, args, {
+      encoding: "utf8",
+      stdio: ["ignore", "pipe", "inherit"],
+      maxBuffer: 64 * 1024 * 1024,
+      ...options,
+    });
+  } catch {
+    return null;
+  }
+}
+
+// Crates by the code they put in the binary. Built with the size-report
+// profile, which is release with symbols kept.
+function crateSizes() {
+  if (!run("cargo", ["bloat", "--version"], { stdio: "pipe" })) return null;
+  const output = run(
+    "cargo",
+    [
+      "bloat",
+      "--profile",
+      "size-report",
+      "--bin",
+      "Smudge",
+      "--crates",
+      "-n",
+      String(TOP_CRATES),
+      "--message-format",
+      "json",
+    ],
+    { cwd: tauriDir },
+  );
+  if (!output) return null;
+  const bloat = JSON.parse(output);
+  return {
+    textSection: bloat["text-section-size"],
+    crates: bloat.crates.map(({ name, size }) => ({ name, size })),
+  };
+}
+
+function llvmSize() {
+  const sysroot = run("rustc", ["--print", "sysroot"])?.trim();
+  const host = run("rustc", ["-vV"])?.match(/^host: (.+)$/m)?.[1];
+  if (!sysroot || !host) return null;
+  const exe = process.platform === "win32" ? "llvm-size.exe" : "llvm-size";
+  const candidate = path.join(sysroot, "lib", "rustlib", host, "bin", exe);
+  return fs.existsSync(candidate) ? candidate : null;
+}
+
+// Sections of the shipped (stripped) binary, largest first
+function sectionSizes(binary) {
+  const tool = llvmSize();
+  if (!tool) return null;
+  const output = run(tool, ["-A", binary]);
+  if (!output) return null;
+  return output
+    .split("\n")
+    .map((line) => line.trim().split(/\s+/))
+    .filter(([name, size]) => name && /^\d+$/.test(size ?? "") && name !== "Total")
+    .map(([name, size]) => ({ name, size: Number(size) }))
+    .filter(({ size }) => size > 0)
+    .sort((a, b) => b.size - a.size);
+}
+
+// Installers and update payloads; the .app bundle is counted as a whole
+function bundleSizes() {
+  const bundleDir = path.join(releaseDir, "bundle");
+  if (!fs.existsSync(bundleDir)) return [];
+  return fs
+    .readdirSync(bundleDir)
+    .filter((kind) => kind !== "size-report")
+    .filter((kind) => fs.statSync(path.join(bundleDir, kind)).isDirectory())
+    .flatMap((kind) =>
+      fs
+        .readdirSync(path.join(bundleDir, kind))
+        .filter((name) => !name.endsWith(".sig"))
+        .map((name) => ({
+          name: `${kind}/${name}`,
+          size: sizeOf(path.join(bundleDir, kind, name)),
+        })),
+    )
+    .sort((a, b) => b.size - a.size);
+}
+
+function chunkSizes() {
+  const assetsDir = path.join(root, "dist", "assets");
+  if (!fs.existsSync(assetsDir)) return null;
+  return fs
+    .readdirSync(assetsDir)
+    .map((name) => ({
+      // Without the content hash, so chunks line up with a baseline
+      name: name.replace(/-[\w-]{8}(\.\w+)$/, "$1"),
+      size: sizeOf(path.join(assetsDir, name)),
+    }))
+    .sort((a, b) => b.size - a.size);
+}
+
+function readBaseline() {
+  const index = process.argv.indexOf("--baseline");
+  if (index === -1) return null;
+  const file = process.argv[index + 1];
+  if (!file) {
+    console.error("--baseline needs the path of an earlier size-report.json");
+    process.exit(1);
+  }
+  return JSON.parse(fs.readFileSync(file, "utf8"));
+}
+
+function table(rows, baselineRows) {
+  const previous = new Map((baselineRows ?? []).map((row) => [row.name, row.size]));
+  const lines = baselineRows
+    ? ["| | Size | Change |", "| --- | ---: | ---: |"]
+    : ["| | Size |", "| --- | ---: |"];
+  for (const { name, size } of rows) {
+    const cells = [`\`${name}\``, formatBytes(size)];
+    if (baselineRows) cells.push(formatDelta(size, previous.get(name)) || "new");
+    lines.push(`| ${cells.join(" | ")} |`);
+  }
+  return lines.join("\n");
+}
+
+function markdown(report, baseline) {
+  const lines = [`# Size report`, "", `${report.target}, ${report.generatedAt}`, ""];
+  const binaryDelta = formatDelta(report.binary.size, baseline?.binary?.size);
+  lines.push(
+    `App binary: **${formatBytes(report.binary.size)}**${binaryDelta ? ` (${binaryDelta})` : ""}`,
+    "",
+  );
+
+  lines.push("## Largest crates", "");
+  if (report.crates) {
+    lines.push(
+      `Code in the text section (${formatBytes(report.crates.textSection)} before stripping)`,
+      "",
+      table(report.crates.crates, baseline?.crates?.crates),
+    );
+  } else {
+    lines.push("_Skipped: install cargo-bloat with `cargo install cargo-bloat`_");
+  }
+
+  lines.push("", "## Sections", "");
+  if (report.sections) {
+    lines.push(table(report.sections, baseline?.sections));
+  } else {
+    lines.push("_Skipped: install llvm-size with `rustup component add llvm-tools`_");
+  }
+
+  lines.push("", "## Bundles", "");
+  lines.push(
+    report.bundles.length
+      ? table(report.bundles, baseline?.bundles)
+      : "_None: run `npm run tauri build` first_",
+  );
+
+  lines.push("", "## Frontend chunks", "");
+  lines.push(
+    report.chunks
+      ? table(report.chunks.slice(0, TOP_CHUNKS), baseline?.chunks)
+      : "_None: run `npm run build` first_",
+  );
+
+  return lines.join("\n") + "\n";
+}
+
+const binary = path.join(releaseDir, binaryName);
+if (!fs.existsSync(binary)) {
+  console.error(`No release build at ${binary}; run \`npm run tauri build\` first`);
+  process.exit(1);
+}
+
+const baseline = readBaseline();
+const report = {
+  generatedAt: new Date().toISOString(),
+  target: `${process.platform}-${process.arch}`,
+  binary: { name: binaryName, size: sizeOf(binary) },
+  crates: crateSizes(),
+  sections: sectionSizes(binary),
+  bundles: bundleSizes(),
+  chunks: chunkSizes(),
+};
+
+fs.mkdirSync(outDir, { recursive: true });
+fs.writeFileSync(path.join(outDir, "size-report.json"), JSON.stringify(report, null, 2) + "\n");
+const text = markdown(report, baseline);
+fs.writeFileSync(path.join(outDir, "size-report.md"), text);
+console.log(text);
+console.log(`Written to ${path.relative(root, outDir)}`);
@@ -1,3 +1,82 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
 fn main() {
+    file_types();
+    identifier();
     tauri_build::build()
 }
+
+/// Expose the bundle identifier as `SMUDGE_IDENTIFIER`, which names the app
+/// data folder. The command line (cli.rs) finds it without starting Tauri.
+fn identifier() {
+    let config = std::fs::read_to_string("tauri.conf.json").expect("read tauri.conf.json");
+    let config: serde_json::Value = serde_json::from_str(&config).expect("parse tauri.conf.json");
+    let identifier = config["identifier"]
+        .as_str()
+        .expect("identifier in tauri.conf.json");
+    println!("cargo:rustc-env=SMUDGE_IDENTIFIER={}", identifier);
+}
+
+/// Generate `FILE_TYPES` (file_associations.rs) from `fileAssociations` in
+/// tauri.conf.json, which the bundler also turns into the Info.plist
+/// document types and installer registrations, so the three can't drift
+/// apart. Every non-system UTI must be declared, smudge's own by the
+/// association's `exportedType` and the rest in Info.plist's
+/// `UTImportedTypeDeclarations`, or Launch Services won't know its
+/// extensions.
+fn file_types() {
+    println!("cargo:rerun-if-changed=tauri.conf.json");
+    println!("cargo:rerun-if-changed=Info.plist");
+
+    let config = std::fs::read_to_string("tauri.conf.json").expect("read tauri.conf.json");
+    let config: serde_json::Value = serde_json::from_str(&config).expect("parse tauri.conf.json");
+    let info_plist = std::fs::read_to_string("Info.plist").expect("read Info.plist");
+    let imported = info_plist
+        .split_once("<key>UTImportedTypeDeclarations</key>")
+        .map_or("", |(_, rest)| rest);
+
+    let associations = config["bundle"]["fileAssociations"]
+        .as_array()
+        .expect("bundle.fileAssociations in tauri.conf.json");
+    let mut out = String::from("pub const FILE_TYPES: &[FileType] = &[\n");
+    for association in associations {
+        let name = association["name"]
+            .as_str()
+            .expect("every file association has a name");
+        let mime_type = association["mimeType"]
+            .as_str()
+            .unwrap_or_else(|| panic!("file association {:?} has no mimeType", name));
+        let uti = association["contentTypes"][0]
+            .as_str()
+            .unwrap_or_else(|| panic!("file association {:?} has no contentTypes", name));
+        let exported = association["exportedType"]["identifier"].as_str() == Some(uti);
+        if !uti.starts_with("public.")
+            && !exported
+            && !imported.contains(&format!("<string>{}</string>", uti))
+        {
+            panic!("{} isn't in Info.plist's UTImportedTypeDeclarations", uti);
+        }
+        // The last part of a reverse-DNS UTI names the type
+        let id = uti.rsplit('.').next().unwrap_or(uti);
+        let extensions: Vec<&str> = association["ext"]
+            .as_array()
+            .unwrap_or_else(|| panic!("file association {:?} has no ext", name))
+            .iter()
+            .filter_map(|ext| ext.as_str())
+            .collect();
+        writeln!(
+            out,
+            concat!(
+                "    FileType {{ id: {:?}, name: {:?}, extensions: &{:?}, ",
+                "mime_type: {:?}, uti: {:?} }},"
+            ),
+            id, name, extensions, mime_type, uti
+        )
+        .unwrap();
+    }
+    out.push_str("];\n");
+
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR");
+    std::fs::write(Path::new(&out_dir).join("file_types.rs"), out).expect("write file_types.rs");
+}
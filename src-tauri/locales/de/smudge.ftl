@@ -0,0 +1,83 @@
+# smudge's strings in German. Missing keys fall back to en-US.
+
+## Menus
+
+menu-file = Ablage
+menu-edit = Bearbeiten
+menu-view = Darstellung
+menu-window = Fenster
+menu-new-note = Neue Notiz
+menu-new-from-template = Neu aus Vorlage
+menu-reload-note = Notiz neu laden
+menu-new-project = Neues Projekt …
+menu-open-project = Projekt öffnen …
+menu-open-recent = Benutzte Projekte öffnen
+menu-clear-recent = Einträge löschen
+menu-close-project = Projekt schließen
+menu-recent-projects = Benutzte Projekte
+menu-settings = Einstellungen
+menu-settings-ellipsis = Einstellungen …
+menu-toggle-sidebar = Seitenleiste ein-/ausblenden
+menu-command-palette = Befehlspalette
+menu-actual-size = Originalgröße
+menu-zoom-in = Vergrößern
+menu-zoom-out = Verkleinern
+menu-about = Über { $app }
+menu-services = Dienste
+menu-hide = { $app } ausblenden
+menu-hide-others = Andere ausblenden
+menu-show-all = Alle einblenden
+menu-quit = { $app } beenden
+menu-undo = Widerrufen
+menu-redo = Wiederholen
+menu-undo-action = „{ $action }“ widerrufen
+menu-redo-action = „{ $action }“ wiederholen
+menu-cut = Ausschneiden
+menu-copy = Kopieren
+menu-paste = Einsetzen
+menu-select-all = Alles auswählen
+menu-minimize = Im Dock ablegen
+menu-zoom = Zoomen
+menu-fullscreen = Vollbildmodus aktivieren
+menu-close-window = Fenster schließen
+
+## Notes
+
+note-untitled = Ohne Titel
+notes-count =
+    { $count ->
+        [0] Keine Notizen
+        [one] Eine Notiz
+       *[other] { $count } Notizen
+    }
+
+## Templates
+
+template-daily-journal = Tagebuch
+template-meeting-notes = Besprechungsnotizen
+template-project-plan = Projektplan
+
+## Dialogs
+
+dialog-insert-image-title = Bild einfügen
+dialog-insert-image-button = Einfügen
+dialog-export-notes-title =
+    { $count ->
+        [one] Notiz exportieren
+       *[other] { $count } Notizen exportieren
+    }
+dialog-export-notes-button = Exportieren
+dialog-export-format = Format:
+dialog-export-logs-title = Protokolle exportieren
+dialog-export-logs-button = Exportieren
+dialog-choose-notes-folder-title = Notizordner auswählen
+dialog-choose-notes-folder-button = Ordner verwenden
+dialog-add-watch-folder-title = Überwachten Ordner hinzufügen
+dialog-add-watch-folder-button = Überwachen
+dialog-open-project-title = Projekt öffnen
+dialog-open-project-button = Öffnen
+dialog-new-project-title = Neues Projekt
+dialog-new-project-button = Erstellen
+dialog-filter-images = Bilder
+dialog-filter-log = Protokoll
+dialog-filter-project = Smudge-Projekt
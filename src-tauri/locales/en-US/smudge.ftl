@@ -0,0 +1,85 @@
+# smudge's strings in US English, the fallback for every other catalog.
+# Keys are shared with the other catalogs in this folder; $app is the
+# app's name.
+
+## Menus
+
+menu-file = File
+menu-edit = Edit
+menu-view = View
+menu-window = Window
+menu-new-note = New Note
+menu-new-from-template = New from Template
+menu-reload-note = Reload Note
+menu-new-project = New Project…
+menu-open-project = Open Project…
+menu-open-recent = Open Recent
+menu-clear-recent = Clear Menu
+menu-close-project = Close Project
+menu-recent-projects = Recent Projects
+menu-settings = Settings
+menu-settings-ellipsis = Settings…
+menu-toggle-sidebar = Toggle Sidebar
+menu-command-palette = Command Palette
+menu-actual-size = Actual Size
+menu-zoom-in = Zoom In
+menu-zoom-out = Zoom Out
+menu-about = About { $app }
+menu-services = Services
+menu-hide = Hide { $app }
+menu-hide-others = Hide Others
+menu-show-all = Show All
+menu-quit = Quit { $app }
+menu-undo = Undo
+menu-redo = Redo
+menu-undo-action = Undo { $action }
+menu-redo-action = Redo { $action }
+menu-cut = Cut
+menu-copy = Copy
+menu-paste = Paste
+menu-select-all = Select All
+menu-minimize = Minimize
+menu-zoom = Zoom
+menu-fullscreen = Enter Full Screen
+menu-close-window = Close Window
+
+## Notes
+
+note-untitled = Untitled
+notes-count =
+    { $count ->
+        [0] No notes
+        [one] One note
+       *[other] { $count } notes
+    }
+
+## Templates
+
+template-daily-journal = Daily Journal
+template-meeting-notes = Meeting Notes
+template-project-plan = Project Plan
+
+## Dialogs
+
+dialog-insert-image-title = Insert Image
+dialog-insert-image-button = Insert
+dialog-export-notes-title =
+    { $count ->
+        [one] Export Note
+       *[other] Export { $count } Notes
+    }
+dialog-export-notes-button = Export
+dialog-export-format = Format:
+dialog-export-logs-title = Export Logs
+dialog-export-logs-button = Export
+dialog-choose-notes-folder-title = Choose Notes Folder
+dialog-choose-notes-folder-button = Use Folder
+dialog-add-watch-folder-title = Add Watch Folder
+dialog-add-watch-folder-button = Watch
+dialog-open-project-title = Open Project
+dialog-open-project-button = Open
+dialog-new-project-title = New Project
+dialog-new-project-button = Create
+dialog-filter-images = Images
+dialog-filter-log = Log
+dialog-filter-project = Smudge Project
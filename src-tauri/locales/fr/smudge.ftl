@@ -0,0 +1,83 @@
+# smudge's strings in French. Missing keys fall back to en-US.
+
+## Menus
+
+menu-file = Fichier
+menu-edit = Édition
+menu-view = Présentation
+menu-window = Fenêtre
+menu-new-note = Nouvelle note
+menu-new-from-template = Nouveau à partir d’un modèle
+menu-reload-note = Recharger la note
+menu-new-project = Nouveau projet…
+menu-open-project = Ouvrir un projet…
+menu-open-recent = Ouvrir l’élément récent
+menu-clear-recent = Effacer le menu
+menu-close-project = Fermer le projet
+menu-recent-projects = Projets récents
+menu-settings = Réglages
+menu-settings-ellipsis = Réglages…
+menu-toggle-sidebar = Afficher/masquer la barre latérale
+menu-command-palette = Palette de commandes
+menu-actual-size = Taille réelle
+menu-zoom-in = Zoom avant
+menu-zoom-out = Zoom arrière
+menu-about = À propos de { $app }
+menu-services = Services
+menu-hide = Masquer { $app }
+menu-hide-others = Masquer les autres
+menu-show-all = Tout afficher
+menu-quit = Quitter { $app }
+menu-undo = Annuler
+menu-redo = Rétablir
+menu-undo-action = Annuler { $action }
+menu-redo-action = Rétablir { $action }
+menu-cut = Couper
+menu-copy = Copier
+menu-paste = Coller
+menu-select-all = Tout sélectionner
+menu-minimize = Placer dans le Dock
+menu-zoom = Réduire/agrandir
+menu-fullscreen = Passer en mode plein écran
+menu-close-window = Fermer la fenêtre
+
+## Notes
+
+note-untitled = Sans titre
+notes-count =
+    { $count ->
+        [0] Aucune note
+        [one] Une note
+       *[other] { $count } notes
+    }
+
+## Templates
+
+template-daily-journal = Journal quotidien
+template-meeting-notes = Notes de réunion
+template-project-plan = Plan de projet
+
+## Dialogs
+
+dialog-insert-image-title = Insérer une image
+dialog-insert-image-button = Insérer
+dialog-export-notes-title =
+    { $count ->
+        [one] Exporter la note
+       *[other] Exporter { $count } notes
+    }
+dialog-export-notes-button = Exporter
+dialog-export-format = Format :
+dialog-export-logs-title = Exporter les journaux
+dialog-export-logs-button = Exporter
+dialog-choose-notes-folder-title = Choisir le dossier de notes
+dialog-choose-notes-folder-button = Utiliser ce dossier
+dialog-add-watch-folder-title = Ajouter un dossier surveillé
+dialog-add-watch-folder-button = Surveiller
+dialog-open-project-title = Ouvrir un projet
+dialog-open-project-button = Ouvrir
+dialog-new-project-title = Nouveau projet
+dialog-new-project-button = Créer
+dialog-filter-images = Images
+dialog-filter-log = Journal
+dialog-filter-project = Projet Smudge
@@ -0,0 +1,253 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sent to the focused window with an `Announcement` where there's no native
+/// way to make one, so the frontend can put it in a live region
+#[cfg(not(any(target_os = "macos", windows)))]
+pub const ANNOUNCEMENT_EVENT: &str = "accessibility-announcement";
+
+/// Last accessibility preferences sent to the frontend
+pub struct AccessibilityState {
+    high_contrast: AtomicBool,
+    reduce_motion: AtomicBool,
+    reduce_transparency: AtomicBool,
+    /// f64 bits
+    text_scale: AtomicU64,
+}
+
+impl AccessibilityState {
+    pub fn new() -> Self {
+        Self {
+            high_contrast: AtomicBool::new(is_high_contrast()),
+            reduce_motion: AtomicBool::new(reduces_motion()),
+            reduce_transparency: AtomicBool::new(reduces_transparency()),
+            text_scale: AtomicU64::new(text_scale().to_bits()),
+        }
+    }
+
+    pub fn high_contrast(&self) -> bool {
+        self.high_contrast.load(Ordering::SeqCst)
+    }
+
+    pub fn reduce_motion(&self) -> bool {
+        self.reduce_motion.load(Ordering::SeqCst)
+    }
+
+    pub fn reduce_transparency(&self) -> bool {
+        self.reduce_transparency.load(Ordering::SeqCst)
+    }
+
+    pub fn text_scale(&self) -> f64 {
+        f64::from_bits(self.text_scale.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(windows)]
+fn read_registry_value(key: &str, value: &str) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let output = std::process::Command::new("reg")
+        .args(["query", key, "/v", value])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .last()
+        .map(str::to_string)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn read_gsetting(schema: &str, key: &str) -> Option<String> {
+    let output = std::process::Command::new("gsettings")
+        .args(["get", schema, key])
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether the OS asks for high contrast: "Increase contrast" on macOS, a
+/// High Contrast theme on Windows, and the GNOME high-contrast a11y key on
+/// Linux.
+pub fn is_high_contrast() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        tauri_runtime_wry::tao::platform::macos::increases_contrast()
+    }
+    #[cfg(windows)]
+    {
+        // HCF_HIGHCONTRASTON is bit 0 of the Flags value
+        read_registry_value(r"HKCU\Control Panel\Accessibility\HighContrast", "Flags")
+            .and_then(|flags| flags.parse::<u32>().ok())
+            .is_some_and(|flags| flags & 1 != 0)
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        read_gsetting("org.gnome.desktop.a11y.interface", "high-contrast").as_deref()
+            == Some("true")
+    }
+}
+
+/// Whether the OS asks for less motion: "Reduce motion" on macOS, "Show
+/// animations in Windows" turned off, and GNOME's enable-animations turned off.
+pub fn reduces_motion() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        tauri_runtime_wry::tao::platform::macos::reduces_motion()
+    }
+    #[cfg(windows)]
+    {
+        read_registry_value(r"HKCU\Control Panel\Desktop\WindowMetrics", "MinAnimate").as_deref()
+            == Some("0")
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        read_gsetting("org.gnome.desktop.interface", "enable-animations").as_deref()
+            == Some("false")
+    }
+}
+
+/// Whether the OS asks for solid backgrounds instead of translucent ones:
+/// "Reduce transparency" on macOS and "Transparency effects" turned off on
+/// Windows. GNOME has no such setting.
+pub fn reduces_transparency() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        tauri_runtime_wry::tao::platform::macos::reduces_transparency()
+    }
+    #[cfg(windows)]
+    {
+        read_registry_value(
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+            "EnableTransparency",
+        )
+        .as_deref()
+            == Some("0x0")
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        false
+    }
+}
+
+/// The OS text size multiplier: "Make text bigger" on Windows and GNOME's
+/// text-scaling-factor. macOS has no system-wide text size for apps, so it's
+/// always 1 there.
+///
+/// Native menus and dialogs already follow the setting on Windows and Linux
+/// and can't be resized on macOS, so only the frontend needs to apply this.
+pub fn text_scale() -> f64 {
+    #[cfg(target_os = "macos")]
+    let scale: Option<f64> = None;
+    #[cfg(windows)]
+    // A percentage from 100 to 225, as a hex DWORD; missing until changed
+    let scale = read_registry_value(r"HKCU\Software\Microsoft\Accessibility", "TextScaleFactor")
+        .and_then(|value| u32::from_str_radix(value.trim_start_matches("0x"), 16).ok())
+        .map(|percent| percent as f64 / 100.0);
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let scale = read_gsetting("org.gnome.desktop.interface", "text-scaling-factor")
+        .and_then(|value| value.parse::<f64>().ok());
+
+    scale.filter(|s| s.is_finite() && *s > 0.0).unwrap_or(1.0)
+}
+
+/// Match native window animations to the reduce-motion preference. Windows
+/// and GTK already skip them when the system setting is off; AppKit keeps
+/// animating windows that open and close unless told otherwise.
+pub fn apply_window_animations(app: &AppHandle, reduce_motion: bool) {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = app.run_on_main_thread(move || {
+            tauri_runtime_wry::tao::platform::macos::set_window_animations_enabled(!reduce_motion)
+        });
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app, reduce_motion);
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Announcement {
+    pub message: String,
+    pub interrupt: bool,
+}
+
+/// Have the screen reader speak `message`: VoiceOver on macOS and Narrator
+/// (or any UI Automation client) on Windows. With `interrupt` it cuts off
+/// what's being spoken, like an assertive live region; otherwise it waits its
+/// turn. Elsewhere the focused window is sent `ANNOUNCEMENT_EVENT` instead.
+pub fn announce(app: &AppHandle, message: String, interrupt: bool) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        app.run_on_main_thread(move || {
+            tauri_runtime_wry::tao::platform::macos::announce(&message, interrupt)
+        })
+        .map_err(|e| e.to_string())
+    }
+    #[cfg(windows)]
+    {
+        let window = focused_window(app).ok_or("There's no window to announce from")?;
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?.0 as isize;
+        tauri_runtime_wry::tao::platform::windows::announce(hwnd, &message, interrupt)
+            .map_err(|e| format!("Couldn't announce: {}", e))
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        let window = focused_window(app).ok_or("There's no window to announce from")?;
+        window
+            .emit_to(
+                window.label(),
+                ANNOUNCEMENT_EVENT,
+                Announcement { message, interrupt },
+            )
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn focused_window(app: &AppHandle) -> Option<tauri::WebviewWindow> {
+    app.webview_windows()
+        .into_values()
+        .find(|window| window.is_focused().unwrap_or(false))
+        .or_else(|| app.get_webview_window("main"))
+}
+
+/// Re-read the preferences and emit "high-contrast-changed",
+/// "reduce-motion-changed", "reduce-transparency-changed" or
+/// "text-scale-changed" for the ones that changed.
+///
+/// macOS and Windows report changes to the display settings, but not to the
+/// text size, and Linux reports none, so they're also checked whenever a
+/// window regains focus (the user just came back from System Settings) or the
+/// system theme changes.
+pub fn refresh(app: &AppHandle) {
+    let Some(state) = app.try_state::<AccessibilityState>() else {
+        return;
+    };
+    let high_contrast = is_high_contrast();
+    if state.high_contrast.swap(high_contrast, Ordering::SeqCst) != high_contrast {
+        let _ = app.emit("high-contrast-changed", high_contrast);
+    }
+    let reduce_motion = reduces_motion();
+    if state.reduce_motion.swap(reduce_motion, Ordering::SeqCst) != reduce_motion {
+        apply_window_animations(app, reduce_motion);
+        let _ = app.emit("reduce-motion-changed", reduce_motion);
+    }
+    let reduce_transparency = reduces_transparency();
+    if state
+        .reduce_transparency
+        .swap(reduce_transparency, Ordering::SeqCst)
+        != reduce_transparency
+    {
+        let _ = app.emit("reduce-transparency-changed", reduce_transparency);
+    }
+    let text_scale = text_scale();
+    if f64::from_bits(
+        state
+            .text_scale
+            .swap(text_scale.to_bits(), Ordering::SeqCst),
+    ) != text_scale
+    {
+        let _ = app.emit("text-scale-changed", text_scale);
+    }
+}
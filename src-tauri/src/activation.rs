@@ -0,0 +1,67 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sent to every window with the new `ActivationState` when the app is
+/// activated, deactivated, hidden or shown again
+pub const EVENT: &str = "app-activation";
+
+/// A change reported by the OS. macOS only; elsewhere the app always counts
+/// as active and shown.
+pub enum Change {
+    Activated,
+    Deactivated,
+    Hidden,
+    Unhidden,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivationState {
+    /// Whether the app is the one receiving keyboard input
+    pub active: bool,
+    /// Whether the app is hidden, e.g. with Cmd+H, so none of its windows show
+    pub hidden: bool,
+}
+
+#[derive(Default)]
+pub struct Activation {
+    inactive: AtomicBool,
+    hidden: AtomicBool,
+}
+
+impl Activation {
+    pub fn state(&self) -> ActivationState {
+        ActivationState {
+            active: !self.inactive.load(Ordering::Relaxed),
+            hidden: self.hidden.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Whether work the user isn't waiting on should hold off
+    pub fn is_backgrounded(&self) -> bool {
+        let state = self.state();
+        !state.active || state.hidden
+    }
+}
+
+/// Whether the app is backgrounded; false before `Activation` is managed
+pub fn is_backgrounded(app: &AppHandle) -> bool {
+    app.try_state::<Activation>()
+        .is_some_and(|activation| activation.is_backgrounded())
+}
+
+/// Record `change` and tell every window, so they can pause rendering while
+/// the app is in the background and pick up again when it isn't
+pub fn changed(app: &AppHandle, change: Change) {
+    let Some(activation) = app.try_state::<Activation>() else {
+        return;
+    };
+    match change {
+        Change::Activated => activation.inactive.store(false, Ordering::Relaxed),
+        Change::Deactivated => activation.inactive.store(true, Ordering::Relaxed),
+        Change::Hidden => activation.hidden.store(true, Ordering::Relaxed),
+        Change::Unhidden => activation.hidden.store(false, Ordering::Relaxed),
+    }
+    let _ = app.emit(EVENT, activation.state());
+}
@@ -0,0 +1,36 @@
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager};
+
+/// Show `label` on the Dock icon, e.g. an unread count, or remove it with
+/// `None`. macOS only; elsewhere this does nothing.
+pub fn set_badge_label(app: &AppHandle, label: Option<String>) -> Result<(), String> {
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    let label = label.filter(|label| !label.is_empty());
+    #[cfg(target_os = "macos")]
+    window.set_badge_label(label).map_err(|e| e.to_string())?;
+    #[cfg(not(target_os = "macos"))]
+    let _ = (window, label);
+    Ok(())
+}
+
+/// Show `progress`, from 0 to 1, as a bar over the Dock icon on macOS and
+/// across the main window's taskbar button on Windows, or remove it with
+/// `None`
+pub fn set_progress(app: &AppHandle, progress: Option<f64>) -> Result<(), String> {
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    let state = match progress {
+        Some(progress) => ProgressBarState {
+            status: Some(ProgressBarStatus::Normal),
+            progress: Some((progress.clamp(0.0, 1.0) * 100.0).round() as u64),
+        },
+        None => ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: None,
+        },
+    };
+    window.set_progress_bar(state).map_err(|e| e.to_string())
+}
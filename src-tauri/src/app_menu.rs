@@ -0,0 +1,670 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::menu::{
+    AboutMetadata, CheckMenuItem, IsMenuItem, Menu, MenuId, MenuItem, MenuItemKind,
+    PredefinedMenuItem, Submenu,
+};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow, Wry};
+
+use crate::{i18n, menus, projects, templates, windows};
+
+/// Sent to the focused window, or the main window, with the frontend's id of
+/// the chosen item
+pub const EVENT: &str = "app-menu";
+
+/// The app menu's items share Tauri's menu event channel with every other
+/// menu, so their ids are prefixed
+const ITEM_ID_PREFIX: &str = "app:";
+
+/// The frontend's ids for the "New from Template" items: this, then the
+/// template's id
+const NEW_FROM_TEMPLATE_PREFIX: &str = "newFromTemplate:";
+
+/// An entry of the menu bar (macOS) or the main window's menu as the frontend
+/// describes it. The top level holds submenus.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum AppMenuItem {
+    Item {
+        id: String,
+        title: String,
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+        /// Shown with a checkmark column when set; only items created with
+        /// one can be checked later
+        #[serde(default)]
+        checked: Option<bool>,
+        /// Key equivalent, e.g. "CmdOrCtrl+N". Pressing it chooses the item
+        /// while it's enabled.
+        #[serde(default)]
+        accelerator: Option<String>,
+        /// Announced by screen readers instead of the title
+        #[serde(default)]
+        accessibility_title: Option<String>,
+        #[serde(default)]
+        help: Option<String>,
+    },
+    Submenu {
+        /// Needed to enable or re-title the submenu later
+        #[serde(default)]
+        id: Option<String>,
+        title: String,
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+        /// Lets macOS add its own items: window switching to the Window menu
+        /// and a search field to the Help menu
+        #[serde(default)]
+        role: Option<SubmenuRole>,
+        items: Vec<AppMenuItem>,
+    },
+    /// An item the OS implements, like Copy or Quit. Its title is the OS's
+    /// unless given.
+    Predefined {
+        item: PredefinedItem,
+        #[serde(default)]
+        title: Option<String>,
+    },
+    Separator,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubmenuRole {
+    Window,
+    Help,
+}
+
+/// Standard items, most of which only macOS implements. The rest are left
+/// out of the menu elsewhere.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PredefinedItem {
+    About,
+    Services,
+    Hide,
+    HideOthers,
+    ShowAll,
+    Quit,
+    Undo,
+    Redo,
+    Cut,
+    Copy,
+    Paste,
+    SelectAll,
+    Minimize,
+    Zoom,
+    Fullscreen,
+    CloseWindow,
+}
+
+/// A change to an item already in the menu. Fields left out stay as they are.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemUpdate {
+    pub id: String,
+    pub enabled: Option<bool>,
+    pub checked: Option<bool>,
+    pub title: Option<String>,
+}
+
+/// The menu as last set, with its items by the frontend's ids
+#[derive(Default)]
+pub struct AppMenu {
+    built: Mutex<Option<Built>>,
+}
+
+struct Built {
+    menu: Menu<Wry>,
+    items: HashMap<String, MenuItemKind<Wry>>,
+    /// Items given screen reader descriptions, forgotten when the menu is
+    /// replaced
+    described: Vec<String>,
+    /// Whether it's smudge's own menu rather than the frontend's, and so is
+    /// rebuilt when the language changes
+    default: bool,
+    /// The Edit menu's Undo and Redo, retitled for what they'd undo and redo
+    undo: Option<PredefinedMenuItem<Wry>>,
+    redo: Option<PredefinedMenuItem<Wry>>,
+}
+
+/// Replace the menu bar (macOS) or the main window's menu with `items`
+pub fn set(app: &AppHandle, items: &[AppMenuItem]) -> Result<(), String> {
+    let mut built = Built {
+        menu: Menu::new(app).map_err(|e| e.to_string())?,
+        items: HashMap::new(),
+        described: Vec::new(),
+        default: false,
+        undo: None,
+        redo: None,
+    };
+    let mut roles = Vec::new();
+    let children = build_items(app, items, &mut built, &mut roles).map_err(|e| e.to_string())?;
+    for child in &children {
+        built
+            .menu
+            .append(child.as_ref())
+            .map_err(|e| e.to_string())?;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        app.set_menu(built.menu.clone())
+            .map_err(|e| e.to_string())?;
+        // NSApp only takes these once the menu is in the menu bar
+        for (submenu, role) in &roles {
+            let _ = match role {
+                SubmenuRole::Window => submenu.set_as_windows_menu_for_nsapp(),
+                SubmenuRole::Help => submenu.set_as_help_menu_for_nsapp(),
+            };
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        // Only the main window has a menu bar; the mini window and tool
+        // windows stay bare
+        let _ = roles;
+        if let Some(window) = app.get_webview_window("main") {
+            window
+                .set_menu(built.menu.clone())
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let state = app.state::<AppMenu>();
+    let previous = state.built.lock().expect("app menu mutex").replace(built);
+    if let Some(previous) = previous {
+        menus::clear(&previous.described);
+    }
+    Ok(())
+}
+
+/// Show smudge's own menu, in the current language
+pub fn set_default(app: &AppHandle) -> Result<(), String> {
+    set(app, &default_items(app))?;
+    if let Some(built) = app
+        .state::<AppMenu>()
+        .built
+        .lock()
+        .expect("app menu mutex")
+        .as_mut()
+    {
+        built.default = true;
+    }
+    Ok(())
+}
+
+/// Rebuild smudge's own menu, in the language just switched to or with the
+/// recent projects just changed. A menu the frontend set is left for it to
+/// set again.
+pub fn rebuild(app: &AppHandle) {
+    let is_default = app
+        .try_state::<AppMenu>()
+        .and_then(|state| {
+            let built = state.built.lock().expect("app menu mutex");
+            built.as_ref().map(|built| built.default)
+        })
+        .unwrap_or(false);
+    if is_default {
+        if let Err(e) = set_default(app) {
+            log::warn!(error:% = e; "couldn't rebuild the app menu");
+        }
+    }
+}
+
+/// Give a newly created main window the menu (Windows and Linux, where each
+/// window has its own)
+pub fn attach(window: &WebviewWindow) {
+    if cfg!(target_os = "macos") {
+        return;
+    }
+    let Some(state) = window.try_state::<AppMenu>() else {
+        return;
+    };
+    let menu = match state.built.lock().expect("app menu mutex").as_ref() {
+        Some(built) => built.menu.clone(),
+        None => return,
+    };
+    if let Err(e) = window.set_menu(menu) {
+        log::warn!(error:% = e; "couldn't give the window its menu");
+    }
+}
+
+fn build_items(
+    app: &AppHandle,
+    items: &[AppMenuItem],
+    built: &mut Built,
+    roles: &mut Vec<(Submenu<Wry>, SubmenuRole)>,
+) -> tauri::Result<Vec<Box<dyn IsMenuItem<Wry>>>> {
+    let mut children: Vec<Box<dyn IsMenuItem<Wry>>> = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            AppMenuItem::Item {
+                id,
+                title,
+                enabled,
+                checked,
+                accelerator,
+                accessibility_title,
+                help,
+            } => {
+                let menu_id = format!("{}{}", ITEM_ID_PREFIX, id);
+                // Descriptions are read as the native item is created
+                if accessibility_title.is_some() || help.is_some() {
+                    menus::set_item_accessibility(
+                        &MenuId::new(&menu_id),
+                        accessibility_title.clone(),
+                        help.clone(),
+                    );
+                    built.described.push(menu_id.clone());
+                }
+                let accelerator = accelerator.as_deref();
+                let kind = match checked {
+                    Some(checked) => MenuItemKind::Check(CheckMenuItem::with_id(
+                        app,
+                        menu_id,
+                        title,
+                        *enabled,
+                        *checked,
+                        accelerator,
+                    )?),
+                    None => MenuItemKind::MenuItem(MenuItem::with_id(
+                        app,
+                        menu_id,
+                        title,
+                        *enabled,
+                        accelerator,
+                    )?),
+                };
+                built.items.insert(id.clone(), kind.clone());
+                children.push(Box::new(kind));
+            }
+            AppMenuItem::Submenu {
+                id,
+                title,
+                enabled,
+                role,
+                items,
+            } => {
+                let grandchildren = build_items(app, items, built, roles)?;
+                let grandchildren: Vec<&dyn IsMenuItem<Wry>> =
+                    grandchildren.iter().map(|item| item.as_ref()).collect();
+                let submenu = Submenu::with_items(app, title, *enabled, &grandchildren)?;
+                if let Some(role) = role {
+                    roles.push((submenu.clone(), *role));
+                }
+                if let Some(id) = id {
+                    built
+                        .items
+                        .insert(id.clone(), MenuItemKind::Submenu(submenu.clone()));
+                }
+                children.push(Box::new(submenu));
+            }
+            AppMenuItem::Predefined { item, title } => {
+                if let Some(native) = predefined(app, *item, title.as_deref())? {
+                    match item {
+                        PredefinedItem::Undo => built.undo = Some(native.clone()),
+                        PredefinedItem::Redo => built.redo = Some(native.clone()),
+                        _ => {}
+                    }
+                    children.push(Box::new(native));
+                }
+            }
+            AppMenuItem::Separator => children.push(Box::new(PredefinedMenuItem::separator(app)?)),
+        }
+    }
+    Ok(children)
+}
+
+fn predefined(
+    app: &AppHandle,
+    item: PredefinedItem,
+    title: Option<&str>,
+) -> tauri::Result<Option<PredefinedMenuItem<Wry>>> {
+    let macos_only = matches!(
+        item,
+        PredefinedItem::Services
+            | PredefinedItem::Hide
+            | PredefinedItem::HideOthers
+            | PredefinedItem::ShowAll
+            | PredefinedItem::Zoom
+            | PredefinedItem::Fullscreen
+    );
+    if macos_only && !cfg!(target_os = "macos") {
+        return Ok(None);
+    }
+    let item = match item {
+        PredefinedItem::About => {
+            let package = app.package_info();
+            let metadata = AboutMetadata {
+                name: Some(package.name.clone()),
+                version: Some(package.version.to_string()),
+                ..Default::default()
+            };
+            PredefinedMenuItem::about(app, title, Some(metadata))?
+        }
+        PredefinedItem::Services => PredefinedMenuItem::services(app, title)?,
+        PredefinedItem::Hide => PredefinedMenuItem::hide(app, title)?,
+        PredefinedItem::HideOthers => PredefinedMenuItem::hide_others(app, title)?,
+        PredefinedItem::ShowAll => PredefinedMenuItem::show_all(app, title)?,
+        PredefinedItem::Quit => PredefinedMenuItem::quit(app, title)?,
+        PredefinedItem::Undo => PredefinedMenuItem::undo(app, title)?,
+        PredefinedItem::Redo => PredefinedMenuItem::redo(app, title)?,
+        PredefinedItem::Cut => PredefinedMenuItem::cut(app, title)?,
+        PredefinedItem::Copy => PredefinedMenuItem::copy(app, title)?,
+        PredefinedItem::Paste => PredefinedMenuItem::paste(app, title)?,
+        PredefinedItem::SelectAll => PredefinedMenuItem::select_all(app, title)?,
+        PredefinedItem::Minimize => PredefinedMenuItem::minimize(app, title)?,
+        PredefinedItem::Zoom => PredefinedMenuItem::maximize(app, title)?,
+        PredefinedItem::Fullscreen => PredefinedMenuItem::fullscreen(app, title)?,
+        PredefinedItem::CloseWindow => PredefinedMenuItem::close_window(app, title)?,
+    };
+    Ok(Some(item))
+}
+
+/// Enable, check or re-title items already in the menu, by the frontend's ids
+pub fn update(app: &AppHandle, updates: Vec<ItemUpdate>) -> Result<(), String> {
+    let state = app.state::<AppMenu>();
+    let built = state.built.lock().expect("app menu mutex");
+    let Some(built) = built.as_ref() else {
+        return Err("The app menu hasn't been set".into());
+    };
+    for update in updates {
+        let Some(item) = built.items.get(&update.id) else {
+            return Err(format!("No app menu item \"{}\"", update.id));
+        };
+        apply(item, &update).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Title the Edit menu's Undo and Redo, e.g. "Undo Move Card"
+pub fn set_undo_titles(app: &AppHandle, undo: &str, redo: &str) {
+    let Some(state) = app.try_state::<AppMenu>() else {
+        return;
+    };
+    let built = state.built.lock().expect("app menu mutex");
+    let Some(built) = built.as_ref() else {
+        return;
+    };
+    for (item, title) in [(&built.undo, undo), (&built.redo, redo)] {
+        if let Some(item) = item {
+            let _ = item.set_text(title);
+        }
+    }
+}
+
+fn apply(item: &MenuItemKind<Wry>, update: &ItemUpdate) -> Result<(), String> {
+    let result = match item {
+        MenuItemKind::MenuItem(item) => {
+            if update.checked.is_some() {
+                return Err(format!("\"{}\" has no checkmark", update.id));
+            }
+            apply_common(update, |t| item.set_text(t), |e| item.set_enabled(e))
+        }
+        MenuItemKind::Check(item) => {
+            if let Some(checked) = update.checked {
+                item.set_checked(checked).map_err(|e| e.to_string())?;
+            }
+            apply_common(update, |t| item.set_text(t), |e| item.set_enabled(e))
+        }
+        MenuItemKind::Submenu(submenu) => {
+            apply_common(update, |t| submenu.set_text(t), |e| submenu.set_enabled(e))
+        }
+        _ => Ok(()),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+fn apply_common(
+    update: &ItemUpdate,
+    set_text: impl Fn(&str) -> tauri::Result<()>,
+    set_enabled: impl Fn(bool) -> tauri::Result<()>,
+) -> tauri::Result<()> {
+    if let Some(title) = &update.title {
+        set_text(title)?;
+    }
+    if let Some(enabled) = update.enabled {
+        set_enabled(enabled)?;
+    }
+    Ok(())
+}
+
+/// Items the backend carries out itself, in whichever menu the frontend
+/// puts them
+const ZOOM_IN: &str = "zoomIn";
+const ZOOM_OUT: &str = "zoomOut";
+const ACTUAL_SIZE: &str = "actualSize";
+/// Followed by the project's path
+const OPEN_RECENT_PROJECT_PREFIX: &str = "openRecentProject:";
+const CLEAR_RECENT_PROJECTS: &str = "clearRecentProjects";
+const CLOSE_PROJECT: &str = "closeProject";
+
+/// Handle a menu event if it came from the app menu: zoom the window it
+/// applies to, or pass the item's id to it
+pub fn handle(app: &AppHandle, id: &str) {
+    let Some(id) = id.strip_prefix(ITEM_ID_PREFIX) else {
+        return;
+    };
+    if let Some(path) = id.strip_prefix(OPEN_RECENT_PROJECT_PREFIX) {
+        if let Err(e) = projects::open_chosen(app, std::path::Path::new(path)) {
+            log::warn!(path = path, error:% = e; "recent project not opened");
+        }
+        return;
+    }
+    if id == CLEAR_RECENT_PROJECTS {
+        projects::clear_recent(app);
+        return;
+    }
+    let focused = app
+        .webview_windows()
+        .into_values()
+        .find(|window| window.is_focused().unwrap_or(false));
+    let Some(window) = focused.or_else(|| app.get_webview_window("main")) else {
+        return;
+    };
+    if id == CLOSE_PROJECT {
+        if let Err(e) = projects::close_window_project(app, window.label()) {
+            log::warn!(window = window.label(), error:% = e; "project not closed");
+        }
+        return;
+    }
+    let zoomed = match id {
+        ZOOM_IN => windows::zoom_in(&window),
+        ZOOM_OUT => windows::zoom_out(&window),
+        ACTUAL_SIZE => windows::reset_zoom(&window),
+        _ => {
+            let _ = window.emit_to(window.label(), EVENT, id);
+            return;
+        }
+    };
+    if let Err(e) = zoomed {
+        log::warn!(window = window.label(), error:% = e; "window not zoomed");
+    }
+}
+
+fn item(id: &str, title: &str, accelerator: Option<&str>) -> AppMenuItem {
+    AppMenuItem::Item {
+        id: id.into(),
+        title: title.into(),
+        enabled: true,
+        checked: None,
+        accelerator: accelerator.map(Into::into),
+        accessibility_title: None,
+        help: None,
+    }
+}
+
+fn submenu(title: &str, role: Option<SubmenuRole>, items: Vec<AppMenuItem>) -> AppMenuItem {
+    AppMenuItem::Submenu {
+        id: None,
+        title: title.into(),
+        enabled: true,
+        role,
+        items,
+    }
+}
+
+/// An item the OS implements, titled in the current language
+fn predefined_item(app: &AppHandle, item: PredefinedItem) -> AppMenuItem {
+    use PredefinedItem::*;
+
+    let name = app.package_info().name.clone();
+    let key = match item {
+        About => "menu-about",
+        Services => "menu-services",
+        Hide => "menu-hide",
+        HideOthers => "menu-hide-others",
+        ShowAll => "menu-show-all",
+        Quit => "menu-quit",
+        Undo => "menu-undo",
+        Redo => "menu-redo",
+        Cut => "menu-cut",
+        Copy => "menu-copy",
+        Paste => "menu-paste",
+        SelectAll => "menu-select-all",
+        Minimize => "menu-minimize",
+        Zoom => "menu-zoom",
+        Fullscreen => "menu-fullscreen",
+        CloseWindow => "menu-close-window",
+    };
+    AppMenuItem::Predefined {
+        item,
+        title: Some(i18n::text_with(app, key, &[("app", &name)])),
+    }
+}
+
+/// smudge's menu until the frontend sets its own: the app's commands with
+/// their shortcuts, and the standard Edit and Window menus, in the current
+/// language
+pub fn default_items(app: &AppHandle) -> Vec<AppMenuItem> {
+    use PredefinedItem::*;
+
+    let text = |key| i18n::text(app, key);
+    let predefined_item = |item| predefined_item(app, item);
+    let mut file = vec![
+        item("newNote", &text("menu-new-note"), Some("CmdOrCtrl+N")),
+        item("reloadNote", &text("menu-reload-note"), Some("CmdOrCtrl+R")),
+    ];
+    let templates: Vec<AppMenuItem> = templates::list(app)
+        .iter()
+        .map(|template| {
+            let id = format!("{}{}", NEW_FROM_TEMPLATE_PREFIX, template.id);
+            item(&id, &template.name, None)
+        })
+        .collect();
+    if !templates.is_empty() {
+        file.insert(1, submenu(&text("menu-new-from-template"), None, templates));
+    }
+    file.extend([
+        AppMenuItem::Separator,
+        item(
+            "newProject",
+            &text("menu-new-project"),
+            Some("CmdOrCtrl+Shift+N"),
+        ),
+        item(
+            "openProject",
+            &text("menu-open-project"),
+            Some("CmdOrCtrl+O"),
+        ),
+    ]);
+    let mut recent: Vec<AppMenuItem> = projects::recent(app)
+        .iter()
+        .map(|project| {
+            let id = format!("{}{}", OPEN_RECENT_PROJECT_PREFIX, project.path);
+            item(&id, &project.name, None)
+        })
+        .collect();
+    if !recent.is_empty() {
+        recent.extend([
+            AppMenuItem::Separator,
+            item(CLEAR_RECENT_PROJECTS, &text("menu-clear-recent"), None),
+        ]);
+        file.push(submenu(&text("menu-open-recent"), None, recent));
+    }
+    file.extend([
+        AppMenuItem::Separator,
+        item(CLOSE_PROJECT, &text("menu-close-project"), None),
+        predefined_item(CloseWindow),
+    ]);
+    if !cfg!(target_os = "macos") {
+        file.extend([
+            AppMenuItem::Separator,
+            item("settings", &text("menu-settings"), Some("CmdOrCtrl+,")),
+            AppMenuItem::Separator,
+            predefined_item(Quit),
+        ]);
+    }
+    let edit = vec![
+        predefined_item(Undo),
+        predefined_item(Redo),
+        AppMenuItem::Separator,
+        predefined_item(Cut),
+        predefined_item(Copy),
+        predefined_item(Paste),
+        predefined_item(SelectAll),
+    ];
+    let view = vec![
+        item(
+            "toggleSidebar",
+            &text("menu-toggle-sidebar"),
+            Some("CmdOrCtrl+\\"),
+        ),
+        item(
+            "commandPalette",
+            &text("menu-command-palette"),
+            Some("CmdOrCtrl+P"),
+        ),
+        AppMenuItem::Separator,
+        item(ACTUAL_SIZE, &text("menu-actual-size"), Some("CmdOrCtrl+0")),
+        item(ZOOM_IN, &text("menu-zoom-in"), Some("CmdOrCtrl+=")),
+        item(ZOOM_OUT, &text("menu-zoom-out"), Some("CmdOrCtrl+-")),
+        AppMenuItem::Separator,
+        predefined_item(Fullscreen),
+    ];
+
+    let mut menu = Vec::new();
+    if cfg!(target_os = "macos") {
+        menu.push(submenu(
+            "Smudge",
+            None,
+            vec![
+                predefined_item(About),
+                AppMenuItem::Separator,
+                item(
+                    "settings",
+                    &text("menu-settings-ellipsis"),
+                    Some("CmdOrCtrl+,"),
+                ),
+                AppMenuItem::Separator,
+                predefined_item(Services),
+                AppMenuItem::Separator,
+                predefined_item(Hide),
+                predefined_item(HideOthers),
+                predefined_item(ShowAll),
+                AppMenuItem::Separator,
+                predefined_item(Quit),
+            ],
+        ));
+    }
+    menu.push(submenu(&text("menu-file"), None, file));
+    menu.push(submenu(&text("menu-edit"), None, edit));
+    menu.push(submenu(&text("menu-view"), None, view));
+    if cfg!(target_os = "macos") {
+        menu.push(submenu(
+            &text("menu-window"),
+            Some(SubmenuRole::Window),
+            vec![predefined_item(Minimize), predefined_item(Zoom)],
+        ));
+    }
+    menu
+}
@@ -0,0 +1,47 @@
+use tauri::{AppHandle, Emitter, Manager, Theme};
+
+/// Sent to every window with "light" or "dark" when the app's effective
+/// appearance changes, whether the user toggled dark mode or smudge forced
+/// one
+pub const EVENT: &str = "appearance-changed";
+
+/// The theme a settings mode forces; "system" and anything unknown follow
+/// the OS
+pub fn parse(mode: Option<&str>) -> Option<Theme> {
+    match mode {
+        Some("light") => Some(Theme::Light),
+        Some("dark") => Some(Theme::Dark),
+        _ => None,
+    }
+}
+
+fn name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+/// Force every window light or dark, or let them follow the OS again with
+/// `None`
+pub fn set_app(app: &AppHandle, theme: Option<Theme>) {
+    app.set_theme(theme);
+    log::debug!(theme:? = theme; "app appearance set");
+}
+
+/// Force one window light or dark, or let it follow the app again
+pub fn set_window(app: &AppHandle, label: &str, theme: Option<Theme>) -> Result<(), String> {
+    let window = app
+        .get_webview_window(label)
+        .ok_or_else(|| format!("No window labelled {}", label))?;
+    window.set_theme(theme).map_err(|e| e.to_string())
+}
+
+/// The app's effective appearance changed: tell every window, and recheck
+/// the accessibility preferences, since turning on high contrast changes
+/// the appearance too
+pub fn changed(app: &AppHandle, theme: Theme) {
+    log::info!(theme = name(theme); "appearance changed");
+    crate::accessibility::refresh(app);
+    let _ = app.emit(EVENT, name(theme));
+}
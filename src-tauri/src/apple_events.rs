@@ -0,0 +1,236 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::batch::BatchOperation;
+
+/// Sent to the main window when AppleScript asks for something
+pub const EVENT: &str = "apple-event";
+
+/// smudge's own event class, as in `«event SmdgNewN»`. Smudge.sdef gives its
+/// events their AppleScript terms.
+const EVENT_CLASS: &[u8; 4] = b"Smdg";
+/// Create a note
+const NEW_NOTE: &[u8; 4] = b"NewN";
+/// Open the note whose id is the direct object
+const OPEN_NOTE: &[u8; 4] = b"OpnN";
+/// Export the note whose id is the direct object, replying with the written
+/// file's path
+const EXPORT_NOTE: &[u8; 4] = b"ExpN";
+/// Reply with how many windows are showing
+const WINDOW_COUNT: &[u8; 4] = b"WinC";
+
+/// Export's folder, a file reference
+const DESTINATION: &[u8; 4] = b"Dest";
+/// Export's format, an `export format` enumerator
+const FORMAT: &[u8; 4] = b"Frmt";
+const FORMAT_MARKDOWN: &str = "Mkdn";
+
+/// `errAEParamMissed`, `errAENoSuchObject` and `errAEEventFailed`
+const ERROR_PARAMETER_MISSING: i32 = -1715;
+const ERROR_NO_SUCH_OBJECT: i32 = -1728;
+const ERROR_FAILED: i32 = -10000;
+
+/// What the event asks the main window to do
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    tag = "action",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum AppleEventAction {
+    NewNote,
+    OpenNote { note_id: String },
+}
+
+/// An Apple Event as tao delivered it
+pub struct Received {
+    pub event_class: u32,
+    pub event_id: u32,
+    pub direct_object: Option<String>,
+    pub parameters: Vec<(u32, String)>,
+    /// Set for the events registered with a reply, whose sender waits for it
+    pub reply_id: Option<u64>,
+}
+
+impl Received {
+    fn parameter(&self, keyword: &[u8; 4]) -> Option<&str> {
+        let keyword = u32::from_be_bytes(*keyword);
+        self.parameters
+            .iter()
+            .find(|(k, _)| *k == keyword)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The answer a script gets back
+#[derive(Debug)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+enum Reply {
+    Text(String),
+    Integer(i32),
+    Error { number: i32, message: String },
+}
+
+impl Reply {
+    fn error(number: i32, message: impl Into<String>) -> Self {
+        Reply::Error {
+            number,
+            message: message.into(),
+        }
+    }
+}
+
+/// Start taking smudge's events from AppleScript, e.g.
+/// `tell application "Smudge" to export note "meeting-notes" to desktop`.
+/// macOS only; elsewhere this does nothing.
+pub fn register(app: &AppHandle) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    app.run_on_main_thread(|| {
+        use tauri_runtime_wry::tao::platform::macos;
+
+        let event_class = macos::four_char_code(EVENT_CLASS);
+        for event_id in [NEW_NOTE, OPEN_NOTE] {
+            macos::register_apple_event(event_class, macos::four_char_code(event_id));
+        }
+        for event_id in [EXPORT_NOTE, WINDOW_COUNT] {
+            macos::register_apple_event_with_reply(event_class, macos::four_char_code(event_id));
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app, EVENT_CLASS);
+    Ok(())
+}
+
+/// Handle an Apple Event if it's one of smudge's. Notes are shown by the
+/// main window; the rest runs the same code as the frontend's commands and
+/// replies with the result.
+pub fn handle(app: &AppHandle, event: Received) {
+    if event.event_class.to_be_bytes() != *EVENT_CLASS {
+        return;
+    }
+    match &event.event_id.to_be_bytes() {
+        NEW_NOTE => show(app, AppleEventAction::NewNote),
+        OPEN_NOTE => {
+            if let Some(note_id) = event.direct_object {
+                show(app, AppleEventAction::OpenNote { note_id });
+            }
+        }
+        EXPORT_NOTE => export_note(app, event),
+        WINDOW_COUNT => {
+            let count = app
+                .webview_windows()
+                .values()
+                .filter(|window| window.is_visible().unwrap_or(false))
+                .count();
+            reply(app, event.reply_id, Reply::Integer(count as i32));
+        }
+        _ => {}
+    }
+}
+
+/// Bring up the main window and tell it what was asked
+fn show(app: &AppHandle, action: AppleEventAction) {
+    log::debug!(action:? = action; "Apple Event");
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    let _ = window.emit(EVENT, action);
+}
+
+/// Export one note like the note list's Export, through `batch_process`
+fn export_note(app: &AppHandle, event: Received) {
+    let reply_id = event.reply_id;
+    let (Some(note_id), Some(destination)) =
+        (event.direct_object.clone(), event.parameter(DESTINATION))
+    else {
+        let missing = Reply::error(
+            ERROR_PARAMETER_MISSING,
+            "Give a note id and a folder to export to",
+        );
+        reply(app, reply_id, missing);
+        return;
+    };
+    let operation = match event.parameter(FORMAT) {
+        Some(FORMAT_MARKDOWN) => BatchOperation::Export {
+            destination: destination.to_string(),
+        },
+        _ => BatchOperation::ExportHtml {
+            destination: destination.to_string(),
+        },
+    };
+    log::debug!(note_id:% = note_id, destination:% = destination; "Apple Event export");
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let outcome = match exported(&app, &note_id, operation).await {
+            Ok(path) => Reply::Text(path),
+            Err(error) => error,
+        };
+        reply(&app, reply_id, outcome);
+    });
+}
+
+async fn exported(
+    app: &AppHandle,
+    note_id: &str,
+    operation: BatchOperation,
+) -> Result<String, Reply> {
+    let folder = app
+        .state::<crate::AppState>()
+        .app_config
+        .read()
+        .expect("app_config read lock")
+        .notes_folder
+        .clone()
+        .ok_or_else(|| Reply::error(ERROR_FAILED, "Smudge has no notes folder yet"))?;
+    let path = PathBuf::from(folder).join(format!("{}.md", note_id));
+    if !path.is_file() {
+        let message = format!("There's no note \"{}\"", note_id);
+        return Err(Reply::error(ERROR_NO_SUCH_OBJECT, message));
+    }
+    let paths = vec![path.to_string_lossy().into_owned()];
+    let report = crate::run_batch(app.clone(), paths, operation, None, app.state())
+        .await
+        .map_err(|e| Reply::error(ERROR_FAILED, e))?;
+    let result = report
+        .results
+        .into_iter()
+        .next()
+        .ok_or_else(|| Reply::error(ERROR_FAILED, "The export was cancelled"))?;
+    match result.output {
+        Some(output) if result.success => Ok(output),
+        _ => Err(Reply::error(
+            ERROR_FAILED,
+            result
+                .error
+                .unwrap_or_else(|| "Failed to export".to_string()),
+        )),
+    }
+}
+
+/// Answer an event registered with a reply; the others were answered when
+/// they arrived
+fn reply(app: &AppHandle, reply_id: Option<u64>, reply: Reply) {
+    let Some(reply_id) = reply_id else {
+        return;
+    };
+    log::debug!(reply_id, reply:? = reply; "Apple Event reply");
+    #[cfg(target_os = "macos")]
+    {
+        use tauri_runtime_wry::tao::platform::macos::{self, AppleEventReply};
+
+        let reply = match reply {
+            Reply::Text(text) => AppleEventReply::Text(text),
+            Reply::Integer(value) => AppleEventReply::Integer(value),
+            Reply::Error { number, message } => AppleEventReply::Error { number, message },
+        };
+        let _ = app.run_on_main_thread(move || macos::reply_to_apple_event(reply_id, reply));
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = app;
+}
@@ -0,0 +1,477 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use zip::write::SimpleFileOptions;
+use zip::{AesMode, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::jobs::{Cancelled, Job};
+
+/// Progress is reported, and pauses and cancels take effect, at least this
+/// often within a file
+const PROGRESS_STEP: u64 = 1024 * 1024;
+const CHUNK: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Format {
+    Zip,
+    TarGz,
+}
+
+impl Format {
+    /// The format an archive's name says it has
+    pub fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Format::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Format::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// What an archive held or was given
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+fn cancelled(Cancelled: Cancelled) -> String {
+    "Cancelled".to_string()
+}
+
+/// Bytes moved so far, reported to the job every `PROGRESS_STEP`
+struct Progress<'a> {
+    job: &'a Job,
+    done: u64,
+    reported: u64,
+}
+
+impl<'a> Progress<'a> {
+    fn new(job: &'a Job) -> Self {
+        Self {
+            job,
+            done: 0,
+            reported: 0,
+        }
+    }
+
+    /// Count `bytes`, and report and wait out a pause if a step has passed
+    fn add(&mut self, bytes: u64) -> Result<(), String> {
+        self.done += bytes;
+        self.report(false)
+    }
+
+    fn set(&mut self, done: u64) -> Result<(), String> {
+        self.done = done;
+        self.report(false)
+    }
+
+    fn report(&mut self, always: bool) -> Result<(), String> {
+        if always || self.done - self.reported >= PROGRESS_STEP {
+            self.reported = self.done;
+            self.job.set_completed(self.done as usize);
+            self.job.checkpoint_blocking().map_err(cancelled)?;
+        }
+        Ok(())
+    }
+}
+
+/// `io::copy` that passes `counted` the size of each chunk as it goes
+fn copy(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    mut counted: impl FnMut(u64) -> Result<(), String>,
+) -> Result<u64, String> {
+    let mut buffer = vec![0; CHUNK];
+    let mut total = 0;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(total),
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+        writer
+            .write_all(&buffer[..read])
+            .map_err(|e| e.to_string())?;
+        total += read as u64;
+        counted(read as u64)?;
+    }
+}
+
+/// A file to pack and its name in the archive
+struct Entry {
+    path: PathBuf,
+    name: String,
+    /// `None` for a directory
+    size: Option<u64>,
+}
+
+/// Everything under `sources`, each named from its own parent, so packing a
+/// folder puts its name at the top of the archive. Symbolic links are left
+/// out rather than followed.
+fn entries(sources: &[PathBuf]) -> Result<Vec<Entry>, String> {
+    fn walk(path: &Path, name: String, entries: &mut Vec<Entry>) -> Result<(), String> {
+        let metadata = fs::symlink_metadata(path).map_err(|e| e.to_string())?;
+        if metadata.is_file() {
+            entries.push(Entry {
+                path: path.to_path_buf(),
+                name,
+                size: Some(metadata.len()),
+            });
+        } else if metadata.is_dir() {
+            let mut children: Vec<_> = fs::read_dir(path)
+                .map_err(|e| e.to_string())?
+                .filter_map(Result::ok)
+                .collect();
+            children.sort_by_key(|child| child.file_name());
+            entries.push(Entry {
+                path: path.to_path_buf(),
+                name: name.clone(),
+                size: None,
+            });
+            for child in children {
+                let child_name = format!("{}/{}", name, child.file_name().to_string_lossy());
+                walk(&child.path(), child_name, entries)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut entries = Vec::new();
+    for source in sources {
+        let name = source
+            .file_name()
+            .ok_or_else(|| format!("{} can't be packed", source.display()))?
+            .to_string_lossy()
+            .into_owned();
+        walk(source, name, &mut entries)?;
+    }
+    Ok(entries)
+}
+
+/// Pack `sources`, files or folders, into an archive at `destination`, with
+/// progress in bytes read. Only zip archives take a `password`, which
+/// encrypts each file with AES-256. The archive is written aside and only
+/// replaces `destination` once it's whole.
+pub fn pack(
+    job: &Job,
+    sources: &[PathBuf],
+    destination: &Path,
+    format: Option<Format>,
+    password: Option<&str>,
+) -> Result<Summary, String> {
+    let format = format
+        .or_else(|| Format::of(destination))
+        .ok_or("Name the archive .zip, .tar.gz or .tgz, or pick its format")?;
+    if password.is_some() && format == Format::TarGz {
+        return Err("Only zip archives can have a password".to_string());
+    }
+    let entries = entries(sources)?;
+    let summary = Summary {
+        files: entries.iter().filter(|entry| entry.size.is_some()).count(),
+        bytes: entries.iter().filter_map(|entry| entry.size).sum(),
+    };
+    job.set_total(summary.bytes as usize);
+
+    let mut staged = destination.as_os_str().to_owned();
+    staged.push(".part");
+    let staged = PathBuf::from(staged);
+    let file = File::create(&staged).map_err(|e| e.to_string())?;
+    let written = match format {
+        Format::Zip => pack_zip(job, &entries, file, password),
+        Format::TarGz => pack_tar_gz(job, &entries, file),
+    };
+    match written.and_then(|()| fs::rename(&staged, destination).map_err(|e| e.to_string())) {
+        Ok(()) => Ok(summary),
+        Err(e) => {
+            let _ = fs::remove_file(&staged);
+            Err(e)
+        }
+    }
+}
+
+fn pack_zip(
+    job: &Job,
+    entries: &[Entry],
+    file: File,
+    password: Option<&str>,
+) -> Result<(), String> {
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(
+            entries
+                .iter()
+                .any(|entry| entry.size > Some(u32::MAX as u64)),
+        );
+    let options = match password {
+        Some(password) => options.with_aes_encryption(AesMode::Aes256, password),
+        None => options,
+    };
+    let mut progress = Progress::new(job);
+    for entry in entries {
+        job.checkpoint_blocking().map_err(cancelled)?;
+        if entry.size.is_none() {
+            zip.add_directory(entry.name.as_str(), options)
+                .map_err(|e| e.to_string())?;
+            continue;
+        }
+        let mut options = options;
+        if let Ok(modified) = fs::metadata(&entry.path).and_then(|m| m.modified()) {
+            let modified = time::OffsetDateTime::from(modified);
+            if let Ok(modified) = zip::DateTime::try_from(modified) {
+                options = options.last_modified_time(modified);
+            }
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Ok(metadata) = fs::metadata(&entry.path) {
+                options = options.unix_permissions(metadata.permissions().mode());
+            }
+        }
+        zip.start_file(entry.name.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        let mut reader = BufReader::new(File::open(&entry.path).map_err(|e| e.to_string())?);
+        copy(&mut reader, &mut zip, |read| progress.add(read))?;
+    }
+    zip.finish()
+        .map_err(|e| e.to_string())?
+        .flush()
+        .map_err(|e| e.to_string())?;
+    progress.report(true)
+}
+
+fn pack_tar_gz(job: &Job, entries: &[Entry], file: File) -> Result<(), String> {
+    let encoder = GzEncoder::new(BufWriter::new(file), flate2::Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    let mut progress = Progress::new(job);
+    for entry in entries {
+        job.checkpoint_blocking().map_err(cancelled)?;
+        if entry.size.is_none() {
+            tar.append_dir(&entry.name, &entry.path)
+                .map_err(|e| e.to_string())?;
+            continue;
+        }
+        let file = File::open(&entry.path).map_err(|e| e.to_string())?;
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&file.metadata().map_err(|e| e.to_string())?);
+        // Counted as the builder reads it in
+        let mut reader = Counted {
+            inner: BufReader::new(file),
+            progress: &mut progress,
+            error: None,
+        };
+        let appended = tar.append_data(&mut header, &entry.name, &mut reader);
+        if let Some(error) = reader.error {
+            return Err(error);
+        }
+        appended.map_err(|e| e.to_string())?;
+    }
+    tar.into_inner()
+        .and_then(|encoder| encoder.finish())
+        .and_then(|mut writer| writer.flush())
+        .map_err(|e| e.to_string())?;
+    progress.report(true)
+}
+
+/// A reader that counts into `progress`, for writers that do their own
+/// reading. A cancel fails the read and is kept in `error`.
+struct Counted<'a, 'b, R> {
+    inner: R,
+    progress: &'a mut Progress<'b>,
+    error: Option<String>,
+}
+
+impl<R: Read> Read for Counted<'_, '_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buffer)?;
+        if let Err(e) = self.progress.add(read as u64) {
+            self.error = Some(e.clone());
+            return Err(io::Error::other(e));
+        }
+        Ok(read)
+    }
+}
+
+/// A reader that keeps a shared count of what it's read, to follow a stream
+/// consumed by a decoder
+struct Tally<R> {
+    inner: R,
+    read: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for Tally<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buffer)?;
+        self.read.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+/// `name` as a path under the destination, or `None` if it would land
+/// anywhere else, as with `..` or an absolute path
+fn enclosed(name: &Path) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+fn escapes(name: &str) -> String {
+    format!("The archive's \"{}\" would land outside the folder", name)
+}
+
+/// Extract the archive at `archive` into the folder `destination`, with
+/// progress in bytes: written for zip archives, read for tar.gz ones, whose
+/// size isn't known ahead. An entry that would land outside `destination`
+/// fails the extraction, for zip archives before anything is written, and
+/// links are skipped. Files already there are replaced. What was extracted
+/// before a cancel stays.
+pub fn extract(
+    job: &Job,
+    archive: &Path,
+    destination: &Path,
+    password: Option<&str>,
+) -> Result<Summary, String> {
+    let format = Format::of(archive).ok_or("Only .zip, .tar.gz and .tgz archives can be opened")?;
+    if password.is_some() && format == Format::TarGz {
+        return Err("Only zip archives can have a password".to_string());
+    }
+    fs::create_dir_all(destination).map_err(|e| e.to_string())?;
+    let file = File::open(archive).map_err(|e| e.to_string())?;
+    match format {
+        Format::Zip => extract_zip(job, file, destination, password),
+        Format::TarGz => extract_tar_gz(job, file, destination),
+    }
+}
+
+fn zip_error(e: zip::result::ZipError) -> String {
+    match e {
+        zip::result::ZipError::InvalidPassword => "The password is wrong".to_string(),
+        zip::result::ZipError::UnsupportedArchive(message) if message.contains("assword") => {
+            "The archive needs a password".to_string()
+        }
+        e => e.to_string(),
+    }
+}
+
+fn extract_zip(
+    job: &Job,
+    file: File,
+    destination: &Path,
+    password: Option<&str>,
+) -> Result<Summary, String> {
+    let mut zip = ZipArchive::new(BufReader::new(file)).map_err(zip_error)?;
+    let mut total = 0;
+    for index in 0..zip.len() {
+        let entry = zip.by_index_raw(index).map_err(zip_error)?;
+        if enclosed(Path::new(entry.name())).is_none() {
+            return Err(escapes(entry.name()));
+        }
+        total += entry.size();
+    }
+    job.set_total(total as usize);
+
+    let mut progress = Progress::new(job);
+    let mut summary = Summary { files: 0, bytes: 0 };
+    for index in 0..zip.len() {
+        job.checkpoint_blocking().map_err(cancelled)?;
+        let mut entry = match password {
+            Some(password) => zip.by_index_decrypt(index, password.as_bytes()),
+            None => zip.by_index(index),
+        }
+        .map_err(zip_error)?;
+        let name = entry.name().to_string();
+        let path = destination.join(enclosed(Path::new(&name)).ok_or_else(|| escapes(&name))?);
+        if entry.is_dir() {
+            fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+            continue;
+        }
+        if entry.is_symlink() {
+            log::debug!(entry = name.as_str(); "link in archive skipped");
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut writer = BufWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+        let written = copy(&mut entry, &mut writer, |read| progress.add(read))?;
+        writer.flush().map_err(|e| e.to_string())?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o777));
+        }
+        summary.files += 1;
+        summary.bytes += written;
+    }
+    progress.report(true)?;
+    Ok(summary)
+}
+
+fn extract_tar_gz(job: &Job, file: File, destination: &Path) -> Result<Summary, String> {
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    job.set_total(size as usize);
+    let read = Arc::new(AtomicU64::new(0));
+    let reader = Tally {
+        inner: BufReader::new(file),
+        read: Arc::clone(&read),
+    };
+    let mut tar = tar::Archive::new(GzDecoder::new(reader));
+    let mut progress = Progress::new(job);
+    let mut summary = Summary { files: 0, bytes: 0 };
+    // Entries only come one after another, so one that escapes is found as
+    // it's reached, after those before it were written
+    for entry in tar.entries().map_err(|e| e.to_string())? {
+        job.checkpoint_blocking().map_err(cancelled)?;
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let name = entry.path().map_err(|e| e.to_string())?.into_owned();
+        let path =
+            destination.join(enclosed(&name).ok_or_else(|| escapes(&name.to_string_lossy()))?);
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => {
+                fs::create_dir_all(&path).map_err(|e| e.to_string())?;
+            }
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                let mut writer = BufWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+                let written = copy(&mut entry, &mut writer, |_| {
+                    progress.set(read.load(Ordering::Relaxed))
+                })?;
+                writer.flush().map_err(|e| e.to_string())?;
+                #[cfg(unix)]
+                if let Ok(mode) = entry.header().mode() {
+                    use std::os::unix::fs::PermissionsExt;
+                    let _ = fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o777));
+                }
+                summary.files += 1;
+                summary.bytes += written;
+            }
+            kind => {
+                log::debug!(entry:% = name.display(), kind:? = kind; "archive entry skipped");
+            }
+        }
+    }
+    progress.set(read.load(Ordering::Relaxed))?;
+    progress.report(true)?;
+    Ok(summary)
+}
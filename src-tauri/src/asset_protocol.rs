@@ -0,0 +1,445 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tauri::http::{header, HeaderValue, Request, Response, StatusCode};
+use tauri::ipc::InvokeBody;
+use tauri::{AppHandle, Manager, UriSchemeResponder};
+
+use crate::{executor, frontend_assets, thumbnails, AppState};
+
+/// Host of the `smudge` scheme's asset URLs: `smudge://asset/file/<path>`
+/// for a file, `smudge://asset/thumbnail/<size>/<path>` for a PNG thumbnail
+/// of one and `smudge://asset/memory/<name>` for data the backend holds.
+/// On Windows, where custom schemes are served over http, they're
+/// `http://smudge.localhost/asset/...`.
+pub const HOST: &str = "asset";
+
+/// Names the data `put_request` serves
+const NAME_HEADER: &str = "x-smudge-asset-name";
+/// Its content type, when the name's extension doesn't say
+const TYPE_HEADER: &str = "x-smudge-asset-type";
+
+/// Most bytes one range response carries. A video asking for everything
+/// from some offset gets this much and asks again for the rest.
+const MAX_RANGE: u64 = 4 * 1024 * 1024;
+/// Most bytes answered whole. Responses are held in memory, so anything
+/// bigger has to be asked for in ranges, as media elements do.
+const MAX_BODY: u64 = 64 * 1024 * 1024;
+/// Bytes looked at to tell what a file without a known extension is
+const SNIFF_BYTES: usize = 16;
+
+struct MemoryAsset {
+    bytes: Arc<Vec<u8>>,
+    content_type: String,
+    etag: String,
+}
+
+/// Serves files from the notes and watch folders, and data put in memory,
+/// to the webview under `smudge://asset/`. Responses support ranges, so media
+/// can seek, and carry ETags, so the webview revalidates instead of reloading.
+#[derive(Default)]
+pub struct AssetProtocol {
+    memory: Mutex<HashMap<String, MemoryAsset>>,
+}
+
+impl AssetProtocol {
+    /// Serve `bytes` at `smudge://asset/memory/<name>` until it's removed,
+    /// replacing whatever was there
+    pub fn put(&self, name: &str, bytes: Vec<u8>, content_type: Option<String>) {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        let content_type = content_type.unwrap_or_else(|| sniff(&bytes, name).to_string());
+        let asset = MemoryAsset {
+            bytes: Arc::new(bytes),
+            content_type,
+            etag: format!("\"{:016x}\"", hasher.finish()),
+        };
+        self.memory
+            .lock()
+            .expect("memory assets mutex")
+            .insert(name.to_string(), asset);
+    }
+
+    /// `put` for a command sent the bytes as its raw body, named by the
+    /// `x-smudge-asset-name` header
+    pub fn put_request(&self, request: &tauri::ipc::Request<'_>) -> Result<String, String> {
+        let header = |name| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let name = header(NAME_HEADER).ok_or("The asset needs a name")?;
+        if name.is_empty() || name.contains('/') {
+            return Err(format!("Asset name {:?} isn't allowed", name));
+        }
+        let InvokeBody::Raw(bytes) = request.body() else {
+            return Err("The asset's data has to be sent as raw bytes".to_string());
+        };
+        self.put(&name, bytes.clone(), header(TYPE_HEADER));
+        Ok(name)
+    }
+
+    pub fn remove(&self, name: &str) -> bool {
+        self.memory
+            .lock()
+            .expect("memory assets mutex")
+            .remove(name)
+            .is_some()
+    }
+
+    fn respond(&self, app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+        let path = percent_encoding::percent_decode_str(request.uri().path()).decode_utf8_lossy();
+        // Either smudge://asset/<kind>/... or <base>/asset/<kind>/...
+        let path = match request.uri().host() {
+            Some(HOST) => Some(path.as_ref()),
+            _ => path.strip_prefix("/asset"),
+        };
+        let Some(path) = path.and_then(|path| path.strip_prefix('/')) else {
+            return error(StatusCode::NOT_FOUND, "Not an asset URL");
+        };
+
+        let (kind, rest) = path.split_once('/').unwrap_or((path, ""));
+        match kind {
+            "file" => serve_file(app, request, Path::new(rest)),
+            "thumbnail" => serve_thumbnail(app, request, rest),
+            "memory" => {
+                let asset = {
+                    let memory = self.memory.lock().expect("memory assets mutex");
+                    memory.get(rest).map(|asset| {
+                        (
+                            asset.bytes.clone(),
+                            asset.content_type.clone(),
+                            asset.etag.clone(),
+                        )
+                    })
+                };
+                let Some((bytes, content_type, etag)) = asset else {
+                    return error(StatusCode::NOT_FOUND, rest);
+                };
+                let len = bytes.len() as u64;
+                respond_with(request, &content_type, &etag, len, |start, count| {
+                    let start = start as usize;
+                    Ok(bytes[start..start + count as usize].to_vec())
+                })
+            }
+            _ => error(StatusCode::NOT_FOUND, path),
+        }
+    }
+}
+
+/// Protocol handler for the `smudge` scheme. Reads happen on the executor,
+/// off the main thread.
+pub fn serve(app: &AppHandle, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let origin = request.headers().get(header::ORIGIN).cloned();
+        let ready = app.try_state::<AssetProtocol>().is_some();
+        let mut response = match app.try_state::<executor::Executor>() {
+            Some(executor) if ready => {
+                let app = app.clone();
+                executor
+                    .run(executor::Priority::Interactive, move || {
+                        app.state::<AssetProtocol>().respond(&app, &request)
+                    })
+                    .await
+                    .unwrap_or_else(|err| error(StatusCode::INTERNAL_SERVER_ERROR, &err))
+            }
+            _ => error(StatusCode::SERVICE_UNAVAILABLE, "Not ready"),
+        };
+        // Only the app's own pages may read what's served
+        if let Some(origin) = origin.filter(|origin| is_app_origin(&app, origin)) {
+            response
+                .headers_mut()
+                .insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        }
+        responder.respond(response);
+    });
+}
+
+/// Whether `origin` is where the app's pages are loaded from: its own scheme,
+/// or the dev server while developing
+fn is_app_origin(app: &AppHandle, origin: &HeaderValue) -> bool {
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    if [
+        "tauri://localhost",
+        "http://tauri.localhost",
+        "https://tauri.localhost",
+    ]
+    .contains(&origin)
+    {
+        return true;
+    }
+    tauri::is_dev()
+        && app
+            .config()
+            .build
+            .dev_url
+            .as_ref()
+            .is_some_and(|url| url.origin().ascii_serialization() == origin)
+}
+
+/// Folders whose files can be served: the notes folder and the watch
+/// folders, as they are now
+fn allowed_roots(app: &AppHandle) -> Vec<PathBuf> {
+    let state = app.state::<AppState>();
+    let mut roots: Vec<PathBuf> = Vec::new();
+    if let Some(folder) = &state
+        .app_config
+        .read()
+        .expect("app_config read lock")
+        .notes_folder
+    {
+        roots.push(PathBuf::from(folder));
+    }
+    if let Some(watch_folders) = &state
+        .settings
+        .read()
+        .expect("settings read lock")
+        .watch_folders
+    {
+        roots.extend(
+            watch_folders
+                .iter()
+                .map(|folder| PathBuf::from(&folder.path)),
+        );
+    }
+    roots
+        .into_iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .collect()
+}
+
+/// `path` resolved, if it's in one of the allowed folders
+fn allowed_path(app: &AppHandle, path: &Path) -> Result<PathBuf, (StatusCode, String)> {
+    // Resolving links and `..` first, so neither can reach outside the roots
+    let Ok(path) = path.canonicalize() else {
+        return Err((StatusCode::NOT_FOUND, path.to_string_lossy().into_owned()));
+    };
+    if !allowed_roots(app).iter().any(|root| path.starts_with(root)) {
+        log::warn!(path:? = path; "asset outside the allowed folders refused");
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Outside the allowed folders".to_string(),
+        ));
+    }
+    Ok(path)
+}
+
+fn serve_file(app: &AppHandle, request: &Request<Vec<u8>>, path: &Path) -> Response<Vec<u8>> {
+    let path = match allowed_path(app, path) {
+        Ok(path) => path,
+        Err((status, message)) => return error(status, &message),
+    };
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) => return error(StatusCode::NOT_FOUND, &e.to_string()),
+    };
+    let metadata = match file.metadata() {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return error(StatusCode::NOT_FOUND, &path.to_string_lossy()),
+    };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |modified| modified.as_nanos());
+    let etag = format!("\"{:x}-{:x}\"", metadata.len(), modified);
+
+    let mut head = [0u8; SNIFF_BYTES];
+    let sniffed = file.read(&mut head).unwrap_or(0);
+    let content_type = sniff(&head[..sniffed], &path.to_string_lossy());
+
+    respond_with(
+        request,
+        content_type,
+        &etag,
+        metadata.len(),
+        |start, count| {
+            let mut bytes = Vec::with_capacity(count as usize);
+            file.seek(SeekFrom::Start(start))?;
+            file.by_ref().take(count).read_to_end(&mut bytes)?;
+            Ok(bytes)
+        },
+    )
+}
+
+/// `<size>/<path>`: the thumbnail of a file in the allowed folders, at least
+/// `size` pixels along its longer side
+fn serve_thumbnail(app: &AppHandle, request: &Request<Vec<u8>>, rest: &str) -> Response<Vec<u8>> {
+    let Some((size, path)) = rest.split_once('/') else {
+        return error(StatusCode::NOT_FOUND, rest);
+    };
+    let Ok(size) = size.parse::<u32>() else {
+        return error(StatusCode::BAD_REQUEST, "Not a thumbnail size");
+    };
+    let path = match allowed_path(app, Path::new(path)) {
+        Ok(path) => path,
+        Err((status, message)) => return error(status, &message),
+    };
+    // Drawing a cloud placeholder's thumbnail would download it
+    if app.state::<AppState>().storage().is_placeholder(&path) {
+        return error(StatusCode::NOT_FOUND, "Not downloaded");
+    }
+    match app.state::<thumbnails::Thumbnails>().get(&path, size) {
+        Ok(Some(thumbnail)) => {
+            let png = thumbnail.png;
+            respond_with(
+                request,
+                "image/png",
+                &thumbnail.etag,
+                png.len() as u64,
+                |start, count| Ok(png[start as usize..(start + count) as usize].to_vec()),
+            )
+        }
+        Ok(None) => error(StatusCode::NOT_FOUND, "No thumbnail for this file"),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    }
+}
+
+/// Answer `request` for a body of `len` bytes, reading only the part it
+/// asks for with `read(start, count)`
+fn respond_with(
+    request: &Request<Vec<u8>>,
+    content_type: &str,
+    etag: &str,
+    len: u64,
+    read: impl FnOnce(u64, u64) -> std::io::Result<Vec<u8>>,
+) -> Response<Vec<u8>> {
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::ACCEPT_RANGES, "bytes");
+
+    let matches = |name: header::HeaderName| {
+        request
+            .headers()
+            .get(name)
+            .is_some_and(|tag| tag.as_bytes() == etag.as_bytes())
+    };
+    if matches(header::IF_NONE_MATCH) {
+        return builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Vec::new())
+            .unwrap_or_default();
+    }
+
+    // A range for a different version of the file gets the whole file
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|range| range.to_str().ok())
+        .filter(|_| !request.headers().contains_key(header::IF_RANGE) || matches(header::IF_RANGE))
+        .and_then(|range| parse_range(range, len));
+    let (status, start, count) = match range {
+        None if len > MAX_BODY => {
+            return error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Too large to load whole; ask for ranges",
+            );
+        }
+        None => (StatusCode::OK, 0, len),
+        Some(Err(())) => {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Vec::new())
+                .unwrap_or_default();
+        }
+        Some(Ok((start, end))) => (
+            StatusCode::PARTIAL_CONTENT,
+            start,
+            (end - start + 1).min(MAX_RANGE),
+        ),
+    };
+
+    let body = match read(start, count) {
+        Ok(body) => body,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
+    let mut builder = builder.status(status);
+    if status == StatusCode::PARTIAL_CONTENT {
+        // The file can shrink between reading its length and its bytes
+        let Some(end) = (start + body.len() as u64)
+            .checked_sub(1)
+            .filter(|&end| end >= start)
+        else {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Vec::new())
+                .unwrap_or_default();
+        };
+        builder = builder.header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", start, end, len),
+        );
+    }
+    builder.body(body).unwrap_or_default()
+}
+
+/// The first and last byte of a single `bytes=` range, `None` when there's
+/// no range to honour (several ranges are answered with the whole body) and
+/// `Some(Err)` when it starts past the end
+fn parse_range(range: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (len.saturating_sub(suffix), len.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, len.saturating_sub(1)),
+        (start, end) => (
+            start.parse().ok()?,
+            end.parse::<u64>().ok()?.min(len.saturating_sub(1)),
+        ),
+    };
+    if start >= len || start > end {
+        return Some(Err(()));
+    }
+    Some(Ok((start, end)))
+}
+
+/// By `name`'s extension, or from the first bytes when it has none smudge
+/// knows
+fn sniff(head: &[u8], name: &str) -> &'static str {
+    let by_extension = frontend_assets::content_type(name);
+    if by_extension != "application/octet-stream" {
+        return by_extension;
+    }
+    match head {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => "audio/wav",
+        [b'%', b'P', b'D', b'F', ..] => "application/pdf",
+        [_, _, _, _, b'f', b't', b'y', b'p', b'q', b't', ..] => "video/quicktime",
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => "video/mp4",
+        [0x1A, 0x45, 0xDF, 0xA3, ..] => "video/webm",
+        [b'I', b'D', b'3', ..] => "audio/mpeg",
+        [b'O', b'g', b'g', b'S', ..] => "audio/ogg",
+        _ => by_extension,
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(message.as_bytes().to_vec())
+        .unwrap_or_default()
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_runtime_wry::tao::audio::{self, AudioError, PlaybackState};
+
+/// Sent to every window with a `Command` when a media key is pressed while
+/// something is playing
+pub const MEDIA_EVENT: &str = "media-command";
+
+/// A sound for `play`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Sound {
+    /// One of the system's sounds, such as "Glass" on macOS or
+    /// "SystemAsterisk" on Windows
+    System {
+        name: String,
+    },
+    File {
+        path: String,
+    },
+}
+
+/// What the media preview is playing, as the system's controls show it.
+/// Times are in seconds.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NowPlaying {
+    title: String,
+    artist: Option<String>,
+    duration: Option<f64>,
+    #[serde(default)]
+    position: f64,
+    playing: bool,
+}
+
+/// A media key or control the user pressed
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Command {
+    Play,
+    Pause,
+    TogglePlayPause,
+    Stop,
+    NextTrack,
+    PreviousTrack,
+    /// The scrubber was dragged to `position` seconds
+    Seek {
+        position: f64,
+    },
+}
+
+fn message(error: AudioError) -> String {
+    match error {
+        AudioError::Unsupported => "Sounds aren't supported on this system".to_string(),
+        error => format!("Couldn't play the sound: {}", error),
+    }
+}
+
+fn seconds(seconds: f64) -> Duration {
+    Duration::try_from_secs_f64(seconds.max(0.0)).unwrap_or_default()
+}
+
+/// Run `f` on the main thread, which tao's audio calls need
+async fn on_main_thread<T: Send + 'static>(
+    app: &AppHandle,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.run_on_main_thread(move || {
+        let _ = tx.send(f());
+    })
+    .map_err(|e| e.to_string())?;
+    rx.await
+        .map_err(|_| "The main thread dropped the call".to_string())
+}
+
+/// Play `sound` without waiting for it to finish
+pub async fn play(app: &AppHandle, sound: Sound) -> Result<(), String> {
+    let sound = match sound {
+        Sound::System { name } => audio::Sound::System(name),
+        Sound::File { path } => audio::Sound::File(PathBuf::from(path)),
+    };
+    on_main_thread(app, move || audio::play_sound(&sound))
+        .await?
+        .map_err(message)
+}
+
+/// Set how loud smudge's sounds are, from 0 to 1: the app's volume in the
+/// Windows mixer, and the volume sounds play at on macOS
+pub fn set_volume(volume: f64) -> Result<(), String> {
+    audio::set_volume(volume as f32).map_err(message)
+}
+
+pub fn volume() -> f64 {
+    audio::volume() as f64
+}
+
+/// Show `now_playing` in the system's media controls and start sending the
+/// media keys to the windows, or with `None` clear them and stop
+pub async fn set_now_playing(
+    app: &AppHandle,
+    now_playing: Option<NowPlaying>,
+) -> Result<(), String> {
+    let now_playing = now_playing.map(|now_playing| audio::NowPlaying {
+        title: now_playing.title,
+        artist: now_playing.artist,
+        duration: now_playing.duration.map(seconds),
+        position: seconds(now_playing.position),
+        state: if now_playing.playing {
+            PlaybackState::Playing
+        } else {
+            PlaybackState::Paused
+        },
+    });
+    on_main_thread(app, move || audio::set_now_playing(now_playing.as_ref()))
+        .await?
+        .map_err(|e| match e {
+            AudioError::Unsupported => "Media controls aren't supported on this system".to_string(),
+            e => format!("Couldn't update the media controls: {}", e),
+        })
+}
+
+/// Tell the windows a media key was pressed
+pub fn command(app: &AppHandle, command: audio::MediaCommand) {
+    let command = match command {
+        audio::MediaCommand::Play => Command::Play,
+        audio::MediaCommand::Pause => Command::Pause,
+        audio::MediaCommand::TogglePlayPause => Command::TogglePlayPause,
+        audio::MediaCommand::Stop => Command::Stop,
+        audio::MediaCommand::NextTrack => Command::NextTrack,
+        audio::MediaCommand::PreviousTrack => Command::PreviousTrack,
+        audio::MediaCommand::Seek(position) => Command::Seek {
+            position: position.as_secs_f64(),
+        },
+        _ => return,
+    };
+    log::debug!(command:? = command; "media command");
+    let _ = app.emit(MEDIA_EVENT, command);
+}
@@ -0,0 +1,493 @@
+use base64::Engine;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::{Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Url};
+use tokio::net::TcpListener;
+use tokio::sync::{oneshot, Notify};
+
+use crate::{credentials, deep_link, http, preferences, sandbox};
+
+/// Host of the `smudge://` links providers redirect to with `Redirect::Scheme`
+const REDIRECT_HOST: &str = "oauth";
+/// Path of the redirect, on loopback and after the scheme's host alike
+const REDIRECT_PATH: &str = "/callback";
+/// How long the user has to finish signing in, in the browser
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// Access tokens this close to expiring are refreshed before they're used
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// How the browser hands the authorization code back
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Redirect {
+    /// `http://127.0.0.1:<port>/callback` on a port picked for the sign-in,
+    /// as RFC 8252 recommends for desktop apps
+    #[default]
+    Loopback,
+    /// `smudge://oauth/callback`, for providers that only take registered
+    /// redirect URIs without ports
+    Scheme,
+}
+
+/// An OAuth 2 authorization server an integration signs in to, as a public
+/// client with PKCE
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Provider {
+    /// Names the provider's tokens, e.g. "github"
+    pub id: String,
+    pub client_id: String,
+    pub authorization_url: String,
+    /// Has to be on an allowed HTTP host
+    pub token_url: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub redirect: Redirect,
+    /// More authorization parameters, such as `prompt` or `audience`
+    #[serde(default)]
+    pub extra_params: BTreeMap<String, String>,
+}
+
+/// What's kept in the keychain for a provider. The provider comes along so
+/// tokens can be refreshed knowing only its id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Tokens {
+    provider: Provider,
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Seconds since the Unix epoch; `None` when the provider didn't say
+    expires_at: Option<u64>,
+    scopes: Vec<String>,
+}
+
+/// A signed-in provider, without its tokens
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub provider: String,
+    /// When the current access token expires, in seconds since the Unix
+    /// epoch. It's refreshed as it's asked for.
+    pub expires_at: Option<u64>,
+    pub scopes: Vec<String>,
+    pub refreshable: bool,
+}
+
+impl From<&Tokens> for Session {
+    fn from(tokens: &Tokens) -> Self {
+        Self {
+            provider: tokens.provider.id.clone(),
+            expires_at: tokens.expires_at,
+            scopes: tokens.scopes.clone(),
+            refreshable: tokens.refresh_token.is_some(),
+        }
+    }
+}
+
+/// A token endpoint's answer, successful or not (RFC 6749 5.1 and 5.2)
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+    scope: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Sign-ins waiting for the browser, by their `state`, and a lock per
+/// provider so concurrent callers share one refresh
+#[derive(Default)]
+pub struct Auth {
+    pending: Mutex<HashMap<String, oneshot::Sender<Url>>>,
+    refreshing: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn random(bytes: usize) -> Result<String, String> {
+    let mut buffer = vec![0u8; bytes];
+    getrandom::fill(&mut buffer).map_err(|e| format!("Couldn't make a random value: {}", e))?;
+    Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(buffer))
+}
+
+/// The S256 challenge for `verifier` (RFC 7636 4.2)
+fn challenge(verifier: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+fn check_id(id: &str) -> Result<(), String> {
+    let valid = !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("\"{}\" isn't a provider id", id))
+    }
+}
+
+/// Whether `url` is a provider sending the browser back through the scheme
+pub fn is_redirect(url: &Url) -> bool {
+    url.scheme() == deep_link::SCHEME
+        && url.host_str() == Some(REDIRECT_HOST)
+        && url.path() == REDIRECT_PATH
+}
+
+/// Hand a `smudge://oauth/callback` link to the sign-in waiting for it.
+/// Returns whether one was.
+pub fn redirected(app: &AppHandle, url: &Url) -> bool {
+    let Some(state) = url
+        .query_pairs()
+        .find(|(name, _)| name == "state")
+        .map(|(_, state)| state.into_owned())
+    else {
+        return false;
+    };
+    let waiting = app.try_state::<Auth>().and_then(|auth| {
+        auth.pending
+            .lock()
+            .expect("auth pending mutex")
+            .remove(&state)
+    });
+    match waiting {
+        Some(sender) => sender.send(url.clone()).is_ok(),
+        None => {
+            log::warn!("OAuth redirect for no sign-in");
+            false
+        }
+    }
+}
+
+/// Sign in to `provider` in the system browser: authorize with PKCE, take
+/// the code from the redirect, exchange it, and keep the tokens in the
+/// keychain. Signing in again replaces them.
+pub async fn sign_in(app: &AppHandle, provider: Provider) -> Result<Session, String> {
+    check_id(&provider.id)?;
+    let token_url = http::client(app)?.check(&provider.token_url, &["https"])?;
+    let mut authorization_url = Url::parse(&provider.authorization_url)
+        .map_err(|e| format!("Invalid authorization URL: {}", e))?;
+    if authorization_url.scheme() != "https" {
+        return Err("The authorization URL has to be https".to_string());
+    }
+
+    let verifier = random(32)?;
+    let state = random(16)?;
+    let (sender, receiver) = oneshot::channel();
+    let redirect_uri = match provider.redirect {
+        Redirect::Loopback => {
+            sandbox::require("loopbackRedirect")?;
+            let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+                .await
+                .map_err(|e| format!("Couldn't listen for the redirect: {}", e))?;
+            let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+            tauri::async_runtime::spawn(listen(app.clone(), listener, state.clone(), sender));
+            format!("http://127.0.0.1:{}{}", port, REDIRECT_PATH)
+        }
+        Redirect::Scheme => {
+            app.state::<Auth>()
+                .pending
+                .lock()
+                .expect("auth pending mutex")
+                .insert(state.clone(), sender);
+            format!("{}://{}{}", deep_link::SCHEME, REDIRECT_HOST, REDIRECT_PATH)
+        }
+    };
+
+    {
+        let mut query = authorization_url.query_pairs_mut();
+        query
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &provider.client_id)
+            .append_pair("redirect_uri", &redirect_uri)
+            .append_pair("state", &state)
+            .append_pair("code_challenge", &challenge(&verifier))
+            .append_pair("code_challenge_method", "S256");
+        if !provider.scopes.is_empty() {
+            query.append_pair("scope", &provider.scopes.join(" "));
+        }
+        for (name, value) in &provider.extra_params {
+            query.append_pair(name, value);
+        }
+    }
+    log::info!(provider = provider.id.as_str(), redirect:? = provider.redirect; "OAuth sign-in started");
+    open::that(authorization_url.as_str())
+        .map_err(|e| format!("Couldn't open the browser: {}", e))?;
+
+    let waited = tokio::time::timeout(SIGN_IN_TIMEOUT, receiver).await;
+    // A scheme redirect that never came is still waiting
+    app.state::<Auth>()
+        .pending
+        .lock()
+        .expect("auth pending mutex")
+        .remove(&state);
+    let redirect = match waited {
+        Ok(Ok(redirect)) => redirect,
+        Ok(Err(_)) => return Err("The sign-in was abandoned".to_string()),
+        Err(_) => return Err("Signing in took too long".to_string()),
+    };
+
+    let params: HashMap<String, String> = redirect.query_pairs().into_owned().collect();
+    if params.get("state") != Some(&state) {
+        return Err("The redirect doesn't belong to this sign-in".to_string());
+    }
+    if let Some(error) = params.get("error") {
+        return Err(match params.get("error_description") {
+            Some(description) => format!("The sign-in failed: {} ({})", description, error),
+            None => format!("The sign-in failed: {}", error),
+        });
+    }
+    let code = params
+        .get("code")
+        .ok_or("The redirect has no authorization code")?;
+
+    let response = exchange(
+        app,
+        token_url,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &redirect_uri),
+            ("client_id", &provider.client_id),
+            ("code_verifier", &verifier),
+        ],
+    )
+    .await?;
+    let tokens = from_response(provider, response, None)?;
+    save(app, &tokens)?;
+    log::info!(provider = tokens.provider.id.as_str(); "OAuth sign-in finished");
+    Ok(Session::from(&tokens))
+}
+
+/// Answer the one redirect to `listener` carrying `state`, then stop
+/// listening. Other requests, like a browser's favicon fetch, get a 404.
+/// Each connection is served on its own task, so one that's left open
+/// can't hold up the redirect.
+async fn listen(
+    app: AppHandle,
+    listener: TcpListener,
+    state: String,
+    sender: oneshot::Sender<Url>,
+) {
+    let sender = Arc::new(Mutex::new(Some(sender)));
+    let state = Arc::new(state);
+    let answered = Arc::new(Notify::new());
+    let deadline = tokio::time::sleep(SIGN_IN_TIMEOUT);
+    tokio::pin!(deadline);
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    log::warn!(error:% = e; "OAuth redirect accept failed");
+                    continue;
+                }
+            },
+            _ = answered.notified() => return,
+            _ = &mut deadline => return,
+        };
+        let service = {
+            let app = app.clone();
+            let sender = Arc::clone(&sender);
+            let state = Arc::clone(&state);
+            let answered = Arc::clone(&answered);
+            hyper::service::service_fn(move |request| {
+                let response = answer(&app, &sender, &state, &answered, request);
+                async move { Ok::<_, Infallible>(response) }
+            })
+        };
+        let io = hyper_util::rt::TokioIo::new(stream);
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .keep_alive(false)
+                .serve_connection(io, service)
+                .await
+            {
+                log::debug!(error:% = e; "OAuth redirect connection failed");
+            }
+        });
+    }
+}
+
+fn answer(
+    app: &AppHandle,
+    sender: &Mutex<Option<oneshot::Sender<Url>>>,
+    state: &str,
+    answered: &Notify,
+    request: Request<Incoming>,
+) -> Response<Full<Bytes>> {
+    let page = |status: StatusCode, text: &str| {
+        let mut response = Response::new(Full::new(Bytes::from(format!(
+            "<!doctype html><meta charset=\"utf-8\"><title>Smudge</title><p>{}</p>",
+            text
+        ))));
+        *response.status_mut() = status;
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        response
+    };
+    if request.uri().path() != REDIRECT_PATH {
+        return page(StatusCode::NOT_FOUND, "Not found");
+    }
+    let url = Url::parse(&format!("http://127.0.0.1{}", request.uri()))
+        .expect("a request URI is a valid path and query");
+    // Another page can send the browser here too; only the provider's
+    // redirect knows the state, and anything else leaves the sign-in waiting
+    if !url
+        .query_pairs()
+        .any(|(name, value)| name == "state" && value == state)
+    {
+        return page(
+            StatusCode::BAD_REQUEST,
+            "This isn't the sign-in Smudge started.",
+        );
+    }
+    let Some(sender) = sender.lock().expect("auth redirect mutex").take() else {
+        return page(StatusCode::GONE, "This sign-in is already over.");
+    };
+    let _ = sender.send(url);
+    answered.notify_one();
+    // The browser tab is left behind; bring smudge back over it
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_focus();
+    }
+    page(
+        StatusCode::OK,
+        "You're signed in. You can close this tab and go back to Smudge.",
+    )
+}
+
+/// POST to the token endpoint and read its answer
+async fn exchange(
+    app: &AppHandle,
+    token_url: Url,
+    form: &[(&str, &str)],
+) -> Result<TokenResponse, String> {
+    let timeout = Duration::from_secs(preferences::current(app).network.request_timeout_secs);
+    let (status, body) = http::client(app)?
+        .post_form(token_url, form, timeout)
+        .await?;
+    let response: TokenResponse = serde_json::from_str(&body).map_err(|_| {
+        format!(
+            "The token endpoint answered {} with something other than JSON",
+            status
+        )
+    })?;
+    if let Some(error) = &response.error {
+        return Err(match &response.error_description {
+            Some(description) => format!("{} ({})", description, error),
+            None => error.clone(),
+        });
+    }
+    if !(200..300).contains(&status) {
+        return Err(format!("The token endpoint answered {}", status));
+    }
+    Ok(response)
+}
+
+/// What to keep from a token response. Refreshes may leave out the refresh
+/// token and scopes, which then stay as they were.
+fn from_response(
+    provider: Provider,
+    response: TokenResponse,
+    previous: Option<&Tokens>,
+) -> Result<Tokens, String> {
+    let access_token = response
+        .access_token
+        .ok_or("The token endpoint sent no access token")?;
+    let scopes = match response.scope {
+        Some(scope) => scope.split_whitespace().map(str::to_string).collect(),
+        None => previous.map_or_else(|| provider.scopes.clone(), |p| p.scopes.clone()),
+    };
+    Ok(Tokens {
+        access_token,
+        refresh_token: response
+            .refresh_token
+            .or_else(|| previous.and_then(|p| p.refresh_token.clone())),
+        expires_at: response.expires_in.map(|seconds| now() + seconds),
+        scopes,
+        provider,
+    })
+}
+
+fn save(app: &AppHandle, tokens: &Tokens) -> Result<(), String> {
+    let json = serde_json::to_string(tokens).map_err(|e| e.to_string())?;
+    credentials::store_oauth(app, &tokens.provider.id, &json)
+}
+
+fn load(app: &AppHandle, id: &str) -> Result<Option<Tokens>, String> {
+    check_id(id)?;
+    credentials::retrieve_oauth(app, id)?
+        .map(|json| {
+            serde_json::from_str(&json).map_err(|_| "The saved tokens are damaged".to_string())
+        })
+        .transpose()
+}
+
+/// The provider's session, if signed in
+pub fn session(app: &AppHandle, id: &str) -> Result<Option<Session>, String> {
+    Ok(load(app, id)?.as_ref().map(Session::from))
+}
+
+/// A current access token for the provider, refreshed first when it's
+/// about to expire. Fails when signed out, or when the refresh is refused,
+/// which needs signing in again.
+pub async fn access_token(app: &AppHandle, id: &str) -> Result<String, String> {
+    let lock = {
+        let auth = app.state::<Auth>();
+        let mut refreshing = auth.refreshing.lock().expect("auth refreshing mutex");
+        Arc::clone(refreshing.entry(id.to_string()).or_default())
+    };
+    // Another caller may have refreshed while this one waited
+    let _refreshing = lock.lock().await;
+    let tokens = load(app, id)?.ok_or_else(|| format!("Not signed in to {}", id))?;
+    let fresh = tokens
+        .expires_at
+        .is_none_or(|expires_at| expires_at > now() + REFRESH_MARGIN.as_secs());
+    if fresh {
+        return Ok(tokens.access_token);
+    }
+    let Some(refresh_token) = tokens.refresh_token.clone() else {
+        return Err(format!("The session with {} expired; sign in again", id));
+    };
+    let token_url = http::client(app)?.check(&tokens.provider.token_url, &["https"])?;
+    let response = exchange(
+        app,
+        token_url,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+            ("client_id", &tokens.provider.client_id),
+        ],
+    )
+    .await
+    .map_err(|e| format!("Couldn't refresh the session with {}: {}", id, e))?;
+    let refreshed = from_response(tokens.provider.clone(), response, Some(&tokens))?;
+    save(app, &refreshed)?;
+    log::debug!(provider = id; "OAuth tokens refreshed");
+    Ok(refreshed.access_token)
+}
+
+/// Forget the provider's tokens
+pub fn sign_out(app: &AppHandle, id: &str) -> Result<(), String> {
+    check_id(id)?;
+    credentials::delete_oauth(app, id)
+}
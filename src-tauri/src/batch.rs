@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::jobs::Job;
+
+/// Work applied to every file in a batch
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BatchOperation {
+    /// Import files into the notes folder through the ingest pipeline
+    Import {
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    /// Copy files as-is into `destination`
+    Export { destination: String },
+    /// Render markdown to standalone HTML files in `destination`
+    ExportHtml { destination: String },
+}
+
+/// Outcome for one file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemResult {
+    pub path: String,
+    pub success: bool,
+    /// Note ID or written file, depending on the operation
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Per-file report returned when the batch finishes or is cancelled
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub job_id: String,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Files never started because the job was cancelled
+    pub skipped: Vec<String>,
+    pub cancelled: bool,
+    pub results: Vec<BatchItemResult>,
+}
+
+/// Upper bound on files processed at once
+fn worker_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(2)
+        .clamp(1, 4)
+}
+
+/// Run `process` over `paths` on a bounded pool, honouring the job's
+/// pause/cancel state between files.
+pub async fn run<F, Fut>(job: Arc<Job>, paths: Vec<String>, process: F) -> BatchReport
+where
+    F: Fn(PathBuf) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = Result<String, String>> + Send + 'static,
+{
+    job.set_total(paths.len());
+    let permits = Arc::new(Semaphore::new(worker_count()));
+    let process = Arc::new(process);
+    let mut tasks = JoinSet::new();
+
+    for path in paths {
+        let job = Arc::clone(&job);
+        let permits = Arc::clone(&permits);
+        let process = Arc::clone(&process);
+        tasks.spawn(async move {
+            let Ok(_permit) = permits.acquire().await else {
+                return (path, None);
+            };
+            if job.checkpoint().await.is_err() {
+                return (path, None);
+            }
+            let result = process(PathBuf::from(&path)).await;
+            job.advance(Some(path.clone()));
+            (path, Some(result))
+        });
+    }
+
+    let mut results = Vec::new();
+    let mut skipped = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((path, Some(result))) => results.push(match result {
+                Ok(output) => BatchItemResult {
+                    path,
+                    success: true,
+                    output: Some(output),
+                    error: None,
+                },
+                Err(error) => BatchItemResult {
+                    path,
+                    success: false,
+                    output: None,
+                    error: Some(error),
+                },
+            }),
+            Ok((path, None)) => skipped.push(path),
+            Err(e) => results.push(BatchItemResult {
+                path: String::new(),
+                success: false,
+                output: None,
+                error: Some(e.to_string()),
+            }),
+        }
+    }
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+    BatchReport {
+        job_id: job.id.clone(),
+        succeeded,
+        failed: results.len() - succeeded,
+        cancelled: !skipped.is_empty(),
+        skipped,
+        results,
+    }
+}
+
+/// Copy `source` into `destination`, keeping its file name
+pub async fn export_file(source: &Path, destination: &Path) -> Result<String, String> {
+    let name = source.file_name().ok_or("Invalid file path")?;
+    let target = destination.join(name);
+    tokio::fs::copy(source, &target)
+        .await
+        .map_err(|e| format!("Failed to export: {}", e))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+/// Render a markdown file to `<destination>/<stem>.html`
+pub async fn export_html(
+    source: &Path,
+    destination: &Path,
+    title_of: fn(&str) -> String,
+) -> Result<String, String> {
+    let markdown = tokio::fs::read_to_string(source)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let html = render_html(&markdown, title_of);
+
+    let stem = source.file_stem().ok_or("Invalid file path")?;
+    let target = destination.join(format!("{}.html", stem.to_string_lossy()));
+    tokio::fs::write(&target, html)
+        .await
+        .map_err(|e| format!("Failed to write HTML: {}", e))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+/// A standalone HTML page for `markdown`
+pub fn render_html(markdown: &str, title_of: fn(&str) -> String) -> String {
+    render_page(&title_of(markdown), markdown)
+}
+
+/// A standalone HTML page for `markdown` titled `title`
+pub fn render_page(title: &str, markdown: &str) -> String {
+    let parser = pulldown_cmark::Parser::new_ext(markdown, pulldown_cmark::Options::all());
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, parser);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        body
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,145 @@
+use crate::permissions::{self, Kind};
+use base64::Engine;
+use std::path::Path;
+use tauri::WebviewWindow;
+use tauri_runtime_wry::tao::capture::{self, CaptureError};
+use tauri_runtime_wry::tao::dpi::{PhysicalPosition, PhysicalSize};
+
+/// What a capture command gives back: the PNG as a `data:` URL when it isn't
+/// written to a file, or the file's path when it is
+pub fn deliver(png: Vec<u8>, path: Option<&Path>) -> Result<String, String> {
+    match path {
+        Some(path) => {
+            std::fs::write(path, png).map_err(|e| {
+                format!("Couldn't save the screenshot to {}: {}", path.display(), e)
+            })?;
+            Ok(path.to_string_lossy().into_owned())
+        }
+        None => Ok(format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png)
+        )),
+    }
+}
+
+/// A failed capture as a message for the frontend. Missing permission opens
+/// its pane in System Settings, since only the user can turn it on.
+fn message(error: CaptureError) -> String {
+    match error {
+        CaptureError::PermissionDenied => {
+            permissions::request(Kind::ScreenRecording);
+            "Smudge needs Screen Recording access. Allow it in System Settings > Privacy & Security > Screen Recording, then restart Smudge."
+                .to_string()
+        }
+        CaptureError::Unsupported => "Screenshots aren't supported on this system".to_string(),
+        error => format!("Couldn't take the screenshot: {}", error),
+    }
+}
+
+/// `window` as it's drawn, even where other windows cover it, as PNG. Linux
+/// has no way to draw a single window, so there it's the screen area the
+/// window covers, whatever is on top.
+pub async fn window(window: &WebviewWindow) -> Result<Vec<u8>, String> {
+    platform::window(window).await.map_err(message)
+}
+
+/// Everything on the monitor named `name`, as `availableMonitors` names it,
+/// or on the one `window` is on, as PNG
+pub async fn screen(window: &WebviewWindow, name: Option<&str>) -> Result<Vec<u8>, String> {
+    let monitor = match name {
+        Some(name) => window
+            .available_monitors()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|m| m.name().map(String::as_str) == Some(name))
+            .ok_or_else(|| format!("No monitor named {}", name))?,
+        None => window
+            .current_monitor()
+            .map_err(|e| e.to_string())?
+            .ok_or("The window isn't on a monitor")?,
+    };
+    let position = PhysicalPosition::new(monitor.position().x, monitor.position().y);
+    let size = PhysicalSize::new(monitor.size().width, monitor.size().height);
+    area(window, position, size, monitor.scale_factor())
+        .await
+        .map_err(message)
+}
+
+/// `capture_area` on the main thread, which macOS needs
+async fn area(
+    window: &WebviewWindow,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    scale_factor: f64,
+) -> Result<Vec<u8>, CaptureError> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    window
+        .run_on_main_thread(move || {
+            let _ = tx.send(capture::capture_area(position, size, scale_factor));
+        })
+        .map_err(|e| CaptureError::Os(e.to_string()))?;
+    rx.await
+        .map_err(|_| CaptureError::Os("the capture was dropped".into()))?
+}
+
+/// CGWindowList through tao, with the window's number
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::capture::CaptureError;
+    use tauri_runtime_wry::tao::platform::macos;
+
+    pub async fn window(window: &WebviewWindow) -> Result<Vec<u8>, CaptureError> {
+        let ns_window = window
+            .ns_window()
+            .map_err(|e| CaptureError::Os(e.to_string()))? as usize;
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        window
+            .run_on_main_thread(move || {
+                let _ = tx.send(unsafe { macos::capture_window(ns_window as *mut c_void) });
+            })
+            .map_err(|e| CaptureError::Os(e.to_string()))?;
+        rx.await
+            .map_err(|_| CaptureError::Os("the capture was dropped".into()))?
+    }
+}
+
+/// PrintWindow through tao, which draws WebView2's content too
+#[cfg(windows)]
+mod platform {
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::capture::CaptureError;
+    use tauri_runtime_wry::tao::platform::windows;
+
+    pub async fn window(window: &WebviewWindow) -> Result<Vec<u8>, CaptureError> {
+        let hwnd = window
+            .hwnd()
+            .map_err(|e| CaptureError::Os(e.to_string()))?
+            .0 as isize;
+        tauri::async_runtime::spawn_blocking(move || windows::capture_window(hwnd))
+            .await
+            .map_err(|e| CaptureError::Os(e.to_string()))?
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::capture::CaptureError;
+    use tauri_runtime_wry::tao::dpi::{PhysicalPosition, PhysicalSize};
+
+    pub async fn window(window: &WebviewWindow) -> Result<Vec<u8>, CaptureError> {
+        let failed = |e: tauri::Error| CaptureError::Os(e.to_string());
+        let position = window.outer_position().map_err(failed)?;
+        let size = window.outer_size().map_err(failed)?;
+        let scale_factor = window.scale_factor().map_err(failed)?;
+        super::area(
+            window,
+            PhysicalPosition::new(position.x, position.y),
+            PhysicalSize::new(size.width, size.height),
+            scale_factor,
+        )
+        .await
+    }
+}
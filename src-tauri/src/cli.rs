@@ -0,0 +1,176 @@
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use crate::{batch, storage, SearchIndex};
+
+/// A command failed, or its arguments were wrong (as clap exits)
+const EXIT_FAILED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+/// smudge's subcommands, run without windows or the web view. A command
+/// line that doesn't start with one opens the app as usual, since the app
+/// is also launched with files and links as arguments.
+#[derive(Debug, Parser)]
+#[command(
+    name = "smudge",
+    version,
+    about = "Work with smudge notes from the command line"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Export markdown files, printing each written file's path
+    Export {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[arg(long, short, value_enum, default_value_t = Format::Html)]
+        format: Format,
+        /// Folder to write to, the current folder if not given
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// Manage the search index
+    Index {
+        #[command(subcommand)]
+        command: IndexCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum IndexCommand {
+    /// Index every note in the notes folder again
+    Rebuild {
+        /// Notes folder to index, the app's if not given
+        #[arg(long)]
+        folder: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    /// A standalone HTML page, as the app's Export
+    Html,
+    /// A copy of the markdown file
+    Markdown,
+}
+
+/// Whether `args` ask for a subcommand rather than the app
+fn is_command(args: &[OsString]) -> bool {
+    let Some(first) = args.get(1).and_then(|arg| arg.to_str()) else {
+        return false;
+    };
+    matches!(first, "help" | "--help" | "-h" | "--version" | "-V")
+        || Cli::command()
+            .get_subcommands()
+            .any(|command| command.get_name() == first)
+}
+
+/// Run the subcommand `args` name. Returns its exit code, or None if they
+/// don't name one and the app should start.
+pub fn run(args: Vec<OsString>) -> Option<i32> {
+    if !is_command(&args) {
+        return None;
+    }
+    attach_console();
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return Some(if e.use_stderr() { EXIT_USAGE } else { 0 });
+        }
+    };
+    let outcome = match cli.command {
+        Command::Export { files, format, out } => export(&files, format, out),
+        Command::Index {
+            command: IndexCommand::Rebuild { folder },
+        } => rebuild_index(folder),
+    };
+    Some(match outcome {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("smudge: {}", e);
+            EXIT_FAILED
+        }
+    })
+}
+
+/// Export every file, going on past failures, which are reported together
+fn export(files: &[PathBuf], format: Format, out: Option<PathBuf>) -> Result<(), String> {
+    let destination = match out {
+        Some(out) => out,
+        None => std::env::current_dir().map_err(|e| e.to_string())?,
+    };
+    std::fs::create_dir_all(&destination)
+        .map_err(|e| format!("Failed to create export folder: {}", e))?;
+
+    let mut failed = 0;
+    for file in files {
+        let exported = tauri::async_runtime::block_on(async {
+            match format {
+                Format::Html => batch::export_html(file, &destination, crate::extract_title).await,
+                Format::Markdown => batch::export_file(file, &destination).await,
+            }
+        });
+        match exported {
+            Ok(path) => println!("{}", path),
+            Err(e) => {
+                eprintln!("smudge: {}: {}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        1 => Err("1 file failed to export".to_string()),
+        n => Err(format!("{} files failed to export", n)),
+    }
+}
+
+/// The app's data folder, where Tauri's `app_data_dir` puts it
+fn app_data_dir() -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join(env!("SMUDGE_IDENTIFIER")))
+        .ok_or_else(|| "No data folder for this user".to_string())
+}
+
+fn rebuild_index(folder: Option<PathBuf>) -> Result<(), String> {
+    let app_data = app_data_dir()?;
+    let folder = match folder {
+        Some(folder) => folder,
+        None => crate::read_app_config(&app_data.join("config.json"))
+            .notes_folder
+            .map(PathBuf::from)
+            .ok_or("Smudge has no notes folder yet; pass --folder")?,
+    };
+    if !folder.is_dir() {
+        return Err(format!("{} isn't a folder", folder.display()));
+    }
+
+    // The running app holds the index's writer lock
+    let search_index = SearchIndex::new(&app_data.join("search_index")).map_err(|e| {
+        format!(
+            "Failed to open the search index (is Smudge running?): {}",
+            e
+        )
+    })?;
+    search_index
+        .rebuild_index(&folder, storage::detect(&folder).as_ref())
+        .map_err(|e| e.to_string())?;
+    println!("Indexed {}", folder.display());
+    Ok(())
+}
+
+/// Release builds on Windows are GUI programs with no console of their own,
+/// so print to the one they were started from
+fn attach_console() {
+    #[cfg(windows)]
+    unsafe {
+        use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
@@ -0,0 +1,182 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_runtime_wry::tao::clipboard::{self, ClipboardContents, ClipboardError, ClipboardFormat};
+
+use crate::fs_scope::{self, Access, FsScope, GrantSource};
+
+/// Sent to every window with a `ClipboardChanged` when the clipboard's
+/// contents change, in smudge or another app
+pub const EVENT: &str = "clipboard-changed";
+
+/// The clipboard doesn't announce changes, so its change count is polled
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What the clipboard holds, in each format it holds it in
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardData {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub html: Option<String>,
+    #[serde(default)]
+    pub rtf: Option<String>,
+    /// Base64-encoded PNG
+    #[serde(default)]
+    pub png: Option<String>,
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Format {
+    Text,
+    Html,
+    Rtf,
+    Png,
+    Files,
+}
+
+impl From<ClipboardFormat> for Format {
+    fn from(format: ClipboardFormat) -> Self {
+        match format {
+            ClipboardFormat::Text => Self::Text,
+            ClipboardFormat::Html => Self::Html,
+            ClipboardFormat::Rtf => Self::Rtf,
+            ClipboardFormat::Png => Self::Png,
+            ClipboardFormat::Files => Self::Files,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClipboardChanged {
+    change_count: u64,
+    formats: Vec<Format>,
+}
+
+/// Everything on the clipboard. Copied files become readable by commands,
+/// as if they'd been dropped. Linux only has text.
+pub fn read(app: &AppHandle) -> Result<ClipboardData, String> {
+    let contents = match clipboard::read() {
+        Ok(contents) => contents,
+        Err(ClipboardError::Unsupported) => {
+            return Ok(ClipboardData {
+                text: app.clipboard().read_text().ok(),
+                ..Default::default()
+            })
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+    let scope = app.state::<FsScope>();
+    for path in &contents.files {
+        scope.grant(app, path, Access::Read, GrantSource::Pasted);
+    }
+    Ok(ClipboardData {
+        text: contents.text,
+        html: contents.html,
+        rtf: contents.rtf,
+        png: contents
+            .png
+            .map(|png| base64::engine::general_purpose::STANDARD.encode(png)),
+        files: contents.files,
+    })
+}
+
+/// Replace what the clipboard holds with `data`. Files have to be readable
+/// by commands. Linux only takes text and HTML.
+pub fn write(app: &AppHandle, data: ClipboardData) -> Result<(), String> {
+    let files = data
+        .files
+        .iter()
+        .map(|path| fs_scope::check(app, &path.to_string_lossy(), Access::Read))
+        .collect::<Result<Vec<_>, _>>()?;
+    let png = data
+        .png
+        .map(|png| {
+            base64::engine::general_purpose::STANDARD
+                .decode(png)
+                .map_err(|e| format!("Failed to decode base64: {}", e))
+        })
+        .transpose()?;
+    let contents = ClipboardContents {
+        text: data.text,
+        html: data.html,
+        rtf: data.rtf,
+        png,
+        files,
+    };
+    log::debug!(
+        text = contents.text.is_some(),
+        html = contents.html.is_some(),
+        rtf = contents.rtf.is_some(),
+        png = contents.png.is_some(),
+        files = contents.files.len();
+        "clipboard written"
+    );
+    match clipboard::write(&contents) {
+        Ok(()) => Ok(()),
+        Err(ClipboardError::Unsupported) => match (contents.html, contents.text) {
+            (Some(html), text) => app.clipboard().write_html(html, text),
+            (None, Some(text)) => app.clipboard().write_text(text),
+            (None, None) => app.clipboard().clear(),
+        }
+        .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// The formats on the clipboard, without reading them
+pub fn formats(app: &AppHandle) -> Vec<Format> {
+    match clipboard::formats() {
+        Ok(formats) => formats.into_iter().map(Format::from).collect(),
+        Err(ClipboardError::Unsupported) if app.clipboard().read_text().is_ok() => {
+            vec![Format::Text]
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// A value that changes with the clipboard's contents. Where there's no
+/// change count, a hash of its text.
+fn change_count(app: &AppHandle, supported: bool) -> u64 {
+    if supported {
+        return clipboard::change_count();
+    }
+    let mut hasher = DefaultHasher::new();
+    app.clipboard().read_text().ok().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Emit `EVENT` whenever the clipboard changes, for as long as the app runs
+pub fn watch(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let supported = !matches!(clipboard::formats(), Err(ClipboardError::Unsupported));
+        let mut last = change_count(&app, supported);
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let count = change_count(&app, supported);
+            if count == last {
+                continue;
+            }
+            last = count;
+            let _ = app.emit(
+                EVENT,
+                ClipboardChanged {
+                    change_count: count,
+                    formats: formats(&app),
+                },
+            );
+        }
+    });
+}
@@ -0,0 +1,464 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+
+/// Sent to every window with the `NetworkStatus` whenever it changes
+pub const EVENT: &str = "network-changed";
+
+/// Answers `PROBE_BODY` when nothing sits between the machine and the
+/// internet; a captive portal answers with its sign-in page or a redirect
+const PROBE_URL: &str = "http://captive.apple.com/hotspot-detect.html";
+const PROBE_BODY: &str = "Success";
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InterfaceType {
+    Wifi,
+    Wired,
+    Cellular,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    /// Whether the internet can be reached: there's a route to it and no
+    /// captive portal in the way
+    pub online: bool,
+    /// The interface traffic to the internet goes out on, when there's one
+    pub interface_type: Option<InterfaceType>,
+    /// A metered connection, such as a phone's hotspot, or Low Data Mode on
+    /// macOS. Large transfers should wait.
+    pub expensive: bool,
+    /// A network that wants a sign-in page filled in before it lets traffic
+    /// through, as in hotels and airports
+    pub captive_portal: bool,
+}
+
+/// What the OS reports about the route to the internet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NetworkPath {
+    satisfied: bool,
+    interface_type: Option<InterfaceType>,
+    expensive: bool,
+    /// `None` when the OS doesn't know, and the probe has to find out
+    captive_portal: Option<bool>,
+}
+
+/// The latest status, which `wait_for_online` watches
+pub struct Connectivity {
+    status: watch::Sender<NetworkStatus>,
+    path: Mutex<Option<NetworkPath>>,
+    /// Bumped by each path, so a probe for one that's been replaced is dropped
+    generation: AtomicU64,
+}
+
+/// Online until the OS says otherwise, so nothing waits on a monitor that
+/// couldn't start
+const ASSUMED: NetworkStatus = NetworkStatus {
+    online: true,
+    interface_type: None,
+    expensive: false,
+    captive_portal: false,
+};
+
+impl Default for Connectivity {
+    fn default() -> Self {
+        let (status, _) = watch::channel(ASSUMED);
+        Self {
+            status,
+            path: Mutex::new(None),
+            generation: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Manage `Connectivity` and start following the OS's reports
+pub fn start(app: &AppHandle) {
+    app.manage(Connectivity::default());
+    let handle = app.clone();
+    if let Err(e) = platform::monitor(move |path| changed(&handle, path)) {
+        log::warn!(error:% = e; "network monitor not started");
+    }
+}
+
+/// The current status; online before `start`
+pub fn current(app: &AppHandle) -> NetworkStatus {
+    app.try_state::<Connectivity>()
+        .map_or(ASSUMED, |connectivity| *connectivity.status.borrow())
+}
+
+pub fn is_online(app: &AppHandle) -> bool {
+    current(app).online
+}
+
+/// Resolve once the internet can be reached, right away if it can now
+pub async fn wait_for_online(app: &AppHandle) {
+    let Some(connectivity) = app.try_state::<Connectivity>() else {
+        return;
+    };
+    let mut status = connectivity.status.subscribe();
+    let _ = status.wait_for(|status| status.online).await;
+}
+
+fn changed(app: &AppHandle, path: NetworkPath) {
+    let Some(connectivity) = app.try_state::<Connectivity>() else {
+        return;
+    };
+    // Linux reports every route change, most of which leave the path as is
+    if connectivity
+        .path
+        .lock()
+        .expect("network path mutex")
+        .replace(path)
+        == Some(path)
+    {
+        return;
+    }
+    let generation = connectivity.generation.fetch_add(1, Ordering::Relaxed) + 1;
+    match path.captive_portal {
+        Some(captive_portal) => update(app, path, captive_portal),
+        None if !path.satisfied => update(app, path, false),
+        None => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let captive_portal = probe().await;
+                let current = app
+                    .state::<Connectivity>()
+                    .generation
+                    .load(Ordering::Relaxed);
+                if current == generation {
+                    update(&app, path, captive_portal);
+                }
+            });
+        }
+    }
+}
+
+fn update(app: &AppHandle, path: NetworkPath, captive_portal: bool) {
+    let status = NetworkStatus {
+        online: path.satisfied && !captive_portal,
+        interface_type: path.interface_type,
+        expensive: path.expensive,
+        captive_portal,
+    };
+    let modified = app
+        .state::<Connectivity>()
+        .status
+        .send_if_modified(|current| std::mem::replace(current, status) != status);
+    if modified {
+        log::info!(
+            online = status.online,
+            interface:? = status.interface_type,
+            expensive = status.expensive,
+            captive_portal = status.captive_portal;
+            "network changed"
+        );
+        let _ = app.emit(EVENT, status);
+    }
+}
+
+/// Whether a captive portal is in the way. A probe that fails outright
+/// doesn't count: the probe's host may be all that's blocked.
+async fn probe() -> bool {
+    let client = match reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!(error:% = e; "captive portal probe not sent");
+            return false;
+        }
+    };
+    let response = match client.get(PROBE_URL).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            log::debug!(error:% = e; "captive portal probe failed");
+            return false;
+        }
+    };
+    if !response.status().is_success() {
+        return true;
+    }
+    !response
+        .text()
+        .await
+        .is_ok_and(|body| body.contains(PROBE_BODY))
+}
+
+/// `NWPathMonitor`, through the Network framework's C API, which calls back
+/// on a queue of its own
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{InterfaceType, NetworkPath};
+    use block2::RcBlock;
+    use std::ffi::{c_char, c_int, c_void};
+
+    type NwObject = *mut c_void;
+
+    /// `nw_path_status_satisfied`
+    const STATUS_SATISFIED: c_int = 1;
+    /// `nw_interface_type_t`
+    const INTERFACE_WIFI: c_int = 1;
+    const INTERFACE_CELLULAR: c_int = 2;
+    const INTERFACE_WIRED: c_int = 3;
+
+    #[link(name = "Network", kind = "framework")]
+    extern "C" {
+        fn nw_path_monitor_create() -> NwObject;
+        fn nw_path_monitor_set_queue(monitor: NwObject, queue: *mut c_void);
+        fn nw_path_monitor_set_update_handler(
+            monitor: NwObject,
+            handler: &block2::Block<dyn Fn(NwObject)>,
+        );
+        fn nw_path_monitor_start(monitor: NwObject);
+        fn nw_path_get_status(path: NwObject) -> c_int;
+        fn nw_path_is_expensive(path: NwObject) -> bool;
+        fn nw_path_is_constrained(path: NwObject) -> bool;
+        fn nw_path_uses_interface_type(path: NwObject, interface_type: c_int) -> bool;
+    }
+
+    extern "C" {
+        fn dispatch_queue_create(label: *const c_char, attributes: *mut c_void) -> *mut c_void;
+    }
+
+    fn network_path(path: NwObject) -> NetworkPath {
+        unsafe {
+            let satisfied = nw_path_get_status(path) == STATUS_SATISFIED;
+            let interface_type = satisfied.then(|| {
+                if nw_path_uses_interface_type(path, INTERFACE_WIFI) {
+                    InterfaceType::Wifi
+                } else if nw_path_uses_interface_type(path, INTERFACE_WIRED) {
+                    InterfaceType::Wired
+                } else if nw_path_uses_interface_type(path, INTERFACE_CELLULAR) {
+                    InterfaceType::Cellular
+                } else {
+                    InterfaceType::Other
+                }
+            });
+            NetworkPath {
+                satisfied,
+                interface_type,
+                expensive: nw_path_is_expensive(path) || nw_path_is_constrained(path),
+                // The Network framework leaves captive portals to the probe
+                captive_portal: None,
+            }
+        }
+    }
+
+    pub fn monitor(changed: impl Fn(NetworkPath) + Send + Sync + 'static) -> Result<(), String> {
+        // The monitor and its queue run as long as the app, and are never
+        // released
+        unsafe {
+            let monitor = nw_path_monitor_create();
+            if monitor.is_null() {
+                return Err("NWPathMonitor couldn't be created".to_string());
+            }
+            let queue =
+                dispatch_queue_create(c"smudge.connectivity".as_ptr(), std::ptr::null_mut());
+            let handler = RcBlock::new(move |path: NwObject| changed(network_path(path)));
+            nw_path_monitor_set_update_handler(monitor, &handler);
+            nw_path_monitor_set_queue(monitor, queue);
+            nw_path_monitor_start(monitor);
+        }
+        Ok(())
+    }
+}
+
+/// The connectivity hints Windows derives from the Network List Manager's
+/// probes, which call back on the thread pool
+#[cfg(windows)]
+mod platform {
+    use super::{InterfaceType, NetworkPath};
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::NetworkManagement::IpHelper::{
+        GetBestInterface, GetIfEntry2, NotifyNetworkConnectivityHintChange,
+        IF_TYPE_ETHERNET_CSMACD, IF_TYPE_IEEE80211, IF_TYPE_WWANPP, IF_TYPE_WWANPP2, MIB_IF_ROW2,
+    };
+    use windows::Win32::Networking::WinSock::{self, NL_NETWORK_CONNECTIVITY_HINT};
+
+    type Changed = Box<dyn Fn(NetworkPath) + Send + Sync>;
+
+    /// Traffic to this address goes out on the interface that reaches the
+    /// internet
+    const PUBLIC_ADDRESS: [u8; 4] = [1, 1, 1, 1];
+
+    fn interface_type() -> Option<InterfaceType> {
+        let mut index = 0;
+        let address = u32::from_ne_bytes(PUBLIC_ADDRESS);
+        if unsafe { GetBestInterface(address, &mut index) } != 0 {
+            return None;
+        }
+        let mut row: MIB_IF_ROW2 = unsafe { std::mem::zeroed() };
+        row.InterfaceIndex = index;
+        unsafe { GetIfEntry2(&mut row) }.ok().ok()?;
+        Some(match row.Type {
+            IF_TYPE_IEEE80211 => InterfaceType::Wifi,
+            IF_TYPE_ETHERNET_CSMACD => InterfaceType::Wired,
+            IF_TYPE_WWANPP | IF_TYPE_WWANPP2 => InterfaceType::Cellular,
+            _ => InterfaceType::Other,
+        })
+    }
+
+    fn network_path(hint: NL_NETWORK_CONNECTIVITY_HINT) -> NetworkPath {
+        let (satisfied, captive_portal) = match hint.ConnectivityLevel {
+            WinSock::NetworkConnectivityLevelHintInternetAccess => (true, Some(false)),
+            WinSock::NetworkConnectivityLevelHintConstrainedInternetAccess => (true, Some(true)),
+            WinSock::NetworkConnectivityLevelHintNone
+            | WinSock::NetworkConnectivityLevelHintLocalAccess => (false, Some(false)),
+            // Unknown or hidden: go by the probe
+            _ => (true, None),
+        };
+        let metered = matches!(
+            hint.ConnectivityCost,
+            WinSock::NetworkConnectivityCostHintFixed
+                | WinSock::NetworkConnectivityCostHintVariable
+        );
+        NetworkPath {
+            satisfied,
+            interface_type: satisfied.then(interface_type).flatten(),
+            expensive: metered || hint.Roaming || hint.ApproachingDataLimit || hint.OverDataLimit,
+            captive_portal,
+        }
+    }
+
+    unsafe extern "system" fn hint_changed(
+        context: *const c_void,
+        hint: NL_NETWORK_CONNECTIVITY_HINT,
+    ) {
+        let changed = &*(context as *const Changed);
+        changed(network_path(hint));
+    }
+
+    pub fn monitor(changed: impl Fn(NetworkPath) + Send + Sync + 'static) -> Result<(), String> {
+        // Never cancelled, so the callback lives as long as the app
+        let context = Box::into_raw(Box::new(Box::new(changed) as Changed));
+        let mut handle = HANDLE::default();
+        let registered = unsafe {
+            NotifyNetworkConnectivityHintChange(
+                Some(hint_changed),
+                Some(context as *const c_void),
+                true,
+                &mut handle,
+            )
+        };
+        registered.ok().map_err(|e| {
+            drop(unsafe { Box::from_raw(context) });
+            e.to_string()
+        })
+    }
+}
+
+/// A netlink socket that hears of link and route changes, after each of
+/// which the default route is looked up again
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::{InterfaceType, NetworkPath};
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+
+    /// The interface of the default IPv4 route, or else the IPv6 one
+    fn default_interface() -> Option<String> {
+        let ipv4 = fs::read_to_string("/proc/net/route")
+            .ok()
+            .and_then(|routes| {
+                routes.lines().skip(1).find_map(|line| {
+                    let fields: Vec<&str> = line.split_whitespace().collect();
+                    (fields.get(1) == Some(&"00000000")).then(|| fields[0].to_string())
+                })
+            });
+        ipv4.or_else(|| {
+            let routes = fs::read_to_string("/proc/net/ipv6_route").ok()?;
+            routes.lines().find_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let default = fields.first() == Some(&"00000000000000000000000000000000")
+                    && fields.get(1) == Some(&"00")
+                    && fields.last() != Some(&"lo");
+                default.then(|| fields.last().map(|name| name.to_string()))?
+            })
+        })
+    }
+
+    fn interface_type(name: &str) -> InterfaceType {
+        let device = Path::new("/sys/class/net").join(name);
+        if device.join("wireless").exists() || device.join("phy80211").exists() {
+            InterfaceType::Wifi
+        } else if name.starts_with("ww") {
+            InterfaceType::Cellular
+        } else if device.join("device").exists() {
+            InterfaceType::Wired
+        } else {
+            InterfaceType::Other
+        }
+    }
+
+    fn network_path() -> NetworkPath {
+        let interface_type = default_interface().map(|name| interface_type(&name));
+        NetworkPath {
+            satisfied: interface_type.is_some(),
+            interface_type,
+            expensive: interface_type == Some(InterfaceType::Cellular),
+            captive_portal: None,
+        }
+    }
+
+    pub fn monitor(changed: impl Fn(NetworkPath) + Send + Sync + 'static) -> Result<(), String> {
+        let socket = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::NETLINK_ROUTE,
+            )
+        };
+        if socket < 0 {
+            return Err(io::Error::last_os_error().to_string());
+        }
+        let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as u16;
+        address.nl_groups =
+            (libc::RTMGRP_LINK | libc::RTMGRP_IPV4_ROUTE | libc::RTMGRP_IPV6_ROUTE) as u32;
+        let bound = unsafe {
+            libc::bind(
+                socket,
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as u32,
+            )
+        };
+        if bound < 0 {
+            let error = io::Error::last_os_error();
+            unsafe { libc::close(socket) };
+            return Err(error.to_string());
+        }
+        changed(network_path());
+        std::thread::Builder::new()
+            .name("netlink".to_string())
+            .spawn(move || {
+                let mut buffer = [0u8; 8192];
+                loop {
+                    let read =
+                        unsafe { libc::recv(socket, buffer.as_mut_ptr().cast(), buffer.len(), 0) };
+                    if read < 0 {
+                        let error = io::Error::last_os_error();
+                        // A full buffer drops messages, which only means
+                        // looking again
+                        if !matches!(error.raw_os_error(), Some(libc::EINTR | libc::ENOBUFS)) {
+                            log::warn!(error:% = error; "netlink socket failed");
+                            unsafe { libc::close(socket) };
+                            return;
+                        }
+                    }
+                    changed(network_path());
+                }
+            })
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,220 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuId, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, LogicalPosition, Manager, WebviewWindow, Wry};
+use tokio::sync::oneshot;
+
+use crate::menus;
+
+/// Context menu items share Tauri's menu event channel with every other
+/// menu, so their ids carry this, the menu's number and a colon
+const ID_PREFIX: &str = "context:";
+
+/// A context menu entry as the frontend describes it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ContextMenuItem {
+    Item {
+        id: String,
+        title: String,
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+        /// Shown with a checkmark column when set
+        #[serde(default)]
+        checked: Option<bool>,
+        /// Shortcut shown next to the title, e.g. "CmdOrCtrl+D". Only a
+        /// hint: pressing it while the menu is closed does nothing.
+        #[serde(default)]
+        accelerator: Option<String>,
+        /// Announced by screen readers instead of the title
+        #[serde(default)]
+        accessibility_title: Option<String>,
+        #[serde(default)]
+        help: Option<String>,
+    },
+    Submenu {
+        title: String,
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+        items: Vec<ContextMenuItem>,
+    },
+    Separator,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Context menus that are up, waiting for the user to pick an item
+#[derive(Default)]
+pub struct ContextMenus {
+    next_menu: AtomicU64,
+    pending: Mutex<HashMap<u64, oneshot::Sender<Option<String>>>>,
+}
+
+impl ContextMenus {
+    fn settle(&self, menu: u64, id: Option<String>) {
+        let sender = self
+            .pending
+            .lock()
+            .expect("context menus mutex")
+            .remove(&menu);
+        if let Some(sender) = sender {
+            let _ = sender.send(id);
+        }
+    }
+}
+
+/// Pop up a native menu of `items` over `window` at `position`, in logical
+/// pixels from the top left of its content, or at the mouse if `None`.
+/// Resolves to the id of the item the user picked, or `None` if they
+/// dismissed the menu. Unlike an HTML menu it can extend past the window.
+pub async fn show(
+    app: &AppHandle,
+    window: WebviewWindow,
+    items: Vec<ContextMenuItem>,
+    position: Option<LogicalPosition<f64>>,
+) -> Result<Option<String>, String> {
+    let menus = app.state::<ContextMenus>();
+    let number = menus.next_menu.fetch_add(1, Ordering::Relaxed);
+    let prefix = format!("{}{}:", ID_PREFIX, number);
+
+    let mut described = Vec::new();
+    let menu = build_menu(app, &prefix, &items, &mut described).map_err(|e| e.to_string())?;
+
+    let (sender, receiver) = oneshot::channel();
+    menus
+        .pending
+        .lock()
+        .expect("context menus mutex")
+        .insert(number, sender);
+
+    let handle = app.clone();
+    let shown = app.run_on_main_thread(move || {
+        let popped = match position {
+            Some(position) => window.popup_menu_at(&menu, position),
+            None => window.popup_menu(&menu),
+        };
+        if let Err(e) = popped {
+            log::warn!(error:% = e; "context menu not shown");
+        }
+        // The popup runs its own tracking loop on the main thread, and the
+        // chosen item's event is queued as it closes. Queuing twice puts the
+        // dismissal behind that event, so a choice is never lost to it.
+        let inner = handle.clone();
+        let _ = handle.run_on_main_thread(move || {
+            let settle = inner.clone();
+            let _ = inner.run_on_main_thread(move || {
+                settle.state::<ContextMenus>().settle(number, None);
+            });
+        });
+    });
+    if let Err(e) = shown {
+        menus.settle(number, None);
+        menus::clear(&described);
+        return Err(e.to_string());
+    }
+
+    let chosen = receiver.await.unwrap_or(None);
+    menus::clear(&described);
+    Ok(chosen)
+}
+
+fn build_menu(
+    app: &AppHandle,
+    prefix: &str,
+    items: &[ContextMenuItem],
+    described: &mut Vec<String>,
+) -> tauri::Result<Menu<Wry>> {
+    let items = build_items(app, prefix, items, described)?;
+    let items: Vec<&dyn IsMenuItem<Wry>> = items.iter().map(|item| item.as_ref()).collect();
+    Menu::with_items(app, &items)
+}
+
+fn build_items(
+    app: &AppHandle,
+    prefix: &str,
+    items: &[ContextMenuItem],
+    described: &mut Vec<String>,
+) -> tauri::Result<Vec<Box<dyn IsMenuItem<Wry>>>> {
+    let mut built: Vec<Box<dyn IsMenuItem<Wry>>> = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            ContextMenuItem::Item {
+                id,
+                title,
+                enabled,
+                checked,
+                accelerator,
+                accessibility_title,
+                help,
+            } => {
+                let id = format!("{}{}", prefix, id);
+                // Descriptions are read as the native item is created
+                if accessibility_title.is_some() || help.is_some() {
+                    menus::set_item_accessibility(
+                        &MenuId::new(&id),
+                        accessibility_title.clone(),
+                        help.clone(),
+                    );
+                    described.push(id.clone());
+                }
+                let accelerator = accelerator.as_deref();
+                match checked {
+                    Some(checked) => built.push(Box::new(CheckMenuItem::with_id(
+                        app,
+                        id,
+                        title,
+                        *enabled,
+                        *checked,
+                        accelerator,
+                    )?)),
+                    None => built.push(Box::new(MenuItem::with_id(
+                        app,
+                        id,
+                        title,
+                        *enabled,
+                        accelerator,
+                    )?)),
+                }
+            }
+            ContextMenuItem::Submenu {
+                title,
+                enabled,
+                items,
+            } => {
+                let children = build_items(app, prefix, items, described)?;
+                let children: Vec<&dyn IsMenuItem<Wry>> =
+                    children.iter().map(|item| item.as_ref()).collect();
+                built.push(Box::new(Submenu::with_items(
+                    app, title, *enabled, &children,
+                )?));
+            }
+            ContextMenuItem::Separator => built.push(Box::new(PredefinedMenuItem::separator(app)?)),
+        }
+    }
+    Ok(built)
+}
+
+/// Handle a menu event if it came from a context menu: resolve the menu's
+/// `show` with the frontend's id for the item
+pub fn handle(app: &AppHandle, id: &str) {
+    let Some(rest) = id.strip_prefix(ID_PREFIX) else {
+        return;
+    };
+    let Some((number, item)) = rest.split_once(':') else {
+        return;
+    };
+    let Ok(number) = number.parse() else {
+        return;
+    };
+    if let Some(menus) = app.try_state::<ContextMenus>() {
+        menus.settle(number, Some(item.to_string()));
+    }
+}
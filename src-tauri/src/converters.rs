@@ -0,0 +1,405 @@
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::{batch, pdf, print};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Format {
+    Markdown,
+    Html,
+    Csv,
+    Json,
+    Pdf,
+}
+
+/// A file on its way between formats: a note's markdown
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub title: String,
+    pub markdown: String,
+}
+
+/// How to convert; only `destination` is always needed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Options {
+    /// Folder the converted files are written to
+    pub destination: String,
+    /// CSV: whether the first row names the columns
+    #[serde(default = "header_by_default")]
+    pub header: bool,
+    /// CSV: the separator between fields, a comma if not given
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// PDF: the paper's orientation, the system's default if not given
+    #[serde(default)]
+    pub orientation: Option<print::Orientation>,
+}
+
+fn header_by_default() -> bool {
+    true
+}
+
+impl Options {
+    fn delimiter(&self) -> char {
+        self.delimiter.unwrap_or(',')
+    }
+}
+
+/// Reads a file's text, named `name`, into a document
+type Import = fn(text: &str, name: &str, options: &Options) -> Result<Document, String>;
+
+enum Export {
+    Text(fn(document: &Document, options: &Options) -> Result<String, String>),
+    /// Rendered to HTML and printed to a PDF from a hidden window
+    Pdf,
+}
+
+/// What smudge can read and write in one format
+pub struct Converter {
+    pub format: Format,
+    /// File extensions, the first of which converted files are given
+    pub extensions: &'static [&'static str],
+    import: Option<Import>,
+    export: Option<Export>,
+}
+
+pub const CONVERTERS: &[Converter] = &[
+    Converter {
+        format: Format::Markdown,
+        extensions: &["md", "markdown", "txt"],
+        import: Some(import_markdown),
+        export: Some(Export::Text(export_markdown)),
+    },
+    Converter {
+        format: Format::Html,
+        extensions: &["html", "htm"],
+        import: None,
+        export: Some(Export::Text(export_html)),
+    },
+    Converter {
+        format: Format::Csv,
+        extensions: &["csv"],
+        import: Some(import_csv),
+        export: Some(Export::Text(export_csv)),
+    },
+    Converter {
+        format: Format::Json,
+        extensions: &["json"],
+        import: Some(import_json),
+        export: Some(Export::Text(export_json)),
+    },
+    Converter {
+        format: Format::Pdf,
+        extensions: &["pdf"],
+        import: None,
+        export: Some(Export::Pdf),
+    },
+];
+
+/// A format as `list_converters` reports it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConverterInfo {
+    pub format: Format,
+    pub extensions: &'static [&'static str],
+    pub can_import: bool,
+    pub can_export: bool,
+}
+
+pub fn list() -> Vec<ConverterInfo> {
+    CONVERTERS
+        .iter()
+        .map(|converter| ConverterInfo {
+            format: converter.format,
+            extensions: converter.extensions,
+            can_import: converter.import.is_some(),
+            can_export: converter.export.is_some(),
+        })
+        .collect()
+}
+
+fn converter(format: Format) -> &'static Converter {
+    CONVERTERS
+        .iter()
+        .find(|converter| converter.format == format)
+        .expect("every format has a converter")
+}
+
+/// The format a file's extension says it's in
+fn detect(path: &Path) -> Option<Format> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    CONVERTERS
+        .iter()
+        .find(|converter| converter.extensions.contains(&extension.as_str()))
+        .map(|converter| converter.format)
+}
+
+/// Convert `source` from `from` (by its extension if None) to `to`,
+/// writing `<destination>/<stem>.<extension>`. Returns the written file.
+pub async fn convert_file(
+    app: &AppHandle,
+    source: &Path,
+    from: Option<Format>,
+    to: Format,
+    options: &Options,
+) -> Result<String, String> {
+    let from = from
+        .or_else(|| detect(source))
+        .ok_or("The file's format isn't one smudge can convert")?;
+    let import = converter(from)
+        .import
+        .ok_or_else(|| format!("{:?} files can't be converted from", from))?;
+    let target = converter(to);
+    let export = target
+        .export
+        .as_ref()
+        .ok_or_else(|| format!("Files can't be converted to {:?}", to))?;
+
+    let name = source
+        .file_stem()
+        .ok_or("Invalid file path")?
+        .to_string_lossy()
+        .into_owned();
+    let text = tokio::fs::read_to_string(source)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let output = Path::new(&options.destination).join(format!("{}.{}", name, target.extensions[0]));
+
+    // Parsing and rendering are CPU work, kept off the async runtime
+    let (document, converted) = {
+        let options = options.clone();
+        let export = match export {
+            Export::Text(export) => Some(*export),
+            Export::Pdf => None,
+        };
+        tauri::async_runtime::spawn_blocking(move || {
+            let document = import(&text, &name, &options)?;
+            let converted = export
+                .map(|export| export(&document, &options))
+                .transpose()?;
+            Ok::<_, String>((document, converted))
+        })
+        .await
+        .map_err(|e| e.to_string())??
+    };
+    match converted {
+        Some(converted) => tokio::fs::write(&output, converted)
+            .await
+            .map_err(|e| format!("Failed to write file: {}", e))?,
+        None => {
+            let html = batch::render_page(&document.title, &document.markdown);
+            let layout = pdf::Options {
+                orientation: options.orientation,
+                ..Default::default()
+            };
+            pdf::render_html(app, html, &output, layout).await?
+        }
+    }
+    Ok(output.to_string_lossy().into_owned())
+}
+
+fn import_markdown(text: &str, _name: &str, _options: &Options) -> Result<Document, String> {
+    Ok(Document {
+        title: crate::extract_title(text),
+        markdown: text.to_string(),
+    })
+}
+
+fn export_markdown(document: &Document, _options: &Options) -> Result<String, String> {
+    Ok(document.markdown.clone())
+}
+
+fn export_html(document: &Document, _options: &Options) -> Result<String, String> {
+    Ok(batch::render_page(&document.title, &document.markdown))
+}
+
+/// A CSV file as a note holding one table, titled with the file's name
+fn import_csv(text: &str, name: &str, options: &Options) -> Result<Document, String> {
+    let mut rows = parse_csv(text, options.delimiter());
+    if rows.is_empty() {
+        return Err("The file has no rows".to_string());
+    }
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let header = if options.header {
+        rows.remove(0)
+    } else {
+        (1..=width).map(|n| format!("Column {}", n)).collect()
+    };
+    let table = markdown_table(&header, &rows, width);
+    Ok(Document {
+        title: name.to_string(),
+        markdown: format!("# {}\n\n{}", name, table),
+    })
+}
+
+/// Records of `text`, with fields quoted as RFC 4180 has it
+fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{FEFF}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            c if quoted => field.push(c),
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    // Blank lines aren't records
+    rows.retain(|row| !(row.len() == 1 && row[0].is_empty()));
+    rows
+}
+
+fn markdown_table(header: &[String], rows: &[Vec<String>], width: usize) -> String {
+    let cell = |text: &str| {
+        text.replace('|', "\\|")
+            .replace("\r\n", "<br>")
+            .replace('\n', "<br>")
+    };
+    let line = |cells: &[String]| {
+        let cells: Vec<String> = (0..width)
+            .map(|i| cell(cells.get(i).map_or("", String::as_str)))
+            .collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+    let mut table = line(header);
+    table.push_str(&format!("|{}\n", " --- |".repeat(width)));
+    for row in rows {
+        table.push_str(&line(row));
+    }
+    table
+}
+
+/// The note's first table as CSV
+fn export_csv(document: &Document, options: &Options) -> Result<String, String> {
+    let rows = first_table(&document.markdown).ok_or("The note has no table to export as CSV")?;
+    let delimiter = options.delimiter();
+    let field = |text: &str| {
+        if text.contains([delimiter, '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_string()
+        }
+    };
+    let mut csv = String::new();
+    for row in rows.iter().skip(usize::from(!options.header)) {
+        let fields: Vec<String> = row.iter().map(|text| field(text)).collect();
+        csv.push_str(&fields.join(&delimiter.to_string()));
+        csv.push_str("\r\n");
+    }
+    Ok(csv)
+}
+
+/// The cells' text of the first table in `markdown`, its header row first
+fn first_table(markdown: &str) -> Option<Vec<Vec<String>>> {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut in_table = false;
+    for event in Parser::new_ext(markdown, pulldown_cmark::Options::ENABLE_TABLES) {
+        match event {
+            Event::Start(Tag::Table(_)) => in_table = true,
+            Event::End(TagEnd::Table) => return Some(rows),
+            Event::Start(Tag::TableHead) | Event::Start(Tag::TableRow) if in_table => {
+                rows.push(Vec::new())
+            }
+            Event::Start(Tag::TableCell) if in_table => {
+                if let Some(row) = rows.last_mut() {
+                    row.push(String::new());
+                }
+            }
+            Event::Text(text) | Event::Code(text) if in_table => {
+                if let Some(cell) = rows.last_mut().and_then(|row| row.last_mut()) {
+                    cell.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak if in_table => {
+                if let Some(cell) = rows.last_mut().and_then(|row| row.last_mut()) {
+                    cell.push('\n');
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A JSON file as a note: an exported note (`title` and `content`) comes
+/// back as it was, an array of objects becomes a table and anything else a
+/// code block
+fn import_json(text: &str, name: &str, _options: &Options) -> Result<Document, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("The file isn't valid JSON: {}", e))?;
+    if let Some(content) = value.get("content").and_then(|v| v.as_str()) {
+        return Ok(Document {
+            title: value
+                .get("title")
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| crate::extract_title(content)),
+            markdown: content.to_string(),
+        });
+    }
+    let body = match value.as_array() {
+        Some(items) if !items.is_empty() && items.iter().all(|item| item.is_object()) => {
+            let mut columns: Vec<String> = Vec::new();
+            for item in items.iter().filter_map(|item| item.as_object()) {
+                for key in item.keys() {
+                    if !columns.contains(key) {
+                        columns.push(key.clone());
+                    }
+                }
+            }
+            let rows: Vec<Vec<String>> = items
+                .iter()
+                .filter_map(|item| item.as_object())
+                .map(|item| {
+                    columns
+                        .iter()
+                        .map(|column| match item.get(column) {
+                            None | Some(serde_json::Value::Null) => String::new(),
+                            Some(serde_json::Value::String(text)) => text.clone(),
+                            Some(other) => other.to_string(),
+                        })
+                        .collect()
+                })
+                .collect();
+            markdown_table(&columns, &rows, columns.len())
+        }
+        _ => {
+            let pretty = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+            format!("```json\n{}\n```\n", pretty)
+        }
+    };
+    Ok(Document {
+        title: name.to_string(),
+        markdown: format!("# {}\n\n{}", name, body),
+    })
+}
+
+fn export_json(document: &Document, _options: &Options) -> Result<String, String> {
+    let json = serde_json::json!({
+        "title": document.title,
+        "content": document.markdown,
+        "html": batch::render_page(&document.title, &document.markdown),
+    });
+    serde_json::to_string_pretty(&json).map_err(|e| e.to_string())
+}
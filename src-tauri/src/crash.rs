@@ -0,0 +1,573 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sent to the frontend once it's ready when the previous run crashed, with
+/// the `CrashReport`
+pub const EVENT: &str = "crash-detected";
+
+/// Log lines kept with a report
+const BREADCRUMBS: usize = 50;
+
+/// Crash reports kept on disk, newest first; older ones are deleted at launch
+const KEPT_REPORTS: usize = 5;
+
+/// Where `upload` sends a report the user agreed to share
+const UPLOAD_URL: &str = "https://crashes.smudge.app/v1/reports";
+
+/// This run's files in the crash directory. A clean exit deletes them, so
+/// finding any at launch means the previous run died: `current.json` is a
+/// panic report, `current.signal` holds the fatal signal or exception code
+/// and `current.dmp` is a Windows minidump.
+const CURRENT_REPORT: &str = "current.json";
+const CURRENT_MARKER: &str = "current.signal";
+const CURRENT_DUMP: &str = "current.dmp";
+
+/// A crash of a previous run, kept as `report.json` in its own folder under
+/// the crash directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    /// Name of the report's folder
+    pub id: String,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub crashed_at: u64,
+    /// The panic message, or the signal or exception that killed the app
+    pub message: String,
+    /// Where the panic happened
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: Option<String>,
+    /// The fatal signal's name or the Windows exception code, if the app
+    /// didn't just panic
+    pub signal: Option<String>,
+    /// The last lines logged before the crash
+    pub breadcrumbs: Vec<String>,
+    /// Whether `minidump.dmp` is next to the report
+    pub minidump: bool,
+    /// The crash report macOS wrote for the same crash
+    pub system_report: Option<PathBuf>,
+    pub uploaded: bool,
+}
+
+/// Paths the handlers write to, fixed at install since a crashing process
+/// can't be trusted to work them out
+struct Context {
+    dir: PathBuf,
+    app_version: String,
+}
+
+static CONTEXT: OnceLock<Context> = OnceLock::new();
+
+/// A report found at launch, until the frontend can be told about it
+#[derive(Default)]
+pub struct PendingCrash(Mutex<Option<CrashReport>>);
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn crash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| e.to_string())?
+        .join("crashes");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+fn report_dir(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    // Ids come back from the frontend, so only names `collect` makes
+    if !id.starts_with("crash-") || !id[6..].bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("Unknown crash report: {}", id));
+    }
+    let dir = crash_dir(app)?.join(id);
+    if !dir.is_dir() {
+        return Err(format!("Unknown crash report: {}", id));
+    }
+    Ok(dir)
+}
+
+/// Look for the previous run's crash, then start catching this run's panics,
+/// fatal signals and unhandled exceptions. Runs first in setup, before the
+/// log file is opened, so the log still ends with the previous run's lines.
+pub fn install(app: &AppHandle) {
+    let dir = match crash_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::warn!(error:% = e; "couldn't create the crash directory");
+            app.manage(PendingCrash::default());
+            return;
+        }
+    };
+    let pending = collect(app, &dir);
+    if let Some(ref report) = pending {
+        log::warn!(
+            id = report.id.as_str(), message = report.message.as_str();
+            "the previous run crashed"
+        );
+    }
+    prune(&dir);
+    app.manage(PendingCrash(Mutex::new(pending)));
+
+    let context = Context {
+        dir: dir.clone(),
+        app_version: app.package_info().version.to_string(),
+    };
+    if CONTEXT.set(context).is_err() {
+        return;
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        record_panic(info);
+        previous(info);
+    }));
+    platform::install(&dir);
+}
+
+/// Tell the frontend about the previous run's crash. Called when the
+/// frontend reports ready.
+pub fn frontend_ready(app: &AppHandle) {
+    let pending = app
+        .try_state::<PendingCrash>()
+        .and_then(|state| state.0.lock().ok()?.take());
+    if let Some(report) = pending {
+        let _ = app.emit(EVENT, report);
+    }
+}
+
+/// The run is ending normally, so panics it caught and survived aren't
+/// crashes
+pub fn exited() {
+    if let Some(context) = CONTEXT.get() {
+        for name in [CURRENT_REPORT, CURRENT_MARKER, CURRENT_DUMP] {
+            let _ = std::fs::remove_file(context.dir.join(name));
+        }
+    }
+}
+
+/// Write a report for a panic as it happens. A panic that's caught and
+/// survived leaves it for `exited` to delete.
+fn record_panic(info: &std::panic::PanicHookInfo) {
+    let Some(context) = CONTEXT.get() else {
+        return;
+    };
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let crashed_at = timestamp();
+    let report = CrashReport {
+        id: format!("crash-{}", crashed_at),
+        app_version: context.app_version.clone(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        crashed_at,
+        message: message.to_string(),
+        location: info.location().map(|location| location.to_string()),
+        thread: std::thread::current().name().map(str::to_string),
+        backtrace: Some(std::backtrace::Backtrace::force_capture().to_string()),
+        signal: None,
+        breadcrumbs: crate::logging::breadcrumbs(BREADCRUMBS),
+        minidump: false,
+        system_report: None,
+        uploaded: false,
+    };
+    if let Ok(content) = serde_json::to_string_pretty(&report) {
+        let _ = std::fs::write(context.dir.join(CURRENT_REPORT), content);
+    }
+}
+
+/// Turn what the previous run left in `dir` into a report in a folder of its
+/// own
+fn collect(app: &AppHandle, dir: &Path) -> Option<CrashReport> {
+    let marker = dir.join(CURRENT_MARKER);
+    let signal = std::fs::read_to_string(&marker)
+        .ok()
+        .map(|code| platform::describe(code.trim()));
+    let panic = std::fs::read_to_string(dir.join(CURRENT_REPORT))
+        .ok()
+        .and_then(|content| serde_json::from_str::<CrashReport>(&content).ok());
+
+    let mut report = match (panic, signal) {
+        (None, None) => return None,
+        // A panic that ended in an abort keeps its message
+        (Some(mut report), signal) => {
+            report.signal = signal;
+            report
+        }
+        (None, Some(signal)) => {
+            let crashed_at = std::fs::metadata(&marker)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or_else(timestamp);
+            let log_dir = dir.parent().unwrap_or(dir);
+            CrashReport {
+                id: format!("crash-{}", crashed_at),
+                app_version: app.package_info().version.to_string(),
+                os: std::env::consts::OS.to_string(),
+                arch: std::env::consts::ARCH.to_string(),
+                crashed_at,
+                message: format!("Terminated by {}", signal),
+                location: None,
+                thread: None,
+                backtrace: None,
+                signal: Some(signal),
+                breadcrumbs: crate::logging::tail(log_dir, BREADCRUMBS),
+                minidump: false,
+                system_report: None,
+                uploaded: false,
+            }
+        }
+    };
+
+    let folder = dir.join(&report.id);
+    if std::fs::create_dir_all(&folder).is_err() {
+        return None;
+    }
+    report.minidump = std::fs::rename(dir.join(CURRENT_DUMP), folder.join("minidump.dmp")).is_ok();
+    report.system_report = platform::system_report(report.crashed_at);
+    let content = serde_json::to_string_pretty(&report).ok()?;
+    std::fs::write(folder.join("report.json"), content).ok()?;
+    for name in [CURRENT_REPORT, CURRENT_MARKER] {
+        let _ = std::fs::remove_file(dir.join(name));
+    }
+    Some(report)
+}
+
+/// Delete all but the newest `KEPT_REPORTS` reports
+fn prune(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut reports: Vec<(u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let crashed_at = name.to_str()?.strip_prefix("crash-")?.parse().ok()?;
+            Some((crashed_at, entry.path()))
+        })
+        .collect();
+    reports.sort_by_key(|(crashed_at, _)| std::cmp::Reverse(*crashed_at));
+    for (_, path) in reports.into_iter().skip(KEPT_REPORTS) {
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
+
+/// Send a report, with its minidump or macOS's own report, to smudge's crash
+/// collector. Only ever called when the user chose to send it.
+pub async fn upload(app: &AppHandle, id: &str) -> Result<(), String> {
+    let folder = report_dir(app, id)?;
+    let content = std::fs::read_to_string(folder.join("report.json")).map_err(|e| e.to_string())?;
+    let mut report: CrashReport = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    let minidump = if report.minidump {
+        let dump = std::fs::read(folder.join("minidump.dmp")).map_err(|e| e.to_string())?;
+        Some(base64::engine::general_purpose::STANDARD.encode(dump))
+    } else {
+        None
+    };
+    // macOS writes no minidump; its own report has the crashed thread's
+    // frames and each image's UUID, which the collector symbolicates against
+    // the release's dSYM
+    let system_report = report
+        .system_report
+        .as_deref()
+        .and_then(|path| std::fs::read_to_string(path).ok());
+    let body = serde_json::json!({
+        "report": &report,
+        "minidump": minidump,
+        "systemReport": system_report,
+    });
+    let response = reqwest::Client::new()
+        .post(UPLOAD_URL)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Couldn't send the crash report: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Couldn't send the crash report: the server answered {}",
+            response.status()
+        ));
+    }
+    log::info!(id = id; "uploaded crash report");
+    report.uploaded = true;
+    let content = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    std::fs::write(folder.join("report.json"), content).map_err(|e| e.to_string())
+}
+
+/// Delete a report the user doesn't want to send
+pub fn discard(app: &AppHandle, id: &str) -> Result<(), String> {
+    std::fs::remove_dir_all(report_dir(app, id)?).map_err(|e| e.to_string())
+}
+
+/// Fatal signals through `sigaction`. The handler only writes the signal
+/// number, as nothing that allocates or locks is safe there; the rest of the
+/// report is put together at the next launch.
+#[cfg(unix)]
+mod platform {
+    use std::ffi::{c_int, c_void, CString};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::OnceLock;
+
+    const SIGNALS: [c_int; 6] = [
+        libc::SIGSEGV,
+        libc::SIGBUS,
+        libc::SIGILL,
+        libc::SIGFPE,
+        libc::SIGABRT,
+        libc::SIGTRAP,
+    ];
+
+    static MARKER: OnceLock<CString> = OnceLock::new();
+    static PREVIOUS: OnceLock<Vec<(c_int, libc::sigaction)>> = OnceLock::new();
+
+    pub fn install(dir: &Path) {
+        let path = dir.join(super::CURRENT_MARKER);
+        let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+            return;
+        };
+        let _ = MARKER.set(path);
+        let mut previous = Vec::new();
+        for signal in SIGNALS {
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = handle as *const () as usize;
+                // On the alternate stack Rust sets up, so stack overflows are
+                // caught too
+                action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+                libc::sigemptyset(&mut action.sa_mask);
+                let mut old: libc::sigaction = std::mem::zeroed();
+                if libc::sigaction(signal, &action, &mut old) == 0 {
+                    previous.push((signal, old));
+                }
+            }
+        }
+        let _ = PREVIOUS.set(previous);
+    }
+
+    extern "C" fn handle(signal: c_int, _info: *mut libc::siginfo_t, _context: *mut c_void) {
+        if let Some(marker) = MARKER.get() {
+            let mut digits = [0u8; 12];
+            let mut start = digits.len();
+            let mut value = signal.unsigned_abs();
+            loop {
+                start -= 1;
+                digits[start] = b'0' + (value % 10) as u8;
+                value /= 10;
+                if value == 0 {
+                    break;
+                }
+            }
+            unsafe {
+                let fd = libc::open(
+                    marker.as_ptr(),
+                    libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC,
+                    0o644 as libc::c_uint,
+                );
+                if fd >= 0 {
+                    libc::write(fd, digits[start..].as_ptr().cast(), digits.len() - start);
+                    libc::close(fd);
+                }
+            }
+        }
+        // Hand the signal on to whoever had it before, Rust's stack overflow
+        // handler or the default action
+        if let Some(previous) = PREVIOUS.get() {
+            for (previous_signal, action) in previous {
+                if *previous_signal == signal {
+                    unsafe { libc::sigaction(signal, action, std::ptr::null_mut()) };
+                }
+            }
+        }
+        unsafe { libc::raise(signal) };
+    }
+
+    /// The signal's name for the number the handler wrote
+    pub fn describe(code: &str) -> String {
+        let name = match code.parse::<c_int>() {
+            Ok(libc::SIGSEGV) => "SIGSEGV",
+            Ok(libc::SIGBUS) => "SIGBUS",
+            Ok(libc::SIGILL) => "SIGILL",
+            Ok(libc::SIGFPE) => "SIGFPE",
+            Ok(libc::SIGABRT) => "SIGABRT",
+            Ok(libc::SIGTRAP) => "SIGTRAP",
+            _ => return format!("signal {}", code),
+        };
+        name.to_string()
+    }
+
+    /// The `.ips` file macOS wrote to ~/Library/Logs/DiagnosticReports for a
+    /// crash at `crashed_at`
+    #[cfg(target_os = "macos")]
+    pub fn system_report(crashed_at: u64) -> Option<PathBuf> {
+        use std::time::UNIX_EPOCH;
+
+        let executable = std::env::current_exe().ok()?;
+        let name = executable.file_name()?.to_str()?.to_string();
+        let home = std::env::var_os("HOME")?;
+        let reports = Path::new(&home).join("Library/Logs/DiagnosticReports");
+        std::fs::read_dir(reports)
+            .ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let file_name = entry.file_name();
+                let file_name = file_name.to_str()?;
+                if !file_name.starts_with(&format!("{}-", name)) || !file_name.ends_with(".ips") {
+                    return None;
+                }
+                let written = entry
+                    .metadata()
+                    .ok()?
+                    .modified()
+                    .ok()?
+                    .duration_since(UNIX_EPOCH)
+                    .ok()?
+                    .as_secs();
+                // ReportCrash writes it a moment after the process dies
+                (written + 1 >= crashed_at && written <= crashed_at + 120)
+                    .then(|| (written, entry.path()))
+            })
+            .max_by_key(|(written, _)| *written)
+            .map(|(_, path)| path)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn system_report(_crashed_at: u64) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Unhandled exceptions through the top-level exception filter, which writes
+/// a minidump next to the exception code
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::OnceLock;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, GENERIC_WRITE, HANDLE};
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, WriteFile, CREATE_ALWAYS, FILE_ATTRIBUTE_NORMAL, FILE_SHARE_NONE,
+    };
+    use windows::Win32::System::Diagnostics::Debug::{
+        MiniDumpWithThreadInfo, MiniDumpWriteDump, SetUnhandledExceptionFilter, EXCEPTION_POINTERS,
+        LPTOP_LEVEL_EXCEPTION_FILTER, MINIDUMP_EXCEPTION_INFORMATION,
+    };
+    use windows::Win32::System::Threading::{
+        GetCurrentProcess, GetCurrentProcessId, GetCurrentThreadId,
+    };
+
+    /// `EXCEPTION_CONTINUE_SEARCH`: let Windows Error Reporting have it next
+    const CONTINUE_SEARCH: i32 = 0;
+
+    struct Paths {
+        marker: Vec<u16>,
+        dump: Vec<u16>,
+    }
+
+    static PATHS: OnceLock<Paths> = OnceLock::new();
+    static PREVIOUS: OnceLock<LPTOP_LEVEL_EXCEPTION_FILTER> = OnceLock::new();
+
+    fn wide(path: &Path) -> Vec<u16> {
+        path.as_os_str().encode_wide().chain([0]).collect()
+    }
+
+    pub fn install(dir: &Path) {
+        let _ = PATHS.set(Paths {
+            marker: wide(&dir.join(super::CURRENT_MARKER)),
+            dump: wide(&dir.join(super::CURRENT_DUMP)),
+        });
+        let previous = unsafe { SetUnhandledExceptionFilter(Some(handle)) };
+        let _ = PREVIOUS.set(previous);
+    }
+
+    fn create(path: &[u16]) -> Option<HANDLE> {
+        unsafe {
+            CreateFileW(
+                PCWSTR(path.as_ptr()),
+                GENERIC_WRITE.0,
+                FILE_SHARE_NONE,
+                None,
+                CREATE_ALWAYS,
+                FILE_ATTRIBUTE_NORMAL,
+                None,
+            )
+        }
+        .ok()
+    }
+
+    unsafe extern "system" fn handle(exception: *const EXCEPTION_POINTERS) -> i32 {
+        if let Some(paths) = PATHS.get() {
+            let code = unsafe { exception.as_ref() }
+                .and_then(|exception| unsafe { exception.ExceptionRecord.as_ref() })
+                .map(|record| record.ExceptionCode.0 as u32)
+                .unwrap_or(0);
+            let mut hex = *b"0x00000000";
+            for (index, digit) in hex[2..].iter_mut().enumerate() {
+                *digit = b"0123456789ABCDEF"[((code >> (28 - index * 4)) & 0xF) as usize];
+            }
+            if let Some(file) = create(&paths.marker) {
+                unsafe {
+                    let _ = WriteFile(file, Some(&hex), None, None);
+                    let _ = CloseHandle(file);
+                }
+            }
+            if let Some(file) = create(&paths.dump) {
+                let information = MINIDUMP_EXCEPTION_INFORMATION {
+                    ThreadId: unsafe { GetCurrentThreadId() },
+                    ExceptionPointers: exception as *mut _,
+                    ClientPointers: false.into(),
+                };
+                unsafe {
+                    let _ = MiniDumpWriteDump(
+                        GetCurrentProcess(),
+                        GetCurrentProcessId(),
+                        file,
+                        MiniDumpWithThreadInfo,
+                        Some(&information),
+                        None,
+                        None,
+                    );
+                    let _ = CloseHandle(file);
+                }
+            }
+        }
+        match PREVIOUS.get().copied().flatten() {
+            Some(previous) => unsafe { previous(exception) },
+            None => CONTINUE_SEARCH,
+        }
+    }
+
+    /// The exception code the filter wrote, named when it's a common one
+    pub fn describe(code: &str) -> String {
+        let name = match code {
+            "0xC0000005" => "EXCEPTION_ACCESS_VIOLATION",
+            "0xC00000FD" => "EXCEPTION_STACK_OVERFLOW",
+            "0xC000001D" => "EXCEPTION_ILLEGAL_INSTRUCTION",
+            "0xC0000094" => "EXCEPTION_INT_DIVIDE_BY_ZERO",
+            "0xC0000409" => "STATUS_STACK_BUFFER_OVERRUN",
+            _ => return format!("exception {}", code),
+        };
+        format!("{} ({})", name, code)
+    }
+
+    /// Windows Error Reporting keeps its reports to itself
+    pub fn system_report(_crashed_at: u64) -> Option<PathBuf> {
+        None
+    }
+}
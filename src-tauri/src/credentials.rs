@@ -0,0 +1,259 @@
+use tauri::{AppHandle, Runtime};
+
+/// The only secrets the frontend may store, read or delete. Anything else is
+/// refused, so a compromised webview can't use the keychain as storage or
+/// probe for other items under smudge's name.
+pub const ALLOWED: &[&str] = &["ai-api-key", "extension-token", "git-token", "sync-token"];
+
+fn check_allowed(key: &str) -> Result<(), String> {
+    if ALLOWED.contains(&key) {
+        Ok(())
+    } else {
+        Err(format!("\"{}\" isn't a credential smudge keeps", key))
+    }
+}
+
+/// Secrets are filed under the bundle identifier, with `key` as the account
+fn service<R: Runtime>(app: &AppHandle<R>) -> String {
+    app.config().identifier.clone()
+}
+
+/// Save `secret` under `key`, replacing what was there
+pub fn store(app: &AppHandle, key: &str, secret: &str) -> Result<(), String> {
+    check_allowed(key)?;
+    platform::store(&service(app), key, secret)?;
+    log::info!(key = key; "stored credential");
+    Ok(())
+}
+
+/// The secret saved under `key`, if any
+pub fn retrieve(app: &AppHandle, key: &str) -> Result<Option<String>, String> {
+    check_allowed(key)?;
+    platform::retrieve(&service(app), key)
+}
+
+/// Forget the secret saved under `key`. Deleting one that isn't there is
+/// not an error.
+pub fn delete(app: &AppHandle, key: &str) -> Result<(), String> {
+    check_allowed(key)?;
+    platform::delete(&service(app), key)?;
+    log::info!(key = key; "deleted credential");
+    Ok(())
+}
+
+/// OAuth tokens are filed apart from `ALLOWED`, so the frontend only gets
+/// them through `auth`, which keeps refresh tokens to itself
+fn oauth_key(provider: &str) -> String {
+    format!("oauth-{}", provider)
+}
+
+pub fn store_oauth(app: &AppHandle, provider: &str, tokens: &str) -> Result<(), String> {
+    platform::store(&service(app), &oauth_key(provider), tokens)?;
+    log::info!(provider = provider; "stored OAuth tokens");
+    Ok(())
+}
+
+pub fn retrieve_oauth(app: &AppHandle, provider: &str) -> Result<Option<String>, String> {
+    platform::retrieve(&service(app), &oauth_key(provider))
+}
+
+pub fn delete_oauth(app: &AppHandle, provider: &str) -> Result<(), String> {
+    platform::delete(&service(app), &oauth_key(provider))?;
+    log::info!(provider = provider; "deleted OAuth tokens");
+    Ok(())
+}
+
+/// The key that wraps the database's data key, also kept apart from
+/// `ALLOWED` so the frontend can never read it
+const DATABASE_KEY: &str = "database-key";
+
+pub fn store_database_key<R: Runtime>(app: &AppHandle<R>, key: &str) -> Result<(), String> {
+    platform::store(&service(app), DATABASE_KEY, key)?;
+    log::info!("stored the database key");
+    Ok(())
+}
+
+pub fn retrieve_database_key<R: Runtime>(app: &AppHandle<R>) -> Result<Option<String>, String> {
+    platform::retrieve(&service(app), DATABASE_KEY)
+}
+
+pub fn delete_database_key<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    platform::delete(&service(app), DATABASE_KEY)?;
+    log::info!("deleted the database key");
+    Ok(())
+}
+
+/// Generic passwords in the login keychain
+#[cfg(target_os = "macos")]
+mod platform {
+    use security_framework::passwords;
+
+    /// `errSecItemNotFound`
+    const ITEM_NOT_FOUND: i32 = -25300;
+
+    pub fn store(service: &str, key: &str, secret: &str) -> Result<(), String> {
+        passwords::set_generic_password(service, key, secret.as_bytes())
+            .map_err(|e| format!("Couldn't save to the keychain: {}", e))
+    }
+
+    pub fn retrieve(service: &str, key: &str) -> Result<Option<String>, String> {
+        match passwords::get_generic_password(service, key) {
+            Ok(secret) => String::from_utf8(secret)
+                .map(Some)
+                .map_err(|_| "The saved credential isn't text".to_string()),
+            Err(e) if e.code() == ITEM_NOT_FOUND => Ok(None),
+            Err(e) => Err(format!("Couldn't read the keychain: {}", e)),
+        }
+    }
+
+    pub fn delete(service: &str, key: &str) -> Result<(), String> {
+        match passwords::delete_generic_password(service, key) {
+            Err(e) if e.code() != ITEM_NOT_FOUND => {
+                Err(format!("Couldn't delete from the keychain: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Generic credentials in Credential Manager, named "<service>/<key>"
+#[cfg(windows)]
+mod platform {
+    use windows::core::{HSTRING, PWSTR};
+    use windows::Win32::Foundation::ERROR_NOT_FOUND;
+    use windows::Win32::Security::Credentials::{
+        CredDeleteW, CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE,
+        CRED_TYPE_GENERIC,
+    };
+
+    fn target(service: &str, key: &str) -> HSTRING {
+        HSTRING::from(format!("{}/{}", service, key))
+    }
+
+    fn not_found(e: &windows::core::Error) -> bool {
+        e.code() == ERROR_NOT_FOUND.to_hresult()
+    }
+
+    pub fn store(service: &str, key: &str, secret: &str) -> Result<(), String> {
+        let target = target(service, key);
+        let mut user_name: Vec<u16> = key.encode_utf16().chain([0]).collect();
+        let mut blob = secret.as_bytes().to_vec();
+        let credential = CREDENTIALW {
+            Type: CRED_TYPE_GENERIC,
+            TargetName: PWSTR(target.as_ptr() as *mut u16),
+            CredentialBlobSize: blob.len() as u32,
+            CredentialBlob: blob.as_mut_ptr(),
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            UserName: PWSTR(user_name.as_mut_ptr()),
+            ..Default::default()
+        };
+        unsafe { CredWriteW(&credential, 0) }
+            .map_err(|e| format!("Couldn't save to Credential Manager: {}", e))
+    }
+
+    pub fn retrieve(service: &str, key: &str) -> Result<Option<String>, String> {
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        match unsafe {
+            CredReadW(
+                &target(service, key),
+                CRED_TYPE_GENERIC,
+                None,
+                &mut credential,
+            )
+        } {
+            Ok(()) => {}
+            Err(e) if not_found(&e) => return Ok(None),
+            Err(e) => return Err(format!("Couldn't read Credential Manager: {}", e)),
+        }
+        let secret = unsafe {
+            let credential = &*credential;
+            std::slice::from_raw_parts(
+                credential.CredentialBlob,
+                credential.CredentialBlobSize as usize,
+            )
+            .to_vec()
+        };
+        unsafe { CredFree(credential as *const _) };
+        String::from_utf8(secret)
+            .map(Some)
+            .map_err(|_| "The saved credential isn't text".to_string())
+    }
+
+    pub fn delete(service: &str, key: &str) -> Result<(), String> {
+        match unsafe { CredDeleteW(&target(service, key), CRED_TYPE_GENERIC, None) } {
+            Err(e) if !not_found(&e) => {
+                Err(format!("Couldn't delete from Credential Manager: {}", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The Secret Service (GNOME Keyring, KWallet) through libsecret's
+/// secret-tool, with the service and key as lookup attributes
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    fn secret_tool(args: &[&str], input: Option<&str>) -> Result<std::process::Output, String> {
+        let mut child = Command::new("secret-tool")
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Couldn't run secret-tool (is libsecret installed?): {}", e))?;
+        // The secret goes through stdin, never the command line
+        if let Some(input) = input {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(input.as_bytes())
+                    .map_err(|e| format!("Couldn't pass the secret to secret-tool: {}", e))?;
+            }
+        }
+        drop(child.stdin.take());
+        child.wait_with_output().map_err(|e| e.to_string())
+    }
+
+    pub fn store(service: &str, key: &str, secret: &str) -> Result<(), String> {
+        let label = format!("{} {}", service, key);
+        let output = secret_tool(
+            &["store", "--label", &label, "service", service, "key", key],
+            Some(secret),
+        )?;
+        if !output.status.success() {
+            return Err(format!(
+                "Couldn't save to the keyring: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn retrieve(service: &str, key: &str) -> Result<Option<String>, String> {
+        let output = secret_tool(&["lookup", "service", service, "key", key], None)?;
+        // secret-tool fails quietly when nothing matches
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.trim().is_empty() {
+                return Ok(None);
+            }
+            return Err(format!("Couldn't read the keyring: {}", stderr.trim()));
+        }
+        String::from_utf8(output.stdout)
+            .map(Some)
+            .map_err(|_| "The saved credential isn't text".to_string())
+    }
+
+    pub fn delete(service: &str, key: &str) -> Result<(), String> {
+        let output = secret_tool(&["clear", "service", service, "key", key], None)?;
+        if !output.status.success() && !output.stderr.is_empty() {
+            return Err(format!(
+                "Couldn't delete from the keyring: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+}
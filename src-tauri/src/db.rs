@@ -0,0 +1,602 @@
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::db_encryption;
+
+/// The database, in app data
+const FILE_NAME: &str = "smudge.db";
+/// A backup staged by `stage_restore`, swapped in at the next launch
+const RESTORE_FILE_NAME: &str = "smudge.db.restore";
+/// The encrypted copy `Database::encrypt` writes before swapping it in
+const ENCRYPTING_FILE_NAME: &str = "smudge.db.encrypting";
+
+/// Namespaces the frontend's commands use are kept apart from smudge's own
+const FRONTEND_PREFIX: &str = "frontend.";
+
+/// Schema changes in order. `PRAGMA user_version` counts those that have
+/// run, so a shipped migration never changes; add another instead.
+const MIGRATIONS: &[&str] = &[
+    // 1: JSON values by namespace and key
+    "CREATE TABLE entries (
+        namespace TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        updated INTEGER NOT NULL,
+        PRIMARY KEY (namespace, key)
+    ) WITHOUT ROWID;",
+];
+
+/// JSON files the database replaced, imported once at launch and then
+/// removed
+enum Legacy {
+    /// An object whose members become the namespace's entries
+    Map {
+        file: &'static str,
+        namespace: &'static str,
+    },
+    /// A value stored whole under one key
+    Value {
+        file: &'static str,
+        namespace: &'static str,
+        key: &'static str,
+    },
+}
+
+const LEGACY: &[Legacy] = &[
+    Legacy::Map {
+        file: "window-placement.json",
+        namespace: crate::placement::NAMESPACE,
+    },
+    Legacy::Map {
+        file: "dialog-directories.json",
+        namespace: crate::dialogs::NAMESPACE,
+    },
+    Legacy::Value {
+        file: "window-layout.json",
+        namespace: crate::windows::NAMESPACE,
+        key: crate::windows::LAYOUT_KEY,
+    },
+];
+
+/// An entry as the frontend gets it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub key: String,
+    pub value: serde_json::Value,
+    /// Seconds since the epoch
+    pub updated: i64,
+}
+
+/// Whether the database is encrypted at rest, and if so whether it's
+/// locked
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Encryption {
+    pub encrypted: bool,
+    /// Only an encrypted database locks, and nothing can read or write it
+    /// until it's unlocked
+    pub locked: bool,
+}
+
+struct Store {
+    path: PathBuf,
+    /// `None` while the database is locked
+    connection: Option<Connection>,
+    encrypted: bool,
+}
+
+/// smudge's structured data: JSON values by namespace and key in a SQLite
+/// database in WAL mode, optionally encrypted page by page. Cheap to clone;
+/// clones share the connection.
+#[derive(Clone)]
+pub struct Database(Arc<Mutex<Store>>);
+
+/// The open connection, held for as long as this is
+struct Connected<'a>(MutexGuard<'a, Store>);
+
+impl Deref for Connected<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.0.connection.as_ref().expect("connected")
+    }
+}
+
+impl DerefMut for Connected<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.0.connection.as_mut().expect("connected")
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn sql_error(e: rusqlite::Error) -> String {
+    e.to_string()
+}
+
+/// Bring the schema up to date, one transaction per migration
+fn migrate(connection: &mut Connection) -> Result<(), String> {
+    let version: usize = connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(sql_error)?;
+    if version > MIGRATIONS.len() {
+        return Err("The database is from a newer version of Smudge".to_string());
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction().map_err(sql_error)?;
+        transaction.execute_batch(migration).map_err(sql_error)?;
+        transaction
+            .pragma_update(None, "user_version", index + 1)
+            .map_err(sql_error)?;
+        transaction.commit().map_err(sql_error)?;
+        log::info!(version = index + 1; "database migrated");
+    }
+    Ok(())
+}
+
+/// Open the database at `path` in WAL mode and bring its schema up to date
+fn connect(path: &Path, encrypted: bool) -> Result<Connection, String> {
+    let mut connection = if encrypted {
+        let connection = db_encryption::open(path, OpenFlags::default())?;
+        // Temporary files don't go through the encryption
+        connection
+            .pragma_update(None, "temp_store", "MEMORY")
+            .map_err(sql_error)?;
+        connection
+    } else {
+        Connection::open(path).map_err(sql_error)?
+    };
+    // journal_mode answers with the mode it ended up in
+    let mode: String = connection
+        .query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))
+        .map_err(sql_error)?;
+    if mode != "wal" {
+        // An encrypted database's rollback journal would hold its pages in
+        // the clear
+        if encrypted {
+            return Err(format!(
+                "The encrypted database can't be opened in {} mode",
+                mode
+            ));
+        }
+        log::warn!(mode:% = mode; "database not in WAL mode");
+    }
+    connection
+        .pragma_update(None, "synchronous", "NORMAL")
+        .map_err(sql_error)?;
+    connection
+        .busy_timeout(Duration::from_secs(5))
+        .map_err(sql_error)?;
+    migrate(&mut connection)?;
+    Ok(connection)
+}
+
+/// Copy every table in `source`, and the schema version, into a new
+/// encrypted database at `destination`
+fn copy_encrypted(source: &Connection, destination: &Path) -> Result<(), String> {
+    let mut target = db_encryption::open(destination, OpenFlags::default())?;
+    db_encryption::prepare(&target)?;
+    target
+        .pragma_update(None, "temp_store", "MEMORY")
+        .map_err(sql_error)?;
+    let mode: String = target
+        .query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))
+        .map_err(sql_error)?;
+    if mode != "wal" {
+        return Err(format!(
+            "The encrypted database can't be written in {} mode",
+            mode
+        ));
+    }
+    let transaction = target.transaction().map_err(sql_error)?;
+
+    // Tables first, so their indexes have something to index
+    let mut statement = source
+        .prepare(
+            "SELECT type, name, sql FROM sqlite_schema
+             WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%'
+             ORDER BY type = 'table' DESC, rowid",
+        )
+        .map_err(sql_error)?;
+    let schema = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(sql_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(sql_error)?;
+    for (kind, name, sql) in &schema {
+        transaction.execute_batch(sql).map_err(sql_error)?;
+        if kind != "table" {
+            continue;
+        }
+        let name = name.replace('"', "\"\"");
+        let mut rows = source
+            .prepare(&format!("SELECT * FROM \"{}\"", name))
+            .map_err(sql_error)?;
+        let columns = rows.column_count();
+        let placeholders = vec!["?"; columns].join(", ");
+        let mut insert = transaction
+            .prepare(&format!(
+                "INSERT INTO \"{}\" VALUES ({})",
+                name, placeholders
+            ))
+            .map_err(sql_error)?;
+        let mut rows = rows.query([]).map_err(sql_error)?;
+        while let Some(row) = rows.next().map_err(sql_error)? {
+            let values = (0..columns)
+                .map(|column| row.get::<_, rusqlite::types::Value>(column))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(sql_error)?;
+            insert
+                .execute(rusqlite::params_from_iter(values))
+                .map_err(sql_error)?;
+        }
+    }
+
+    let version: usize = source
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(sql_error)?;
+    transaction
+        .pragma_update(None, "user_version", version)
+        .map_err(sql_error)?;
+    transaction.commit().map_err(sql_error)?;
+    // Closing checkpoints the WAL into the file about to be swapped in
+    target.close().map_err(|(_, e)| sql_error(e))
+}
+
+/// Remove the database at `path` and its WAL files, if they're there
+fn remove_database(path: &Path) {
+    let _ = std::fs::remove_file(path);
+    remove_wal(path);
+}
+
+fn remove_wal(path: &Path) {
+    for suffix in ["-wal", "-shm"] {
+        let mut wal = path.as_os_str().to_owned();
+        wal.push(suffix);
+        let _ = std::fs::remove_file(wal);
+    }
+}
+
+/// Check that `path` is a database smudge can restore. An encrypted backup
+/// can only be read with the key it was made with.
+fn check_backup(path: &Path) -> Result<(), String> {
+    let connection = if db_encryption::is_encrypted(path) {
+        if !db_encryption::is_unlocked() {
+            return Err("The backup is encrypted, and its key isn't loaded".to_string());
+        }
+        db_encryption::open(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?
+    } else {
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(sql_error)?
+    };
+    let version: usize = connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|_| "That isn't a Smudge backup".to_string())?;
+    if version == 0 {
+        return Err("That isn't a Smudge backup".to_string());
+    }
+    if version > MIGRATIONS.len() {
+        return Err("The backup is from a newer version of Smudge".to_string());
+    }
+    Ok(())
+}
+
+/// Swap in a backup staged by `stage_restore`, dropping the database it
+/// replaces along with its WAL files
+fn restore_staged(dir: &Path) {
+    let staged = dir.join(RESTORE_FILE_NAME);
+    if !staged.exists() {
+        return;
+    }
+    let path = dir.join(FILE_NAME);
+    remove_wal(&path);
+    match std::fs::rename(&staged, &path) {
+        Ok(()) => log::info!("database restored from backup"),
+        Err(e) => log::warn!(error:% = e; "backup not restored"),
+    }
+}
+
+impl Database {
+    pub fn open<R: Runtime>(app: &AppHandle<R>) -> Result<Self, String> {
+        let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        restore_staged(&dir);
+        // Left by an encryption that didn't finish
+        remove_database(&dir.join(ENCRYPTING_FILE_NAME));
+
+        let path = dir.join(FILE_NAME);
+        let encrypted = db_encryption::is_encrypted(&path);
+        if encrypted {
+            db_encryption::load(app, &dir)?;
+        }
+        let connection = connect(&path, encrypted)?;
+
+        let database = Self(Arc::new(Mutex::new(Store {
+            path,
+            connection: Some(connection),
+            encrypted,
+        })));
+        database.import_legacy(&dir);
+        Ok(database)
+    }
+
+    fn import_legacy(&self, dir: &Path) {
+        for legacy in LEGACY {
+            let (file, namespace) = match legacy {
+                Legacy::Map { file, namespace }
+                | Legacy::Value {
+                    file, namespace, ..
+                } => (dir.join(file), *namespace),
+            };
+            let Some(value) = std::fs::read_to_string(&file)
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            else {
+                continue;
+            };
+            let imported = match (legacy, value) {
+                (Legacy::Map { .. }, serde_json::Value::Object(members)) => {
+                    self.replace(namespace, members.iter())
+                }
+                (Legacy::Value { key, .. }, value) => self.put(namespace, key, &value),
+                _ => Err("not an object".to_string()),
+            };
+            match imported {
+                Ok(()) => {
+                    let _ = std::fs::remove_file(&file);
+                    log::info!(file:? = file; "imported into the database");
+                }
+                Err(e) => log::warn!(file:? = file, error:% = e; "not imported"),
+            }
+        }
+    }
+
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.0.lock().expect("database mutex")
+    }
+
+    fn connection(&self) -> Result<Connected<'_>, String> {
+        let store = self.store();
+        if store.connection.is_none() {
+            return Err("The database is locked".to_string());
+        }
+        Ok(Connected(store))
+    }
+
+    pub fn encryption(&self) -> Encryption {
+        let store = self.store();
+        Encryption {
+            encrypted: store.encrypted,
+            locked: store.connection.is_none(),
+        }
+    }
+
+    /// Encrypt the database in place: copy it into a new encrypted database
+    /// and swap that in. Its data key is wrapped by a new key kept in the
+    /// keychain.
+    pub fn encrypt<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), String> {
+        let mut store = self.store();
+        if store.encrypted {
+            return Err("The database is already encrypted".to_string());
+        }
+        let path = store.path.clone();
+        let dir = path.parent().ok_or("Invalid database path")?;
+        let encrypting = dir.join(ENCRYPTING_FILE_NAME);
+        remove_database(&encrypting);
+
+        let source = store.connection.as_ref().ok_or("The database is locked")?;
+        db_encryption::create(app, dir)?;
+        if let Err(e) = copy_encrypted(source, &encrypting) {
+            remove_database(&encrypting);
+            db_encryption::discard(app, dir);
+            return Err(format!("Failed to encrypt the database: {}", e));
+        }
+
+        // Closing the plain database checkpoints its WAL, which goes with it
+        store.connection = None;
+        if let Err(e) = std::fs::rename(&encrypting, &path) {
+            remove_database(&encrypting);
+            db_encryption::discard(app, dir);
+            store.connection = Some(connect(&path, false)?);
+            return Err(format!("Failed to encrypt the database: {}", e));
+        }
+        remove_wal(&path);
+        store.connection = Some(connect(&path, true)?);
+        store.encrypted = true;
+        log::info!("database encrypted");
+        Ok(())
+    }
+
+    /// Close an encrypted database and let go of its key, until `unlock`.
+    /// A plain database stays open.
+    pub fn lock(&self) {
+        let mut store = self.store();
+        if !store.encrypted || store.connection.is_none() {
+            return;
+        }
+        // Closing checkpoints the WAL, which needs the key
+        store.connection = None;
+        db_encryption::forget();
+        log::info!("database locked");
+    }
+
+    /// Unwrap the key with the keychain's and open the database again
+    pub fn unlock<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), String> {
+        let mut store = self.store();
+        if store.connection.is_some() {
+            return Ok(());
+        }
+        let dir = store.path.parent().ok_or("Invalid database path")?;
+        db_encryption::load(app, dir)?;
+        let connection = connect(&store.path, true)?;
+        store.connection = Some(connection);
+        log::info!("database unlocked");
+        Ok(())
+    }
+
+    pub fn get<T: DeserializeOwned>(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> Result<Option<T>, String> {
+        let value: Option<String> = self
+            .connection()?
+            .query_row(
+                "SELECT value FROM entries WHERE namespace = ?1 AND key = ?2",
+                params![namespace, key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(sql_error)?;
+        value
+            .map(|value| serde_json::from_str(&value).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    pub fn put<T: Serialize + ?Sized>(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &T,
+    ) -> Result<(), String> {
+        let value = serde_json::to_string(value).map_err(|e| e.to_string())?;
+        self.connection()?
+            .execute(
+                "INSERT INTO entries (namespace, key, value, updated) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (namespace, key) DO UPDATE
+                 SET value = excluded.value, updated = excluded.updated",
+                params![namespace, key, value, now()],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, namespace: &str, key: &str) -> Result<(), String> {
+        self.connection()?
+            .execute(
+                "DELETE FROM entries WHERE namespace = ?1 AND key = ?2",
+                params![namespace, key],
+            )
+            .map_err(sql_error)?;
+        Ok(())
+    }
+
+    /// Every entry in `namespace` by key. Values that no longer decode as
+    /// `T` are left out.
+    pub fn list<T: DeserializeOwned>(&self, namespace: &str) -> Result<Vec<(String, T)>, String> {
+        Ok(self
+            .entries(namespace)?
+            .into_iter()
+            .filter_map(|entry| Some((entry.key, serde_json::from_value(entry.value).ok()?)))
+            .collect())
+    }
+
+    pub fn entries(&self, namespace: &str) -> Result<Vec<Entry>, String> {
+        let connection = self.connection()?;
+        let mut statement = connection
+            .prepare("SELECT key, value, updated FROM entries WHERE namespace = ?1 ORDER BY key")
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map(params![namespace], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })
+            .map_err(sql_error)?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let (key, value, updated) = row.map_err(sql_error)?;
+            if let Ok(value) = serde_json::from_str(&value) {
+                entries.push(Entry {
+                    key,
+                    value,
+                    updated,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Make `entries` the whole of `namespace`, in one transaction
+    pub fn replace<'a, K, T>(
+        &self,
+        namespace: &str,
+        entries: impl IntoIterator<Item = (K, &'a T)>,
+    ) -> Result<(), String>
+    where
+        K: AsRef<str>,
+        T: Serialize + 'a,
+    {
+        let mut connection = self.connection()?;
+        let transaction = connection.transaction().map_err(sql_error)?;
+        transaction
+            .execute(
+                "DELETE FROM entries WHERE namespace = ?1",
+                params![namespace],
+            )
+            .map_err(sql_error)?;
+        let updated = now();
+        for (key, value) in entries {
+            let value = serde_json::to_string(value).map_err(|e| e.to_string())?;
+            transaction
+                .execute(
+                    "INSERT INTO entries (namespace, key, value, updated) VALUES (?1, ?2, ?3, ?4)",
+                    params![namespace, key.as_ref(), value, updated],
+                )
+                .map_err(sql_error)?;
+        }
+        transaction.commit().map_err(sql_error)
+    }
+
+    /// Write a consistent copy of the whole database to `destination`, a
+    /// single file `stage_restore` takes back
+    pub fn back_up(&self, destination: &Path) -> Result<(), String> {
+        // VACUUM INTO won't overwrite; the save dialog already asked
+        if destination.exists() {
+            std::fs::remove_file(destination).map_err(|e| e.to_string())?;
+        }
+        let destination = destination.to_str().ok_or("Invalid backup path")?;
+        // An encrypted database's backup is encrypted with the same key
+        self.connection()?
+            .execute("VACUUM INTO ?1", params![destination])
+            .map_err(sql_error)?;
+        Ok(())
+    }
+}
+
+/// The namespace the frontend's `namespace` is stored under
+pub fn frontend_namespace(namespace: &str) -> Result<String, String> {
+    if namespace.is_empty() {
+        return Err("Empty namespace".to_string());
+    }
+    Ok(format!("{}{}", FRONTEND_PREFIX, namespace))
+}
+
+/// Check the backup at `source` and stage it to replace the database at the
+/// next launch, before anything reads it. The caller restarts the app.
+pub fn stage_restore<R: Runtime>(app: &AppHandle<R>, source: &Path) -> Result<(), String> {
+    check_backup(source)?;
+    let dir: PathBuf = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    std::fs::copy(source, dir.join(RESTORE_FILE_NAME))
+        .map_err(|e| format!("Failed to stage the backup: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,853 @@
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use rusqlite::{ffi, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Once, RwLock};
+use tauri::{AppHandle, Runtime};
+
+use crate::credentials;
+
+/// The SQLite VFS encrypted databases are opened through
+const VFS_NAME: &CStr = c"smudge-encrypted";
+
+/// The data key, wrapped by the keychain's key, beside the database
+const KEY_FILE_NAME: &str = "smudge.db.key";
+
+/// Encrypted databases are always made with pages this size
+pub const PAGE_SIZE: usize = 4096;
+const TAG_LEN: usize = 16;
+/// The bytes SQLite leaves unused at the end of each page, which hold the
+/// page's tag and nonce
+const RESERVE: usize = TAG_LEN + NONCE_LEN;
+/// How a page is laid out on disk: its encrypted content, then the tag,
+/// then the nonce
+const SEALED: usize = PAGE_SIZE - RESERVE;
+
+/// What every unencrypted database starts with
+const MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// A WAL file's header, then each frame's header before its page
+const WAL_HEADER: u64 = 32;
+const WAL_FRAME_HEADER: u64 = 24;
+
+/// The data key while the database is unlocked
+static KEY: RwLock<Option<LessSafeKey>> = RwLock::new(None);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyFile {
+    /// The data key sealed with the keychain's key, then that seal's nonce,
+    /// in base64
+    wrapped: String,
+}
+
+fn random<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    getrandom::fill(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+fn aead_key(bytes: &[u8]) -> Result<LessSafeKey, String> {
+    UnboundKey::new(&AES_256_GCM, bytes)
+        .map(LessSafeKey::new)
+        .map_err(|_| "The database key is the wrong length".to_string())
+}
+
+/// Whether the database at `path` is encrypted: it's there, but doesn't
+/// start as SQLite databases do
+pub fn is_encrypted(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| &header != MAGIC)
+}
+
+/// Whether the data key is loaded, so encrypted databases can be read
+pub fn is_unlocked() -> bool {
+    KEY.read().expect("database key lock").is_some()
+}
+
+/// Make a data key for a database about to be encrypted in `dir`, wrap it
+/// with a new key kept in the keychain, and load it
+pub fn create<R: Runtime>(app: &AppHandle<R>, dir: &Path) -> Result<(), String> {
+    let data_key = random::<32>()?;
+    let wrapping_key = random::<32>()?;
+    let engine = base64::engine::general_purpose::STANDARD;
+    credentials::store_database_key(app, &engine.encode(wrapping_key))?;
+
+    let nonce = random::<NONCE_LEN>()?;
+    let mut wrapped = data_key.to_vec();
+    aead_key(&wrapping_key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut wrapped,
+        )
+        .map_err(|_| "The database key couldn't be wrapped".to_string())?;
+    wrapped.extend_from_slice(&nonce);
+    let key_file = KeyFile {
+        wrapped: engine.encode(&wrapped),
+    };
+    let json = serde_json::to_string(&key_file).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(KEY_FILE_NAME), json)
+        .map_err(|e| format!("Failed to save the database key: {}", e))?;
+    *KEY.write().expect("database key lock") = Some(aead_key(&data_key)?);
+    Ok(())
+}
+
+/// Unwrap the data key for the encrypted database in `dir` with the
+/// keychain's key, and load it
+pub fn load<R: Runtime>(app: &AppHandle<R>, dir: &Path) -> Result<(), String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let content = std::fs::read_to_string(dir.join(KEY_FILE_NAME))
+        .map_err(|e| format!("The database key is missing: {}", e))?;
+    let key_file: KeyFile = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    let mut wrapped = engine.decode(key_file.wrapped).map_err(|e| e.to_string())?;
+    let wrapping_key =
+        credentials::retrieve_database_key(app)?.ok_or("The database key isn't in the keychain")?;
+    let wrapping_key = engine.decode(wrapping_key).map_err(|e| e.to_string())?;
+
+    let Some(split) = wrapped.len().checked_sub(NONCE_LEN) else {
+        return Err("The database key file is damaged".to_string());
+    };
+    let nonce = Nonce::try_assume_unique_for_key(&wrapped[split..])
+        .map_err(|_| "The database key file is damaged".to_string())?;
+    let data_key = aead_key(&wrapping_key)?
+        .open_in_place(nonce, Aad::empty(), &mut wrapped[..split])
+        .map_err(|_| "The keychain's key doesn't unlock the database".to_string())?;
+    let data_key = aead_key(data_key)?;
+    *KEY.write().expect("database key lock") = Some(data_key);
+    Ok(())
+}
+
+/// Let go of the data key. Encrypted databases can't be read until `load`
+/// unwraps it again.
+pub fn forget() {
+    *KEY.write().expect("database key lock") = None;
+}
+
+/// Forget the data key made by `create`, and everything that wrapped it,
+/// after encrypting the database failed
+pub fn discard<R: Runtime>(app: &AppHandle<R>, dir: &Path) {
+    forget();
+    let _ = std::fs::remove_file(dir.join(KEY_FILE_NAME));
+    if let Err(e) = credentials::delete_database_key(app) {
+        log::warn!(error:% = e; "database key not deleted from the keychain");
+    }
+}
+
+/// Open the encrypted database at `path`, or make it if it isn't there. A new
+/// one has to be `prepare`d before anything is written to it.
+pub fn open(path: &Path, flags: OpenFlags) -> Result<Connection, String> {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| unsafe { vfs::register() });
+    Connection::open_with_flags_and_vfs(path, flags, VFS_NAME).map_err(|e| e.to_string())
+}
+
+/// Set a new encrypted database's page size and leave room at the end of
+/// each page for its tag and nonce
+pub fn prepare(connection: &Connection) -> Result<(), String> {
+    connection
+        .pragma_update(None, "page_size", PAGE_SIZE)
+        .map_err(|e| e.to_string())?;
+    let mut reserve = RESERVE as c_int;
+    let result = unsafe {
+        ffi::sqlite3_file_control(
+            connection.handle(),
+            c"main".as_ptr(),
+            ffi::SQLITE_FCNTL_RESERVE_BYTES,
+            (&mut reserve as *mut c_int).cast(),
+        )
+    };
+    if result != ffi::SQLITE_OK {
+        return Err("Couldn't make room for the database's encryption".to_string());
+    }
+    Ok(())
+}
+
+/// The files the VFS opens
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum Kind {
+    Plain,
+    Database,
+    Wal,
+}
+
+/// What a page is sealed to: the kind of file it's in and its number
+/// there, so it can't be moved to another page or file
+fn page_aad(kind: Kind, number: u64) -> Aad<[u8; 9]> {
+    let mut aad = [0u8; 9];
+    aad[0] = kind as u8;
+    aad[1..].copy_from_slice(&number.to_be_bytes());
+    Aad::from(aad)
+}
+
+/// Seal `page`, the `number`th of its file, in place with a fresh nonce
+fn seal(
+    key: &LessSafeKey,
+    kind: Kind,
+    number: u64,
+    page: &mut [u8; PAGE_SIZE],
+) -> Result<(), c_int> {
+    let nonce = random::<NONCE_LEN>().map_err(|_| ffi::SQLITE_IOERR_WRITE)?;
+    let (content, reserve) = page.split_at_mut(SEALED);
+    let tag = key
+        .seal_in_place_separate_tag(
+            Nonce::assume_unique_for_key(nonce),
+            page_aad(kind, number),
+            content,
+        )
+        .map_err(|_| ffi::SQLITE_IOERR_WRITE)?;
+    reserve[..TAG_LEN].copy_from_slice(tag.as_ref());
+    reserve[TAG_LEN..].copy_from_slice(&nonce);
+    Ok(())
+}
+
+/// Open `page`, the `number`th of its file, in place, leaving its reserved
+/// bytes zero as SQLite wrote them. Every page written was sealed, so one
+/// of zeros has been tampered with.
+fn open_page(
+    key: &LessSafeKey,
+    kind: Kind,
+    number: u64,
+    page: &mut [u8; PAGE_SIZE],
+) -> Result<(), c_int> {
+    if page.iter().all(|&byte| byte == 0) {
+        return Err(ffi::SQLITE_NOTADB);
+    }
+    let (sealed, nonce) = page.split_at_mut(SEALED + TAG_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| ffi::SQLITE_NOTADB)?;
+    key.open_in_place(nonce, page_aad(kind, number), sealed)
+        .map_err(|_| ffi::SQLITE_NOTADB)?;
+    page[SEALED..].fill(0);
+    Ok(())
+}
+
+/// A VFS over the platform's that encrypts every page of the database and
+/// its WAL, and passes everything else through: the WAL's headers, the
+/// shared memory index and temporary files, which connections keep in
+/// memory
+mod vfs {
+    use super::*;
+    use std::mem::size_of;
+    use std::ptr::null_mut;
+
+    /// SQLite allocates `szOsFile` bytes for each file: this, then the
+    /// platform VFS's own file
+    #[repr(C)]
+    struct File {
+        base: ffi::sqlite3_file,
+        kind: Kind,
+        real: *mut ffi::sqlite3_file,
+    }
+
+    /// A stretch of a file: plain bytes, or part of the page starting at
+    /// the offset given
+    enum Region {
+        Plain { end: u64 },
+        Page { start: u64 },
+    }
+
+    fn region(kind: Kind, offset: u64) -> Region {
+        let page = PAGE_SIZE as u64;
+        match kind {
+            Kind::Plain => Region::Plain { end: u64::MAX },
+            Kind::Database => Region::Page {
+                start: offset / page * page,
+            },
+            Kind::Wal if offset < WAL_HEADER => Region::Plain { end: WAL_HEADER },
+            Kind::Wal => {
+                let frame = WAL_FRAME_HEADER + page;
+                let start = WAL_HEADER + (offset - WAL_HEADER) / frame * frame;
+                if offset < start + WAL_FRAME_HEADER {
+                    Region::Plain {
+                        end: start + WAL_FRAME_HEADER,
+                    }
+                } else {
+                    Region::Page {
+                        start: start + WAL_FRAME_HEADER,
+                    }
+                }
+            }
+        }
+    }
+
+    /// The number of the page starting at `start`: its place in the
+    /// database, or its frame's in the WAL
+    fn page_number(kind: Kind, start: u64) -> u64 {
+        let page = PAGE_SIZE as u64;
+        match kind {
+            Kind::Wal => (start - WAL_HEADER - WAL_FRAME_HEADER) / (WAL_FRAME_HEADER + page),
+            Kind::Plain | Kind::Database => start / page,
+        }
+    }
+
+    unsafe fn file<'a>(file: *mut ffi::sqlite3_file) -> &'a mut File {
+        &mut *(file as *mut File)
+    }
+
+    unsafe fn methods<'a>(real: *mut ffi::sqlite3_file) -> &'a ffi::sqlite3_io_methods {
+        &*(*real).pMethods
+    }
+
+    unsafe fn read_real(real: *mut ffi::sqlite3_file, buffer: &mut [u8], offset: u64) -> c_int {
+        (methods(real).xRead.expect("xRead"))(
+            real,
+            buffer.as_mut_ptr().cast(),
+            buffer.len() as c_int,
+            offset as i64,
+        )
+    }
+
+    unsafe fn write_real(real: *mut ffi::sqlite3_file, buffer: &[u8], offset: u64) -> c_int {
+        (methods(real).xWrite.expect("xWrite"))(
+            real,
+            buffer.as_ptr().cast(),
+            buffer.len() as c_int,
+            offset as i64,
+        )
+    }
+
+    /// The page starting at `start`, opened. Past the end of the file it's
+    /// zeros, and the read is short.
+    unsafe fn read_page(
+        real: *mut ffi::sqlite3_file,
+        kind: Kind,
+        start: u64,
+        page: &mut [u8; PAGE_SIZE],
+    ) -> c_int {
+        let result = read_real(real, page, start);
+        if result != ffi::SQLITE_OK && result != ffi::SQLITE_IOERR_SHORT_READ {
+            return result;
+        }
+        if result == ffi::SQLITE_IOERR_SHORT_READ {
+            let mut size = 0;
+            let status = (methods(real).xFileSize.expect("xFileSize"))(real, &mut size);
+            if status != ffi::SQLITE_OK {
+                return status;
+            }
+            // Nothing was there to open; a page cut short is opened, and fails
+            if start >= size as u64 {
+                return result;
+            }
+        }
+        let key = KEY.read().expect("database key lock");
+        let Some(key) = key.as_ref() else {
+            return ffi::SQLITE_IOERR_AUTH;
+        };
+        match open_page(key, kind, page_number(kind, start), page) {
+            Ok(()) => result,
+            Err(error) => error,
+        }
+    }
+
+    unsafe extern "C" fn read(
+        file_: *mut ffi::sqlite3_file,
+        buffer: *mut c_void,
+        amount: c_int,
+        offset: i64,
+    ) -> c_int {
+        let file = file(file_);
+        let buffer = std::slice::from_raw_parts_mut(buffer as *mut u8, amount as usize);
+        let mut offset = offset as u64;
+        let mut done = 0;
+        let mut short = false;
+        while done < buffer.len() {
+            let rest = &mut buffer[done..];
+            let length = match region(file.kind, offset) {
+                Region::Plain { end } => {
+                    let length = rest.len().min((end - offset) as usize);
+                    match read_real(file.real, &mut rest[..length], offset) {
+                        ffi::SQLITE_OK => {}
+                        ffi::SQLITE_IOERR_SHORT_READ => short = true,
+                        error => return error,
+                    }
+                    length
+                }
+                Region::Page { start } => {
+                    let mut page = [0u8; PAGE_SIZE];
+                    match read_page(file.real, file.kind, start, &mut page) {
+                        ffi::SQLITE_OK => {}
+                        ffi::SQLITE_IOERR_SHORT_READ => short = true,
+                        error => return error,
+                    }
+                    let from = (offset - start) as usize;
+                    let length = rest.len().min(PAGE_SIZE - from);
+                    rest[..length].copy_from_slice(&page[from..from + length]);
+                    length
+                }
+            };
+            done += length;
+            offset += length as u64;
+        }
+        if short {
+            ffi::SQLITE_IOERR_SHORT_READ
+        } else {
+            ffi::SQLITE_OK
+        }
+    }
+
+    /// Only databases `prepare` made, with room for each page's tag and
+    /// nonce, are written through the VFS
+    fn check_header(page: &[u8; PAGE_SIZE]) -> bool {
+        let page_size = u16::from_be_bytes([page[16], page[17]]) as usize;
+        page_size == PAGE_SIZE && page[20] as usize >= RESERVE
+    }
+
+    unsafe extern "C" fn write(
+        file_: *mut ffi::sqlite3_file,
+        buffer: *const c_void,
+        amount: c_int,
+        offset: i64,
+    ) -> c_int {
+        let file = file(file_);
+        let buffer = std::slice::from_raw_parts(buffer as *const u8, amount as usize);
+        let mut offset = offset as u64;
+        let mut done = 0;
+        while done < buffer.len() {
+            let rest = &buffer[done..];
+            let length = match region(file.kind, offset) {
+                Region::Plain { end } => {
+                    let length = rest.len().min((end - offset) as usize);
+                    let result = write_real(file.real, &rest[..length], offset);
+                    if result != ffi::SQLITE_OK {
+                        return result;
+                    }
+                    length
+                }
+                Region::Page { start } => {
+                    let from = (offset - start) as usize;
+                    let length = rest.len().min(PAGE_SIZE - from);
+                    let mut page = [0u8; PAGE_SIZE];
+                    // Part of a page is written over the rest of it
+                    if length < PAGE_SIZE {
+                        let result = read_page(file.real, file.kind, start, &mut page);
+                        if result != ffi::SQLITE_OK && result != ffi::SQLITE_IOERR_SHORT_READ {
+                            return result;
+                        }
+                    }
+                    page[from..from + length].copy_from_slice(&rest[..length]);
+                    if file.kind == Kind::Database && start == 0 && !check_header(&page) {
+                        return ffi::SQLITE_IOERR_WRITE;
+                    }
+                    let key = KEY.read().expect("database key lock");
+                    let Some(key) = key.as_ref() else {
+                        return ffi::SQLITE_IOERR_AUTH;
+                    };
+                    if let Err(error) =
+                        seal(key, file.kind, page_number(file.kind, start), &mut page)
+                    {
+                        return error;
+                    }
+                    let result = write_real(file.real, &page, start);
+                    if result != ffi::SQLITE_OK {
+                        return result;
+                    }
+                    length
+                }
+            };
+            done += length;
+            offset += length as u64;
+        }
+        ffi::SQLITE_OK
+    }
+
+    unsafe extern "C" fn close(file_: *mut ffi::sqlite3_file) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xClose.expect("xClose"))(real)
+    }
+
+    unsafe extern "C" fn truncate(file_: *mut ffi::sqlite3_file, size: i64) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xTruncate.expect("xTruncate"))(real, size)
+    }
+
+    unsafe extern "C" fn sync(file_: *mut ffi::sqlite3_file, flags: c_int) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xSync.expect("xSync"))(real, flags)
+    }
+
+    unsafe extern "C" fn file_size(file_: *mut ffi::sqlite3_file, size: *mut i64) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xFileSize.expect("xFileSize"))(real, size)
+    }
+
+    unsafe extern "C" fn lock(file_: *mut ffi::sqlite3_file, level: c_int) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xLock.expect("xLock"))(real, level)
+    }
+
+    unsafe extern "C" fn unlock(file_: *mut ffi::sqlite3_file, level: c_int) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xUnlock.expect("xUnlock"))(real, level)
+    }
+
+    unsafe extern "C" fn check_reserved_lock(
+        file_: *mut ffi::sqlite3_file,
+        out: *mut c_int,
+    ) -> c_int {
+        let real = file(file_).real;
+        (methods(real)
+            .xCheckReservedLock
+            .expect("xCheckReservedLock"))(real, out)
+    }
+
+    unsafe extern "C" fn file_control(
+        file_: *mut ffi::sqlite3_file,
+        op: c_int,
+        arg: *mut c_void,
+    ) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xFileControl.expect("xFileControl"))(real, op, arg)
+    }
+
+    unsafe extern "C" fn sector_size(file_: *mut ffi::sqlite3_file) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xSectorSize.expect("xSectorSize"))(real)
+    }
+
+    unsafe extern "C" fn device_characteristics(file_: *mut ffi::sqlite3_file) -> c_int {
+        let real = file(file_).real;
+        (methods(real)
+            .xDeviceCharacteristics
+            .expect("xDeviceCharacteristics"))(real)
+    }
+
+    unsafe extern "C" fn shm_map(
+        file_: *mut ffi::sqlite3_file,
+        region: c_int,
+        size: c_int,
+        extend: c_int,
+        out: *mut *mut c_void,
+    ) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xShmMap.expect("xShmMap"))(real, region, size, extend, out)
+    }
+
+    unsafe extern "C" fn shm_lock(
+        file_: *mut ffi::sqlite3_file,
+        offset: c_int,
+        n: c_int,
+        flags: c_int,
+    ) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xShmLock.expect("xShmLock"))(real, offset, n, flags)
+    }
+
+    unsafe extern "C" fn shm_barrier(file_: *mut ffi::sqlite3_file) {
+        let real = file(file_).real;
+        (methods(real).xShmBarrier.expect("xShmBarrier"))(real)
+    }
+
+    unsafe extern "C" fn shm_unmap(file_: *mut ffi::sqlite3_file, delete: c_int) -> c_int {
+        let real = file(file_).real;
+        (methods(real).xShmUnmap.expect("xShmUnmap"))(real, delete)
+    }
+
+    /// Version 2: without `xFetch`, SQLite never maps the file into memory
+    /// and every page goes through `read`
+    static METHODS: ffi::sqlite3_io_methods = ffi::sqlite3_io_methods {
+        iVersion: 2,
+        xClose: Some(close),
+        xRead: Some(read),
+        xWrite: Some(write),
+        xTruncate: Some(truncate),
+        xSync: Some(sync),
+        xFileSize: Some(file_size),
+        xLock: Some(lock),
+        xUnlock: Some(unlock),
+        xCheckReservedLock: Some(check_reserved_lock),
+        xFileControl: Some(file_control),
+        xSectorSize: Some(sector_size),
+        xDeviceCharacteristics: Some(device_characteristics),
+        xShmMap: Some(shm_map),
+        xShmLock: Some(shm_lock),
+        xShmBarrier: Some(shm_barrier),
+        xShmUnmap: Some(shm_unmap),
+        xFetch: None,
+        xUnfetch: None,
+    };
+
+    unsafe fn parent<'a>(vfs: *mut ffi::sqlite3_vfs) -> &'a mut ffi::sqlite3_vfs {
+        &mut *((*vfs).pAppData as *mut ffi::sqlite3_vfs)
+    }
+
+    unsafe extern "C" fn open(
+        vfs: *mut ffi::sqlite3_vfs,
+        name: ffi::sqlite3_filename,
+        file_: *mut ffi::sqlite3_file,
+        flags: c_int,
+        out_flags: *mut c_int,
+    ) -> c_int {
+        let parent = parent(vfs);
+        let file = file(file_);
+        file.base.pMethods = std::ptr::null();
+        file.real = (file_ as *mut u8).add(size_of::<File>()) as *mut ffi::sqlite3_file;
+        file.kind = if flags & ffi::SQLITE_OPEN_MAIN_DB != 0 {
+            Kind::Database
+        } else if flags & ffi::SQLITE_OPEN_WAL != 0 {
+            Kind::Wal
+        } else {
+            Kind::Plain
+        };
+        let result = (parent.xOpen.expect("xOpen"))(parent, name, file.real, flags, out_flags);
+        // SQLite closes the file even when opening failed, as long as it has
+        // methods
+        if !(*file.real).pMethods.is_null() {
+            file.base.pMethods = &METHODS;
+        }
+        result
+    }
+
+    unsafe extern "C" fn delete(
+        vfs: *mut ffi::sqlite3_vfs,
+        name: *const c_char,
+        sync_dir: c_int,
+    ) -> c_int {
+        let parent = parent(vfs);
+        (parent.xDelete.expect("xDelete"))(parent, name, sync_dir)
+    }
+
+    unsafe extern "C" fn access(
+        vfs: *mut ffi::sqlite3_vfs,
+        name: *const c_char,
+        flags: c_int,
+        out: *mut c_int,
+    ) -> c_int {
+        let parent = parent(vfs);
+        (parent.xAccess.expect("xAccess"))(parent, name, flags, out)
+    }
+
+    unsafe extern "C" fn full_pathname(
+        vfs: *mut ffi::sqlite3_vfs,
+        name: *const c_char,
+        size: c_int,
+        out: *mut c_char,
+    ) -> c_int {
+        let parent = parent(vfs);
+        (parent.xFullPathname.expect("xFullPathname"))(parent, name, size, out)
+    }
+
+    unsafe extern "C" fn randomness(
+        vfs: *mut ffi::sqlite3_vfs,
+        size: c_int,
+        out: *mut c_char,
+    ) -> c_int {
+        let parent = parent(vfs);
+        (parent.xRandomness.expect("xRandomness"))(parent, size, out)
+    }
+
+    unsafe extern "C" fn sleep(vfs: *mut ffi::sqlite3_vfs, microseconds: c_int) -> c_int {
+        let parent = parent(vfs);
+        (parent.xSleep.expect("xSleep"))(parent, microseconds)
+    }
+
+    unsafe extern "C" fn current_time(vfs: *mut ffi::sqlite3_vfs, out: *mut f64) -> c_int {
+        let parent = parent(vfs);
+        (parent.xCurrentTime.expect("xCurrentTime"))(parent, out)
+    }
+
+    unsafe extern "C" fn get_last_error(
+        vfs: *mut ffi::sqlite3_vfs,
+        size: c_int,
+        out: *mut c_char,
+    ) -> c_int {
+        let parent = parent(vfs);
+        match parent.xGetLastError {
+            Some(get_last_error) => get_last_error(parent, size, out),
+            None => 0,
+        }
+    }
+
+    unsafe extern "C" fn current_time_int64(vfs: *mut ffi::sqlite3_vfs, out: *mut i64) -> c_int {
+        let parent = parent(vfs);
+        match parent.xCurrentTimeInt64 {
+            Some(current_time_int64) => current_time_int64(parent, out),
+            None => {
+                let mut days = 0.0;
+                let result = current_time(vfs, &mut days);
+                *out = (days * 86_400_000.0) as i64;
+                result
+            }
+        }
+    }
+
+    /// Register the VFS over the platform's default, for as long as the app
+    /// runs. Extensions aren't loaded through it.
+    pub unsafe fn register() {
+        let parent = ffi::sqlite3_vfs_find(std::ptr::null());
+        if parent.is_null() {
+            log::error!("no SQLite VFS to encrypt through");
+            return;
+        }
+        let vfs = Box::new(ffi::sqlite3_vfs {
+            iVersion: 2,
+            szOsFile: (size_of::<File>() as c_int) + (*parent).szOsFile,
+            mxPathname: (*parent).mxPathname,
+            pNext: null_mut(),
+            zName: VFS_NAME.as_ptr(),
+            pAppData: parent.cast(),
+            xOpen: Some(open),
+            xDelete: Some(delete),
+            xAccess: Some(access),
+            xFullPathname: Some(full_pathname),
+            xDlOpen: None,
+            xDlError: None,
+            xDlSym: None,
+            xDlClose: None,
+            xRandomness: Some(randomness),
+            xSleep: Some(sleep),
+            xCurrentTime: Some(current_time),
+            xGetLastError: Some(get_last_error),
+            xCurrentTimeInt64: Some(current_time_int64),
+            xSetSystemCall: None,
+            xGetSystemCall: None,
+            xNextSystemCall: None,
+        });
+        let result = ffi::sqlite3_vfs_register(Box::leak(vfs), 0);
+        if result != ffi::SQLITE_OK {
+            log::error!(code = result; "encrypted SQLite VFS not registered");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Load a test key once; every test encrypts with the same one
+    fn unlock() {
+        static KEY_ONCE: Once = Once::new();
+        KEY_ONCE.call_once(|| {
+            *KEY.write().expect("database key lock") = Some(aead_key(&[7u8; 32]).unwrap());
+        });
+    }
+
+    /// A database `name` in a new folder, with enough rows to fill a few
+    /// pages, closed so its WAL is checkpointed into it
+    fn make(name: &str) -> PathBuf {
+        unlock();
+        let dir = std::env::temp_dir().join(format!(
+            "smudge-db-encryption-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("smudge.db");
+        let connection = open(&path, OpenFlags::default()).unwrap();
+        prepare(&connection).unwrap();
+        connection
+            .pragma_update(None, "temp_store", "MEMORY")
+            .unwrap();
+        let mode: String = connection
+            .query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+        connection
+            .execute_batch("CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)")
+            .unwrap();
+        for id in 0..200 {
+            connection
+                .execute(
+                    "INSERT INTO notes (id, body) VALUES (?1, ?2)",
+                    (id, format!("note {} {}", id, "text ".repeat(40))),
+                )
+                .unwrap();
+        }
+        drop(connection);
+        path
+    }
+
+    fn count(path: &Path) -> Result<i64, rusqlite::Error> {
+        let connection = open(path, OpenFlags::default()).unwrap();
+        connection.query_row(
+            "SELECT count(*) FROM notes WHERE body LIKE 'note %'",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    fn edit(path: &Path, edit: impl FnOnce(&mut Vec<u8>)) {
+        let mut bytes = std::fs::read(path).unwrap();
+        edit(&mut bytes);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = make("round-trip");
+        assert!(is_encrypted(&path));
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.len() > 3 * PAGE_SIZE);
+        assert!(!bytes.windows(5).any(|window| window == b"note "));
+        assert_eq!(count(&path).unwrap(), 200);
+    }
+
+    #[test]
+    fn wal_round_trip() {
+        let path = make("wal");
+        let writer = open(&path, OpenFlags::default()).unwrap();
+        writer.pragma_update(None, "wal_autocheckpoint", 0).unwrap();
+        writer
+            .execute("UPDATE notes SET body = 'note changed' WHERE id < 100", [])
+            .unwrap();
+        let wal = std::fs::read(path.with_extension("db-wal")).unwrap();
+        assert!(wal.len() as u64 > WAL_HEADER + WAL_FRAME_HEADER + PAGE_SIZE as u64);
+        assert!(!wal.windows(12).any(|window| window == b"note changed"));
+        let changed: i64 = open(&path, OpenFlags::default())
+            .unwrap()
+            .query_row(
+                "SELECT count(*) FROM notes WHERE body = 'note changed'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(changed, 100);
+    }
+
+    #[test]
+    fn flipped_byte_is_refused() {
+        let path = make("flipped");
+        edit(&path, |bytes| bytes[PAGE_SIZE + 100] ^= 1);
+        assert!(count(&path).is_err());
+    }
+
+    #[test]
+    fn swapped_pages_are_refused() {
+        let path = make("swapped");
+        edit(&path, |bytes| {
+            let (first, second) = bytes[PAGE_SIZE..3 * PAGE_SIZE].split_at_mut(PAGE_SIZE);
+            first.swap_with_slice(second);
+        });
+        assert!(count(&path).is_err());
+    }
+
+    #[test]
+    fn zeroed_page_is_refused() {
+        let path = make("zeroed");
+        edit(&path, |bytes| bytes[PAGE_SIZE..2 * PAGE_SIZE].fill(0));
+        assert!(count(&path).is_err());
+    }
+
+    #[test]
+    fn page_is_sealed_to_its_place() {
+        let key = aead_key(&[7u8; 32]).unwrap();
+        let mut page = [0u8; PAGE_SIZE];
+        page[..5].copy_from_slice(b"hello");
+        seal(&key, Kind::Database, 3, &mut page).unwrap();
+
+        let mut moved = page;
+        assert!(open_page(&key, Kind::Database, 4, &mut moved).is_err());
+        let mut other_file = page;
+        assert!(open_page(&key, Kind::Wal, 3, &mut other_file).is_err());
+        open_page(&key, Kind::Database, 3, &mut page).unwrap();
+        assert_eq!(&page[..5], b"hello");
+        assert!(page[SEALED..].iter().all(|&byte| byte == 0));
+    }
+}
@@ -0,0 +1,185 @@
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+use crate::{auth, shortcuts};
+use crate::universal_links::{DeepLinkRoute, UniversalLinksConfig};
+use crate::{FrontendReady, PendingDeepLinks};
+
+/// smudge's own URL scheme, e.g. `smudge://notes/<id>`. Declared under
+/// `CFBundleURLTypes` in Info.plist; registered at runtime elsewhere.
+pub const SCHEME: &str = "smudge";
+
+/// Signals the main window that links are waiting in `get_pending_deep_links`
+pub const RECEIVED_EVENT: &str = "deep-link-received";
+
+/// Links among command line arguments, as Windows and Linux pass them when
+/// launching the scheme's handler, or as a second copy forwards them
+pub fn urls_from_args<I, S>(args: I) -> Vec<Url>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter()
+        .filter_map(|arg| Url::parse(arg.as_ref()).ok())
+        .filter(|url| url.scheme() == SCHEME)
+        .collect()
+}
+
+/// The app route `url` links to: smudge:// links and https universal links
+/// both map through universal-links.json
+pub fn resolve(config: &UniversalLinksConfig, url: &Url) -> Option<DeepLinkRoute> {
+    if url.scheme() == SCHEME {
+        config.resolve_app_url(url)
+    } else {
+        config.resolve(url)
+    }
+}
+
+/// Resolve links from any source, buffer them for the frontend and signal it
+/// if it's listening. Before then it checks the buffer once it has loaded.
+/// x-callback-url links run a Shortcuts action instead, and OAuth redirects
+/// go to the sign-in waiting for them. Returns whether any link resolved.
+pub fn received(app: &AppHandle, urls: &[Url]) -> bool {
+    let (redirects, urls): (Vec<&Url>, Vec<&Url>) =
+        urls.iter().partition(|url| auth::is_redirect(url));
+    let mut redirected = false;
+    for url in redirects {
+        redirected |= auth::redirected(app, url);
+    }
+    let (callbacks, urls): (Vec<&Url>, Vec<&Url>) = urls
+        .into_iter()
+        .partition(|url| shortcuts::is_callback_url(url));
+    for url in &callbacks {
+        shortcuts::received_url(app, url);
+    }
+    let handled = redirected || !callbacks.is_empty();
+    let Some(config) = app.try_state::<UniversalLinksConfig>() else {
+        return handled;
+    };
+    let routes: Vec<DeepLinkRoute> = urls
+        .into_iter()
+        .filter_map(|url| resolve(&config, url))
+        .collect();
+    if routes.is_empty() {
+        return handled;
+    }
+    if let Some(state) = app.try_state::<PendingDeepLinks>() {
+        state
+            .0
+            .lock()
+            .expect("pending deep links mutex")
+            .extend(routes);
+    }
+
+    let frontend_ready = app
+        .try_state::<FrontendReady>()
+        .map(|s| *s.0.lock().expect("frontend ready mutex"))
+        .unwrap_or(false);
+    if frontend_ready {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.emit(RECEIVED_EVENT, ());
+        }
+    }
+    true
+}
+
+/// Make this executable the scheme's handler for the current user, unless it
+/// already is. Installers don't know the scheme, so this runs on every
+/// launch and also catches the app having moved. A no-op on macOS, where
+/// Launch Services reads the scheme from Info.plist.
+pub fn register(app: &AppHandle) -> Result<(), String> {
+    platform::register(app)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use tauri::AppHandle;
+
+    pub fn register(_app: &AppHandle) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::SCHEME;
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+    use tauri::AppHandle;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    fn reg(args: &[&str]) -> Result<String, String> {
+        let output = Command::new("reg")
+            .args(args)
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| format!("Failed to run reg: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    pub fn register(_app: &AppHandle) -> Result<(), String> {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let class = format!(r"HKCU\Software\Classes\{}", SCHEME);
+        let open_command = format!(r"{}\shell\open\command", class);
+        let command = format!("\"{}\" \"%1\"", exe.to_string_lossy());
+
+        let current = reg(&["query", &open_command, "/ve"]).ok();
+        if current.is_some_and(|output| output.contains(&command)) {
+            return Ok(());
+        }
+        reg(&["add", &class, "/ve", "/d", "URL:Smudge", "/f"])?;
+        reg(&["add", &class, "/v", "URL Protocol", "/d", "", "/f"])?;
+        reg(&["add", &open_command, "/ve", "/d", &command, "/f"])?;
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::SCHEME;
+    use std::process::Command;
+    use tauri::{AppHandle, Manager};
+
+    /// A hidden desktop entry of its own, since the installed smudge.desktop
+    /// isn't writable and doesn't list the scheme
+    const DESKTOP_FILE: &str = "smudge-url-handler.desktop";
+
+    pub fn register(app: &AppHandle) -> Result<(), String> {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let applications = app
+            .path()
+            .data_dir()
+            .map_err(|e| e.to_string())?
+            .join("applications");
+        let path = applications.join(DESKTOP_FILE);
+        let entry = format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Smudge\n\
+             Exec=\"{}\" %u\n\
+             NoDisplay=true\n\
+             Terminal=false\n\
+             MimeType=x-scheme-handler/{};\n",
+            exe.to_string_lossy(),
+            SCHEME
+        );
+        if std::fs::read_to_string(&path).is_ok_and(|current| current == entry) {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&applications).map_err(|e| e.to_string())?;
+        std::fs::write(&path, entry).map_err(|e| e.to_string())?;
+        let mime_type = format!("x-scheme-handler/{}", SCHEME);
+        let status = Command::new("xdg-mime")
+            .args(["default", DESKTOP_FILE, &mime_type])
+            .status()
+            .map_err(|e| format!("Failed to run xdg-mime: {}", e))?;
+        if !status.success() {
+            return Err(format!("xdg-mime exited with {}", status));
+        }
+        Ok(())
+    }
+}
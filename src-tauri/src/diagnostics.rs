@@ -0,0 +1,324 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+/// Longest recording `record_window` accepts
+pub const MAX_RECORDING_SECONDS: u32 = 60;
+
+/// The oldest ffmpeg whose grabber records a single window: gdigrab's
+/// `hwnd=` input came with 7.1, x11grab's `window_id` option with 5.1
+#[cfg(windows)]
+const FFMPEG_MIN_VERSION: (u32, u32) = (7, 1);
+#[cfg(not(any(target_os = "macos", windows)))]
+const FFMPEG_MIN_VERSION: (u32, u32) = (5, 1);
+
+/// Screen area to record, in logical points (what `screencapture` takes)
+#[cfg(target_os = "macos")]
+#[derive(Debug, Clone, Copy)]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// What `record` records: the window's screen area on macOS, whatever is on
+/// top of it
+#[cfg(target_os = "macos")]
+pub type Target = Region;
+/// What `record` records: the window itself, by HWND on Windows and by X11
+/// window id on Linux
+#[cfg(not(target_os = "macos"))]
+pub type Target = u64;
+
+/// The recording target for `window`
+pub fn target(window: &WebviewWindow) -> Result<Target, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let scale_factor = window.scale_factor().map_err(|e| e.to_string())?;
+        let position = window.outer_position().map_err(|e| e.to_string())?;
+        let size = window.outer_size().map_err(|e| e.to_string())?;
+        Ok(Region {
+            x: position.x as f64 / scale_factor,
+            y: position.y as f64 / scale_factor,
+            width: size.width as f64 / scale_factor,
+            height: size.height as f64 / scale_factor,
+        })
+    }
+    #[cfg(windows)]
+    {
+        Ok(window.hwnd().map_err(|e| e.to_string())?.0 as usize as u64)
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+        let handle = window.window_handle().map_err(|e| e.to_string())?;
+        match handle.as_raw() {
+            RawWindowHandle::Xlib(handle) => Ok(u64::from(handle.window)),
+            RawWindowHandle::Xcb(handle) => Ok(u64::from(handle.window.get())),
+            _ => Err("Recording needs an X11 session".to_string()),
+        }
+    }
+}
+
+/// Contents of `info.json` in a diagnostics bundle
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleInfo {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub created_at: u64,
+    pub recordings: Vec<String>,
+    pub screenshots: Vec<String>,
+    /// Whether `platform.log` has the windowing layer's recent diagnostics
+    pub platform_log: bool,
+    /// Whether `smudge.log` has the app's log, rotated files included
+    pub app_log: bool,
+    /// Whether `telemetry.json` has the counters and timings, kept only
+    /// while telemetry is on
+    pub telemetry: bool,
+    /// Whether `trace.json` has the recent command and job intervals, in the
+    /// Chrome tracing format
+    pub trace: bool,
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn diagnostics_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| e.to_string())?
+        .join("diagnostics");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// Where recordings and screenshots wait until the next diagnostics bundle
+/// picks them up
+pub fn recordings_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = diagnostics_dir(app)?.join("recordings");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+pub fn recording_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(recordings_dir(app)?.join(format!("recording-{}.mp4", timestamp())))
+}
+
+pub fn screenshot_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(recordings_dir(app)?.join(format!("screenshot-{}.png", timestamp())))
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+    fn CGRequestScreenCaptureAccess() -> bool;
+}
+
+/// Whether the app may record the screen. On macOS this asks for Screen
+/// Recording access the first time; the user has to grant it in System
+/// Settings and relaunch before recording works. Other platforms don't
+/// gate screen capture.
+pub fn ensure_permission() -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    unsafe {
+        if !CGPreflightScreenCaptureAccess() && !CGRequestScreenCaptureAccess() {
+            return Err(
+                "Smudge needs Screen Recording access. Allow it in System Settings > Privacy & Security > Screen Recording, then restart Smudge."
+                    .to_string(),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Whether this system has what `record` runs. macOS always does; Windows
+/// and Linux need a recent enough ffmpeg on the PATH, which smudge doesn't
+/// ship.
+pub fn ensure_recorder() -> Result<(), String> {
+    #[cfg(not(target_os = "macos"))]
+    {
+        let (major, minor) = FFMPEG_MIN_VERSION;
+        let unsupported = || {
+            format!(
+                "Recording a window needs ffmpeg {}.{} or later on the PATH",
+                major, minor
+            )
+        };
+        let output = Command::new("ffmpeg")
+            .arg("-version")
+            .output()
+            .map_err(|_| unsupported())?;
+        // "ffmpeg version 7.1.1-full_build-www.gyan.dev ...". Builds from git
+        // say "N-118000-g..." and can't be told apart, so they're let through.
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let version = stdout.split_whitespace().nth(2).and_then(|version| {
+            let mut parts = version.trim_start_matches('n').split('.');
+            let major: u32 = parts.next()?.parse().ok()?;
+            let minor = parts
+                .next()
+                .and_then(|minor| minor.split(|c: char| !c.is_ascii_digit()).next())
+                .and_then(|minor| minor.parse().ok())
+                .unwrap_or(0);
+            Some((major, minor))
+        });
+        if version.is_some_and(|version| version < FFMPEG_MIN_VERSION) {
+            return Err(unsupported());
+        }
+    }
+    Ok(())
+}
+
+fn run(command: &mut Command, tool: &str) -> Result<(), String> {
+    let output = command.output().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            format!("{} is not installed", tool)
+        } else {
+            format!("Failed to run {}: {}", tool, e)
+        }
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} failed: {}", tool, stderr.trim()));
+    }
+    Ok(())
+}
+
+/// Record `target` for `seconds` into an mp4 at `output`. Blocks until the
+/// recording is done.
+///
+/// macOS uses `screencapture`, which records through ScreenCaptureKit, and
+/// converts its .mov with `avconvert`. Windows and Linux grab the window
+/// alone with ffmpeg's gdigrab / x11grab devices; check `ensure_recorder`
+/// first.
+pub fn record(target: Target, seconds: u32, output: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let mov = output.with_extension("mov");
+        run(
+            Command::new("screencapture")
+                .arg("-x")
+                .arg("-v")
+                .args(["-V", &seconds.to_string()])
+                .arg(format!(
+                    "-R{},{},{},{}",
+                    target.x.round(),
+                    target.y.round(),
+                    target.width.round(),
+                    target.height.round()
+                ))
+                .arg(&mov),
+            "screencapture",
+        )?;
+        let converted = run(
+            Command::new("avconvert")
+                .arg("--source")
+                .arg(&mov)
+                .arg("--output")
+                .arg(output)
+                .args(["--preset", "PresetHighestQuality", "--replace"]),
+            "avconvert",
+        );
+        let _ = std::fs::remove_file(&mov);
+        converted
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let mut ffmpeg = Command::new("ffmpeg");
+        ffmpeg.args(["-y", "-loglevel", "error", "-framerate", "30"]);
+        #[cfg(windows)]
+        ffmpeg.args(["-f", "gdigrab", "-i", &format!("hwnd={:#x}", target)]);
+        #[cfg(not(windows))]
+        {
+            let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
+            ffmpeg.args(["-f", "x11grab", "-window_id", &format!("{:#x}", target)]);
+            ffmpeg.args(["-i", &display]);
+        }
+        ffmpeg
+            .args(["-t", &seconds.to_string()])
+            // Encoders want even dimensions
+            .args(["-vf", "crop=trunc(iw/2)*2:trunc(ih/2)*2"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+            .arg(output);
+        run(&mut ffmpeg, "ffmpeg")
+    }
+}
+
+/// Recent messages from the windowing layer about callbacks that are hard to
+/// observe otherwise, such as files the OS asked the app to open
+pub fn platform_diagnostics() -> Vec<String> {
+    tauri_runtime_wry::tao::diag::recent()
+}
+
+/// Collect pending recordings, screenshots and system details into a new folder under the
+/// diagnostics directory, returning its path
+pub fn create_bundle(app: &AppHandle) -> Result<PathBuf, String> {
+    let created_at = timestamp();
+    let bundle = diagnostics_dir(app)?.join(format!("bundle-{}", created_at));
+    std::fs::create_dir_all(&bundle).map_err(|e| e.to_string())?;
+
+    let mut recordings = Vec::new();
+    let mut screenshots = Vec::new();
+    let pending = std::fs::read_dir(recordings_dir(app)?).map_err(|e| e.to_string())?;
+    for entry in pending.flatten() {
+        let path = entry.path();
+        let list = match path.extension().and_then(|e| e.to_str()) {
+            Some("mp4") => &mut recordings,
+            Some("png") => &mut screenshots,
+            _ => continue,
+        };
+        let name = entry.file_name();
+        std::fs::rename(&path, bundle.join(&name)).map_err(|e| e.to_string())?;
+        list.push(name.to_string_lossy().into_owned());
+    }
+
+    let platform_log = platform_diagnostics();
+    if !platform_log.is_empty() {
+        std::fs::write(bundle.join("platform.log"), platform_log.join("\n"))
+            .map_err(|e| e.to_string())?;
+    }
+
+    let app_log = crate::logging::contents().filter(|contents| !contents.is_empty());
+    if let Some(ref contents) = app_log {
+        std::fs::write(bundle.join("smudge.log"), contents).map_err(|e| e.to_string())?;
+    }
+
+    let telemetry = crate::telemetry::report(app).enabled;
+    if telemetry {
+        std::fs::write(
+            bundle.join("telemetry.json"),
+            crate::telemetry::export_json(app)?,
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let trace = crate::profiling::export_json()?;
+    std::fs::write(bundle.join("trace.json"), trace).map_err(|e| e.to_string())?;
+
+    let info = BundleInfo {
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        created_at,
+        recordings,
+        screenshots,
+        platform_log: !platform_log.is_empty(),
+        app_log: app_log.is_some(),
+        telemetry,
+        trace: true,
+    };
+    let content = serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?;
+    std::fs::write(bundle.join("info.json"), content).map_err(|e| e.to_string())?;
+    Ok(bundle)
+}
@@ -0,0 +1,269 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::db::Database;
+use crate::fs_scope::{Access, FsScope, GrantSource};
+
+/// A file type choice, e.g. "Markdown" for md and markdown
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+/// What the frontend can customize about a file dialog. Labels are shown
+/// as given; the frontend takes them from the `dialog-*` messages of the
+/// catalogs.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DialogOptions {
+    /// Key for the remembered directory, e.g. "export" or "import". Dialogs
+    /// without a purpose don't remember where they were.
+    pub purpose: Option<String>,
+    pub title: Option<String>,
+    /// Label for the accept button
+    pub button_label: Option<String>,
+    #[serde(default)]
+    pub filters: Vec<FileFilter>,
+    /// Suggested file name in save dialogs
+    pub default_name: Option<String>,
+    /// Starting directory; overrides the remembered one
+    pub default_path: Option<String>,
+    /// Uniform type identifiers, e.g. "net.daringfireball.markdown", allowed
+    /// alongside the filters' extensions. macOS only.
+    #[serde(default)]
+    pub content_types: Vec<String>,
+    /// Let the user browse into packages like .app bundles instead of
+    /// picking them as files. macOS only.
+    pub treat_packages_as_directories: Option<bool>,
+    pub show_hidden_files: Option<bool>,
+    /// Tags the save panel starts with; the user's edits come back with the
+    /// pick. macOS only.
+    pub tags: Option<Vec<String>>,
+    /// Controls shown with the dialog, whose values come back with the pick
+    #[serde(default)]
+    pub accessory: Vec<AccessoryControl>,
+}
+
+/// A control shown in the dialog, like a format picker in a save panel
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum AccessoryControl {
+    Checkbox {
+        label: String,
+        #[serde(default)]
+        checked: bool,
+    },
+    Popup {
+        label: String,
+        options: Vec<String>,
+        #[serde(default)]
+        selected: usize,
+    },
+}
+
+impl From<AccessoryControl> for rfd::AccessoryControl {
+    fn from(control: AccessoryControl) -> Self {
+        match control {
+            AccessoryControl::Checkbox { label, checked } => {
+                rfd::AccessoryControl::Checkbox { label, checked }
+            }
+            AccessoryControl::Popup {
+                label,
+                options,
+                selected,
+            } => rfd::AccessoryControl::Popup {
+                label,
+                options,
+                selected,
+            },
+        }
+    }
+}
+
+/// An accessory control's value: whether a checkbox is checked, or the
+/// index of a popup's option
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(untagged)]
+pub enum AccessoryValue {
+    Checked(bool),
+    Selected(usize),
+}
+
+/// What the user picked, with the accessory controls' values in the order
+/// they were given and the tags they gave a saved file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Picked {
+    pub path: PathBuf,
+    pub accessory: Vec<AccessoryValue>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DialogKind {
+    OpenFile,
+    PickFolder,
+    SaveFile,
+}
+
+/// Database namespace of the directories, by purpose
+pub const NAMESPACE: &str = "dialog-directories";
+
+/// Last directory used per dialog purpose, persisted to the database
+pub struct DialogDirectories {
+    db: Option<Database>,
+    saved: Mutex<HashMap<String, PathBuf>>,
+}
+
+impl DialogDirectories {
+    pub fn load(app: &AppHandle) -> Self {
+        let db = app.try_state::<Database>().map(|db| db.inner().clone());
+        let saved = db
+            .as_ref()
+            .and_then(|db| db.list(NAMESPACE).ok())
+            .map(|saved| saved.into_iter().collect())
+            .unwrap_or_default();
+        Self {
+            db,
+            saved: Mutex::new(saved),
+        }
+    }
+
+    /// The remembered directory for `purpose`, if it still exists
+    pub fn get(&self, purpose: &str) -> Option<PathBuf> {
+        self.saved
+            .lock()
+            .expect("dialog directories mutex")
+            .get(purpose)
+            .filter(|dir| dir.is_dir())
+            .cloned()
+    }
+
+    pub fn remember(&self, purpose: &str, directory: &Path) -> Result<(), String> {
+        self.saved
+            .lock()
+            .expect("dialog directories mutex")
+            .insert(purpose.to_string(), directory.to_path_buf());
+        match &self.db {
+            Some(db) => db.put(NAMESPACE, purpose, directory),
+            None => Ok(()),
+        }
+    }
+}
+
+type PickFuture = Pin<Box<dyn Future<Output = Option<rfd::FileHandle>> + Send>>;
+
+/// Show a native file dialog attached to `window`, starting in the
+/// directory last used for the same purpose. Uses rfd directly rather than
+/// the dialog plugin, which can't set the accept button label.
+pub async fn show(
+    app: &AppHandle,
+    window: &WebviewWindow,
+    kind: DialogKind,
+    options: DialogOptions,
+) -> Result<Option<Picked>, String> {
+    let directory = options.default_path.map(PathBuf::from).or_else(|| {
+        let purpose = options.purpose.as_deref()?;
+        app.state::<DialogDirectories>().get(purpose)
+    });
+
+    let mut dialog = rfd::AsyncFileDialog::new()
+        .set_parent(window)
+        .set_can_create_directories(true);
+    if let Some(title) = options.title {
+        dialog = dialog.set_title(title);
+    }
+    if let Some(label) = options.button_label {
+        dialog = dialog.set_button_label(label);
+    }
+    if let Some(directory) = directory {
+        dialog = dialog.set_directory(directory);
+    }
+    if let Some(name) = options.default_name {
+        dialog = dialog.set_file_name(name);
+    }
+    for filter in options.filters {
+        dialog = dialog.add_filter(filter.name, &filter.extensions);
+    }
+    for content_type in options.content_types {
+        dialog = dialog.add_content_type(content_type);
+    }
+    if let Some(treat) = options.treat_packages_as_directories {
+        dialog = dialog.set_treat_packages_as_directories(treat);
+    }
+    if let Some(show) = options.show_hidden_files {
+        dialog = dialog.set_show_hidden_files(show);
+    }
+    if let Some(tags) = options.tags {
+        dialog = dialog.set_tags(&tags);
+    }
+    for control in options.accessory {
+        dialog = dialog.add_accessory_control(control.into());
+    }
+
+    // Like the dialog plugin: create the panel on the main thread, wait for
+    // it elsewhere
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.run_on_main_thread(move || {
+        let picked: PickFuture = match kind {
+            DialogKind::OpenFile => Box::pin(dialog.pick_file()),
+            DialogKind::PickFolder => Box::pin(dialog.pick_folder()),
+            DialogKind::SaveFile => Box::pin(dialog.save_file()),
+        };
+        std::thread::spawn(move || {
+            let _ = tx.send(tauri::async_runtime::block_on(picked));
+        });
+    })
+    .map_err(|e| e.to_string())?;
+    let picked = rx
+        .await
+        .map_err(|_| "Dialog closed unexpectedly".to_string())?
+        .map(|handle| {
+            let choices = handle.choices();
+            let accessory = choices
+                .accessory
+                .iter()
+                .map(|value| match *value {
+                    rfd::AccessoryValue::Checked(checked) => AccessoryValue::Checked(checked),
+                    rfd::AccessoryValue::Selected(index) => AccessoryValue::Selected(index),
+                })
+                .collect();
+            Picked {
+                path: handle.path().to_path_buf(),
+                accessory,
+                tags: choices.tags.clone(),
+            }
+        });
+
+    // Whatever the user picks is theirs to open and save, and sandboxed
+    // builds keep access to it across launches
+    if let Some(picked) = &picked {
+        app.state::<FsScope>()
+            .grant(app, &picked.path, Access::ReadWrite, GrantSource::Dialog);
+    }
+
+    if let (Some(purpose), Some(picked)) = (&options.purpose, &picked) {
+        let directory = match kind {
+            DialogKind::PickFolder => Some(picked.path.as_path()),
+            DialogKind::OpenFile | DialogKind::SaveFile => picked.path.parent(),
+        };
+        if let Some(directory) = directory {
+            let _ = app
+                .state::<DialogDirectories>()
+                .remember(purpose, directory);
+        }
+    }
+    Ok(picked)
+}
@@ -0,0 +1,299 @@
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Sent to the window that subscribed with a `Changed` once a directory's
+/// changes settle
+pub const EVENT: &str = "directory-changed";
+
+/// How long a directory has to be quiet before its changes are sent
+const QUIET: Duration = Duration::from_millis(150);
+/// Longest changes wait while a directory keeps changing
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// One file's change, after coalescing everything that happened to it
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Change {
+    Created { path: PathBuf },
+    Modified { path: PathBuf },
+    Removed { path: PathBuf },
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Changed {
+    id: String,
+    changes: Vec<Change>,
+}
+
+struct Watch {
+    /// Window that subscribed, whose closing ends the watch
+    window: String,
+    /// Dropping it stops the events and, with them, the thread debouncing
+    /// them
+    _watcher: RecommendedWatcher,
+}
+
+/// Directories the frontend subscribed to. notify picks the backend: FSEvents
+/// on macOS, `ReadDirectoryChangesW` on Windows and inotify on Linux.
+#[derive(Default)]
+pub struct DirectoryWatches {
+    watches: Mutex<HashMap<String, Watch>>,
+    next_id: AtomicU64,
+}
+
+impl DirectoryWatches {
+    /// Start sending `window` the changes in `path`, and in its
+    /// subdirectories if `recursive`. Returns the id the changes come with.
+    pub fn watch(
+        &self,
+        app: &AppHandle,
+        window: &str,
+        path: &Path,
+        recursive: bool,
+    ) -> Result<String, String> {
+        if !path.is_dir() {
+            return Err(format!("{} isn't a directory", path.display()));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed).to_string();
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<notify::Event, notify::Error>| {
+                if let Ok(event) = res {
+                    let _ = sender.send(event);
+                }
+            },
+            Config::default(),
+        )
+        .map_err(|e| e.to_string())?;
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher
+            .watch(path, mode)
+            .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+
+        let app = app.clone();
+        let target = window.to_string();
+        let thread_id = id.clone();
+        std::thread::Builder::new()
+            .name(format!("smudge-watch-{}", id))
+            .spawn(move || {
+                debounce(&receiver, |changes| {
+                    let _ = app.emit_to(
+                        target.as_str(),
+                        EVENT,
+                        Changed {
+                            id: thread_id.clone(),
+                            changes,
+                        },
+                    );
+                })
+            })
+            .map_err(|e| e.to_string())?;
+
+        self.watches
+            .lock()
+            .expect("directory watches mutex")
+            .insert(
+                id.clone(),
+                Watch {
+                    window: window.to_string(),
+                    _watcher: watcher,
+                },
+            );
+        log::debug!(id = id.as_str(), path:? = path, recursive = recursive; "directory watched");
+        Ok(id)
+    }
+
+    pub fn unwatch(&self, id: &str) -> Result<(), String> {
+        self.watches
+            .lock()
+            .expect("directory watches mutex")
+            .remove(id)
+            .map(|_| ())
+            .ok_or_else(|| format!("No directory watch {}", id))
+    }
+
+    /// Stop the watches `window` started
+    pub fn window_closed(&self, window: &str) {
+        self.watches
+            .lock()
+            .expect("directory watches mutex")
+            .retain(|_, watch| watch.window != window);
+    }
+}
+
+/// Collect events until they stop for `QUIET` or have waited `MAX_DELAY`,
+/// then hand `send` what they add up to. Returns once the watcher is gone.
+fn debounce(receiver: &mpsc::Receiver<notify::Event>, send: impl Fn(Vec<Change>)) {
+    while let Ok(first) = receiver.recv() {
+        let started = Instant::now();
+        let mut changes = Changes::default();
+        changes.add(first);
+        loop {
+            let left = MAX_DELAY.saturating_sub(started.elapsed());
+            if left.is_zero() {
+                break;
+            }
+            match receiver.recv_timeout(QUIET.min(left)) {
+                Ok(event) => changes.add(event),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        let changes = changes.finish();
+        if !changes.is_empty() {
+            send(changes);
+        }
+    }
+}
+
+/// Changes coalesced per file, in the order files first changed
+#[derive(Default)]
+struct Changes {
+    changes: Vec<Change>,
+    /// The old path of a rename whose new path hasn't been seen yet, with
+    /// the backend's id pairing the two when it has one
+    rename_from: Option<(PathBuf, Option<usize>)>,
+}
+
+impl Changes {
+    fn add(&mut self, event: notify::Event) {
+        let tracker = event.attrs.tracker();
+        let mut paths = event.paths.into_iter();
+        match event.kind {
+            EventKind::Create(_) => paths.for_each(|path| self.created(path)),
+            EventKind::Remove(_) => paths.for_each(|path| self.removed(path)),
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    self.renamed(from, to);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                for path in paths {
+                    self.rename_from(path, tracker);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                for path in paths {
+                    self.rename_to(path, tracker);
+                }
+            }
+            // FSEvents doesn't say which side of a rename a path is; the
+            // side that's still there is the new one
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                for path in paths {
+                    if path.exists() {
+                        self.rename_to(path, tracker);
+                    } else {
+                        self.rename_from(path, tracker);
+                    }
+                }
+            }
+            EventKind::Modify(_) => paths.for_each(|path| self.modified(path)),
+            EventKind::Access(_) | EventKind::Any | EventKind::Other => {}
+        }
+    }
+
+    fn position(&self, path: &Path) -> Option<usize> {
+        self.changes.iter().position(|change| match change {
+            Change::Created { path: p }
+            | Change::Modified { path: p }
+            | Change::Removed { path: p } => p == path,
+            Change::Renamed { to, .. } => to == path,
+        })
+    }
+
+    fn created(&mut self, path: PathBuf) {
+        match self.position(&path) {
+            // Removed and created again is a replacement
+            Some(i) if matches!(self.changes[i], Change::Removed { .. }) => {
+                self.changes[i] = Change::Modified { path };
+            }
+            Some(_) => {}
+            None => self.changes.push(Change::Created { path }),
+        }
+    }
+
+    fn modified(&mut self, path: PathBuf) {
+        if self.position(&path).is_none() {
+            self.changes.push(Change::Modified { path });
+        }
+    }
+
+    fn removed(&mut self, path: PathBuf) {
+        match self.position(&path) {
+            Some(i) => match &self.changes[i] {
+                // Created and removed within the window: nothing to report
+                Change::Created { .. } => {
+                    self.changes.remove(i);
+                }
+                Change::Renamed { from, .. } => {
+                    let from = from.clone();
+                    self.changes[i] = Change::Removed { path: from };
+                }
+                _ => self.changes[i] = Change::Removed { path },
+            },
+            None => self.changes.push(Change::Removed { path }),
+        }
+    }
+
+    fn renamed(&mut self, from: PathBuf, to: PathBuf) {
+        match self.position(&from) {
+            // A new file moved before it was reported is just a new file
+            Some(i) if matches!(self.changes[i], Change::Created { .. }) => {
+                self.changes[i] = Change::Created { path: to };
+            }
+            // Renamed twice keeps the first old path
+            Some(i) => {
+                let from = match &self.changes[i] {
+                    Change::Renamed { from, .. } => from.clone(),
+                    _ => from,
+                };
+                self.changes[i] = Change::Renamed { from, to };
+            }
+            None => self.changes.push(Change::Renamed { from, to }),
+        }
+    }
+
+    fn rename_from(&mut self, path: PathBuf, tracker: Option<usize>) {
+        // An old path left waiting was moved out of the directory
+        if let Some((unpaired, _)) = self.rename_from.take() {
+            self.removed(unpaired);
+        }
+        self.rename_from = Some((path, tracker));
+    }
+
+    fn rename_to(&mut self, path: PathBuf, tracker: Option<usize>) {
+        match self.rename_from.take() {
+            Some((from, from_tracker))
+                if tracker.is_none() || from_tracker.is_none() || tracker == from_tracker =>
+            {
+                self.renamed(from, path);
+            }
+            unpaired => {
+                self.rename_from = unpaired;
+                // Moved in from outside the directory
+                self.created(path);
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<Change> {
+        if let Some((unpaired, _)) = self.rename_from.take() {
+            self.removed(unpaired);
+        }
+        self.changes
+    }
+}
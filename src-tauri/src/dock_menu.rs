@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sent to the main window when a Dock menu item is chosen
+pub const EVENT: &str = "dock-menu";
+
+/// The Dock menu's items share Tauri's menu event channel with every other
+/// menu, so their ids are prefixed
+const NEW_NOTE_ID: &str = "dock:new-note";
+const TEMPLATE_ID_PREFIX: &str = "dock:template:";
+const NOTE_ID_PREFIX: &str = "dock:note:";
+/// Followed by the project's path
+const PROJECT_ID_PREFIX: &str = "dock:project:";
+
+/// Notes listed at most. AppKit puts the app's windows and its own items
+/// below them.
+const MAX_NOTES: usize = 10;
+
+/// A recent note as the frontend lists it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DockMenuNote {
+    pub id: String,
+    pub title: String,
+}
+
+/// What the chosen item asks the main window to do
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    tag = "action",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum DockMenuAction {
+    NewNote,
+    NewFromTemplate { template_id: String },
+    OpenNote { note_id: String },
+}
+
+#[cfg(target_os = "macos")]
+thread_local! {
+    // muda's native items call back into the menu, so it has to outlive its
+    // time in the Dock
+    static MENU: std::cell::RefCell<Option<muda::Menu>> = const { std::cell::RefCell::new(None) };
+    // The notes last listed, kept for when the menu is rebuilt without them
+    static NOTES: std::cell::RefCell<Vec<DockMenuNote>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Replace the Dock icon's menu with "New Note", "New from Template",
+/// "Recent Projects" and `notes`, in order, in the current language.
+/// macOS only; elsewhere this does nothing.
+pub fn set(app: &AppHandle, notes: Vec<DockMenuNote>) -> Result<(), String> {
+    update(app, Some(notes))
+}
+
+/// Rebuild the Dock icon's menu with the notes it lists, e.g. when the
+/// recent projects change
+pub fn refresh(app: &AppHandle) -> Result<(), String> {
+    update(app, None)
+}
+
+fn update(app: &AppHandle, notes: Option<Vec<DockMenuNote>>) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let labels = Labels {
+            new_note: crate::i18n::text(app, "menu-new-note"),
+            new_from_template: crate::i18n::text(app, "menu-new-from-template"),
+            recent_projects: crate::i18n::text(app, "menu-recent-projects"),
+            untitled: crate::i18n::text(app, "note-untitled"),
+        };
+        let templates = crate::templates::list(app);
+        let projects = crate::projects::recent(app);
+        app.run_on_main_thread(move || {
+            let notes = NOTES.with_borrow_mut(|current| {
+                if let Some(notes) = notes {
+                    *current = notes;
+                }
+                current.clone()
+            });
+            let _ = install(&notes, &templates, &projects, &labels);
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app, notes);
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+struct Labels {
+    new_note: String,
+    new_from_template: String,
+    recent_projects: String,
+    untitled: String,
+}
+
+#[cfg(target_os = "macos")]
+fn install(
+    notes: &[DockMenuNote],
+    templates: &[crate::templates::Template],
+    projects: &[crate::projects::RecentProject],
+    labels: &Labels,
+) -> muda::Result<()> {
+    use muda::ContextMenu;
+
+    let menu = muda::Menu::new();
+    menu.append(&muda::MenuItem::with_id(
+        NEW_NOTE_ID,
+        &labels.new_note,
+        true,
+        None,
+    ))?;
+    if !templates.is_empty() {
+        let submenu = muda::Submenu::new(&labels.new_from_template, true);
+        for template in templates {
+            let id = format!("{}{}", TEMPLATE_ID_PREFIX, template.id);
+            submenu.append(&muda::MenuItem::with_id(id, &template.name, true, None))?;
+        }
+        menu.append(&submenu)?;
+    }
+    if !projects.is_empty() {
+        let submenu = muda::Submenu::new(&labels.recent_projects, true);
+        for project in projects {
+            let id = format!("{}{}", PROJECT_ID_PREFIX, project.path);
+            submenu.append(&muda::MenuItem::with_id(id, &project.name, true, None))?;
+        }
+        menu.append(&submenu)?;
+    }
+    if !notes.is_empty() {
+        menu.append(&muda::PredefinedMenuItem::separator())?;
+    }
+    for note in notes.iter().take(MAX_NOTES) {
+        let title = match note.title.trim() {
+            "" => labels.untitled.as_str(),
+            title => title,
+        };
+        let id = format!("{}{}", NOTE_ID_PREFIX, note.id);
+        menu.append(&muda::MenuItem::with_id(id, title, true, None))?;
+    }
+
+    // SAFETY: `ns_menu` is the menu's NSMenu, which tao retains until it's
+    // replaced; the muda menu is kept below for as long
+    unsafe { tauri_runtime_wry::tao::platform::macos::set_dock_menu(menu.ns_menu()) };
+    MENU.with_borrow_mut(|current| *current = Some(menu));
+    Ok(())
+}
+
+/// Handle a menu event if it came from the Dock menu: open the chosen
+/// project, or bring up the main window and tell it what was chosen
+pub fn handle(app: &AppHandle, id: &str) {
+    if let Some(path) = id.strip_prefix(PROJECT_ID_PREFIX) {
+        if let Err(e) = crate::projects::open_chosen(app, std::path::Path::new(path)) {
+            log::warn!(path = path, error:% = e; "recent project not opened");
+        }
+        return;
+    }
+    let action = if id == NEW_NOTE_ID {
+        DockMenuAction::NewNote
+    } else if let Some(template_id) = id.strip_prefix(TEMPLATE_ID_PREFIX) {
+        DockMenuAction::NewFromTemplate {
+            template_id: template_id.to_string(),
+        }
+    } else if let Some(note_id) = id.strip_prefix(NOTE_ID_PREFIX) {
+        DockMenuAction::OpenNote {
+            note_id: note_id.to_string(),
+        }
+    } else {
+        return;
+    };
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    let _ = window.emit(EVENT, action);
+}
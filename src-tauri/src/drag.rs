@@ -0,0 +1,275 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tauri::WebviewWindow;
+
+/// Sent to the window a drag began in with the `Operation` once it ends
+pub const EVENT: &str = "drag-ended";
+
+/// A list row being dragged out to the Finder or Explorer
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Item {
+    /// The note's markdown file itself
+    File { path: String },
+    /// The note rendered as an HTML page, written only where it's dropped
+    Html { path: String },
+}
+
+/// What the destination did with the dragged notes
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+    None,
+    Copy,
+    Move,
+    Link,
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+impl From<tauri_runtime_wry::tao::drag::DragOperation> for Operation {
+    fn from(operation: tauri_runtime_wry::tao::drag::DragOperation) -> Self {
+        use tauri_runtime_wry::tao::drag::DragOperation;
+        match operation {
+            DragOperation::Copy => Operation::Copy,
+            DragOperation::Move => Operation::Move,
+            DragOperation::Link => Operation::Link,
+            _ => Operation::None,
+        }
+    }
+}
+
+/// Notes promised as HTML in the current drag, by promise id, until the
+/// Finder asks for them
+#[cfg(target_os = "macos")]
+#[derive(Default)]
+pub struct Promises(std::sync::Mutex<std::collections::HashMap<String, std::path::PathBuf>>);
+
+/// Start dragging `items` out of `window`, showing the base64 PNG `image`
+/// under the pointer if there is one. The mouse button has to still be down.
+pub fn start(
+    window: &WebviewWindow,
+    items: Vec<Item>,
+    image: Option<String>,
+) -> Result<(), String> {
+    if items.is_empty() {
+        return Err("Nothing to drag".to_string());
+    }
+    let image = image.as_deref().map(decode_image).transpose()?;
+    platform::start(window, items, image)
+}
+
+/// Write the note promised as `id` to `path`, where the Finder asked for it
+#[cfg(target_os = "macos")]
+pub fn promise_requested(
+    app: &tauri::AppHandle,
+    request: u64,
+    id: String,
+    path: std::path::PathBuf,
+) {
+    use tauri::Manager;
+    use tauri_runtime_wry::tao::platform::macos;
+
+    let source = app
+        .state::<Promises>()
+        .0
+        .lock()
+        .expect("drag promises mutex")
+        .remove(&id);
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = match source {
+            Some(source) => write_html(&source, &path),
+            None => Err(format!("No note was promised as {}", id)),
+        };
+        if let Err(e) = &result {
+            log::warn!(path:? = path; "couldn't write a dragged note: {}", e);
+        }
+        let _ = app.run_on_main_thread(move || {
+            macos::fulfill_file_promise(request, result.err().as_deref())
+        });
+    });
+}
+
+/// Tell the window tao reported the end of a drag for
+#[cfg(target_os = "macos")]
+pub fn ended(
+    app: &tauri::AppHandle,
+    window_id: tauri_runtime_wry::tao::window::WindowId,
+    operation: Operation,
+) {
+    use tauri::Manager;
+    use tauri_runtime_wry::tao::platform::macos;
+
+    if matches!(operation, Operation::None) {
+        // Nothing took the drag, so none of its promises will be asked for
+        app.state::<Promises>()
+            .0
+            .lock()
+            .expect("drag promises mutex")
+            .clear();
+    }
+    let window = app.webview_windows().into_values().find(|window| {
+        window
+            .ns_window()
+            .is_ok_and(|ns_window| macos::window_id_for_ns_window(ns_window) == window_id)
+    });
+    if let Some(window) = window {
+        emit_ended(&window, operation);
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn emit_ended(window: &WebviewWindow, operation: Operation) {
+    use tauri::Emitter;
+
+    log::debug!(window = window.label(), operation:? = operation; "drag ended");
+    let _ = window.emit_to(window.label(), EVENT, operation);
+}
+
+fn decode_image(png: &str) -> Result<tauri::image::Image<'static>, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(png)
+        .map_err(|e| format!("Invalid drag image: {}", e))?;
+    tauri::image::Image::from_bytes(&bytes)
+        .map(|image| image.to_owned())
+        .map_err(|e| format!("Invalid drag image: {}", e))
+}
+
+/// Render the note at `source` to `target` as HTML
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn write_html(source: &std::path::Path, target: &std::path::Path) -> Result<(), String> {
+    let markdown =
+        std::fs::read_to_string(source).map_err(|e| format!("Failed to read file: {}", e))?;
+    std::fs::write(
+        target,
+        crate::batch::render_html(&markdown, crate::extract_title),
+    )
+    .map_err(|e| format!("Failed to write HTML: {}", e))
+}
+
+/// The file name a note dragged as HTML gets
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn html_name(source: &std::path::Path) -> Result<String, String> {
+    let stem = source.file_stem().ok_or("Invalid file path")?;
+    Ok(format!("{}.html", stem.to_string_lossy()))
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn drag_image(
+    image: Option<tauri::image::Image<'static>>,
+) -> Option<tauri_runtime_wry::tao::drag::DragImage> {
+    image.map(|image| tauri_runtime_wry::tao::drag::DragImage {
+        rgba: image.rgba().to_vec(),
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+/// NSDraggingSession through tao, with HTML as file promises
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Item, Promises};
+    use std::path::PathBuf;
+    use tauri::{Manager, WebviewWindow};
+    use tauri_runtime_wry::tao::drag::DragItem;
+    use tauri_runtime_wry::tao::platform::macos;
+
+    pub fn start(
+        window: &WebviewWindow,
+        items: Vec<Item>,
+        image: Option<tauri::image::Image<'static>>,
+    ) -> Result<(), String> {
+        let promises = window.state::<Promises>();
+        let mut promises = promises.0.lock().expect("drag promises mutex");
+        promises.clear();
+        let items = items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| match item {
+                Item::File { path } => Ok(DragItem::File(PathBuf::from(path))),
+                Item::Html { path } => {
+                    let source = PathBuf::from(path);
+                    let file_name = super::html_name(&source)?;
+                    let id = format!("html-{}", index);
+                    promises.insert(id.clone(), source);
+                    Ok(DragItem::Promise {
+                        id,
+                        file_name,
+                        file_type: "public.html".to_string(),
+                    })
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        drop(promises);
+
+        let image = super::drag_image(image);
+        crate::windows::with_ns_window(window, move |ns_window| {
+            let result = unsafe { macos::start_drag(ns_window, &items, image.as_ref()) };
+            if let Err(e) = result {
+                log::warn!("couldn't start dragging notes: {}", e);
+            }
+        })
+    }
+}
+
+/// DoDragDrop through tao. Explorer can't be promised files, so notes
+/// dragged as HTML are rendered into a temporary folder first.
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::Item;
+    use std::path::PathBuf;
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::drag::DragItem;
+    use tauri_runtime_wry::tao::platform::windows;
+
+    pub fn start(
+        window: &WebviewWindow,
+        items: Vec<Item>,
+        image: Option<tauri::image::Image<'static>>,
+    ) -> Result<(), String> {
+        // Replaced by each drag; the last one's files may still be being
+        // copied when it returns
+        let staging = std::env::temp_dir().join("smudge-drag");
+        let _ = std::fs::remove_dir_all(&staging);
+        std::fs::create_dir_all(&staging)
+            .map_err(|e| format!("Failed to create drag folder: {}", e))?;
+        let items = items
+            .into_iter()
+            .map(|item| match item {
+                Item::File { path } => Ok(DragItem::File(PathBuf::from(path))),
+                Item::Html { path } => {
+                    let source = PathBuf::from(path);
+                    let target = staging.join(super::html_name(&source)?);
+                    super::write_html(&source, &target)?;
+                    Ok(DragItem::File(target))
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let image = super::drag_image(image);
+        let target = window.clone();
+        window
+            .run_on_main_thread(move || {
+                // Runs a modal loop until the drop
+                match windows::start_drag(&items, image.as_ref()) {
+                    Ok(operation) => super::emit_ended(&target, operation.into()),
+                    Err(e) => log::warn!("couldn't start dragging notes: {}", e),
+                }
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use super::Item;
+    use tauri::WebviewWindow;
+
+    pub fn start(
+        _window: &WebviewWindow,
+        _items: Vec<Item>,
+        _image: Option<tauri::image::Image<'static>>,
+    ) -> Result<(), String> {
+        Err("Dragging notes out is only available on macOS and Windows".to_string())
+    }
+}
@@ -0,0 +1,84 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{Emitter, Manager, PhysicalPosition, Window};
+
+use crate::fs_scope::FsScope;
+
+/// Tauri's own file-drop event, which the frontend's `onDragDropEvent`
+/// listens for
+const DROP_EVENT: &str = "tauri://drag-drop";
+
+/// Shaped like the payload Tauri sends with `DROP_EVENT`
+#[derive(Debug, Clone, Serialize)]
+struct Dropped {
+    paths: Vec<PathBuf>,
+    position: PhysicalPosition<f64>,
+}
+
+/// Where promised files are written, a folder per drop
+fn folder() -> PathBuf {
+    std::env::temp_dir().join("smudge-drops")
+}
+
+/// Remove the files promised to an earlier run. Anything kept from them was
+/// copied into the notes folder when it was opened.
+pub fn clear() {
+    let _ = std::fs::remove_dir_all(folder());
+}
+
+/// A drop on `window` that named no files. Mail attachments, photos from
+/// Photos and images from browsers are promised instead, so have them
+/// written and send their paths as a second drop at the same `position`.
+pub fn dropped(window: &Window, position: PhysicalPosition<f64>) {
+    #[cfg(target_os = "macos")]
+    {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        let destination = folder().join(nanos.to_string());
+        if let Err(e) = std::fs::create_dir_all(&destination) {
+            log::warn!(error:% = e; "couldn't create a folder for promised files");
+            return;
+        }
+        let target = window.clone();
+        let completion = move |files: Vec<std::io::Result<PathBuf>>| {
+            let paths: Vec<PathBuf> = files
+                .into_iter()
+                .filter_map(|file| {
+                    file.inspect_err(|e| log::warn!(error:% = e; "a promised file wasn't written"))
+                        .ok()
+                })
+                .collect();
+            if !paths.is_empty() {
+                received(&target, paths, position);
+            }
+        };
+        match tauri_runtime_wry::tao::platform::macos::receive_file_promises(
+            &destination,
+            completion,
+        ) {
+            Ok(0) => {
+                let _ = std::fs::remove_dir(&destination);
+            }
+            Ok(count) => log::info!(label = window.label(), count; "receiving promised files"),
+            Err(e) => log::warn!(error:% = e; "couldn't receive promised files"),
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (window, position);
+    }
+}
+
+/// Let the frontend at the files that were promised, as it is at dropped
+/// ones, and hand them to it
+fn received(window: &Window, paths: Vec<PathBuf>, position: PhysicalPosition<f64>) {
+    if let Some(scope) = window.try_state::<FsScope>() {
+        scope.dropped(window.app_handle(), &paths);
+    }
+    let assets = window.asset_protocol_scope();
+    for path in &paths {
+        let _ = assets.allow_file(path);
+    }
+    let _ = window.emit_to(window.label(), DROP_EVENT, Dropped { paths, position });
+}
@@ -0,0 +1,194 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// How soon a command's work has to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// The user is waiting on it, like a search as they type
+    Interactive,
+    /// Can wait behind interactive work, like rebuilding the search index
+    Background,
+}
+
+/// Counters for one priority, as reported by the health command
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueMetrics {
+    /// Tasks waiting for a worker
+    pub queued: usize,
+    /// Deepest the queue has been since launch
+    pub peak_queued: usize,
+    pub running: usize,
+    pub completed: u64,
+    pub panicked: u64,
+    /// Mean time from submission to a worker picking it up
+    pub average_wait_ms: f64,
+    #[serde(skip)]
+    started: u64,
+    #[serde(skip)]
+    total_wait: Duration,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutorMetrics {
+    pub workers: usize,
+    pub interactive: QueueMetrics,
+    pub background: QueueMetrics,
+}
+
+struct Task {
+    run: Box<dyn FnOnce() -> bool + Send>,
+    submitted: Instant,
+}
+
+#[derive(Default)]
+struct Queues {
+    interactive: VecDeque<Task>,
+    background: VecDeque<Task>,
+    interactive_metrics: QueueMetrics,
+    background_metrics: QueueMetrics,
+}
+
+impl Queues {
+    fn parts(&mut self, priority: Priority) -> (&mut VecDeque<Task>, &mut QueueMetrics) {
+        match priority {
+            Priority::Interactive => (&mut self.interactive, &mut self.interactive_metrics),
+            Priority::Background => (&mut self.background, &mut self.background_metrics),
+        }
+    }
+
+    /// Interactive work first. Reserved workers never take background work,
+    /// so a backlog of it can't hold up interactive commands.
+    fn next(&mut self, reserved: bool) -> Option<(Priority, Task)> {
+        if let Some(task) = self.interactive.pop_front() {
+            return Some((Priority::Interactive, task));
+        }
+        if reserved {
+            return None;
+        }
+        self.background
+            .pop_front()
+            .map(|task| (Priority::Background, task))
+    }
+}
+
+struct Shared {
+    queues: Mutex<Queues>,
+    available: Condvar,
+}
+
+/// Bounded worker pool for CPU-heavy command work, so it neither blocks the
+/// async runtime nor grows a thread per request the way `spawn_blocking`
+/// does.
+pub struct Executor {
+    shared: Arc<Shared>,
+    workers: usize,
+}
+
+/// Workers that only run interactive tasks
+const RESERVED_WORKERS: usize = 1;
+
+fn worker_count() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(2)
+        .clamp(RESERVED_WORKERS + 1, 8)
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        let shared = Arc::new(Shared {
+            queues: Mutex::new(Queues::default()),
+            available: Condvar::new(),
+        });
+        let workers = worker_count();
+        for index in 0..workers {
+            let shared = Arc::clone(&shared);
+            let reserved = index < RESERVED_WORKERS;
+            let _ = thread::Builder::new()
+                .name(format!("smudge-executor-{}", index))
+                .spawn(move || work(&shared, reserved));
+        }
+        Self { shared, workers }
+    }
+}
+
+impl Executor {
+    /// Run `f` on the pool and wait for its result
+    pub async fn run<T, F>(&self, priority: Priority, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let task = Task {
+            run: Box::new(move || match catch_unwind(AssertUnwindSafe(f)) {
+                Ok(value) => {
+                    let _ = tx.send(value);
+                    true
+                }
+                Err(_) => false,
+            }),
+            submitted: Instant::now(),
+        };
+        {
+            let mut queues = self.shared.queues.lock().expect("executor mutex");
+            let (queue, metrics) = queues.parts(priority);
+            queue.push_back(task);
+            metrics.queued = queue.len();
+            metrics.peak_queued = metrics.peak_queued.max(queue.len());
+        }
+        // Reserved workers can't take background tasks, so wake everyone
+        // rather than risk waking only one of them
+        self.shared.available.notify_all();
+        rx.await.map_err(|_| "Task panicked".to_string())
+    }
+
+    pub fn metrics(&self) -> ExecutorMetrics {
+        let queues = self.shared.queues.lock().expect("executor mutex");
+        ExecutorMetrics {
+            workers: self.workers,
+            interactive: queues.interactive_metrics.clone(),
+            background: queues.background_metrics.clone(),
+        }
+    }
+}
+
+fn work(shared: &Shared, reserved: bool) {
+    loop {
+        let (priority, task) = {
+            let mut queues = shared.queues.lock().expect("executor mutex");
+            let (priority, task) = loop {
+                if let Some(next) = queues.next(reserved) {
+                    break next;
+                }
+                queues = shared.available.wait(queues).expect("executor mutex");
+            };
+            let (queue, metrics) = queues.parts(priority);
+            metrics.queued = queue.len();
+            metrics.running += 1;
+            metrics.started += 1;
+            metrics.total_wait += task.submitted.elapsed();
+            metrics.average_wait_ms =
+                metrics.total_wait.as_secs_f64() * 1000.0 / metrics.started as f64;
+            (priority, task)
+        };
+
+        let finished = (task.run)();
+
+        let mut queues = shared.queues.lock().expect("executor mutex");
+        let (_, metrics) = queues.parts(priority);
+        metrics.running -= 1;
+        if finished {
+            metrics.completed += 1;
+        } else {
+            metrics.panicked += 1;
+        }
+    }
+}
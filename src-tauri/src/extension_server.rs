@@ -0,0 +1,348 @@
+use base64::Engine;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::{Method, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::{AppHandle, Manager, Url};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+use crate::{credentials, deep_link};
+
+/// Where the companion extension finds the server, on 127.0.0.1 only
+pub const PORT: u16 = 51837;
+/// The token requests have to carry, kept in the keychain so the extension
+/// stays paired across launches
+const TOKEN_KEY: &str = "extension-token";
+/// Clips are a URL, a title and a selection; nothing legitimate is bigger
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// Pages can reach loopback too. Only extensions' own pages may call, and
+/// only with the token.
+const EXTENSION_ORIGINS: &[&str] = &[
+    "chrome-extension://",
+    "moz-extension://",
+    "safari-web-extension://",
+];
+
+/// What the extension sends
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Clip {
+    url: String,
+    title: Option<String>,
+    /// The text selected on the page
+    selection: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionServerStatus {
+    pub running: bool,
+    pub port: u16,
+    /// What to paste into the extension to pair it; `None` while stopped
+    pub token: Option<String>,
+}
+
+/// The opt-in server a browser extension pushes pages and selections to.
+/// It listens on loopback only and answers only requests bearing the
+/// pairing token. Clips become `smudge://clip` links and go through
+/// `deep_link::received`, so the frontend handles them like any other link.
+#[derive(Default)]
+pub struct ExtensionServer {
+    running: Mutex<Option<Running>>,
+    /// The last server stopped, which has to let go of the port before
+    /// another can listen on it
+    stopping: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+struct Running {
+    /// Stops the server and closes its connections
+    stop: watch::Sender<bool>,
+    /// Read on every request, so a new one unpairs connections already open
+    token: Arc<RwLock<String>>,
+    served: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl ExtensionServer {
+    /// Listen on `PORT` until `stop`, pairing with the saved token or a new
+    /// one. Already running is fine.
+    pub async fn start(&self, app: &AppHandle) -> Result<(), String> {
+        if self
+            .running
+            .lock()
+            .expect("extension server mutex")
+            .is_some()
+        {
+            return Ok(());
+        }
+        let token = match credentials::retrieve(app, TOKEN_KEY)? {
+            Some(token) => token,
+            None => new_token(app)?,
+        };
+        let stopping = self.stopping.lock().expect("extension server mutex").take();
+        if let Some(stopping) = stopping {
+            let _ = stopping.await;
+        }
+        let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, PORT)))
+            .await
+            .map_err(|e| format!("Couldn't listen on port {}: {}", PORT, e))?;
+        let (stop, stopped) = watch::channel(false);
+        let token = Arc::new(RwLock::new(token));
+        let served =
+            tauri::async_runtime::spawn(serve(app.clone(), listener, Arc::clone(&token), stopped));
+        *self.running.lock().expect("extension server mutex") = Some(Running {
+            stop,
+            token,
+            served,
+        });
+        log::info!(port = PORT; "extension server started");
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        if let Some(running) = self.running.lock().expect("extension server mutex").take() {
+            running.stop.send_replace(true);
+            *self.stopping.lock().expect("extension server mutex") = Some(running.served);
+            log::info!("extension server stopped");
+        }
+    }
+
+    pub fn status(&self) -> ExtensionServerStatus {
+        let running = self.running.lock().expect("extension server mutex");
+        ExtensionServerStatus {
+            running: running.is_some(),
+            port: PORT,
+            token: running
+                .as_ref()
+                .map(|running| running.token.read().expect("token read lock").clone()),
+        }
+    }
+
+    /// Replace the token, unpairing the extension, in the running server
+    /// too
+    pub async fn reset_token(&self, app: &AppHandle) -> Result<(), String> {
+        let token = new_token(app)?;
+        if let Some(running) = &*self.running.lock().expect("extension server mutex") {
+            *running.token.write().expect("token write lock") = token;
+        }
+        Ok(())
+    }
+}
+
+fn new_token(app: &AppHandle) -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Couldn't make a token: {}", e))?;
+    let token = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+    credentials::store(app, TOKEN_KEY, &token)?;
+    Ok(token)
+}
+
+async fn serve(
+    app: AppHandle,
+    listener: TcpListener,
+    token: Arc<RwLock<String>>,
+    mut stopped: watch::Receiver<bool>,
+) {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    log::warn!(error:% = e; "extension server accept failed");
+                    continue;
+                }
+            },
+            _ = stopped.changed() => return,
+        };
+        let app = app.clone();
+        let token = Arc::clone(&token);
+        let mut stopped = stopped.clone();
+        tauri::async_runtime::spawn(async move {
+            let service = hyper::service::service_fn(move |request| {
+                let app = app.clone();
+                let token = token.read().expect("token read lock").clone();
+                async move { Ok::<_, Infallible>(handle(&app, &token, request).await) }
+            });
+            let io = hyper_util::rt::TokioIo::new(stream);
+            let connection =
+                hyper::server::conn::http1::Builder::new().serve_connection(io, service);
+            let mut connection = std::pin::pin!(connection);
+            // Kept-alive connections end with the server, after the request
+            // they're answering
+            let mut closing = false;
+            let result = loop {
+                tokio::select! {
+                    result = connection.as_mut() => break result,
+                    _ = stopped.changed(), if !closing => {
+                        closing = true;
+                        connection.as_mut().graceful_shutdown();
+                    }
+                }
+            };
+            if let Err(e) = result {
+                log::debug!(error:% = e; "extension server connection failed");
+            }
+        });
+    }
+}
+
+fn respond(status: StatusCode, body: serde_json::Value) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body.to_string())));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    respond(status, serde_json::json!({ "error": message }))
+}
+
+/// Whether the request was addressed to loopback by number or as
+/// localhost, not through a name rebound to 127.0.0.1 by some page
+fn is_loopback_host(request: &Request<Incoming>) -> bool {
+    let Some(host) = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+    else {
+        return false;
+    };
+    [format!("127.0.0.1:{}", PORT), format!("localhost:{}", PORT)]
+        .contains(&host.to_ascii_lowercase())
+}
+
+/// Compares without stopping at the first difference, so timing doesn't
+/// give the token away
+fn token_matches(request: &Request<Incoming>, token: &str) -> bool {
+    let Some(given) = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+async fn handle(app: &AppHandle, token: &str, request: Request<Incoming>) -> Response<Full<Bytes>> {
+    if !is_loopback_host(&request) {
+        return error(StatusCode::FORBIDDEN, "Unexpected host");
+    }
+    let origin = request.headers().get(header::ORIGIN).cloned();
+    if let Some(origin) = &origin {
+        let from_extension = origin.to_str().is_ok_and(|origin| {
+            EXTENSION_ORIGINS
+                .iter()
+                .any(|prefix| origin.starts_with(prefix))
+        });
+        if !from_extension {
+            return error(StatusCode::FORBIDDEN, "Only extensions may connect");
+        }
+    }
+
+    let mut response = if request.method() == Method::OPTIONS {
+        let mut response = Response::new(Full::new(Bytes::new()));
+        *response.status_mut() = StatusCode::NO_CONTENT;
+        let headers = response.headers_mut();
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static("GET, POST"),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            HeaderValue::from_static("authorization, content-type"),
+        );
+        response
+    } else if !token_matches(&request, token) {
+        error(StatusCode::UNAUTHORIZED, "Missing or wrong token")
+    } else {
+        route(app, request).await
+    };
+    if let Some(origin) = origin {
+        response
+            .headers_mut()
+            .insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    }
+    response
+}
+
+async fn route(app: &AppHandle, request: Request<Incoming>) -> Response<Full<Bytes>> {
+    match (request.method(), request.uri().path()) {
+        (&Method::GET, "/status") => respond(
+            StatusCode::OK,
+            serde_json::json!({
+                "app": "smudge",
+                "version": app.package_info().version.to_string(),
+            }),
+        ),
+        (&Method::POST, "/clip") => {
+            let body = match Limited::new(request.into_body(), MAX_BODY_BYTES)
+                .collect()
+                .await
+            {
+                Ok(body) => body.to_bytes(),
+                Err(_) => return error(StatusCode::PAYLOAD_TOO_LARGE, "The clip is too large"),
+            };
+            let clip: Clip = match serde_json::from_slice(&body) {
+                Ok(clip) => clip,
+                Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+            };
+            match clip_url(&clip) {
+                Ok(url) => {
+                    let accepted = deep_link::received(app, &[url]);
+                    respond(StatusCode::OK, serde_json::json!({ "accepted": accepted }))
+                }
+                Err(e) => error(StatusCode::BAD_REQUEST, &e),
+            }
+        }
+        _ => error(StatusCode::NOT_FOUND, "No such endpoint"),
+    }
+}
+
+/// `smudge://clip?url=...&title=...&selection=...`, the link a clip is
+/// handled as
+fn clip_url(clip: &Clip) -> Result<Url, String> {
+    let page = Url::parse(&clip.url).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !matches!(page.scheme(), "http" | "https") {
+        return Err("Only web pages can be clipped".to_string());
+    }
+    let mut url = Url::parse(&format!("{}://clip", deep_link::SCHEME)).expect("valid clip URL");
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("url", page.as_str());
+        if let Some(title) = clip.title.as_deref().filter(|title| !title.is_empty()) {
+            query.append_pair("title", title);
+        }
+        if let Some(selection) = clip.selection.as_deref().filter(|text| !text.is_empty()) {
+            query.append_pair("selection", selection);
+        }
+    }
+    Ok(url)
+}
+
+/// Start the server at launch if the user turned it on
+pub fn start_if_enabled(app: &AppHandle, enabled: bool) {
+    if !enabled {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let server = app.state::<ExtensionServer>();
+        if let Err(e) = server.start(&app).await {
+            log::warn!(error:% = e; "extension server not started");
+        }
+    });
+}
@@ -0,0 +1,455 @@
+use serde::Serialize;
+
+/// A document type smudge can open. Generated by build.rs from
+/// `fileAssociations` in tauri.conf.json, which the installers register.
+pub struct FileType {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    /// MIME type, for xdg-mime
+    #[cfg_attr(any(target_os = "macos", windows), allow(dead_code))]
+    pub mime_type: &'static str,
+    /// Uniform type identifier, for Launch Services
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub uti: &'static str,
+}
+
+include!(concat!(env!("OUT_DIR"), "/file_types.rs"));
+
+pub fn find(id: &str) -> Result<&'static FileType, String> {
+    FILE_TYPES
+        .iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| format!("Unknown file type: {}", id))
+}
+
+/// The file type an extension belongs to, with or without its leading dot
+pub fn find_by_extension(ext: &str) -> Result<&'static FileType, String> {
+    let ext = ext.trim_start_matches('.');
+    FILE_TYPES
+        .iter()
+        .find(|t| t.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+        .ok_or_else(|| format!("Smudge doesn't open .{} files", ext))
+}
+
+/// Who opens a file type right now
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Association {
+    pub id: String,
+    pub name: String,
+    pub extensions: Vec<String>,
+    pub owned: bool,
+    /// Bundle ID, ProgID or desktop file of the current default app
+    pub handler: Option<String>,
+}
+
+/// Outcome of claiming or releasing a file type
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssociationChange {
+    pub association: Association,
+    /// The OS wants the user to confirm in its own settings (Windows)
+    pub needs_confirmation: bool,
+}
+
+/// Identifies smudge to the platform's default-app registry
+pub struct AppId<'a> {
+    /// Bundle identifier from tauri.conf.json
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub bundle_id: &'a str,
+}
+
+/// Make the running copy of smudge known to the platform as a handler for
+/// every type in `FILE_TYPES`, so it's offered in Open With even when it
+/// wasn't installed by an installer. Doesn't change any defaults.
+pub fn register() -> Result<(), String> {
+    // A dev build would shadow the installed app
+    if cfg!(debug_assertions) {
+        return Ok(());
+    }
+    platform::register()
+}
+
+/// Whether smudge opens files with `ext` by default
+pub fn is_default_handler(ext: &str, app: &AppId) -> Result<bool, String> {
+    Ok(status(find_by_extension(ext)?, app).owned)
+}
+
+pub fn status(file_type: &FileType, app: &AppId) -> Association {
+    let handler = platform::default_handler(file_type);
+    Association {
+        id: file_type.id.to_string(),
+        name: file_type.name.to_string(),
+        extensions: file_type.extensions.iter().map(|e| e.to_string()).collect(),
+        owned: handler
+            .as_deref()
+            .is_some_and(|h| platform::is_own_handler(h, app)),
+        handler,
+    }
+}
+
+/// Make smudge the default app for `file_type`. Returns the handler it
+/// replaced, so `release` can hand the type back later.
+pub fn claim(file_type: &FileType, app: &AppId) -> Result<(Option<String>, bool), String> {
+    let previous =
+        platform::default_handler(file_type).filter(|h| !platform::is_own_handler(h, app));
+    let needs_confirmation = platform::claim(file_type, app)?;
+    Ok((previous, needs_confirmation))
+}
+
+/// Stop being the default app for `file_type`, restoring `previous` where
+/// the platform allows setting it
+pub fn release(file_type: &FileType, app: &AppId, previous: Option<&str>) -> Result<(), String> {
+    platform::release(file_type, app, previous)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{AppId, FileType};
+    use std::ffi::{c_char, c_void, CStr, CString};
+
+    type CFStringRef = *const c_void;
+
+    const UTF8: u32 = 0x0800_0100;
+    const ROLES_ALL: u32 = 0xFFFF_FFFF;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            alloc: *const c_void,
+            string: *const c_char,
+            encoding: u32,
+        ) -> CFStringRef;
+        fn CFStringGetCString(
+            string: CFStringRef,
+            buffer: *mut c_char,
+            size: isize,
+            encoding: u32,
+        ) -> bool;
+        fn CFRelease(cf: *const c_void);
+        fn CFBundleGetMainBundle() -> *const c_void;
+        fn CFBundleCopyBundleURL(bundle: *const c_void) -> *const c_void;
+    }
+
+    #[link(name = "CoreServices", kind = "framework")]
+    extern "C" {
+        fn LSCopyDefaultRoleHandlerForContentType(
+            content_type: CFStringRef,
+            role: u32,
+        ) -> CFStringRef;
+        fn LSSetDefaultRoleHandlerForContentType(
+            content_type: CFStringRef,
+            role: u32,
+            handler: CFStringRef,
+        ) -> i32;
+        fn LSRegisterURL(url: *const c_void, update: bool) -> i32;
+    }
+
+    struct CfString(CFStringRef);
+
+    impl CfString {
+        fn new(value: &str) -> Option<Self> {
+            let value = CString::new(value).ok()?;
+            let string =
+                unsafe { CFStringCreateWithCString(std::ptr::null(), value.as_ptr(), UTF8) };
+            (!string.is_null()).then_some(Self(string))
+        }
+
+        fn to_string(&self) -> Option<String> {
+            let mut buffer = [0 as c_char; 1024];
+            let ok = unsafe {
+                CFStringGetCString(self.0, buffer.as_mut_ptr(), buffer.len() as isize, UTF8)
+            };
+            ok.then(|| {
+                unsafe { CStr::from_ptr(buffer.as_ptr()) }
+                    .to_string_lossy()
+                    .into_owned()
+            })
+        }
+    }
+
+    impl Drop for CfString {
+        fn drop(&mut self) {
+            unsafe { CFRelease(self.0) }
+        }
+    }
+
+    /// Re-register the app bundle, which Launch Services otherwise only
+    /// notices when Finder sees it in a folder it's browsing
+    pub fn register() -> Result<(), String> {
+        let is_bundle = std::env::current_exe().is_ok_and(|exe| {
+            exe.components()
+                .any(|c| c.as_os_str().to_string_lossy().ends_with(".app"))
+        });
+        if !is_bundle {
+            return Ok(());
+        }
+        let status = unsafe {
+            let url = CFBundleCopyBundleURL(CFBundleGetMainBundle());
+            if url.is_null() {
+                return Err("Couldn't find the app bundle".to_string());
+            }
+            let status = LSRegisterURL(url, true);
+            CFRelease(url);
+            status
+        };
+        if status != 0 {
+            return Err(format!(
+                "Couldn't register with Launch Services (error {})",
+                status
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn default_handler(file_type: &FileType) -> Option<String> {
+        let uti = CfString::new(file_type.uti)?;
+        let handler = unsafe { LSCopyDefaultRoleHandlerForContentType(uti.0, ROLES_ALL) };
+        if handler.is_null() {
+            return None;
+        }
+        CfString(handler).to_string()
+    }
+
+    pub fn is_own_handler(handler: &str, app: &AppId) -> bool {
+        // Launch Services lowercases bundle IDs
+        handler.eq_ignore_ascii_case(app.bundle_id)
+    }
+
+    fn set_handler(file_type: &FileType, bundle_id: &str) -> Result<(), String> {
+        let uti = CfString::new(file_type.uti).ok_or("Invalid type identifier")?;
+        let handler = CfString::new(bundle_id).ok_or("Invalid bundle identifier")?;
+        let status = unsafe { LSSetDefaultRoleHandlerForContentType(uti.0, ROLES_ALL, handler.0) };
+        if status != 0 {
+            return Err(format!(
+                "Couldn't change the default app for {} (error {})",
+                file_type.name, status
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn claim(file_type: &FileType, app: &AppId) -> Result<bool, String> {
+        set_handler(file_type, app.bundle_id)?;
+        Ok(false)
+    }
+
+    pub fn release(
+        file_type: &FileType,
+        _app: &AppId,
+        previous: Option<&str>,
+    ) -> Result<(), String> {
+        // Launch Services has no "unset"; TextEdit opens Markdown otherwise
+        set_handler(file_type, previous.unwrap_or("com.apple.TextEdit"))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{AppId, FileType, FILE_TYPES};
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    fn prog_id(file_type: &FileType) -> String {
+        format!("Smudge.{}", file_type.id)
+    }
+
+    fn reg(args: &[&str]) -> Result<String, String> {
+        let output = Command::new("reg")
+            .args(args)
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| format!("Failed to run reg: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// The REG_SZ data on the line naming `value`
+    fn query(key: &str, value: Option<&str>) -> Option<String> {
+        let mut args = vec!["query", key];
+        match value {
+            Some(value) => args.extend(["/v", value]),
+            None => args.push("/ve"),
+        }
+        let output = reg(&args).ok()?;
+        output
+            .lines()
+            .find_map(|line| line.split_once("REG_SZ"))
+            .map(|(_, data)| data.trim().to_string())
+    }
+
+    fn exe_path() -> Result<String, String> {
+        std::env::current_exe()
+            .map(|p| p.to_string_lossy().into_owned())
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn default_handler(file_type: &FileType) -> Option<String> {
+        // The user's choice wins over what installers register
+        let ext = file_type.extensions.first()?;
+        query(
+            &format!(
+                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\FileExts\.{}\UserChoice",
+                ext
+            ),
+            Some("ProgId"),
+        )
+        .or_else(|| query(&format!(r"HKCR\.{}", ext), None))
+    }
+
+    pub fn is_own_handler(handler: &str, _app: &AppId) -> bool {
+        let Ok(exe) = exe_path() else {
+            return false;
+        };
+        query(&format!(r"HKCR\{}\shell\open\command", handler), None)
+            .is_some_and(|command| command.to_lowercase().contains(&exe.to_lowercase()))
+    }
+
+    /// Add smudge's ProgID for each type and list it under the types'
+    /// extensions, which puts it in Open With
+    pub fn register() -> Result<(), String> {
+        FILE_TYPES.iter().try_for_each(register_prog_id)
+    }
+
+    fn register_prog_id(file_type: &FileType) -> Result<(), String> {
+        let prog_id = prog_id(file_type);
+        let class = format!(r"HKCU\Software\Classes\{}", prog_id);
+        let exe = exe_path()?;
+        let command = format!("\"{}\" \"%1\"", exe);
+        reg(&["add", &class, "/ve", "/d", file_type.name, "/f"])?;
+        reg(&[
+            "add",
+            &format!(r"{}\DefaultIcon", class),
+            "/ve",
+            "/d",
+            &format!("\"{}\",0", exe),
+            "/f",
+        ])?;
+        reg(&[
+            "add",
+            &format!(r"{}\shell\open\command", class),
+            "/ve",
+            "/d",
+            &command,
+            "/f",
+        ])?;
+        for ext in file_type.extensions {
+            reg(&[
+                "add",
+                &format!(r"HKCU\Software\Classes\.{}\OpenWithProgids", ext),
+                "/v",
+                &prog_id,
+                "/t",
+                "REG_NONE",
+                "/f",
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Windows doesn't let apps change the default themselves. Register
+    /// smudge as a handler and open Default Apps for the user to pick it.
+    pub fn claim(file_type: &FileType, _app: &AppId) -> Result<bool, String> {
+        register_prog_id(file_type)?;
+        open::that("ms-settings:defaultapps").map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
+    pub fn release(
+        file_type: &FileType,
+        _app: &AppId,
+        _previous: Option<&str>,
+    ) -> Result<(), String> {
+        // Once smudge's ProgID is gone Windows asks which app to use next
+        let prog_id = prog_id(file_type);
+        for ext in file_type.extensions {
+            let _ = reg(&[
+                "delete",
+                &format!(r"HKCU\Software\Classes\.{}\OpenWithProgids", ext),
+                "/v",
+                &prog_id,
+                "/f",
+            ]);
+        }
+        let _ = reg(&[
+            "delete",
+            &format!(r"HKCU\Software\Classes\{}", prog_id),
+            "/f",
+        ]);
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::{AppId, FileType};
+    use std::process::Command;
+
+    /// The desktop file smudge was launched from, falling back to the name
+    /// the Linux bundles install
+    fn desktop_id() -> String {
+        std::env::var("GIO_LAUNCHED_DESKTOP_FILE")
+            .ok()
+            .and_then(|path| {
+                std::path::Path::new(&path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "smudge.desktop".to_string())
+    }
+
+    fn xdg_mime(args: &[&str]) -> Result<String, String> {
+        let output = Command::new("xdg-mime").args(args).output().map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                "xdg-mime is not installed".to_string()
+            } else {
+                format!("Failed to run xdg-mime: {}", e)
+            }
+        })?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// The Linux bundles install smudge's desktop file, which is where
+    /// handlers are declared
+    pub fn register() -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn default_handler(file_type: &FileType) -> Option<String> {
+        xdg_mime(&["query", "default", file_type.mime_type])
+            .ok()
+            .filter(|handler| !handler.is_empty())
+    }
+
+    pub fn is_own_handler(handler: &str, _app: &AppId) -> bool {
+        handler.eq_ignore_ascii_case(&desktop_id())
+    }
+
+    pub fn claim(file_type: &FileType, _app: &AppId) -> Result<bool, String> {
+        xdg_mime(&["default", &desktop_id(), file_type.mime_type])?;
+        Ok(false)
+    }
+
+    pub fn release(
+        file_type: &FileType,
+        _app: &AppId,
+        previous: Option<&str>,
+    ) -> Result<(), String> {
+        let Some(previous) = previous else {
+            return Err(format!(
+                "No previous app is known for {}; pick one in your desktop's default apps settings",
+                file_type.name
+            ));
+        };
+        xdg_mime(&["default", previous, file_type.mime_type])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Finder's label colors, in the order of their tag color index
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TagColor {
+    Gray,
+    Green,
+    Purple,
+    Blue,
+    Yellow,
+    Red,
+    Orange,
+}
+
+impl TagColor {
+    const ALL: [TagColor; 7] = [
+        TagColor::Gray,
+        TagColor::Green,
+        TagColor::Purple,
+        TagColor::Blue,
+        TagColor::Yellow,
+        TagColor::Red,
+        TagColor::Orange,
+    ];
+
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get((index as usize).checked_sub(1)?).copied()
+    }
+
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    fn index(self) -> u8 {
+        Self::ALL.iter().position(|c| *c == self).unwrap_or(0) as u8 + 1
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTag {
+    pub name: String,
+    /// Label color. Only Finder shows these; Linux file managers keep names only.
+    pub color: Option<TagColor>,
+}
+
+/// What the OS knows about a file, timestamps in seconds since the epoch
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMetadata {
+    pub path: String,
+    pub size: u64,
+    /// None on filesystems that don't record creation time
+    pub created: Option<i64>,
+    pub modified: Option<i64>,
+    pub tags: Vec<FileTag>,
+}
+
+fn to_secs(time: std::io::Result<SystemTime>) -> Option<i64> {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+}
+
+fn from_secs(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+}
+
+pub fn read(path: &Path) -> Result<FileMetadata, String> {
+    let metadata = std::fs::metadata(path).map_err(|e| e.to_string())?;
+    Ok(FileMetadata {
+        path: path.to_string_lossy().into_owned(),
+        size: metadata.len(),
+        created: to_secs(metadata.created()),
+        modified: to_secs(metadata.modified()),
+        tags: platform::read_tags(path)?,
+    })
+}
+
+/// Replace the file's tags. Duplicate names are dropped, keeping the first.
+pub fn write_tags(path: &Path, tags: &[FileTag]) -> Result<(), String> {
+    if !path.exists() {
+        return Err("Path does not exist".to_string());
+    }
+    let mut unique: Vec<FileTag> = Vec::new();
+    for tag in tags {
+        let name = tag.name.trim();
+        if !name.is_empty() && !unique.iter().any(|t| t.name == name) {
+            unique.push(FileTag {
+                name: name.to_string(),
+                color: tag.color,
+            });
+        }
+    }
+    platform::write_tags(path, &unique)
+}
+
+/// Set the creation and/or modification date. Linux has no way to set a
+/// creation date, so asking for one there is an error.
+pub fn write_dates(path: &Path, created: Option<i64>, modified: Option<i64>) -> Result<(), String> {
+    let mut times = std::fs::FileTimes::new();
+    if let Some(modified) = modified {
+        times = times.set_modified(from_secs(modified));
+    }
+    if let Some(created) = created {
+        #[cfg(target_os = "macos")]
+        {
+            use std::os::macos::fs::FileTimesExt;
+            times = times.set_created(from_secs(created));
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::FileTimesExt;
+            times = times.set_created(from_secs(created));
+        }
+        #[cfg(not(any(target_os = "macos", windows)))]
+        {
+            let _ = created;
+            return Err("This system can't change creation dates".to_string());
+        }
+    }
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    file.set_times(times).map_err(|e| e.to_string())
+}
+
+/// Show `paths` in the system file manager with them selected
+pub fn reveal(paths: &[PathBuf]) -> Result<(), String> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    if let Some(missing) = paths.iter().find(|p| !p.exists()) {
+        return Err(format!("{} does not exist", missing.display()));
+    }
+
+    #[cfg(target_os = "macos")]
+    std::process::Command::new("open")
+        .arg("-R")
+        .args(paths)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    #[cfg(windows)]
+    {
+        // explorer can select only one item from the command line, and
+        // /select requires backslashes
+        let windows_path = paths[0].to_string_lossy().replace('/', "\\");
+        std::process::Command::new("explorer")
+            .args(["/select,", &windows_path])
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        // File managers implementing org.freedesktop.FileManager1 (Nautilus,
+        // Dolphin, Nemo, ...) select items; otherwise open the folder
+        let uris: Vec<String> = paths
+            .iter()
+            .filter_map(|p| url::Url::from_file_path(p).ok())
+            .map(|u| u.to_string())
+            .collect();
+        let shown = std::process::Command::new("dbus-send")
+            .args([
+                "--session",
+                "--print-reply",
+                "--dest=org.freedesktop.FileManager1",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+            ])
+            .arg(format!("array:string:{}", uris.join(",")))
+            .arg("string:")
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if !shown {
+            let parent = paths[0]
+                .parent()
+                .ok_or_else(|| "Cannot determine parent directory".to_string())?;
+            std::process::Command::new("xdg-open")
+                .arg(parent)
+                .spawn()
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{FileTag, TagColor};
+    use std::path::Path;
+
+    /// Finder keeps tags as a binary plist of "name\ncolor" strings
+    const USER_TAGS: &str = "com.apple.metadata:_kMDItemUserTags";
+
+    pub fn read_tags(path: &Path) -> Result<Vec<FileTag>, String> {
+        let Some(data) = xattr::get(path, USER_TAGS).map_err(|e| e.to_string())? else {
+            return Ok(Vec::new());
+        };
+        let names: Vec<String> = plist::from_bytes(&data).map_err(|e| e.to_string())?;
+        Ok(names
+            .into_iter()
+            .map(|entry| match entry.rsplit_once('\n') {
+                Some((name, color)) => FileTag {
+                    name: name.to_string(),
+                    color: color.parse().ok().and_then(TagColor::from_index),
+                },
+                None => FileTag {
+                    name: entry,
+                    color: None,
+                },
+            })
+            .collect())
+    }
+
+    pub fn write_tags(path: &Path, tags: &[FileTag]) -> Result<(), String> {
+        if tags.is_empty() {
+            if xattr::get(path, USER_TAGS).is_ok_and(|data| data.is_some()) {
+                xattr::remove(path, USER_TAGS).map_err(|e| e.to_string())?;
+            }
+            return Ok(());
+        }
+        let entries: Vec<String> = tags
+            .iter()
+            .map(|tag| format!("{}\n{}", tag.name, tag.color.map_or(0, TagColor::index)))
+            .collect();
+        let mut data = Vec::new();
+        plist::to_writer_binary(&mut data, &entries).map_err(|e| e.to_string())?;
+        xattr::set(path, USER_TAGS, &data).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::FileTag;
+    use std::path::{Path, PathBuf};
+
+    /// Explorer has no tag property for plain-text files, so tags live in an
+    /// NTFS alternate data stream, which travels with the file on NTFS volumes
+    fn stream(path: &Path) -> PathBuf {
+        let mut stream = path.as_os_str().to_os_string();
+        stream.push(":Smudge.Tags");
+        PathBuf::from(stream)
+    }
+
+    pub fn read_tags(path: &Path) -> Result<Vec<FileTag>, String> {
+        match std::fs::read_to_string(stream(path)) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| e.to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn write_tags(path: &Path, tags: &[FileTag]) -> Result<(), String> {
+        if tags.is_empty() {
+            return match std::fs::remove_file(stream(path)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+                _ => Ok(()),
+            };
+        }
+        let content = serde_json::to_string(tags).map_err(|e| e.to_string())?;
+        std::fs::write(stream(path), content).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::FileTag;
+    use std::path::Path;
+
+    /// Comma-separated tag names, as read by Dolphin and Baloo
+    const TAGS: &str = "user.xdg.tags";
+
+    pub fn read_tags(path: &Path) -> Result<Vec<FileTag>, String> {
+        let Some(data) = xattr::get(path, TAGS).map_err(|e| e.to_string())? else {
+            return Ok(Vec::new());
+        };
+        Ok(String::from_utf8_lossy(&data)
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| FileTag {
+                name: name.to_string(),
+                color: None,
+            })
+            .collect())
+    }
+
+    pub fn write_tags(path: &Path, tags: &[FileTag]) -> Result<(), String> {
+        if tags.is_empty() {
+            if xattr::get(path, TAGS).is_ok_and(|data| data.is_some()) {
+                xattr::remove(path, TAGS).map_err(|e| e.to_string())?;
+            }
+            return Ok(());
+        }
+        let names: Vec<&str> = tags.iter().map(|tag| tag.name.as_str()).collect();
+        xattr::set(path, TAGS, names.join(",").as_bytes()).map_err(|e| e.to_string())
+    }
+}
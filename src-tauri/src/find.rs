@@ -0,0 +1,553 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, WebviewWindow};
+
+/// Sent to a window whenever its matches are counted again or the active
+/// match moves
+pub const EVENT: &str = "find-changed";
+
+/// How to match the query
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Options {
+    pub case_sensitive: bool,
+    /// Only whole words; Windows only
+    pub whole_word: bool,
+    /// Highlight every match, not just the active one; Windows only
+    pub highlight_all: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            case_sensitive: false,
+            whole_word: false,
+            highlight_all: true,
+        }
+    }
+}
+
+/// Where finding is at in a window
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Matches {
+    pub query: String,
+    /// How many matches the page has, None until it's known
+    pub match_count: Option<u32>,
+    /// Which of them is active, counting from 1; None when there's none
+    pub active_match: Option<u32>,
+}
+
+struct Session {
+    options: Options,
+    matches: Matches,
+}
+
+/// What each window is finding, by label, managed as Tauri state
+#[derive(Default)]
+pub struct Finds {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl Finds {
+    /// Forget what `window` was finding
+    pub fn window_closed(&self, window: &str) {
+        self.sessions.lock().expect("finds mutex").remove(window);
+    }
+}
+
+/// Change `window`'s matches and tell it, if it's still finding
+fn changed(window: &WebviewWindow, change: impl FnOnce(&mut Matches)) {
+    let matches = {
+        let finds = window.state::<Finds>();
+        let mut sessions = finds.sessions.lock().expect("finds mutex");
+        let Some(session) = sessions.get_mut(window.label()) else {
+            return;
+        };
+        change(&mut session.matches);
+        session.matches.clone()
+    };
+    let _ = window.emit_to(window.label(), EVENT, matches);
+}
+
+/// Find `query` in `window`'s page with the web view's own find, selecting
+/// the first match. An empty query stops finding.
+pub async fn find(
+    window: &WebviewWindow,
+    query: String,
+    options: Options,
+) -> Result<Matches, String> {
+    if query.is_empty() {
+        stop(window).await?;
+        return Ok(Matches::default());
+    }
+    window
+        .state::<Finds>()
+        .sessions
+        .lock()
+        .expect("finds mutex")
+        .insert(
+            window.label().to_string(),
+            Session {
+                options: options.clone(),
+                matches: Matches {
+                    query: query.clone(),
+                    ..Default::default()
+                },
+            },
+        );
+    let (match_count, active_match) = platform::start(window, &query, &options).await?;
+    changed(window, |matches| {
+        matches.match_count = match_count;
+        matches.active_match = active_match;
+    });
+    Ok(Matches {
+        query,
+        match_count,
+        active_match,
+    })
+}
+
+/// Move to the next match, or the previous one when `backwards`, wrapping
+/// around the page
+pub async fn find_next(window: &WebviewWindow, backwards: bool) -> Result<Matches, String> {
+    let (query, options, current) = {
+        let finds = window.state::<Finds>();
+        let sessions = finds.sessions.lock().expect("finds mutex");
+        let session = sessions
+            .get(window.label())
+            .ok_or_else(|| "Nothing is being found".to_string())?;
+        (
+            session.matches.query.clone(),
+            session.options.clone(),
+            session.matches.clone(),
+        )
+    };
+    let (match_count, active_match) =
+        platform::step(window, &query, &options, &current, backwards).await?;
+    changed(window, |matches| {
+        matches.match_count = match_count;
+        matches.active_match = active_match;
+    });
+    Ok(Matches {
+        query,
+        match_count,
+        active_match,
+    })
+}
+
+/// Stop finding in `window`, clearing its highlights
+pub async fn stop(window: &WebviewWindow) -> Result<(), String> {
+    window.state::<Finds>().window_closed(window.label());
+    platform::stop(window).await
+}
+
+/// WKWebView finds one match at a time and doesn't count them, so the page
+/// counts its text nodes' matches itself; a match split across elements is
+/// found but not counted. Its find can't match whole words or highlight
+/// every match.
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Matches, Options};
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::MainThreadMarker;
+    use objc2_foundation::{NSError, NSNumber, NSString};
+    use objc2_web_kit::{WKFindConfiguration, WKFindResult, WKWebView};
+    use std::cell::Cell;
+    use std::ptr::NonNull;
+    use tauri::WebviewWindow;
+
+    fn count_script(query: &str, case_sensitive: bool) -> String {
+        let query = serde_json::to_string(query).unwrap_or_else(|_| "\"\"".to_string());
+        format!(
+            r#"(() => {{
+  const fold = (text) => ({case_sensitive} ? text : text.toLocaleLowerCase());
+  const query = fold({query});
+  const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT);
+  let count = 0;
+  while (walker.nextNode()) {{
+    const text = fold(walker.currentNode.data);
+    for (let at = text.indexOf(query); at !== -1; at = text.indexOf(query, at + query.length)) {{
+      count++;
+    }}
+  }}
+  return count;
+}})()"#
+        )
+    }
+
+    /// Find `query` from the selection on, and hand whether it was found to
+    /// `then`
+    fn find_string(
+        webview: &WKWebView,
+        query: &str,
+        options: &Options,
+        backwards: bool,
+        then: impl FnOnce(bool) + 'static,
+    ) {
+        let mtm = MainThreadMarker::new().expect("web views are used on the main thread");
+        let configuration = unsafe { WKFindConfiguration::new(mtm) };
+        unsafe {
+            configuration.setCaseSensitive(options.case_sensitive);
+            configuration.setBackwards(backwards);
+            configuration.setWraps(true);
+        }
+        let then = Cell::new(Some(then));
+        let found = RcBlock::new(move |result: NonNull<WKFindResult>| {
+            if let Some(then) = then.take() {
+                then(unsafe { result.as_ref().matchFound() });
+            }
+        });
+        unsafe {
+            webview.findString_withConfiguration_completionHandler(
+                &NSString::from_str(query),
+                Some(&configuration),
+                &found,
+            )
+        };
+    }
+
+    pub async fn start(
+        window: &WebviewWindow,
+        query: &str,
+        options: &Options,
+    ) -> Result<(Option<u32>, Option<u32>), String> {
+        let query = query.to_string();
+        let options = options.clone();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        window
+            .with_webview(move |webview| {
+                let webview: Retained<WKWebView> =
+                    unsafe { Retained::retain(webview.inner().cast::<WKWebView>()) }
+                        .expect("a web view");
+                let script = NSString::from_str(&count_script(&query, options.case_sensitive));
+                let tx = Cell::new(Some(tx));
+                let counted = {
+                    let webview = webview.clone();
+                    RcBlock::new(move |result: *mut AnyObject, _error: *mut NSError| {
+                        let count = unsafe { result.as_ref() }
+                            .and_then(|result| result.downcast_ref::<NSNumber>())
+                            .map(|count| count.as_u32());
+                        let Some(tx) = tx.take() else {
+                            return;
+                        };
+                        // Start from the top rather than wherever the
+                        // selection was
+                        unsafe {
+                            webview.evaluateJavaScript_completionHandler(
+                                &NSString::from_str("getSelection().removeAllRanges()"),
+                                None,
+                            )
+                        };
+                        find_string(&webview, &query, &options, false, move |found| {
+                            let matches = match (found, count) {
+                                (false, _) => (Some(0), None),
+                                (true, Some(0) | None) => (None, None),
+                                (true, count) => (count, Some(1)),
+                            };
+                            let _ = tx.send(matches);
+                        });
+                    })
+                };
+                unsafe { webview.evaluateJavaScript_completionHandler(&script, Some(&counted)) };
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The web view didn't answer".to_string())
+    }
+
+    pub async fn step(
+        window: &WebviewWindow,
+        query: &str,
+        options: &Options,
+        current: &Matches,
+        backwards: bool,
+    ) -> Result<(Option<u32>, Option<u32>), String> {
+        let query = query.to_string();
+        let options = options.clone();
+        let count = current.match_count;
+        let active = current.active_match;
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        window
+            .with_webview(move |webview| {
+                let webview = unsafe { &*webview.inner().cast::<WKWebView>() };
+                find_string(webview, &query, &options, backwards, move |found| {
+                    let active = match (found, count, active) {
+                        (false, _, _) => None,
+                        (true, Some(count), Some(active)) if count > 0 => Some(if backwards {
+                            if active <= 1 {
+                                count
+                            } else {
+                                active - 1
+                            }
+                        } else if active >= count {
+                            1
+                        } else {
+                            active + 1
+                        }),
+                        (true, _, active) => active,
+                    };
+                    let _ = tx.send((if found { count } else { Some(0) }, active));
+                });
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The web view didn't answer".to_string())
+    }
+
+    pub async fn stop(window: &WebviewWindow) -> Result<(), String> {
+        window
+            .with_webview(|webview| {
+                let webview = unsafe { &*webview.inner().cast::<WKWebView>() };
+                unsafe {
+                    webview.evaluateJavaScript_completionHandler(
+                        &NSString::from_str("getSelection().removeAllRanges()"),
+                        None,
+                    )
+                };
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// WebView2's find, which counts and highlights matches itself and says
+/// when the count or the active match changes
+#[cfg(windows)]
+mod platform {
+    use super::{changed, Matches, Options};
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use tauri::WebviewWindow;
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        ICoreWebView2, ICoreWebView2Environment15, ICoreWebView2Find, ICoreWebView2_2,
+        ICoreWebView2_28,
+    };
+    use webview2_com::{
+        FindActiveMatchIndexChangedEventHandler, FindMatchCountChangedEventHandler,
+        FindStartCompletedHandler,
+    };
+    use windows::core::{Interface, HSTRING};
+
+    /// A window's find, with the tokens of the handlers watching it
+    struct Watched {
+        find: ICoreWebView2Find,
+        match_count: i64,
+        active_match: i64,
+    }
+
+    thread_local! {
+        // Web views only run on the main thread, so this is only used there
+        static WATCHED: RefCell<HashMap<String, Watched>> = RefCell::new(HashMap::new());
+    }
+
+    fn core(webview: &tauri::webview::PlatformWebview) -> windows::core::Result<ICoreWebView2> {
+        unsafe { webview.controller().CoreWebView2() }
+    }
+
+    fn read(find: &ICoreWebView2Find) -> windows::core::Result<(Option<u32>, Option<u32>)> {
+        let mut count = 0;
+        let mut active = 0;
+        unsafe {
+            find.MatchCount(&mut count)?;
+            find.ActiveMatchIndex(&mut active)?;
+        }
+        Ok((
+            u32::try_from(count).ok(),
+            u32::try_from(active).ok().filter(|active| *active > 0),
+        ))
+    }
+
+    /// Tell `window` when its find's count or active match changes, once
+    /// for each find
+    fn watch(window: &WebviewWindow, find: &ICoreWebView2Find) -> windows::core::Result<()> {
+        let label = window.label().to_string();
+        let watched = WATCHED.with(|watched| {
+            watched
+                .borrow()
+                .get(&label)
+                .is_some_and(|watched| &watched.find == find)
+        });
+        if watched {
+            return Ok(());
+        }
+        let mut match_count = 0;
+        let mut active_match = 0;
+        let counted = window.clone();
+        let moved = window.clone();
+        unsafe {
+            find.add_MatchCountChanged(
+                &FindMatchCountChangedEventHandler::create(Box::new(move |find, _| {
+                    if let Some(find) = find {
+                        let (count, _) = read(&find)?;
+                        changed(&counted, |matches| matches.match_count = count);
+                    }
+                    Ok(())
+                })),
+                &mut match_count,
+            )?;
+            find.add_ActiveMatchIndexChanged(
+                &FindActiveMatchIndexChangedEventHandler::create(Box::new(move |find, _| {
+                    if let Some(find) = find {
+                        let (_, active) = read(&find)?;
+                        changed(&moved, |matches| matches.active_match = active);
+                    }
+                    Ok(())
+                })),
+                &mut active_match,
+            )?;
+        }
+        WATCHED.with(|watched| {
+            watched.borrow_mut().insert(
+                label,
+                Watched {
+                    find: find.clone(),
+                    match_count,
+                    active_match,
+                },
+            )
+        });
+        Ok(())
+    }
+
+    fn find(webview: &tauri::webview::PlatformWebview) -> windows::core::Result<ICoreWebView2Find> {
+        unsafe { core(webview)?.cast::<ICoreWebView2_28>()?.Find() }
+    }
+
+    pub async fn start(
+        window: &WebviewWindow,
+        query: &str,
+        options: &Options,
+    ) -> Result<(Option<u32>, Option<u32>), String> {
+        let query = HSTRING::from(query);
+        let options = options.clone();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let handle = window.clone();
+        window
+            .with_webview(move |webview| {
+                let tx = Rc::new(Cell::new(Some(tx)));
+                let started = (|| unsafe {
+                    let find = find(&webview)?;
+                    let find_options = core(&webview)?
+                        .cast::<ICoreWebView2_2>()?
+                        .Environment()?
+                        .cast::<ICoreWebView2Environment15>()?
+                        .CreateFindOptions()?;
+                    find_options.SetFindTerm(&query)?;
+                    find_options.SetIsCaseSensitive(options.case_sensitive)?;
+                    find_options.SetShouldMatchWord(options.whole_word)?;
+                    find_options.SetShouldHighlightAllMatches(options.highlight_all)?;
+                    find_options.SetSuppressDefaultFindDialog(true)?;
+                    watch(&handle, &find)?;
+                    let done = Rc::clone(&tx);
+                    let started = find.clone();
+                    find.Start(
+                        &find_options,
+                        &FindStartCompletedHandler::create(Box::new(move |result| {
+                            let result = result
+                                .and_then(|()| read(&started))
+                                .map_err(|e| e.message());
+                            if let Some(tx) = done.take() {
+                                let _ = tx.send(result);
+                            }
+                            Ok(())
+                        })),
+                    )
+                })();
+                if let Err(e) = started {
+                    if let Some(tx) = tx.take() {
+                        let _ = tx.send(Err(e.message()));
+                    }
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The web view didn't answer".to_string())?
+            .map_err(|e| format!("Couldn't find in the page: {}", e))
+    }
+
+    /// WebView2 remembers the query, so only the direction is needed
+    pub async fn step(
+        window: &WebviewWindow,
+        _query: &str,
+        _options: &Options,
+        _current: &Matches,
+        backwards: bool,
+    ) -> Result<(Option<u32>, Option<u32>), String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        window
+            .with_webview(move |webview| {
+                let stepped = (|| unsafe {
+                    let find = find(&webview)?;
+                    if backwards {
+                        find.FindPrevious()?;
+                    } else {
+                        find.FindNext()?;
+                    }
+                    read(&find)
+                })();
+                let _ = tx.send(stepped.map_err(|e| e.message()));
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The web view didn't answer".to_string())?
+            .map_err(|e| format!("Couldn't find in the page: {}", e))
+    }
+
+    pub async fn stop(window: &WebviewWindow) -> Result<(), String> {
+        let label = window.label().to_string();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        window
+            .with_webview(move |webview| {
+                let stopped = (|| unsafe {
+                    if let Some(watched) =
+                        WATCHED.with(|watched| watched.borrow_mut().remove(&label))
+                    {
+                        watched.find.remove_MatchCountChanged(watched.match_count)?;
+                        watched
+                            .find
+                            .remove_ActiveMatchIndexChanged(watched.active_match)?;
+                    }
+                    find(&webview)?.Stop()
+                })();
+                let _ = tx.send(stopped.map_err(|e| e.message()));
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The web view didn't answer".to_string())?
+            .map_err(|e| format!("Couldn't stop finding: {}", e))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::{Matches, Options};
+    use tauri::WebviewWindow;
+
+    const UNSUPPORTED: &str = "Finding in the page is only available on macOS and Windows";
+
+    pub async fn start(
+        _window: &WebviewWindow,
+        _query: &str,
+        _options: &Options,
+    ) -> Result<(Option<u32>, Option<u32>), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub async fn step(
+        _window: &WebviewWindow,
+        _query: &str,
+        _options: &Options,
+        _current: &Matches,
+        _backwards: bool,
+    ) -> Result<(Option<u32>, Option<u32>), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub async fn stop(_window: &WebviewWindow) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::sync::OnceLock;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::utils::assets::{AssetKey, AssetsIter, CspHash};
+use tauri::{AppHandle, Assets, Manager, UriSchemeResponder, Wry};
+
+use crate::executor;
+
+/// Vite puts content-hashed chunks here, so their URLs never change meaning
+const HASHED_PREFIX: &str = "/assets/";
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const REVALIDATE: &str = "no-cache";
+/// Read size while decompressing
+const CHUNK: usize = 16 * 1024;
+
+struct Entry {
+    /// Brotli-compressed, as `generate_context!` embedded it
+    compressed: Cow<'static, [u8]>,
+    etag: String,
+}
+
+/// Serves the frontend over the `tauri` scheme in place of Tauri's own
+/// handler. Assets stay brotli-compressed in the binary (Tauri's
+/// `compression` feature) and are decompressed on the executor instead of the
+/// main thread, with content types for everything Vite emits and cache
+/// headers that let the webview keep hashed chunks. HTML still goes through
+/// Tauri's asset resolver, which adds the CSP nonces and hashes.
+pub struct FrontendAssets {
+    embedded: OnceLock<Box<dyn Assets<Wry>>>,
+    entries: OnceLock<HashMap<String, Entry>>,
+}
+
+impl FrontendAssets {
+    /// Take over `context`'s embedded assets. The context keeps resolving
+    /// them through the returned value, which lives for the rest of the app.
+    pub fn install(context: &mut tauri::Context<Wry>) -> &'static Self {
+        let this: &'static Self = Box::leak(Box::new(Self {
+            embedded: OnceLock::new(),
+            entries: OnceLock::new(),
+        }));
+        let embedded = context.set_assets(Box::new(this));
+        let _ = this.embedded.set(embedded);
+        this
+    }
+
+    fn embedded(&'static self) -> &'static dyn Assets<Wry> {
+        self.embedded
+            .get()
+            .expect("frontend assets installed")
+            .as_ref()
+    }
+
+    /// Compressed assets by key. Empty in dev, where Tauri doesn't embed the
+    /// frontend and everything falls back to the resolver.
+    fn entries(&'static self) -> &'static HashMap<String, Entry> {
+        self.entries.get_or_init(|| {
+            self.embedded()
+                .iter()
+                .map(|(key, compressed)| {
+                    let mut hasher = DefaultHasher::new();
+                    compressed.hash(&mut hasher);
+                    let etag = format!("\"{:016x}\"", hasher.finish());
+                    (key.into_owned(), Entry { compressed, etag })
+                })
+                .collect()
+        })
+    }
+
+    /// Protocol handler for the `tauri` scheme
+    pub fn serve(
+        &'static self,
+        app: &AppHandle,
+        request: Request<Vec<u8>>,
+        responder: UriSchemeResponder,
+    ) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let response = match app.try_state::<executor::Executor>() {
+                Some(executor) => {
+                    let app = app.clone();
+                    executor
+                        .run(executor::Priority::Interactive, move || {
+                            self.respond(&app, &request)
+                        })
+                        .await
+                        .unwrap_or_else(|err| error(StatusCode::INTERNAL_SERVER_ERROR, &err))
+                }
+                None => self.respond(&app, &request),
+            };
+            responder.respond(response);
+        });
+    }
+
+    fn respond(&'static self, app: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+        let path = percent_encoding::percent_decode_str(request.uri().path()).decode_utf8_lossy();
+        let key = match path.trim_end_matches('/') {
+            "" => "/index.html".to_string(),
+            path => format!("/{}", path.trim_start_matches('/')),
+        };
+        let origin = format!(
+            "{}://{}",
+            request.uri().scheme_str().unwrap_or("tauri"),
+            request.uri().host().unwrap_or("localhost")
+        );
+
+        let entry = self.entries().get(&key).filter(|_| !key.ends_with(".html"));
+        let Some(entry) = entry else {
+            return resolve(app, &key, &origin);
+        };
+
+        let cache_control = if key.starts_with(HASHED_PREFIX) {
+            IMMUTABLE
+        } else {
+            REVALIDATE
+        };
+        let builder = Response::builder()
+            .header(header::CACHE_CONTROL, cache_control)
+            .header(header::ETAG, &entry.etag)
+            .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, &origin);
+
+        let revalidated = request
+            .headers()
+            .get(header::IF_NONE_MATCH)
+            .is_some_and(|tag| tag.as_bytes() == entry.etag.as_bytes());
+        if revalidated {
+            return builder
+                .status(StatusCode::NOT_MODIFIED)
+                .body(Vec::new())
+                .unwrap_or_default();
+        }
+
+        match decompress(&entry.compressed) {
+            Ok(body) => builder
+                .header(header::CONTENT_TYPE, content_type(&key))
+                .body(body)
+                .unwrap_or_default(),
+            Err(err) => error(StatusCode::INTERNAL_SERVER_ERROR, &err.to_string()),
+        }
+    }
+}
+
+/// HTML, and anything not embedded, through Tauri's resolver
+fn resolve(app: &AppHandle, key: &str, origin: &str) -> Response<Vec<u8>> {
+    let Some(asset) = app.asset_resolver().get(key.to_string()) else {
+        return error(StatusCode::NOT_FOUND, key);
+    };
+    let mut builder = Response::builder()
+        .header(header::CONTENT_TYPE, asset.mime_type)
+        // HTML carries fresh CSP nonces on every load
+        .header(header::CACHE_CONTROL, REVALIDATE)
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    if let Some(csp) = asset.csp_header {
+        builder = builder.header(header::CONTENT_SECURITY_POLICY, csp);
+    }
+    builder.body(asset.bytes).unwrap_or_default()
+}
+
+fn decompress(compressed: &[u8]) -> std::io::Result<Vec<u8>> {
+    // Text typically compresses 3-5x; sizing for that avoids most regrowth
+    let mut body = Vec::with_capacity(compressed.len() * 4);
+    brotli_decompressor::Decompressor::new(compressed, CHUNK).read_to_end(&mut body)?;
+    Ok(body)
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(message.as_bytes().to_vec())
+        .unwrap_or_default()
+}
+
+/// By extension. Tauri's own table misses fonts, images and WebAssembly,
+/// which it then sniffs or serves as octet-stream.
+pub fn content_type(key: &str) -> &'static str {
+    let extension = key.rsplit_once('.').map(|(_, ext)| ext).unwrap_or("");
+    match extension.to_ascii_lowercase().as_str() {
+        "html" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/vnd.microsoft.icon",
+        "woff2" => "font/woff2",
+        "woff" => "font/woff",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "md" | "markdown" => "text/markdown; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The context resolves assets through this, so both serve the same bytes
+impl Assets<Wry> for &'static FrontendAssets {
+    fn get(&self, key: &AssetKey) -> Option<Cow<'_, [u8]>> {
+        self.embedded().get(key)
+    }
+
+    fn iter(&self) -> Box<AssetsIter<'_>> {
+        self.embedded().iter()
+    }
+
+    fn csp_hashes(&self, html_path: &AssetKey) -> Box<dyn Iterator<Item = CspHash<'_>> + '_> {
+        self.embedded().csp_hashes(html_path)
+    }
+}
@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::sandbox::Bookmarks;
+use crate::AppState;
+
+/// What a grant lets the frontend do
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Access {
+    Read,
+    ReadWrite,
+}
+
+/// How the user granted a path
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GrantSource {
+    /// Picked in an open, folder or save dialog
+    Dialog,
+    /// Opened from the Finder or Explorer, or passed on the command line
+    Opened,
+    /// Dropped on a window
+    Dropped,
+    /// Copied in another app and pasted
+    Pasted,
+}
+
+/// A path the user handed the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Grant {
+    pub path: PathBuf,
+    /// Grants on a folder cover everything in it
+    pub folder: bool,
+    pub access: Access,
+    pub source: GrantSource,
+    /// Milliseconds since the Unix epoch
+    pub granted_at: u64,
+}
+
+/// The paths commands taking a path from the frontend may touch: what the
+/// user picked, opened or dropped, plus the notes folder. Grants are
+/// persisted to app data, and sandboxed macOS builds keep a security-scoped
+/// bookmark for each so access survives relaunch.
+pub struct FsScope {
+    path: Option<PathBuf>,
+    grants: Mutex<Vec<Grant>>,
+}
+
+impl FsScope {
+    pub fn load(app: &AppHandle) -> Self {
+        let path = app
+            .path()
+            .app_data_dir()
+            .ok()
+            .map(|dir| dir.join("fs-grants.json"));
+        let grants = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            grants: Mutex::new(grants),
+        }
+    }
+
+    /// Let the frontend at `path` from now on, keeping the broader access
+    /// if it was already granted
+    pub fn grant(&self, app: &AppHandle, path: &Path, access: Access, source: GrantSource) {
+        let path = canonical(path);
+        let folder = path.is_dir();
+        {
+            let mut grants = self.grants.lock().expect("fs scope mutex");
+            let granted_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64);
+            match grants.iter_mut().find(|grant| grant.path == path) {
+                Some(grant) => {
+                    grant.access = grant.access.max(access);
+                    grant.folder = folder;
+                    grant.granted_at = granted_at;
+                }
+                None => grants.push(Grant {
+                    path: path.clone(),
+                    folder,
+                    access,
+                    source,
+                    granted_at,
+                }),
+            }
+        }
+        // Save destinations don't exist yet, so there's nothing to bookmark
+        if path.exists() {
+            let bookmarks = app.state::<Bookmarks>();
+            let bookmarked = match source {
+                GrantSource::Opened => bookmarks.bookmark_document(&path),
+                GrantSource::Dialog | GrantSource::Dropped | GrantSource::Pasted => {
+                    bookmarks.grant(&path)
+                }
+            };
+            if let Err(e) = bookmarked {
+                log::warn!(error:% = e; "couldn't bookmark a granted path");
+            }
+        }
+        if let Err(e) = self.persist() {
+            log::warn!(error:% = e; "file access grants not saved");
+        }
+    }
+
+    /// Grant read and write access to files the OS asked the app to open
+    pub fn opened(&self, app: &AppHandle, paths: &[PathBuf]) {
+        for path in paths {
+            self.grant(app, path, Access::ReadWrite, GrantSource::Opened);
+        }
+    }
+
+    /// Grant read and write access to files dropped on a window
+    pub fn dropped(&self, app: &AppHandle, paths: &[PathBuf]) {
+        for path in paths {
+            self.grant(app, path, Access::ReadWrite, GrantSource::Dropped);
+        }
+    }
+
+    pub fn list(&self) -> Vec<Grant> {
+        self.grants.lock().expect("fs scope mutex").clone()
+    }
+
+    /// Take back the grant on `path`. Sandboxed builds keep access until the
+    /// app quits, but commands refuse the path straight away.
+    pub fn revoke(&self, app: &AppHandle, path: &Path) -> Result<(), String> {
+        let path = canonical(path);
+        {
+            let mut grants = self.grants.lock().expect("fs scope mutex");
+            let before = grants.len();
+            grants.retain(|grant| grant.path != path);
+            if grants.len() == before {
+                return Err(format!("{} wasn't granted", path.display()));
+            }
+        }
+        app.state::<Bookmarks>().revoke(&path)?;
+        self.persist()
+    }
+
+    /// `path`, resolved, if the frontend may use it for `access`
+    pub fn check(&self, app: &AppHandle, path: &Path, access: Access) -> Result<PathBuf, String> {
+        let resolved = canonical(path);
+        // Left over when the path couldn't be resolved
+        if resolved
+            .components()
+            .any(|c| matches!(c, Component::ParentDir))
+        {
+            return Err(format!("{} can't be resolved", path.display()));
+        }
+        let notes_folder = {
+            let state = app.state::<AppState>();
+            let app_config = state.app_config.read().expect("app_config read lock");
+            app_config
+                .notes_folder
+                .as_deref()
+                .map(|folder| canonical(Path::new(folder)))
+        };
+        // A root was never picked as the notes folder, see `picked_folder`
+        if notes_folder
+            .is_some_and(|folder| folder.parent().is_some() && resolved.starts_with(folder))
+        {
+            return Ok(resolved);
+        }
+        let grants = self.grants.lock().expect("fs scope mutex");
+        let covered = grants.iter().any(|grant| {
+            grant.access >= access
+                && (grant.path == resolved || (grant.folder && resolved.starts_with(&grant.path)))
+        });
+        if covered {
+            drop(grants);
+            // A document opened in an earlier launch needs its bookmark
+            // resolved before it can be read
+            if let Err(e) = app.state::<Bookmarks>().open_document(&resolved) {
+                log::warn!(error:% = e; "couldn't reopen a bookmarked document");
+            }
+            Ok(resolved)
+        } else {
+            log::warn!(path:? = path; "path outside the granted scope refused");
+            Err(format!(
+                "Smudge hasn't been given access to {}",
+                path.display()
+            ))
+        }
+    }
+
+    /// `path`, resolved, if the user picked the folder in a dialog. Everything
+    /// in the notes folder is in scope, so it can't be just any path the
+    /// frontend names.
+    pub fn picked_folder(&self, path: &Path) -> Result<PathBuf, String> {
+        let resolved = canonical(path);
+        if resolved.parent().is_none() {
+            return Err(format!(
+                "{} can't be the notes folder; choose a folder inside it",
+                path.display()
+            ));
+        }
+        let grants = self.grants.lock().expect("fs scope mutex");
+        let picked = grants.iter().any(|grant| {
+            grant.source == GrantSource::Dialog
+                && grant.access == Access::ReadWrite
+                && grant.path == resolved
+        });
+        if picked {
+            Ok(resolved)
+        } else {
+            log::warn!(path:? = path; "notes folder not picked in a dialog refused");
+            Err(format!(
+                "Choose {} in a folder dialog to use it as the notes folder",
+                path.display()
+            ))
+        }
+    }
+
+    /// Let go of a document the frontend closed. It's opened again the next
+    /// time a command uses it.
+    pub fn close(&self, app: &AppHandle, path: &Path) -> Result<(), String> {
+        app.state::<Bookmarks>().close_document(&canonical(path))
+    }
+
+    fn persist(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let content = {
+            let grants = self.grants.lock().expect("fs scope mutex");
+            serde_json::to_string_pretty(&*grants).map_err(|e| e.to_string())?
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(path, content).map_err(|e| e.to_string())
+    }
+}
+
+/// `path` with links and `..` resolved, so neither can step outside a grant.
+/// A file that doesn't exist yet resolves through its folder.
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(resolved) = path.canonicalize() {
+        return resolved;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map(|parent| parent.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// `path` if the frontend may use it for `access`, for commands to check
+/// their arguments with
+pub fn check(app: &AppHandle, path: &str, access: Access) -> Result<PathBuf, String> {
+    app.state::<FsScope>().check(app, Path::new(path), access)
+}
@@ -0,0 +1,66 @@
+use serde::Serialize;
+use tauri_runtime_wry::tao::event::TouchPhase;
+
+/// Sent to a window with the `Gesture` when the trackpad is pinched,
+/// rotated, double-tapped with two fingers or force-pressed over it
+pub const EVENT: &str = "trackpad-gesture";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    Started,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+impl From<TouchPhase> for Phase {
+    fn from(phase: TouchPhase) -> Self {
+        match phase {
+            TouchPhase::Started => Phase::Started,
+            TouchPhase::Ended => Phase::Ended,
+            TouchPhase::Cancelled => Phase::Cancelled,
+            _ => Phase::Moved,
+        }
+    }
+}
+
+/// A trackpad gesture, reported by AppKit even over the webview, so the
+/// frontend can zoom natively rather than guess from scroll deltas
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Gesture {
+    /// Zoom in by a factor of `1 + delta`
+    Magnify { delta: f64, phase: Phase },
+    /// Degrees counterclockwise since the last one
+    Rotate { delta: f32, phase: Phase },
+    /// Zoom to fit whatever is under the pointer, or back out
+    SmartMagnify,
+    /// How hard the trackpad is pressed, from 0 to 1; stage 2 is a force
+    /// click
+    Pressure {
+        pressure: f32,
+        stage: i64,
+        phase: Phase,
+    },
+}
+
+/// Tell the window tao reported `gesture` for
+#[cfg(target_os = "macos")]
+pub fn performed(
+    app: &tauri::AppHandle,
+    window_id: tauri_runtime_wry::tao::window::WindowId,
+    gesture: Gesture,
+) {
+    use tauri::{Emitter, Manager};
+    use tauri_runtime_wry::tao::platform::macos;
+
+    let window = app.webview_windows().into_values().find(|window| {
+        window
+            .ns_window()
+            .is_ok_and(|ns_window| macos::window_id_for_ns_window(ns_window) == window_id)
+    });
+    if let Some(window) = window {
+        let _ = window.emit_to(window.label(), EVENT, gesture);
+    }
+}
@@ -0,0 +1,183 @@
+use muda::accelerator::{Accelerator, Code, Modifiers};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_runtime_wry::tao::global_shortcut::{
+    self, GlobalShortcut, GlobalShortcutError, ShortcutId,
+};
+use tauri_runtime_wry::tao::keyboard::{KeyCode, ModifiersState};
+
+/// Name of the shortcut that brings up the main window for a new note
+pub const QUICK_CAPTURE: &str = "quick-capture";
+
+/// Sent to the main window when the quick capture shortcut is pressed
+pub const QUICK_CAPTURE_EVENT: &str = "quick-capture";
+
+/// Sent to the main window with the name of any other shortcut that was
+/// pressed
+pub const EVENT: &str = "global-shortcut";
+
+struct Registered {
+    id: ShortcutId,
+    accelerator: String,
+    shortcut: GlobalShortcut,
+}
+
+/// smudge's registered shortcuts by name. Only touched on the main thread,
+/// where tao keeps its own registrations.
+#[derive(Default)]
+pub struct GlobalShortcuts(Mutex<HashMap<String, Registered>>);
+
+// muda and tao name keys after the same W3C codes
+macro_rules! key_code {
+    ($code:expr; $($key:ident),* $(,)?) => {
+        match $code {
+            $(Code::$key => Some(KeyCode::$key),)*
+            _ => None,
+        }
+    };
+}
+
+/// Parse an accelerator like "CmdOrCtrl+Shift+Space"
+fn parse(accelerator: &str) -> Result<GlobalShortcut, String> {
+    let accelerator: Accelerator = accelerator
+        .parse()
+        .map_err(|e| format!("Invalid shortcut: {}", e))?;
+    let mods = accelerator.modifiers();
+    let mut modifiers = ModifiersState::empty();
+    modifiers.set(ModifiersState::SHIFT, mods.contains(Modifiers::SHIFT));
+    modifiers.set(ModifiersState::CONTROL, mods.contains(Modifiers::CONTROL));
+    modifiers.set(ModifiersState::ALT, mods.contains(Modifiers::ALT));
+    modifiers.set(
+        ModifiersState::SUPER,
+        mods.intersects(Modifiers::SUPER | Modifiers::META),
+    );
+    let key = key_code!(accelerator.key();
+        KeyA, KeyB, KeyC, KeyD, KeyE, KeyF, KeyG, KeyH, KeyI, KeyJ, KeyK, KeyL, KeyM,
+        KeyN, KeyO, KeyP, KeyQ, KeyR, KeyS, KeyT, KeyU, KeyV, KeyW, KeyX, KeyY, KeyZ,
+        Digit0, Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+        F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+        F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24,
+        Backquote, Backslash, BracketLeft, BracketRight, Comma, Equal, Minus, Period,
+        Quote, Semicolon, Slash, Backspace, Enter, Space, Tab, Escape, Delete, End,
+        Home, Insert, PageDown, PageUp, ArrowDown, ArrowLeft, ArrowRight, ArrowUp,
+        Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7,
+        Numpad8, Numpad9, NumpadAdd, NumpadDecimal, NumpadDivide, NumpadEnter,
+        NumpadEqual, NumpadMultiply, NumpadSubtract,
+    )
+    .ok_or_else(|| format!("{} can't be used in a global shortcut", accelerator.key()))?;
+    Ok(GlobalShortcut::new(modifiers, key))
+}
+
+fn describe(error: GlobalShortcutError, accelerator: &str) -> String {
+    match error {
+        GlobalShortcutError::Unavailable => {
+            format!("{} is already used by another app", accelerator)
+        }
+        error => format!("Couldn't register {}: {}", accelerator, error),
+    }
+}
+
+fn register_on_main_thread(
+    app: &AppHandle,
+    name: String,
+    accelerator: String,
+    shortcut: GlobalShortcut,
+) -> Result<(), String> {
+    let state = app.state::<GlobalShortcuts>();
+    let mut shortcuts = state.0.lock().expect("global shortcuts mutex");
+    for (other, registered) in shortcuts.iter() {
+        if registered.shortcut != shortcut {
+            continue;
+        }
+        if *other == name {
+            return Ok(());
+        }
+        return Err(format!("{} is already used for {}", accelerator, other));
+    }
+    // The new shortcut is registered before the old one is let go, so a
+    // conflict leaves the old one working
+    let id = global_shortcut::register(shortcut).map_err(|e| describe(e, &accelerator))?;
+    log::info!(
+        name = name.as_str(), accelerator = accelerator.as_str();
+        "registered global shortcut"
+    );
+    let replaced = shortcuts.insert(
+        name,
+        Registered {
+            id,
+            accelerator,
+            shortcut,
+        },
+    );
+    if let Some(replaced) = replaced {
+        global_shortcut::unregister(replaced.id);
+    }
+    Ok(())
+}
+
+/// Register `accelerator` under `name`, replacing what `name` had before.
+/// Fails if smudge uses the accelerator for something else or another app
+/// holds it.
+pub async fn register(app: &AppHandle, name: String, accelerator: String) -> Result<(), String> {
+    let shortcut = parse(&accelerator)?;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        let _ = tx.send(register_on_main_thread(
+            &handle,
+            name,
+            accelerator,
+            shortcut,
+        ));
+    })
+    .map_err(|e| e.to_string())?;
+    rx.await.map_err(|_| "The shortcut wasn't registered".to_string())?
+}
+
+/// Let go of the shortcut registered under `name`, if any
+pub fn unregister(app: &AppHandle, name: String) -> Result<(), String> {
+    let handle = app.clone();
+    app.run_on_main_thread(move || {
+        let state = handle.state::<GlobalShortcuts>();
+        let removed = state.0.lock().expect("global shortcuts mutex").remove(&name);
+        if let Some(removed) = removed {
+            global_shortcut::unregister(removed.id);
+            log::info!(
+                name = name.as_str(), accelerator = removed.accelerator.as_str();
+                "unregistered global shortcut"
+            );
+        }
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// A registered shortcut was pressed: quick capture summons the main window,
+/// anything else is passed on to it by name
+pub fn pressed(app: &AppHandle, id: ShortcutId) {
+    let Some(state) = app.try_state::<GlobalShortcuts>() else {
+        return;
+    };
+    let name = state
+        .0
+        .lock()
+        .expect("global shortcuts mutex")
+        .iter()
+        .find(|(_, registered)| registered.id == id)
+        .map(|(name, _)| name.clone());
+    let Some(name) = name else {
+        return;
+    };
+    log::debug!(name = name.as_str(); "global shortcut pressed");
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if name == QUICK_CAPTURE {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.emit(QUICK_CAPTURE_EVENT, ());
+    } else {
+        let _ = window.emit(EVENT, name);
+    }
+}
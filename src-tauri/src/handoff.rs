@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::WebviewWindow;
+
+/// Activity type advertised for the focused note. Must match the
+/// `NSUserActivityTypes` entry in Info.plist.
+pub const ACTIVITY_TYPE: &str = "com.smudge.editing";
+
+/// The focused document and where the user is in it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HandoffDocument {
+    pub note_id: String,
+    pub title: String,
+    pub position: Option<u32>,
+    pub scroll_top: Option<f64>,
+}
+
+impl HandoffDocument {
+    /// Encode as activity `userInfo` (string values only, see tao's `UserActivity`)
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub fn to_user_info(&self) -> HashMap<String, String> {
+        let mut info = HashMap::new();
+        info.insert("noteId".to_string(), self.note_id.clone());
+        info.insert("title".to_string(), self.title.clone());
+        if let Some(position) = self.position {
+            info.insert("position".to_string(), position.to_string());
+        }
+        if let Some(scroll_top) = self.scroll_top {
+            info.insert("scrollTop".to_string(), scroll_top.to_string());
+        }
+        info
+    }
+
+    /// Decode a continued activity's `userInfo`. Returns None without a note id.
+    pub fn from_user_info(info: &HashMap<String, String>) -> Option<Self> {
+        Some(Self {
+            note_id: info.get("noteId")?.clone(),
+            title: info.get("title").cloned().unwrap_or_default(),
+            position: info.get("position").and_then(|p| p.parse().ok()),
+            scroll_top: info.get("scrollTop").and_then(|s| s.parse().ok()),
+        })
+    }
+}
+
+/// Advertise `doc` from `window`. AppKit offers the key window's activity
+/// to other devices, so the main and mini windows each advertise their own
+/// note, and repeated calls update it in place. No-op off macOS.
+pub fn advertise(window: &WebviewWindow, doc: &HandoffDocument) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        use tauri_runtime_wry::tao::platform::macos::UserActivity;
+
+        let activity = UserActivity {
+            activity_type: ACTIVITY_TYPE.to_string(),
+            title: Some(doc.title.clone()),
+            user_info: doc.to_user_info(),
+            webpage_url: None,
+        };
+        set_window_activity(window, Some(activity))?;
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (window, doc);
+    Ok(())
+}
+
+/// Stop advertising `window`'s note. No-op off macOS.
+pub fn clear(window: &WebviewWindow) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    set_window_activity(window, None)?;
+    #[cfg(not(target_os = "macos"))]
+    let _ = window;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn set_window_activity(
+    window: &WebviewWindow,
+    activity: Option<tauri_runtime_wry::tao::platform::macos::UserActivity>,
+) -> Result<(), String> {
+    // NSUserActivity must be published from the main thread
+    crate::windows::with_ns_window(window, move |ns_window| unsafe {
+        tauri_runtime_wry::tao::platform::macos::set_window_user_activity(
+            ns_window,
+            activity.as_ref(),
+        )
+    })
+}
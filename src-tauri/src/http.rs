@@ -0,0 +1,850 @@
+use base64::Engine;
+use futures_util::StreamExt;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Url};
+use tokio::io::AsyncWriteExt;
+
+use crate::db::Database;
+use crate::jobs::Job;
+use crate::preferences;
+
+/// The cookie jar and the allowed hosts
+const NAMESPACE: &str = "http";
+const COOKIES_KEY: &str = "cookies";
+const ALLOWED_HOSTS_KEY: &str = "allowedHosts";
+/// What the server called each unfinished download's file, by destination
+/// path, so it's only resumed if the server still has the same one
+const DOWNLOADS_NAMESPACE: &str = "http.downloads";
+
+const HTTP_SCHEMES: &[&str] = &["http", "https"];
+/// As many as reqwest's default policy follows
+const MAX_REDIRECTS: usize = 10;
+/// Download progress is reported at most this often
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A request the frontend makes
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRequest {
+    #[serde(default = "default_method")]
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+    /// `body` is base64, for binary bodies
+    #[serde(default)]
+    pub body_base64: bool,
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub response_type: ResponseType,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResponseType {
+    #[default]
+    Text,
+    /// The body base64 encoded, for binary responses
+    Base64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpResponse {
+    pub status: u16,
+    /// Where the request ended up after redirects
+    pub url: String,
+    /// Repeated headers are joined with ", "
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRequest {
+    pub url: String,
+    /// Where the file goes. It's written to `<path>.part` until it's
+    /// complete.
+    pub path: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Download {
+    pub path: String,
+    pub bytes: u64,
+    /// Continued from what an earlier attempt left
+    pub resumed: bool,
+    /// False when the job was cancelled; downloading it again resumes
+    pub complete: bool,
+}
+
+/// The client's cookie jar, saved to the database whenever a response
+/// changes it. Session cookies don't outlive the app.
+#[derive(Default)]
+struct Cookies {
+    store: RwLock<cookie_store::CookieStore>,
+    changed: AtomicBool,
+}
+
+impl reqwest::cookie::CookieStore for Cookies {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let mut store = self.store.write().expect("cookies write lock");
+        for header in cookie_headers {
+            if let Ok(cookie) = header.to_str() {
+                let _ = store.parse(cookie, url);
+            }
+        }
+        self.changed.store(true, Ordering::Relaxed);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let cookies = self
+            .store
+            .read()
+            .expect("cookies read lock")
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if cookies.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&cookies).ok()
+    }
+}
+
+/// HTTP for the frontend, for what the webview's fetch can't do: writing
+/// straight to disk, resuming downloads and going through the system's
+/// proxy. One client serves every request so connections and cookies are
+/// shared. Only hosts the user has allowed can be reached.
+pub struct Http {
+    /// Rebuilt when the network preferences change
+    client: RwLock<reqwest::Client>,
+    cookies: Arc<Cookies>,
+    /// Host names, or `*.example.com` for a domain's subdomains. The
+    /// client's redirect policy holds them too.
+    allowed_hosts: Arc<RwLock<Vec<String>>>,
+    database: Option<Database>,
+}
+
+impl Http {
+    pub fn load(app: &AppHandle) -> Result<Self, String> {
+        let database = app.try_state::<Database>().map(|db| db.inner().clone());
+        let saved = |key| -> Option<String> { database.as_ref()?.get(NAMESPACE, key).ok()? };
+        let store = saved(COOKIES_KEY)
+            .and_then(|json| cookie_store::serde::json::load(json.as_bytes()).ok())
+            .unwrap_or_default();
+        let allowed_hosts = database
+            .as_ref()
+            .and_then(|db| db.get(NAMESPACE, ALLOWED_HOSTS_KEY).ok().flatten())
+            .unwrap_or_default();
+        let cookies = Arc::new(Cookies {
+            store: RwLock::new(store),
+            changed: AtomicBool::new(false),
+        });
+        let allowed_hosts = Arc::new(RwLock::new(allowed_hosts));
+
+        let built = build_client(
+            app,
+            &cookies,
+            &allowed_hosts,
+            &preferences::current(app).network,
+        )?;
+        preferences::on_change(app, |preferences| preferences.network.clone(), {
+            let app = app.clone();
+            move |preferences| {
+                let Ok(http) = client(&app) else {
+                    return;
+                };
+                match build_client(
+                    &app,
+                    &http.cookies,
+                    &http.allowed_hosts,
+                    &preferences.network,
+                ) {
+                    Ok(rebuilt) => {
+                        *http.client.write().expect("http client write lock") = rebuilt;
+                    }
+                    Err(e) => log::warn!(error:% = e; "HTTP client not rebuilt"),
+                }
+            }
+        });
+
+        Ok(Self {
+            client: RwLock::new(built),
+            cookies,
+            allowed_hosts,
+            database,
+        })
+    }
+
+    pub fn allowed_hosts(&self) -> Vec<String> {
+        self.allowed_hosts
+            .read()
+            .expect("allowed hosts read lock")
+            .clone()
+    }
+
+    /// Replace the allowed hosts. Adding any is up to the user, see
+    /// `confirm_allowed_hosts`.
+    fn set_allowed_hosts(&self, hosts: Vec<String>) -> Result<(), String> {
+        if let Some(db) = &self.database {
+            db.put(NAMESPACE, ALLOWED_HOSTS_KEY, &hosts)?;
+        }
+        *self
+            .allowed_hosts
+            .write()
+            .expect("allowed hosts write lock") = hosts;
+        Ok(())
+    }
+
+    pub fn clear_cookies(&self) -> Result<(), String> {
+        self.cookies
+            .store
+            .write()
+            .expect("cookies write lock")
+            .clear();
+        self.cookies.changed.store(true, Ordering::Relaxed);
+        self.save_cookies()
+    }
+
+    /// `url` parsed, if it has one of `schemes` and is to an allowed host.
+    /// WebSockets are held to the same hosts.
+    pub fn check(&self, url: &str, schemes: &[&str]) -> Result<Url, String> {
+        let url = Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+        check_url(&self.allowed_hosts, &url, schemes)?;
+        Ok(url)
+    }
+
+    /// POST `form` to `url`, which `check` has passed, and read the status
+    /// and body. For the backend's own calls, such as exchanging OAuth codes.
+    pub async fn post_form(
+        &self,
+        url: Url,
+        form: &[(&str, &str)],
+        timeout: Duration,
+    ) -> Result<(u16, String), String> {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(form)
+            .finish();
+        let response = self
+            .request_builder(reqwest::Method::POST, url, &HashMap::new())?
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .header(reqwest::header::ACCEPT, "application/json")
+            .timeout(timeout)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = self.save_cookies() {
+            log::warn!(error:% = e; "cookies not saved");
+        }
+        let status = response.status().as_u16();
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        Ok((status, body))
+    }
+
+    fn save_cookies(&self) -> Result<(), String> {
+        if !self.cookies.changed.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let Some(db) = &self.database else {
+            return Ok(());
+        };
+        let mut json = Vec::new();
+        cookie_store::serde::json::save(
+            &self.cookies.store.read().expect("cookies read lock"),
+            &mut json,
+        )
+        .map_err(|e| e.to_string())?;
+        db.put(
+            NAMESPACE,
+            COOKIES_KEY,
+            &String::from_utf8_lossy(&json).into_owned(),
+        )
+    }
+
+    fn request_builder(
+        &self,
+        method: reqwest::Method,
+        url: Url,
+        headers: &HashMap<String, String>,
+    ) -> Result<reqwest::RequestBuilder, String> {
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| format!("Invalid header {}: {}", name, e))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| format!("Invalid value for header {}: {}", name, e))?;
+            header_map.append(name, value);
+        }
+        Ok(self
+            .client
+            .read()
+            .expect("http client read lock")
+            .request(method, url)
+            .headers(header_map))
+    }
+}
+
+/// Whether `url` has one of `schemes` and is to one of `allowed_hosts`
+fn check_url(
+    allowed_hosts: &RwLock<Vec<String>>,
+    url: &Url,
+    schemes: &[&str],
+) -> Result<(), String> {
+    if !schemes.contains(&url.scheme()) {
+        return Err(format!("{} URLs aren't supported", url.scheme()));
+    }
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    let allowed = allowed_hosts
+        .read()
+        .expect("allowed hosts read lock")
+        .iter()
+        .any(|pattern| match pattern.strip_prefix("*.") {
+            Some(domain) => host.ends_with(&format!(".{}", domain)),
+            None => *pattern == host,
+        });
+    if !allowed {
+        return Err(format!("{} isn't an allowed host", host));
+    }
+    Ok(())
+}
+
+fn build_client(
+    app: &AppHandle,
+    cookies: &Arc<Cookies>,
+    allowed_hosts: &Arc<RwLock<Vec<String>>>,
+    network: &preferences::Network,
+) -> Result<reqwest::Client, String> {
+    // Every hop of a redirect is held to the allowed hosts, like the first
+    let allowed_hosts = Arc::clone(allowed_hosts);
+    let redirect = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            return attempt.error("Too many redirects");
+        }
+        match check_url(&allowed_hosts, attempt.url(), HTTP_SCHEMES) {
+            Ok(()) => attempt.follow(),
+            Err(e) => attempt.error(e),
+        }
+    });
+    let builder = reqwest::Client::builder()
+        .redirect(redirect)
+        .cookie_provider(Arc::clone(cookies))
+        .user_agent(format!("Smudge/{}", app.package_info().version))
+        .connect_timeout(Duration::from_secs(network.connect_timeout_secs))
+        .read_timeout(Duration::from_secs(network.read_timeout_secs));
+    let builder = match network.proxy.as_str() {
+        "direct" => builder.no_proxy(),
+        "system" => {
+            // reqwest reads the proxy settings, but not PAC files
+            #[cfg(target_os = "macos")]
+            let builder = builder.proxy(reqwest::Proxy::custom(system_proxy::for_url));
+            builder
+        }
+        proxy => builder.proxy(reqwest::Proxy::all(proxy).map_err(|e| e.to_string())?),
+    };
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Set the allowed hosts to `hosts`, once the user has agreed to any that
+/// weren't already allowed. Whether they're set.
+pub async fn confirm_allowed_hosts(
+    app: &AppHandle,
+    hosts: Vec<String>,
+    window: Option<tauri::WebviewWindow>,
+) -> Result<bool, String> {
+    let hosts: Vec<String> = hosts
+        .iter()
+        .map(|host| host.trim().to_ascii_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect();
+    let current = client(app)?.allowed_hosts();
+    let added: Vec<&String> = hosts
+        .iter()
+        .filter(|host| !current.contains(host))
+        .collect();
+    if !added.is_empty() {
+        let list = added
+            .iter()
+            .map(|host| host.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut dialog = rfd::AsyncMessageDialog::new()
+            .set_level(rfd::MessageLevel::Warning)
+            .set_title("Allow Smudge to connect?")
+            .set_description(format!(
+                "Smudge will be able to send requests to {} and read what they answer.",
+                list
+            ))
+            .set_buttons(rfd::MessageButtons::OkCancelCustom(
+                "Allow".to_string(),
+                "Don't Allow".to_string(),
+            ));
+        if let Some(window) = &window {
+            dialog = dialog.set_parent(window);
+        }
+        // Like dialogs::show: create the alert on the main thread, wait for
+        // it elsewhere
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.run_on_main_thread(move || {
+            let answer = dialog.show();
+            std::thread::spawn(move || {
+                let _ = tx.send(tauri::async_runtime::block_on(answer));
+            });
+        })
+        .map_err(|e| e.to_string())?;
+        let answer = rx
+            .await
+            .map_err(|_| "Dialog closed unexpectedly".to_string())?;
+        let allowed = matches!(answer, rfd::MessageDialogResult::Custom(ref label) if label == "Allow")
+            || answer == rfd::MessageDialogResult::Ok;
+        if !allowed {
+            log::info!(hosts:% = list; "allowing hosts declined");
+            return Ok(false);
+        }
+    }
+    client(app)?.set_allowed_hosts(hosts)?;
+    Ok(true)
+}
+
+pub fn client(app: &AppHandle) -> Result<tauri::State<'_, Http>, String> {
+    app.try_state::<Http>()
+        .ok_or_else(|| "The HTTP client isn't available".to_string())
+}
+
+/// Make `request` and read the whole response
+pub async fn request(app: &AppHandle, request: HttpRequest) -> Result<HttpResponse, String> {
+    let http = client(app)?;
+    let network = preferences::current(app).network.clone();
+    let url = http.check(&request.url, HTTP_SCHEMES)?;
+    let method = reqwest::Method::from_bytes(request.method.to_ascii_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid method {}", request.method))?;
+    let mut builder = http
+        .request_builder(method, url, &request.headers)?
+        .timeout(request.timeout_ms.map_or(
+            Duration::from_secs(network.request_timeout_secs),
+            Duration::from_millis,
+        ));
+    if let Some(body) = request.body {
+        builder = if request.body_base64 {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(body)
+                .map_err(|e| format!("Invalid base64 body: {}", e))?;
+            builder.body(bytes)
+        } else {
+            builder.body(body)
+        };
+    }
+
+    let mut response = builder.send().await.map_err(|e| e.to_string())?;
+    if let Err(e) = http.save_cookies() {
+        log::warn!(error:% = e; "cookies not saved");
+    }
+    let status = response.status().as_u16();
+    let final_url = response.url().to_string();
+    let mut headers: HashMap<String, String> = HashMap::new();
+    for (name, value) in response.headers() {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        headers
+            .entry(name.as_str().to_string())
+            .and_modify(|joined| {
+                joined.push_str(", ");
+                joined.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if body.len() + chunk.len() > network.max_response_mb as usize * 1024 * 1024 {
+            return Err("The response is too large; download it instead".to_string());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = match request.response_type {
+        ResponseType::Text => String::from_utf8_lossy(&body).into_owned(),
+        ResponseType::Base64 => base64::engine::general_purpose::STANDARD.encode(body),
+    };
+    Ok(HttpResponse {
+        status,
+        url: final_url,
+        headers,
+        body,
+    })
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// Download `request.url` to `request.path` as `job`, continuing what an
+/// earlier attempt left if the server still has the same file and supports
+/// ranges. Progress is in bytes. Cancelling keeps what was downloaded.
+pub async fn download(
+    app: &AppHandle,
+    job: &Job,
+    request: DownloadRequest,
+) -> Result<Download, String> {
+    let http = client(app)?;
+    let url = http.check(&request.url, HTTP_SCHEMES)?;
+    let path = PathBuf::from(&request.path);
+    let partial = partial_path(&path);
+    let validator: Option<String> = http
+        .database
+        .as_ref()
+        .and_then(|db| db.get(DOWNLOADS_NAMESPACE, &request.path).ok().flatten());
+    let mut offset = match &validator {
+        Some(_) => tokio::fs::metadata(&partial)
+            .await
+            .map_or(0, |metadata| metadata.len()),
+        None => 0,
+    };
+
+    let response = loop {
+        let mut builder =
+            http.request_builder(reqwest::Method::GET, url.clone(), &request.headers)?;
+        if let (Some(validator), true) = (&validator, offset > 0) {
+            builder = builder
+                .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+                .header(reqwest::header::IF_RANGE, validator);
+        }
+        let response = builder.send().await.map_err(|e| e.to_string())?;
+        // The part already holds the whole file, or something else
+        // entirely; start over
+        if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+            offset = 0;
+            continue;
+        }
+        break response.error_for_status().map_err(|e| e.to_string())?;
+    };
+    if let Err(e) = http.save_cookies() {
+        log::warn!(error:% = e; "cookies not saved");
+    }
+
+    let resumed = offset > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    if !resumed {
+        offset = 0;
+    }
+    if let Some(db) = &http.database {
+        let headers = response.headers();
+        let validator = headers
+            .get(reqwest::header::ETAG)
+            .or_else(|| headers.get(reqwest::header::LAST_MODIFIED))
+            .and_then(|value| value.to_str().ok());
+        let _ = match validator {
+            Some(validator) => db.put(DOWNLOADS_NAMESPACE, &request.path, validator),
+            None => db.delete(DOWNLOADS_NAMESPACE, &request.path),
+        };
+    }
+    if let Some(len) = response.content_length() {
+        job.set_total((offset + len) as usize);
+    }
+    job.set_completed(offset as usize);
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&partial)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+    let mut stream = response.bytes_stream();
+    let mut written = offset;
+    let mut reported = Instant::now();
+    while let Some(chunk) = stream.next().await {
+        if job.checkpoint().await.is_err() {
+            file.flush().await.map_err(|e| e.to_string())?;
+            return Ok(Download {
+                path: request.path,
+                bytes: written,
+                resumed,
+                complete: false,
+            });
+        }
+        let chunk = chunk.map_err(|e| e.to_string())?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+        written += chunk.len() as u64;
+        if reported.elapsed() >= PROGRESS_INTERVAL {
+            job.set_completed(written as usize);
+            reported = Instant::now();
+        }
+    }
+    file.flush().await.map_err(|e| e.to_string())?;
+    drop(file);
+
+    tokio::fs::rename(&partial, &path)
+        .await
+        .map_err(|e| format!("Failed to move the download to {}: {}", path.display(), e))?;
+    if let Some(db) = &http.database {
+        let _ = db.delete(DOWNLOADS_NAMESPACE, &request.path);
+    }
+    job.set_completed(written as usize);
+    Ok(Download {
+        path: request.path,
+        bytes: written,
+        resumed,
+        complete: true,
+    })
+}
+
+/// The system's proxy for each URL, from CFNetwork, which also runs PAC
+/// files and follows Network settings' per-host exceptions. PAC results are
+/// cached per host for a while, as running one means fetching it.
+#[cfg(target_os = "macos")]
+mod system_proxy {
+    use core_foundation::array::{CFArray, CFArrayRef};
+    use core_foundation::base::{kCFAllocatorDefault, CFIndex, CFType, TCFType};
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::error::CFErrorRef;
+    use core_foundation::number::CFNumber;
+    use core_foundation::runloop::{CFRunLoop, CFRunLoopSource, CFRunLoopSourceRef};
+    use core_foundation::string::{CFString, CFStringRef};
+    use core_foundation::url::{CFURLCreateWithString, CFURLRef, CFURL};
+    use std::collections::HashMap;
+    use std::ffi::c_void;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+    use tauri::Url;
+
+    type Proxies = CFArray<CFDictionary<CFString, CFType>>;
+    type PacCallback = extern "C" fn(client: *mut c_void, proxies: CFArrayRef, error: CFErrorRef);
+
+    /// How long a host's proxy is remembered
+    const CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+    /// How long a PAC file gets to answer before the connection goes direct
+    const PAC_TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[repr(C)]
+    struct StreamClientContext {
+        version: CFIndex,
+        info: *mut c_void,
+        retain: *const c_void,
+        release: *const c_void,
+        copy_description: *const c_void,
+    }
+
+    #[link(name = "CFNetwork", kind = "framework")]
+    extern "C" {
+        static kCFProxyTypeKey: CFStringRef;
+        static kCFProxyHostNameKey: CFStringRef;
+        static kCFProxyPortNumberKey: CFStringRef;
+        static kCFProxyAutoConfigurationURLKey: CFStringRef;
+        static kCFProxyAutoConfigurationJavaScriptKey: CFStringRef;
+        static kCFProxyTypeNone: CFStringRef;
+        static kCFProxyTypeHTTP: CFStringRef;
+        static kCFProxyTypeHTTPS: CFStringRef;
+        static kCFProxyTypeAutoConfigurationURL: CFStringRef;
+        static kCFProxyTypeAutoConfigurationJavaScript: CFStringRef;
+
+        fn CFNetworkCopySystemProxySettings() -> CFDictionaryRef;
+        fn CFNetworkCopyProxiesForURL(url: CFURLRef, proxy_settings: CFDictionaryRef)
+            -> CFArrayRef;
+        fn CFNetworkExecuteProxyAutoConfigurationURL(
+            proxy_auto_config_url: CFURLRef,
+            target_url: CFURLRef,
+            callback: PacCallback,
+            client_context: *mut StreamClientContext,
+        ) -> CFRunLoopSourceRef;
+        fn CFNetworkExecuteProxyAutoConfigurationScript(
+            proxy_auto_configuration_script: CFStringRef,
+            target_url: CFURLRef,
+            callback: PacCallback,
+            client_context: *mut StreamClientContext,
+        ) -> CFRunLoopSourceRef;
+    }
+
+    /// When the proxy for each scheme, host and port was looked up, and what
+    /// it was
+    type Cache = HashMap<String, (Instant, Option<String>)>;
+
+    static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+    /// The proxy to reach `url` through, `None` to connect directly. What
+    /// `reqwest::Proxy::custom` calls for each connection.
+    pub fn for_url(url: &Url) -> Option<String> {
+        let key = format!(
+            "{}://{}:{}",
+            url.scheme(),
+            url.host_str()?,
+            url.port_or_known_default()?
+        );
+        if let Some((at, proxy)) = CACHE
+            .lock()
+            .expect("proxy cache mutex")
+            .get_or_insert_with(HashMap::new)
+            .get(&key)
+        {
+            if at.elapsed() < CACHE_TTL {
+                return proxy.clone();
+            }
+        }
+        let proxy = resolve(url);
+        CACHE
+            .lock()
+            .expect("proxy cache mutex")
+            .get_or_insert_with(HashMap::new)
+            .insert(key, (Instant::now(), proxy.clone()));
+        proxy
+    }
+
+    fn key(key: CFStringRef) -> CFString {
+        unsafe { CFString::wrap_under_get_rule(key) }
+    }
+
+    fn value(proxy: &CFDictionary<CFString, CFType>, name: CFStringRef) -> Option<CFType> {
+        proxy.find(key(name)).map(|value| value.clone())
+    }
+
+    fn resolve(url: &Url) -> Option<String> {
+        let target = unsafe {
+            let string = CFString::new(url.as_str());
+            let target = CFURLCreateWithString(
+                kCFAllocatorDefault,
+                string.as_concrete_TypeRef(),
+                std::ptr::null(),
+            );
+            if target.is_null() {
+                return None;
+            }
+            CFURL::wrap_under_create_rule(target)
+        };
+        let proxies = unsafe {
+            let settings = CFNetworkCopySystemProxySettings();
+            if settings.is_null() {
+                return None;
+            }
+            let settings = CFDictionary::<CFString, CFType>::wrap_under_create_rule(settings);
+            let proxies = CFNetworkCopyProxiesForURL(
+                target.as_concrete_TypeRef(),
+                settings.as_concrete_TypeRef(),
+            );
+            if proxies.is_null() {
+                return None;
+            }
+            Proxies::wrap_under_create_rule(proxies)
+        };
+        first_usable(&target, &proxies, true)
+    }
+
+    /// The first proxy in `proxies` reqwest can use. SOCKS and FTP proxies
+    /// are skipped.
+    fn first_usable(target: &CFURL, proxies: &Proxies, run_pac: bool) -> Option<String> {
+        for proxy in proxies.iter() {
+            let kind = value(&proxy, unsafe { kCFProxyTypeKey })?.downcast::<CFString>()?;
+            if kind == key(unsafe { kCFProxyTypeNone }) {
+                return None;
+            }
+            if kind == key(unsafe { kCFProxyTypeHTTP }) || kind == key(unsafe { kCFProxyTypeHTTPS })
+            {
+                let host = value(&proxy, unsafe { kCFProxyHostNameKey })?.downcast::<CFString>()?;
+                let port = value(&proxy, unsafe { kCFProxyPortNumberKey })
+                    .and_then(|port| port.downcast::<CFNumber>())
+                    .and_then(|port| port.to_i64())
+                    .unwrap_or(8080);
+                return Some(format!("http://{}:{}", host, port));
+            }
+            let pac = kind == key(unsafe { kCFProxyTypeAutoConfigurationURL })
+                || kind == key(unsafe { kCFProxyTypeAutoConfigurationJavaScript });
+            if pac && run_pac {
+                if let Some(resolved) = execute_pac(&proxy, target) {
+                    return first_usable(target, &resolved, false);
+                }
+            }
+        }
+        None
+    }
+
+    extern "C" fn pac_finished(client: *mut c_void, proxies: CFArrayRef, error: CFErrorRef) {
+        let result = unsafe { &mut *(client as *mut Option<Option<Proxies>>) };
+        *result = Some(if error.is_null() && !proxies.is_null() {
+            Some(unsafe { Proxies::wrap_under_get_rule(proxies) })
+        } else {
+            None
+        });
+    }
+
+    /// Runs the PAC file or script `proxy` points at for `target`, on this
+    /// thread's run loop
+    fn execute_pac(proxy: &CFDictionary<CFString, CFType>, target: &CFURL) -> Option<Proxies> {
+        let result: *mut Option<Option<Proxies>> = Box::into_raw(Box::new(None));
+        let mut context = StreamClientContext {
+            version: 0,
+            info: result.cast(),
+            retain: std::ptr::null(),
+            release: std::ptr::null(),
+            copy_description: std::ptr::null(),
+        };
+        let source = if let Some(pac_url) = value(proxy, unsafe { kCFProxyAutoConfigurationURLKey })
+            .and_then(|url| url.downcast::<CFURL>())
+        {
+            unsafe {
+                CFNetworkExecuteProxyAutoConfigurationURL(
+                    pac_url.as_concrete_TypeRef(),
+                    target.as_concrete_TypeRef(),
+                    pac_finished,
+                    &mut context,
+                )
+            }
+        } else if let Some(script) = value(proxy, unsafe { kCFProxyAutoConfigurationJavaScriptKey })
+            .and_then(|script| script.downcast::<CFString>())
+        {
+            unsafe {
+                CFNetworkExecuteProxyAutoConfigurationScript(
+                    script.as_concrete_TypeRef(),
+                    target.as_concrete_TypeRef(),
+                    pac_finished,
+                    &mut context,
+                )
+            }
+        } else {
+            std::ptr::null_mut()
+        };
+        if source.is_null() {
+            drop(unsafe { Box::from_raw(result) });
+            return None;
+        }
+
+        let source = unsafe { CFRunLoopSource::wrap_under_create_rule(source) };
+        let mode = CFString::from_static_string("smudge.pac");
+        let run_loop = CFRunLoop::get_current();
+        run_loop.add_source(&source, mode.as_concrete_TypeRef());
+        let deadline = Instant::now() + PAC_TIMEOUT;
+        while unsafe { (*result).is_none() } {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                log::warn!("PAC file timed out; connecting directly");
+                break;
+            }
+            CFRunLoop::run_in_mode(mode.as_concrete_TypeRef(), left, true);
+        }
+        // Removed before the result is freed, so a late answer can't
+        // reach it
+        run_loop.remove_source(&source, mode.as_concrete_TypeRef());
+        unsafe {
+            core_foundation::runloop::CFRunLoopSourceInvalidate(source.as_concrete_TypeRef())
+        };
+        unsafe { Box::from_raw(result) }.flatten()
+    }
+}
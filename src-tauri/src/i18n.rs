@@ -0,0 +1,280 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter, Manager};
+use unic_langid::LanguageIdentifier;
+
+/// Sent to every window with a `LocaleInfo` when the language smudge uses
+/// changes, once the native menus are in the new one
+pub const EVENT: &str = "locale-changed";
+
+/// Has every key; used for whatever the chosen catalog lacks
+const FALLBACK: &str = "en-US";
+
+/// The catalogs bundled with smudge, by language tag
+const CATALOGS: &[(&str, &str)] = &[
+    ("en-US", include_str!("../locales/en-US/smudge.ftl")),
+    ("de", include_str!("../locales/de/smudge.ftl")),
+    ("fr", include_str!("../locales/fr/smudge.ftl")),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleInfo {
+    /// The catalog in use, one of `available`
+    pub locale: String,
+    /// The system's languages, most preferred first, as BCP 47 tags
+    pub system_languages: Vec<String>,
+    /// The language picked in smudge over the system's, if any
+    pub chosen: Option<String>,
+    pub available: Vec<String>,
+}
+
+/// smudge's translations, managed as Tauri state. The catalog is picked
+/// from the language chosen in smudge, if any, then the system's languages
+/// in order, and falls back to US English.
+pub struct I18n {
+    bundles: HashMap<&'static str, FluentBundle<FluentResource>>,
+    chosen: RwLock<Option<String>>,
+    info: RwLock<LocaleInfo>,
+}
+
+impl I18n {
+    pub fn new(chosen: Option<String>) -> Self {
+        let bundles = CATALOGS
+            .iter()
+            .filter_map(|(tag, source)| Some((*tag, bundle(tag, source)?)))
+            .collect();
+        let i18n = Self {
+            bundles,
+            chosen: RwLock::new(chosen),
+            info: RwLock::new(LocaleInfo {
+                locale: FALLBACK.to_string(),
+                system_languages: Vec::new(),
+                chosen: None,
+                available: CATALOGS.iter().map(|(tag, _)| tag.to_string()).collect(),
+            }),
+        };
+        i18n.refresh();
+        i18n
+    }
+
+    pub fn info(&self) -> LocaleInfo {
+        self.info.read().expect("locale read lock").clone()
+    }
+
+    /// Pick the catalog again. Returns whether it changed.
+    fn refresh(&self) -> bool {
+        let system_languages = system_languages();
+        let chosen = self.chosen.read().expect("chosen locale read lock").clone();
+        let preferred: Vec<String> = chosen
+            .iter()
+            .chain(system_languages.iter())
+            .cloned()
+            .collect();
+        let mut info = self.info.write().expect("locale write lock");
+        let previous = info.locale.clone();
+        info.locale = negotiate(&preferred).to_string();
+        info.system_languages = system_languages;
+        info.chosen = chosen;
+        if info.locale != previous {
+            log::info!(locale = info.locale.as_str(); "locale changed");
+        }
+        info.locale != previous
+    }
+
+    /// The message `key` in the current language, with `args` filled in.
+    /// Numbers are formatted for the language and pick plural forms.
+    pub fn translate(&self, key: &str, args: &HashMap<String, Value>) -> Result<String, String> {
+        let locale = self.info.read().expect("locale read lock").locale.clone();
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            let value = match value {
+                Value::Number(number) => FluentValue::from(number.as_f64().unwrap_or_default()),
+                Value::String(text) => FluentValue::from(text.clone()),
+                Value::Null => FluentValue::None,
+                other => FluentValue::from(other.to_string()),
+            };
+            fluent_args.set(name.clone(), value);
+        }
+        for tag in [locale.as_str(), FALLBACK] {
+            let Some(bundle) = self.bundles.get(tag) else {
+                continue;
+            };
+            let Some(pattern) = bundle.get_message(key).and_then(|message| message.value()) else {
+                continue;
+            };
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+            if !errors.is_empty() {
+                log::debug!(key = key, errors:? = errors; "translation formatted with errors");
+            }
+            return Ok(text.into_owned());
+        }
+        Err(format!("No message {}", key))
+    }
+}
+
+fn bundle(tag: &str, source: &str) -> Option<FluentBundle<FluentResource>> {
+    let language: LanguageIdentifier = tag.parse().ok()?;
+    let resource =
+        FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+            log::warn!(locale = tag, errors:? = errors; "catalog has syntax errors");
+            resource
+        });
+    let mut bundle = FluentBundle::new_concurrent(vec![language]);
+    // The marks isolating arguments' direction show as boxes in native menus
+    bundle.set_use_isolating(false);
+    if let Err(errors) = bundle.add_resource(resource) {
+        log::warn!(locale = tag, errors:? = errors; "catalog has duplicate messages");
+    }
+    Some(bundle)
+}
+
+/// The catalog for `languages`, most preferred first: the first that has one
+/// for its tag, or failing that for its language, so "de-AT" gets "de" and
+/// "en-GB" gets "en-US"
+fn negotiate(languages: &[String]) -> &'static str {
+    let available: Vec<(&'static str, LanguageIdentifier)> = CATALOGS
+        .iter()
+        .filter_map(|(tag, _)| Some((*tag, tag.parse().ok()?)))
+        .collect();
+    for language in languages {
+        let Ok(wanted) = language.parse::<LanguageIdentifier>() else {
+            continue;
+        };
+        if let Some((tag, _)) = available.iter().find(|(_, id)| *id == wanted) {
+            return tag;
+        }
+        if let Some((tag, _)) = available
+            .iter()
+            .find(|(_, id)| id.language == wanted.language)
+        {
+            return tag;
+        }
+    }
+    FALLBACK
+}
+
+/// The system's languages, most preferred first: the `AppleLanguages` order
+/// on macOS, the preferred UI languages on Windows, and `LANGUAGE`, then the
+/// first of `LC_ALL`, `LC_MESSAGES` and `LANG`, on Linux
+pub fn system_languages() -> Vec<String> {
+    #[cfg(target_os = "macos")]
+    {
+        tauri_runtime_wry::tao::platform::macos::preferred_languages()
+    }
+    #[cfg(windows)]
+    {
+        windows_languages().unwrap_or_default()
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        let mut languages: Vec<String> = std::env::var("LANGUAGE")
+            .unwrap_or_default()
+            .split(':')
+            .map(str::to_string)
+            .collect();
+        languages.extend(
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|value| !value.is_empty()),
+        );
+        // "de_DE.UTF-8@euro" is "de-DE"; "C" and "POSIX" name no language
+        let mut tags: Vec<String> = Vec::new();
+        for language in languages {
+            let tag = language
+                .split(['.', '@'])
+                .next()
+                .unwrap_or_default()
+                .replace('_', "-");
+            if !tag.is_empty() && tag != "C" && tag != "POSIX" && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+}
+
+#[cfg(windows)]
+fn windows_languages() -> Option<Vec<String>> {
+    use windows::core::PWSTR;
+    use windows::Win32::Globalization::{GetUserPreferredUILanguages, MUI_LANGUAGE_NAME};
+
+    let mut count = 0;
+    let mut length = 0;
+    unsafe { GetUserPreferredUILanguages(MUI_LANGUAGE_NAME, &mut count, None, &mut length) }
+        .ok()?;
+    let mut buffer = vec![0u16; length as usize];
+    unsafe {
+        GetUserPreferredUILanguages(
+            MUI_LANGUAGE_NAME,
+            &mut count,
+            Some(PWSTR(buffer.as_mut_ptr())),
+            &mut length,
+        )
+    }
+    .ok()?;
+    // Separated and ended by nulls, with an extra null at the end
+    Some(
+        buffer
+            .split(|&unit| unit == 0)
+            .filter(|language| !language.is_empty())
+            .map(String::from_utf16_lossy)
+            .collect(),
+    )
+}
+
+/// `key` in the current language for smudge's own UI, or the key itself if
+/// no catalog has it
+pub fn text(app: &AppHandle, key: &str) -> String {
+    text_with(app, key, &[])
+}
+
+/// `text` with string arguments
+pub fn text_with(app: &AppHandle, key: &str, args: &[(&str, &str)]) -> String {
+    let args = args
+        .iter()
+        .map(|(name, value)| (name.to_string(), Value::from(*value)))
+        .collect();
+    app.try_state::<I18n>()
+        .and_then(|i18n| i18n.translate(key, &args).ok())
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// The system's languages may have changed. If that changes the catalog,
+/// rebuild the native menus in it and tell the windows, so the frontend can
+/// re-render and set its own menus again.
+pub fn refresh(app: &AppHandle) {
+    let Some(i18n) = app.try_state::<I18n>() else {
+        return;
+    };
+    if i18n.refresh() {
+        changed(app, i18n.info());
+    }
+}
+
+/// Use `language` over the system's languages, or follow them again with
+/// `None`
+pub fn choose(app: &AppHandle, language: Option<String>) -> Result<LocaleInfo, String> {
+    if let Some(language) = &language {
+        language
+            .parse::<LanguageIdentifier>()
+            .map_err(|e| format!("{} isn't a language tag: {}", language, e))?;
+    }
+    let i18n = app.state::<I18n>();
+    *i18n.chosen.write().expect("chosen locale write lock") = language;
+    if i18n.refresh() {
+        changed(app, i18n.info());
+    }
+    Ok(i18n.info())
+}
+
+fn changed(app: &AppHandle, info: LocaleInfo) {
+    crate::app_menu::rebuild(app);
+    let _ = app.emit(EVENT, info);
+}
@@ -0,0 +1,301 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+use tauri::ipc::{InvokeBody, Request, Response};
+
+/// Payloads at least this large go over IPC as MessagePack instead of JSON.
+/// Below it JSON's overhead doesn't matter and it's easier to debug.
+pub const BINARY_THRESHOLD: usize = 32 * 1024;
+
+/// Commands that take MessagePack arguments and can reply in it. Each reads
+/// its arguments with [`args`] and replies with [`reply`].
+pub const BINARY_COMMANDS: &[&str] = &["read_note", "save_note"];
+
+/// Sent with a MessagePack request body
+const CODEC_HEADER: &str = "x-smudge-codec";
+/// Sent by callers that can decode a MessagePack reply
+const ACCEPT_HEADER: &str = "x-smudge-accept";
+const MSGPACK: &str = "msgpack";
+/// Deepest arrays and maps are nested, as serde_json limits JSON, so a
+/// payload can't recurse until the stack overflows
+const MAX_DEPTH: usize = 128;
+
+/// What the frontend needs to decide when to send MessagePack
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryCodec {
+    pub threshold: usize,
+    pub commands: &'static [&'static str],
+}
+
+pub fn negotiate() -> BinaryCodec {
+    BinaryCodec {
+        threshold: BINARY_THRESHOLD,
+        commands: BINARY_COMMANDS,
+    }
+}
+
+fn header_is(request: &Request<'_>, name: &str, value: &str) -> bool {
+    request
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case(value))
+}
+
+/// Whether the caller can take a MessagePack reply
+pub fn accepts_binary(request: &Request<'_>) -> bool {
+    header_is(request, ACCEPT_HEADER, MSGPACK)
+}
+
+/// The command's arguments, from a JSON body or a MessagePack one
+pub fn args<T: DeserializeOwned>(request: &Request<'_>) -> Result<T, String> {
+    match request.body() {
+        InvokeBody::Json(value) => T::deserialize(value).map_err(|e| e.to_string()),
+        InvokeBody::Raw(bytes) => {
+            if !header_is(request, CODEC_HEADER, MSGPACK) {
+                return Err("Binary arguments without a codec header".to_string());
+            }
+            let value = decode(bytes)?;
+            T::deserialize(value).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// `value` as MessagePack when the caller accepts it and it's large enough,
+/// JSON otherwise
+pub fn reply<T: Serialize>(value: &T, binary: bool) -> Result<Response, String> {
+    if binary {
+        let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+        let mut bytes = Vec::new();
+        encode(&value, &mut bytes);
+        if bytes.len() >= BINARY_THRESHOLD {
+            return Ok(Response::new(bytes));
+        }
+        return serde_json::to_string(&value)
+            .map(Response::new)
+            .map_err(|e| e.to_string());
+    }
+    serde_json::to_string(value)
+        .map(Response::new)
+        .map_err(|e| e.to_string())
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize, fix: (u8, usize), tags: [u8; 3]) {
+    let (fix_tag, fix_max) = fix;
+    if len <= fix_max {
+        out.push(fix_tag | len as u8);
+    } else if len <= u8::MAX as usize && tags[0] != 0 {
+        out.extend([tags[0], len as u8]);
+    } else if len <= u16::MAX as usize {
+        out.push(tags[1]);
+        out.extend((len as u16).to_be_bytes());
+    } else {
+        out.push(tags[2]);
+        out.extend((len as u32).to_be_bytes());
+    }
+}
+
+fn encode_number(number: &Number, out: &mut Vec<u8>) {
+    if let Some(n) = number.as_u64() {
+        match n {
+            0..=0x7f => out.push(n as u8),
+            0x80..=0xff => out.extend([0xcc, n as u8]),
+            0x100..=0xffff => {
+                out.push(0xcd);
+                out.extend((n as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                out.push(0xce);
+                out.extend((n as u32).to_be_bytes());
+            }
+            _ => {
+                out.push(0xcf);
+                out.extend(n.to_be_bytes());
+            }
+        }
+    } else if let Some(n) = number.as_i64() {
+        // Only negative numbers get here
+        if n >= -32 {
+            out.push(n as i8 as u8);
+        } else if n >= i8::MIN as i64 {
+            out.extend([0xd0, n as i8 as u8]);
+        } else if n >= i16::MIN as i64 {
+            out.push(0xd1);
+            out.extend((n as i16).to_be_bytes());
+        } else if n >= i32::MIN as i64 {
+            out.push(0xd2);
+            out.extend((n as i32).to_be_bytes());
+        } else {
+            out.push(0xd3);
+            out.extend(n.to_be_bytes());
+        }
+    } else {
+        out.push(0xcb);
+        out.extend(number.as_f64().unwrap_or(0.0).to_be_bytes());
+    }
+}
+
+/// MessagePack for a JSON value. Maps keep their keys as strings.
+fn encode(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Null => out.push(0xc0),
+        Value::Bool(b) => out.push(if *b { 0xc3 } else { 0xc2 }),
+        Value::Number(n) => encode_number(n, out),
+        Value::String(s) => {
+            write_len(out, s.len(), (0xa0, 31), [0xd9, 0xda, 0xdb]);
+            out.extend(s.as_bytes());
+        }
+        Value::Array(items) => {
+            write_len(out, items.len(), (0x90, 15), [0, 0xdc, 0xdd]);
+            for item in items {
+                encode(item, out);
+            }
+        }
+        Value::Object(map) => {
+            write_len(out, map.len(), (0x80, 15), [0, 0xde, 0xdf]);
+            for (key, item) in map {
+                write_len(out, key.len(), (0xa0, 31), [0xd9, 0xda, 0xdb]);
+                out.extend(key.as_bytes());
+                encode(item, out);
+            }
+        }
+    }
+}
+
+/// JSON has no NaN or infinity; they decode as null the way `JSON.stringify`
+/// sends them
+fn float(value: f64) -> Value {
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Arrays and maps the reader is inside
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or("Truncated MessagePack payload")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn len(&mut self, width: usize) -> Result<usize, String> {
+        Ok(match width {
+            1 => self.array::<1>()?[0] as usize,
+            2 => u16::from_be_bytes(self.array()?) as usize,
+            _ => u32::from_be_bytes(self.array()?) as usize,
+        })
+    }
+
+    fn string(&mut self, len: usize) -> Result<String, String> {
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "Invalid UTF-8 in MessagePack string".into())
+    }
+
+    /// `read` one level deeper, if it isn't too deep
+    fn nested(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<Value, String>,
+    ) -> Result<Value, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err("MessagePack payload nested too deeply".to_string());
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn items(&mut self, len: usize) -> Result<Value, String> {
+        self.nested(|reader| {
+            (0..len)
+                .map(|_| reader.value())
+                .collect::<Result<_, _>>()
+                .map(Value::Array)
+        })
+    }
+
+    fn map(&mut self, len: usize) -> Result<Value, String> {
+        self.nested(|reader| {
+            let mut map = Map::new();
+            for _ in 0..len {
+                let key = match reader.value()? {
+                    Value::String(key) => key,
+                    other => other.to_string(),
+                };
+                map.insert(key, reader.value()?);
+            }
+            Ok(Value::Object(map))
+        })
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        let tag = self.array::<1>()?[0];
+        Ok(match tag {
+            0x00..=0x7f => Value::from(tag),
+            0x80..=0x8f => self.map((tag & 0x0f) as usize)?,
+            0x90..=0x9f => self.items((tag & 0x0f) as usize)?,
+            0xa0..=0xbf => Value::String(self.string((tag & 0x1f) as usize)?),
+            0xc0 => Value::Null,
+            0xc2 => Value::Bool(false),
+            0xc3 => Value::Bool(true),
+            // Binary comes back as a byte array, the way JSON would carry it
+            0xc4..=0xc6 => {
+                let len = self.len(1 << (tag - 0xc4))?;
+                Value::from(self.take(len)?.to_vec())
+            }
+            0xca => float(f32::from_be_bytes(self.array()?) as f64),
+            0xcb => float(f64::from_be_bytes(self.array()?)),
+            0xcc => Value::from(self.array::<1>()?[0]),
+            0xcd => Value::from(u16::from_be_bytes(self.array()?)),
+            0xce => Value::from(u32::from_be_bytes(self.array()?)),
+            0xcf => Value::from(u64::from_be_bytes(self.array()?)),
+            0xd0 => Value::from(i8::from_be_bytes(self.array()?)),
+            0xd1 => Value::from(i16::from_be_bytes(self.array()?)),
+            0xd2 => Value::from(i32::from_be_bytes(self.array()?)),
+            0xd3 => Value::from(i64::from_be_bytes(self.array()?)),
+            0xd9..=0xdb => {
+                let len = self.len(1 << (tag - 0xd9))?;
+                Value::String(self.string(len)?)
+            }
+            0xdc | 0xdd => {
+                let len = self.len(2 << (tag - 0xdc))?;
+                self.items(len)?
+            }
+            0xde | 0xdf => {
+                let len = self.len(2 << (tag - 0xde))?;
+                self.map(len)?
+            }
+            0xe0..=0xff => Value::from(tag as i8),
+            _ => return Err(format!("Unsupported MessagePack type 0x{:02x}", tag)),
+        })
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<Value, String> {
+    let mut reader = Reader {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+    let value = reader.value()?;
+    if reader.pos != bytes.len() {
+        return Err("Trailing bytes after MessagePack payload".to_string());
+    }
+    Ok(value)
+}
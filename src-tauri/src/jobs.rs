@@ -0,0 +1,358 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+
+use crate::{diagnostics, executor, fs_scope, logging, profiling, telemetry};
+
+/// Lifecycle of a job, as reported to the frontend
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JobState {
+    Running,
+    Paused,
+    Cancelled,
+    Completed,
+    Failed,
+}
+
+/// Progress payload emitted on the job's `job://<id>` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobProgress {
+    pub id: String,
+    pub name: String,
+    pub state: JobState,
+    pub completed: usize,
+    pub total: usize,
+    pub message: Option<String>,
+    /// What a completed job returned, on its last event
+    pub result: Option<Value>,
+    /// Why a failed job failed, on its last event
+    pub error: Option<String>,
+}
+
+/// Returned by [`Job::checkpoint`] once the job has been cancelled
+#[derive(Debug, Clone, Copy)]
+pub struct Cancelled;
+
+/// A running job's control surface. Workers call `checkpoint` between units
+/// of work; pause/resume/cancel take effect there.
+pub struct Job {
+    pub id: String,
+    pub name: String,
+    control: watch::Sender<JobState>,
+    progress: Mutex<(usize, usize)>,
+    app: AppHandle,
+}
+
+impl Job {
+    pub fn state(&self) -> JobState {
+        *self.control.borrow()
+    }
+
+    /// Wait out a pause, then return `Err(Cancelled)` if the job was cancelled
+    pub async fn checkpoint(&self) -> Result<(), Cancelled> {
+        let mut rx = self.control.subscribe();
+        loop {
+            match *rx.borrow_and_update() {
+                JobState::Cancelled => return Err(Cancelled),
+                JobState::Paused => {}
+                _ => return Ok(()),
+            }
+            if rx.changed().await.is_err() {
+                return Err(Cancelled);
+            }
+        }
+    }
+
+    /// `checkpoint` for work running on a worker thread rather than a task
+    pub fn checkpoint_blocking(&self) -> Result<(), Cancelled> {
+        tauri::async_runtime::block_on(self.checkpoint())
+    }
+
+    pub fn set_total(&self, total: usize) {
+        self.progress.lock().expect("job progress mutex").1 = total;
+        self.emit(None);
+    }
+
+    /// Report progress measured in something other than units, like bytes
+    pub fn set_completed(&self, completed: usize) {
+        self.progress.lock().expect("job progress mutex").0 = completed;
+        self.emit(None);
+    }
+
+    /// Count one finished unit of work and report progress
+    pub fn advance(&self, message: Option<String>) {
+        self.progress.lock().expect("job progress mutex").0 += 1;
+        self.emit(message);
+    }
+
+    fn change_state(&self, state: JobState) -> bool {
+        self.control.send_if_modified(|current| {
+            // Finished jobs can't be resumed or cancelled
+            if matches!(
+                *current,
+                JobState::Cancelled | JobState::Completed | JobState::Failed
+            ) || *current == state
+            {
+                return false;
+            }
+            *current = state;
+            true
+        })
+    }
+
+    fn set_state(&self, state: JobState) -> bool {
+        let changed = self.change_state(state);
+        if changed {
+            self.emit(None);
+        }
+        changed
+    }
+
+    /// Complete or fail the job with `outcome`, unless it was cancelled, and
+    /// send its last event
+    fn end(&self, outcome: Result<Value, String>) {
+        let mut progress = self.progress(None);
+        match outcome {
+            Ok(result) if self.change_state(JobState::Completed) => {
+                progress.result = Some(result);
+            }
+            Err(error) if self.change_state(JobState::Failed) => progress.error = Some(error),
+            _ => {}
+        }
+        progress.state = self.state();
+        let _ = self.app.emit(&format!("job://{}", self.id), progress);
+    }
+
+    pub fn progress(&self, message: Option<String>) -> JobProgress {
+        let (completed, total) = *self.progress.lock().expect("job progress mutex");
+        JobProgress {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            state: self.state(),
+            completed,
+            total,
+            message,
+            result: None,
+            error: None,
+        }
+    }
+
+    fn emit(&self, message: Option<String>) {
+        let _ = self
+            .app
+            .emit(&format!("job://{}", self.id), self.progress(message));
+    }
+}
+
+/// All jobs that haven't finished yet, managed as Tauri state
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, Arc<Job>>>,
+    next_id: AtomicU64,
+}
+
+impl JobRegistry {
+    /// Register a job. The frontend may pick the ID so it can control the
+    /// job before the command that runs it returns.
+    pub fn start(&self, app: &AppHandle, name: &str, id: Option<String>) -> Result<Arc<Job>, String> {
+        let id = id.unwrap_or_else(|| {
+            format!("{}-{}", name, self.next_id.fetch_add(1, Ordering::Relaxed))
+        });
+        let mut jobs = self.jobs.lock().expect("jobs mutex");
+        if jobs.contains_key(&id) {
+            return Err(format!("Job {} is already running", id));
+        }
+        let (control, _) = watch::channel(JobState::Running);
+        let job = Arc::new(Job {
+            id: id.clone(),
+            name: name.to_string(),
+            control,
+            progress: Mutex::new((0, 0)),
+            app: app.clone(),
+        });
+        jobs.insert(id, Arc::clone(&job));
+        Ok(job)
+    }
+
+    /// Mark the job completed (unless it was cancelled) and forget it
+    pub fn finish(&self, job: &Job) {
+        job.set_state(JobState::Completed);
+        self.jobs.lock().expect("jobs mutex").remove(&job.id);
+    }
+
+    /// Run `work` for `job` on the executor's background queue, so it never
+    /// holds up the invoke handler, then end the job with what it returned
+    /// and forget it. Its last `job://<id>` event carries the result or the
+    /// error.
+    pub async fn run<T, F>(&self, app: &AppHandle, job: Arc<Job>, work: F) -> Result<T, String>
+    where
+        T: Serialize + Send + 'static,
+        F: FnOnce(&Job) -> Result<T, String> + Send + 'static,
+    {
+        let worker = Arc::clone(&job);
+        let started = std::time::Instant::now();
+        let outcome = app
+            .state::<executor::Executor>()
+            .run(executor::Priority::Background, move || {
+                let _interval = profiling::interval(profiling::Category::Job, &worker.name);
+                work(&worker)
+            })
+            .await
+            .and_then(|outcome| outcome);
+        job.end(match &outcome {
+            Ok(result) => serde_json::to_value(result).map_err(|e| e.to_string()),
+            Err(e) => Err(e.clone()),
+        });
+        self.jobs.lock().expect("jobs mutex").remove(&job.id);
+        let ended = match job.state() {
+            JobState::Cancelled => "cancelled",
+            JobState::Failed => "failed",
+            _ => "completed",
+        };
+        telemetry::record(app, &format!("job.{}", job.name), started.elapsed());
+        telemetry::count(app, &format!("job.{}.{}", job.name, ended));
+        outcome
+    }
+
+    fn get(&self, id: &str) -> Result<Arc<Job>, String> {
+        self.jobs
+            .lock()
+            .expect("jobs mutex")
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("No running job {}", id))
+    }
+
+    pub fn pause(&self, id: &str) -> Result<(), String> {
+        self.get(id)?.set_state(JobState::Paused);
+        Ok(())
+    }
+
+    pub fn resume(&self, id: &str) -> Result<(), String> {
+        self.get(id)?.set_state(JobState::Running);
+        Ok(())
+    }
+
+    pub fn cancel(&self, id: &str) -> Result<(), String> {
+        self.get(id)?.set_state(JobState::Cancelled);
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<JobProgress> {
+        self.jobs
+            .lock()
+            .expect("jobs mutex")
+            .values()
+            .map(|job| job.progress(None))
+            .collect()
+    }
+}
+
+/// A job the frontend can submit by name. `run` gets the arguments it was
+/// submitted with and runs on a worker thread.
+struct Kind {
+    name: &'static str,
+    run: fn(&AppHandle, &Job, Value) -> Result<Value, String>,
+}
+
+const KINDS: &[Kind] = &[
+    Kind {
+        name: "rebuildSearchIndex",
+        run: rebuild_search_index,
+    },
+    Kind {
+        name: "exportBackup",
+        run: export_backup,
+    },
+    Kind {
+        name: "exportLogs",
+        run: export_logs,
+    },
+    Kind {
+        name: "diagnosticsBundle",
+        run: diagnostics_bundle,
+    },
+];
+
+/// Start the job named `name` in the background and return its ID without
+/// waiting for it
+pub fn submit(
+    app: &AppHandle,
+    name: &str,
+    args: Value,
+    id: Option<String>,
+) -> Result<String, String> {
+    let kind = KINDS
+        .iter()
+        .find(|kind| kind.name == name)
+        .ok_or_else(|| format!("No job named {}", name))?;
+    let job = app.state::<JobRegistry>().start(app, name, id)?;
+    let id = job.id.clone();
+    let run = kind.run;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let handle = app.clone();
+        let jobs = app.state::<JobRegistry>();
+        if let Err(e) = jobs
+            .run(&app, job, move |job| run(&handle, job, args))
+            .await
+        {
+            log::warn!(error:% = e; "job failed");
+        }
+    });
+    Ok(id)
+}
+
+/// Arguments of jobs that write one file
+#[derive(Deserialize)]
+struct PathArgs {
+    path: String,
+}
+
+fn path_args(args: Value) -> Result<String, String> {
+    serde_json::from_value::<PathArgs>(args)
+        .map(|args| args.path)
+        .map_err(|e| format!("Invalid job arguments: {}", e))
+}
+
+/// Runs `step` as the job's only unit of work
+fn single_step<T: Serialize>(
+    job: &Job,
+    step: impl FnOnce() -> Result<T, String>,
+) -> Result<Value, String> {
+    job.set_total(1);
+    job.checkpoint_blocking()
+        .map_err(|Cancelled| "Cancelled".to_string())?;
+    let result = step()?;
+    job.advance(None);
+    serde_json::to_value(result).map_err(|e| e.to_string())
+}
+
+fn rebuild_search_index(app: &AppHandle, job: &Job, _args: Value) -> Result<Value, String> {
+    single_step(job, || crate::rebuild_search_index_now(app))
+}
+
+fn export_backup(app: &AppHandle, job: &Job, args: Value) -> Result<Value, String> {
+    let path = path_args(args)?;
+    fs_scope::check(app, &path, fs_scope::Access::ReadWrite)?;
+    single_step(job, || crate::database(app)?.back_up(Path::new(&path)))
+}
+
+fn export_logs(app: &AppHandle, job: &Job, args: Value) -> Result<Value, String> {
+    let path = path_args(args)?;
+    fs_scope::check(app, &path, fs_scope::Access::ReadWrite)?;
+    single_step(job, || logging::export(Path::new(&path)))
+}
+
+fn diagnostics_bundle(app: &AppHandle, job: &Job, _args: Value) -> Result<Value, String> {
+    single_step(job, || {
+        diagnostics::create_bundle(app).map(|bundle| bundle.to_string_lossy().into_owned())
+    })
+}
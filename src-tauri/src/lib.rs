@@ -4,19 +4,140 @@ use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use tantivy::collector::TopDocs;
 use tantivy::query::QueryParser;
 use tantivy::schema::*;
 use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy};
-use tauri::{AppHandle, Emitter, Manager, RunEvent, State};
+use tauri::webview::PageLoadEvent;
+use tauri::RunEvent;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 use tokio::fs;
 
+mod accessibility;
+mod activation;
+mod app_icon;
+mod app_menu;
+mod appearance;
+mod apple_events;
+mod archive;
+mod asset_protocol;
+mod audio;
+mod auth;
+mod batch;
+mod capture;
+mod cli;
+mod clipboard;
+mod connectivity;
+mod context_menu;
+mod converters;
+mod crash;
+mod db;
+mod db_encryption;
+mod credentials;
+mod deep_link;
+mod diagnostics;
+mod dialogs;
+mod directory_watch;
+mod executor;
+mod extension_server;
+mod file_associations;
+mod file_metadata;
+mod find;
+mod frontend_assets;
+mod fs_scope;
 mod git;
+mod global_shortcuts;
+mod handoff;
+mod http;
+mod i18n;
+mod ipc_codec;
+mod jobs;
+mod logging;
+mod login_item;
+mod main_window;
+mod materials;
+mod menus;
+mod mini_window;
+mod monitors;
+mod native_events;
+mod notifications;
+mod occlusion;
+mod pdf;
+mod placement;
+mod permissions;
+mod plugins;
+mod power;
+mod preferences;
+mod pressure;
+mod presentation;
+mod print;
+mod profiling;
+mod projects;
+mod quarantine;
+mod quick_look;
+mod quit;
+mod recent_documents;
+mod remote_control;
+mod remote_sync;
+mod sandbox;
+mod scheduler;
+mod search;
+mod services_menu;
+mod share;
+mod shortcuts;
+mod sidecar;
+mod single_instance;
+mod snapshot;
+mod spaces;
+mod spelling;
+mod spotlight;
+mod startup;
+mod state_restoration;
+mod storage;
+mod streams;
+mod telemetry;
+mod templates;
+mod text_input;
+mod thumbnails;
+mod titlebar;
+mod trash;
+mod tray;
+mod undo;
+mod updates;
+mod url_routing;
+mod versions;
+mod watch_folders;
+mod websocket;
+mod webview_data;
+mod webview_pool;
+mod window_frames;
+// Tabs are NSWindow tabs, so the commands fail elsewhere
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+mod window_tabs;
+mod windows;
+// The Dock menu's items are only built on macOS
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+mod dock_menu;
+// https links are only delivered by the OS on Apple platforms
+#[cfg_attr(not(any(target_os = "macos", target_os = "ios")), allow(dead_code))]
+mod universal_links;
+// Dragging out goes through AppKit or OLE, so the command fails elsewhere
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+mod drag;
+// Only AppKit drops promise files rather than naming them
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+mod drop_promises;
+// Trackpad gestures only come from AppKit
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+mod gestures;
+// Only MacBook Pros have a Touch Bar
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+mod touch_bar;
 
 // State for tracking files opened via OS file association (double-click .md)
 #[derive(Default)]
@@ -26,32 +147,34 @@ pub struct OpenedFiles(Mutex<Vec<PathBuf>>);
 #[derive(Default)]
 pub struct FrontendReady(Mutex<bool>);
 
-fn debug_open_log(message: &str) {
-    let mut log_path = std::env::var("HOME")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| PathBuf::from("/tmp"))
-        .join("Library")
-        .join("Application Support")
-        .join("com.smudge")
-        .join("open-debug.log");
-
-    if let Some(parent) = log_path.parent() {
-        let _ = std::fs::create_dir_all(parent);
-    } else {
-        log_path = PathBuf::from("/tmp/smudge-open-debug.log");
+// Whether the app has a Dock icon (macOS). Hidden, it runs as an accessory app
+pub struct DockVisible(AtomicBool);
+
+impl Default for DockVisible {
+    fn default() -> Self {
+        Self(AtomicBool::new(true))
     }
+}
 
-    let mut file = match std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_path)
-    {
-        Ok(file) => file,
-        Err(_) => return,
-    };
-    let _ = writeln!(file, "{}", message);
+// Deep links resolved to app routes, buffered until the frontend picks them up
+#[derive(Default)]
+pub struct PendingDeepLinks(Mutex<Vec<universal_links::DeepLinkRoute>>);
+
+// Watchers for the user's import folders, restarted when the folder list changes
+#[derive(Default)]
+pub struct WatchFolderState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    // Last event time per file, so each file is imported once it settles
+    pending: Arc<Mutex<HashMap<PathBuf, Instant>>>,
 }
 
+// How long a watched file must go unchanged before it's imported
+const WATCH_FOLDER_SETTLE: Duration = Duration::from_secs(1);
+
+// Handoff continued from another device, buffered until the frontend picks it up
+#[derive(Default)]
+pub struct PendingHandoff(Mutex<Option<handoff::HandoffDocument>>);
+
 fn is_markdown_file(path: &Path) -> bool {
     path.extension()
         .and_then(|ext| ext.to_str())
@@ -63,7 +186,7 @@ fn is_markdown_file(path: &Path) -> bool {
 fn collect_startup_opened_files_from_args() -> Vec<PathBuf> {
     let mut paths = Vec::new();
     let args: Vec<String> = std::env::args().collect();
-    debug_open_log(&format!("[startup] args={:?}", args));
+    log::debug!(args:? = args; "launch arguments");
     for arg in std::env::args_os().skip(1) {
         let parsed_path = if let Some(s) = arg.to_str() {
             // Finder often adds this process serial number arg.
@@ -91,14 +214,9 @@ fn collect_startup_opened_files_from_args() -> Vec<PathBuf> {
             continue;
         }
 
-        #[cfg(target_os = "macos")]
-        if let Some(p) = path.to_str() {
-            remove_quarantine(p);
-        }
-
         paths.push(path);
     }
-    debug_open_log(&format!("[startup] collected_paths={:?}", paths));
+    log::debug!(paths:? = paths; "files opened at launch");
     paths
 }
 
@@ -109,6 +227,9 @@ pub struct NoteMetadata {
     pub title: String,
     pub preview: String,
     pub modified: i64,
+    // Content is in the cloud, not on this device
+    #[serde(rename = "isPlaceholder", default)]
+    pub is_placeholder: bool,
 }
 
 // Full note content
@@ -165,10 +286,42 @@ pub struct EditorFontSettings {
     pub line_height: Option<f32>,         // default 1.6
 }
 
-// App config (stored in app data directory - just the notes folder path)
+// App config (stored in app data directory - notes folder path and window chrome)
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppConfig {
     pub notes_folder: Option<String>,
+    #[serde(default)]
+    pub titlebar: titlebar::TitlebarMode,
+    #[serde(default)]
+    pub mini_window_corner: mini_window::Corner,
+    #[serde(default)]
+    pub placement_policy: placement::PlacementPolicy,
+    /// Default app each claimed file type had before smudge took it over
+    #[serde(default)]
+    pub previous_handlers: HashMap<String, String>,
+    #[serde(default)]
+    pub quarantine_policy: quarantine::QuarantinePolicy,
+    /// Warm mini windows kept ready; None for the default
+    #[serde(default)]
+    pub webview_pool_size: Option<usize>,
+    /// Reopen windows where they were and with the note they showed
+    #[serde(default)]
+    pub restore_windows: bool,
+    /// Accelerator that summons the main window for a new note from anywhere
+    #[serde(default)]
+    pub quick_capture_shortcut: Option<String>,
+    /// Icon in the menu bar (macOS) or notification area
+    #[serde(default)]
+    pub tray_icon: bool,
+    /// Pause animation while every window is hidden behind others (macOS)
+    #[serde(default)]
+    pub render_throttling: bool,
+    /// Take clips from the browser extension over loopback
+    #[serde(default)]
+    pub extension_server: bool,
+    /// Language picked over the system's, as a BCP 47 tag
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 // Per-folder settings (stored in .smudge/settings.json within notes folder)
@@ -181,6 +334,10 @@ pub struct Settings {
     pub git_enabled: Option<bool>,
     #[serde(rename = "pinnedNoteIds")]
     pub pinned_note_ids: Option<Vec<String>>,
+    #[serde(rename = "handoffEnabled")]
+    pub handoff_enabled: Option<bool>,
+    #[serde(rename = "watchFolders")]
+    pub watch_folders: Option<Vec<watch_folders::WatchFolder>>,
 }
 
 // Search result
@@ -334,14 +491,18 @@ impl SearchIndex {
         Ok(results)
     }
 
-    fn rebuild_index(&self, notes_folder: &PathBuf) -> Result<()> {
+    fn rebuild_index(&self, notes_folder: &PathBuf, storage: &dyn storage::StorageProvider) -> Result<()> {
         let mut writer = self.writer.lock().expect("search writer mutex");
         writer.delete_all_documents()?;
 
         if notes_folder.exists() {
             for entry in std::fs::read_dir(notes_folder)?.flatten() {
                 let file_path = entry.path();
-                if file_path.extension().is_some_and(|ext| ext == "md") {
+                // Reading a placeholder would download it; it gets indexed by
+                // the file watcher once its content arrives.
+                if file_path.extension().is_some_and(|ext| ext == "md")
+                    && !storage.is_placeholder(&file_path)
+                {
                     if let Ok(content) = std::fs::read_to_string(&file_path) {
                         let metadata = entry.metadata()?;
                         let modified = metadata
@@ -382,6 +543,8 @@ pub struct AppState {
     pub file_watcher: Mutex<Option<FileWatcherState>>,
     pub search_index: Mutex<Option<SearchIndex>>,
     pub debounce_map: Arc<Mutex<HashMap<PathBuf, Instant>>>,
+    pub storage: RwLock<Arc<dyn storage::StorageProvider>>, // cloud provider backing notes_folder
+    pub spotlight: spotlight::NoteIndex,                     // notes listed in Spotlight
 }
 
 impl Default for AppState {
@@ -393,10 +556,18 @@ impl Default for AppState {
             file_watcher: Mutex::new(None),
             search_index: Mutex::new(None),
             debounce_map: Arc::new(Mutex::new(HashMap::new())),
+            storage: RwLock::new(Arc::new(storage::Local)),
+            spotlight: spotlight::NoteIndex::default(),
         }
     }
 }
 
+impl AppState {
+    fn storage(&self) -> Arc<dyn storage::StorageProvider> {
+        Arc::clone(&self.storage.read().expect("storage read lock"))
+    }
+}
+
 // Utility: Sanitize filename from title
 fn sanitize_filename(title: &str) -> String {
     let sanitized: String = title
@@ -560,7 +731,7 @@ fn get_app_config_path(app: &AppHandle) -> Result<PathBuf> {
 
 // Get per-folder settings file path (in .smudge/ within notes folder)
 fn get_settings_path(notes_folder: &str) -> PathBuf {
-    let smudge_dir = PathBuf::from(notes_folder).join(".smudge");
+    let smudge_dir = PathBuf::from(notes_folder).join(projects::DATA_DIR);
     std::fs::create_dir_all(&smudge_dir).ok();
     smudge_dir.join("settings.json")
 }
@@ -574,13 +745,15 @@ fn get_search_index_path(app: &AppHandle) -> Result<PathBuf> {
 
 // Load app config from disk (notes folder path)
 fn load_app_config(app: &AppHandle) -> AppConfig {
-    let path = match get_app_config_path(app) {
-        Ok(p) => p,
-        Err(_) => return AppConfig::default(),
-    };
+    match get_app_config_path(app) {
+        Ok(path) => read_app_config(&path),
+        Err(_) => AppConfig::default(),
+    }
+}
 
+fn read_app_config(path: &Path) -> AppConfig {
     if path.exists() {
-        std::fs::read_to_string(&path)
+        std::fs::read_to_string(path)
             .ok()
             .and_then(|content| serde_json::from_str(&content).ok())
             .unwrap_or_default()
@@ -638,9 +811,30 @@ fn get_notes_folder(state: State<AppState>) -> Option<String> {
         .clone()
 }
 
+// Change the notes folder in place: the windows showing the active project
+// show this folder instead. It must have been picked in a folder dialog.
 #[tauri::command]
 fn set_notes_folder(app: AppHandle, path: String, state: State<AppState>) -> Result<(), String> {
-    let path_buf = PathBuf::from(&path);
+    let path = app
+        .state::<fs_scope::FsScope>()
+        .picked_folder(Path::new(&path))?
+        .to_string_lossy()
+        .into_owned();
+    let previous = state
+        .app_config
+        .read()
+        .expect("app_config read lock")
+        .notes_folder
+        .clone();
+    activate_notes_folder(&app, &path)?;
+    projects::folder_chosen(&app, previous.as_deref().map(Path::new), Path::new(&path));
+    Ok(())
+}
+
+// Serve the notes in `path`, with its settings, storage and index
+fn activate_notes_folder(app: &AppHandle, path: &str) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let path_buf = PathBuf::from(path);
 
     // Verify it's a valid directory
     if !path_buf.exists() {
@@ -652,34 +846,47 @@ fn set_notes_folder(app: AppHandle, path: String, state: State<AppState>) -> Res
     std::fs::create_dir_all(&assets).map_err(|e| e.to_string())?;
 
     // Create .smudge config folder
-    let smudge_dir = path_buf.join(".smudge");
+    let smudge_dir = path_buf.join(projects::DATA_DIR);
     std::fs::create_dir_all(&smudge_dir).map_err(|e| e.to_string())?;
 
     // Load per-folder settings (starts fresh with defaults if none exist)
-    let settings = load_settings(&path);
+    let settings = load_settings(path);
 
     // Update app config
     {
         let mut app_config = state.app_config.write().expect("app_config write lock");
-        app_config.notes_folder = Some(path.clone());
+        app_config.notes_folder = Some(path.to_string());
     }
 
+    appearance::set_app(app, appearance::parse(Some(&settings.theme.mode)));
+
     // Update settings in memory
     {
         let mut current_settings = state.settings.write().expect("settings write lock");
         *current_settings = settings;
     }
 
+    // Detect whether the folder is synced by a cloud provider
+    let storage: Arc<dyn storage::StorageProvider> = Arc::from(storage::detect(&path_buf));
+    *state.storage.write().expect("storage write lock") = Arc::clone(&storage);
+
+    // Watch folders are per notes folder, like the rest of the settings
+    restart_watch_folders(app)?;
+
+    // The previous folder's notes leave Spotlight; list_notes adds these
+    state.spotlight.clear();
+    remote_sync::folder_changed(app);
+
     // Save app config to disk
     {
         let app_config = state.app_config.read().expect("app_config read lock");
-        save_app_config(&app, &app_config).map_err(|e| e.to_string())?;
+        save_app_config(app, &app_config).map_err(|e| e.to_string())?;
     }
 
     // Initialize search index
-    if let Ok(index_path) = get_search_index_path(&app) {
+    if let Ok(index_path) = get_search_index_path(app) {
         if let Ok(search_index) = SearchIndex::new(&index_path) {
-            let _ = search_index.rebuild_index(&path_buf);
+            let _ = search_index.sync(&path_buf, storage.as_ref());
             let mut index = state.search_index.lock().expect("search index mutex");
             *index = Some(search_index);
         }
@@ -688,6 +895,80 @@ fn set_notes_folder(app: AppHandle, path: String, state: State<AppState>) -> Res
     Ok(())
 }
 
+// The open projects, the active one among them, and the recent ones
+#[tauri::command]
+fn list_projects(app: AppHandle) -> projects::ProjectsStatus {
+    projects::status(&app)
+}
+
+// Make an empty project at `path`, a bundle if it ends in .smudge, and open
+// it in a new window
+#[tauri::command]
+fn create_project(app: AppHandle, path: String) -> Result<projects::ProjectInfo, String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    projects::create(&app, &path)
+}
+
+// Open the project at `path` in a window of its own, or switch to it if it's
+// open already
+#[tauri::command]
+fn open_project(app: AppHandle, path: String) -> Result<projects::ProjectInfo, String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    projects::open(&app, &path)
+}
+
+#[tauri::command]
+fn switch_project(app: AppHandle, path: String) -> Result<projects::ProjectInfo, String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    projects::switch(&app, &path)
+}
+
+// Close the project at `path`, or the calling window's, with its windows
+#[tauri::command]
+fn close_project(
+    app: AppHandle,
+    path: Option<String>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    match path {
+        Some(path) => {
+            let path = fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+            projects::close(&app, &path)
+        }
+        None => projects::close_window_project(&app, window.label()),
+    }
+}
+
+// An open project's settings, which override the defaults while it's active
+#[tauri::command]
+fn get_project_settings(app: AppHandle, path: String) -> Result<Settings, String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+    projects::settings(&app, &path)
+}
+
+#[tauri::command]
+fn clear_recent_projects(app: AppHandle) {
+    projects::clear_recent(&app);
+}
+
+// Serve no notes, once the last project closed, until a folder is chosen
+fn deactivate_notes_folder(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state
+        .app_config
+        .write()
+        .expect("app_config write lock")
+        .notes_folder = None;
+    *state.settings.write().expect("settings write lock") = Settings::default();
+    *state.file_watcher.lock().expect("file watcher mutex") = None;
+    *state.search_index.lock().expect("search index mutex") = None;
+    restart_watch_folders(app)?;
+    state.spotlight.clear();
+    remote_sync::folder_changed(app);
+    let app_config = state.app_config.read().expect("app_config read lock");
+    save_app_config(app, &app_config).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 async fn list_notes(state: State<'_, AppState>) -> Result<Vec<NoteMetadata>, String> {
     let folder = {
@@ -704,12 +985,24 @@ async fn list_notes(state: State<'_, AppState>) -> Result<Vec<NoteMetadata>, Str
     }
 
     let mut notes: Vec<NoteMetadata> = Vec::new();
+    let storage = state.storage();
 
     // Use tokio for async file reading
     let mut entries = fs::read_dir(&path).await.map_err(|e| e.to_string())?;
 
     while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
         let file_path = entry.path();
+        // Cloud placeholders are listed without reading them (which would
+        // block on a download), reusing whatever the cache knows about them
+        let placeholder_path = storage::icloud_stub_target(&file_path).unwrap_or_else(|| file_path.clone());
+        if placeholder_path.extension().is_some_and(|ext| ext == "md")
+            && storage.is_placeholder(&file_path)
+        {
+            if let Ok(metadata) = entry.metadata().await {
+                notes.push(placeholder_metadata(&state, &placeholder_path, &metadata));
+            }
+            continue;
+        }
         if file_path.extension().is_some_and(|ext| ext == "md") {
             // Get metadata first (single syscall)
             if let Ok(metadata) = entry.metadata().await {
@@ -732,6 +1025,7 @@ async fn list_notes(state: State<'_, AppState>) -> Result<Vec<NoteMetadata>, Str
                         title: extract_title(&content),
                         preview: generate_preview(&content),
                         modified,
+                        is_placeholder: false,
                     });
                 }
             }
@@ -769,11 +1063,66 @@ async fn list_notes(state: State<'_, AppState>) -> Result<Vec<NoteMetadata>, Str
         }
     }
 
+    state.spotlight.sync(&notes);
+
     Ok(notes)
 }
 
+// List entry for a note whose content isn't on this device
+fn placeholder_metadata(state: &AppState, path: &Path, metadata: &std::fs::Metadata) -> NoteMetadata {
+    let id = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    let cache = state.notes_cache.read().expect("cache read lock");
+    let (title, preview) = cache
+        .get(&id)
+        .map(|note| (note.title.clone(), note.preview.clone()))
+        .unwrap_or_else(|| (id.clone(), String::new()));
+
+    NoteMetadata {
+        id,
+        title,
+        preview,
+        modified,
+        is_placeholder: true,
+    }
+}
+
+// Download a cloud placeholder before it's read, reporting progress to the frontend
+async fn materialize_file(app: &AppHandle, path: &Path) -> Result<(), String> {
+    storage::materialize(path, storage::DOWNLOAD_TIMEOUT, |progress| {
+        let _ = app.emit("download-progress", progress);
+    })
+    .await
+}
+
+#[derive(Deserialize)]
+struct ReadNoteArgs {
+    id: String,
+}
+
+// Takes and returns MessagePack for large notes (see ipc_codec)
 #[tauri::command]
-async fn read_note(id: String, state: State<'_, AppState>) -> Result<Note, String> {
+async fn read_note(
+    app: AppHandle,
+    request: tauri::ipc::Request<'_>,
+    state: State<'_, AppState>,
+) -> Result<tauri::ipc::Response, String> {
+    let ReadNoteArgs { id } = ipc_codec::args(&request)?;
+    let note = telemetry::timed(&app, "command.readNote", load_note(&app, id, &state)).await?;
+    ipc_codec::reply(&note, ipc_codec::accepts_binary(&request))
+}
+
+async fn load_note(app: &AppHandle, id: String, state: &AppState) -> Result<Note, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config
@@ -783,6 +1132,9 @@ async fn read_note(id: String, state: State<'_, AppState>) -> Result<Note, Strin
     };
 
     let file_path = PathBuf::from(&folder).join(format!("{}.md", id));
+
+    materialize_file(app, &file_path).await?;
+
     if !file_path.exists() {
         return Err("Note not found".to_string());
     }
@@ -810,12 +1162,50 @@ async fn read_note(id: String, state: State<'_, AppState>) -> Result<Note, Strin
     })
 }
 
-#[tauri::command]
-async fn save_note(
+#[derive(Deserialize)]
+struct SaveNoteArgs {
     id: Option<String>,
     content: String,
+}
+
+// Takes and returns MessagePack for large notes (see ipc_codec)
+#[tauri::command]
+async fn save_note(
+    request: tauri::ipc::Request<'_>,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<Note, String> {
+) -> Result<tauri::ipc::Response, String> {
+    let SaveNoteArgs { id, content } = ipc_codec::args(&request)?;
+    let note = telemetry::timed(
+        &app,
+        "command.saveNote",
+        write_note(id.clone(), content, &state),
+    )
+    .await?;
+    note_saved(&app, id.as_deref(), &note);
+    ipc_codec::reply(&note, ipc_codec::accepts_binary(&request))
+}
+
+/// Tell plugins about a save and keep it as a version, off the command's
+/// thread
+fn note_saved(app: &AppHandle, previous_id: Option<&str>, note: &Note) {
+    plugins::dispatch(app, "note-saved", serde_json::json!({ "id": note.id }));
+    let app = app.clone();
+    let previous_id = previous_id.map(str::to_string);
+    let (id, content) = (note.id.clone(), note.content.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(previous_id) = previous_id.filter(|previous| *previous != id) {
+            if let Err(e) = versions::renamed(&app, &previous_id, &id) {
+                log::warn!(note = id.as_str(), error:% = e; "version history not moved");
+            }
+        }
+        if let Err(e) = versions::snapshot(&app, &id, &content) {
+            log::warn!(note = id.as_str(), error:% = e; "version not kept");
+        }
+    });
+}
+
+async fn write_note(id: Option<String>, content: String, state: &AppState) -> Result<Note, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config
@@ -901,17 +1291,27 @@ async fn save_note(
         cache.remove(old_id_str);
     }
 
-    Ok(Note {
+    let note = Note {
         id: final_id,
         title,
         content,
         path: file_path.to_string_lossy().into_owned(),
         modified,
-    })
+    };
+    state.spotlight.saved(
+        &note,
+        &generate_preview(&note.content),
+        old_id.as_ref().map(|(old_id_str, _)| old_id_str.as_str()),
+    );
+    Ok(note)
 }
 
 #[tauri::command]
-async fn delete_note(id: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn delete_note(
+    id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<trash::TrashHandle>, String> {
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config
@@ -920,12 +1320,17 @@ async fn delete_note(id: String, state: State<'_, AppState>) -> Result<(), Strin
             .ok_or("Notes folder not set")?
     };
 
+    // Trashed rather than removed, so the delete can be undone
     let file_path = PathBuf::from(&folder).join(format!("{}.md", id));
-    if file_path.exists() {
-        fs::remove_file(&file_path)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
+    let handle = if file_path.exists() {
+        let handle =
+            tauri::async_runtime::spawn_blocking(move || trash::move_to_trash(&file_path))
+                .await
+                .map_err(|e| e.to_string())??;
+        Some(handle)
+    } else {
+        None
+    };
 
     // Update search index
     {
@@ -941,7 +1346,69 @@ async fn delete_note(id: String, state: State<'_, AppState>) -> Result<(), Strin
         cache.remove(&id);
     }
 
-    Ok(())
+    state.spotlight.deleted(&id);
+    plugins::dispatch(&app, "note-deleted", serde_json::json!({ "id": id }));
+
+    Ok(handle)
+}
+
+// Version history commands: snapshots kept as notes are saved or change on
+// disk, and on macOS the versions the system keeps of the file
+
+// Newest first, including those of a deleted note
+#[tauri::command]
+async fn list_note_versions(
+    id: String,
+    app: AppHandle,
+) -> Result<Vec<versions::Version>, String> {
+    tauri::async_runtime::spawn_blocking(move || versions::history(&app, &id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn get_note_version(
+    id: String,
+    version: String,
+    app: AppHandle,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || versions::content(&app, &id, &version))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// A unified diff between two versions, or from one to the note as it is now
+// when `to` is null
+#[tauri::command]
+async fn diff_note_versions(
+    id: String,
+    from: String,
+    to: Option<String>,
+    app: AppHandle,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || versions::diff(&app, &id, &from, to.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Save a version's content as the note's, which keeps what it replaces as a
+// version too
+#[tauri::command]
+async fn restore_note_version(
+    id: String,
+    version: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Note, String> {
+    let content = {
+        let (app, id) = (app.clone(), id.clone());
+        tauri::async_runtime::spawn_blocking(move || versions::content(&app, &id, &version))
+            .await
+            .map_err(|e| e.to_string())??
+    };
+    let note = write_note(Some(id.clone()), content, &state).await?;
+    note_saved(&app, Some(&id), &note);
+    Ok(note)
 }
 
 #[tauri::command]
@@ -994,6 +1461,33 @@ async fn create_note(state: State<'_, AppState>) -> Result<Note, String> {
     })
 }
 
+// The built-in document templates and the user's own
+#[tauri::command]
+fn list_templates(app: AppHandle) -> Vec<templates::Template> {
+    templates::list(&app)
+}
+
+// The folder the user's templates are kept in, made if it isn't there yet
+#[tauri::command]
+fn templates_folder(app: AppHandle) -> Result<String, String> {
+    templates::user_dir(&app).map(|dir| dir.to_string_lossy().into_owned())
+}
+
+// Create a note from a template, with its {{variables}} filled in from
+// `variables`
+#[tauri::command]
+async fn create_note_from_template(
+    template: String,
+    variables: HashMap<String, String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Note, String> {
+    let content = templates::instantiate(&app, &template, &variables)?;
+    let note = write_note(None, content, &state).await?;
+    note_saved(&app, None, &note);
+    Ok(note)
+}
+
 #[tauri::command]
 fn get_settings(state: State<AppState>) -> Settings {
     state.settings.read().expect("settings read lock").clone()
@@ -1001,6 +1495,7 @@ fn get_settings(state: State<AppState>) -> Settings {
 
 #[tauri::command]
 fn update_settings(
+    app: AppHandle,
     new_settings: Settings,
     state: State<AppState>,
 ) -> Result<(), String> {
@@ -1009,32 +1504,58 @@ fn update_settings(
         app_config.notes_folder.clone().ok_or("Notes folder not set")?
     };
 
-    {
+    let (watch_folders_changed, theme_changed) = {
         let mut settings = state.settings.write().expect("settings write lock");
+        let changed = settings.watch_folders != new_settings.watch_folders;
+        let theme_changed = settings.theme.mode != new_settings.theme.mode;
         *settings = new_settings;
+        (changed, theme_changed)
+    };
+
+    {
+        let settings = state.settings.read().expect("settings read lock");
+        save_settings(&folder, &settings).map_err(|e| e.to_string())?;
     }
 
-    let settings = state.settings.read().expect("settings read lock");
-    save_settings(&folder, &settings).map_err(|e| e.to_string())?;
+    if watch_folders_changed {
+        restart_watch_folders(&app)?;
+    }
+    if theme_changed {
+        let mode = state.settings.read().expect("settings read lock").theme.mode.clone();
+        appearance::set_app(&app, appearance::parse(Some(&mode)));
+    }
 
     Ok(())
 }
 
 #[tauri::command]
-async fn search_notes(query: String, state: State<'_, AppState>) -> Result<Vec<SearchResult>, String> {
+async fn search_notes(
+    app: AppHandle,
+    query: String,
+    state: State<'_, AppState>,
+    executor: State<'_, executor::Executor>,
+) -> Result<Vec<SearchResult>, String> {
     if query.trim().is_empty() {
         return Ok(vec![]);
     }
 
-    // Check if search index is available and use it (scoped to drop lock before await)
-    let search_result = {
-        let index = state.search_index.lock().expect("search index mutex");
-        if let Some(ref search_index) = *index {
-            Some(search_index.search(&query, 20).map_err(|e| e.to_string()))
-        } else {
-            None
-        }
-    };
+    // Query the index on the executor, off the async runtime
+    let search_query = query.clone();
+    let handle = app.clone();
+    let search_result = telemetry::timed(
+        &app,
+        "command.searchNotes",
+        executor.run(executor::Priority::Interactive, move || {
+            let state = handle.state::<AppState>();
+            let index = state.search_index.lock().expect("search index mutex");
+            index.as_ref().map(|search_index| {
+                search_index
+                    .search(&search_query, 20)
+                    .map_err(|e| e.to_string())
+            })
+        }),
+    )
+    .await?;
 
     if let Some(result) = search_result {
         result
@@ -1044,6 +1565,33 @@ async fn search_notes(query: String, state: State<'_, AppState>) -> Result<Vec<S
     }
 }
 
+// Ranked search with snippets, narrowed by `filters`. Unlike search_notes
+// there's no fallback: without an index it fails.
+#[tauri::command]
+async fn search(
+    app: AppHandle,
+    query: String,
+    filters: Option<search::Filters>,
+    executor: State<'_, executor::Executor>,
+) -> Result<Vec<search::SearchHit>, String> {
+    if query.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    let filters = filters.unwrap_or_default();
+
+    executor
+        .run(executor::Priority::Interactive, move || {
+            let state = app.state::<AppState>();
+            let index = state.search_index.lock().expect("search index mutex");
+            index
+                .as_ref()
+                .ok_or("The search index isn't ready")?
+                .search_filtered(&query, &filters)
+                .map_err(|e| e.to_string())
+        })
+        .await?
+}
+
 // Fallback search when Tantivy index isn't available - searches title and full content
 async fn fallback_search(query: &str, state: &State<'_, AppState>) -> Result<Vec<SearchResult>, String> {
     let folder = {
@@ -1074,6 +1622,7 @@ async fn fallback_search(query: &str, state: &State<'_, AppState>) -> Result<Vec
 
     let query_lower = query.to_lowercase();
     let mut results: Vec<SearchResult> = Vec::new();
+    let storage = state.storage();
 
     for (id, title, preview, modified) in cache_data {
         let title_lower = title.to_lowercase();
@@ -1085,7 +1634,9 @@ async fn fallback_search(query: &str, state: &State<'_, AppState>) -> Result<Vec
 
         // Read file content asynchronously and search in it
         let file_path = PathBuf::from(&folder).join(format!("{}.md", &id));
-        if let Ok(content) = tokio::fs::read_to_string(&file_path).await {
+        if storage.is_placeholder(&file_path) {
+            // Title-only match; don't download every note to search it
+        } else if let Ok(content) = tokio::fs::read_to_string(&file_path).await {
             let content_lower = content.to_lowercase();
             if content_lower.contains(&query_lower) {
                 // Higher score if in title, lower if only in content
@@ -1161,6 +1712,15 @@ fn setup_file_watcher(
                             _ => continue,
                         };
 
+                        // Eviction by the cloud provider shows up as a modify
+                        // (or a delete, for iCloud .icloud stubs), but the note
+                        // still exists; keep its index entry and list item.
+                        if let Some(state) = app_handle.try_state::<AppState>() {
+                            if state.storage().is_placeholder(path) {
+                                continue;
+                            }
+                        }
+
                         // Extract note ID from filename
                         let note_id = path
                             .file_stem()
@@ -1172,27 +1732,11 @@ fn setup_file_watcher(
                         if let Some(state) = app_handle.try_state::<AppState>() {
                             let index = state.search_index.lock().expect("search index mutex");
                             if let Some(ref search_index) = *index {
-                                match kind {
-                                    "created" | "modified" => {
-                                        // Read file and index it
-                                        if let Ok(content) = std::fs::read_to_string(path) {
-                                            let title = extract_title(&content);
-                                            let modified = std::fs::metadata(path)
-                                                .ok()
-                                                .and_then(|m| m.modified().ok())
-                                                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                                                .map(|d| d.as_secs() as i64)
-                                                .unwrap_or(0);
-                                            let _ = search_index.index_note(&note_id, &title, &content, modified);
-                                        }
-                                    }
-                                    "deleted" => {
-                                        let _ = search_index.delete_note(&note_id);
-                                    }
-                                    _ => {}
-                                }
+                                search::note_changed(search_index, path);
                             }
                         }
+                        remote_sync::note_changed(&app_handle, &note_id);
+                        versions::file_changed(&app_handle, &note_id, path);
 
                         let _ = app_handle.emit(
                             "file-change",
@@ -1250,6 +1794,23 @@ fn copy_to_clipboard(app: AppHandle, text: String) -> Result<(), String> {
     app.clipboard().write_text(text).map_err(|e| e.to_string())
 }
 
+// Everything on the clipboard, including images and copied files the
+// webview's paste event doesn't see
+#[tauri::command]
+fn read_clipboard(app: AppHandle) -> Result<clipboard::ClipboardData, String> {
+    clipboard::read(&app)
+}
+
+#[tauri::command]
+fn write_clipboard(app: AppHandle, data: clipboard::ClipboardData) -> Result<(), String> {
+    clipboard::write(&app, data)
+}
+
+#[tauri::command]
+fn get_clipboard_formats(app: AppHandle) -> Vec<clipboard::Format> {
+    clipboard::formats(&app)
+}
+
 #[tauri::command]
 async fn save_clipboard_image(
     base64_data: String,
@@ -1311,9 +1872,11 @@ async fn save_clipboard_image(
 
 #[tauri::command]
 async fn copy_image_to_assets(
+    app: AppHandle,
     source_path: String,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
+    fs_scope::check(&app, &source_path, fs_scope::Access::Read)?;
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config
@@ -1368,8 +1931,9 @@ async fn copy_image_to_assets(
     Ok(format!("assets/{}", target_name))
 }
 
-#[tauri::command]
-fn rebuild_search_index(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+// Index every note in the notes folder from scratch, blocking until it's done
+fn rebuild_search_index_now(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
     let folder = {
         let app_config = state.app_config.read().expect("app_config read lock");
         app_config
@@ -1378,12 +1942,12 @@ fn rebuild_search_index(app: AppHandle, state: State<AppState>) -> Result<(), St
             .ok_or("Notes folder not set")?
     };
 
-    let index_path = get_search_index_path(&app).map_err(|e| e.to_string())?;
+    let index_path = get_search_index_path(app).map_err(|e| e.to_string())?;
 
     // Create new index
     let search_index = SearchIndex::new(&index_path).map_err(|e| e.to_string())?;
     search_index
-        .rebuild_index(&PathBuf::from(&folder))
+        .rebuild_index(&PathBuf::from(&folder), state.storage().as_ref())
         .map_err(|e| e.to_string())?;
 
     let mut index = state.search_index.lock().expect("search index mutex");
@@ -1392,89 +1956,166 @@ fn rebuild_search_index(app: AppHandle, state: State<AppState>) -> Result<(), St
     Ok(())
 }
 
-// UI helper commands - wrap Tauri plugins for consistent invoke-based API
-
 #[tauri::command]
-async fn open_folder_dialog(
+async fn rebuild_search_index(
     app: AppHandle,
-    default_path: Option<String>,
-) -> Result<Option<String>, String> {
-    use tauri_plugin_dialog::DialogExt;
+    executor: State<'_, executor::Executor>,
+) -> Result<(), String> {
+    executor
+        .run(executor::Priority::Background, move || {
+            rebuild_search_index_now(&app)
+        })
+        .await?
+}
 
-    // Run blocking dialog on a separate thread to avoid blocking the async runtime
-    let result = tauri::async_runtime::spawn_blocking(move || {
-        let mut builder = app.dialog().file().set_can_create_directories(true);
+// Cloud storage commands
 
-        if let Some(path) = default_path {
-            builder = builder.set_directory(path);
-        }
+#[tauri::command]
+fn get_storage_info(state: State<AppState>) -> storage::StorageInfo {
+    state.storage().info()
+}
 
-        builder.blocking_pick_folder()
-    })
-    .await
-    .map_err(|e| format!("Dialog task failed: {}", e))?;
+fn note_path(state: &AppState, id: &str) -> Result<PathBuf, String> {
+    let app_config = state.app_config.read().expect("app_config read lock");
+    let folder = app_config.notes_folder.as_ref().ok_or("Notes folder not set")?;
+    Ok(PathBuf::from(folder).join(format!("{}.md", id)))
+}
 
-    Ok(result.map(|p| p.to_string()))
+#[tauri::command]
+fn download_note(id: String, state: State<AppState>) -> Result<(), String> {
+    let path = note_path(&state, &id)?;
+    state.storage().request_download(&path)
 }
 
 #[tauri::command]
-async fn reveal_in_file_manager(path: String) -> Result<(), String> {
-    let path_buf = PathBuf::from(&path);
-    if !path_buf.exists() {
-        return Err("Path does not exist".to_string());
+fn evict_note(id: String, state: State<AppState>) -> Result<(), String> {
+    let path = note_path(&state, &id)?;
+    let storage = state.storage();
+    if !storage.supports_eviction() {
+        return Err(format!("{} doesn't support freeing up space", storage.name()));
     }
+    storage.evict(&path)
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .args(["-R", &path])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
+// UI helper commands - wrap Tauri plugins for consistent invoke-based API
 
-    #[cfg(target_os = "windows")]
-    {
-        // Windows explorer /select requires backslashes
-        let windows_path = path.replace("/", "\\");
-        std::process::Command::new("explorer")
-            .args(["/select,", &windows_path])
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
+#[tauri::command]
+async fn open_folder_dialog(
+    app: AppHandle,
+    window: WebviewWindow,
+    options: Option<dialogs::DialogOptions>,
+) -> Result<Option<String>, String> {
+    let picked = dialogs::show(
+        &app,
+        &window,
+        dialogs::DialogKind::PickFolder,
+        options.unwrap_or_default(),
+    )
+    .await?;
+    Ok(picked.map(|p| p.path.to_string_lossy().into_owned()))
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        // Linux: open containing directory (most file managers don't support selecting)
-        let parent = path_buf
-            .parent()
-            .ok_or_else(|| "Cannot determine parent directory".to_string())?;
-        let parent_str = parent
-            .to_str()
-            .ok_or_else(|| "Path contains invalid UTF-8".to_string())?;
-        std::process::Command::new("xdg-open")
-            .arg(parent_str)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-    {
-        return Err("Unsupported platform".to_string());
-    }
+#[tauri::command]
+async fn open_file_dialog(
+    app: AppHandle,
+    window: WebviewWindow,
+    options: Option<dialogs::DialogOptions>,
+) -> Result<Option<String>, String> {
+    let picked = dialogs::show(
+        &app,
+        &window,
+        dialogs::DialogKind::OpenFile,
+        options.unwrap_or_default(),
+    )
+    .await?;
+    Ok(picked.map(|p| p.path.to_string_lossy().into_owned()))
+}
 
-    Ok(())
+#[tauri::command]
+async fn save_file_dialog(
+    app: AppHandle,
+    window: WebviewWindow,
+    options: Option<dialogs::DialogOptions>,
+) -> Result<Option<String>, String> {
+    let picked = dialogs::show(
+        &app,
+        &window,
+        dialogs::DialogKind::SaveFile,
+        options.unwrap_or_default(),
+    )
+    .await?;
+    Ok(picked.map(|p| p.path.to_string_lossy().into_owned()))
 }
 
+/// Like the dialog commands above, with the accessory controls' values and
+/// the saved file's tags alongside the path
 #[tauri::command]
-async fn open_url_safe(url: String) -> Result<(), String> {
-    // Validate URL scheme - only allow http, https, mailto
-    let parsed = url::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+async fn show_file_dialog(
+    app: AppHandle,
+    window: WebviewWindow,
+    kind: dialogs::DialogKind,
+    options: Option<dialogs::DialogOptions>,
+) -> Result<Option<dialogs::Picked>, String> {
+    dialogs::show(&app, &window, kind, options.unwrap_or_default()).await
+}
 
-    match parsed.scheme() {
-        "http" | "https" | "mailto" => {}
-        scheme => {
-            return Err(format!(
-                "URL scheme '{}' is not allowed. Only http, https, and mailto are permitted.",
+#[tauri::command]
+async fn reveal_in_file_manager(app: AppHandle, path: String) -> Result<(), String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+    file_metadata::reveal(&[path])
+}
+
+// File metadata commands
+
+#[tauri::command]
+async fn reveal_files(app: AppHandle, paths: Vec<String>) -> Result<(), String> {
+    let paths = paths
+        .iter()
+        .map(|path| fs_scope::check(&app, path, fs_scope::Access::Read))
+        .collect::<Result<Vec<_>, _>>()?;
+    file_metadata::reveal(&paths)
+}
+
+#[tauri::command]
+async fn get_file_metadata(
+    app: AppHandle,
+    path: String,
+) -> Result<file_metadata::FileMetadata, String> {
+    fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+    file_metadata::read(Path::new(&path))
+}
+
+#[tauri::command]
+async fn set_file_tags(
+    app: AppHandle,
+    path: String,
+    tags: Vec<file_metadata::FileTag>,
+) -> Result<(), String> {
+    fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    file_metadata::write_tags(Path::new(&path), &tags)
+}
+
+#[tauri::command]
+async fn set_file_dates(
+    app: AppHandle,
+    path: String,
+    created: Option<i64>,
+    modified: Option<i64>,
+) -> Result<(), String> {
+    fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    file_metadata::write_dates(Path::new(&path), created, modified)
+}
+
+#[tauri::command]
+async fn open_url_safe(url: String) -> Result<(), String> {
+    // Validate URL scheme - only allow http, https, mailto
+    let parsed = url::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+
+    match parsed.scheme() {
+        "http" | "https" | "mailto" => {}
+        scheme => {
+            return Err(format!(
+                "URL scheme '{}' is not allowed. Only http, https, and mailto are permitted.",
                 scheme
             ))
         }
@@ -1488,6 +2129,9 @@ async fn open_url_safe(url: String) -> Result<(), String> {
 
 #[tauri::command]
 async fn git_is_available() -> bool {
+    if sandbox::require("git").is_err() {
+        return false;
+    }
     tauri::async_runtime::spawn_blocking(git::is_available)
         .await
         .unwrap_or(false)
@@ -1632,6 +2276,10 @@ async fn git_push_with_upstream(state: State<'_, AppState>) -> Result<git::GitRe
 async fn ai_check_claude_cli() -> Result<bool, String> {
     use std::process::Command;
 
+    if sandbox::require("aiAssistant").is_err() {
+        return Ok(false);
+    }
+
     let which_cmd = if cfg!(target_os = "windows") {
         "where"
     } else {
@@ -1649,12 +2297,16 @@ async fn ai_check_claude_cli() -> Result<bool, String> {
 // AI execute command
 #[tauri::command]
 async fn ai_execute_claude(
+    app: AppHandle,
     file_path: String,
     prompt: String,
 ) -> Result<AiExecutionResult, String> {
     use std::process::{Child, Command, Stdio};
     use std::io::Write;
 
+    sandbox::require("aiAssistant")?;
+    fs_scope::check(&app, &file_path, fs_scope::Access::ReadWrite)?;
+
     // Check if claude CLI exists
     let which_cmd = if cfg!(target_os = "windows") {
         "where"
@@ -1833,201 +2485,3048 @@ fn get_opened_files(state: State<OpenedFiles>) -> Vec<String> {
     paths
 }
 
+// Markdown files the app was launched to open. Unlike get_opened_files this
+// isn't drained, so the frontend can ask on any load.
 #[tauri::command]
-fn mark_frontend_ready(state: State<FrontendReady>) {
-    let mut ready = state.0.lock().expect("frontend ready mutex");
-    *ready = true;
+fn get_launch_files(app: AppHandle, scope: State<fs_scope::FsScope>) -> Vec<String> {
+    let files = launch_files();
+    let paths: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+    scope.opened(&app, &paths);
+    files
 }
 
-/// Remove macOS quarantine extended attribute so Gatekeeper doesn't block
-/// files the user explicitly chose to open.
-#[cfg(target_os = "macos")]
-fn remove_quarantine(path: &str) {
-    let _ = std::process::Command::new("xattr")
-        .args(["-d", "com.apple.quarantine", path])
-        .output();
+fn launch_files() -> Vec<String> {
+    #[cfg(target_os = "macos")]
+    let files = tauri_runtime_wry::tao::platform::macos::launch_files();
+    // Windows and Linux pass them as arguments
+    #[cfg(not(target_os = "macos"))]
+    let files: Vec<PathBuf> = std::env::args_os().skip(1).map(PathBuf::from).collect();
+    files
+        .iter()
+        .filter(|path| path.is_file() && is_markdown_file(path))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
 }
 
 #[tauri::command]
-async fn read_external_file(path: String) -> Result<String, String> {
-    #[cfg(target_os = "macos")]
-    remove_quarantine(&path);
+fn get_pending_deep_links(state: State<PendingDeepLinks>) -> Vec<universal_links::DeepLinkRoute> {
+    let mut links = state.0.lock().expect("pending deep links mutex");
+    std::mem::take(&mut *links)
+}
 
-    fs::read_to_string(&path)
-        .await
-        .map_err(|e| format!("Failed to read file: {}", e))
+#[tauri::command]
+fn get_pending_handoff(state: State<PendingHandoff>) -> Option<handoff::HandoffDocument> {
+    state.0.lock().expect("pending handoff mutex").take()
+}
+
+fn handoff_enabled(state: &AppState) -> bool {
+    state
+        .settings
+        .read()
+        .expect("settings read lock")
+        .handoff_enabled
+        .unwrap_or(false)
 }
 
+// Titlebar commands
+
 #[tauri::command]
-async fn write_external_file(path: String, content: String) -> Result<(), String> {
-    fs::write(&path, &content)
-        .await
-        .map_err(|e| format!("Failed to write file: {}", e))
+fn get_titlebar_info(state: State<AppState>) -> titlebar::TitlebarInfo {
+    let mode = state.app_config.read().expect("app_config read lock").titlebar;
+    titlebar::info(mode)
 }
 
 #[tauri::command]
-async fn import_external_file_to_notes(path: String, state: State<'_, AppState>) -> Result<String, String> {
-    let folder = {
-        let app_config = state.app_config.read().expect("app_config read lock");
-        app_config
-            .notes_folder
-            .clone()
-            .ok_or("Notes folder not set")?
-    };
+fn set_titlebar_mode(
+    mode: titlebar::TitlebarMode,
+    app: AppHandle,
+    window: WebviewWindow,
+    state: State<AppState>,
+) -> Result<titlebar::TitlebarInfo, String> {
+    titlebar::apply(&window, mode)?;
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.titlebar = mode;
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())?;
+    Ok(titlebar::info(mode))
+}
 
-    let source_path = PathBuf::from(&path);
-    if !source_path.exists() || !source_path.is_file() {
-        return Err("External file not found".to_string());
-    }
-    if !is_markdown_file(&source_path) {
-        return Err("External file is not markdown".to_string());
+#[tauri::command]
+fn titlebar_double_click(window: WebviewWindow) -> Result<(), String> {
+    titlebar::double_click(&window)
+}
+
+// Move the calling window's traffic lights, or give them back to the OS
+// with null (macOS)
+#[tauri::command]
+fn set_traffic_light_position(
+    position: Option<tauri::LogicalPosition<f64>>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    titlebar::set_traffic_lights(&window, position)
+}
+
+// Hide the calling window's title, keeping the traffic lights (macOS)
+#[tauri::command]
+fn set_title_hidden(hidden: bool, window: WebviewWindow) -> Result<(), String> {
+    titlebar::set_title_hidden(&window, hidden)
+}
+
+// Host native buttons in the calling window's title bar, replacing the ones
+// it had; clicks arrive as "titlebar-button" (macOS)
+#[tauri::command]
+async fn set_titlebar_accessories(
+    accessories: Vec<titlebar::Accessory>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    titlebar::set_accessories(&window, accessories).await
+}
+
+// Mini window commands
+
+// Move `note_id` between the main window and the always-on-top mini window.
+// Called from whichever window currently shows the note.
+#[tauri::command]
+fn swap_mini_mode(
+    note_id: String,
+    app: AppHandle,
+    window: WebviewWindow,
+    state: State<AppState>,
+) -> Result<(), String> {
+    if mini_window::is_mini(window.label()) {
+        // The close handler below restores the main window
+        let _ = app.emit_to("main", "mini-mode-exit", &note_id);
+        return window.close().map_err(|e| e.to_string());
     }
 
-    let folder_path = PathBuf::from(&folder);
-    if source_path.starts_with(&folder_path) {
-        if let Some(stem) = source_path.file_stem().and_then(|s| s.to_str()) {
-            return Ok(stem.to_string());
-        }
+    if let Some(existing) = mini_window::find(&app) {
+        existing.close().map_err(|e| e.to_string())?;
     }
+    let (corner, policy) = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        (app_config.mini_window_corner, app_config.placement_policy)
+    };
+    let monitor = placement::resolve_monitor(&app, policy, mini_window::LABEL);
+    let mini = mini_window::open(&app, &note_id, corner, monitor)?;
+
+    let handle = app.clone();
+    let tracked = mini.clone();
+    mini.on_window_event(move |event| {
+        if !matches!(event, tauri::WindowEvent::CloseRequested { .. }) {
+            return;
+        }
+        // Remember which corner the user dragged it to
+        if let Some(corner) = mini_window::nearest_corner(&tracked) {
+            if let Some(state) = handle.try_state::<AppState>() {
+                let mut app_config = state.app_config.write().expect("app_config write lock");
+                app_config.mini_window_corner = corner;
+                let _ = save_app_config(&handle, &app_config);
+            }
+        }
+        if let Some(main) = handle.get_webview_window("main") {
+            let _ = main.show();
+            let _ = main.set_focus();
+        }
+    });
 
-    let content = fs::read_to_string(&source_path)
-        .await
-        .map_err(|e| format!("Failed to read external file for import: {}", e))?;
+    window.hide().map_err(|e| e.to_string())
+}
 
-    let base_name = source_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("imported");
-    let base_id = sanitize_filename(base_name);
+// Called by a warm window once it's listening for its claim
+#[tauri::command]
+fn webview_pool_ready(window: WebviewWindow, pool: State<webview_pool::WebviewPool>) {
+    pool.mark_ready(window.label());
+}
 
-    // Stable ID per source path so reopening the same file updates one imported note.
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    source_path.to_string_lossy().hash(&mut hasher);
-    let path_hash = hasher.finish() as u32;
-    let final_id = format!("{}-{:08x}", base_id, path_hash);
-    let target_path = folder_path.join(format!("{}.md", final_id));
+#[tauri::command]
+fn get_webview_pool_size(pool: State<webview_pool::WebviewPool>) -> usize {
+    pool.size()
+}
 
-    fs::write(&target_path, &content)
-        .await
-        .map_err(|e| format!("Failed to import external file: {}", e))?;
+// 0 turns the pool off; sizes above webview_pool::MAX_SIZE are capped
+#[tauri::command]
+fn set_webview_pool_size(
+    size: usize,
+    app: AppHandle,
+    state: State<AppState>,
+    pool: State<webview_pool::WebviewPool>,
+) -> Result<(), String> {
+    pool.resize(&app, size);
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.webview_pool_size = Some(pool.size());
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())
+}
 
-    let metadata = fs::metadata(&target_path)
-        .await
-        .map_err(|e| format!("Failed to read imported file metadata: {}", e))?;
-    let modified = metadata
-        .modified()
-        .ok()
-        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-        .map(|d| d.as_secs() as i64)
-        .unwrap_or(0);
-    let title = extract_title(&content);
+// Window placement commands
 
-    {
-        let index = state.search_index.lock().expect("search index mutex");
-        if let Some(ref search_index) = *index {
-            let _ = search_index.index_note(&final_id, &title, &content, modified);
-        }
-    }
+#[tauri::command]
+fn get_placement_policy(state: State<AppState>) -> placement::PlacementPolicy {
+    state.app_config.read().expect("app_config read lock").placement_policy
+}
 
-    {
-        let mut cache = state.notes_cache.write().expect("cache write lock");
-        cache.insert(
-            final_id.clone(),
-            NoteMetadata {
-                id: final_id.clone(),
-                title,
-                preview: generate_preview(&content),
-                modified,
-            },
-        );
+#[tauri::command]
+fn set_placement_policy(
+    policy: placement::PlacementPolicy,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.placement_policy = policy;
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())
+}
+
+// State restoration commands
+
+#[tauri::command]
+fn get_restore_windows(state: State<AppState>) -> bool {
+    state.app_config.read().expect("app_config read lock").restore_windows
+}
+
+#[tauri::command]
+fn set_restore_windows(
+    enabled: bool,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
+    state_restoration::apply(&app, enabled)?;
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.restore_windows = enabled;
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())
+}
+
+// Render throttling: with it on, "render-throttling" tells the windows to
+// stop animating while all of them are hidden behind other windows
+
+// None where occlusion isn't reported (everywhere but macOS)
+#[tauri::command]
+fn get_render_throttling(state: State<AppState>) -> Option<bool> {
+    let enabled = state
+        .app_config
+        .read()
+        .expect("app_config read lock")
+        .render_throttling;
+    cfg!(target_os = "macos").then_some(enabled)
+}
+
+#[tauri::command]
+fn set_render_throttling(
+    enabled: bool,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
+    occlusion::set_throttling(&app, enabled);
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.render_throttling = enabled;
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn is_render_throttled(app: AppHandle) -> bool {
+    occlusion::is_throttled(&app)
+}
+
+// Browser extension commands: the loopback server the extension sends pages
+// and selections to, which arrive as `clip` deep links
+
+#[tauri::command]
+fn get_extension_server(
+    server: State<extension_server::ExtensionServer>,
+) -> extension_server::ExtensionServerStatus {
+    server.status()
+}
+
+// Start or stop the server, and start it at launch from now on if enabled
+#[tauri::command]
+async fn set_extension_server_enabled(
+    enabled: bool,
+    app: AppHandle,
+    server: State<'_, extension_server::ExtensionServer>,
+    state: State<'_, AppState>,
+) -> Result<extension_server::ExtensionServerStatus, String> {
+    if enabled {
+        sandbox::require("extensionServer")?;
+        server.start(&app).await?;
+    } else {
+        server.stop();
     }
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.extension_server = enabled;
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())?;
+    Ok(server.status())
+}
 
-    Ok(final_id)
+// Make a new pairing token; the extension has to be given it again
+#[tauri::command]
+async fn reset_extension_token(
+    app: AppHandle,
+    server: State<'_, extension_server::ExtensionServer>,
+) -> Result<extension_server::ExtensionServerStatus, String> {
+    server.reset_token(&app).await?;
+    Ok(server.status())
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_fs::init())
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_clipboard_manager::init())
-        .plugin(tauri_plugin_updater::Builder::new().build())
-        .setup(|app| {
-            // Load app config on startup (contains notes folder path)
-            let app_config = load_app_config(app.handle());
+// Plugin commands: WebAssembly extensions from the plugins folder in app
+// data, each limited to what its manifest's capabilities allow
 
-            // Load per-folder settings if notes folder is set
-            let settings = if let Some(ref folder) = app_config.notes_folder {
-                load_settings(folder)
-            } else {
-                Settings::default()
-            };
+#[tauri::command]
+fn list_plugins(app: AppHandle, plugins: State<plugins::Plugins>) -> Vec<plugins::PluginInfo> {
+    plugins.list(&app)
+}
 
-            // Initialize search index if notes folder is set
-            let search_index = if let Some(ref folder) = app_config.notes_folder {
-                if let Ok(index_path) = get_search_index_path(app.handle()) {
-                    SearchIndex::new(&index_path)
-                        .ok()
-                        .inspect(|idx| {
-                            let _ = idx.rebuild_index(&PathBuf::from(folder));
-                        })
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+// Run a command a plugin's manifest declares, with JSON args and result
+#[tauri::command]
+async fn plugin_invoke(
+    id: String,
+    command: String,
+    args: Option<serde_json::Value>,
+    app: AppHandle,
+) -> Result<serde_json::Value, String> {
+    plugins::invoke(&app, &id, &command, args.unwrap_or_default()).await
+}
 
-            let state = AppState {
-                app_config: RwLock::new(app_config),
-                settings: RwLock::new(settings),
-                notes_cache: RwLock::new(HashMap::new()),
-                file_watcher: Mutex::new(None),
-                search_index: Mutex::new(search_index),
-                debounce_map: Arc::new(Mutex::new(HashMap::new())),
-            };
-            app.manage(state);
-            app.manage(OpenedFiles::default());
-            app.manage(FrontendReady::default());
+#[tauri::command]
+async fn set_plugin_enabled(
+    id: String,
+    enabled: bool,
+    app: AppHandle,
+) -> Result<Vec<plugins::PluginInfo>, String> {
+    let reload = app.clone();
+    app.state::<executor::Executor>()
+        .run(executor::Priority::Background, move || {
+            plugins::set_enabled(&reload, &id, enabled)
+        })
+        .await??;
+    Ok(app.state::<plugins::Plugins>().list(&app))
+}
 
-            // Cold-start fallback for macOS file association:
-            // Finder can pass opened documents as launch arguments.
-            #[cfg(target_os = "macos")]
-            {
-                let startup_paths = collect_startup_opened_files_from_args();
-                debug_open_log(&format!("[setup] startup_paths={:?}", startup_paths));
-                if !startup_paths.is_empty() {
+// Find the plugins again, picking up ones added or changed on disk
+#[tauri::command]
+async fn reload_plugins(app: AppHandle) -> Result<Vec<plugins::PluginInfo>, String> {
+    let reload = app.clone();
+    app.state::<executor::Executor>()
+        .run(executor::Priority::Background, move || {
+            reload.state::<plugins::Plugins>().reload(&reload)
+        })
+        .await?;
+    Ok(app.state::<plugins::Plugins>().list(&app))
+}
+
+// What the calling window wants back when it's restored, e.g. the open note
+#[tauri::command]
+fn set_restorable_state(
+    state: Option<String>,
+    window: WebviewWindow,
+    windows: State<windows::Windows>,
+) -> Result<(), String> {
+    windows.set_state(window.label(), state.clone());
+    state_restoration::save(&window, state)
+}
+
+// The main window's state is handed over once; a note window's is whatever
+// it last saved
+#[tauri::command]
+fn take_restored_state(
+    window: WebviewWindow,
+    state: State<state_restoration::RestoredState>,
+    windows: State<windows::Windows>,
+) -> Option<String> {
+    if window.label() == "main" {
+        return state.take();
+    }
+    windows.get(window.label()).and_then(|layout| layout.state)
+}
+
+// Window manager commands: the main window and note windows, whose layout
+// is saved as it changes and reopened at launch when windows are restored
+
+#[tauri::command]
+fn list_windows(app: AppHandle) -> Vec<windows::WindowInfo> {
+    windows::list(&app)
+}
+
+// Open a note window whose frontend starts from `state`, as
+// set_restorable_state takes it. Resolves with its label.
+#[tauri::command]
+fn open_window(state: Option<String>, app: AppHandle) -> Result<String, String> {
+    let window = windows::open(&app, state)?;
+    Ok(window.label().to_string())
+}
+
+fn managed_window(app: &AppHandle, label: &str) -> Result<WebviewWindow, String> {
+    app.get_webview_window(label)
+        .filter(|window| windows::is_managed(window.label()))
+        .ok_or_else(|| format!("No window labelled {}", label))
+}
+
+#[tauri::command]
+fn close_window(label: String, app: AppHandle) -> Result<(), String> {
+    managed_window(&app, &label)?
+        .close()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn focus_window(label: String, app: AppHandle) -> Result<(), String> {
+    let window = managed_window(&app, &label)?;
+    let _ = window.unminimize();
+    window.show().map_err(|e| e.to_string())?;
+    window.set_focus().map_err(|e| e.to_string())
+}
+
+// Zoom the calling window's content, 1 being actual size. Kept for the
+// window's label, and announced to it with "zoom-changed". The stepping
+// commands resolve with the new zoom.
+#[tauri::command]
+fn set_window_zoom(zoom: f64, window: WebviewWindow) -> Result<(), String> {
+    windows::set_zoom(&window, zoom).map(|_| ())
+}
+
+#[tauri::command]
+fn get_window_zoom(window: WebviewWindow) -> f64 {
+    windows::zoom(&window)
+}
+
+#[tauri::command]
+fn zoom_window_in(window: WebviewWindow) -> Result<f64, String> {
+    windows::zoom_in(&window)
+}
+
+#[tauri::command]
+fn zoom_window_out(window: WebviewWindow) -> Result<f64, String> {
+    windows::zoom_out(&window)
+}
+
+#[tauri::command]
+fn reset_window_zoom(window: WebviewWindow) -> Result<f64, String> {
+    windows::reset_zoom(&window)
+}
+
+// Find `query` in the calling window's page with the web view's own find,
+// resolving with the match count and the active match. Changes to either
+// are announced to the window with "find-changed".
+#[tauri::command]
+async fn find_in_page(
+    query: String,
+    options: Option<find::Options>,
+    window: WebviewWindow,
+) -> Result<find::Matches, String> {
+    find::find(&window, query, options.unwrap_or_default()).await
+}
+
+#[tauri::command]
+async fn find_next(backwards: bool, window: WebviewWindow) -> Result<find::Matches, String> {
+    find::find_next(&window, backwards).await
+}
+
+#[tauri::command]
+async fn stop_find(window: WebviewWindow) -> Result<(), String> {
+    find::stop(&window).await
+}
+
+// Start an action that the changes registered until end_undo_group make
+// together, named for the Edit menu ("Move Card")
+#[tauri::command]
+fn begin_undo_group(name: String, window: WebviewWindow) {
+    undo::begin_group(&window, name);
+}
+
+#[tauri::command]
+fn end_undo_group(window: WebviewWindow) -> Result<(), String> {
+    undo::end_group(&window)
+}
+
+// Record a change the calling window just made, with the patches that
+// revert and remake it. Outside a group it's an action of its own. Undoing
+// and redoing hand the patches back with "undo-apply".
+#[tauri::command]
+fn register_undo(name: String, change: undo::Change, window: WebviewWindow) {
+    undo::register(&window, name, change);
+}
+
+#[tauri::command]
+fn undo(window: WebviewWindow) -> Result<(), String> {
+    undo::undo(&window)
+}
+
+#[tauri::command]
+fn redo(window: WebviewWindow) -> Result<(), String> {
+    undo::redo(&window)
+}
+
+#[tauri::command]
+fn mark_undo_saved(window: WebviewWindow) {
+    undo::mark_saved(&window);
+}
+
+#[tauri::command]
+fn clear_undo(window: WebviewWindow) -> Result<(), String> {
+    undo::clear(&window)
+}
+
+// The calling window's undo state, kept across reloads of its page. Called
+// once as the page loads.
+#[tauri::command]
+fn restore_undo(window: WebviewWindow) -> Result<undo::UndoState, String> {
+    undo::restore(&window)
+}
+
+// The misspelled words in `text` from the system's spell checker, with
+// offsets in UTF-16 code units like JavaScript's. `language` defaults to
+// the spelling.language preference, or the checker's own.
+#[tauri::command]
+async fn check_spelling(
+    text: String,
+    language: Option<String>,
+) -> Result<Vec<spelling::Misspelling>, String> {
+    spelling::check(text, language).await
+}
+
+#[tauri::command]
+async fn spelling_suggestions(
+    word: String,
+    language: Option<String>,
+) -> Result<Vec<String>, String> {
+    spelling::suggestions(word, language).await
+}
+
+#[tauri::command]
+async fn spelling_languages() -> Result<Vec<String>, String> {
+    spelling::languages().await
+}
+
+// Open the system's emoji and symbols picker, or start dictating, into the
+// focused field of the calling window, bringing it to the front first
+#[tauri::command]
+async fn show_character_palette(window: WebviewWindow) -> Result<(), String> {
+    text_input::show_character_palette(&window).await
+}
+
+#[tauri::command]
+async fn start_dictation(window: WebviewWindow) -> Result<(), String> {
+    text_input::start_dictation(&window).await
+}
+
+// Sent by the page when an input method starts or finishes composing, and
+// passed on to the window as "composition-changed"
+#[tauri::command]
+fn composition_changed(composing: bool, window: WebviewWindow) {
+    text_input::composition_changed(&window, composing);
+}
+
+// Run `schedule.task` every `schedule.interval_secs`, returning its id
+#[tauri::command]
+fn schedule_task(
+    schedule: scheduler::Schedule,
+    app: AppHandle,
+    scheduler: State<scheduler::Scheduler>,
+) -> Result<String, String> {
+    scheduler.schedule(&app, schedule)
+}
+
+#[tauri::command]
+fn cancel_scheduled_task(id: String, scheduler: State<scheduler::Scheduler>) -> Result<(), String> {
+    scheduler.cancel(&id)
+}
+
+#[tauri::command]
+fn list_scheduled_tasks(scheduler: State<scheduler::Scheduler>) -> Vec<scheduler::ScheduledTask> {
+    scheduler.list()
+}
+
+// Start streaming `source` to the calling window, which pulls its chunks
+// from the stream scheme
+#[tauri::command]
+fn open_stream(
+    source: streams::Source,
+    app: AppHandle,
+    window: WebviewWindow,
+    streams: State<streams::Streams>,
+) -> Result<streams::StreamInfo, String> {
+    streams.open(&app, window.label(), source)
+}
+
+#[tauri::command]
+fn close_stream(id: String, streams: State<streams::Streams>) {
+    streams.close(&id);
+}
+
+// Serve the request's raw body at smudge://asset/memory/<name>, returning
+// the name
+#[tauri::command]
+fn put_asset_data(
+    request: tauri::ipc::Request<'_>,
+    assets: State<asset_protocol::AssetProtocol>,
+) -> Result<String, String> {
+    assets.put_request(&request)
+}
+
+#[tauri::command]
+fn remove_asset_data(name: String, assets: State<asset_protocol::AssetProtocol>) -> bool {
+    assets.remove(&name)
+}
+
+// Paths the user picked, opened or dropped, which commands may touch
+#[tauri::command]
+fn list_fs_grants(scope: State<fs_scope::FsScope>) -> Vec<fs_scope::Grant> {
+    scope.list()
+}
+
+#[tauri::command]
+fn revoke_fs_grant(
+    path: String,
+    app: AppHandle,
+    scope: State<fs_scope::FsScope>,
+) -> Result<(), String> {
+    scope.revoke(&app, Path::new(&path))
+}
+
+// The frontend closed a document it opened from outside the notes folder
+#[tauri::command]
+fn close_document(
+    path: String,
+    app: AppHandle,
+    scope: State<fs_scope::FsScope>,
+) -> Result<(), String> {
+    scope.close(&app, Path::new(&path))
+}
+
+// Send the calling window "directory-changed" events for `path`, debounced
+// and coalesced. Returns the id the events carry.
+#[tauri::command]
+fn watch_directory(
+    path: String,
+    recursive: bool,
+    app: AppHandle,
+    window: WebviewWindow,
+    watches: State<directory_watch::DirectoryWatches>,
+) -> Result<String, String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+    watches.watch(&app, window.label(), &path, recursive)
+}
+
+#[tauri::command]
+fn unwatch_directory(
+    id: String,
+    watches: State<directory_watch::DirectoryWatches>,
+) -> Result<(), String> {
+    watches.unwatch(&id)
+}
+
+// Pop up a native menu over the calling window and resolve to the id of the
+// item picked, or null if it was dismissed
+#[tauri::command]
+async fn show_context_menu(
+    items: Vec<context_menu::ContextMenuItem>,
+    position: Option<tauri::LogicalPosition<f64>>,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<Option<String>, String> {
+    context_menu::show(&app, window, items, position).await
+}
+
+// Replace the menu bar (macOS) or the main window's menu; chosen items come
+// back as "app-menu"
+#[tauri::command]
+fn set_app_menu(app: AppHandle, items: Vec<app_menu::AppMenuItem>) -> Result<(), String> {
+    app_menu::set(&app, &items)
+}
+
+// Enable, check or re-title app menu items by id
+#[tauri::command]
+fn update_app_menu(app: AppHandle, updates: Vec<app_menu::ItemUpdate>) -> Result<(), String> {
+    app_menu::update(&app, updates)
+}
+
+// Show these controls on the Touch Bar while the calling window is key;
+// "touch-bar" reports them being used
+#[tauri::command]
+async fn set_touch_bar(
+    items: Vec<touch_bar::TouchBarItem>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    touch_bar::set(&window, items).await
+}
+
+// How the calling window takes part in Spaces, Mission Control and Stage
+// Manager, replacing what it had (macOS)
+#[tauri::command]
+fn set_collection_behavior(
+    behaviors: Vec<spaces::Behavior>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    spaces::apply(&window, &behaviors)
+}
+
+// Take the calling window into or out of full screen, its own Space on
+// macOS
+#[tauri::command]
+fn set_window_full_screen(full_screen: bool, window: WebviewWindow) -> Result<(), String> {
+    spaces::set_full_screen(&window, full_screen)
+}
+
+// Which Space or virtual desktop the calling window is on. Moves between
+// them are announced to every window with "space-changed".
+#[tauri::command]
+async fn get_window_space(window: WebviewWindow) -> Result<spaces::Space, String> {
+    spaces::space(&window).await
+}
+
+// Put two windows side by side, each filling half of the screen the left
+// one is on
+#[tauri::command]
+fn tile_windows(left: String, right: String, app: AppHandle) -> Result<(), String> {
+    let window = |label: &str| {
+        app.get_webview_window(label)
+            .ok_or_else(|| format!("No window labelled {}", label))
+    };
+    spaces::tile(&window(&left)?, &window(&right)?)
+}
+
+// Show a material (sidebar, HUD, Mica, Acrylic...) behind the calling
+// window's page, whose background becomes transparent, or make it opaque
+// again with null
+#[tauri::command]
+fn set_window_material(
+    material: Option<materials::Material>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    sandbox::require("windowMaterials")?;
+    materials::apply(&window, material)
+}
+
+// Materials behind rectangles of the calling window's page, such as a
+// sidebar, replacing the ones it had. Windows uses the first for the whole
+// window.
+#[tauri::command]
+fn set_material_regions(
+    regions: Vec<materials::Region>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    sandbox::require("windowMaterials")?;
+    materials::set_regions(&window, regions)
+}
+
+// Preferences commands: app-wide tuning from the preferences file, which
+// can also be edited by hand. Changes arrive as "preferences-changed".
+
+#[tauri::command]
+fn get_preferences(
+    preferences: State<preferences::PreferenceStore>,
+) -> preferences::PreferencesStatus {
+    preferences.status()
+}
+
+// Set one value by its dotted key, e.g. "network.proxy", or all of them with
+// an empty key, and save the file
+#[tauri::command]
+fn set_preference(
+    key: String,
+    value: serde_json::Value,
+    app: AppHandle,
+    preferences: State<preferences::PreferenceStore>,
+) -> Result<preferences::PreferencesStatus, String> {
+    preferences.set(&app, &key, value)
+}
+
+// Language commands: smudge's translations, picked from the system's
+// languages unless one is chosen. Changes arrive as "locale-changed".
+
+#[tauri::command]
+fn get_locale(i18n: State<i18n::I18n>) -> i18n::LocaleInfo {
+    i18n.info()
+}
+
+// Use `language` over the system's languages, or follow them again with null
+#[tauri::command]
+fn set_locale(
+    language: Option<String>,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<i18n::LocaleInfo, String> {
+    let info = i18n::choose(&app, language.clone())?;
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.language = language;
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())?;
+    Ok(info)
+}
+
+#[tauri::command]
+fn translate(
+    key: String,
+    args: Option<HashMap<String, serde_json::Value>>,
+    i18n: State<i18n::I18n>,
+) -> Result<String, String> {
+    i18n.translate(&key, &args.unwrap_or_default())
+}
+
+// Database commands: the frontend's structured data, as JSON values by
+// namespace and key
+
+fn database(app: &AppHandle) -> Result<tauri::State<'_, db::Database>, String> {
+    app.try_state::<db::Database>()
+        .ok_or_else(|| "The database isn't open".to_string())
+}
+
+#[tauri::command]
+fn db_get(
+    namespace: String,
+    key: String,
+    app: AppHandle,
+) -> Result<Option<serde_json::Value>, String> {
+    database(&app)?.get(&db::frontend_namespace(&namespace)?, &key)
+}
+
+#[tauri::command]
+fn db_put(
+    namespace: String,
+    key: String,
+    value: serde_json::Value,
+    app: AppHandle,
+) -> Result<(), String> {
+    database(&app)?.put(&db::frontend_namespace(&namespace)?, &key, &value)
+}
+
+#[tauri::command]
+fn db_delete(namespace: String, key: String, app: AppHandle) -> Result<(), String> {
+    database(&app)?.delete(&db::frontend_namespace(&namespace)?, &key)
+}
+
+#[tauri::command]
+fn db_list(namespace: String, app: AppHandle) -> Result<Vec<db::Entry>, String> {
+    database(&app)?.entries(&db::frontend_namespace(&namespace)?)
+}
+
+// Write everything in the database to one backup file
+#[tauri::command]
+async fn export_backup(
+    path: String,
+    app: AppHandle,
+    executor: State<'_, executor::Executor>,
+) -> Result<(), String> {
+    fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    executor
+        .run(executor::Priority::Background, move || {
+            database(&app)?.back_up(Path::new(&path))
+        })
+        .await?
+}
+
+// Restore a backup from export_backup. It replaces the database as the app
+// restarts, so nothing in memory writes over it.
+#[tauri::command]
+fn import_backup(path: String, app: AppHandle) -> Result<(), String> {
+    fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+    db::stage_restore(&app, Path::new(&path))?;
+    app.restart()
+}
+
+// Whether the database is encrypted at rest, and locked
+#[tauri::command]
+fn database_encryption(app: AppHandle) -> Result<db::Encryption, String> {
+    Ok(database(&app)?.encryption())
+}
+
+// Encrypt the database in place. Its key is wrapped by one kept in the
+// keychain, and it locks along with the screen.
+#[tauri::command]
+async fn encrypt_database(
+    app: AppHandle,
+    executor: State<'_, executor::Executor>,
+) -> Result<(), String> {
+    executor
+        .run(executor::Priority::Background, move || {
+            database(&app)?.encrypt(&app)
+        })
+        .await?
+}
+
+#[tauri::command]
+fn lock_database(app: AppHandle) -> Result<(), String> {
+    database(&app)?.lock();
+    Ok(())
+}
+
+#[tauri::command]
+fn unlock_database(app: AppHandle) -> Result<(), String> {
+    database(&app)?.unlock(&app)
+}
+
+// Sync commands: the notes folder mirrored against a sync server
+
+#[tauri::command]
+fn get_sync_config(app: AppHandle) -> remote_sync::SyncConfig {
+    remote_sync::config(&app)
+}
+
+// Save the config and restart sync with it. The server's token is stored
+// separately, as the "sync-token" credential.
+#[tauri::command]
+fn set_sync_config(config: remote_sync::SyncConfig, app: AppHandle) -> Result<(), String> {
+    remote_sync::configure(&app, config)
+}
+
+#[tauri::command]
+fn get_sync_status(app: AppHandle) -> remote_sync::SyncStatus {
+    remote_sync::status(&app)
+}
+
+// Sync without waiting for the interval or a retry after a failure
+#[tauri::command]
+fn sync_now(app: AppHandle) -> Result<(), String> {
+    remote_sync::sync_now(&app)
+}
+
+#[tauri::command]
+fn get_sync_conflicts(app: AppHandle) -> Result<Vec<remote_sync::SyncConflict>, String> {
+    remote_sync::conflicts(&app)
+}
+
+// Keep `content` for a conflicted note, or delete it with None; it's sent
+// at the next sync
+#[tauri::command]
+fn resolve_sync_conflict(
+    id: String,
+    content: Option<String>,
+    app: AppHandle,
+) -> Result<(), String> {
+    remote_sync::resolve(&app, &id, content.as_deref())
+}
+
+// HTTP commands: requests from the backend, for what the webview's fetch
+// can't do. Only hosts in the allowlist can be reached.
+
+#[tauri::command]
+async fn http_request(
+    request: http::HttpRequest,
+    app: AppHandle,
+) -> Result<http::HttpResponse, String> {
+    http::request(&app, request).await
+}
+
+// Bundled helper executables, supervised: restarted with backoff when they
+// exit and ended when the app quits. Their output arrives line by line on
+// `sidecar://<name>`.
+
+#[tauri::command]
+fn start_sidecar(
+    name: String,
+    args: Option<Vec<String>>,
+    app: AppHandle,
+) -> Result<sidecar::SidecarStatus, String> {
+    sidecar::start(&app, &name, args.unwrap_or_default())
+}
+
+#[tauri::command]
+fn stop_sidecar(name: String, app: AppHandle) -> Result<(), String> {
+    sidecar::stop(&app, &name)
+}
+
+#[tauri::command]
+async fn write_sidecar_stdin(name: String, data: String, app: AppHandle) -> Result<(), String> {
+    sidecar::write(&app, &name, data.as_bytes()).await
+}
+
+#[tauri::command]
+fn list_sidecars(app: AppHandle) -> Vec<sidecar::SidecarStatus> {
+    sidecar::list(&app)
+}
+
+// Pack files and folders into a .zip or .tar.gz as a cancellable job, with
+// progress in bytes on `job://<id>`. Only zip archives take a password.
+#[tauri::command]
+async fn pack_archive(
+    sources: Vec<String>,
+    destination: String,
+    format: Option<archive::Format>,
+    password: Option<String>,
+    job_id: Option<String>,
+    app: AppHandle,
+    jobs: State<'_, jobs::JobRegistry>,
+) -> Result<archive::Summary, String> {
+    let sources = sources
+        .iter()
+        .map(|source| fs_scope::check(&app, source, fs_scope::Access::Read))
+        .collect::<Result<Vec<_>, _>>()?;
+    let destination = fs_scope::check(&app, &destination, fs_scope::Access::ReadWrite)?;
+    let job = jobs.start(&app, "packArchive", job_id)?;
+    jobs.run(&app, job, move |job| {
+        archive::pack(job, &sources, &destination, format, password.as_deref())
+    })
+    .await
+}
+
+// Extract a .zip, .tar.gz or .tgz into a folder as a cancellable job, with
+// progress in bytes on `job://<id>`. Entries that would land outside the
+// folder fail it.
+#[tauri::command]
+async fn extract_archive(
+    archive: String,
+    destination: String,
+    password: Option<String>,
+    job_id: Option<String>,
+    app: AppHandle,
+    jobs: State<'_, jobs::JobRegistry>,
+) -> Result<archive::Summary, String> {
+    let archive = fs_scope::check(&app, &archive, fs_scope::Access::Read)?;
+    let destination = fs_scope::check(&app, &destination, fs_scope::Access::ReadWrite)?;
+    let job = jobs.start(&app, "extractArchive", job_id)?;
+    jobs.run(&app, job, move |job| {
+        archive::extract(job, &archive, &destination, password.as_deref())
+    })
+    .await
+}
+
+// Download to a file as a cancellable job, with progress in bytes on
+// `job://<id>`. Downloading to the same path again resumes where a failed
+// or cancelled download stopped.
+#[tauri::command]
+async fn http_download(
+    request: http::DownloadRequest,
+    job_id: Option<String>,
+    app: AppHandle,
+    jobs: State<'_, jobs::JobRegistry>,
+) -> Result<http::Download, String> {
+    fs_scope::check(&app, &request.path, fs_scope::Access::ReadWrite)?;
+    let job = jobs.start(&app, "download", job_id)?;
+    let download = http::download(&app, &job, request).await;
+    jobs.finish(&job);
+    download
+}
+
+#[tauri::command]
+fn get_network_status(app: AppHandle) -> connectivity::NetworkStatus {
+    connectivity::current(&app)
+}
+
+// Resolves once the internet can be reached: a route to it and no captive
+// portal in the way
+#[tauri::command]
+async fn wait_for_online(app: AppHandle) {
+    connectivity::wait_for_online(&app).await
+}
+
+// How short of memory the system is and how hot it's running
+#[tauri::command]
+fn current_pressure(app: AppHandle) -> pressure::Pressure {
+    pressure::current(&app)
+}
+
+#[tauri::command]
+fn get_http_allowed_hosts(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(http::client(&app)?.allowed_hosts())
+}
+
+// Hosts are names like "example.com", or "*.example.com" for its subdomains.
+// Any not already allowed are only added if the user allows them; whether
+// they were.
+#[tauri::command]
+async fn set_http_allowed_hosts(
+    hosts: Vec<String>,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<bool, String> {
+    http::confirm_allowed_hosts(&app, hosts, Some(window)).await
+}
+
+#[tauri::command]
+fn clear_http_cookies(app: AppHandle) -> Result<(), String> {
+    http::client(&app)?.clear_cookies()
+}
+
+// Web view data commands: what sites keep in the web views, which share one
+// store, for signing out and resetting. The HTTP client's cookies are apart.
+
+#[tauri::command]
+async fn list_webview_data(window: WebviewWindow) -> Result<Vec<webview_data::SiteData>, String> {
+    webview_data::sites(&window).await
+}
+
+// Remove `kinds` of data for `sites`, or for every site when null
+#[tauri::command]
+async fn clear_webview_data(
+    sites: Option<Vec<String>>,
+    kinds: Vec<webview_data::Kind>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    webview_data::clear(&window, sites, kinds).await
+}
+
+// Async, since reading WebView2's cookies from the main thread deadlocks
+#[tauri::command]
+async fn export_webview_cookies(
+    path: String,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<usize, String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    webview_data::export_cookies(&window, &path)
+}
+
+#[tauri::command]
+async fn import_webview_cookies(
+    path: String,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<usize, String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+    webview_data::import_cookies(&window, &path)
+}
+
+// OAuth commands: sign in to a provider in the system browser with PKCE. The
+// tokens stay in the keychain; the frontend asks for a current access token
+// for each request, refreshed as needed.
+
+#[tauri::command]
+async fn oauth_sign_in(
+    provider: auth::Provider,
+    app: AppHandle,
+) -> Result<auth::Session, String> {
+    auth::sign_in(&app, provider).await
+}
+
+#[tauri::command]
+async fn oauth_access_token(provider: String, app: AppHandle) -> Result<String, String> {
+    auth::access_token(&app, &provider).await
+}
+
+#[tauri::command]
+fn oauth_session(provider: String, app: AppHandle) -> Result<Option<auth::Session>, String> {
+    auth::session(&app, &provider)
+}
+
+#[tauri::command]
+fn oauth_sign_out(provider: String, app: AppHandle) -> Result<(), String> {
+    auth::sign_out(&app, &provider)
+}
+
+// WebSocket commands: connections the backend keeps open, reconnecting when
+// they drop. Messages and state changes arrive on `websocket://<id>`.
+
+// Open a connection, or get back the one with `id` after a reload. Its URL
+// must be ws or wss to a host allowed for HTTP.
+#[tauri::command]
+fn websocket_connect(
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    id: Option<String>,
+    app: AppHandle,
+    websockets: State<websocket::WebSockets>,
+) -> Result<websocket::WebSocketStatus, String> {
+    websockets.connect(&app, &url, headers.unwrap_or_default(), id)
+}
+
+// Send a message, queued while the connection is down
+#[tauri::command]
+fn websocket_send(
+    id: String,
+    payload: websocket::Payload,
+    websockets: State<websocket::WebSockets>,
+) -> Result<(), String> {
+    websockets.send(&id, payload)
+}
+
+#[tauri::command]
+fn websocket_close(id: String, websockets: State<websocket::WebSockets>) -> Result<(), String> {
+    websockets.close(&id)
+}
+
+// The messages received after `afterSeq` that are still kept
+#[tauri::command]
+fn websocket_replay(
+    id: String,
+    after_seq: u64,
+    websockets: State<websocket::WebSockets>,
+) -> Result<Vec<websocket::ReceivedMessage>, String> {
+    websockets.replay(&id, after_seq)
+}
+
+#[tauri::command]
+fn list_websockets(websockets: State<websocket::WebSockets>) -> Vec<websocket::WebSocketStatus> {
+    websockets.list()
+}
+
+// Shortcuts commands
+
+// Intents Shortcuts ran before the frontend was listening, or since it was
+// last signalled
+#[tauri::command]
+fn get_pending_intents(intents: State<shortcuts::Intents>) -> Vec<shortcuts::Invocation> {
+    shortcuts::take_pending(&intents)
+}
+
+// Finish an intent, handing its outcome back to the shortcut that ran it
+#[tauri::command]
+fn complete_intent(
+    id: u64,
+    completion: shortcuts::Completion,
+    intents: State<shortcuts::Intents>,
+) -> Result<(), String> {
+    shortcuts::complete(&intents, id, completion)
+}
+
+// Open file routing commands
+
+// The folder or file the calling window owns: files opened from Finder
+// inside it are sent to this window as "paths-opened" (macOS). None gives it
+// up.
+#[tauri::command]
+fn set_window_scope(path: Option<String>, window: WebviewWindow) -> Result<(), String> {
+    url_routing::set_scope(&window, path.map(PathBuf::from))
+}
+
+// Permission commands
+
+// Every privacy permission's status; always granted off macOS
+#[tauri::command]
+fn get_permissions() -> permissions::Statuses {
+    permissions::statuses()
+}
+
+// Ask for a permission, opening its System Settings pane when only the user
+// can turn it on. Returns the status before asking; changes arrive as
+// "permissions-changed".
+#[tauri::command]
+fn request_permission(kind: permissions::Kind) -> permissions::Status {
+    permissions::request(kind)
+}
+
+// Global shortcut commands
+
+#[tauri::command]
+fn get_quick_capture_shortcut(state: State<AppState>) -> Option<String> {
+    state
+        .app_config
+        .read()
+        .expect("app_config read lock")
+        .quick_capture_shortcut
+        .clone()
+}
+
+// None turns quick capture off. Fails, keeping the old shortcut, if the new
+// one is taken.
+#[tauri::command]
+async fn set_quick_capture_shortcut(
+    shortcut: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let name = global_shortcuts::QUICK_CAPTURE.to_string();
+    match &shortcut {
+        Some(accelerator) => global_shortcuts::register(&app, name, accelerator.clone()).await?,
+        None => global_shortcuts::unregister(&app, name)?,
+    }
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.quick_capture_shortcut = shortcut;
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())
+}
+
+// Login item commands
+
+#[tauri::command]
+fn launch_at_login(app: AppHandle) -> Result<bool, String> {
+    login_item::launch_at_login(&app)
+}
+
+#[tauri::command]
+fn set_launch_at_login(enabled: bool, app: AppHandle) -> Result<(), String> {
+    login_item::set_launch_at_login(&app, enabled)
+}
+
+// Credential commands: secrets in the OS keychain, for the keys in
+// credentials::ALLOWED only
+
+#[tauri::command]
+fn store_credential(key: String, secret: String, app: AppHandle) -> Result<(), String> {
+    credentials::store(&app, &key, &secret)
+}
+
+#[tauri::command]
+fn retrieve_credential(key: String, app: AppHandle) -> Result<Option<String>, String> {
+    credentials::retrieve(&app, &key)
+}
+
+#[tauri::command]
+fn delete_credential(key: String, app: AppHandle) -> Result<(), String> {
+    credentials::delete(&app, &key)
+}
+
+// Crash report commands. Reports only leave the machine through
+// upload_crash_report, when the user chose to send one.
+
+#[tauri::command]
+async fn upload_crash_report(id: String, app: AppHandle) -> Result<(), String> {
+    crash::upload(&app, &id).await
+}
+
+#[tauri::command]
+fn discard_crash_report(id: String, app: AppHandle) -> Result<(), String> {
+    crash::discard(&app, &id)
+}
+
+// Window tab commands, macOS only
+
+#[tauri::command]
+fn add_tabbed_window(
+    tab: String,
+    window: WebviewWindow,
+    windows: State<windows::Windows>,
+) -> Result<(), String> {
+    window_tabs::add(&window, &tab)?;
+    windows.tabbed(window.label(), &tab);
+    Ok(())
+}
+
+#[tauri::command]
+fn select_next_tab(window: WebviewWindow) -> Result<(), String> {
+    window_tabs::select_next(&window)
+}
+
+#[tauri::command]
+fn merge_all_windows(
+    window: WebviewWindow,
+    windows: State<windows::Windows>,
+) -> Result<(), String> {
+    window_tabs::merge_all(&window)?;
+    windows.merged(window.label());
+    Ok(())
+}
+
+// Dragging notes out to the Finder or Explorer, from a list row's dragstart;
+// "drag-ended" follows with what the destination did
+
+#[tauri::command]
+async fn start_drag(
+    items: Vec<drag::Item>,
+    image: Option<String>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    drag::start(&window, items, image)
+}
+
+// Quick Look commands: the panel on macOS, a preview handler window on
+// Windows; "quick-look" reports it opening and closing
+
+#[tauri::command]
+async fn preview_file(paths: Vec<String>, app: AppHandle) -> Result<(), String> {
+    for path in &paths {
+        fs_scope::check(&app, path, fs_scope::Access::Read)?;
+    }
+    quick_look::preview(&app, paths).await
+}
+
+#[tauri::command]
+fn close_preview(app: AppHandle) -> Result<(), String> {
+    quick_look::close(&app)
+}
+
+// The OS share sheet, anchored at a point in the window on macOS;
+// "share-completed" reports how it went under the returned id
+
+#[tauri::command]
+async fn share_items(
+    items: Vec<share::Item>,
+    position: tauri::LogicalPosition<f64>,
+    window: WebviewWindow,
+) -> Result<u64, String> {
+    share::share(&window, items, position).await
+}
+
+// Spotlight entries for things other than notes, which are indexed as
+// they're listed and saved. Choosing one opens its link; no-ops off macOS
+
+#[tauri::command]
+fn index_searchable_items(items: Vec<spotlight::Item>) -> Result<(), String> {
+    spotlight::index(items)
+}
+
+#[tauri::command]
+fn remove_searchable_items(urls: Vec<String>) -> Result<(), String> {
+    spotlight::remove(urls)
+}
+
+// Print the window or a PDF, through the print dialog unless the options
+// are silent; "print-completed" reports how it went under the returned id
+
+#[tauri::command]
+async fn print(
+    source: print::Source,
+    options: Option<print::Options>,
+    window: WebviewWindow,
+) -> Result<u64, String> {
+    print::print(&window, source, options.unwrap_or_default()).await
+}
+
+// Render a window or a page of HTML as a paginated PDF, without any dialog:
+// saved at `output`, or handed back as bytes
+
+#[tauri::command]
+async fn render_to_pdf(
+    source: pdf::Source,
+    options: Option<pdf::Options>,
+    output: String,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<String, String> {
+    let output = fs_scope::check(&app, &output, fs_scope::Access::ReadWrite)?;
+    pdf::render(&app, &window, source, &output, options.unwrap_or_default()).await?;
+    Ok(output.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+async fn render_pdf_bytes(
+    source: pdf::Source,
+    options: Option<pdf::Options>,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<tauri::ipc::Response, String> {
+    static NEXT_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let output = std::env::temp_dir().join(format!(
+        "smudge-render-{}-{}.pdf",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    let rendered = pdf::render(&app, &window, source, &output, options.unwrap_or_default()).await;
+    let bytes = rendered.and_then(|()| std::fs::read(&output).map_err(|e| e.to_string()));
+    let _ = std::fs::remove_file(&output);
+    Ok(tauri::ipc::Response::new(bytes?))
+}
+
+// Move the calling window onto another display, by the name
+// availableMonitors gives it; "monitors-changed" reports displays coming,
+// going and being rearranged
+
+#[tauri::command]
+fn move_to_monitor(
+    name: String,
+    mode: Option<placement::MoveMode>,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    placement::move_to_monitor(&window.as_ref().window(), &name, mode.unwrap_or_default())
+}
+
+// Appearance commands: "light" or "dark" forces one, null follows the OS
+
+#[tauri::command]
+fn set_appearance(mode: Option<String>, app: AppHandle) -> Result<(), String> {
+    appearance::set_app(&app, appearance::parse(mode.as_deref()));
+    Ok(())
+}
+
+#[tauri::command]
+fn set_window_appearance(
+    label: String,
+    mode: Option<String>,
+    app: AppHandle,
+) -> Result<(), String> {
+    appearance::set_window(&app, &label, appearance::parse(mode.as_deref()))
+}
+
+// Shortcuts the frontend registers are reported back by name with the
+// "global-shortcut" event. They last until smudge quits.
+#[tauri::command]
+async fn register_global_shortcut(
+    name: String,
+    accelerator: String,
+    app: AppHandle,
+) -> Result<(), String> {
+    global_shortcuts::register(&app, name, accelerator).await
+}
+
+#[tauri::command]
+fn unregister_global_shortcut(name: String, app: AppHandle) -> Result<(), String> {
+    global_shortcuts::unregister(&app, name)
+}
+
+// Notification commands
+
+// What the user does with the notification comes back as a
+// "notification-response" event with its id
+#[tauri::command]
+async fn show_notification(
+    notification: notifications::NotificationRequest,
+    app: AppHandle,
+) -> Result<(), String> {
+    notifications::show(&app, notification).await
+}
+
+#[tauri::command]
+fn remove_notification(id: String, app: AppHandle) -> Result<(), String> {
+    notifications::remove(&app, id)
+}
+
+// Update commands
+
+#[tauri::command]
+async fn check_for_update(
+    app: AppHandle,
+    allow_downgrade: Option<bool>,
+) -> Result<Option<updates::UpdateInfo>, String> {
+    // Builds that can't update themselves never have one
+    if sandbox::require("updates").is_err() {
+        return Ok(None);
+    }
+    updates::check(&app, allow_downgrade.unwrap_or(false)).await
+}
+
+#[tauri::command]
+async fn download_update(app: AppHandle) -> Result<updates::Verification, String> {
+    sandbox::require("updates")?;
+    updates::download(&app).await
+}
+
+#[tauri::command]
+async fn install_update(app: AppHandle) -> Result<updates::Verification, String> {
+    sandbox::require("updates")?;
+    updates::install(&app).await
+}
+
+/// Start the installed update. Windows installers restart smudge themselves.
+#[tauri::command]
+fn restart_after_update(app: AppHandle) {
+    log::info!("restarting into the update");
+    app.restart();
+}
+
+// Quarantine commands
+
+#[tauri::command]
+fn get_quarantine_policy(state: State<AppState>) -> quarantine::QuarantinePolicy {
+    state.app_config.read().expect("app_config read lock").quarantine_policy
+}
+
+#[tauri::command]
+fn set_quarantine_policy(
+    policy: quarantine::QuarantinePolicy,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.quarantine_policy = policy;
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_quarantine_info(
+    app: AppHandle,
+    path: String,
+) -> Result<quarantine::QuarantineInfo, String> {
+    fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+    quarantine::inspect(Path::new(&path))
+}
+
+/// Callers confirm with the user first; this removes the OS warning on open
+#[tauri::command]
+async fn strip_quarantine(app: AppHandle, path: String) -> Result<(), String> {
+    sandbox::require("stripQuarantine")?;
+    fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    quarantine::strip(Path::new(&path))
+}
+
+// Sandbox commands
+
+// Which features this build can offer, e.g. to hide or explain missing ones
+#[tauri::command]
+fn get_capabilities() -> sandbox::CapabilityReport {
+    sandbox::report()
+}
+
+// Trash commands
+
+#[tauri::command]
+async fn move_to_trash(
+    app: AppHandle,
+    paths: Vec<String>,
+) -> Result<Vec<trash::TrashHandle>, String> {
+    for path in &paths {
+        fs_scope::check(&app, path, fs_scope::Access::ReadWrite)?;
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        paths
+            .iter()
+            .map(|path| trash::move_to_trash(Path::new(path)))
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn restore_from_trash(handle: trash::TrashHandle) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || trash::restore(&handle))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+// File association commands
+
+fn app_id(app: &AppHandle) -> file_associations::AppId<'_> {
+    file_associations::AppId {
+        bundle_id: &app.config().identifier,
+    }
+}
+
+#[tauri::command]
+async fn list_file_associations(app: AppHandle) -> Vec<file_associations::Association> {
+    let app_id = app_id(&app);
+    file_associations::FILE_TYPES
+        .iter()
+        .map(|file_type| file_associations::status(file_type, &app_id))
+        .collect()
+}
+
+#[tauri::command]
+async fn claim_file_association(
+    id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<file_associations::AssociationChange, String> {
+    claim_file_type(&app, &state, file_associations::find(&id)?)
+}
+
+/// Claim `file_type`, remembering who had it so releasing can hand it back
+fn claim_file_type(
+    app: &AppHandle,
+    state: &AppState,
+    file_type: &'static file_associations::FileType,
+) -> Result<file_associations::AssociationChange, String> {
+    let app_id = app_id(app);
+    let (previous, needs_confirmation) = file_associations::claim(file_type, &app_id)?;
+    if let Some(previous) = previous {
+        let mut app_config = state.app_config.write().expect("app_config write lock");
+        app_config
+            .previous_handlers
+            .insert(file_type.id.to_string(), previous);
+        save_app_config(app, &app_config).map_err(|e| e.to_string())?;
+    }
+    Ok(file_associations::AssociationChange {
+        association: file_associations::status(file_type, &app_id),
+        needs_confirmation,
+    })
+}
+
+#[tauri::command]
+async fn is_default_handler(ext: String, app: AppHandle) -> Result<bool, String> {
+    file_associations::is_default_handler(&ext, &app_id(&app))
+}
+
+#[tauri::command]
+async fn set_default_handler(
+    ext: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<file_associations::AssociationChange, String> {
+    claim_file_type(&app, &state, file_associations::find_by_extension(&ext)?)
+}
+
+#[tauri::command]
+async fn release_file_association(
+    id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<file_associations::AssociationChange, String> {
+    let file_type = file_associations::find(&id)?;
+    let app_id = app_id(&app);
+    let previous = state
+        .app_config
+        .read()
+        .expect("app_config read lock")
+        .previous_handlers
+        .get(&id)
+        .cloned();
+    file_associations::release(file_type, &app_id, previous.as_deref())?;
+    {
+        let mut app_config = state.app_config.write().expect("app_config write lock");
+        if app_config.previous_handlers.remove(&id).is_some() {
+            save_app_config(&app, &app_config).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(file_associations::AssociationChange {
+        association: file_associations::status(file_type, &app_id),
+        needs_confirmation: false,
+    })
+}
+
+// Presentation mode commands
+
+#[tauri::command]
+fn list_displays(app: AppHandle) -> Result<Vec<presentation::Display>, String> {
+    presentation::displays(&app)
+}
+
+#[tauri::command]
+fn start_presentation(
+    display: Option<String>,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<(), String> {
+    presentation::start(&app, &window, display.as_deref())?;
+    let _ = app.emit("presentation", true);
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_presentation(app: AppHandle) -> Result<(), String> {
+    presentation::stop(&app)?;
+    let _ = app.emit("presentation", false);
+    Ok(())
+}
+
+#[tauri::command]
+fn is_presenting(app: AppHandle) -> bool {
+    app.state::<presentation::PresentationState>()
+        .active_label()
+        .is_some()
+}
+
+// Dock commands
+
+// Hide the Dock icon to keep running from the menu bar and windows alone, or
+// bring it back. Quick toggles are debounced by tao.
+#[tauri::command]
+fn set_dock_visible(app: AppHandle, visible: bool) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    app.set_dock_visibility(visible).map_err(|e| e.to_string())?;
+    app.state::<DockVisible>().0.store(visible, Ordering::SeqCst);
+    let _ = app.emit("dock-visibility-changed", visible);
+    Ok(())
+}
+
+#[tauri::command]
+fn is_dock_visible(state: State<DockVisible>) -> bool {
+    state.0.load(Ordering::SeqCst)
+}
+
+// List "New Note" and recent notes in the Dock icon's menu (macOS)
+#[tauri::command]
+fn set_dock_menu(app: AppHandle, notes: Vec<dock_menu::DockMenuNote>) -> Result<(), String> {
+    dock_menu::set(&app, notes)
+}
+
+// Tray commands
+
+#[tauri::command]
+fn get_tray_visible(state: State<AppState>) -> bool {
+    state.app_config.read().expect("app_config read lock").tray_icon
+}
+
+// Show smudge's icon in the menu bar (macOS) or notification area. With the
+// Dock icon hidden too, smudge runs as a menu bar app. The menu starts empty.
+#[tauri::command]
+fn set_tray_visible(visible: bool, app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    tray::set_visible(&app, visible)?;
+    let _ = app.emit("tray-visibility-changed", visible);
+    let mut app_config = state.app_config.write().expect("app_config write lock");
+    app_config.tray_icon = visible;
+    save_app_config(&app, &app_config).map_err(|e| e.to_string())
+}
+
+// Rebuild the tray icon's menu; chosen items come back as "tray-menu"
+#[tauri::command]
+fn set_tray_menu(app: AppHandle, items: Vec<tray::TrayMenuItem>) -> Result<(), String> {
+    tray::set_menu(&app, items)
+}
+
+// Text on the Dock icon, e.g. an unread count; None removes it (macOS)
+#[tauri::command]
+fn set_badge_label(app: AppHandle, label: Option<String>) -> Result<(), String> {
+    app_icon::set_badge_label(&app, label)
+}
+
+// Progress from 0 to 1 on the Dock icon (macOS) or taskbar button (Windows);
+// None removes it
+#[tauri::command]
+fn set_dock_progress(app: AppHandle, progress: Option<f64>) -> Result<(), String> {
+    app_icon::set_progress(&app, progress)
+}
+
+// Empty "Open Recent" and the Dock menu's recent documents (macOS) or the
+// jump list (Windows)
+#[tauri::command]
+fn clear_recent_documents(app: AppHandle) -> Result<(), String> {
+    recent_documents::clear(&app)
+}
+
+// Run another app's Services menu item on text and return what it sent
+// back (macOS)
+#[tauri::command]
+async fn perform_service(
+    app: AppHandle,
+    menu_item: String,
+    text: String,
+) -> Result<Option<String>, String> {
+    services_menu::perform(&app, menu_item, text).await
+}
+
+// Accessibility commands
+
+#[tauri::command]
+fn get_high_contrast(state: State<accessibility::AccessibilityState>) -> bool {
+    state.high_contrast()
+}
+
+#[tauri::command]
+fn get_reduce_motion(state: State<accessibility::AccessibilityState>) -> bool {
+    state.reduce_motion()
+}
+
+#[tauri::command]
+fn get_reduce_transparency(state: State<accessibility::AccessibilityState>) -> bool {
+    state.reduce_transparency()
+}
+
+#[tauri::command]
+fn get_text_scale(state: State<accessibility::AccessibilityState>) -> f64 {
+    state.text_scale()
+}
+
+// Have the screen reader speak `message`, cutting off what it's saying with
+// `interrupt`
+#[tauri::command]
+fn announce(message: String, interrupt: Option<bool>, app: AppHandle) -> Result<(), String> {
+    accessibility::announce(&app, message, interrupt.unwrap_or(false))
+}
+
+// Diagnostics commands
+
+// Record the calling window to an mp4 that the next diagnostics bundle picks up
+#[tauri::command]
+async fn record_window(
+    seconds: u32,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<String, String> {
+    if seconds == 0 || seconds > diagnostics::MAX_RECORDING_SECONDS {
+        return Err(format!(
+            "Recordings can be 1 to {} seconds long",
+            diagnostics::MAX_RECORDING_SECONDS
+        ));
+    }
+    sandbox::require("screenRecording")?;
+    diagnostics::ensure_recorder()?;
+    diagnostics::ensure_permission()?;
+
+    let target = diagnostics::target(&window)?;
+    let output = diagnostics::recording_path(&app)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        diagnostics::record(target, seconds, &output)?;
+        Ok(output.to_string_lossy().into_owned())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Screenshot the window `label`, or the calling window, as a PNG `data:` URL,
+// or save it at `path` and return that
+#[tauri::command]
+async fn capture_window(
+    label: Option<String>,
+    path: Option<String>,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<String, String> {
+    let path = path
+        .map(|path| fs_scope::check(&app, &path, fs_scope::Access::ReadWrite))
+        .transpose()?;
+    let window = match label {
+        Some(label) => app
+            .get_webview_window(&label)
+            .ok_or_else(|| format!("No window {}", label))?,
+        None => window,
+    };
+    let png = capture::window(&window).await?;
+    capture::deliver(png, path.as_deref())
+}
+
+// Screenshot the monitor named `monitor`, or the calling window's, like
+// `capture_window`
+#[tauri::command]
+async fn capture_screen(
+    monitor: Option<String>,
+    path: Option<String>,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<String, String> {
+    sandbox::require("screenRecording")?;
+    let path = path
+        .map(|path| fs_scope::check(&app, &path, fs_scope::Access::ReadWrite))
+        .transpose()?;
+    let png = capture::screen(&window, monitor.as_deref()).await?;
+    capture::deliver(png, path.as_deref())
+}
+
+// Screenshot the window `label`, or the calling window, into the next
+// diagnostics bundle, and return the file's path
+#[tauri::command]
+async fn attach_screenshot(
+    label: Option<String>,
+    app: AppHandle,
+    window: WebviewWindow,
+) -> Result<String, String> {
+    let window = match label {
+        Some(label) => app
+            .get_webview_window(&label)
+            .ok_or_else(|| format!("No window {}", label))?,
+        None => window,
+    };
+    let png = capture::window(&window).await?;
+    capture::deliver(png, Some(&diagnostics::screenshot_path(&app)?))
+}
+
+// Play one of the system's sounds, or a sound file, without waiting for it
+#[tauri::command]
+async fn play_sound(sound: audio::Sound, app: AppHandle) -> Result<(), String> {
+    if let audio::Sound::File { path } = &sound {
+        fs_scope::check(&app, path, fs_scope::Access::Read)?;
+    }
+    audio::play(&app, sound).await
+}
+
+#[tauri::command]
+fn set_app_volume(volume: f64) -> Result<(), String> {
+    audio::set_volume(volume)
+}
+
+#[tauri::command]
+fn get_app_volume() -> f64 {
+    audio::volume()
+}
+
+// Describe what the media preview is playing to the system's media controls,
+// which sends the media keys to the windows as `media-command` until it's
+// cleared with null
+#[tauri::command]
+async fn set_now_playing(
+    now_playing: Option<audio::NowPlaying>,
+    app: AppHandle,
+) -> Result<(), String> {
+    audio::set_now_playing(&app, now_playing).await
+}
+
+#[tauri::command]
+async fn create_diagnostics_bundle(
+    app: AppHandle,
+    executor: State<'_, executor::Executor>,
+) -> Result<String, String> {
+    let bundle = executor
+        .run(executor::Priority::Background, move || {
+            diagnostics::create_bundle(&app)
+        })
+        .await??;
+    Ok(bundle.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+fn get_platform_diagnostics() -> Vec<String> {
+    diagnostics::platform_diagnostics()
+}
+
+// Turn recording of platform diagnostics on or off; it's on by default
+#[tauri::command]
+fn set_platform_diagnostics(enabled: bool) {
+    tauri_runtime_wry::tao::diag::set_enabled(enabled);
+}
+
+#[tauri::command]
+fn get_recent_logs() -> Vec<String> {
+    logging::recent()
+}
+
+// Copy the log, including rotated files, to a file the user picked
+#[tauri::command]
+async fn export_logs(
+    app: AppHandle,
+    path: String,
+    executor: State<'_, executor::Executor>,
+) -> Result<(), String> {
+    fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    executor
+        .run(executor::Priority::Background, move || {
+            logging::export(Path::new(&path))
+        })
+        .await?
+}
+
+// Write the recent command, job and timed intervals to `path` as a Chrome
+// trace, for chrome://tracing, Perfetto or speedscope
+#[tauri::command]
+fn export_profile(app: AppHandle, path: String) -> Result<(), String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    profiling::export(&path)
+}
+
+#[tauri::command]
+fn clear_profile() {
+    profiling::clear();
+}
+
+// "error", "warn", "info", "debug" or "trace", until the app quits
+#[tauri::command]
+fn set_log_level(level: String) -> Result<(), String> {
+    logging::set_level(logging::parse_level(&level)?);
+    Ok(())
+}
+
+// Called by the main window once it has painted, which ends the launch.
+// Warm webviews are only created after this.
+#[tauri::command]
+fn record_first_paint(app: AppHandle, timeline: State<startup::StartupTimeline>) {
+    let launching = timeline.report().total_ms.is_none();
+    timeline.finish(startup::FIRST_PAINT);
+    timeline.finish(startup::LAUNCH);
+    // Reloads of the webview paint again, but the launch only ends once
+    if launching {
+        for span in timeline.report().spans {
+            if let Some(end_ms) = span.end_ms {
+                let duration = Duration::from_secs_f64((end_ms - span.start_ms).max(0.0) / 1000.0);
+                telemetry::record(&app, &format!("startup.{}", span.name), duration);
+            }
+        }
+    }
+    webview_pool::start(&app);
+}
+
+// Counters and timings kept while telemetry is on in the preferences, for
+// the diagnostics pane; exported only on request, for a bug report
+
+#[tauri::command]
+fn get_telemetry(app: AppHandle) -> telemetry::Report {
+    telemetry::report(&app)
+}
+
+#[tauri::command]
+fn reset_telemetry(app: AppHandle) {
+    telemetry::reset(&app)
+}
+
+#[tauri::command]
+fn export_telemetry(path: String, app: AppHandle) -> Result<(), String> {
+    let path = fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    telemetry::export(&app, &path)
+}
+
+#[tauri::command]
+fn startup_report(timeline: State<startup::StartupTimeline>) -> startup::StartupReport {
+    timeline.report()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Health {
+    executor: executor::ExecutorMetrics,
+    search_index_ready: bool,
+}
+
+#[tauri::command]
+fn get_health(state: State<AppState>, executor: State<executor::Executor>) -> Health {
+    Health {
+        executor: executor.metrics(),
+        search_index_ready: state
+            .search_index
+            .lock()
+            .expect("search index mutex")
+            .is_some(),
+    }
+}
+
+// Which commands take MessagePack, and from what payload size
+#[tauri::command]
+fn ipc_binary_codec() -> ipc_codec::BinaryCodec {
+    ipc_codec::negotiate()
+}
+
+// Menu accessibility commands
+
+// Register titles and help for menu items ahead of building the menu
+#[tauri::command]
+fn set_menu_accessibility(items: Vec<menus::ItemAccessibility>) {
+    menus::set_all(items);
+}
+
+#[tauri::command]
+fn clear_menu_accessibility(ids: Vec<String>) {
+    menus::clear(&ids);
+}
+
+// Handoff commands
+
+#[tauri::command]
+fn handoff_advertise(
+    document: handoff::HandoffDocument,
+    window: WebviewWindow,
+    state: State<AppState>,
+) -> Result<(), String> {
+    if handoff_enabled(&state) {
+        handoff::advertise(&window, &document)
+    } else {
+        handoff::clear(&window)
+    }
+}
+
+#[tauri::command]
+fn handoff_clear(window: WebviewWindow) -> Result<(), String> {
+    handoff::clear(&window)
+}
+
+#[tauri::command]
+fn mark_frontend_ready(app: AppHandle, state: State<FrontendReady>) {
+    let mut ready = state.0.lock().expect("frontend ready mutex");
+    *ready = true;
+    updates::confirm_launch(&app);
+    crash::frontend_ready(&app);
+}
+
+/// The frontend's answer to "quit-requested": whether everything was saved
+#[tauri::command]
+fn reply_to_quit(app: AppHandle, saved: bool) {
+    quit::finish(&app, saved);
+}
+
+#[tauri::command]
+async fn read_external_file(app: AppHandle, path: String) -> Result<String, String> {
+    fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+    materialize_file(&app, Path::new(&path)).await?;
+    quarantine::opened(&app, &[PathBuf::from(&path)]);
+
+    let content = fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let _ = recent_documents::add(&app, PathBuf::from(&path));
+    Ok(content)
+}
+
+#[tauri::command]
+async fn write_external_file(app: AppHandle, path: String, content: String) -> Result<(), String> {
+    fs_scope::check(&app, &path, fs_scope::Access::ReadWrite)?;
+    fs::write(&path, &content)
+        .await
+        .map_err(|e| format!("Failed to write file: {}", e))
+}
+
+#[tauri::command]
+async fn import_external_file_to_notes(app: AppHandle, path: String) -> Result<String, String> {
+    fs_scope::check(&app, &path, fs_scope::Access::Read)?;
+    let source_path = PathBuf::from(&path);
+    if !is_markdown_file(&source_path) {
+        return Err("External file is not markdown".to_string());
+    }
+    let note_id = ingest_file(&app, &source_path, &[]).await?;
+    let _ = recent_documents::add(&app, source_path);
+    Ok(note_id)
+}
+
+// Stable ID per source path so re-importing the same file updates one imported note.
+fn imported_note_id(source_path: &Path) -> String {
+    let base_name = source_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("imported");
+    let base_id = sanitize_filename(base_name);
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    source_path.to_string_lossy().hash(&mut hasher);
+    let path_hash = hasher.finish() as u32;
+    format!("{}-{:08x}", base_id, path_hash)
+}
+
+// Ingest pipeline: copy an outside file into the notes folder as a note,
+// indexing and caching it. Returns the note ID.
+async fn ingest_file(app: &AppHandle, source_path: &Path, tags: &[String]) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let folder = {
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config
+            .notes_folder
+            .clone()
+            .ok_or("Notes folder not set")?
+    };
+
+    materialize_file(app, source_path).await?;
+    if !source_path.exists() || !source_path.is_file() {
+        return Err("External file not found".to_string());
+    }
+
+    let folder_path = PathBuf::from(&folder);
+    if source_path.starts_with(&folder_path) {
+        if let Some(stem) = source_path.file_stem().and_then(|s| s.to_str()) {
+            return Ok(stem.to_string());
+        }
+    }
+
+    let content = fs::read_to_string(source_path)
+        .await
+        .map_err(|e| format!("Failed to read external file for import: {}", e))?;
+    let content = watch_folders::apply_tags(&content, tags);
+
+    let final_id = imported_note_id(source_path);
+    let target_path = folder_path.join(format!("{}.md", final_id));
+
+    fs::write(&target_path, &content)
+        .await
+        .map_err(|e| format!("Failed to import external file: {}", e))?;
+    let quarantine_policy = state
+        .app_config
+        .read()
+        .expect("app_config read lock")
+        .quarantine_policy;
+    let _ = quarantine::carry_over(quarantine_policy, source_path, &target_path);
+
+    let metadata = fs::metadata(&target_path)
+        .await
+        .map_err(|e| format!("Failed to read imported file metadata: {}", e))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let title = extract_title(&content);
+
+    {
+        let index = state.search_index.lock().expect("search index mutex");
+        if let Some(ref search_index) = *index {
+            let _ = search_index.index_note(&final_id, &title, &content, modified);
+        }
+    }
+
+    {
+        let mut cache = state.notes_cache.write().expect("cache write lock");
+        cache.insert(
+            final_id.clone(),
+            NoteMetadata {
+                id: final_id.clone(),
+                title,
+                preview: generate_preview(&content),
+                modified,
+                is_placeholder: false,
+            },
+        );
+    }
+
+    Ok(final_id)
+}
+
+// Job commands
+
+#[tauri::command]
+fn list_jobs(jobs: State<jobs::JobRegistry>) -> Vec<jobs::JobProgress> {
+    jobs.list()
+}
+
+#[tauri::command]
+fn pause_job(id: String, jobs: State<jobs::JobRegistry>) -> Result<(), String> {
+    jobs.pause(&id)
+}
+
+#[tauri::command]
+fn resume_job(id: String, jobs: State<jobs::JobRegistry>) -> Result<(), String> {
+    jobs.resume(&id)
+}
+
+#[tauri::command]
+fn cancel_job(id: String, jobs: State<jobs::JobRegistry>) -> Result<(), String> {
+    jobs.cancel(&id)
+}
+
+// Start a named job in the background and return its ID at once. Progress,
+// then the result or error, arrive on `job://<id>`.
+#[tauri::command]
+fn submit_job(
+    app: AppHandle,
+    name: String,
+    args: Option<serde_json::Value>,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    jobs::submit(&app, &name, args.unwrap_or(serde_json::Value::Null), job_id)
+}
+
+// Apply one operation to many files as a cancellable job. Progress is emitted
+// on `job://<id>`; the report lists the outcome for every file.
+#[tauri::command]
+async fn batch_process(
+    app: AppHandle,
+    paths: Vec<String>,
+    operation: batch::BatchOperation,
+    job_id: Option<String>,
+    jobs: State<'_, jobs::JobRegistry>,
+) -> Result<batch::BatchReport, String> {
+    for path in &paths {
+        fs_scope::check(&app, path, fs_scope::Access::Read)?;
+    }
+    if let batch::BatchOperation::Export { destination }
+    | batch::BatchOperation::ExportHtml { destination } = &operation
+    {
+        fs_scope::check(&app, destination, fs_scope::Access::ReadWrite)?;
+    }
+    run_batch(app, paths, operation, job_id, jobs).await
+}
+
+// batch_process without checking the paths against the scope, for callers
+// the user authorized another way, like scripts sending Apple Events
+async fn run_batch(
+    app: AppHandle,
+    paths: Vec<String>,
+    operation: batch::BatchOperation,
+    job_id: Option<String>,
+    jobs: State<'_, jobs::JobRegistry>,
+) -> Result<batch::BatchReport, String> {
+    let job = jobs.start(&app, "batch", job_id)?;
+    let title = match &operation {
+        batch::BatchOperation::Import { .. } => "Import finished",
+        batch::BatchOperation::Export { .. } | batch::BatchOperation::ExportHtml { .. } => {
+            "Export finished"
+        }
+    };
+
+    let report = match operation {
+        batch::BatchOperation::Import { tags } => {
+            let tags = Arc::new(tags);
+            let app = app.clone();
+            batch::run(Arc::clone(&job), paths, move |path| {
+                let app = app.clone();
+                let tags = Arc::clone(&tags);
+                async move { ingest_file(&app, &path, &tags).await }
+            })
+            .await
+        }
+        batch::BatchOperation::Export { destination } => {
+            let destination = PathBuf::from(destination);
+            if let Err(e) = fs::create_dir_all(&destination).await {
+                jobs.finish(&job);
+                return Err(format!("Failed to create export folder: {}", e));
+            }
+            batch::run(Arc::clone(&job), paths, move |path| {
+                let destination = destination.clone();
+                async move { batch::export_file(&path, &destination).await }
+            })
+            .await
+        }
+        batch::BatchOperation::ExportHtml { destination } => {
+            let destination = PathBuf::from(destination);
+            if let Err(e) = fs::create_dir_all(&destination).await {
+                jobs.finish(&job);
+                return Err(format!("Failed to create export folder: {}", e));
+            }
+            batch::run(Arc::clone(&job), paths, move |path| {
+                let destination = destination.clone();
+                async move { batch::export_html(&path, &destination, extract_title).await }
+            })
+            .await
+        }
+    };
+
+    jobs.finish(&job);
+    if activation::is_backgrounded(&app) {
+        let mut body = format!("{} succeeded, {} failed", report.succeeded, report.failed);
+        if report.cancelled {
+            body.push_str(", cancelled");
+        }
+        notifications::job_finished(&app, &job.id, title.to_string(), body).await;
+    }
+    Ok(report)
+}
+
+// The formats convert reads and writes
+#[tauri::command]
+fn list_converters() -> Vec<converters::ConverterInfo> {
+    converters::list()
+}
+
+// Convert files between formats into options.destination as a cancellable
+// job, like batch_process. `from` is taken from each file's extension if
+// not given.
+#[tauri::command]
+async fn convert(
+    app: AppHandle,
+    input: Vec<String>,
+    from: Option<converters::Format>,
+    to: converters::Format,
+    options: converters::Options,
+    job_id: Option<String>,
+    jobs: State<'_, jobs::JobRegistry>,
+) -> Result<batch::BatchReport, String> {
+    for path in &input {
+        fs_scope::check(&app, path, fs_scope::Access::Read)?;
+    }
+    fs_scope::check(&app, &options.destination, fs_scope::Access::ReadWrite)?;
+    let job = jobs.start(&app, "convert", job_id)?;
+    if let Err(e) = fs::create_dir_all(&options.destination).await {
+        jobs.finish(&job);
+        return Err(format!("Failed to create export folder: {}", e));
+    }
+    let options = Arc::new(options);
+    let handle = app.clone();
+    let report = batch::run(Arc::clone(&job), input, move |path| {
+        let app = handle.clone();
+        let options = Arc::clone(&options);
+        async move { converters::convert_file(&app, &path, from, to, &options).await }
+    })
+    .await;
+
+    jobs.finish(&job);
+    if activation::is_backgrounded(&app) {
+        let mut body = format!("{} succeeded, {} failed", report.succeeded, report.failed);
+        if report.cancelled {
+            body.push_str(", cancelled");
+        }
+        notifications::job_finished(&app, &job.id, "Conversion finished".to_string(), body).await;
+    }
+    Ok(report)
+}
+
+// Watch folder import result, emitted to the frontend
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchFolderImportEvent {
+    source_path: String,
+    note_id: Option<String>,
+    error: Option<String>,
+}
+
+// (Re)start watching the import folders from the current settings
+fn restart_watch_folders(app: &AppHandle) -> Result<(), String> {
+    let (folders, notes_folder) = {
+        let state = app.state::<AppState>();
+        let settings = state.settings.read().expect("settings read lock");
+        let app_config = state.app_config.read().expect("app_config read lock");
+        (
+            settings.watch_folders.clone().unwrap_or_default(),
+            app_config.notes_folder.clone(),
+        )
+    };
+    // Never watch the notes folder itself, imports would feed back into it
+    let folders: Vec<_> = folders
+        .into_iter()
+        .filter(|f| {
+            notes_folder
+                .as_ref()
+                .is_none_or(|notes| !Path::new(&f.path).starts_with(notes))
+        })
+        .collect();
+
+    let state = app.state::<WatchFolderState>();
+    let mut watcher = state.watcher.lock().expect("watch folder watcher mutex");
+    *watcher = None;
+    if folders.is_empty() {
+        return Ok(());
+    }
+
+    let app_handle = app.clone();
+    let pending = Arc::clone(&state.pending);
+    *watcher = Some(watch_folders::watch(folders, move |rule, path| {
+        let seen = Instant::now();
+        pending
+            .lock()
+            .expect("watch folder pending mutex")
+            .insert(path.clone(), seen);
+
+        let app = app_handle.clone();
+        let rule = rule.clone();
+        let pending = Arc::clone(&pending);
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(WATCH_FOLDER_SETTLE).await;
+            {
+                // A later event for the same file restarts the wait
+                let mut pending = pending.lock().expect("watch folder pending mutex");
+                if pending.get(&path) != Some(&seen) {
+                    return;
+                }
+                pending.remove(&path);
+            }
+            import_from_watch_folder(&app, &rule, &path).await;
+        });
+    })?);
+
+    Ok(())
+}
+
+async fn import_from_watch_folder(app: &AppHandle, rule: &watch_folders::WatchFolder, path: &Path) {
+    // Moved sources are gone by the time their own events settle
+    if !path.is_file() {
+        return;
+    }
+
+    // Copies are imported once; later edits to the source would overwrite the note
+    if rule.action == watch_folders::ImportAction::Copy {
+        let notes_folder = app
+            .state::<AppState>()
+            .app_config
+            .read()
+            .expect("app_config read lock")
+            .notes_folder
+            .clone();
+        if let Some(folder) = notes_folder {
+            let target = PathBuf::from(folder).join(format!("{}.md", imported_note_id(path)));
+            if target.exists() {
+                return;
+            }
+        }
+    }
+
+    let result = ingest_file(app, path, &rule.tags).await;
+    if result.is_ok() && rule.action == watch_folders::ImportAction::Move {
+        let _ = fs::remove_file(path).await;
+    }
+
+    let (note_id, error) = match result {
+        Ok(id) => (Some(id), None),
+        Err(e) => (None, Some(e)),
+    };
+    let _ = app.emit(
+        "watch-folder-import",
+        WatchFolderImportEvent {
+            source_path: path.to_string_lossy().into_owned(),
+            note_id,
+            error,
+        },
+    );
+}
+
+// Route URLs delivered by the OS: markdown files are buffered for the editor,
+// smudge:// and https universal links go to deep_link.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn handle_opened_urls(app: &AppHandle, urls: Vec<tauri::Url>) {
+    log::debug!(count = urls.len(), urls:? = urls; "URLs opened");
+    let mut file_paths: Vec<PathBuf> = Vec::new();
+    let mut bundles: Vec<PathBuf> = Vec::new();
+    let mut links: Vec<tauri::Url> = Vec::new();
+
+    for url in urls {
+        if let Ok(path) = url.to_file_path() {
+            // application:openFile: with a project bundle
+            if projects::is_bundle(&path) {
+                bundles.push(path);
+                continue;
+            }
+            if !is_markdown_file(&path) {
+                continue;
+            }
+            file_paths.push(path);
+        } else {
+            links.push(url);
+        }
+    }
+
+    if !links.is_empty() && !deep_link::received(app, &links) {
+        log::debug!(links:? = links; "links no route matched");
+    }
+    projects::open_bundles(app, bundles);
+    if file_paths.is_empty() {
+        return;
+    }
+
+    if let Some(scope) = app.try_state::<fs_scope::FsScope>() {
+        scope.opened(app, &file_paths);
+    }
+    quarantine::opened(app, &file_paths);
+
+    // Always buffer — frontend retrieves via get_opened_files
+    if let Some(state) = app.try_state::<OpenedFiles>() {
+        let mut files = state.0.lock().expect("opened files mutex");
+        files.extend(file_paths.iter().cloned());
+        log::debug!(count = files.len(); "files waiting for the editor");
+    }
+
+    // Notify frontend to check for new files
+    let frontend_ready = app
+        .try_state::<FrontendReady>()
+        .map(|s| *s.0.lock().expect("frontend ready mutex"))
+        .unwrap_or(false);
+
+    if frontend_ready {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.emit("file-opened", "check");
+            log::debug!("signalled the editor to check for opened files");
+        }
+    }
+}
+
+// Route native events that Tauri's RunEvent doesn't cover
+fn handle_native_event(app: &AppHandle, event: native_events::NativeEvent) {
+    match event {
+        native_events::NativeEvent::TerminateRequested => {
+            let frontend_ready = app
+                .try_state::<FrontendReady>()
+                .map(|s| *s.0.lock().expect("frontend ready mutex"))
+                .unwrap_or(false);
+            quit::requested(app, frontend_ready);
+        }
+        native_events::NativeEvent::Activation(change) => {
+            if matches!(change, activation::Change::Activated) {
+                permissions::refresh(app);
+            }
+            activation::changed(app, change)
+        }
+        native_events::NativeEvent::PermissionAnswered => permissions::refresh(app),
+        native_events::NativeEvent::Power(change) => power::changed(app, change),
+        native_events::NativeEvent::AppleEvent {
+            event_class,
+            event_id,
+            direct_object,
+            parameters,
+            reply_id,
+        } => apple_events::handle(
+            app,
+            apple_events::Received {
+                event_class,
+                event_id,
+                direct_object,
+                parameters,
+                reply_id,
+            },
+        ),
+        native_events::NativeEvent::ServiceRequest {
+            message,
+            text,
+            files,
+        } => services_menu::handle(app, &message, text, files),
+        native_events::NativeEvent::GlobalShortcut(id) => global_shortcuts::pressed(app, id),
+        native_events::NativeEvent::Notification { id, response } => {
+            notifications::handle(app, id, response)
+        }
+        native_events::NativeEvent::ThemeChanged(theme) => appearance::changed(app, theme),
+        native_events::NativeEvent::LocaleChanged => i18n::refresh(app),
+        native_events::NativeEvent::AccessibilityChanged => accessibility::refresh(app),
+        native_events::NativeEvent::QuickLook(change) => quick_look::changed(app, change),
+        native_events::NativeEvent::ShareCompleted { id, outcome } => {
+            share::completed(app, id, outcome)
+        }
+        native_events::NativeEvent::MediaCommand(command) => audio::command(app, command),
+        native_events::NativeEvent::PrintCompleted { id, outcome } => {
+            print::completed(app, id, outcome)
+        }
+        native_events::NativeEvent::Monitors(change) => monitors::changed(app, change),
+        native_events::NativeEvent::AppOccluded(occluded) => occlusion::app_changed(app, occluded),
+        #[cfg(target_os = "macos")]
+        native_events::NativeEvent::WindowOccluded {
+            window_id,
+            occluded,
+        } => occlusion::window_changed(app, window_id, occluded),
+        #[cfg(target_os = "macos")]
+        native_events::NativeEvent::WindowOpenUrls { window_id, urls } => {
+            url_routing::deliver(app, window_id, urls)
+        }
+        #[cfg(target_os = "macos")]
+        native_events::NativeEvent::WindowTab { window_id, change } => {
+            window_tabs::changed(app, window_id, change)
+        }
+        #[cfg(target_os = "macos")]
+        native_events::NativeEvent::WindowDragEnded {
+            window_id,
+            operation,
+        } => drag::ended(app, window_id, operation),
+        #[cfg(target_os = "macos")]
+        native_events::NativeEvent::FilePromiseRequested { request, id, path } => {
+            drag::promise_requested(app, request, id, path)
+        }
+        #[cfg(target_os = "macos")]
+        native_events::NativeEvent::TrackpadGesture { window_id, gesture } => {
+            gestures::performed(app, window_id, gesture)
+        }
+        #[cfg(target_os = "macos")]
+        native_events::NativeEvent::TouchBar { window_id, action } => {
+            touch_bar::used(app, window_id, action)
+        }
+        #[cfg(target_os = "macos")]
+        native_events::NativeEvent::TitlebarButtonPressed { window_id, id } => {
+            titlebar::button_pressed(app, window_id, id)
+        }
+        native_events::NativeEvent::RestoreState { identifier, state } => {
+            let enabled = app
+                .try_state::<AppState>()
+                .map(|s| s.app_config.read().expect("app_config read lock").restore_windows)
+                .unwrap_or(false);
+            if enabled {
+                state_restoration::restore(app, &identifier, state);
+            }
+        }
+        native_events::NativeEvent::ContinueUserActivity {
+            activity_type,
+            user_info,
+        } => {
+            if shortcuts::continued(app, &activity_type, &user_info) {
+                return;
+            }
+            if activity_type != handoff::ACTIVITY_TYPE {
+                return;
+            }
+            let enabled = app
+                .try_state::<AppState>()
+                .map(|state| handoff_enabled(&state))
+                .unwrap_or(false);
+            if !enabled {
+                return;
+            }
+            let Some(document) = handoff::HandoffDocument::from_user_info(&user_info) else {
+                return;
+            };
+
+            if let Some(state) = app.try_state::<PendingHandoff>() {
+                *state.0.lock().expect("pending handoff mutex") = Some(document);
+            }
+
+            let frontend_ready = app
+                .try_state::<FrontendReady>()
+                .map(|s| *s.0.lock().expect("frontend ready mutex"))
+                .unwrap_or(false);
+            if frontend_ready {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.emit("handoff", "check");
+                }
+            }
+        }
+    }
+}
+
+/// Run the subcommand on the command line without starting the app, if
+/// there is one. Returns its exit code, or None to start the app.
+pub fn run_cli() -> Option<i32> {
+    cli::run(std::env::args_os().collect())
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let timeline = startup::StartupTimeline::begin();
+    logging::init();
+    // Debug builds note callbacks that keep memory after their autorelease
+    // pool drains in the platform log
+    #[cfg(debug_assertions)]
+    tauri_runtime_wry::tao::diag::set_autorelease_tracking(true);
+    let mut context = tauri::generate_context!();
+    // A second copy hands its arguments to the running one and exits, or
+    // relays remote control over its stdio to it
+    let Some(instance) = single_instance::acquire(&context.config().identifier) else {
+        let args: Vec<String> = std::env::args().collect();
+        if remote_control::requested(&args) == Some(remote_control::Transport::Stdio) {
+            std::process::exit(remote_control::relay(&context.config().identifier));
+        }
+        return;
+    };
+    let frontend = frontend_assets::FrontendAssets::install(&mut context);
+    tauri::Builder::default()
+        .register_asynchronous_uri_scheme_protocol("tauri", move |ctx, request, responder| {
+            frontend.serve(ctx.app_handle(), request, responder)
+        })
+        .register_asynchronous_uri_scheme_protocol(streams::SCHEME, |ctx, request, responder| {
+            streams::serve(ctx.app_handle(), request, responder)
+        })
+        .register_asynchronous_uri_scheme_protocol(deep_link::SCHEME, |ctx, request, responder| {
+            asset_protocol::serve(ctx.app_handle(), request, responder)
+        })
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .setup(|app| {
+            timeline.start(startup::SETUP);
+            app.manage(timeline);
+            // Before the log file opens, so a crashed run's last lines are
+            // still at its end
+            crash::install(app.handle());
+            if let Ok(dir) = app.path().app_log_dir() {
+                if let Err(e) = logging::open(&dir) {
+                    log::warn!(error:% = e; "couldn't open the log file");
+                }
+            }
+            // First, since other state reads it as it's set up
+            app.manage(preferences::PreferenceStore::load(app.handle()));
+            if let Err(e) = app
+                .state::<preferences::PreferenceStore>()
+                .watch(app.handle())
+            {
+                log::warn!(error:% = e; "preferences file not watched");
+            }
+            app.manage(executor::Executor::default());
+            app.manage(scheduler::Scheduler::default());
+            app.manage(streams::Streams::default());
+            app.manage(directory_watch::DirectoryWatches::default());
+            app.manage(context_menu::ContextMenus::default());
+            app.manage(asset_protocol::AssetProtocol::default());
+            app.manage(thumbnails::Thumbnails::open(app.handle()));
+
+            // An update that keeps failing to launch is rolled back before
+            // anything else starts
+            updates::launched(app.handle());
+
+            // Sandboxed builds regain access to the notes and watch folders
+            // before anything reads them
+            let bookmarks = sandbox::Bookmarks::load(app.handle());
+            bookmarks.restore();
+            app.manage(bookmarks);
+            // What the frontend may read and write outside the notes folder
+            app.manage(fs_scope::FsScope::load(app.handle()));
+
+            // Load app config on startup (contains notes folder path)
+            let app_config = load_app_config(app.handle());
+
+            // Load per-folder settings if notes folder is set
+            let settings = if let Some(ref folder) = app_config.notes_folder {
+                load_settings(folder)
+            } else {
+                Settings::default()
+            };
+
+            // Detect the cloud provider syncing the notes folder, if any
+            let storage: Arc<dyn storage::StorageProvider> = match app_config.notes_folder {
+                Some(ref folder) => Arc::from(storage::detect(Path::new(folder))),
+                None => Arc::new(storage::Local),
+            };
+
+            // Initialize search index if notes folder is set
+            let search_index = if let Some(ref folder) = app_config.notes_folder {
+                if let Ok(index_path) = get_search_index_path(app.handle()) {
+                    SearchIndex::new(&index_path)
+                        .ok()
+                        .inspect(|idx| {
+                            let _ = idx.sync(Path::new(folder), storage.as_ref());
+                        })
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+
+            // Native chrome matches the theme the notes folder asks for
+            appearance::set_app(app.handle(), appearance::parse(Some(&settings.theme.mode)));
+
+            let accessibility = accessibility::AccessibilityState::new();
+            accessibility::apply_window_animations(app.handle(), accessibility.reduce_motion());
+
+            // The main window is created here rather than from the config so
+            // it starts with everything its first render needs
+            let snapshot = snapshot::StartupSnapshot::new(
+                app_config.notes_folder.clone(),
+                settings.clone(),
+                titlebar::info(app_config.titlebar),
+                &accessibility,
+                storage.as_ref(),
+            );
+
+            match db::Database::open(app.handle()) {
+                Ok(database) => {
+                    app.manage(database);
+                }
+                Err(e) => log::error!(error:% = e; "database not opened"),
+            }
+            match http::Http::load(app.handle()) {
+                Ok(http) => {
+                    app.manage(http);
+                }
+                Err(e) => log::error!(error:% = e; "HTTP client not created"),
+            }
+            app.manage(websocket::WebSockets::default());
+            app.manage(auth::Auth::default());
+            connectivity::start(app.handle());
+            pressure::start(app.handle());
+            telemetry::start(app.handle());
+            app.manage(sidecar::Sidecars::default());
+            webview_data::watch(app.handle());
+            spelling::watch(app.handle());
+            spaces::watch(app.handle());
+            // The main window starts hidden so it can be placed first
+            app.manage(remote_sync::RemoteSync::default());
+            app.manage(placement::Placements::load(app.handle()));
+            app.manage(windows::Windows::load(app.handle()));
+            app.manage(window_frames::WindowFrames::default());
+            app.manage(find::Finds::default());
+            app.manage(undo::Undo::default());
+            #[cfg(target_os = "macos")]
+            app.manage(drag::Promises::default());
+            drop_promises::clear();
+            app.manage(dialogs::DialogDirectories::load(app.handle()));
+            app.manage(i18n::I18n::new(app_config.language.clone()));
+            app.manage(app_menu::AppMenu::default());
+            if let Err(e) = app_menu::set_default(app.handle()) {
+                log::warn!(error:% = e; "couldn't set the app menu");
+            }
+            main_window::build(
+                app.handle(),
+                &snapshot,
+                app_config.titlebar,
+                app_config.placement_policy,
+            );
+            let _ = state_restoration::apply(app.handle(), app_config.restore_windows);
+            let quick_capture_shortcut = app_config.quick_capture_shortcut.clone();
+            let restore_windows = app_config.restore_windows;
+            if app_config.tray_icon {
+                let _ = tray::set_visible(app.handle(), true);
+            }
+
+            app.manage(webview_pool::WebviewPool::new(
+                app_config
+                    .webview_pool_size
+                    .unwrap_or(webview_pool::DEFAULT_SIZE),
+            ));
+
+            app.manage(occlusion::Occlusion::new(app_config.render_throttling));
+            app.manage(extension_server::ExtensionServer::default());
+            extension_server::start_if_enabled(app.handle(), app_config.extension_server);
+            app.manage(plugins::Plugins::new(app.handle()));
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let plugins = handle.clone();
+                let _ = handle
+                    .state::<executor::Executor>()
+                    .run(executor::Priority::Background, move || {
+                        plugins.state::<plugins::Plugins>().reload(&plugins)
+                    })
+                    .await;
+            });
+
+            let state = AppState {
+                app_config: RwLock::new(app_config),
+                settings: RwLock::new(settings),
+                notes_cache: RwLock::new(HashMap::new()),
+                file_watcher: Mutex::new(None),
+                search_index: Mutex::new(search_index),
+                debounce_map: Arc::new(Mutex::new(HashMap::new())),
+                storage: RwLock::new(storage),
+                spotlight: spotlight::NoteIndex::default(),
+            };
+            app.manage(state);
+            app.manage(projects::Projects::load(app.handle()));
+            remote_sync::start(app.handle());
+            app.manage(OpenedFiles::default());
+            app.manage(FrontendReady::default());
+            app.manage(DockVisible::default());
+            app.manage(quit::QuitRequest::default());
+            app.manage(PendingDeepLinks::default());
+            app.manage(universal_links::load_config());
+            app.manage(PendingHandoff::default());
+            app.manage(shortcuts::Intents::default());
+            app.manage(WatchFolderState::default());
+            app.manage(jobs::JobRegistry::default());
+            app.manage(print::PdfJobs::default());
+            app.manage(updates::PendingUpdate::default());
+            app.manage(presentation::PresentationState::default());
+            app.manage(activation::Activation::default());
+            app.manage(permissions::Permissions::new());
+            app.manage(power::Power::default());
+            app.manage(state_restoration::RestoredState::default());
+            app.manage(accessibility);
+            // Note windows reopen the way they were, and the main window's
+            // state waits for it in case AppKit doesn't restore it
+            if restore_windows {
+                windows::restore_layout(app.handle());
+                let main_state = app
+                    .state::<windows::Windows>()
+                    .get("main")
+                    .and_then(|layout| layout.state);
+                app.state::<state_restoration::RestoredState>().seed(main_state);
+            }
+            windows::prune(app.handle());
+            let _ = restart_watch_folders(app.handle());
+            let _ = apple_events::register(app.handle());
+            let _ = services_menu::register(app.handle());
+            let _ = shortcuts::donate(app.handle());
+            app.manage(global_shortcuts::GlobalShortcuts::default());
+            notifications::init(app.handle());
+            clipboard::watch(app.handle());
+            app.manage(url_routing::WindowScopes::default());
+            let _ = url_routing::install(app.handle());
+            if let Some(accelerator) = quick_capture_shortcut {
+                let handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    let name = global_shortcuts::QUICK_CAPTURE.to_string();
+                    if let Err(e) = global_shortcuts::register(&handle, name, accelerator).await {
+                        log::warn!("quick capture shortcut not registered: {}", e);
+                    }
+                });
+            }
+            instance.serve(app.handle());
+            app.manage(remote_control::RemoteControl::default());
+            let args: Vec<String> = std::env::args().collect();
+            if let Some(transport) = remote_control::requested(&args) {
+                remote_control::start(app.handle(), transport);
+            }
+            app.wry_plugin(native_events::NativeEventsBuilder::new(
+                app.handle().clone(),
+                handle_native_event,
+            ));
+
+            // Cold-start fallback for macOS file association:
+            // Finder can pass opened documents as launch arguments.
+            #[cfg(target_os = "macos")]
+            {
+                let startup_paths = collect_startup_opened_files_from_args();
+                if !startup_paths.is_empty() {
+                    app.state::<fs_scope::FsScope>()
+                        .opened(app.handle(), &startup_paths);
+                    quarantine::opened(app.handle(), &startup_paths);
                     if let Some(opened) = app.try_state::<OpenedFiles>() {
                         let mut files = opened.0.lock().expect("opened files mutex");
                         files.extend(startup_paths);
-                        debug_open_log(&format!(
-                            "[setup] buffered_startup_files={:?}",
-                            *files
-                        ));
+                        log::debug!(count = files.len(); "files waiting for the editor");
                     }
                 }
             }
+            // Windows and Linux pass opened documents as arguments too
+            #[cfg(not(target_os = "macos"))]
+            if let Some(opened) = app.try_state::<OpenedFiles>() {
+                let launch_files: Vec<PathBuf> =
+                    launch_files().into_iter().map(PathBuf::from).collect();
+                app.state::<fs_scope::FsScope>()
+                    .opened(app.handle(), &launch_files);
+                quarantine::opened(app.handle(), &launch_files);
+                opened.0.lock().expect("opened files mutex").extend(launch_files);
+            }
+            // Windows and Linux launch the scheme's handler with the link as
+            // an argument. Registering spawns processes, so it's off the
+            // main thread.
+            #[cfg(not(target_os = "macos"))]
+            deep_link::received(app.handle(), &deep_link::urls_from_args(std::env::args()));
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let _ = deep_link::register(&handle);
+                // Offered in Open With even if nothing installed it
+                if let Err(e) = file_associations::register() {
+                    log::warn!(error:% = e; "couldn't register file types");
+                }
+            });
+            app.state::<startup::StartupTimeline>().finish(startup::SETUP);
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(profiling::commands(tauri::generate_handler![
             get_notes_folder,
             set_notes_folder,
             list_notes,
             read_note,
             save_note,
             delete_note,
+            list_note_versions,
+            get_note_version,
+            diff_note_versions,
+            restore_note_version,
             create_note,
+            create_note_from_template,
+            list_templates,
+            templates_folder,
             get_settings,
             update_settings,
             search_notes,
+            search,
             start_file_watcher,
             rebuild_search_index,
+            get_storage_info,
+            download_note,
+            evict_note,
             copy_to_clipboard,
+            read_clipboard,
+            write_clipboard,
+            get_clipboard_formats,
             copy_image_to_assets,
             save_clipboard_image,
             open_folder_dialog,
+            open_file_dialog,
+            save_file_dialog,
+            show_file_dialog,
             reveal_in_file_manager,
+            reveal_files,
+            get_file_metadata,
+            set_file_tags,
+            set_file_dates,
             open_url_safe,
             git_is_available,
             git_get_status,
@@ -2039,55 +5538,405 @@ pub fn run() {
             ai_check_claude_cli,
             ai_execute_claude,
             get_opened_files,
+            get_launch_files,
+            get_pending_deep_links,
+            get_pending_handoff,
+            swap_mini_mode,
+            get_placement_policy,
+            list_displays,
+            get_high_contrast,
+            get_reduce_motion,
+            get_reduce_transparency,
+            get_text_scale,
+            announce,
+            record_window,
+            capture_window,
+            capture_screen,
+            attach_screenshot,
+            play_sound,
+            set_app_volume,
+            get_app_volume,
+            set_now_playing,
+            create_diagnostics_bundle,
+            get_platform_diagnostics,
+            set_platform_diagnostics,
+            get_recent_logs,
+            export_logs,
+            export_profile,
+            clear_profile,
+            set_log_level,
+            record_first_paint,
+            startup_report,
+            get_telemetry,
+            reset_telemetry,
+            export_telemetry,
+            ipc_binary_codec,
+            get_health,
+            webview_pool_ready,
+            get_webview_pool_size,
+            set_webview_pool_size,
+            set_menu_accessibility,
+            clear_menu_accessibility,
+            start_presentation,
+            stop_presentation,
+            is_presenting,
+            set_placement_policy,
+            get_restore_windows,
+            set_restore_windows,
+            get_render_throttling,
+            set_render_throttling,
+            is_render_throttled,
+            get_extension_server,
+            set_extension_server_enabled,
+            reset_extension_token,
+            list_plugins,
+            plugin_invoke,
+            set_plugin_enabled,
+            reload_plugins,
+            list_windows,
+            open_window,
+            close_window,
+            focus_window,
+            set_window_zoom,
+            get_window_zoom,
+            zoom_window_in,
+            zoom_window_out,
+            reset_window_zoom,
+            find_in_page,
+            find_next,
+            stop_find,
+            list_projects,
+            create_project,
+            open_project,
+            switch_project,
+            close_project,
+            get_project_settings,
+            clear_recent_projects,
+            begin_undo_group,
+            end_undo_group,
+            register_undo,
+            undo,
+            redo,
+            mark_undo_saved,
+            clear_undo,
+            restore_undo,
+            check_spelling,
+            spelling_suggestions,
+            spelling_languages,
+            show_character_palette,
+            start_dictation,
+            composition_changed,
+            set_window_full_screen,
+            get_window_space,
+            tile_windows,
+            schedule_task,
+            cancel_scheduled_task,
+            list_scheduled_tasks,
+            open_stream,
+            close_stream,
+            put_asset_data,
+            remove_asset_data,
+            list_fs_grants,
+            revoke_fs_grant,
+            close_document,
+            watch_directory,
+            unwatch_directory,
+            show_context_menu,
+            set_app_menu,
+            update_app_menu,
+            set_touch_bar,
+            set_collection_behavior,
+            set_window_material,
+            set_material_regions,
+            get_permissions,
+            request_permission,
+            set_window_scope,
+            get_quick_capture_shortcut,
+            set_quick_capture_shortcut,
+            launch_at_login,
+            set_launch_at_login,
+            set_appearance,
+            set_window_appearance,
+            add_tabbed_window,
+            select_next_tab,
+            merge_all_windows,
+            start_drag,
+            preview_file,
+            close_preview,
+            share_items,
+            print,
+            render_to_pdf,
+            render_pdf_bytes,
+            index_searchable_items,
+            remove_searchable_items,
+            move_to_monitor,
+            store_credential,
+            retrieve_credential,
+            delete_credential,
+            upload_crash_report,
+            discard_crash_report,
+            register_global_shortcut,
+            unregister_global_shortcut,
+            show_notification,
+            remove_notification,
+            set_restorable_state,
+            take_restored_state,
+            list_file_associations,
+            claim_file_association,
+            release_file_association,
+            is_default_handler,
+            set_default_handler,
+            move_to_trash,
+            restore_from_trash,
+            check_for_update,
+            download_update,
+            install_update,
+            restart_after_update,
+            get_quarantine_policy,
+            set_quarantine_policy,
+            get_quarantine_info,
+            strip_quarantine,
+            get_capabilities,
+            get_titlebar_info,
+            set_titlebar_mode,
+            titlebar_double_click,
+            set_traffic_light_position,
+            set_title_hidden,
+            set_titlebar_accessories,
+            handoff_advertise,
+            handoff_clear,
+            get_preferences,
+            set_preference,
+            get_locale,
+            set_locale,
+            translate,
+            db_get,
+            db_put,
+            db_delete,
+            db_list,
+            export_backup,
+            import_backup,
+            database_encryption,
+            encrypt_database,
+            lock_database,
+            unlock_database,
+            get_sync_config,
+            set_sync_config,
+            get_sync_status,
+            sync_now,
+            get_sync_conflicts,
+            resolve_sync_conflict,
+            http_request,
+            http_download,
+            start_sidecar,
+            stop_sidecar,
+            write_sidecar_stdin,
+            list_sidecars,
+            pack_archive,
+            extract_archive,
+            get_network_status,
+            wait_for_online,
+            current_pressure,
+            get_http_allowed_hosts,
+            set_http_allowed_hosts,
+            clear_http_cookies,
+            list_webview_data,
+            clear_webview_data,
+            export_webview_cookies,
+            import_webview_cookies,
+            oauth_sign_in,
+            oauth_access_token,
+            oauth_session,
+            oauth_sign_out,
+            websocket_connect,
+            websocket_send,
+            websocket_close,
+            websocket_replay,
+            list_websockets,
+            get_pending_intents,
+            complete_intent,
             mark_frontend_ready,
+            reply_to_quit,
+            set_dock_visible,
+            get_tray_visible,
+            set_tray_visible,
+            set_tray_menu,
+            is_dock_visible,
+            set_dock_menu,
+            set_badge_label,
+            set_dock_progress,
+            perform_service,
+            clear_recent_documents,
             read_external_file,
             write_external_file,
             import_external_file_to_notes,
-        ])
-        .build(tauri::generate_context!())
-        .expect("error while building tauri application")
-        .run(|app, event| {
-            #[cfg(any(target_os = "macos", target_os = "ios"))]
-            if let RunEvent::Opened { urls } = event {
-                debug_open_log(&format!("[run_event] opened_urls={:?}", urls));
-                let mut file_paths: Vec<PathBuf> = Vec::new();
+            batch_process,
+            list_converters,
+            convert,
+            list_jobs,
+            pause_job,
+            resume_job,
+            cancel_job,
+            submit_job,
+        ]))
+        .on_page_load(|webview, payload| {
+            if payload.event() == PageLoadEvent::Started {
+                webview_data::page_load_started(webview);
+                spelling::page_load_started(webview);
+            }
+            if payload.event() == PageLoadEvent::Finished {
+                text_input::page_load_finished(webview);
+            }
+            if webview.label() != "main" {
+                return;
+            }
+            let timeline = webview.state::<startup::StartupTimeline>();
+            match payload.event() {
+                PageLoadEvent::Started => {
+                    timeline.start(startup::WEBVIEW);
+                    timeline.start(startup::FIRST_PAINT);
+                }
+                PageLoadEvent::Finished => timeline.finish(startup::WEBVIEW),
+            }
+        })
+        .on_menu_event(|app, event| {
+            dock_menu::handle(app, event.id().as_ref());
+            tray::handle_menu(app, event.id().as_ref());
+            context_menu::handle(app, event.id().as_ref());
+            app_menu::handle(app, event.id().as_ref());
+        })
+        .on_window_event(|window, event| {
+            if matches!(
+                event,
+                tauri::WindowEvent::Focused(true) | tauri::WindowEvent::ThemeChanged(_)
+            ) {
+                accessibility::refresh(window.app_handle());
+            }
+            if let tauri::WindowEvent::Focused(true) = event {
+                projects::window_focused(window.app_handle(), window.label());
+            }
+            if let tauri::WindowEvent::Destroyed = event {
+                url_routing::forget(window.app_handle(), window.label());
+                if let Some(streams) = window.try_state::<streams::Streams>() {
+                    streams.window_closed(window.label());
+                }
+                if let Some(watches) = window.try_state::<directory_watch::DirectoryWatches>() {
+                    watches.window_closed(window.label());
+                }
+                if let Some(finds) = window.try_state::<find::Finds>() {
+                    finds.window_closed(window.label());
+                }
+                if let Some(undo) = window.try_state::<undo::Undo>() {
+                    undo.window_closed(window.label());
+                }
+                if let Some(projects) = window.try_state::<projects::Projects>() {
+                    projects.window_closed(window.app_handle(), window.label());
+                }
+                if windows::is_managed(window.label()) {
+                    windows::changed(window.app_handle());
+                }
+            }
 
-                for url in urls {
-                    if let Ok(path) = url.to_file_path() {
-                        if !is_markdown_file(&path) {
-                            continue;
-                        }
-                        // Strip quarantine so Gatekeeper won't block the file
-                        #[cfg(target_os = "macos")]
-                        if let Some(p) = path.to_str() {
-                            remove_quarantine(p);
-                        }
-                        file_paths.push(path);
+            match event {
+                // Coalesced, so the frontend gets one event per frame and
+                // placements are recorded once the window settles
+                tauri::WindowEvent::Moved(_)
+                | tauri::WindowEvent::Resized(_)
+                | tauri::WindowEvent::ScaleFactorChanged { .. } => {
+                    if let Some(frames) = window.try_state::<window_frames::WindowFrames>() {
+                        frames.changed(window);
                     }
                 }
-
-                if file_paths.is_empty() {
-                    return;
+                // Dropping files on a window hands them to the app
+                tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, position }) => {
+                    // Promised files aren't written until asked for, so the
+                    // drop reports no paths; they follow as a second drop
+                    if paths.is_empty() {
+                        drop_promises::dropped(window, *position);
+                    } else if let Some(scope) = window.try_state::<fs_scope::FsScope>() {
+                        scope.dropped(window.app_handle(), paths);
+                    }
                 }
-
-                // Always buffer paths — frontend retrieves via get_opened_files command
-                if let Some(state) = app.try_state::<OpenedFiles>() {
-                    let mut files = state.0.lock().expect("opened files mutex");
-                    files.extend(file_paths);
-                    debug_open_log(&format!("[run_event] buffered_files={:?}", *files));
+                tauri::WindowEvent::Destroyed if window.label() == "main" => {
+                    if let Some(pool) = window.try_state::<webview_pool::WebviewPool>() {
+                        pool.shutdown();
+                    }
+                    // A recreated main window says when it's ready again
+                    if let Some(ready) = window.try_state::<FrontendReady>() {
+                        *ready.0.lock().expect("frontend ready mutex") = false;
+                    }
                 }
-
-                // Notify frontend to check for new files
-                let frontend_ready = app.try_state::<FrontendReady>()
-                    .map(|s| *s.0.lock().expect("frontend ready mutex"))
-                    .unwrap_or(false);
-
-                if frontend_ready {
+                // Track every window's frame so it can reopen where it was
+                tauri::WindowEvent::CloseRequested { .. } => {
+                    if let Some(placements) = window.try_state::<placement::Placements>() {
+                        placements.record(window);
+                        let _ = placements.persist();
+                    }
+                    // A note window the user closes isn't reopened; the main
+                    // window always is
+                    if let Some(windows) = window.try_state::<windows::Windows>() {
+                        if window.label() == "main" {
+                            windows.frame_changed(window);
+                        } else {
+                            windows.forget(window.label());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        })
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if matches!(event, RunEvent::Ready) {
+                app.state::<startup::StartupTimeline>().finish(startup::NATIVE);
+            }
+            // Quitting never asks the windows to close, so CloseRequested
+            // doesn't save where they were
+            if matches!(event, RunEvent::ExitRequested { .. } | RunEvent::Exit) {
+                if let Some(placements) = app.try_state::<placement::Placements>() {
+                    for window in app.webview_windows().values() {
+                        placements.record(&window.as_ref().window());
+                    }
+                    if matches!(event, RunEvent::Exit) {
+                        if let Err(e) = placements.persist() {
+                            log::warn!(error:% = e; "window placements not saved");
+                        }
+                    }
+                }
+            }
+            if matches!(event, RunEvent::Exit) {
+                sidecar::shutdown(app);
+                crash::exited();
+            }
+            // Launching the app again brings back a hidden Dock icon
+            #[cfg(target_os = "macos")]
+            if let RunEvent::Reopen {
+                has_visible_windows,
+                ..
+            } = event
+            {
+                if !app.state::<DockVisible>().0.load(Ordering::SeqCst) {
+                    let _ = set_dock_visible(app.clone(), true);
                     if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.emit("file-opened", "check");
-                        debug_open_log("[run_event] emitted file-opened signal");
+                        let _ = window.show();
+                        let _ = window.set_focus();
                     }
                 }
+                main_window::reopen(app, has_visible_windows);
+            }
+            // Like other Mac apps, smudge keeps running with no windows open
+            // until it's quit; clicking the Dock icon brings one back
+            #[cfg(target_os = "macos")]
+            if let RunEvent::ExitRequested {
+                code: None, api, ..
+            } = &event
+            {
+                api.prevent_exit();
+            }
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            if let RunEvent::Opened { urls } = event {
+                handle_opened_urls(app, urls);
             }
         });
 }
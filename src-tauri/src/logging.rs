@@ -0,0 +1,259 @@
+use log::kv::{Error as KvError, Key, Value, VisitSource};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The current log file in the app's log directory. Full ones move to
+/// `smudge.log.1`, `smudge.log.2` and so on, oldest last.
+const FILE_NAME: &str = "smudge.log";
+
+/// Size at which the log file is rotated
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Rotated files kept besides the current one
+const ROTATED_FILES: usize = 3;
+
+/// Lines `recent` returns, including ones logged before the file was open
+const RECENT_LINES: usize = 500;
+
+/// Sets the level at launch, e.g. `SMUDGE_LOG=trace` for every delegate
+/// callback tao traces
+const LEVEL_VAR: &str = "SMUDGE_LOG";
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    len: u64,
+}
+
+struct Sink {
+    recent: VecDeque<String>,
+    /// Lines logged before `open`, written to the file once it is
+    pending: Vec<String>,
+    file: Option<LogFile>,
+}
+
+/// Receives what smudge, tao and the other crates log through the `log`
+/// facade. Dependencies other than tao only get through from warnings up.
+struct Logger {
+    sink: Mutex<Sink>,
+}
+
+static LOGGER: Logger = Logger {
+    sink: Mutex::new(Sink {
+        recent: VecDeque::new(),
+        pending: Vec::new(),
+        file: None,
+    }),
+};
+
+/// Appends a record's key-value fields as ` key=value`
+struct Fields<'a>(&'a mut String);
+
+impl<'kvs> VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), KvError> {
+        let _ = write!(self.0, " {}={}", key, value);
+        Ok(())
+    }
+}
+
+fn is_own_target(target: &str) -> bool {
+    target.starts_with("smudge") || target.starts_with("tao")
+}
+
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("{}.{:03}", now.as_secs(), now.subsec_millis())
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self { path, file, len })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.len > 0 && self.len + line.len() as u64 + 1 > MAX_FILE_BYTES {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.len += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        for index in (1..ROTATED_FILES).rev() {
+            let _ = std::fs::rename(
+                rotated_path(&self.path, index),
+                rotated_path(&self.path, index + 1),
+            );
+        }
+        std::fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        *self = Self::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let max = if is_own_target(metadata.target()) {
+            log::max_level()
+        } else {
+            log::max_level().min(LevelFilter::Warn)
+        };
+        metadata.level() <= max
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut line = format!(
+            "{} {} {}: {}",
+            timestamp(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        let _ = record.key_values().visit(&mut Fields(&mut line));
+        #[cfg(debug_assertions)]
+        eprintln!("{}", line);
+
+        let mut guard = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        let sink = &mut *guard;
+        match sink.file {
+            Some(ref mut file) => {
+                let _ = file.write_line(&line);
+            }
+            None if sink.pending.len() < RECENT_LINES => sink.pending.push(line.clone()),
+            None => {}
+        }
+        if sink.recent.len() == RECENT_LINES {
+            sink.recent.pop_front();
+        }
+        sink.recent.push_back(line);
+    }
+
+    fn flush(&self) {
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(ref mut file) = sink.file {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+/// Parse a level name as `SMUDGE_LOG` and `set_level` take it
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .trim()
+        .parse()
+        .map_err(|_| format!("Unknown log level: {}", level))
+}
+
+/// Install the logger, at the level `SMUDGE_LOG` names or at info. Runs
+/// first thing, so lines are kept in memory until `open` has a file for
+/// them.
+pub fn init() {
+    let level = std::env::var(LEVEL_VAR)
+        .ok()
+        .and_then(|level| parse_level(&level).ok())
+        .unwrap_or(DEFAULT_LEVEL);
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// Start writing to `smudge.log` in `dir`, beginning with what was logged
+/// since `init`
+pub fn open(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let mut file = LogFile::open(dir.join(FILE_NAME)).map_err(|e| e.to_string())?;
+    let mut sink = LOGGER.sink.lock().unwrap_or_else(|e| e.into_inner());
+    for line in sink.pending.drain(..) {
+        file.write_line(&line).map_err(|e| e.to_string())?;
+    }
+    sink.file = Some(file);
+    Ok(())
+}
+
+/// Change the level for the rest of the run
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// The most recent lines, oldest first
+pub fn recent() -> Vec<String> {
+    let sink = LOGGER.sink.lock().unwrap_or_else(|e| e.into_inner());
+    sink.recent.iter().cloned().collect()
+}
+
+/// The last `count` lines, for a panic report. Gives up rather than wait
+/// when the logger is busy, which it may be on the thread that panicked.
+pub fn breadcrumbs(count: usize) -> Vec<String> {
+    let sink = match LOGGER.sink.try_lock() {
+        Ok(sink) => sink,
+        Err(std::sync::TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(std::sync::TryLockError::WouldBlock) => return Vec::new(),
+    };
+    let skip = sink.recent.len().saturating_sub(count);
+    sink.recent.iter().skip(skip).cloned().collect()
+}
+
+/// The last `count` lines of `smudge.log` in `dir`. Before `open`, these are
+/// the previous run's last lines.
+pub fn tail(dir: &Path, count: usize) -> Vec<String> {
+    let Ok(contents) = std::fs::read_to_string(dir.join(FILE_NAME)) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = contents.lines().collect();
+    let skip = lines.len().saturating_sub(count);
+    lines[skip..].iter().map(|line| line.to_string()).collect()
+}
+
+/// The file being written to, or `None` before `open`
+pub fn path() -> Option<PathBuf> {
+    let sink = LOGGER.sink.lock().unwrap_or_else(|e| e.into_inner());
+    sink.file.as_ref().map(|file| file.path.clone())
+}
+
+/// Every line still on disk, rotated files first, or `None` before `open`
+pub fn contents() -> Option<String> {
+    let path = {
+        let mut sink = LOGGER.sink.lock().unwrap_or_else(|e| e.into_inner());
+        let file = sink.file.as_mut()?;
+        let _ = file.file.flush();
+        file.path.clone()
+    };
+    let mut contents = String::new();
+    for index in (1..=ROTATED_FILES).rev() {
+        if let Ok(rotated) = std::fs::read_to_string(rotated_path(&path, index)) {
+            contents.push_str(&rotated);
+        }
+    }
+    if let Ok(current) = std::fs::read_to_string(&path) {
+        contents.push_str(&current);
+    }
+    Some(contents)
+}
+
+/// Write every line still on disk to `destination`
+pub fn export(destination: &Path) -> Result<(), String> {
+    let contents = contents().ok_or_else(|| "The log file isn't open".to_string())?;
+    std::fs::write(destination, contents).map_err(|e| e.to_string())
+}
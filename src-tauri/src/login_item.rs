@@ -0,0 +1,164 @@
+use tauri::AppHandle;
+
+/// Whether smudge starts when the user logs in
+pub fn launch_at_login(app: &AppHandle) -> Result<bool, String> {
+    platform::launch_at_login(&app.package_info().name)
+}
+
+/// Start smudge when the user logs in, or stop doing so
+pub fn set_launch_at_login(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let name = &app.package_info().name;
+    platform::set_launch_at_login(name, enabled)?;
+    log::info!(enabled = enabled; "changed launch at login");
+    Ok(())
+}
+
+/// Login items on macOS, where only the user can re-enable an app they
+/// turned off in System Settings
+#[cfg(target_os = "macos")]
+mod platform {
+    use tauri_runtime_wry::tao::platform::macos;
+
+    pub fn launch_at_login(_name: &str) -> Result<bool, String> {
+        Ok(macos::launch_at_login())
+    }
+
+    pub fn set_launch_at_login(_name: &str, enabled: bool) -> Result<(), String> {
+        macos::set_launch_at_login(enabled).map_err(|e| match e.kind() {
+            std::io::ErrorKind::PermissionDenied => {
+                "Allow Smudge in System Settings > General > Login Items".to_string()
+            }
+            _ => format!("Couldn't change the login items: {}", e),
+        })
+    }
+}
+
+/// The Run key, with the user's choice in Task Manager's Startup apps
+/// recorded apart under StartupApproved
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    const RUN: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    const STARTUP_APPROVED: &str =
+        r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\StartupApproved\Run";
+
+    fn reg(args: &[&str]) -> Result<String, String> {
+        let output = Command::new("reg")
+            .args(args)
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| format!("Failed to run reg: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// The data of `value` under `key` if it has type `kind`
+    fn query(key: &str, value: &str, kind: &str) -> Option<String> {
+        let output = reg(&["query", key, "/v", value]).ok()?;
+        output
+            .lines()
+            .find_map(|line| line.split_once(kind))
+            .map(|(_, data)| data.trim().to_string())
+    }
+
+    fn exe_path() -> Result<String, String> {
+        std::env::current_exe()
+            .map(|p| p.to_string_lossy().into_owned())
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn launch_at_login(name: &str) -> Result<bool, String> {
+        let exe = exe_path()?;
+        let registered = query(RUN, name, "REG_SZ")
+            .is_some_and(|command| command.to_lowercase().contains(&exe.to_lowercase()));
+        // The first byte is even while the entry is enabled
+        let disabled = query(STARTUP_APPROVED, name, "REG_BINARY")
+            .and_then(|data| u8::from_str_radix(data.get(..2)?, 16).ok())
+            .is_some_and(|flags| flags % 2 == 1);
+        Ok(registered && !disabled)
+    }
+
+    pub fn set_launch_at_login(name: &str, enabled: bool) -> Result<(), String> {
+        if enabled {
+            let command = format!("\"{}\"", exe_path()?);
+            reg(&["add", RUN, "/v", name, "/d", &command, "/f"])?;
+            // Otherwise an earlier "Disable" in Task Manager still applies
+            let _ = reg(&["delete", STARTUP_APPROVED, "/v", name, "/f"]);
+        } else if query(RUN, name, "REG_SZ").is_some() {
+            reg(&["delete", RUN, "/v", name, "/f"])?;
+        }
+        Ok(())
+    }
+}
+
+/// An XDG autostart entry, which desktops run at login
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use std::path::PathBuf;
+
+    fn entry_path(name: &str) -> Result<PathBuf, String> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .ok_or("Couldn't find the config folder")?;
+        Ok(config
+            .join("autostart")
+            .join(format!("{}.desktop", name.to_lowercase())))
+    }
+
+    /// An AppImage runs from a mount that's gone after it quits, so the
+    /// entry launches the image itself
+    fn exec_path() -> Result<String, String> {
+        if let Some(appimage) = std::env::var_os("APPIMAGE") {
+            return Ok(appimage.to_string_lossy().into_owned());
+        }
+        std::env::current_exe()
+            .map(|p| p.to_string_lossy().into_owned())
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn launch_at_login(name: &str) -> Result<bool, String> {
+        let Ok(entry) = std::fs::read_to_string(entry_path(name)?) else {
+            return Ok(false);
+        };
+        Ok(!entry.lines().any(|line| {
+            line.trim() == "Hidden=true" || line.trim() == "X-GNOME-Autostart-enabled=false"
+        }))
+    }
+
+    pub fn set_launch_at_login(name: &str, enabled: bool) -> Result<(), String> {
+        let path = entry_path(name)?;
+        if !enabled {
+            return match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(format!("Couldn't remove the autostart entry: {}", e))
+                }
+                _ => Ok(()),
+            };
+        }
+        // Quoted for Exec, then escaped as a desktop entry string
+        let exec = exec_path()?
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('`', "\\`")
+            .replace('$', "\\$")
+            .replace('\\', "\\\\");
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\"\nTerminal=false\n\
+             X-GNOME-Autostart-enabled=true\n",
+            name, exec
+        );
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Couldn't create the autostart folder: {}", e))?;
+        }
+        std::fs::write(&path, entry)
+            .map_err(|e| format!("Couldn't write the autostart entry: {}", e))
+    }
+}
@@ -0,0 +1,114 @@
+use tauri::{AppHandle, Manager, WebviewWindow, WebviewWindowBuilder, Wry};
+
+#[cfg(target_os = "macos")]
+use crate::window_tabs;
+use crate::{
+    accessibility, app_menu, placement, snapshot, spaces, titlebar, webview_pool, AppState,
+};
+
+/// Create the main window from its config with `snapshot` injected before
+/// its scripts run, then place and show it
+pub fn build(
+    app: &AppHandle,
+    snapshot: &snapshot::StartupSnapshot,
+    titlebar_mode: titlebar::TitlebarMode,
+    placement_policy: placement::PlacementPolicy,
+) -> Option<WebviewWindow> {
+    let window = builder(app, "main", snapshot)?.build().ok()?;
+    app_menu::attach(&window);
+    let _ = titlebar::apply(&window, titlebar_mode);
+    let _ = spaces::apply(&window, spaces::DOCUMENT);
+    let _ = placement::place(&window.as_ref().window(), placement_policy);
+    let _ = window.show();
+    Some(window)
+}
+
+/// A hidden window labelled `label`, configured like the main window and
+/// with `snapshot` injected before its scripts run
+pub fn builder<'a>(
+    app: &'a AppHandle,
+    label: &str,
+    snapshot: &snapshot::StartupSnapshot,
+) -> Option<WebviewWindowBuilder<'a, Wry, AppHandle>> {
+    let mut config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|config| config.label == "main")?
+        .clone();
+    config.label = label.to_string();
+    let builder = WebviewWindowBuilder::from_config(app, &config)
+        .ok()?
+        .initialization_script(snapshot.script());
+    #[cfg(target_os = "macos")]
+    let builder = builder.tabbing_identifier(window_tabs::TABBING_IDENTIFIER);
+    Some(builder)
+}
+
+/// A snapshot of the app as it is now rather than at launch
+pub fn current_snapshot(app: &AppHandle) -> snapshot::StartupSnapshot {
+    let state = app.state::<AppState>();
+    let app_config = state.app_config.read().expect("app_config read lock");
+    let settings = state.settings.read().expect("settings read lock").clone();
+    let storage = state.storage.read().expect("storage read lock").clone();
+    snapshot::StartupSnapshot::new(
+        app_config.notes_folder.clone(),
+        settings,
+        titlebar::info(app_config.titlebar),
+        &app.state::<accessibility::AccessibilityState>(),
+        storage.as_ref(),
+    )
+}
+
+/// Create the main window again after it was closed, with a snapshot of the
+/// app as it is now rather than at launch
+pub fn recreate(app: &AppHandle) -> Option<WebviewWindow> {
+    let app_config = app
+        .state::<AppState>()
+        .app_config
+        .read()
+        .expect("app_config read lock")
+        .clone();
+    let snapshot = current_snapshot(app);
+    log::info!("recreating the main window");
+    let window = build(
+        app,
+        &snapshot,
+        app_config.titlebar,
+        app_config.placement_policy,
+    )?;
+    // The pool shut down with the old main window
+    if let Some(pool) = app.try_state::<webview_pool::WebviewPool>() {
+        pool.resize(
+            app,
+            app_config
+                .webview_pool_size
+                .unwrap_or(webview_pool::DEFAULT_SIZE),
+        );
+    }
+    Some(window)
+}
+
+/// The Dock icon was clicked. With no windows visible, the main window comes
+/// back, created again if it was closed, and AppKit is told not to
+/// deminiaturize anything else. Otherwise AppKit does what it normally does.
+#[cfg(target_os = "macos")]
+pub fn reopen(app: &AppHandle, has_visible_windows: bool) {
+    if has_visible_windows {
+        return;
+    }
+    match app.get_webview_window("main") {
+        Some(window) => {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        None => {
+            if let Some(window) = recreate(app) {
+                let _ = window.set_focus();
+            }
+        }
+    }
+    tauri_runtime_wry::tao::platform::macos::reply_to_reopen(false);
+}
@@ -0,0 +1,174 @@
+use serde::Deserialize;
+use tauri::WebviewWindow;
+
+/// What shows through the transparent parts of a window's page. The macOS
+/// materials are approximated on Windows by the nearest system backdrop:
+/// Mica for window and sidebar backgrounds, Acrylic for HUDs, menus and
+/// popovers, and tabbed Mica for titlebars and headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Material {
+    Sidebar,
+    HeaderView,
+    Hud,
+    /// What's behind the window, like a Spotlight panel
+    UnderWindow,
+    Titlebar,
+    Menu,
+    Popover,
+    Sheet,
+    WindowBackground,
+    ContentBackground,
+    /// Windows 11's backdrops, approximated on macOS
+    Mica,
+    Acrylic,
+    Tabbed,
+}
+
+/// A rectangle of the window, in logical pixels from its top left, with a
+/// material of its own
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub material: Material,
+}
+
+/// Show `material` behind all of `window`'s page, making the page's own
+/// background transparent, or put the opaque background back with `None`
+pub fn apply(window: &WebviewWindow, material: Option<Material>) -> Result<(), String> {
+    platform::apply(window, material)
+}
+
+/// Show a material behind each of `regions` of `window`, replacing the
+/// regions it had. On Windows, where a backdrop covers the whole window, the
+/// first region's material is used for all of it.
+pub fn set_regions(window: &WebviewWindow, regions: Vec<Region>) -> Result<(), String> {
+    platform::set_regions(window, regions)
+}
+
+/// NSVisualEffectView through tao, behind the web view with its background
+/// turned off
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Material, Region};
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::dpi::{LogicalPosition, LogicalSize};
+    use tauri_runtime_wry::tao::platform::macos::{
+        self, VisualEffectMaterial, VisualEffectRegion, VisualEffectState,
+    };
+
+    fn ns_material(material: Material) -> VisualEffectMaterial {
+        match material {
+            Material::Sidebar => VisualEffectMaterial::Sidebar,
+            Material::HeaderView => VisualEffectMaterial::HeaderView,
+            Material::Hud => VisualEffectMaterial::HudWindow,
+            Material::UnderWindow => VisualEffectMaterial::UnderWindowBackground,
+            Material::Titlebar | Material::Tabbed => VisualEffectMaterial::Titlebar,
+            Material::Menu => VisualEffectMaterial::Menu,
+            Material::Popover | Material::Acrylic => VisualEffectMaterial::Popover,
+            Material::Sheet => VisualEffectMaterial::Sheet,
+            Material::WindowBackground | Material::Mica => VisualEffectMaterial::WindowBackground,
+            Material::ContentBackground => VisualEffectMaterial::ContentBackground,
+        }
+    }
+
+    pub fn apply(window: &WebviewWindow, material: Option<Material>) -> Result<(), String> {
+        // Runs on the main thread with the WKWebView and its NSWindow
+        window
+            .with_webview(move |webview| unsafe {
+                macos::set_view_draws_background(webview.inner(), material.is_none());
+                macos::set_window_visual_effect(
+                    webview.ns_window(),
+                    material.map(ns_material),
+                    VisualEffectState::default(),
+                );
+            })
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn set_regions(window: &WebviewWindow, regions: Vec<Region>) -> Result<(), String> {
+        let regions: Vec<VisualEffectRegion> = regions
+            .into_iter()
+            .map(|region| VisualEffectRegion {
+                position: LogicalPosition::new(region.x, region.y),
+                size: LogicalSize::new(region.width, region.height),
+                material: ns_material(region.material),
+            })
+            .collect();
+        window
+            .with_webview(move |webview| unsafe {
+                // Clearing the regions leaves the page transparent; `apply`
+                // with no material makes it opaque again
+                if !regions.is_empty() {
+                    macos::set_view_draws_background(webview.inner(), false);
+                }
+                macos::set_window_visual_effect_regions(
+                    webview.ns_window(),
+                    &regions,
+                    VisualEffectState::default(),
+                );
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// DWM system backdrops through tao, behind the web view with its
+/// background made transparent
+#[cfg(windows)]
+mod platform {
+    use super::{Material, Region};
+    use tauri::window::Color;
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::platform::windows::{self, Backdrop};
+
+    fn backdrop(material: Material) -> Backdrop {
+        match material {
+            Material::Mica
+            | Material::Sidebar
+            | Material::UnderWindow
+            | Material::WindowBackground
+            | Material::ContentBackground
+            | Material::Sheet => Backdrop::Mica,
+            Material::Acrylic | Material::Hud | Material::Menu | Material::Popover => {
+                Backdrop::Acrylic
+            }
+            Material::Tabbed | Material::Titlebar | Material::HeaderView => Backdrop::Tabbed,
+        }
+    }
+
+    pub fn apply(window: &WebviewWindow, material: Option<Material>) -> Result<(), String> {
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?.0 as isize;
+        windows::set_window_backdrop(hwnd, material.map_or(Backdrop::None, backdrop))
+            .map_err(|e| e.to_string())?;
+        // WebView2 can only be fully transparent or opaque
+        let background = material.map(|_| Color(0, 0, 0, 0));
+        window
+            .set_background_color(background)
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn set_regions(window: &WebviewWindow, regions: Vec<Region>) -> Result<(), String> {
+        apply(window, regions.first().map(|region| region.material))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::{Material, Region};
+    use tauri::WebviewWindow;
+
+    pub fn apply(_window: &WebviewWindow, material: Option<Material>) -> Result<(), String> {
+        if material.is_some() {
+            return Err("Window materials need macOS or Windows 11".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn set_regions(window: &WebviewWindow, regions: Vec<Region>) -> Result<(), String> {
+        apply(window, regions.first().map(|region| region.material))
+    }
+}
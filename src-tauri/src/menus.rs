@@ -0,0 +1,35 @@
+use muda::accessibility::{self, Accessibility};
+use serde::Deserialize;
+use tauri::menu::MenuId;
+
+/// Screen reader title and help for one menu or tray item
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemAccessibility {
+    pub id: String,
+    pub title: Option<String>,
+    pub help: Option<String>,
+}
+
+/// Describe a menu or tray item to screen readers. Has to be called before
+/// the item is added to a menu; the description is read when the native item
+/// is created. Win32 menus only expose the item text, so this is a no-op on
+/// Windows.
+pub fn set_item_accessibility(id: &MenuId, title: Option<String>, help: Option<String>) {
+    let accessibility =
+        (title.is_some() || help.is_some()).then_some(Accessibility { title, help });
+    accessibility::set_accessibility(id, accessibility);
+}
+
+pub fn set_all(items: Vec<ItemAccessibility>) {
+    for item in items {
+        set_item_accessibility(&MenuId::new(item.id), item.title, item.help);
+    }
+}
+
+/// Forget descriptions of items that won't be shown again
+pub fn clear(ids: &[String]) {
+    for id in ids {
+        accessibility::set_accessibility(&MenuId::new(id), None);
+    }
+}
@@ -0,0 +1,149 @@
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, Emitter, Manager, Monitor, PhysicalPosition, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder, Wry,
+};
+
+use crate::{spaces, webview_pool};
+
+/// Label of the mini window. Also listed in capabilities/default.json.
+pub const LABEL: &str = "mini";
+
+const WIDTH: f64 = 360.0;
+const HEIGHT: f64 = 420.0;
+
+/// Gap between the mini window and the edges of the work area, in logical pixels
+const MARGIN: f64 = 16.0;
+
+/// Screen corner the mini window snaps back to when it's reopened
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// Prefix of pooled mini windows' labels (see webview_pool). Also listed in
+/// capabilities/default.json.
+pub const POOLED_PREFIX: &str = "mini-";
+
+/// Whether `label` is the mini window's, cold or pooled
+pub fn is_mini(label: &str) -> bool {
+    label == LABEL || label.starts_with(POOLED_PREFIX)
+}
+
+/// The open mini window, if any. Pooled windows still waiting in the pool
+/// don't count.
+pub fn find(app: &AppHandle) -> Option<WebviewWindow> {
+    let pool = app.try_state::<webview_pool::WebviewPool>();
+    app.webview_windows().into_values().find(|window| {
+        is_mini(window.label()) && !pool.as_ref().is_some_and(|p| p.is_warm(window.label()))
+    })
+}
+
+/// A hidden mini window loading `url`
+pub fn builder<'a>(
+    app: &'a AppHandle,
+    label: &str,
+    url: String,
+) -> WebviewWindowBuilder<'a, Wry, AppHandle> {
+    let builder = WebviewWindowBuilder::new(app, label, WebviewUrl::App(url.into()))
+        .title("Smudge")
+        .inner_size(WIDTH, HEIGHT)
+        .min_inner_size(260.0, 180.0)
+        .always_on_top(true)
+        .visible_on_all_workspaces(true)
+        .skip_taskbar(true)
+        .visible(false);
+    #[cfg(target_os = "macos")]
+    let builder = builder
+        .title_bar_style(tauri::TitleBarStyle::Overlay)
+        .hidden_title(true);
+    builder
+}
+
+/// Open the compact, always-on-top window showing `note_id`, placed in
+/// `corner` of `monitor` (the primary monitor if None). Takes a warm window
+/// from the pool when there is one. Hidden until placed.
+pub fn open(
+    app: &AppHandle,
+    note_id: &str,
+    corner: Corner,
+    monitor: Option<Monitor>,
+) -> Result<WebviewWindow, String> {
+    let warm = app
+        .try_state::<webview_pool::WebviewPool>()
+        .and_then(|pool| pool.take(app));
+    let window = match warm {
+        Some(window) => {
+            window
+                .emit_to(window.label(), webview_pool::CLAIM_EVENT, note_id)
+                .map_err(|e| e.to_string())?;
+            window
+        }
+        None => {
+            let url = format!(
+                "index.html?mini={}",
+                utf8_percent_encode(note_id, NON_ALPHANUMERIC)
+            );
+            builder(app, LABEL, url)
+                .build()
+                .map_err(|e| e.to_string())?
+        }
+    };
+    let monitor = match monitor {
+        Some(monitor) => monitor,
+        None => window
+            .primary_monitor()
+            .map_err(|e| e.to_string())?
+            .ok_or("No monitor available")?,
+    };
+    place(&window, &monitor, corner)?;
+    let _ = spaces::apply(&window, spaces::PALETTE);
+    window.show().map_err(|e| e.to_string())?;
+    Ok(window)
+}
+
+/// Move `window` into `corner` of the monitor's work area
+fn place(window: &WebviewWindow, monitor: &Monitor, corner: Corner) -> Result<(), String> {
+    let area = monitor.work_area();
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let margin = (MARGIN * monitor.scale_factor()) as i32;
+
+    let left = area.position.x + margin;
+    let top = area.position.y + margin;
+    let right = area.position.x + area.size.width as i32 - size.width as i32 - margin;
+    let bottom = area.position.y + area.size.height as i32 - size.height as i32 - margin;
+    let (x, y) = match corner {
+        Corner::TopLeft => (left, top),
+        Corner::TopRight => (right, top),
+        Corner::BottomLeft => (left, bottom),
+        Corner::BottomRight => (right, bottom),
+    };
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|e| e.to_string())
+}
+
+/// The work-area corner nearest to the window's center
+pub fn nearest_corner(window: &WebviewWindow) -> Option<Corner> {
+    let monitor = window.current_monitor().ok()??;
+    let area = monitor.work_area();
+    let position = window.outer_position().ok()?;
+    let size = window.outer_size().ok()?;
+
+    let center_x = position.x + size.width as i32 / 2;
+    let center_y = position.y + size.height as i32 / 2;
+    let left = center_x < area.position.x + area.size.width as i32 / 2;
+    let top = center_y < area.position.y + area.size.height as i32 / 2;
+    Some(match (left, top) {
+        (true, true) => Corner::TopLeft,
+        (false, true) => Corner::TopRight,
+        (true, false) => Corner::BottomLeft,
+        (false, false) => Corner::BottomRight,
+    })
+}
@@ -0,0 +1,42 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::placement;
+
+/// Sent to every window with the `Change` when a display is connected,
+/// disconnected or rearranged
+pub const EVENT: &str = "monitors-changed";
+
+/// A change to the displays, reported by tao: the screen parameters
+/// notification on macOS, display and settings changes on Windows, GDK's
+/// monitor signals on Linux. Monitors are named as `availableMonitors` names
+/// them.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Change {
+    Added {
+        name: Option<String>,
+    },
+    Removed {
+        name: Option<String>,
+    },
+    /// The resolution, scale factor or arrangement changed
+    Changed {
+        name: Option<String>,
+    },
+}
+
+/// Pull back any window the change left out of reach, such as one on a
+/// laptop's external display after undocking, then tell every window so
+/// layouts can adapt
+pub fn changed(app: &AppHandle, change: Change) {
+    log::debug!(change:? = change; "monitors changed");
+    if !matches!(change, Change::Added { .. }) {
+        for window in app.webview_windows().into_values() {
+            if let Err(e) = placement::keep_reachable(&window.as_ref().window()) {
+                log::debug!(window = window.label(); "Window not kept on screen: {}", e);
+            }
+        }
+    }
+    let _ = app.emit(EVENT, change);
+}
@@ -0,0 +1,484 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, EventLoopMessage};
+
+#[cfg(target_os = "macos")]
+use crate::{drag, gestures, touch_bar, window_tabs};
+use crate::{activation, monitors, power, print, quick_look, share};
+use tauri_runtime_wry::tao::audio::MediaCommand;
+use tauri_runtime_wry::tao::event::{Event, PowerEvent, RestorableState};
+use tauri_runtime_wry::tao::event_loop::{ControlFlow, EventLoopProxy, EventLoopWindowTarget};
+use tauri_runtime_wry::tao::global_shortcut::ShortcutId;
+use tauri_runtime_wry::tao::monitor_events::MonitorEvent;
+use tauri_runtime_wry::tao::notification::NotificationResponse;
+use tauri_runtime_wry::tao::print::PrintOutcome;
+use tauri_runtime_wry::tao::quick_look::QuickLookEvent;
+use tauri_runtime_wry::tao::share::ShareOutcome;
+use tauri_runtime_wry::tao::window::Theme;
+#[cfg(target_os = "macos")]
+use tauri_runtime_wry::tao::{event::WindowEvent, touch_bar::TouchBarEvent, window::WindowId};
+use tauri_runtime_wry::{
+    Context, EventLoopIterationContext, Message, Plugin, PluginBuilder, WebContextStore,
+};
+
+/// Events tao emits that Tauri's `RunEvent` doesn't expose
+pub enum NativeEvent {
+    ContinueUserActivity {
+        activity_type: String,
+        user_info: HashMap<String, String>,
+    },
+    /// The app was asked to quit. Deferring the quit is only possible while
+    /// this is being handled.
+    TerminateRequested,
+    /// The app was activated, deactivated, hidden or shown again
+    Activation(activation::Change),
+    /// A window saved when the app last quit, delivered at launch
+    RestoreState {
+        identifier: String,
+        state: RestorableState,
+    },
+    /// The system is going to sleep or woke, or the screen was locked or
+    /// unlocked
+    Power(power::Change),
+    /// An Apple Event registered with `register_apple_event`
+    AppleEvent {
+        event_class: u32,
+        event_id: u32,
+        direct_object: Option<String>,
+        parameters: Vec<(u32, String)>,
+        /// Set for events registered with a reply, which waits for it
+        reply_id: Option<u64>,
+    },
+    /// Another app's selection, sent with one of smudge's services
+    ServiceRequest {
+        message: String,
+        text: Option<String>,
+        files: Vec<PathBuf>,
+    },
+    /// A shortcut registered with `global_shortcut::register` was pressed
+    GlobalShortcut(ShortcutId),
+    /// The user clicked, answered or dismissed a notification
+    Notification {
+        id: String,
+        response: NotificationResponse,
+    },
+    /// The app's effective appearance switched between light and dark
+    ThemeChanged(tauri::Theme),
+    /// The user changed the system's languages or region
+    LocaleChanged,
+    /// The user changed an accessibility display setting
+    AccessibilityChanged,
+    /// A preview shown with `quick_look::preview` opened or closed
+    QuickLook(quick_look::Change),
+    /// A share begun with `share::share` ended
+    ShareCompleted { id: u64, outcome: share::Outcome },
+    /// A print job begun with `print::print` ended
+    PrintCompleted { id: u64, outcome: print::Outcome },
+    /// A media key was pressed while something is playing
+    MediaCommand(MediaCommand),
+    /// A display was connected, disconnected or rearranged
+    Monitors(monitors::Change),
+    /// Every window became hidden behind others, or one shows again
+    AppOccluded(bool),
+    /// The user answered a permission prompt
+    PermissionAnswered,
+    /// URLs the open URL router sent to a window rather than the app
+    #[cfg(target_os = "macos")]
+    WindowOpenUrls {
+        window_id: WindowId,
+        urls: Vec<tauri::Url>,
+    },
+    /// A window became the selected tab of its group or closed as a tab
+    #[cfg(target_os = "macos")]
+    WindowTab {
+        window_id: WindowId,
+        change: window_tabs::Change,
+    },
+    /// A drag begun with `drag::start` ended
+    #[cfg(target_os = "macos")]
+    WindowDragEnded {
+        window_id: WindowId,
+        operation: drag::Operation,
+    },
+    /// A note dragged out as a file promise was dropped and should be
+    /// written to `path`
+    #[cfg(target_os = "macos")]
+    FilePromiseRequested {
+        request: u64,
+        id: String,
+        path: PathBuf,
+    },
+    /// The trackpad was pinched, rotated, double-tapped or pressed over a
+    /// window
+    #[cfg(target_os = "macos")]
+    TrackpadGesture {
+        window_id: WindowId,
+        gesture: gestures::Gesture,
+    },
+    /// A window became hidden behind others, or shows again
+    #[cfg(target_os = "macos")]
+    WindowOccluded { window_id: WindowId, occluded: bool },
+    /// A control on a window's Touch Bar was used
+    #[cfg(target_os = "macos")]
+    TouchBar {
+        window_id: WindowId,
+        action: touch_bar::Action,
+    },
+    /// A button in a window's title bar accessories was clicked
+    #[cfg(target_os = "macos")]
+    TitlebarButtonPressed { window_id: WindowId, id: String },
+}
+
+/// Wry runtime plugin that sees raw tao events before Tauri translates them,
+/// forwarding the ones smudge cares about to `handler`.
+pub struct NativeEventsBuilder<F> {
+    app: AppHandle,
+    handler: F,
+}
+
+impl<F> NativeEventsBuilder<F>
+where
+    F: FnMut(&AppHandle, NativeEvent) + Send + 'static,
+{
+    pub fn new(app: AppHandle, handler: F) -> Self {
+        Self { app, handler }
+    }
+}
+
+pub struct NativeEvents<F> {
+    app: AppHandle,
+    handler: F,
+}
+
+impl<F> PluginBuilder<EventLoopMessage> for NativeEventsBuilder<F>
+where
+    F: FnMut(&AppHandle, NativeEvent) + Send + 'static,
+{
+    type Plugin = NativeEvents<F>;
+
+    fn build(self, _context: Context<EventLoopMessage>) -> Self::Plugin {
+        NativeEvents {
+            app: self.app,
+            handler: self.handler,
+        }
+    }
+}
+
+impl<F> Plugin<EventLoopMessage> for NativeEvents<F>
+where
+    F: FnMut(&AppHandle, NativeEvent) + Send + 'static,
+{
+    fn on_event(
+        &mut self,
+        event: &Event<Message<EventLoopMessage>>,
+        _event_loop: &EventLoopWindowTarget<Message<EventLoopMessage>>,
+        _proxy: &EventLoopProxy<Message<EventLoopMessage>>,
+        _control_flow: &mut ControlFlow,
+        _context: EventLoopIterationContext<'_, EventLoopMessage>,
+        _web_context: &WebContextStore,
+    ) -> bool {
+        match event {
+            Event::ContinueUserActivity {
+                activity_type,
+                user_info,
+                ..
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::ContinueUserActivity {
+                    activity_type: activity_type.clone(),
+                    user_info: user_info.clone(),
+                },
+            ),
+            Event::TerminateRequested => (self.handler)(&self.app, NativeEvent::TerminateRequested),
+            Event::Activated => (self.handler)(
+                &self.app,
+                NativeEvent::Activation(activation::Change::Activated),
+            ),
+            Event::Deactivated => (self.handler)(
+                &self.app,
+                NativeEvent::Activation(activation::Change::Deactivated),
+            ),
+            Event::Hidden => (self.handler)(
+                &self.app,
+                NativeEvent::Activation(activation::Change::Hidden),
+            ),
+            Event::Unhidden => (self.handler)(
+                &self.app,
+                NativeEvent::Activation(activation::Change::Unhidden),
+            ),
+            Event::RestoreState { identifier, state } => (self.handler)(
+                &self.app,
+                NativeEvent::RestoreState {
+                    identifier: identifier.clone(),
+                    state: state.clone(),
+                },
+            ),
+            Event::Power(event) => {
+                let change = match event {
+                    PowerEvent::WillSleep => power::Change::WillSleep,
+                    PowerEvent::DidWake => power::Change::DidWake,
+                    PowerEvent::ScreenLocked => power::Change::ScreenLocked,
+                    PowerEvent::ScreenUnlocked => power::Change::ScreenUnlocked,
+                    _ => return false,
+                };
+                (self.handler)(&self.app, NativeEvent::Power(change))
+            }
+            Event::AppleEvent {
+                event_class,
+                event_id,
+                direct_object,
+                parameters,
+                reply_id,
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::AppleEvent {
+                    event_class: *event_class,
+                    event_id: *event_id,
+                    direct_object: direct_object.clone(),
+                    parameters: parameters.clone(),
+                    reply_id: *reply_id,
+                },
+            ),
+            Event::ServiceRequest {
+                message,
+                text,
+                files,
+                ..
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::ServiceRequest {
+                    message: message.clone(),
+                    text: text.clone(),
+                    files: files.clone(),
+                },
+            ),
+            #[cfg(target_os = "macos")]
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::OpenUrls(urls),
+                ..
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::WindowOpenUrls {
+                    window_id: *window_id,
+                    urls: urls.clone(),
+                },
+            ),
+            #[cfg(target_os = "macos")]
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::TabSelected,
+                ..
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::WindowTab {
+                    window_id: *window_id,
+                    change: window_tabs::Change::Selected,
+                },
+            ),
+            #[cfg(target_os = "macos")]
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::TabClosed,
+                ..
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::WindowTab {
+                    window_id: *window_id,
+                    change: window_tabs::Change::Closed,
+                },
+            ),
+            #[cfg(target_os = "macos")]
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::DragEnded(operation),
+                ..
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::WindowDragEnded {
+                    window_id: *window_id,
+                    operation: (*operation).into(),
+                },
+            ),
+            #[cfg(target_os = "macos")]
+            Event::WindowEvent {
+                event: WindowEvent::FilePromiseRequested { request, id, path },
+                ..
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::FilePromiseRequested {
+                    request: *request,
+                    id: id.clone(),
+                    path: path.clone(),
+                },
+            ),
+            #[cfg(target_os = "macos")]
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::Occluded(occluded),
+                ..
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::WindowOccluded {
+                    window_id: *window_id,
+                    occluded: *occluded,
+                },
+            ),
+            #[cfg(target_os = "macos")]
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::TouchBar(event),
+                ..
+            } => {
+                let action = match event.clone() {
+                    TouchBarEvent::ButtonPressed { id } => touch_bar::Action::ButtonPressed { id },
+                    TouchBarEvent::SliderChanged { id, value } => {
+                        touch_bar::Action::SliderChanged { id, value }
+                    }
+                    TouchBarEvent::SegmentSelected { id, index } => {
+                        touch_bar::Action::SegmentSelected { id, index }
+                    }
+                    TouchBarEvent::CandidateSelected { id, index } => {
+                        touch_bar::Action::CandidateSelected { id, index }
+                    }
+                    _ => return false,
+                };
+                (self.handler)(
+                    &self.app,
+                    NativeEvent::TouchBar {
+                        window_id: *window_id,
+                        action,
+                    },
+                )
+            }
+            #[cfg(target_os = "macos")]
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::TitlebarButtonPressed { id },
+                ..
+            } => (self.handler)(
+                &self.app,
+                NativeEvent::TitlebarButtonPressed {
+                    window_id: *window_id,
+                    id: id.clone(),
+                },
+            ),
+            #[cfg(target_os = "macos")]
+            Event::WindowEvent {
+                window_id, event, ..
+            } => {
+                let gesture = match event {
+                    WindowEvent::TouchpadMagnify { delta, phase, .. } => {
+                        gestures::Gesture::Magnify {
+                            delta: *delta,
+                            phase: (*phase).into(),
+                        }
+                    }
+                    WindowEvent::TouchpadRotate { delta, phase, .. } => gestures::Gesture::Rotate {
+                        delta: *delta,
+                        phase: (*phase).into(),
+                    },
+                    WindowEvent::SmartMagnify { .. } => gestures::Gesture::SmartMagnify,
+                    WindowEvent::TouchpadPressure {
+                        pressure,
+                        stage,
+                        phase,
+                        ..
+                    } => gestures::Gesture::Pressure {
+                        pressure: *pressure,
+                        stage: *stage,
+                        phase: (*phase).into(),
+                    },
+                    _ => return false,
+                };
+                (self.handler)(
+                    &self.app,
+                    NativeEvent::TrackpadGesture {
+                        window_id: *window_id,
+                        gesture,
+                    },
+                )
+            }
+            Event::GlobalShortcut(id) => {
+                (self.handler)(&self.app, NativeEvent::GlobalShortcut(*id))
+            }
+            Event::Notification { id, response } => (self.handler)(
+                &self.app,
+                NativeEvent::Notification {
+                    id: id.clone(),
+                    response: response.clone(),
+                },
+            ),
+            Event::ThemeChanged(theme) => {
+                let theme = match theme {
+                    Theme::Dark => tauri::Theme::Dark,
+                    _ => tauri::Theme::Light,
+                };
+                (self.handler)(&self.app, NativeEvent::ThemeChanged(theme))
+            }
+            Event::LocaleChanged => (self.handler)(&self.app, NativeEvent::LocaleChanged),
+            Event::AccessibilityChanged => {
+                (self.handler)(&self.app, NativeEvent::AccessibilityChanged)
+            }
+            Event::QuickLook(event) => {
+                let change = match event {
+                    QuickLookEvent::Opened => quick_look::Change::Opened,
+                    QuickLookEvent::Closed => quick_look::Change::Closed,
+                    _ => return false,
+                };
+                (self.handler)(&self.app, NativeEvent::QuickLook(change))
+            }
+            Event::ShareCompleted { id, outcome } => {
+                let outcome = match outcome {
+                    ShareOutcome::Shared { service } => share::Outcome::Shared {
+                        service: service.clone(),
+                    },
+                    ShareOutcome::Cancelled => share::Outcome::Cancelled,
+                    ShareOutcome::Failed(message) => share::Outcome::Failed {
+                        message: message.clone(),
+                    },
+                    _ => return false,
+                };
+                (self.handler)(&self.app, NativeEvent::ShareCompleted { id: *id, outcome })
+            }
+            Event::PrintCompleted { id, outcome } => {
+                let outcome = match outcome {
+                    PrintOutcome::Printed => print::Outcome::Printed,
+                    PrintOutcome::Cancelled => print::Outcome::Cancelled,
+                    PrintOutcome::Failed(message) => print::Outcome::Failed {
+                        message: message.clone(),
+                    },
+                    _ => return false,
+                };
+                (self.handler)(&self.app, NativeEvent::PrintCompleted { id: *id, outcome })
+            }
+            Event::MediaCommand(command) => {
+                (self.handler)(&self.app, NativeEvent::MediaCommand(*command))
+            }
+            Event::ApplicationOccluded(occluded) => {
+                (self.handler)(&self.app, NativeEvent::AppOccluded(*occluded))
+            }
+            Event::PermissionChanged { .. } => {
+                (self.handler)(&self.app, NativeEvent::PermissionAnswered)
+            }
+            Event::Monitor(event) => {
+                let change = match event {
+                    MonitorEvent::Added(monitor) => monitors::Change::Added {
+                        name: monitor.name(),
+                    },
+                    MonitorEvent::Removed { name, .. } => {
+                        monitors::Change::Removed { name: name.clone() }
+                    }
+                    MonitorEvent::Changed(monitor) => monitors::Change::Changed {
+                        name: monitor.name(),
+                    },
+                    _ => return false,
+                };
+                (self.handler)(&self.app, NativeEvent::Monitors(change))
+            }
+            _ => {}
+        }
+        // Never swallow events; Tauri still handles everything it knows about
+        false
+    }
+}
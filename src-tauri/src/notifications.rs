@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_runtime_wry::tao::notification::{
+    self, Notification, NotificationAction, NotificationResponse, ReplyField,
+};
+
+use crate::main_window;
+#[cfg(not(target_os = "linux"))]
+use crate::presentation::PresentationState;
+
+/// Sent to the main window with what the user did with a notification
+pub const EVENT: &str = "notification-response";
+
+/// Notifications about finished jobs have ids of this followed by the job id
+pub const JOB_PREFIX: &str = "job:";
+
+/// The action on job notifications that brings up the main window
+const SHOW_ACTION: &str = "show";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationActionRequest {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub destructive: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplyFieldRequest {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub placeholder: String,
+}
+
+/// A notification as the frontend describes it. Showing one with the id of
+/// one still on screen replaces it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRequest {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub subtitle: Option<String>,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub actions: Vec<NotificationActionRequest>,
+    /// A text field, not available on Linux
+    #[serde(default)]
+    pub reply: Option<ReplyFieldRequest>,
+    /// Image paths; Windows and Linux show only the first
+    #[serde(default)]
+    pub attachments: Vec<String>,
+}
+
+impl From<NotificationRequest> for Notification {
+    fn from(request: NotificationRequest) -> Self {
+        let mut notification = Notification::new(request.id, request.title, request.body);
+        notification.subtitle = request.subtitle;
+        notification.actions = request
+            .actions
+            .into_iter()
+            .map(|action| NotificationAction {
+                id: action.id,
+                title: action.title,
+                destructive: action.destructive,
+            })
+            .collect();
+        notification.reply = request.reply.map(|reply| ReplyField {
+            id: reply.id,
+            title: reply.title,
+            placeholder: reply.placeholder,
+        });
+        notification.attachments = request.attachments.into_iter().map(PathBuf::from).collect();
+        notification
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResponseEvent {
+    id: String,
+    /// "activated", "action", "reply" or "dismissed"
+    kind: &'static str,
+    /// The action or reply field's id
+    action_id: Option<String>,
+    text: Option<String>,
+}
+
+/// Attribute Windows toasts to the installer's Start menu shortcut, which
+/// carries the bundle identifier as its AppUserModelID. Called from setup,
+/// on the main thread.
+pub fn init(app: &AppHandle) {
+    #[cfg(windows)]
+    tauri_runtime_wry::tao::platform::windows::set_notification_app_id(&app.config().identifier);
+    #[cfg(not(windows))]
+    let _ = app;
+}
+
+/// Show `request`. On macOS the user is asked to allow notifications the
+/// first time; a refusal is only logged. Nothing is shown while presenting.
+pub async fn show(app: &AppHandle, request: NotificationRequest) -> Result<(), String> {
+    // Linux turns the desktop's banners off while presenting; macOS and
+    // Windows don't let apps do that, so smudge holds its own back
+    #[cfg(not(target_os = "linux"))]
+    if app
+        .try_state::<PresentationState>()
+        .is_some_and(|state| state.active_label().is_some())
+    {
+        log::debug!(id:% = request.id; "notification held back while presenting");
+        return Ok(());
+    }
+    let notification = Notification::from(request);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.run_on_main_thread(move || {
+        let _ = tx.send(notification::show(&notification));
+    })
+    .map_err(|e| e.to_string())?;
+    rx.await
+        .map_err(|_| "The notification wasn't shown".to_string())?
+        .map_err(|e| format!("Couldn't show the notification: {}", e))
+}
+
+/// Take away the notification shown with `id`, if it's still there
+pub fn remove(app: &AppHandle, id: String) -> Result<(), String> {
+    app.run_on_main_thread(move || notification::remove(&id))
+        .map_err(|e| e.to_string())
+}
+
+/// Tell the user a job finished while smudge was in the background. Clicking
+/// the notification or its Show button brings up the main window.
+pub async fn job_finished(app: &AppHandle, job_id: &str, title: String, body: String) {
+    let request = NotificationRequest {
+        id: format!("{}{}", JOB_PREFIX, job_id),
+        title,
+        subtitle: None,
+        body,
+        actions: vec![NotificationActionRequest {
+            id: SHOW_ACTION.to_string(),
+            title: "Show".to_string(),
+            destructive: false,
+        }],
+        reply: None,
+        attachments: Vec::new(),
+    };
+    if let Err(e) = show(app, request).await {
+        log::warn!(job = job_id; "job notification not shown: {}", e);
+    }
+}
+
+/// The user did something with a notification: bring up the main window if
+/// they clicked it or a job notification's Show button, then pass the
+/// response on to it
+pub fn handle(app: &AppHandle, id: String, response: NotificationResponse) {
+    let event = match response {
+        NotificationResponse::Activated => ResponseEvent {
+            id,
+            kind: "activated",
+            action_id: None,
+            text: None,
+        },
+        NotificationResponse::Action(action_id) => ResponseEvent {
+            id,
+            kind: "action",
+            action_id: Some(action_id),
+            text: None,
+        },
+        NotificationResponse::Reply {
+            id: action_id,
+            text,
+        } => ResponseEvent {
+            id,
+            kind: "reply",
+            action_id: Some(action_id),
+            text: Some(text),
+        },
+        NotificationResponse::Dismissed => ResponseEvent {
+            id,
+            kind: "dismissed",
+            action_id: None,
+            text: None,
+        },
+        _ => return,
+    };
+    log::debug!(id = event.id.as_str(), kind = event.kind; "notification answered");
+
+    let summon = match event.kind {
+        "activated" => true,
+        // Other buttons are for the frontend to act on without stealing focus
+        "action" => {
+            event.id.starts_with(JOB_PREFIX) && event.action_id.as_deref() == Some(SHOW_ACTION)
+        }
+        _ => false,
+    };
+    let window = match app.get_webview_window("main") {
+        Some(window) => Some(window),
+        // Closed, on macOS, where smudge keeps running without it
+        None if summon => main_window::recreate(app),
+        None => None,
+    };
+    let Some(window) = window else {
+        return;
+    };
+    if summon {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = window.emit(EVENT, event);
+}
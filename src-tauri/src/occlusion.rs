@@ -0,0 +1,97 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sent to a window with `true` when none of it shows any more, behind other
+/// windows, minimized or on another space, and `false` when it shows again
+#[cfg(target_os = "macos")]
+pub const WINDOW_EVENT: &str = "window-occlusion";
+
+/// Sent to every window with a `Throttling` when rendering should pause or
+/// pick up again
+pub const EVENT: &str = "render-throttling";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Throttling {
+    /// Whether animation loops should stop: throttling is on and none of
+    /// smudge's windows show
+    pub throttled: bool,
+}
+
+/// Whether every window is occluded, reported by AppKit on macOS; elsewhere
+/// the windows always count as showing
+#[derive(Default)]
+pub struct Occlusion {
+    occluded: AtomicBool,
+    throttling: AtomicBool,
+}
+
+impl Occlusion {
+    pub fn new(throttling: bool) -> Self {
+        Self {
+            occluded: AtomicBool::new(false),
+            throttling: AtomicBool::new(throttling),
+        }
+    }
+
+    pub fn throttling(&self) -> Throttling {
+        Throttling {
+            throttled: self.throttling.load(Ordering::Relaxed)
+                && self.occluded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Whether rendering is paused; false before `Occlusion` is managed
+pub fn is_throttled(app: &AppHandle) -> bool {
+    app.try_state::<Occlusion>()
+        .is_some_and(|occlusion| occlusion.throttling().throttled)
+}
+
+/// Apply `update` and tell every window if that paused or resumed rendering
+fn update(app: &AppHandle, update: impl FnOnce(&Occlusion)) {
+    let Some(occlusion) = app.try_state::<Occlusion>() else {
+        return;
+    };
+    let before = occlusion.throttling().throttled;
+    update(&occlusion);
+    let throttling = occlusion.throttling();
+    if throttling.throttled != before {
+        log::debug!(throttled = throttling.throttled; "render throttling changed");
+        let _ = app.emit(EVENT, throttling);
+    }
+}
+
+/// Pause rendering whenever every window is hidden, or never
+pub fn set_throttling(app: &AppHandle, enabled: bool) {
+    update(app, |occlusion| {
+        occlusion.throttling.store(enabled, Ordering::Relaxed)
+    });
+}
+
+/// Every window became hidden, or one shows again
+pub fn app_changed(app: &AppHandle, occluded: bool) {
+    update(app, |occlusion| {
+        occlusion.occluded.store(occluded, Ordering::Relaxed)
+    });
+}
+
+/// Tell the window tao reported occlusion for
+#[cfg(target_os = "macos")]
+pub fn window_changed(
+    app: &AppHandle,
+    window_id: tauri_runtime_wry::tao::window::WindowId,
+    occluded: bool,
+) {
+    use tauri_runtime_wry::tao::platform::macos;
+
+    let window = app.webview_windows().into_values().find(|window| {
+        window
+            .ns_window()
+            .is_ok_and(|ns_window| macos::window_id_for_ns_window(ns_window) == window_id)
+    });
+    if let Some(window) = window {
+        let _ = window.emit_to(window.label(), WINDOW_EVENT, occluded);
+    }
+}
@@ -0,0 +1,253 @@
+use base64::Engine;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::webview::PageLoadEvent;
+use tauri::{AppHandle, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+use tokio::sync::oneshot;
+
+use crate::print::{Margins, Orientation, PaperSize};
+
+/// Prefix of the hidden windows HTML is rendered in
+const WINDOW_PREFIX: &str = "pdf-render-";
+/// A page's size in the hidden window, US Letter at 96 dpi; rendering
+/// reflows to the paper
+const WINDOW_SIZE: (f64, f64) = (816.0, 1056.0);
+/// How long a page gets to load before rendering it fails
+const LOAD_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// What to render
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Source {
+    /// A window's whole page, by label; the calling window's if not given
+    Window { label: Option<String> },
+    /// A page of HTML, loaded in a hidden window that's closed afterwards
+    Html { html: String },
+}
+
+/// How to lay out the pages; the defaults are the system's paper and
+/// margins, without headers or footers
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Options {
+    pub paper_size: Option<PaperSize>,
+    pub margins: Option<Margins>,
+    pub orientation: Option<Orientation>,
+    /// The title and date above each page, and its number below
+    pub header_and_footer: bool,
+    /// The title in the header, the page's own if not given
+    pub title: Option<String>,
+}
+
+/// Render `window`'s whole page as a PDF at `output`, without any dialog
+pub async fn render_window(
+    window: &WebviewWindow,
+    output: &Path,
+    options: Options,
+) -> Result<(), String> {
+    if let Some(paper) = options.paper_size {
+        if paper.width <= 0.0 || paper.height <= 0.0 {
+            return Err("The paper needs a width and a height".to_string());
+        }
+    }
+    platform::render(window, output, options).await
+}
+
+/// Render `html` as a PDF at `output` from a hidden window that's closed
+/// afterwards
+pub async fn render_html(
+    app: &AppHandle,
+    html: String,
+    output: &Path,
+    options: Options,
+) -> Result<(), String> {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let label = format!(
+        "{}{}",
+        WINDOW_PREFIX,
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    let url = format!(
+        "data:text/html;charset=utf-8;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(html)
+    );
+    let url = url.parse().map_err(|_| "Invalid page".to_string())?;
+
+    let (tx, rx) = oneshot::channel();
+    let loaded = Mutex::new(Some(tx));
+    let window = WebviewWindowBuilder::new(app, &label, WebviewUrl::External(url))
+        .title("Smudge")
+        .inner_size(WINDOW_SIZE.0, WINDOW_SIZE.1)
+        .visible(false)
+        .skip_taskbar(true)
+        .focused(false)
+        .on_page_load(move |_, payload| {
+            if payload.event() == PageLoadEvent::Finished {
+                if let Some(tx) = loaded.lock().expect("pdf load mutex").take() {
+                    let _ = tx.send(());
+                }
+            }
+        })
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let result = match tokio::time::timeout(LOAD_TIMEOUT, rx).await {
+        Ok(Ok(())) => render_window(&window, output, options).await,
+        _ => Err("The page didn't load to render".to_string()),
+    };
+    let _ = window.destroy();
+    result
+}
+
+/// Render `source` as a PDF at `output`
+pub async fn render(
+    app: &AppHandle,
+    caller: &WebviewWindow,
+    source: Source,
+    output: &Path,
+    options: Options,
+) -> Result<(), String> {
+    use tauri::Manager;
+
+    match source {
+        Source::Window { label: None } => render_window(caller, output, options).await,
+        Source::Window { label: Some(label) } => {
+            let window = app
+                .get_webview_window(&label)
+                .ok_or_else(|| format!("No window labelled {}", label))?;
+            render_window(&window, output, options).await
+        }
+        Source::Html { html } => render_html(app, html, output, options).await,
+    }
+}
+
+/// WebKit prints the whole page, hidden or not, through a print job saved
+/// as a PDF
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::Options;
+    use crate::print;
+    use std::path::Path;
+    use tauri::WebviewWindow;
+
+    pub async fn render(
+        window: &WebviewWindow,
+        output: &Path,
+        options: Options,
+    ) -> Result<(), String> {
+        let options = print::Options {
+            orientation: options.orientation,
+            job_name: options.title,
+            paper_size: options.paper_size,
+            margins: options.margins,
+            header_and_footer: options.header_and_footer,
+            ..Default::default()
+        };
+        print::save_pdf(window, output, options).await
+    }
+}
+
+/// WebView2's own PDF printing, which lays out the whole page the way its
+/// print preview does, rather than a print job of the window as drawn
+#[cfg(windows)]
+mod platform {
+    use super::Options;
+    use crate::print::Orientation;
+    use std::cell::Cell;
+    use std::path::Path;
+    use std::rc::Rc;
+    use tauri::WebviewWindow;
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        ICoreWebView2Environment6, ICoreWebView2_2, ICoreWebView2_7,
+        COREWEBVIEW2_PRINT_ORIENTATION_LANDSCAPE, COREWEBVIEW2_PRINT_ORIENTATION_PORTRAIT,
+    };
+    use webview2_com::PrintToPdfCompletedHandler;
+    use windows::core::{Interface, HSTRING};
+
+    /// WebView2 measures paper in inches
+    const POINTS_PER_INCH: f64 = 72.0;
+
+    pub async fn render(
+        window: &WebviewWindow,
+        output: &Path,
+        options: Options,
+    ) -> Result<(), String> {
+        let output = HSTRING::from(output);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        window
+            .with_webview(move |webview| {
+                let tx = Rc::new(Cell::new(Some(tx)));
+                let started = (|| unsafe {
+                    let core = webview.controller().CoreWebView2()?;
+                    let settings = core
+                        .cast::<ICoreWebView2_2>()?
+                        .Environment()?
+                        .cast::<ICoreWebView2Environment6>()?
+                        .CreatePrintSettings()?;
+                    if let Some(orientation) = options.orientation {
+                        settings.SetOrientation(match orientation {
+                            Orientation::Portrait => COREWEBVIEW2_PRINT_ORIENTATION_PORTRAIT,
+                            Orientation::Landscape => COREWEBVIEW2_PRINT_ORIENTATION_LANDSCAPE,
+                        })?;
+                    }
+                    if let Some(paper) = options.paper_size {
+                        settings.SetPageWidth(paper.width / POINTS_PER_INCH)?;
+                        settings.SetPageHeight(paper.height / POINTS_PER_INCH)?;
+                    }
+                    if let Some(margins) = options.margins {
+                        settings.SetMarginTop(margins.top / POINTS_PER_INCH)?;
+                        settings.SetMarginRight(margins.right / POINTS_PER_INCH)?;
+                        settings.SetMarginBottom(margins.bottom / POINTS_PER_INCH)?;
+                        settings.SetMarginLeft(margins.left / POINTS_PER_INCH)?;
+                    }
+                    settings.SetShouldPrintHeaderAndFooter(options.header_and_footer)?;
+                    if let Some(title) = &options.title {
+                        settings.SetHeaderTitle(&HSTRING::from(title))?;
+                    }
+                    let done = Rc::clone(&tx);
+                    core.cast::<ICoreWebView2_7>()?.PrintToPdf(
+                        &output,
+                        &settings,
+                        &PrintToPdfCompletedHandler::create(Box::new(move |result, saved| {
+                            let result = match result {
+                                Ok(()) if saved => Ok(()),
+                                Ok(()) => Err("The PDF wasn't saved".to_string()),
+                                Err(e) => Err(e.message()),
+                            };
+                            if let Some(tx) = done.take() {
+                                let _ = tx.send(result);
+                            }
+                            Ok(())
+                        })),
+                    )
+                })();
+                if let Err(e) = started {
+                    if let Some(tx) = tx.take() {
+                        let _ = tx.send(Err(e.message()));
+                    }
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The web view didn't answer".to_string())?
+            .map_err(|e| format!("Couldn't save the PDF: {}", e))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::Options;
+    use std::path::Path;
+    use tauri::WebviewWindow;
+
+    pub async fn render(
+        _window: &WebviewWindow,
+        _output: &Path,
+        _options: Options,
+    ) -> Result<(), String> {
+        Err("Rendering PDFs is only available on macOS and Windows".to_string())
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sent to every window with every permission's `Status` when one changes
+pub const EVENT: &str = "permissions-changed";
+
+/// A privacy permission smudge can use. Only macOS asks the user for these;
+/// elsewhere they're all granted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Kind {
+    /// Reading notes folders and watch folders anywhere, including other
+    /// apps' data
+    FullDiskAccess,
+    ScreenRecording,
+    Accessibility,
+    Camera,
+    Microphone,
+}
+
+const KINDS: [Kind; 5] = [
+    Kind::FullDiskAccess,
+    Kind::ScreenRecording,
+    Kind::Accessibility,
+    Kind::Camera,
+    Kind::Microphone,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub enum Status {
+    Granted,
+    /// Turned down or never turned on; only the user can change it, in
+    /// System Settings
+    Denied,
+    /// The user hasn't been asked yet
+    NotDetermined,
+    /// A configuration profile or parental controls keep it off
+    Restricted,
+}
+
+pub type Statuses = BTreeMap<Kind, Status>;
+
+/// Every permission's status as last sent to the frontend
+pub struct Permissions(Mutex<Statuses>);
+
+impl Permissions {
+    pub fn new() -> Self {
+        Self(Mutex::new(statuses()))
+    }
+}
+
+pub fn status(kind: Kind) -> Status {
+    platform::status(kind)
+}
+
+pub fn statuses() -> Statuses {
+    KINDS.iter().map(|&kind| (kind, status(kind))).collect()
+}
+
+/// Ask for `kind` unless it's granted, and return its status before asking.
+/// The camera and microphone are asked for with the system's prompt the first
+/// time; otherwise System Settings opens at the permission's pane, since only
+/// the user can turn it on. The answer arrives as `EVENT`.
+pub fn request(kind: Kind) -> Status {
+    platform::request(kind)
+}
+
+/// Read every status again and tell every window if any changed. Called when
+/// the app is activated, since permissions are granted in System Settings,
+/// and when the user answers a prompt.
+pub fn refresh(app: &AppHandle) {
+    let Some(permissions) = app.try_state::<Permissions>() else {
+        return;
+    };
+    let now = statuses();
+    {
+        let mut last = permissions.0.lock().expect("permissions mutex");
+        if *last == now {
+            return;
+        }
+        log::info!(statuses:? = now; "permissions changed");
+        *last = now.clone();
+    }
+    let _ = app.emit(EVENT, now);
+}
+
+/// Transparency, Consent and Control through tao
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Kind, Status};
+    use tauri_runtime_wry::tao::permission::{Permission, PermissionStatus};
+    use tauri_runtime_wry::tao::platform::macos;
+
+    fn permission(kind: Kind) -> Permission {
+        match kind {
+            Kind::FullDiskAccess => Permission::FullDiskAccess,
+            Kind::ScreenRecording => Permission::ScreenRecording,
+            Kind::Accessibility => Permission::Accessibility,
+            Kind::Camera => Permission::Camera,
+            Kind::Microphone => Permission::Microphone,
+        }
+    }
+
+    fn status_from(status: PermissionStatus) -> Status {
+        match status {
+            PermissionStatus::Granted => Status::Granted,
+            PermissionStatus::NotDetermined => Status::NotDetermined,
+            PermissionStatus::Restricted => Status::Restricted,
+            _ => Status::Denied,
+        }
+    }
+
+    pub fn status(kind: Kind) -> Status {
+        status_from(macos::permission_status(permission(kind)))
+    }
+
+    pub fn request(kind: Kind) -> Status {
+        status_from(macos::request_permission(permission(kind)))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::{Kind, Status};
+
+    /// Nothing stands between smudge and these beyond ordinary file
+    /// permissions
+    pub fn status(_kind: Kind) -> Status {
+        Status::Granted
+    }
+
+    pub fn request(_kind: Kind) -> Status {
+        Status::Granted
+    }
+}
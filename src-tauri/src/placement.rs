@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, Runtime, Window};
+
+use crate::db::Database;
+use crate::mini_window;
+
+/// How much of a saved window must overlap a display, in physical pixels,
+/// for it to count as reachable
+const MIN_VISIBLE: i32 = 48;
+
+/// Which monitor a window opens on
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PlacementPolicy {
+    /// The monitor under the mouse cursor
+    CursorMonitor,
+    /// The monitor of whichever window has focus
+    FocusedWindowMonitor,
+    /// Where the window with the same label was last closed
+    #[default]
+    Remembered,
+}
+
+/// A window's last known frame: outer position and inner size, in physical
+/// pixels (the same units `set_position` and `set_size` take)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedPlacement {
+    pub monitor: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+/// Database namespace of the placements, by window label
+pub const NAMESPACE: &str = "window-placement";
+
+/// Last known placement per window label, persisted to the database
+pub struct Placements {
+    db: Option<Database>,
+    saved: Mutex<HashMap<String, SavedPlacement>>,
+}
+
+impl Placements {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let db = app.try_state::<Database>().map(|db| db.inner().clone());
+        let saved = db
+            .as_ref()
+            .and_then(|db| db.list(NAMESPACE).ok())
+            .map(|saved| saved.into_iter().collect())
+            .unwrap_or_default();
+        Self {
+            db,
+            saved: Mutex::new(saved),
+        }
+    }
+
+    pub fn get(&self, label: &str) -> Option<SavedPlacement> {
+        self.saved
+            .lock()
+            .expect("placements mutex")
+            .get(label)
+            .cloned()
+    }
+
+    /// Record `window`'s current frame. Maximized windows keep their last
+    /// normal frame so un-maximizing after a restart lands somewhere sensible.
+    pub fn record<R: Runtime>(&self, window: &Window<R>) {
+        let maximized = window.is_maximized().unwrap_or(false);
+        let minimized = window.is_minimized().unwrap_or(false);
+        if minimized {
+            return;
+        }
+        let mut saved = self.saved.lock().expect("placements mutex");
+        // Pooled mini windows share the mini window's placement
+        let label = if mini_window::is_mini(window.label()) {
+            mini_window::LABEL.to_string()
+        } else {
+            window.label().to_string()
+        };
+        if maximized {
+            if let Some(existing) = saved.get_mut(&label) {
+                existing.maximized = true;
+                return;
+            }
+        }
+        let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+            return;
+        };
+        let monitor = window
+            .current_monitor()
+            .ok()
+            .flatten()
+            .and_then(|m| m.name().cloned());
+        saved.insert(
+            label,
+            SavedPlacement {
+                monitor,
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized,
+            },
+        );
+    }
+
+    pub fn persist(&self) -> Result<(), String> {
+        let Some(db) = &self.db else {
+            return Ok(());
+        };
+        let saved = self.saved.lock().expect("placements mutex");
+        db.replace(NAMESPACE, saved.iter())
+    }
+}
+
+/// Pick the monitor a window with `label` should open on
+pub fn resolve_monitor<R: Runtime>(
+    app: &AppHandle<R>,
+    policy: PlacementPolicy,
+    label: &str,
+) -> Option<Monitor> {
+    let monitor = match policy {
+        PlacementPolicy::CursorMonitor => app
+            .cursor_position()
+            .ok()
+            .and_then(|p| app.monitor_from_point(p.x, p.y).ok().flatten()),
+        PlacementPolicy::FocusedWindowMonitor => app
+            .webview_windows()
+            .into_values()
+            .find(|w| w.label() != label && w.is_focused().unwrap_or(false))
+            .and_then(|w| w.current_monitor().ok().flatten()),
+        PlacementPolicy::Remembered => {
+            let saved = app.try_state::<Placements>()?.get(label)?;
+            app.available_monitors()
+                .ok()?
+                .into_iter()
+                .find(|m| saved.monitor.is_some() && m.name() == saved.monitor.as_ref())
+        }
+    };
+    monitor.or_else(|| app.primary_monitor().ok().flatten())
+}
+
+/// How `move_to_monitor` places a window on the monitor it moves to
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MoveMode {
+    /// Keep the window's offset into the work area, pulled in to fit, and
+    /// keep it maximized or fullscreen if it was
+    #[default]
+    KeepRelative,
+    Center,
+    Maximize,
+    Fullscreen,
+}
+
+/// Whether a frame still overlaps some display enough to grab it
+fn is_reachable(
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    monitors: &[Monitor],
+) -> bool {
+    monitors.iter().any(|m| {
+        let area = m.work_area();
+        let left = position.x.max(area.position.x);
+        let right = (position.x + size.width as i32).min(area.position.x + area.size.width as i32);
+        let top = position.y.max(area.position.y);
+        let bottom =
+            (position.y + size.height as i32).min(area.position.y + area.size.height as i32);
+        right - left >= MIN_VISIBLE && bottom - top >= MIN_VISIBLE
+    })
+}
+
+/// Center `size` in the monitor's work area, shrinking it to fit
+fn centered(
+    monitor: &Monitor,
+    size: PhysicalSize<u32>,
+) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    let area = monitor.work_area();
+    let width = size.width.min(area.size.width);
+    let height = size.height.min(area.size.height);
+    let x = area.position.x + (area.size.width - width) as i32 / 2;
+    let y = area.position.y + (area.size.height - height) as i32 / 2;
+    (
+        PhysicalPosition::new(x, y),
+        PhysicalSize::new(width, height),
+    )
+}
+
+/// Move a new window according to `policy`. With the remembered policy the
+/// saved frame is restored as-is, unless no display intersects it any more
+/// (a monitor was unplugged or rearranged), in which case the window is
+/// centered on the primary monitor instead.
+pub fn place<R: Runtime>(window: &Window<R>, policy: PlacementPolicy) -> Result<(), String> {
+    let app = window.app_handle();
+    let label = window.label();
+
+    if policy == PlacementPolicy::Remembered {
+        if let Some(saved) = app.try_state::<Placements>().and_then(|p| p.get(label)) {
+            return restore(window, &saved);
+        }
+    }
+
+    let Some(monitor) = resolve_monitor(app, policy, label) else {
+        return Ok(());
+    };
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+    let (position, size) = centered(&monitor, size);
+    window.set_size(size).map_err(|e| e.to_string())?;
+    window.set_position(position).map_err(|e| e.to_string())
+}
+
+/// Put `window` back in a saved frame, or center it on the primary monitor
+/// if no display intersects the frame any more
+pub fn restore<R: Runtime>(window: &Window<R>, saved: &SavedPlacement) -> Result<(), String> {
+    let monitors = window.available_monitors().map_err(|e| e.to_string())?;
+    let position = PhysicalPosition::new(saved.x, saved.y);
+    let size = PhysicalSize::new(saved.width, saved.height);
+    if is_reachable(position, size, &monitors) {
+        window.set_size(size).map_err(|e| e.to_string())?;
+        window.set_position(position).map_err(|e| e.to_string())?;
+        if saved.maximized {
+            window.maximize().map_err(|e| e.to_string())?;
+        }
+        return Ok(());
+    }
+    let monitor = window
+        .primary_monitor()
+        .map_err(|e| e.to_string())?
+        .or_else(|| monitors.into_iter().next())
+        .ok_or("No monitor available")?;
+    let (position, size) = centered(&monitor, size);
+    window.set_size(size).map_err(|e| e.to_string())?;
+    window.set_position(position).map_err(|e| e.to_string())
+}
+
+/// Center `window` on the primary monitor if no display shows enough of it
+/// any more, as when the monitor it was on is unplugged
+pub fn keep_reachable<R: Runtime>(window: &Window<R>) -> Result<(), String> {
+    if window.is_minimized().unwrap_or(false) || !window.is_visible().unwrap_or(false) {
+        return Ok(());
+    }
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+    let monitors = window.available_monitors().map_err(|e| e.to_string())?;
+    if is_reachable(position, size, &monitors) {
+        return Ok(());
+    }
+    let monitor = window
+        .primary_monitor()
+        .map_err(|e| e.to_string())?
+        .or_else(|| monitors.into_iter().next())
+        .ok_or("No monitor available")?;
+    let (position, size) = centered(&monitor, size);
+    window.set_size(size).map_err(|e| e.to_string())?;
+    window.set_position(position).map_err(|e| e.to_string())
+}
+
+/// Move `window` onto the monitor named `name`, placing it there according
+/// to `mode`. A maximized or fullscreen window is restored before it moves.
+pub fn move_to_monitor<R: Runtime>(
+    window: &Window<R>,
+    name: &str,
+    mode: MoveMode,
+) -> Result<(), String> {
+    let monitor = window
+        .available_monitors()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|m| m.name().map(String::as_str) == Some(name))
+        .ok_or_else(|| format!("No monitor named {}", name))?;
+    let was_fullscreen = window.is_fullscreen().unwrap_or(false);
+    if was_fullscreen {
+        window.set_fullscreen(false).map_err(|e| e.to_string())?;
+    }
+    let was_maximized = window.is_maximized().unwrap_or(false);
+    if was_maximized {
+        window.unmaximize().map_err(|e| e.to_string())?;
+    }
+
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+    let (mut position, size) = centered(&monitor, size);
+    if mode == MoveMode::KeepRelative {
+        let from = window.current_monitor().ok().flatten();
+        if let (Some(from), Ok(current)) = (from, window.outer_position()) {
+            let (old, area) = (from.work_area(), monitor.work_area());
+            let right = area.position.x + (area.size.width - size.width) as i32;
+            let bottom = area.position.y + (area.size.height - size.height) as i32;
+            position = PhysicalPosition::new(
+                (area.position.x + current.x - old.position.x).clamp(area.position.x, right),
+                (area.position.y + current.y - old.position.y).clamp(area.position.y, bottom),
+            );
+        }
+    }
+    // Moved before it's sized, so the window has taken the monitor's scale
+    window.set_position(position).map_err(|e| e.to_string())?;
+    window.set_size(size).map_err(|e| e.to_string())?;
+
+    match mode {
+        MoveMode::Fullscreen => window.set_fullscreen(true),
+        MoveMode::KeepRelative if was_fullscreen => window.set_fullscreen(true),
+        MoveMode::Maximize => window.maximize(),
+        MoveMode::KeepRelative if was_maximized => window.maximize(),
+        _ => Ok(()),
+    }
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,694 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use wasmi::{
+    Caller, Config, Engine, Extern, Instance, Linker, Module, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc,
+};
+
+use crate::executor;
+
+/// The plugin interface's version. Plugins built against another one are
+/// refused rather than guessed at.
+pub const ABI_VERSION: u32 = 1;
+/// Which plugins the user turned off, by ID
+const NAMESPACE: &str = "plugins";
+const DISABLED_KEY: &str = "disabled";
+/// Each plugin's `storage` is its own namespace under this prefix
+const STORAGE_NAMESPACE_PREFIX: &str = "plugin.";
+/// A plugin's memory can't grow past this
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+/// Instructions a plugin may run per call, give or take, so a stuck one
+/// fails instead of holding a worker forever
+const FUEL_PER_CALL: u64 = 2_000_000_000;
+/// What plugins' own events reach the frontend as, with the plugin's ID,
+/// the event's name and its payload
+pub const EMIT_EVENT: &str = "plugin-event";
+/// Where the host's functions are imported from
+const HOST_MODULE: &str = "smudge";
+
+/// What a plugin may use beyond logging. Imports it makes need the matching
+/// capability in its manifest, or it isn't loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+    /// Keep values of its own between runs
+    Storage,
+    /// Hear the app events its manifest lists
+    Events,
+    /// Send events to the frontend, as `EMIT_EVENT`
+    Emit,
+}
+
+/// The host functions, and what each needs
+const IMPORTS: &[(&str, Option<Capability>)] = &[
+    ("log", None),
+    ("storage_get", Some(Capability::Storage)),
+    ("storage_set", Some(Capability::Storage)),
+    ("storage_delete", Some(Capability::Storage)),
+    ("emit", Some(Capability::Emit)),
+];
+
+/// App events plugins can subscribe to
+pub const EVENTS: &[&str] = &["note-saved", "note-deleted"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginCommand {
+    pub name: String,
+    /// How the frontend labels it
+    pub title: Option<String>,
+}
+
+/// `plugin.json`, next to the plugin's module in a folder of its own under
+/// the plugins folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// Reverse-DNS, e.g. `com.example.word-count`
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub abi_version: u32,
+    /// Versions of smudge it works with, as a semver requirement
+    #[serde(default)]
+    pub smudge_version: Option<String>,
+    /// The WebAssembly module, relative to the manifest
+    #[serde(default = "default_module")]
+    pub module: String,
+    #[serde(default)]
+    pub capabilities: Vec<Capability>,
+    #[serde(default)]
+    pub commands: Vec<PluginCommand>,
+    /// Of `EVENTS`; needs the `events` capability
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+fn default_module() -> String {
+    "plugin.wasm".to_string()
+}
+
+impl Manifest {
+    /// Whether this smudge can run the plugin
+    fn check(&self, app_version: &semver::Version) -> Result<(), String> {
+        if self.abi_version != ABI_VERSION {
+            return Err(format!(
+                "Built for plugin interface {}, but this smudge has {}",
+                self.abi_version, ABI_VERSION
+            ));
+        }
+        if let Some(requirement) = &self.smudge_version {
+            let requirement = semver::VersionReq::parse(requirement)
+                .map_err(|e| format!("Invalid smudgeVersion: {}", e))?;
+            if !requirement.matches(app_version) {
+                return Err(format!(
+                    "Needs smudge {}, this is {}",
+                    requirement, app_version
+                ));
+            }
+        }
+        let module = Path::new(&self.module);
+        if !module
+            .components()
+            .all(|part| matches!(part, Component::Normal(_)))
+        {
+            return Err("The module has to be inside the plugin's folder".to_string());
+        }
+        if let Some(event) = self.events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+            return Err(format!("No event named {}", event));
+        }
+        if !self.events.is_empty() && !self.capabilities.contains(&Capability::Events) {
+            return Err("Subscribes to events without the events capability".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A plugin as the frontend lists it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    /// The manifest's ID, or the folder's name when the manifest couldn't
+    /// be read
+    pub id: String,
+    pub path: PathBuf,
+    pub manifest: Option<Manifest>,
+    pub enabled: bool,
+    pub loaded: bool,
+    /// Why it isn't loaded, or why its last call failed
+    pub error: Option<String>,
+}
+
+/// What a plugin's host functions can reach
+struct Host {
+    app: AppHandle,
+    id: String,
+    capabilities: HashSet<Capability>,
+    limits: StoreLimits,
+}
+
+/// A running plugin and the exports the host calls
+struct Loaded {
+    store: Store<Host>,
+    instance: Instance,
+}
+
+struct Plugin {
+    id: String,
+    path: PathBuf,
+    manifest: Option<Manifest>,
+    loaded: Mutex<Option<Loaded>>,
+    error: Mutex<Option<String>>,
+}
+
+impl Plugin {
+    fn info(&self, disabled: &[String]) -> PluginInfo {
+        PluginInfo {
+            id: self.id.clone(),
+            path: self.path.clone(),
+            manifest: self.manifest.clone(),
+            enabled: !disabled.contains(&self.id),
+            loaded: self.loaded.lock().expect("plugin mutex").is_some(),
+            error: self.error.lock().expect("plugin error mutex").clone(),
+        }
+    }
+
+    fn set_error(&self, error: Option<String>) {
+        if let Some(error) = &error {
+            log::warn!(plugin = self.id.as_str(), error:% = error; "plugin failed");
+        }
+        *self.error.lock().expect("plugin error mutex") = error;
+    }
+}
+
+/// Plugins from `<app data>/plugins`, managed as Tauri state. Each is a
+/// WebAssembly module run by an interpreter in a store of its own, so it
+/// can only reach what the host functions its manifest's capabilities allow
+/// give it, in bounded memory and time. Calls run on the executor's
+/// background queue.
+///
+/// The interface, version `ABI_VERSION`: the module exports `memory`,
+/// `smudge_abi_version() -> i32` and `smudge_alloc(len) -> ptr`, and
+/// optionally `smudge_init()`, `smudge_command(name, name_len, args,
+/// args_len) -> i64` and `smudge_event(name, name_len, payload,
+/// payload_len)`. Strings and JSON go as UTF-8 pointer and length pairs;
+/// returned buffers are packed into an i64 as `ptr << 32 | len`, or -1 for
+/// none. Host functions are imported from the `smudge` module.
+pub struct Plugins {
+    engine: Engine,
+    dir: Option<PathBuf>,
+    plugins: Mutex<Vec<Arc<Plugin>>>,
+}
+
+impl Plugins {
+    pub fn new(app: &AppHandle) -> Self {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        Self {
+            engine: Engine::new(&config),
+            dir: app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("plugins"))
+                .ok(),
+            plugins: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Unload every plugin, then find and load the enabled ones again.
+    /// Plugins that can't be loaded are still listed, with the reason.
+    pub fn reload(&self, app: &AppHandle) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let _ = fs::create_dir_all(dir);
+        let disabled = disabled(app);
+        let app_version = app.package_info().version.clone();
+        let mut plugins = Vec::new();
+        let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        entries.sort();
+        for path in entries.into_iter().filter(|path| path.is_dir()) {
+            let folder = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let manifest = read_manifest(&path);
+            let plugin = Plugin {
+                id: manifest
+                    .as_ref()
+                    .map_or(folder, |manifest| manifest.id.clone()),
+                manifest: manifest.as_ref().ok().cloned(),
+                path,
+                loaded: Mutex::new(None),
+                error: Mutex::new(None),
+            };
+            if plugins.iter().any(|p: &Arc<Plugin>| p.id == plugin.id) {
+                plugin.set_error(Some("Another plugin has the same ID".to_string()));
+            } else if let Err(e) = manifest {
+                plugin.set_error(Some(e));
+            } else if !disabled.contains(&plugin.id) {
+                match self.load(app, &plugin, &app_version) {
+                    Ok(loaded) => {
+                        *plugin.loaded.lock().expect("plugin mutex") = Some(loaded);
+                        log::info!(plugin = plugin.id.as_str(); "plugin loaded");
+                    }
+                    Err(e) => plugin.set_error(Some(e)),
+                }
+            }
+            plugins.push(Arc::new(plugin));
+        }
+        *self.plugins.lock().expect("plugins mutex") = plugins;
+    }
+
+    fn load(
+        &self,
+        app: &AppHandle,
+        plugin: &Plugin,
+        app_version: &semver::Version,
+    ) -> Result<Loaded, String> {
+        let manifest = plugin
+            .manifest
+            .as_ref()
+            .expect("loaded plugins have manifests");
+        manifest.check(app_version)?;
+        let bytes = fs::read(plugin.path.join(&manifest.module))
+            .map_err(|e| format!("Couldn't read {}: {}", manifest.module, e))?;
+        let module = Module::new(&self.engine, bytes).map_err(|e| e.to_string())?;
+
+        let capabilities: HashSet<Capability> = manifest.capabilities.iter().copied().collect();
+        for import in module.imports() {
+            let needs = IMPORTS
+                .iter()
+                .find(|(name, _)| import.module() == HOST_MODULE && import.name() == *name)
+                .ok_or_else(|| {
+                    format!(
+                        "Imports {}.{}, which smudge doesn't provide",
+                        import.module(),
+                        import.name()
+                    )
+                })?
+                .1;
+            if let Some(capability) = needs.filter(|needs| !capabilities.contains(needs)) {
+                return Err(format!(
+                    "Imports {} without the {:?} capability",
+                    import.name(),
+                    capability
+                ));
+            }
+        }
+
+        let mut store = Store::new(
+            &self.engine,
+            Host {
+                app: app.clone(),
+                id: plugin.id.clone(),
+                capabilities,
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(MAX_MEMORY_BYTES)
+                    .build(),
+            },
+        );
+        store.limiter(|host| &mut host.limits);
+        store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+        let instance = linker(&self.engine)?
+            .instantiate_and_start(&mut store, &module)
+            .map_err(|e| e.to_string())?;
+
+        let version: TypedFunc<(), i32> = instance
+            .get_typed_func(&store, "smudge_abi_version")
+            .map_err(|_| "Doesn't export smudge_abi_version".to_string())?;
+        let version = version.call(&mut store, ()).map_err(|e| e.to_string())?;
+        if version != ABI_VERSION as i32 {
+            return Err(format!(
+                "Module reports plugin interface {}, but its manifest says {}",
+                version, ABI_VERSION
+            ));
+        }
+        if instance.get_memory(&store, "memory").is_none() {
+            return Err("Doesn't export its memory".to_string());
+        }
+        if let Ok(init) = instance.get_typed_func::<(), ()>(&store, "smudge_init") {
+            init.call(&mut store, ()).map_err(|e| e.to_string())?;
+        }
+        Ok(Loaded { store, instance })
+    }
+
+    pub fn list(&self, app: &AppHandle) -> Vec<PluginInfo> {
+        let disabled = disabled(app);
+        self.plugins
+            .lock()
+            .expect("plugins mutex")
+            .iter()
+            .map(|plugin| plugin.info(&disabled))
+            .collect()
+    }
+
+    fn get(&self, id: &str) -> Result<Arc<Plugin>, String> {
+        self.plugins
+            .lock()
+            .expect("plugins mutex")
+            .iter()
+            .find(|plugin| plugin.id == id)
+            .cloned()
+            .ok_or_else(|| format!("No plugin {}", id))
+    }
+
+    fn loaded(&self) -> Vec<Arc<Plugin>> {
+        self.plugins
+            .lock()
+            .expect("plugins mutex")
+            .iter()
+            .filter(|plugin| plugin.loaded.lock().expect("plugin mutex").is_some())
+            .cloned()
+            .collect()
+    }
+}
+
+fn read_manifest(dir: &Path) -> Result<Manifest, String> {
+    let text = fs::read_to_string(dir.join("plugin.json"))
+        .map_err(|e| format!("Couldn't read plugin.json: {}", e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid plugin.json: {}", e))
+}
+
+fn disabled(app: &AppHandle) -> Vec<String> {
+    crate::database(app)
+        .ok()
+        .and_then(|db| db.get(NAMESPACE, DISABLED_KEY).ok().flatten())
+        .unwrap_or_default()
+}
+
+fn packed(ptr: i32, len: usize) -> i64 {
+    ((ptr as u32 as i64) << 32) | len as i64
+}
+
+/// `len` bytes of the caller's memory at `ptr`
+fn guest_bytes(caller: &Caller<'_, Host>, ptr: i32, len: i32) -> Result<Vec<u8>, wasmi::Error> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmi::Error::new("the plugin doesn't export its memory"))?;
+    let start = ptr as u32 as usize;
+    memory
+        .data(caller)
+        .get(start..start + len as u32 as usize)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| wasmi::Error::new("the plugin passed a buffer outside its memory"))
+}
+
+fn guest_string(caller: &Caller<'_, Host>, ptr: i32, len: i32) -> Result<String, wasmi::Error> {
+    String::from_utf8(guest_bytes(caller, ptr, len)?)
+        .map_err(|_| wasmi::Error::new("the plugin passed a string that isn't UTF-8"))
+}
+
+/// Copies `bytes` into memory the plugin allocates with its
+/// `smudge_alloc`, returning it packed
+fn give(
+    mut store: impl wasmi::AsContextMut<Data = Host>,
+    alloc: Option<Extern>,
+    memory: Option<Extern>,
+    bytes: &[u8],
+) -> Result<i64, wasmi::Error> {
+    let alloc = alloc
+        .and_then(Extern::into_func)
+        .ok_or_else(|| wasmi::Error::new("the plugin doesn't export smudge_alloc"))?
+        .typed::<i32, i32>(&store)?;
+    let memory = memory
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmi::Error::new("the plugin doesn't export its memory"))?;
+    let ptr = alloc.call(&mut store, bytes.len() as i32)?;
+    memory
+        .write(&mut store, ptr as u32 as usize, bytes)
+        .map_err(|_| wasmi::Error::new("smudge_alloc returned a buffer outside its memory"))?;
+    Ok(packed(ptr, bytes.len()))
+}
+
+fn storage(caller: &Caller<'_, Host>) -> Result<crate::db::Database, wasmi::Error> {
+    if !caller.data().capabilities.contains(&Capability::Storage) {
+        return Err(wasmi::Error::new("the plugin lacks the storage capability"));
+    }
+    crate::database(&caller.data().app)
+        .map(|db| db.inner().clone())
+        .map_err(wasmi::Error::new)
+}
+
+/// The host functions, as `IMPORTS` lists them. Capabilities are checked
+/// at load and again on each call.
+fn linker(engine: &Engine) -> Result<Linker<Host>, String> {
+    let mut linker = Linker::new(engine);
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "log",
+            |caller: Caller<'_, Host>,
+             level: i32,
+             ptr: i32,
+             len: i32|
+             -> Result<(), wasmi::Error> {
+                let message = guest_string(&caller, ptr, len)?;
+                let plugin = caller.data().id.as_str();
+                match level {
+                    0 => log::error!(plugin = plugin; "{}", message),
+                    1 => log::warn!(plugin = plugin; "{}", message),
+                    2 => log::info!(plugin = plugin; "{}", message),
+                    _ => log::debug!(plugin = plugin; "{}", message),
+                }
+                Ok(())
+            },
+        )
+        .and_then(|linker| {
+            linker.func_wrap(
+                HOST_MODULE,
+                "storage_get",
+                |mut caller: Caller<'_, Host>, ptr: i32, len: i32| -> Result<i64, wasmi::Error> {
+                    let key = guest_string(&caller, ptr, len)?;
+                    let namespace = format!("{}{}", STORAGE_NAMESPACE_PREFIX, caller.data().id);
+                    let value: Option<String> = storage(&caller)?
+                        .get(&namespace, &key)
+                        .map_err(wasmi::Error::new)?;
+                    match value {
+                        Some(value) => {
+                            let alloc = caller.get_export("smudge_alloc");
+                            let memory = caller.get_export("memory");
+                            give(&mut caller, alloc, memory, value.as_bytes())
+                        }
+                        None => Ok(-1),
+                    }
+                },
+            )
+        })
+        .and_then(|linker| {
+            linker.func_wrap(
+                HOST_MODULE,
+                "storage_set",
+                |caller: Caller<'_, Host>,
+                 key: i32,
+                 key_len: i32,
+                 value: i32,
+                 value_len: i32|
+                 -> Result<i32, wasmi::Error> {
+                    let key = guest_string(&caller, key, key_len)?;
+                    let value = guest_string(&caller, value, value_len)?;
+                    let namespace = format!("{}{}", STORAGE_NAMESPACE_PREFIX, caller.data().id);
+                    storage(&caller)?
+                        .put(&namespace, &key, &value)
+                        .map_err(wasmi::Error::new)?;
+                    Ok(0)
+                },
+            )
+        })
+        .and_then(|linker| {
+            linker.func_wrap(
+                HOST_MODULE,
+                "storage_delete",
+                |caller: Caller<'_, Host>, ptr: i32, len: i32| -> Result<i32, wasmi::Error> {
+                    let key = guest_string(&caller, ptr, len)?;
+                    let namespace = format!("{}{}", STORAGE_NAMESPACE_PREFIX, caller.data().id);
+                    storage(&caller)?
+                        .delete(&namespace, &key)
+                        .map_err(wasmi::Error::new)?;
+                    Ok(0)
+                },
+            )
+        })
+        .and_then(|linker| {
+            linker.func_wrap(
+                HOST_MODULE,
+                "emit",
+                |caller: Caller<'_, Host>,
+                 name: i32,
+                 name_len: i32,
+                 payload: i32,
+                 payload_len: i32|
+                 -> Result<i32, wasmi::Error> {
+                    if !caller.data().capabilities.contains(&Capability::Emit) {
+                        return Err(wasmi::Error::new("the plugin lacks the emit capability"));
+                    }
+                    let name = guest_string(&caller, name, name_len)?;
+                    let payload: Value =
+                        serde_json::from_str(&guest_string(&caller, payload, payload_len)?)
+                            .map_err(|e| wasmi::Error::new(format!("invalid JSON: {}", e)))?;
+                    let host = caller.data();
+                    let _ = host.app.emit(
+                        EMIT_EVENT,
+                        serde_json::json!({ "plugin": host.id, "name": name, "payload": payload }),
+                    );
+                    Ok(0)
+                },
+            )
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(linker)
+}
+
+/// Calls `export` with two strings, topping up the plugin's fuel first.
+/// Returns what it returned, if it returns an i64.
+fn call_with_strings(
+    loaded: &mut Loaded,
+    export: &str,
+    first: &str,
+    second: &str,
+) -> Result<Option<i64>, String> {
+    let Loaded { store, instance } = loaded;
+    store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+    let instance = *instance;
+    let alloc = instance.get_export(&*store, "smudge_alloc");
+    let memory = instance.get_export(&*store, "memory");
+    let first = give(&mut *store, alloc, memory, first.as_bytes()).map_err(|e| e.to_string())?;
+    let second = give(&mut *store, alloc, memory, second.as_bytes()).map_err(|e| e.to_string())?;
+    let args = (
+        (first >> 32) as i32,
+        first as i32,
+        (second >> 32) as i32,
+        second as i32,
+    );
+    if let Ok(func) = instance.get_typed_func::<(i32, i32, i32, i32), i64>(&*store, export) {
+        return func
+            .call(&mut *store, args)
+            .map(Some)
+            .map_err(|e| e.to_string());
+    }
+    instance
+        .get_typed_func::<(i32, i32, i32, i32), ()>(&*store, export)
+        .map_err(|_| format!("The plugin doesn't export {}", export))?
+        .call(&mut *store, args)
+        .map(|()| None)
+        .map_err(|e| e.to_string())
+}
+
+/// Run the plugin's command `command` with `args`. It answers with JSON,
+/// `{"ok": <result>}` or `{"error": "<message>"}`.
+pub async fn invoke(
+    app: &AppHandle,
+    id: &str,
+    command: &str,
+    args: Value,
+) -> Result<Value, String> {
+    let plugin = app.state::<Plugins>().get(id)?;
+    let declared = plugin
+        .manifest
+        .as_ref()
+        .is_some_and(|manifest| manifest.commands.iter().any(|c| c.name == command));
+    if !declared {
+        return Err(format!("{} has no command {}", id, command));
+    }
+    let command = command.to_string();
+    app.state::<executor::Executor>()
+        .run(executor::Priority::Background, move || {
+            let mut loaded = plugin.loaded.lock().expect("plugin mutex");
+            let loaded = loaded
+                .as_mut()
+                .ok_or_else(|| format!("{} isn't loaded", plugin.id))?;
+            let outcome = call_with_strings(loaded, "smudge_command", &command, &args.to_string())
+                .and_then(|result| {
+                    let result = result
+                        .filter(|&result| result >= 0)
+                        .ok_or_else(|| "The command returned nothing".to_string())?;
+                    let memory = loaded
+                        .instance
+                        .get_memory(&loaded.store, "memory")
+                        .expect("checked at load");
+                    let start = (result >> 32) as u32 as usize;
+                    let len = result as u32 as usize;
+                    memory
+                        .data(&loaded.store)
+                        .get(start..start + len)
+                        .ok_or_else(|| "The command's result is outside its memory".to_string())
+                        .and_then(|bytes| {
+                            serde_json::from_slice::<Value>(bytes)
+                                .map_err(|e| format!("The command's result isn't JSON: {}", e))
+                        })
+                });
+            if let Err(e) = &outcome {
+                plugin.set_error(Some(e.clone()));
+            }
+            match outcome? {
+                Value::Object(mut reply) => match (reply.remove("ok"), reply.remove("error")) {
+                    (_, Some(error)) => Err(error
+                        .as_str()
+                        .map_or_else(|| error.to_string(), str::to_string)),
+                    (Some(result), None) => Ok(result),
+                    (None, None) => Ok(Value::Null),
+                },
+                _ => Err("The command's result isn't an object".to_string()),
+            }
+        })
+        .await?
+}
+
+/// Tell the plugins subscribed to `event` about it, in the background
+pub fn dispatch(app: &AppHandle, event: &'static str, payload: Value) {
+    let Some(plugins) = app.try_state::<Plugins>() else {
+        return;
+    };
+    let subscribed: Vec<Arc<Plugin>> = plugins
+        .loaded()
+        .into_iter()
+        .filter(|plugin| {
+            plugin
+                .manifest
+                .as_ref()
+                .is_some_and(|manifest| manifest.events.iter().any(|e| e == event))
+        })
+        .collect();
+    if subscribed.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let payload = payload.to_string();
+        let _ = app
+            .state::<executor::Executor>()
+            .run(executor::Priority::Background, move || {
+                for plugin in subscribed {
+                    let mut loaded = plugin.loaded.lock().expect("plugin mutex");
+                    if let Some(loaded) = loaded.as_mut() {
+                        if let Err(e) = call_with_strings(loaded, "smudge_event", event, &payload) {
+                            plugin.set_error(Some(e));
+                        }
+                    }
+                }
+            })
+            .await;
+    });
+}
+
+/// Turn a plugin on or off for this and later launches
+pub fn set_enabled(app: &AppHandle, id: &str, enabled: bool) -> Result<(), String> {
+    let mut disabled = disabled(app);
+    disabled.retain(|disabled| disabled != id);
+    if !enabled {
+        disabled.push(id.to_string());
+    }
+    crate::database(app)?.put(NAMESPACE, DISABLED_KEY, &disabled)?;
+    app.state::<Plugins>().reload(app);
+    Ok(())
+}
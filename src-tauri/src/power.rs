@@ -0,0 +1,167 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::Database;
+
+/// Sent to every window with the `Change` when the system is about to sleep
+/// or has woken, or the screen is locked or unlocked
+pub const EVENT: &str = "power-event";
+
+/// A change reported by the OS: NSWorkspace and loginwindow notifications on
+/// macOS, power and session messages on Windows, logind on Linux
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Change {
+    WillSleep,
+    DidWake,
+    ScreenLocked,
+    ScreenUnlocked,
+}
+
+#[derive(Default)]
+pub struct Power {
+    asleep: AtomicBool,
+    locked: AtomicBool,
+}
+
+impl Power {
+    /// Whether nobody can be waiting on the app: the system is going to
+    /// sleep or the screen is locked
+    pub fn is_idle(&self) -> bool {
+        self.asleep.load(Ordering::Relaxed) || self.locked.load(Ordering::Relaxed)
+    }
+}
+
+/// Whether the system is idle; false before `Power` is managed
+pub fn is_idle(app: &AppHandle) -> bool {
+    app.try_state::<Power>()
+        .is_some_and(|power| power.is_idle())
+}
+
+/// Record `change` and tell every window, so they can let go of connections
+/// before the system sleeps and check them again once it's back. An
+/// encrypted database locks and unlocks with the screen.
+pub fn changed(app: &AppHandle, change: Change) {
+    let Some(power) = app.try_state::<Power>() else {
+        return;
+    };
+    let database = app.try_state::<Database>();
+    match change {
+        Change::WillSleep => power.asleep.store(true, Ordering::Relaxed),
+        Change::DidWake => power.asleep.store(false, Ordering::Relaxed),
+        Change::ScreenLocked => {
+            power.locked.store(true, Ordering::Relaxed);
+            if let Some(database) = database {
+                database.lock();
+            }
+        }
+        Change::ScreenUnlocked => {
+            power.locked.store(false, Ordering::Relaxed);
+            if let Some(Err(e)) = database.map(|database| database.unlock(app)) {
+                log::warn!(error:% = e; "database not unlocked");
+            }
+        }
+    }
+    let _ = app.emit(EVENT, change);
+}
+
+/// Whether the machine is running on its battery rather than mains power.
+/// False when it can't tell, e.g. on a desktop.
+pub fn on_battery() -> bool {
+    source::on_battery()
+}
+
+#[cfg(target_os = "macos")]
+mod source {
+    use std::ffi::{c_char, c_void, CStr};
+
+    type CFTypeRef = *const c_void;
+
+    const UTF8: u32 = 0x0800_0100;
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPSCopyPowerSourcesInfo() -> CFTypeRef;
+        fn IOPSGetProvidingPowerSourceType(snapshot: CFTypeRef) -> CFTypeRef;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringGetCString(
+            string: CFTypeRef,
+            buffer: *mut c_char,
+            size: isize,
+            encoding: u32,
+        ) -> bool;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    pub fn on_battery() -> bool {
+        unsafe {
+            let snapshot = IOPSCopyPowerSourcesInfo();
+            if snapshot.is_null() {
+                return false;
+            }
+            // Owned by the snapshot
+            let source = IOPSGetProvidingPowerSourceType(snapshot);
+            let mut buffer = [0 as c_char; 32];
+            let copied = !source.is_null()
+                && CFStringGetCString(source, buffer.as_mut_ptr(), buffer.len() as isize, UTF8);
+            let battery = copied && CStr::from_ptr(buffer.as_ptr()).to_bytes() == b"Battery Power";
+            CFRelease(snapshot);
+            battery
+        }
+    }
+}
+
+#[cfg(windows)]
+mod source {
+    /// `ACLineStatus` when the machine is unplugged
+    const AC_OFFLINE: u8 = 0;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+
+    pub fn on_battery() -> bool {
+        let mut status = SystemPowerStatus::default();
+        let ok = unsafe { GetSystemPowerStatus(&mut status) } != 0;
+        ok && status.ac_line_status == AC_OFFLINE
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod source {
+    use std::fs;
+
+    /// On battery when there's a mains supply and none of them is online
+    pub fn on_battery() -> bool {
+        let Ok(supplies) = fs::read_dir("/sys/class/power_supply") else {
+            return false;
+        };
+        let mains: Vec<_> = supplies
+            .filter_map(Result::ok)
+            .map(|supply| supply.path())
+            .filter(|path| {
+                fs::read_to_string(path.join("type")).is_ok_and(|kind| kind.trim() == "Mains")
+            })
+            .collect();
+        !mains.is_empty()
+            && !mains.iter().any(|path| {
+                fs::read_to_string(path.join("online")).is_ok_and(|online| online.trim() == "1")
+            })
+    }
+}
@@ -0,0 +1,509 @@
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+
+use crate::remote_control;
+
+/// Sent to every window with a `PreferencesStatus` when the preferences in
+/// effect change, or the file stops or starts parsing
+pub const EVENT: &str = "preferences-changed";
+
+/// `preferences.toml`, or `preferences.json` if that's what's there
+const FILE_STEM: &str = "preferences";
+/// How long the file has to be quiet before it's read, since editors save
+/// in several steps
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// App-wide tuning, read from the preferences file in the platform's config
+/// folder. Everything has a default, so the file only needs what differs,
+/// and unknown keys are left alone for newer versions. Notes folders keep
+/// their own `Settings`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Preferences {
+    pub network: Network,
+    pub websocket: WebSocket,
+    pub thumbnails: Thumbnails,
+    pub remote_control: RemoteControl,
+    pub webview: Webview,
+    pub telemetry: Telemetry,
+    pub spelling: Spelling,
+}
+
+/// HTTP requests, downloads and sync (see `http.rs`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Network {
+    /// "system" for the system's proxy, "direct" for none, or a proxy URL
+    pub proxy: String,
+    pub connect_timeout_secs: u64,
+    /// A stalled connection is given up after this long without data
+    pub read_timeout_secs: u64,
+    /// Default timeout of a whole request
+    pub request_timeout_secs: u64,
+    /// Bodies larger than this have to be downloaded to disk
+    pub max_response_mb: u64,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            proxy: "system".to_string(),
+            connect_timeout_secs: 30,
+            read_timeout_secs: 60,
+            request_timeout_secs: 30,
+            max_response_mb: 50,
+        }
+    }
+}
+
+/// Connections kept for the frontend (see `websocket.rs`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WebSocket {
+    pub ping_interval_secs: u64,
+    /// A connection quiet for this long is dropped and reconnected
+    pub idle_timeout_secs: u64,
+    /// Longest wait between reconnect attempts
+    pub max_backoff_secs: u64,
+}
+
+impl Default for WebSocket {
+    fn default() -> Self {
+        Self {
+            ping_interval_secs: 30,
+            idle_timeout_secs: 75,
+            max_backoff_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Thumbnails {
+    /// The cache is trimmed back to this at launch
+    pub cache_mb: u64,
+}
+
+impl Default for Thumbnails {
+    fn default() -> Self {
+        Self { cache_mb: 256 }
+    }
+}
+
+/// What clients of `--remote-control` may do (see `remote_control.rs`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RemoteControl {
+    /// Methods clients may call; the rest answer as not found
+    pub methods: Vec<String>,
+    /// Events clients may subscribe to
+    pub events: Vec<String>,
+}
+
+impl Default for RemoteControl {
+    fn default() -> Self {
+        Self {
+            methods: remote_control::DEFAULT_METHODS
+                .iter()
+                .map(|method| method.to_string())
+                .collect(),
+            events: remote_control::DEFAULT_EVENTS
+                .iter()
+                .map(|event| event.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// How the web views present themselves to sites (see `webview_data.rs`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Webview {
+    /// Sent instead of the web view's own user agent when not empty. Not
+    /// applied on Linux.
+    pub user_agent: String,
+    /// Language tags for `Accept-Language` and `navigator.languages`, most
+    /// preferred first. Empty follows the system. Windows only: WebKit
+    /// always sends the app's languages.
+    pub accept_languages: Vec<String>,
+}
+
+/// Counters and timings kept on this machine for the diagnostics pane (see
+/// `telemetry.rs`). Off unless turned on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Telemetry {
+    pub enabled: bool,
+}
+
+/// How the web views' editable fields check and substitute text as it's
+/// typed (see `spelling.rs`). What's unset follows System Settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Spelling {
+    /// The checker's language, as `en_GB` on macOS and `en-GB` on Windows.
+    /// Empty identifies each text's language on macOS and uses the user's
+    /// locale on Windows.
+    pub language: String,
+    /// Underline misspellings. macOS only, as are the rest.
+    pub check_while_typing: Option<bool>,
+    pub correct_automatically: Option<bool>,
+    pub smart_quotes: Option<bool>,
+    pub smart_dashes: Option<bool>,
+    /// The replacements set up in System Settings' Text pane
+    pub text_replacement: Option<bool>,
+}
+
+impl Preferences {
+    /// Values the types allow but smudge can't work with
+    fn validate(&self) -> Result<(), String> {
+        let network = &self.network;
+        if !matches!(network.proxy.as_str(), "system" | "direct") {
+            reqwest::Proxy::all(&network.proxy)
+                .map_err(|e| format!("network.proxy isn't a proxy URL: {}", e))?;
+        }
+        let at_least_one = [
+            ("network.connectTimeoutSecs", network.connect_timeout_secs),
+            ("network.readTimeoutSecs", network.read_timeout_secs),
+            ("network.requestTimeoutSecs", network.request_timeout_secs),
+            ("network.maxResponseMb", network.max_response_mb),
+            (
+                "websocket.pingIntervalSecs",
+                self.websocket.ping_interval_secs,
+            ),
+            ("websocket.maxBackoffSecs", self.websocket.max_backoff_secs),
+        ];
+        for (key, value) in at_least_one {
+            if value == 0 {
+                return Err(format!("{} has to be at least 1", key));
+            }
+        }
+        if self.websocket.idle_timeout_secs <= self.websocket.ping_interval_secs {
+            return Err(
+                "websocket.idleTimeoutSecs has to be longer than pingIntervalSecs".to_string(),
+            );
+        }
+        if self.webview.user_agent.chars().any(char::is_control) {
+            return Err("webview.userAgent can't have control characters".to_string());
+        }
+        if let Some(language) = self.webview.accept_languages.iter().find(|language| {
+            language.is_empty()
+                || !language
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        }) {
+            return Err(format!(
+                "webview.acceptLanguages: {:?} isn't a language tag",
+                language
+            ));
+        }
+        if self
+            .spelling
+            .language
+            .chars()
+            .any(|c| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        {
+            return Err(format!(
+                "spelling.language: {:?} isn't a language",
+                self.spelling.language
+            ));
+        }
+        let methods = &self.remote_control.methods;
+        if let Some(method) = methods
+            .iter()
+            .find(|method| !remote_control::METHODS.contains(&method.as_str()))
+        {
+            return Err(format!("remoteControl.methods: no method {}", method));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Toml,
+    Json,
+}
+
+fn parse(text: &str, format: Format) -> Result<Preferences, String> {
+    let preferences: Preferences = match format {
+        Format::Toml => toml::from_str(text).map_err(|e| e.to_string())?,
+        Format::Json => serde_json::from_str(text).map_err(|e| e.to_string())?,
+    };
+    preferences.validate()?;
+    Ok(preferences)
+}
+
+fn serialize(preferences: &Preferences, format: Format) -> Result<String, String> {
+    match format {
+        Format::Toml => toml::to_string_pretty(preferences).map_err(|e| e.to_string()),
+        Format::Json => serde_json::to_string_pretty(preferences).map_err(|e| e.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreferencesStatus {
+    pub values: Preferences,
+    /// The file they're read from, whether or not it exists yet
+    pub path: Option<PathBuf>,
+    /// Why the file couldn't be used. The last good values stay in effect
+    /// until it's fixed.
+    pub error: Option<String>,
+}
+
+/// The preferences in effect, managed as Tauri state. The file is watched,
+/// so edits made by hand apply without a restart, and Rust code that
+/// depends on a value subscribes to hear when it changes.
+pub struct PreferenceStore {
+    path: Option<PathBuf>,
+    format: Format,
+    values: watch::Sender<Arc<Preferences>>,
+    error: Mutex<Option<String>>,
+    /// What was last written, so the watcher doesn't reload it
+    written: Mutex<Option<String>>,
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl PreferenceStore {
+    /// Reads the preferences file, falling back to the defaults when there
+    /// isn't one or it's invalid
+    pub fn load(app: &AppHandle) -> Self {
+        let dir = app.path().app_config_dir().ok();
+        let (path, format) = match &dir {
+            Some(dir) => {
+                let json = dir.join(format!("{}.json", FILE_STEM));
+                if json.exists() && !dir.join(format!("{}.toml", FILE_STEM)).exists() {
+                    (Some(json), Format::Json)
+                } else {
+                    (Some(dir.join(format!("{}.toml", FILE_STEM))), Format::Toml)
+                }
+            }
+            None => (None, Format::Toml),
+        };
+        let store = Self {
+            path,
+            format,
+            values: watch::channel(Arc::new(Preferences::default())).0,
+            error: Mutex::new(None),
+            written: Mutex::new(None),
+            watcher: Mutex::new(None),
+        };
+        store.read();
+        store
+    }
+
+    /// Reads the file again. Returns whether the values changed.
+    fn read(&self) -> bool {
+        let Some(path) = &self.path else {
+            return false;
+        };
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                *self.error.lock().expect("preferences error mutex") = None;
+                return self.values.send_if_modified(|values| {
+                    let changed = **values != Preferences::default();
+                    *values = Arc::new(Preferences::default());
+                    changed
+                });
+            }
+            Err(e) => {
+                self.failed(format!("Couldn't read {}: {}", path.display(), e));
+                return false;
+            }
+        };
+        if self
+            .written
+            .lock()
+            .expect("preferences written mutex")
+            .as_ref()
+            .is_some_and(|written| *written == text)
+        {
+            return false;
+        }
+        match parse(&text, self.format) {
+            Ok(preferences) => {
+                *self.error.lock().expect("preferences error mutex") = None;
+                self.values.send_if_modified(|values| {
+                    let changed = **values != preferences;
+                    *values = Arc::new(preferences);
+                    changed
+                })
+            }
+            Err(e) => {
+                self.failed(format!("{}: {}", path.display(), e));
+                false
+            }
+        }
+    }
+
+    fn failed(&self, error: String) {
+        log::warn!(error:% = error; "preferences not applied");
+        *self.error.lock().expect("preferences error mutex") = Some(error);
+    }
+
+    /// Start reloading the file when it changes, until the app exits
+    pub fn watch(&self, app: &AppHandle) -> Result<(), String> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        let dir = path.parent().expect("preferences file has a folder");
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let (sender, receiver) = mpsc::channel();
+        let watched = path.clone();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<notify::Event, notify::Error>| {
+                if res.is_ok_and(|event| event.paths.iter().any(|p| p == &watched)) {
+                    let _ = sender.send(());
+                }
+            },
+            notify::Config::default(),
+        )
+        .map_err(|e| e.to_string())?;
+        // The folder rather than the file, which editors replace on save
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+        *self.watcher.lock().expect("preferences watcher mutex") = Some(watcher);
+
+        let app = app.clone();
+        std::thread::Builder::new()
+            .name("smudge-preferences".into())
+            .spawn(move || {
+                while receiver.recv().is_ok() {
+                    while receiver.recv_timeout(SETTLE).is_ok() {}
+                    let store = app.state::<PreferenceStore>();
+                    let had_error = store
+                        .error
+                        .lock()
+                        .expect("preferences error mutex")
+                        .is_some();
+                    let changed = store.read();
+                    let has_error = store
+                        .error
+                        .lock()
+                        .expect("preferences error mutex")
+                        .is_some();
+                    if changed || had_error || has_error {
+                        store.announce(&app);
+                    }
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn announce(&self, app: &AppHandle) {
+        log::info!(path:? = self.path; "preferences changed");
+        let _ = app.emit(EVENT, self.status());
+    }
+
+    pub fn get(&self) -> Arc<Preferences> {
+        Arc::clone(&self.values.borrow())
+    }
+
+    /// Hears every change to the values in effect
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Preferences>> {
+        self.values.subscribe()
+    }
+
+    pub fn status(&self) -> PreferencesStatus {
+        PreferencesStatus {
+            values: (*self.get()).clone(),
+            path: self.path.clone(),
+            error: self.error.lock().expect("preferences error mutex").clone(),
+        }
+    }
+
+    /// Set the value at `key`, a dotted path like `network.proxy`, or every
+    /// value with an empty `key`, and save the file. Refused while the file
+    /// has an error, so fixing it by hand doesn't lose the edits.
+    pub fn set(
+        &self,
+        app: &AppHandle,
+        key: &str,
+        value: Value,
+    ) -> Result<PreferencesStatus, String> {
+        if let Some(error) = self.error.lock().expect("preferences error mutex").clone() {
+            return Err(format!("Fix the preferences file first: {}", error));
+        }
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| "There's no config folder for preferences".to_string())?;
+        let mut json = serde_json::to_value(&*self.get()).map_err(|e| e.to_string())?;
+        if key.is_empty() {
+            json = value;
+        } else {
+            let pointer = format!("/{}", key.replace('.', "/"));
+            let slot = json
+                .pointer_mut(&pointer)
+                .ok_or_else(|| format!("No preference {}", key))?;
+            *slot = value;
+        }
+        let preferences: Preferences =
+            serde_json::from_value(json).map_err(|e| format!("Invalid {}: {}", key, e))?;
+        preferences.validate()?;
+
+        let text = serialize(&preferences, self.format)?;
+        write(path, &text).map_err(|e| format!("Couldn't save {}: {}", path.display(), e))?;
+        *self.written.lock().expect("preferences written mutex") = Some(text);
+        if self.values.send_if_modified(|values| {
+            let changed = **values != preferences;
+            *values = Arc::new(preferences);
+            changed
+        }) {
+            self.announce(app);
+        }
+        Ok(self.status())
+    }
+}
+
+/// Written aside and renamed, so the watcher never reads half a file
+fn write(path: &Path, text: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, text)?;
+    fs::rename(&temp, path)
+}
+
+/// The preferences in effect; the defaults before they're loaded
+pub fn current(app: &AppHandle) -> Arc<Preferences> {
+    app.try_state::<PreferenceStore>()
+        .map(|store| store.get())
+        .unwrap_or_default()
+}
+
+/// Call `changed` with the new preferences whenever the part `section`
+/// picks out of them changes, until the app exits
+pub fn on_change<T, F>(app: &AppHandle, section: fn(&Preferences) -> T, changed: F)
+where
+    T: PartialEq + Send + 'static,
+    F: Fn(&Preferences) + Send + 'static,
+{
+    let Some(store) = app.try_state::<PreferenceStore>() else {
+        return;
+    };
+    let mut receiver = store.subscribe();
+    tauri::async_runtime::spawn(async move {
+        let mut last = section(&receiver.borrow_and_update());
+        while receiver.changed().await.is_ok() {
+            let preferences = Arc::clone(&receiver.borrow_and_update());
+            let next = section(&preferences);
+            if next != last {
+                last = next;
+                changed(&preferences);
+            }
+        }
+    });
+}
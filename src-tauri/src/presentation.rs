@@ -0,0 +1,195 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewWindow};
+
+/// A display the user can present on
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Display {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub primary: bool,
+}
+
+/// Everything presentation mode changed, so it can be put back
+struct Saved {
+    label: String,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    maximized: bool,
+    /// Previous notification banner setting, where the desktop lets us change it
+    banners: Option<String>,
+}
+
+/// The window currently presenting, if any
+#[derive(Default)]
+pub struct PresentationState(Mutex<Option<Saved>>);
+
+impl PresentationState {
+    pub fn active_label(&self) -> Option<String> {
+        self.0
+            .lock()
+            .expect("presentation mutex")
+            .as_ref()
+            .map(|saved| saved.label.clone())
+    }
+}
+
+pub fn displays(app: &AppHandle) -> Result<Vec<Display>, String> {
+    let primary = app
+        .primary_monitor()
+        .map_err(|e| e.to_string())?
+        .and_then(|m| m.name().cloned());
+    Ok(app
+        .available_monitors()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let name = m
+                .name()
+                .cloned()
+                .unwrap_or_else(|| format!("Display {}", i + 1));
+            Display {
+                primary: primary.as_deref() == Some(name.as_str()),
+                width: m.size().width,
+                height: m.size().height,
+                name,
+            }
+        })
+        .collect())
+}
+
+fn find_display(app: &AppHandle, name: &str) -> Result<Monitor, String> {
+    app.available_monitors()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|m| m.name().map(String::as_str) == Some(name))
+        .ok_or_else(|| format!("Display {} is not connected", name))
+}
+
+/// Take `window` full-screen on `display` (its current display if None),
+/// hiding the menu bar and Dock on macOS and notification banners where
+/// the desktop allows it.
+///
+/// macOS has no public API to turn on a Focus, and Windows turns on Focus
+/// Assist for full-screen apps by itself, so neither is requested here.
+pub fn start(app: &AppHandle, window: &WebviewWindow, display: Option<&str>) -> Result<(), String> {
+    let state = app.state::<PresentationState>();
+    let mut current = state.0.lock().expect("presentation mutex");
+    if current.is_some() {
+        return Err("Already presenting".to_string());
+    }
+
+    let maximized = window.is_maximized().map_err(|e| e.to_string())?;
+    if maximized {
+        window.unmaximize().map_err(|e| e.to_string())?;
+    }
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+
+    if let Some(name) = display {
+        let monitor = find_display(app, name)?;
+        window
+            .set_position(*monitor.position())
+            .map_err(|e| e.to_string())?;
+    }
+
+    // On macOS simple fullscreen stays on the current Space and display, and
+    // restores the presentation options it replaces when it exits. Other
+    // platforms get regular fullscreen, which covers the taskbar.
+    window
+        .set_simple_fullscreen(true)
+        .map_err(|e| e.to_string())?;
+    #[cfg(target_os = "macos")]
+    {
+        use tauri_runtime_wry::tao::platform::macos::{
+            set_presentation_chrome, PresentationChrome,
+        };
+
+        // Queued after the fullscreen change, so it replaces the auto-hide
+        // options simple fullscreen sets
+        app.run_on_main_thread(|| set_presentation_chrome(PresentationChrome::Hidden))
+            .map_err(|e| e.to_string())?;
+    }
+
+    let _ = window.set_focus();
+
+    *current = Some(Saved {
+        label: window.label().to_string(),
+        position,
+        size,
+        maximized,
+        banners: mute_banners(),
+    });
+    Ok(())
+}
+
+/// Leave presentation mode and restore the window and desktop settings
+pub fn stop(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<PresentationState>();
+    let Some(saved) = state.0.lock().expect("presentation mutex").take() else {
+        return Ok(());
+    };
+    if let Some(banners) = saved.banners {
+        restore_banners(&banners);
+    }
+    let Some(window) = app.get_webview_window(&saved.label) else {
+        return Ok(());
+    };
+
+    window
+        .set_simple_fullscreen(false)
+        .map_err(|e| e.to_string())?;
+
+    window.set_size(saved.size).map_err(|e| e.to_string())?;
+    window
+        .set_position(saved.position)
+        .map_err(|e| e.to_string())?;
+    if saved.maximized {
+        window.maximize().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Turn off notification banners on GNOME, returning the previous value.
+/// Elsewhere `notifications::show` checks for presentation mode itself.
+fn mute_banners() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let output = std::process::Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let previous = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let _ = std::process::Command::new("gsettings")
+            .args([
+                "set",
+                "org.gnome.desktop.notifications",
+                "show-banners",
+                "false",
+            ])
+            .status();
+        Some(previous)
+    }
+    #[cfg(not(target_os = "linux"))]
+    None
+}
+
+fn restore_banners(previous: &str) {
+    #[cfg(target_os = "linux")]
+    let _ = std::process::Command::new("gsettings")
+        .args([
+            "set",
+            "org.gnome.desktop.notifications",
+            "show-banners",
+            previous,
+        ])
+        .status();
+    #[cfg(not(target_os = "linux"))]
+    let _ = previous;
+}
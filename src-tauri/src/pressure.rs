@@ -0,0 +1,335 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{thumbnails, AppState};
+
+/// Sent to every window with the `Pressure` whenever it changes
+pub const EVENT: &str = "pressure-changed";
+
+/// How short the system is of memory, as the OS grades it
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MemoryPressure {
+    #[default]
+    Normal,
+    /// Caches should be let go of
+    Warning,
+    /// The system is about to start ending apps to free memory
+    Critical,
+}
+
+/// How hot the machine is running, as `NSProcessInfo` grades it
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThermalState {
+    #[default]
+    Nominal,
+    /// Slightly warm; nothing needs to change
+    Fair,
+    /// Fans are at full speed and the CPU is being slowed; background work
+    /// should wait
+    Serious,
+    /// Everything that can stop should
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pressure {
+    pub memory: MemoryPressure,
+    /// Always nominal on Windows and Linux, which don't report it to apps
+    pub thermal: ThermalState,
+}
+
+impl Pressure {
+    /// Whether work that can wait should: memory is short or the machine is
+    /// hot
+    pub fn is_constrained(&self) -> bool {
+        self.memory >= MemoryPressure::Warning || self.thermal >= ThermalState::Serious
+    }
+}
+
+/// One of the readings the OS sends as it changes
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Debug, Clone, Copy)]
+enum Reading {
+    Memory(MemoryPressure),
+    Thermal(ThermalState),
+}
+
+/// The latest pressure the OS reported
+#[derive(Default)]
+pub struct Pressures(Mutex<Pressure>);
+
+/// Manage `Pressures` from the OS's current readings and follow its reports
+pub fn start(app: &AppHandle) {
+    app.manage(Pressures(Mutex::new(platform::current())));
+    let handle = app.clone();
+    if let Err(e) = platform::monitor(move |reading| changed(&handle, reading)) {
+        log::warn!(error:% = e; "memory and thermal pressure not followed");
+    }
+}
+
+/// The current pressure; normal before `start`
+pub fn current(app: &AppHandle) -> Pressure {
+    app.try_state::<Pressures>()
+        .map_or(Pressure::default(), |pressures| {
+            *pressures.0.lock().expect("pressure mutex")
+        })
+}
+
+pub fn is_constrained(app: &AppHandle) -> bool {
+    current(app).is_constrained()
+}
+
+fn changed(app: &AppHandle, reading: Reading) {
+    let Some(pressures) = app.try_state::<Pressures>() else {
+        return;
+    };
+    let (previous, pressure) = {
+        let mut pressure = pressures.0.lock().expect("pressure mutex");
+        let previous = *pressure;
+        match reading {
+            Reading::Memory(memory) => pressure.memory = memory,
+            Reading::Thermal(thermal) => pressure.thermal = thermal,
+        }
+        (previous, *pressure)
+    };
+    if pressure == previous {
+        return;
+    }
+    log::info!(memory:? = pressure.memory, thermal:? = pressure.thermal; "system pressure changed");
+    if pressure.memory > previous.memory {
+        release_memory(app);
+    }
+    let _ = app.emit(EVENT, pressure);
+}
+
+/// Drop what can be read or worked out again: the thumbnails' content
+/// hashes and what searches cached from the index
+fn release_memory(app: &AppHandle) {
+    if let Some(thumbnails) = app.try_state::<thumbnails::Thumbnails>() {
+        thumbnails.release_memory();
+    }
+    if let Some(state) = app.try_state::<AppState>() {
+        let index = state.search_index.lock().expect("search index mutex");
+        if let Some(Err(e)) = index.as_ref().map(|index| index.release_memory()) {
+            log::warn!(error:% = e; "search index caches not released");
+        }
+    }
+}
+
+/// A libdispatch memory pressure source and `NSProcessInfo`'s thermal state
+/// notifications
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{MemoryPressure, Pressure, Reading, ThermalState};
+    use block2::RcBlock;
+    use objc2_foundation::{
+        NSNotificationCenter, NSProcessInfo, NSProcessInfoThermalState,
+        NSProcessInfoThermalStateDidChangeNotification,
+    };
+    use std::ffi::{c_char, c_int, c_ulong, c_void};
+    use std::sync::Arc;
+
+    /// `DISPATCH_MEMORYPRESSURE_*`, which the source's data and the
+    /// `kern.memorystatus_vm_pressure_level` sysctl are graded in
+    const LEVEL_NORMAL: c_ulong = 0x1;
+    const LEVEL_WARN: c_ulong = 0x2;
+    const LEVEL_CRITICAL: c_ulong = 0x4;
+
+    #[repr(C)]
+    struct DispatchSourceType {
+        _private: [u8; 0],
+    }
+
+    extern "C" {
+        static _dispatch_source_type_memorypressure: DispatchSourceType;
+        fn dispatch_queue_create(label: *const c_char, attributes: *mut c_void) -> *mut c_void;
+        fn dispatch_source_create(
+            source_type: *const DispatchSourceType,
+            handle: usize,
+            mask: c_ulong,
+            queue: *mut c_void,
+        ) -> *mut c_void;
+        fn dispatch_source_set_event_handler(
+            source: *mut c_void,
+            handler: &block2::Block<dyn Fn()>,
+        );
+        fn dispatch_source_get_data(source: *mut c_void) -> c_ulong;
+        fn dispatch_resume(object: *mut c_void);
+        fn sysctlbyname(
+            name: *const c_char,
+            oldp: *mut c_void,
+            oldlenp: *mut usize,
+            newp: *mut c_void,
+            newlen: usize,
+        ) -> c_int;
+    }
+
+    fn memory_pressure(level: c_ulong) -> MemoryPressure {
+        if level & LEVEL_CRITICAL != 0 {
+            MemoryPressure::Critical
+        } else if level & LEVEL_WARN != 0 {
+            MemoryPressure::Warning
+        } else {
+            MemoryPressure::Normal
+        }
+    }
+
+    fn thermal_state() -> ThermalState {
+        match NSProcessInfo::processInfo().thermalState() {
+            NSProcessInfoThermalState::Fair => ThermalState::Fair,
+            NSProcessInfoThermalState::Serious => ThermalState::Serious,
+            NSProcessInfoThermalState::Critical => ThermalState::Critical,
+            _ => ThermalState::Nominal,
+        }
+    }
+
+    pub fn current() -> Pressure {
+        let mut level: c_int = 0;
+        let mut size = std::mem::size_of::<c_int>();
+        let result = unsafe {
+            sysctlbyname(
+                c"kern.memorystatus_vm_pressure_level".as_ptr(),
+                (&mut level as *mut c_int).cast(),
+                &mut size,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        Pressure {
+            memory: if result == 0 {
+                memory_pressure(level as c_ulong)
+            } else {
+                MemoryPressure::Normal
+            },
+            thermal: thermal_state(),
+        }
+    }
+
+    pub fn monitor(changed: impl Fn(Reading) + Send + Sync + 'static) -> Result<(), String> {
+        let changed = Arc::new(changed);
+        // The source, its queue and the observer last as long as the app,
+        // and are never released
+        unsafe {
+            let queue = dispatch_queue_create(c"smudge.pressure".as_ptr(), std::ptr::null_mut());
+            let source = dispatch_source_create(
+                &_dispatch_source_type_memorypressure,
+                0,
+                LEVEL_NORMAL | LEVEL_WARN | LEVEL_CRITICAL,
+                queue,
+            );
+            if source.is_null() {
+                return Err("the memory pressure source couldn't be created".to_string());
+            }
+            let memory = changed.clone();
+            let handler = RcBlock::new(move || {
+                memory(Reading::Memory(memory_pressure(dispatch_source_get_data(
+                    source,
+                ))))
+            });
+            dispatch_source_set_event_handler(source, &handler);
+            dispatch_resume(source);
+
+            // Posted on whichever thread noticed; `thermalState` can be read
+            // from any
+            let thermal = RcBlock::new(move |_| changed(Reading::Thermal(thermal_state())));
+            let observer = NSNotificationCenter::defaultCenter()
+                .addObserverForName_object_queue_usingBlock(
+                    Some(NSProcessInfoThermalStateDidChangeNotification),
+                    None,
+                    None,
+                    &thermal,
+                );
+            std::mem::forget(observer);
+        }
+        Ok(())
+    }
+}
+
+/// A low memory resource notification, waited on by a thread of its own.
+/// Windows doesn't tell apps how hot the machine is.
+#[cfg(windows)]
+mod platform {
+    use super::{MemoryPressure, Pressure, Reading};
+    use std::time::Duration;
+    use windows::core::BOOL;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::Memory::{
+        CreateMemoryResourceNotification, LowMemoryResourceNotification,
+        QueryMemoryResourceNotification,
+    };
+    use windows::Win32::System::Threading::{WaitForSingleObject, INFINITE};
+
+    /// The notification can't be waited on to clear, so while memory is low
+    /// it's checked this often
+    const RECHECK: Duration = Duration::from_secs(10);
+
+    fn memory_pressure(notification: HANDLE) -> MemoryPressure {
+        let mut low = BOOL(0);
+        match unsafe { QueryMemoryResourceNotification(notification, &mut low) } {
+            Ok(()) if low.as_bool() => MemoryPressure::Warning,
+            _ => MemoryPressure::Normal,
+        }
+    }
+
+    pub fn current() -> Pressure {
+        let memory = unsafe { CreateMemoryResourceNotification(LowMemoryResourceNotification) }
+            .map_or(MemoryPressure::Normal, |notification| {
+                let memory = memory_pressure(notification);
+                let _ = unsafe { CloseHandle(notification) };
+                memory
+            });
+        Pressure {
+            memory,
+            ..Pressure::default()
+        }
+    }
+
+    pub fn monitor(changed: impl Fn(Reading) + Send + Sync + 'static) -> Result<(), String> {
+        let notification =
+            unsafe { CreateMemoryResourceNotification(LowMemoryResourceNotification) }
+                .map_err(|e| e.to_string())?;
+        // Handles aren't `Send`; the thread owns this one for the app's life
+        let notification = notification.0 as isize;
+        std::thread::Builder::new()
+            .name("memory-pressure".to_string())
+            .spawn(move || {
+                let notification = HANDLE(notification as *mut _);
+                let mut last = memory_pressure(notification);
+                loop {
+                    // Signaled once memory runs low, and for as long as it
+                    // stays low
+                    if last == MemoryPressure::Normal {
+                        unsafe { WaitForSingleObject(notification, INFINITE) };
+                    } else {
+                        std::thread::sleep(RECHECK);
+                    }
+                    let memory = memory_pressure(notification);
+                    if memory != last {
+                        last = memory;
+                        changed(Reading::Memory(memory));
+                    }
+                }
+            })
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::{Pressure, Reading};
+
+    pub fn current() -> Pressure {
+        Pressure::default()
+    }
+
+    pub fn monitor(_changed: impl Fn(Reading) + Send + Sync + 'static) -> Result<(), String> {
+        Ok(())
+    }
+}
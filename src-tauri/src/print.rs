@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewWindow};
+use tokio::sync::oneshot;
+
+/// Sent to every window with a `Completed` when a print job ends
+pub const EVENT: &str = "print-completed";
+
+/// What to print
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Source {
+    /// The window's contents: the whole page on macOS, what's on screen on
+    /// Windows
+    Window,
+    /// A PDF, such as a generated report
+    Pdf { path: String },
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+/// An inclusive range of pages, counting from 1
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Pages {
+    pub first: u32,
+    pub last: u32,
+}
+
+/// A size of paper in points (1/72 inch), held in portrait
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct PaperSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Space left around what's printed, in points
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct Margins {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+/// How to print; the defaults show the print dialog with the system's
+/// settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Options {
+    pub pages: Option<Pages>,
+    pub orientation: Option<Orientation>,
+    /// A printer by name instead of the default one
+    pub printer: Option<String>,
+    /// Print straight to the printer, without a dialog
+    pub silent: bool,
+    /// The job's name in the print queue
+    pub job_name: Option<String>,
+    /// macOS: the paper instead of the printer's
+    pub paper_size: Option<PaperSize>,
+    /// macOS: the margins instead of the printer's
+    pub margins: Option<Margins>,
+    /// macOS: the job's name and the date above each page, its number below
+    pub header_and_footer: bool,
+}
+
+/// How a print job ended
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Outcome {
+    Printed,
+    Cancelled,
+    Failed { message: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Completed {
+    id: u64,
+    outcome: Outcome,
+}
+
+/// Print `source` from `window`, showing the print dialog over it unless
+/// `options` are silent. Returns the id the job's outcome is reported with.
+pub async fn print(
+    window: &WebviewWindow,
+    source: Source,
+    options: Options,
+) -> Result<u64, String> {
+    if let Some(pages) = options.pages {
+        if pages.first == 0 || pages.first > pages.last {
+            return Err(format!(
+                "Pages {}-{} aren't a range to print",
+                pages.first, pages.last
+            ));
+        }
+    }
+    platform::print(window, source, options, None).await
+}
+
+/// Jobs `save_pdf` is waiting on, and outcomes that came in while one was
+/// still starting, before it knew its id
+#[derive(Default)]
+pub struct PdfJobs(Mutex<PdfJobsInner>);
+
+#[derive(Default)]
+struct PdfJobsInner {
+    starting: usize,
+    early: HashMap<u64, Outcome>,
+    waiting: HashMap<u64, oneshot::Sender<Outcome>>,
+}
+
+/// Save the window's whole page as a PDF at `path`, without any dialog,
+/// once the job has finished. The job is named for the file unless
+/// `options` name it. Windows renders PDFs through WebView2 instead (see
+/// `pdf`).
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub async fn save_pdf(window: &WebviewWindow, path: &Path, options: Options) -> Result<(), String> {
+    let jobs = window.state::<PdfJobs>();
+    jobs.0.lock().expect("pdf jobs mutex").starting += 1;
+    let options = Options {
+        silent: true,
+        job_name: options.job_name.or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        }),
+        ..options
+    };
+    let started = platform::print(window, Source::Window, options, Some(path.to_path_buf())).await;
+    let outcome = {
+        let mut inner = jobs.0.lock().expect("pdf jobs mutex");
+        inner.starting -= 1;
+        let early = started.as_ref().ok().and_then(|id| inner.early.remove(id));
+        if inner.starting == 0 {
+            inner.early.clear();
+        }
+        let id = started?;
+        match early {
+            Some(outcome) => Ok(outcome),
+            None => {
+                let (tx, rx) = oneshot::channel();
+                inner.waiting.insert(id, tx);
+                Err(rx)
+            }
+        }
+    };
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(rx) => rx.await.map_err(|_| "The PDF wasn't saved".to_string())?,
+    };
+    match outcome {
+        Outcome::Printed => Ok(()),
+        Outcome::Cancelled => Err("Saving the PDF was cancelled".to_string()),
+        Outcome::Failed { message } => Err(format!("Couldn't save the PDF: {}", message)),
+    }
+}
+
+/// Tell the windows how a print job ended, or the `save_pdf` waiting on it
+pub fn completed(app: &AppHandle, id: u64, outcome: Outcome) {
+    use tauri::Emitter;
+
+    log::debug!(id = id, outcome:? = outcome; "print completed");
+    if let Some(jobs) = app.try_state::<PdfJobs>() {
+        let mut inner = jobs.0.lock().expect("pdf jobs mutex");
+        if let Some(tx) = inner.waiting.remove(&id) {
+            let _ = tx.send(outcome);
+            return;
+        }
+        if inner.starting > 0 {
+            inner.early.insert(id, outcome.clone());
+        }
+    }
+    let _ = app.emit(EVENT, Completed { id, outcome });
+}
+
+/// NSPrintOperation on macOS, the print dialog and GDI on Windows, through
+/// tao
+#[cfg(any(target_os = "macos", windows))]
+mod platform {
+    use super::{Options, Orientation, Source};
+    use std::path::PathBuf;
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::print::{
+        PaperSize, PrintMargins, PrintOptions, PrintOrientation, PrintSource,
+    };
+
+    fn native_source(source: Source) -> PrintSource {
+        match source {
+            Source::Window => PrintSource::Window,
+            Source::Pdf { path } => PrintSource::Pdf(PathBuf::from(path)),
+        }
+    }
+
+    fn native_options(options: Options, output: Option<PathBuf>) -> PrintOptions {
+        PrintOptions {
+            pages: options.pages.map(|pages| pages.first..=pages.last),
+            orientation: options.orientation.map(|orientation| match orientation {
+                Orientation::Portrait => PrintOrientation::Portrait,
+                Orientation::Landscape => PrintOrientation::Landscape,
+            }),
+            printer: options.printer,
+            silent: options.silent,
+            job_name: options.job_name,
+            paper_size: options.paper_size.map(|paper| PaperSize {
+                width: paper.width,
+                height: paper.height,
+            }),
+            margins: options.margins.map(|margins| PrintMargins {
+                top: margins.top,
+                right: margins.right,
+                bottom: margins.bottom,
+                left: margins.left,
+            }),
+            header_and_footer: options.header_and_footer,
+            output,
+        }
+    }
+
+    pub async fn print(
+        window: &WebviewWindow,
+        source: Source,
+        options: Options,
+        output: Option<PathBuf>,
+    ) -> Result<u64, String> {
+        let source = native_source(source);
+        let options = native_options(options, output);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        #[cfg(target_os = "macos")]
+        {
+            use tauri_runtime_wry::tao::platform::macos;
+
+            crate::windows::with_ns_window(window, move |ns_window| {
+                let _ = tx.send(unsafe { macos::print(ns_window, &source, &options) });
+            })?;
+        }
+        #[cfg(windows)]
+        {
+            use tauri_runtime_wry::tao::platform::windows;
+
+            let hwnd = window.hwnd().map_err(|e| e.to_string())?.0 as isize;
+            window
+                .run_on_main_thread(move || {
+                    let _ = tx.send(windows::print(hwnd, &source, &options));
+                })
+                .map_err(|e| e.to_string())?;
+        }
+        rx.await
+            .map_err(|_| "The print job wasn't started".to_string())?
+            .map_err(|e| format!("Couldn't print: {}", e))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::{Options, Source};
+    use std::path::PathBuf;
+    use tauri::WebviewWindow;
+
+    pub async fn print(
+        _window: &WebviewWindow,
+        _source: Source,
+        _options: Options,
+        _output: Option<PathBuf>,
+    ) -> Result<u64, String> {
+        Err("Printing is only available on macOS and Windows".to_string())
+    }
+}
@@ -0,0 +1,376 @@
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::ipc::Invoke;
+use tauri::Runtime;
+
+/// How many finished intervals `chrome_trace` has to draw from
+pub const CAPACITY: usize = 4096;
+
+/// What an interval times. Each is a signpost name in Instruments and an
+/// ETW task on Windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// A command's dispatch: its arguments read and its body run, or for an
+    /// async command, its work spawned
+    Command,
+    /// A job's work on the executor
+    Job,
+    /// A command or step timed for telemetry, from start to finish
+    Timed,
+}
+
+impl Category {
+    fn name(self) -> &'static str {
+        match self {
+            Category::Command => "command",
+            Category::Job => "job",
+            Category::Timed => "timed",
+        }
+    }
+}
+
+/// A finished interval
+struct Span {
+    category: Category,
+    name: String,
+    /// Since `epoch()`
+    start: Duration,
+    duration: Duration,
+    thread: u64,
+}
+
+static RECENT: Mutex<VecDeque<Span>> = Mutex::new(VecDeque::new());
+/// Names of the threads intervals ran on, by `thread_id()`
+static THREADS: Mutex<BTreeMap<u64, String>> = Mutex::new(BTreeMap::new());
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD: Cell<u64> = const { Cell::new(0) };
+}
+
+/// When the trace's clock starts: the first interval
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+/// A small number for this thread, named the first time it's asked for
+fn thread_id() -> u64 {
+    THREAD.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT_THREAD.fetch_add(1, Ordering::Relaxed));
+            let current = std::thread::current();
+            let name = current.name().unwrap_or("unnamed").to_string();
+            THREADS
+                .lock()
+                .expect("profiling threads mutex")
+                .insert(id.get(), name);
+        }
+        id.get()
+    })
+}
+
+/// Times what happens until it's dropped, as a signpost interval while
+/// Instruments or an ETW session is recording and in the recent intervals
+/// `chrome_trace` exports
+pub struct Interval {
+    category: Category,
+    name: String,
+    started: Instant,
+    signpost: platform::Signpost,
+}
+
+pub fn interval(category: Category, name: impl Into<String>) -> Interval {
+    let name = name.into();
+    let started = Instant::now();
+    let signpost = platform::begin(category, &name);
+    Interval {
+        category,
+        name,
+        started,
+        signpost,
+    }
+}
+
+impl Drop for Interval {
+    fn drop(&mut self) {
+        platform::end(self.category, &self.name, &self.signpost);
+        let span = Span {
+            category: self.category,
+            name: std::mem::take(&mut self.name),
+            start: self.started.saturating_duration_since(epoch()),
+            duration: self.started.elapsed(),
+            thread: thread_id(),
+        };
+        let mut recent = RECENT.lock().expect("profiling mutex");
+        if recent.len() == CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(span);
+    }
+}
+
+/// `handler` with each command's dispatch timed as an interval named after
+/// the command
+pub fn commands<R: Runtime>(
+    handler: impl Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let _interval = interval(Category::Command, invoke.message.command());
+        handler(invoke)
+    }
+}
+
+/// The recent intervals in the Chrome tracing format, which chrome://tracing,
+/// Perfetto and speedscope draw as a flame chart per thread
+pub fn chrome_trace() -> Value {
+    let pid = std::process::id();
+    let threads = THREADS.lock().expect("profiling threads mutex").clone();
+    let mut events: Vec<Value> = threads
+        .iter()
+        .map(|(tid, name)| {
+            json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": pid,
+                "tid": tid,
+                "args": { "name": name },
+            })
+        })
+        .collect();
+    let recent = RECENT.lock().expect("profiling mutex");
+    events.extend(recent.iter().map(|span| {
+        json!({
+            "name": span.name,
+            "cat": span.category.name(),
+            "ph": "X",
+            "ts": span.start.as_micros() as u64,
+            "dur": span.duration.as_micros() as u64,
+            "pid": pid,
+            "tid": span.thread,
+        })
+    }));
+    json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+}
+
+pub fn export_json() -> Result<String, String> {
+    serde_json::to_string(&chrome_trace()).map_err(|e| e.to_string())
+}
+
+/// Write the trace to `path`, for loading into a trace viewer
+pub fn export(path: &Path) -> Result<(), String> {
+    std::fs::write(path, export_json()?).map_err(|e| format!("Failed to write the trace: {}", e))
+}
+
+/// Forget the recent intervals, so the next export covers only what
+/// follows
+pub fn clear() {
+    RECENT.lock().expect("profiling mutex").clear();
+}
+
+/// `os_signpost` intervals in the Points of Interest log, which Instruments'
+/// Time Profiler shows by default. Their names are the categories; each
+/// carries what it times as its message.
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::Category;
+    use std::ffi::{c_char, c_void, CStr, CString};
+    use std::sync::OnceLock;
+
+    const SUBSYSTEM: &CStr = c"com.smudge";
+    const CATEGORY: &CStr = c"PointsOfInterest";
+    /// Both have to live in the binary, where Instruments reads them from
+    const FORMAT: &CStr = c"%{public}s";
+
+    /// `os_signpost_type_t`
+    const INTERVAL_BEGIN: u8 = 1;
+    const INTERVAL_END: u8 = 2;
+
+    /// `os_log`'s argument buffer: a summary saying it holds a pointer,
+    /// the argument count, then each argument's kind, size and value
+    const HAS_NON_SCALAR: u8 = 0x02;
+    const PUBLIC_STRING: u8 = 0x22;
+
+    extern "C" {
+        static __dso_handle: c_void;
+        fn os_log_create(subsystem: *const c_char, category: *const c_char) -> *mut c_void;
+        fn os_signpost_enabled(log: *mut c_void) -> bool;
+        fn os_signpost_id_generate(log: *mut c_void) -> u64;
+        fn _os_signpost_emit_with_name_impl(
+            dso: *const c_void,
+            log: *mut c_void,
+            kind: u8,
+            id: u64,
+            name: *const c_char,
+            format: *const c_char,
+            buffer: *const u8,
+            size: u32,
+        );
+    }
+
+    /// The interval's id, or 0 when nothing was recording as it began
+    pub struct Signpost(u64);
+
+    fn log() -> *mut c_void {
+        static LOG: OnceLock<usize> = OnceLock::new();
+        *LOG.get_or_init(
+            || unsafe { os_log_create(SUBSYSTEM.as_ptr(), CATEGORY.as_ptr()) } as usize,
+        ) as *mut c_void
+    }
+
+    fn name(category: Category) -> &'static CStr {
+        match category {
+            Category::Command => c"command",
+            Category::Job => c"job",
+            Category::Timed => c"timed",
+        }
+    }
+
+    fn emit(kind: u8, id: u64, category: Category, message: &str) {
+        let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+        let mut buffer = [0u8; 12];
+        buffer[0] = HAS_NON_SCALAR;
+        buffer[1] = 1;
+        buffer[2] = PUBLIC_STRING;
+        buffer[3] = 8;
+        buffer[4..].copy_from_slice(&(message.as_ptr() as u64).to_ne_bytes());
+        unsafe {
+            _os_signpost_emit_with_name_impl(
+                &__dso_handle,
+                log(),
+                kind,
+                id,
+                name(category).as_ptr(),
+                FORMAT.as_ptr(),
+                buffer.as_ptr(),
+                buffer.len() as u32,
+            )
+        };
+    }
+
+    pub fn begin(category: Category, message: &str) -> Signpost {
+        let log = log();
+        if !unsafe { os_signpost_enabled(log) } {
+            return Signpost(0);
+        }
+        let id = unsafe { os_signpost_id_generate(log) };
+        emit(INTERVAL_BEGIN, id, category, message);
+        Signpost(id)
+    }
+
+    pub fn end(category: Category, message: &str, signpost: &Signpost) {
+        if signpost.0 != 0 {
+            emit(INTERVAL_END, signpost.0, category, message);
+        }
+    }
+}
+
+/// ETW start and stop events from the `Smudge-Profiling` provider,
+/// {6b0a6a8e-3f4c-4f7e-9a51-2d8c6e1f0b47}, paired by activity id. Record
+/// them with `wpr` or `xperf -on` and the provider's GUID.
+#[cfg(windows)]
+mod platform {
+    use super::Category;
+    use std::sync::OnceLock;
+    use windows::core::GUID;
+    use windows::Win32::System::Diagnostics::Etw::{
+        EventActivityIdControl, EventEnabled, EventRegister, EventWriteTransfer,
+        EVENT_ACTIVITY_CTRL_CREATE_ID, EVENT_DATA_DESCRIPTOR, EVENT_DESCRIPTOR, REGHANDLE,
+    };
+
+    const PROVIDER: GUID = GUID::from_u128(0x6b0a6a8e_3f4c_4f7e_9a51_2d8c6e1f0b47);
+
+    /// `win:Start` and `win:Stop`
+    const OPCODE_START: u8 = 1;
+    const OPCODE_STOP: u8 = 2;
+    /// `TRACE_LEVEL_INFORMATION`
+    const LEVEL: u8 = 4;
+
+    /// The interval's activity, or `None` when nothing was recording as it
+    /// began
+    pub struct Signpost(Option<GUID>);
+
+    fn handle() -> Option<REGHANDLE> {
+        static HANDLE: OnceLock<Option<i64>> = OnceLock::new();
+        HANDLE
+            .get_or_init(|| {
+                let mut handle = REGHANDLE::default();
+                let status = unsafe { EventRegister(&PROVIDER, None, None, &mut handle) };
+                (status == 0).then_some(handle.0)
+            })
+            .map(REGHANDLE)
+    }
+
+    fn descriptor(category: Category, opcode: u8) -> EVENT_DESCRIPTOR {
+        let task = match category {
+            Category::Command => 1,
+            Category::Job => 2,
+            Category::Timed => 3,
+        };
+        EVENT_DESCRIPTOR {
+            Id: task * 2 + u16::from(opcode == OPCODE_STOP),
+            Version: 0,
+            Channel: 0,
+            Level: LEVEL,
+            Opcode: opcode,
+            Task: task,
+            Keyword: 0,
+        }
+    }
+
+    fn write(handle: REGHANDLE, category: Category, opcode: u8, activity: &GUID, message: &str) {
+        let message: Vec<u16> = message.encode_utf16().chain(Some(0)).collect();
+        let data = [EVENT_DATA_DESCRIPTOR {
+            Ptr: message.as_ptr() as u64,
+            Size: (message.len() * 2) as u32,
+            ..Default::default()
+        }];
+        unsafe {
+            EventWriteTransfer(
+                handle,
+                &descriptor(category, opcode),
+                Some(activity),
+                None,
+                Some(&data),
+            )
+        };
+    }
+
+    pub fn begin(category: Category, message: &str) -> Signpost {
+        let Some(handle) = handle() else {
+            return Signpost(None);
+        };
+        let descriptor = descriptor(category, OPCODE_START);
+        if !unsafe { EventEnabled(handle, &descriptor) } {
+            return Signpost(None);
+        }
+        let mut activity = GUID::zeroed();
+        unsafe { EventActivityIdControl(EVENT_ACTIVITY_CTRL_CREATE_ID, &mut activity) };
+        write(handle, category, OPCODE_START, &activity, message);
+        Signpost(Some(activity))
+    }
+
+    pub fn end(category: Category, message: &str, signpost: &Signpost) {
+        if let (Some(handle), Some(activity)) = (handle(), &signpost.0) {
+            write(handle, category, OPCODE_STOP, activity, message);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::Category;
+
+    pub struct Signpost;
+
+    pub fn begin(_category: Category, _message: &str) -> Signpost {
+        Signpost
+    }
+
+    pub fn end(_category: Category, _message: &str, _signpost: &Signpost) {}
+}
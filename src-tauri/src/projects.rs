@@ -0,0 +1,522 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db::Database;
+use crate::fs_scope::FsScope;
+use crate::{app_menu, dock_menu, main_window, recent_documents, windows, AppState, Settings};
+
+/// Sent to every window with the `ProjectsStatus` when a project opens,
+/// closes or becomes the active one
+pub const EVENT: &str = "projects-changed";
+
+/// Extension of a project packaged as one document, which Finder shows as a
+/// file and opens in smudge. Declared in tauri.conf.json's
+/// `fileAssociations`.
+pub const BUNDLE_EXTENSION: &str = "smudge";
+/// Where a project keeps what belongs to it rather than to the app, like
+/// the settings it overrides
+pub const DATA_DIR: &str = ".smudge";
+const ASSETS_DIR: &str = "assets";
+
+/// Database namespace and key of the recent projects, most recent first
+pub const NAMESPACE: &str = "projects";
+const RECENTS_KEY: &str = "recent";
+/// Recent projects kept, as many as the menus list
+const MAX_RECENTS: usize = 10;
+
+/// An open project: a notes folder and the windows showing it
+pub struct Project {
+    root: PathBuf,
+    /// Labels of the windows it claimed. While it's active, the windows no
+    /// project claimed are its too.
+    windows: Vec<String>,
+}
+
+impl Project {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            windows: Vec::new(),
+        }
+    }
+
+    pub fn data_dir(&self) -> PathBuf {
+        self.root.join(DATA_DIR)
+    }
+
+    /// The settings it overrides the defaults with, in its data folder
+    pub fn settings(&self) -> Settings {
+        crate::load_settings(&self.root.to_string_lossy())
+    }
+
+    fn info(&self, active: bool) -> ProjectInfo {
+        ProjectInfo {
+            path: self.root.to_string_lossy().into_owned(),
+            name: name(&self.root),
+            data_dir: self.data_dir().to_string_lossy().into_owned(),
+            bundle: is_bundle(&self.root),
+            active,
+            windows: self.windows.clone(),
+        }
+    }
+}
+
+/// A project opened before, as the menus list it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentProject {
+    pub path: String,
+    pub name: String,
+}
+
+/// An open project, as `list_projects` reports it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectInfo {
+    pub path: String,
+    pub name: String,
+    pub data_dir: String,
+    /// Whether it's a `.smudge` bundle rather than a plain folder
+    pub bundle: bool,
+    /// Whether its notes are the ones the backend serves
+    pub active: bool,
+    /// The windows it claimed; the active project's also include any
+    /// window no project claimed
+    pub windows: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectsStatus {
+    pub open: Vec<ProjectInfo>,
+    pub recent: Vec<RecentProject>,
+}
+
+/// Whether `path` is a project packaged as a `.smudge` bundle
+pub fn is_bundle(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(BUNDLE_EXTENSION))
+}
+
+/// A bundle's name without its extension; a folder's as it is
+fn name(root: &Path) -> String {
+    let name = if is_bundle(root) {
+        root.file_stem()
+    } else {
+        root.file_name()
+    };
+    name.map_or_else(
+        || root.to_string_lossy().into_owned(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// The notes folder the backend serves now
+fn active(app: &AppHandle) -> Option<PathBuf> {
+    let state = app.state::<AppState>();
+    let app_config = state.app_config.read().expect("app_config read lock");
+    app_config.notes_folder.as_ref().map(PathBuf::from)
+}
+
+/// The open projects and the recent ones, managed as Tauri state. The
+/// backend serves one project's notes at a time, the focused window's;
+/// the other projects' windows stay open and keep showing their own.
+pub struct Projects {
+    db: Option<Database>,
+    open: Mutex<Vec<Project>>,
+    recent: Mutex<Vec<RecentProject>>,
+}
+
+impl Projects {
+    /// The recent projects, and the notes folder from the last launch as
+    /// the one open project. Called once `AppState` is managed.
+    pub fn load(app: &AppHandle) -> Self {
+        let db = app.try_state::<Database>().map(|db| db.inner().clone());
+        let recent = db
+            .as_ref()
+            .and_then(|db| db.get(NAMESPACE, RECENTS_KEY).ok().flatten())
+            .unwrap_or_default();
+        Self {
+            db,
+            open: Mutex::new(active(app).map(Project::new).into_iter().collect()),
+            recent: Mutex::new(recent),
+        }
+    }
+
+    fn is_open(&self, root: &Path) -> bool {
+        let open = self.open.lock().expect("projects mutex");
+        open.iter().any(|project| project.root == root)
+    }
+
+    fn with<T>(&self, root: &Path, f: impl FnOnce(&mut Project) -> T) -> Option<T> {
+        let mut open = self.open.lock().expect("projects mutex");
+        open.iter_mut().find(|project| project.root == root).map(f)
+    }
+
+    /// The project whose window is labelled `label`, the active one for a
+    /// window no project claimed
+    fn of_window(&self, app: &AppHandle, label: &str) -> Option<PathBuf> {
+        let open = self.open.lock().expect("projects mutex");
+        open.iter()
+            .find(|project| project.windows.iter().any(|window| window == label))
+            .map(|project| project.root.clone())
+            .or_else(|| active(app))
+    }
+
+    /// The open windows showing `root`
+    fn windows(&self, app: &AppHandle, root: &Path) -> Vec<String> {
+        let open = self.open.lock().expect("projects mutex");
+        let mut labels: Vec<String> = open
+            .iter()
+            .find(|project| project.root == root)
+            .map(|project| project.windows.clone())
+            .unwrap_or_default();
+        if active(app).as_deref() == Some(root) {
+            labels.extend(
+                windows::list(app)
+                    .into_iter()
+                    .map(|window| window.label)
+                    .filter(|label| !open.iter().any(|project| project.windows.contains(label))),
+            );
+        }
+        labels.retain(|label| app.get_webview_window(label).is_some());
+        labels
+    }
+
+    /// Have `root` claim every window showing it, so they stay its once
+    /// another project is active
+    fn claim(&self, app: &AppHandle, root: &Path) {
+        let labels = self.windows(app, root);
+        self.with(root, |project| project.windows = labels);
+    }
+
+    /// Put `root` first in the recent projects
+    fn opened(&self, root: &Path) {
+        let path = root.to_string_lossy().into_owned();
+        {
+            let mut recent = self.recent.lock().expect("recent projects mutex");
+            recent.retain(|project| project.path != path);
+            recent.insert(
+                0,
+                RecentProject {
+                    name: name(root),
+                    path,
+                },
+            );
+            recent.truncate(MAX_RECENTS);
+        }
+        if let Err(e) = self.persist() {
+            log::warn!(error:% = e; "recent projects not saved");
+        }
+    }
+
+    fn persist(&self) -> Result<(), String> {
+        let Some(db) = &self.db else {
+            return Ok(());
+        };
+        let recent = self.recent.lock().expect("recent projects mutex");
+        db.put(NAMESPACE, RECENTS_KEY, &*recent)
+    }
+
+    /// Drop a closed window from its project. A project other than the
+    /// active one closes with its last window.
+    pub fn window_closed(&self, app: &AppHandle, label: &str) {
+        let active = active(app);
+        let closed = {
+            let mut open = self.open.lock().expect("projects mutex");
+            let before = open.len();
+            for project in open.iter_mut() {
+                project.windows.retain(|window| window != label);
+            }
+            open.retain(|project| {
+                !project.windows.is_empty() || active.as_deref() == Some(&project.root)
+            });
+            open.len() != before
+        };
+        if closed {
+            changed(app);
+        }
+    }
+}
+
+/// Every open project, and the recent ones that are still there
+pub fn status(app: &AppHandle) -> ProjectsStatus {
+    let projects = app.state::<Projects>();
+    let active = active(app);
+    let open: Vec<ProjectInfo> = {
+        let open = projects.open.lock().expect("projects mutex");
+        open.iter()
+            .map(|project| project.info(active.as_deref() == Some(&project.root)))
+            .collect()
+    };
+    let open = open
+        .into_iter()
+        .map(|mut info| {
+            if info.active {
+                info.windows = projects.windows(app, Path::new(&info.path));
+            }
+            info
+        })
+        .collect();
+    ProjectsStatus {
+        open,
+        recent: recent(app),
+    }
+}
+
+/// The recent projects that are still there, most recent first
+pub fn recent(app: &AppHandle) -> Vec<RecentProject> {
+    let projects = app.state::<Projects>();
+    let recent = projects.recent.lock().expect("recent projects mutex");
+    recent
+        .iter()
+        .filter(|project| Path::new(&project.path).is_dir())
+        .cloned()
+        .collect()
+}
+
+/// Tell every window, and list the recent projects in the File and Dock
+/// menus
+fn changed(app: &AppHandle) {
+    app_menu::rebuild(app);
+    if let Err(e) = dock_menu::refresh(app) {
+        log::warn!(error:% = e; "Dock menu not refreshed");
+    }
+    let _ = app.emit(EVENT, status(app));
+}
+
+/// Make a new, empty project at `path` and open it. A path ending in
+/// `.smudge` makes a bundle.
+pub fn create(app: &AppHandle, path: &Path) -> Result<ProjectInfo, String> {
+    let occupied = std::fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some());
+    if occupied {
+        return Err(format!("{} isn't empty", path.display()));
+    }
+    std::fs::create_dir_all(path.join(DATA_DIR)).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(path.join(ASSETS_DIR)).map_err(|e| e.to_string())?;
+    open(app, path)
+}
+
+/// Open the project at `path` in a window of its own and make it the
+/// active one, or switch to it if it's open
+pub fn open(app: &AppHandle, path: &Path) -> Result<ProjectInfo, String> {
+    let root = std::fs::canonicalize(path)
+        .map_err(|e| format!("Couldn't open {}: {}", path.display(), e))?;
+    if !root.is_dir() {
+        return Err(format!("{} isn't a project", root.display()));
+    }
+    let projects = app.state::<Projects>();
+    if !projects.is_open(&root) {
+        projects
+            .open
+            .lock()
+            .expect("projects mutex")
+            .push(Project::new(root.clone()));
+    }
+    switch(app, &root)
+}
+
+/// Serve the open project `root`'s notes. The previous project claims its
+/// windows, which stay open showing its notes.
+fn activate(app: &AppHandle, root: &Path) -> Result<(), String> {
+    let projects = app.state::<Projects>();
+    let previous = active(app);
+    if previous.as_deref() == Some(root) {
+        return Ok(());
+    }
+    if let Some(previous) = previous.filter(|previous| projects.is_open(previous)) {
+        projects.claim(app, &previous);
+    }
+    crate::activate_notes_folder(app, &root.to_string_lossy())
+}
+
+/// Open a project the user chose outside the frontend, from a menu or the
+/// Finder, and let the frontend at it as if it were picked in a dialog
+pub fn open_chosen(app: &AppHandle, path: &Path) -> Result<ProjectInfo, String> {
+    if let Some(scope) = app.try_state::<FsScope>() {
+        scope.opened(app, &[path.to_path_buf()]);
+    }
+    open(app, path)
+}
+
+/// Make the open project `root` the active one: its notes are served and
+/// its windows come to the front. A project without windows gets a new one.
+pub fn switch(app: &AppHandle, root: &Path) -> Result<ProjectInfo, String> {
+    let projects = app.state::<Projects>();
+    if !projects.is_open(root) {
+        return Err(format!("{} isn't open", root.display()));
+    }
+    activate(app, root)?;
+
+    let labels = projects.windows(app, root);
+    if labels.is_empty() {
+        let window = windows::open(app, None)?;
+        projects.with(root, |project| {
+            project.windows.push(window.label().to_string())
+        });
+    }
+    for (i, label) in labels.iter().enumerate() {
+        let Some(window) = app.get_webview_window(label) else {
+            continue;
+        };
+        let _ = window.show();
+        if i == 0 {
+            let _ = window.unminimize();
+            let _ = window.set_focus();
+        }
+    }
+
+    projects.opened(root);
+    // Bundles are documents, listed with the OS's recent ones too
+    if is_bundle(root) {
+        let _ = recent_documents::add(app, root.to_path_buf());
+    }
+    changed(app);
+    let info = projects
+        .with(root, |project| project.info(true))
+        .ok_or_else(|| format!("{} isn't open", root.display()))?;
+    Ok(info)
+}
+
+/// Close the project `root` and its windows. The next open project becomes
+/// the active one; with none, the main window is left to choose one.
+pub fn close(app: &AppHandle, root: &Path) -> Result<(), String> {
+    let projects = app.state::<Projects>();
+    let labels = projects.windows(app, root);
+    {
+        let mut open = projects.open.lock().expect("projects mutex");
+        let before = open.len();
+        open.retain(|project| project.root != root);
+        if open.len() == before {
+            return Err(format!("{} isn't open", root.display()));
+        }
+    }
+    for label in &labels {
+        let Some(window) = app.get_webview_window(label) else {
+            continue;
+        };
+        // Whichever project is next has the main window, unclaimed
+        if label == "main" {
+            let _ = window.hide();
+        } else {
+            let _ = window.close();
+        }
+    }
+
+    if active(app).as_deref() != Some(root) {
+        changed(app);
+        return Ok(());
+    }
+    let next = {
+        let open = projects.open.lock().expect("projects mutex");
+        open.last().map(|project| project.root.clone())
+    };
+    match next {
+        Some(next) => {
+            switch(app, &next)?;
+        }
+        None => {
+            crate::deactivate_notes_folder(app)?;
+            let main = app
+                .get_webview_window("main")
+                .or_else(|| main_window::recreate(app));
+            if let Some(main) = main {
+                let _ = main.show();
+                let _ = main.set_focus();
+            }
+            changed(app);
+        }
+    }
+    Ok(())
+}
+
+/// A window was focused: its project becomes the active one, so what the
+/// user does there goes to its notes
+pub fn window_focused(app: &AppHandle, label: &str) {
+    let Some(projects) = app.try_state::<Projects>() else {
+        return;
+    };
+    let Some(root) = projects.of_window(app, label) else {
+        return;
+    };
+    if active(app).as_deref() == Some(root.as_path()) {
+        return;
+    }
+    if let Err(e) = activate(app, &root) {
+        log::warn!(path:? = root, error:% = e; "project not activated");
+        return;
+    }
+    changed(app);
+}
+
+/// Close the project `window` shows
+pub fn close_window_project(app: &AppHandle, window: &str) -> Result<(), String> {
+    let root = app
+        .state::<Projects>()
+        .of_window(app, window)
+        .ok_or("No project is open")?;
+    close(app, &root)
+}
+
+/// The notes folder was changed in place, from settings or a shortcut: the
+/// windows that showed `previous` show `root` now
+pub fn folder_chosen(app: &AppHandle, previous: Option<&Path>, root: &Path) {
+    let projects = app.state::<Projects>();
+    {
+        let mut open = projects.open.lock().expect("projects mutex");
+        let windows = previous
+            .and_then(|previous| open.iter().position(|project| project.root == previous))
+            .map(|i| open.remove(i).windows)
+            .unwrap_or_default();
+        match open.iter_mut().find(|project| project.root == root) {
+            Some(project) => project.windows.extend(windows),
+            None => open.push(Project {
+                root: root.to_path_buf(),
+                windows,
+            }),
+        }
+    }
+    projects.opened(root);
+    changed(app);
+}
+
+/// The settings of the open project `root`, whether or not it's active
+pub fn settings(app: &AppHandle, root: &Path) -> Result<Settings, String> {
+    app.state::<Projects>()
+        .with(root, |project| project.settings())
+        .ok_or_else(|| format!("{} isn't open", root.display()))
+}
+
+/// Forget the recent projects
+pub fn clear_recent(app: &AppHandle) {
+    let projects = app.state::<Projects>();
+    projects
+        .recent
+        .lock()
+        .expect("recent projects mutex")
+        .clear();
+    if let Err(e) = projects.persist() {
+        log::warn!(error:% = e; "recent projects not saved");
+    }
+    changed(app);
+}
+
+/// Open the `.smudge` bundles among files the OS asked smudge to open,
+/// each in its own window, and return the rest
+pub fn open_bundles(app: &AppHandle, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    if app.try_state::<Projects>().is_none() {
+        return paths;
+    }
+    let (bundles, rest): (Vec<PathBuf>, Vec<PathBuf>) = paths
+        .into_iter()
+        .partition(|path| path.is_dir() && is_bundle(path));
+    crate::quarantine::opened(app, &bundles);
+    for bundle in bundles {
+        if let Err(e) = open_chosen(app, &bundle) {
+            log::warn!(path:? = bundle, error:% = e; "project not opened");
+        }
+    }
+    rest
+}
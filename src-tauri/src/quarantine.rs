@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// What imports and opened files do with the "downloaded from the internet"
+/// marks: com.apple.quarantine on macOS, the Zone.Identifier stream (mark of
+/// the web) on Windows, user.xdg.origin.url on Linux
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum QuarantinePolicy {
+    /// Copy the source's marks onto the imported note, so other apps treat
+    /// it like the download it came from
+    #[default]
+    Preserve,
+    /// Imported notes never carry marks, and files opened in smudge lose
+    /// theirs
+    Clear,
+}
+
+/// A file's origin marks
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantineInfo {
+    /// The OS will warn or restrict when opening the file. Always false on
+    /// Linux, which only records where files came from.
+    pub quarantined: bool,
+    /// App that downloaded the file, where recorded (macOS)
+    pub agent: Option<String>,
+    /// Page or URL the file was downloaded from
+    pub origin_url: Option<String>,
+    /// Windows security zone, 3 being the internet
+    pub zone: Option<u32>,
+}
+
+pub fn inspect(path: &Path) -> Result<QuarantineInfo, String> {
+    if !path.exists() {
+        return Err("Path does not exist".to_string());
+    }
+    platform::inspect(path)
+}
+
+/// Remove all origin marks from `path`
+pub fn strip(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Err("Path does not exist".to_string());
+    }
+    platform::strip(path)
+}
+
+/// Apply the user's policy to files opened from outside smudge. Marks stay
+/// unless the policy is Clear and this build may remove them.
+pub fn opened(app: &AppHandle, paths: &[PathBuf]) {
+    let policy = app
+        .try_state::<crate::AppState>()
+        .map(|state| {
+            state
+                .app_config
+                .read()
+                .expect("app_config read lock")
+                .quarantine_policy
+        })
+        .unwrap_or_default();
+    if policy == QuarantinePolicy::Preserve || crate::sandbox::require("stripQuarantine").is_err() {
+        return;
+    }
+    for path in paths {
+        if let Err(e) = strip(path) {
+            log::warn!(path:? = path, error:% = e; "download marks not removed");
+        }
+    }
+}
+
+/// Apply `policy` to `target`, a copy of `source` smudge just wrote
+pub fn carry_over(policy: QuarantinePolicy, source: &Path, target: &Path) -> Result<(), String> {
+    match policy {
+        QuarantinePolicy::Preserve => {
+            // Re-imports overwrite the note in place, which keeps old marks
+            platform::strip(target)?;
+            platform::copy_marks(source, target)
+        }
+        QuarantinePolicy::Clear => platform::strip(target),
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::QuarantineInfo;
+    use std::path::Path;
+
+    /// "flags;hex timestamp;agent;event UUID"
+    const QUARANTINE: &str = "com.apple.quarantine";
+    /// Binary plist array of URLs, download first
+    const WHERE_FROMS: &str = "com.apple.metadata:kMDItemWhereFroms";
+
+    fn get(path: &Path, name: &str) -> Result<Option<Vec<u8>>, String> {
+        xattr::get(path, name).map_err(|e| e.to_string())
+    }
+
+    pub fn inspect(path: &Path) -> Result<QuarantineInfo, String> {
+        let mut info = QuarantineInfo::default();
+        if let Some(data) = get(path, QUARANTINE)? {
+            info.quarantined = true;
+            let value = String::from_utf8_lossy(&data);
+            info.agent = value
+                .split(';')
+                .nth(2)
+                .filter(|agent| !agent.is_empty())
+                .map(str::to_string);
+        }
+        if let Some(data) = get(path, WHERE_FROMS)? {
+            let urls: Vec<String> = plist::from_bytes(&data).unwrap_or_default();
+            info.origin_url = urls.into_iter().find(|url| !url.is_empty());
+        }
+        Ok(info)
+    }
+
+    pub fn strip(path: &Path) -> Result<(), String> {
+        for name in [QUARANTINE, WHERE_FROMS] {
+            if get(path, name)?.is_some() {
+                xattr::remove(path, name).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn copy_marks(source: &Path, target: &Path) -> Result<(), String> {
+        for name in [QUARANTINE, WHERE_FROMS] {
+            if let Some(data) = get(source, name)? {
+                xattr::set(target, name, &data).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::QuarantineInfo;
+    use std::path::{Path, PathBuf};
+
+    /// Mark of the web: an INI-style alternate data stream with ZoneId,
+    /// ReferrerUrl and HostUrl under [ZoneTransfer]
+    fn zone_identifier(path: &Path) -> PathBuf {
+        let mut stream = path.as_os_str().to_os_string();
+        stream.push(":Zone.Identifier");
+        PathBuf::from(stream)
+    }
+
+    fn read(path: &Path) -> Result<Option<String>, String> {
+        match std::fs::read_to_string(zone_identifier(path)) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn inspect(path: &Path) -> Result<QuarantineInfo, String> {
+        let Some(content) = read(path)? else {
+            return Ok(QuarantineInfo::default());
+        };
+        let value = |key: &str| {
+            content
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(key)?.strip_prefix('='))
+                .map(|v| v.trim().to_string())
+        };
+        let zone = value("ZoneId").and_then(|z| z.parse().ok());
+        Ok(QuarantineInfo {
+            // Explorer and SmartScreen act on the Internet and Restricted zones
+            quarantined: zone.is_some_and(|z| z >= 3),
+            agent: None,
+            origin_url: value("HostUrl").or_else(|| value("ReferrerUrl")),
+            zone,
+        })
+    }
+
+    pub fn strip(path: &Path) -> Result<(), String> {
+        match std::fs::remove_file(zone_identifier(path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn copy_marks(source: &Path, target: &Path) -> Result<(), String> {
+        match read(source)? {
+            Some(content) => {
+                std::fs::write(zone_identifier(target), content).map_err(|e| e.to_string())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::QuarantineInfo;
+    use std::path::Path;
+
+    /// Set by browsers following the freedesktop xattr conventions
+    const ORIGIN_URL: &str = "user.xdg.origin.url";
+    const REFERRER_URL: &str = "user.xdg.referrer.url";
+
+    fn get(path: &Path, name: &str) -> Result<Option<Vec<u8>>, String> {
+        xattr::get(path, name).map_err(|e| e.to_string())
+    }
+
+    pub fn inspect(path: &Path) -> Result<QuarantineInfo, String> {
+        let url = |data: Vec<u8>| String::from_utf8_lossy(&data).into_owned();
+        Ok(QuarantineInfo {
+            origin_url: get(path, ORIGIN_URL)?.or(get(path, REFERRER_URL)?).map(url),
+            ..QuarantineInfo::default()
+        })
+    }
+
+    pub fn strip(path: &Path) -> Result<(), String> {
+        for name in [ORIGIN_URL, REFERRER_URL] {
+            if get(path, name)?.is_some() {
+                xattr::remove(path, name).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn copy_marks(source: &Path, target: &Path) -> Result<(), String> {
+        for name in [ORIGIN_URL, REFERRER_URL] {
+            if let Some(data) = get(source, name)? {
+                xattr::set(target, name, &data).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+}
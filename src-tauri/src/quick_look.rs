@@ -0,0 +1,78 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+/// Sent to every window with the `Change` when the preview opens or closes
+pub const EVENT: &str = "quick-look";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Change {
+    Opened,
+    Closed,
+}
+
+/// Preview `paths` the way the Finder does on space, starting with the
+/// first. Replaces what an open preview shows.
+pub async fn preview(app: &AppHandle, paths: Vec<String>) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("Nothing to preview".to_string());
+    }
+    platform::preview(app, paths).await
+}
+
+/// Close the preview, if it's open
+pub fn close(app: &AppHandle) -> Result<(), String> {
+    platform::close(app)
+}
+
+/// Tell the windows the preview opened or closed
+pub fn changed(app: &AppHandle, change: Change) {
+    use tauri::Emitter;
+
+    log::debug!(change:? = change; "quick look changed");
+    let _ = app.emit(EVENT, change);
+}
+
+/// The Quick Look panel on macOS, and the shell's preview handlers on
+/// Windows, through tao
+#[cfg(any(target_os = "macos", windows))]
+mod platform {
+    use std::path::PathBuf;
+    use tauri::AppHandle;
+    #[cfg(target_os = "macos")]
+    use tauri_runtime_wry::tao::platform::macos as native;
+    #[cfg(windows)]
+    use tauri_runtime_wry::tao::platform::windows as native;
+
+    pub async fn preview(app: &AppHandle, paths: Vec<String>) -> Result<(), String> {
+        let paths: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.run_on_main_thread(move || {
+            let _ = tx.send(native::preview_files(&paths));
+        })
+        .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The preview wasn't shown".to_string())?
+            .map_err(|e| format!("Couldn't preview: {}", e))
+    }
+
+    pub fn close(app: &AppHandle) -> Result<(), String> {
+        app.run_on_main_thread(native::close_preview)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use tauri::AppHandle;
+
+    const UNSUPPORTED: &str = "Previews are only available on macOS and Windows";
+
+    pub async fn preview(_app: &AppHandle, _paths: Vec<String>) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn close(_app: &AppHandle) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
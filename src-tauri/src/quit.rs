@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sent to the main window when the app is asked to quit. It saves what it
+/// has pending and answers with `reply_to_quit`.
+pub const REQUESTED_EVENT: &str = "quit-requested";
+
+/// How long the frontend gets to answer before the user is asked instead.
+/// Logout and shutdown give up on apps that take much longer.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The quit waiting on the frontend, if any
+#[derive(Default)]
+pub struct QuitRequest {
+    /// Id of the pending request, 0 when there is none
+    pending: AtomicU64,
+    last: AtomicU64,
+}
+
+/// The app was asked to quit (Quit menu item, Cmd+Q, logout or shutdown).
+/// Runs on the main thread while AppKit waits for an answer. Without a
+/// frontend that could have unsaved work, the app just quits.
+pub fn requested(app: &AppHandle, frontend_ready: bool) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if !frontend_ready {
+        return;
+    }
+    let request = app.state::<QuitRequest>();
+    let id = request.last.fetch_add(1, Ordering::SeqCst) + 1;
+    if request
+        .pending
+        .compare_exchange(0, id, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        return;
+    }
+    #[cfg(target_os = "macos")]
+    tauri_runtime_wry::tao::platform::macos::defer_termination();
+    if window.emit(REQUESTED_EVENT, ()).is_err() {
+        finish(app, true);
+        return;
+    }
+
+    // Only ends this request: one answered or cancelled in the meantime
+    // leaves a later one to its own timer
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(REPLY_TIMEOUT).await;
+        let ended = app.state::<QuitRequest>().pending.compare_exchange(
+            id,
+            0,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+        if ended.is_ok() {
+            answer(&app, false);
+        }
+    });
+}
+
+/// Answer a pending quit
+pub fn finish(app: &AppHandle, saved: bool) {
+    let Some(request) = app.try_state::<QuitRequest>() else {
+        return;
+    };
+    if request.pending.swap(0, Ordering::SeqCst) != 0 {
+        answer(app, saved);
+    }
+}
+
+/// If the frontend couldn't save everything, or never answered, the user
+/// decides
+fn answer(app: &AppHandle, saved: bool) {
+    if saved {
+        reply(app, true);
+        return;
+    }
+
+    let mut dialog = rfd::AsyncMessageDialog::new()
+        .set_level(rfd::MessageLevel::Warning)
+        .set_title("Quit Smudge?")
+        .set_description("Some changes may not have been saved. Quit anyway?")
+        .set_buttons(rfd::MessageButtons::OkCancelCustom(
+            "Quit".to_string(),
+            "Cancel".to_string(),
+        ));
+    if let Some(window) = app.get_webview_window("main") {
+        dialog = dialog.set_parent(&window);
+    }
+    let app = app.clone();
+    // Like dialogs::show: create the alert on the main thread, wait for it
+    // elsewhere
+    let _ = app.clone().run_on_main_thread(move || {
+        let answer = dialog.show();
+        std::thread::spawn(move || {
+            let answer = tauri::async_runtime::block_on(answer);
+            let quit = matches!(answer, rfd::MessageDialogResult::Custom(ref label) if label == "Quit")
+                || answer == rfd::MessageDialogResult::Ok;
+            reply(&app, quit);
+        });
+    });
+}
+
+fn reply(app: &AppHandle, quit: bool) {
+    #[cfg(target_os = "macos")]
+    let _ = app.run_on_main_thread(move || {
+        tauri_runtime_wry::tao::platform::macos::reply_to_termination(quit)
+    });
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app, quit);
+}
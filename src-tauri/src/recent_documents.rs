@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// List a file the user opened in the OS's recent documents: "Open Recent"
+/// and the Dock menu on macOS, the jump list on Windows. Elsewhere this does
+/// nothing.
+pub fn add(app: &AppHandle, path: PathBuf) -> Result<(), String> {
+    #[cfg(any(target_os = "macos", windows))]
+    app.run_on_main_thread(move || {
+        #[cfg(target_os = "macos")]
+        tauri_runtime_wry::tao::platform::macos::add_recent_document(&path);
+        #[cfg(windows)]
+        tauri_runtime_wry::tao::platform::windows::add_recent_document(&path);
+    })
+    .map_err(|e| e.to_string())?;
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let _ = (app, path);
+    Ok(())
+}
+
+/// Empty smudge's recent documents. Elsewhere than macOS and Windows this
+/// does nothing.
+pub fn clear(app: &AppHandle) -> Result<(), String> {
+    #[cfg(any(target_os = "macos", windows))]
+    app.run_on_main_thread(|| {
+        #[cfg(target_os = "macos")]
+        tauri_runtime_wry::tao::platform::macos::clear_recent_documents();
+        #[cfg(windows)]
+        tauri_runtime_wry::tao::platform::windows::clear_recent_documents();
+    })
+    .map_err(|e| e.to_string())?;
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let _ = app;
+    Ok(())
+}
@@ -0,0 +1,543 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, EventId, Listener, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+use crate::{batch, preferences, single_instance, windows, AppState};
+
+/// The launch flag. `--remote-control` or `--remote-control=stdio` serves
+/// on the process's own stdin and stdout; `--remote-control=socket` on a
+/// per-user socket (a named pipe on Windows) that clients connect to.
+pub const FLAG: &str = "--remote-control";
+
+/// Every method, whether or not the preferences allow it. `rpc.methods`
+/// is always allowed, so clients can find out what they may call.
+pub const METHODS: &[&str] = &[
+    "rpc.methods",
+    "app.state",
+    "notes.list",
+    "notes.read",
+    "notes.search",
+    "notes.save",
+    "file.open",
+    "export.run",
+    "windows.list",
+    "events.subscribe",
+    "events.unsubscribe",
+];
+
+/// What's allowed unless the preferences say otherwise: everything that
+/// only reads
+pub const DEFAULT_METHODS: &[&str] = &[
+    "app.state",
+    "notes.list",
+    "notes.read",
+    "notes.search",
+    "windows.list",
+    "events.subscribe",
+    "events.unsubscribe",
+];
+
+pub const DEFAULT_EVENTS: &[&str] = &[
+    "file-opened",
+    windows::EVENT,
+    preferences::EVENT,
+    crate::scheduler::EVENT,
+];
+
+/// JSON-RPC's error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The method ran and failed; the message says why
+const FAILED: i64 = -32000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Stdio,
+    Socket,
+}
+
+/// The transport `args` ask for, if they ask for remote control
+pub fn requested<S: AsRef<str>>(args: &[S]) -> Option<Transport> {
+    args.iter()
+        .skip(1)
+        .find_map(|arg| match arg.as_ref().strip_prefix(FLAG)? {
+            "" | "=stdio" => Some(Transport::Stdio),
+            "=socket" => Some(Transport::Socket),
+            _ => None,
+        })
+}
+
+/// Drives the app for external tools and tests: JSON-RPC 2.0, one message
+/// per line, over stdio or a socket. It's off unless smudge is launched with
+/// `FLAG`, and clients can call only the methods the preferences allow (see
+/// `preferences::RemoteControl`) and hear only the events they list, as
+/// `event` notifications.
+#[derive(Default)]
+pub struct RemoteControl {
+    socket: AtomicBool,
+}
+
+/// Start serving over `transport`. The socket is served once however often
+/// it's asked for, since later launches with the flag are forwarded here.
+pub fn start(app: &AppHandle, transport: Transport) {
+    let app = app.clone();
+    match transport {
+        Transport::Stdio => {
+            tauri::async_runtime::spawn(async move {
+                session(app, tokio::io::stdin(), tokio::io::stdout()).await;
+                log::info!("remote control input closed");
+            });
+        }
+        Transport::Socket => {
+            let Some(state) = app.try_state::<RemoteControl>() else {
+                return;
+            };
+            if state.socket.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            let identifier = app.config().identifier.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = platform::serve(&app, &identifier).await {
+                    log::warn!(error:% = e; "remote control socket not served");
+                    app.state::<RemoteControl>()
+                        .socket
+                        .store(false, Ordering::SeqCst);
+                }
+            });
+        }
+    }
+    log::info!(transport:? = transport; "remote control started");
+}
+
+/// For a copy of smudge launched with `--remote-control` while another
+/// runs: its arguments were forwarded, so the running copy serves its
+/// socket, and this one relays stdio to it. Returns the exit code.
+pub fn relay(identifier: &str) -> i32 {
+    match platform::relay(identifier) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("smudge: couldn't reach the running copy: {}", e);
+            1
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        Self::new(FAILED, message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Missing for notifications, which get no reply
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn reply(id: Value, outcome: Result<Value, RpcError>) -> Value {
+    match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    }
+}
+
+/// One client. Its subscriptions end with it.
+struct Session {
+    app: AppHandle,
+    out: mpsc::UnboundedSender<String>,
+    subscriptions: HashMap<String, EventId>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        for (_, id) in self.subscriptions.drain() {
+            self.app.unlisten(id);
+        }
+    }
+}
+
+/// Answer requests from `reader` on `writer` until the client hangs up.
+/// Requests are handled in order; events are written between replies.
+async fn session<R, W>(app: AppHandle, reader: R, mut writer: W)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (out, mut outgoing) = mpsc::unbounded_channel::<String>();
+    let writing = tauri::async_runtime::spawn(async move {
+        while let Some(mut line) = outgoing.recv().await {
+            line.push('\n');
+            if writer.write_all(line.as_bytes()).await.is_err() || writer.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut session = Session {
+        app,
+        out,
+        subscriptions: HashMap::new(),
+    };
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(reply) = session.handle(&line).await {
+            let _ = session.out.send(reply.to_string());
+        }
+    }
+    drop(session);
+    let _ = writing.await;
+}
+
+impl Session {
+    async fn handle(&mut self, line: &str) -> Option<Value> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                return Some(reply(
+                    Value::Null,
+                    Err(RpcError::new(PARSE_ERROR, e.to_string())),
+                ))
+            }
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let request = match serde_json::from_value::<Request>(request) {
+            Ok(request) if request.jsonrpc == "2.0" => request,
+            _ => {
+                return Some(reply(
+                    id,
+                    Err(RpcError::new(INVALID_REQUEST, "Not a JSON-RPC 2.0 request")),
+                ))
+            }
+        };
+        let outcome = self.call(&request.method, request.params).await;
+        if let Err(e) = &outcome {
+            log::debug!(method = request.method.as_str(), error = e.message.as_str(); "remote control call failed");
+        }
+        request.id.map(|id| reply(id, outcome))
+    }
+
+    async fn call(&mut self, method: &str, args: Value) -> Result<Value, RpcError> {
+        let allowed = preferences::current(&self.app)
+            .remote_control
+            .methods
+            .clone();
+        if method != "rpc.methods" && !allowed.iter().any(|allowed| allowed == method) {
+            return Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("No method {}, or it isn't allowed", method),
+            ));
+        }
+        let app = self.app.clone();
+        let result = match method {
+            "rpc.methods" => json!(["rpc.methods"]
+                .into_iter()
+                .chain(allowed.iter().map(String::as_str))
+                .collect::<Vec<_>>()),
+            "app.state" => {
+                let state = app.state::<AppState>();
+                let notes_folder = state
+                    .app_config
+                    .read()
+                    .expect("app_config read lock")
+                    .notes_folder
+                    .clone();
+                json!({
+                    "version": app.package_info().version.to_string(),
+                    "notesFolder": notes_folder,
+                    "windows": windows::list(&app),
+                })
+            }
+            "notes.list" => json!(crate::list_notes(app.state()).await?),
+            "notes.read" => {
+                let NoteId { id } = params(args)?;
+                json!(crate::load_note(&app, id, &app.state::<AppState>()).await?)
+            }
+            "notes.search" => {
+                let Query { query } = params(args)?;
+                json!(crate::search_notes(app.clone(), query, app.state(), app.state()).await?)
+            }
+            "notes.save" => {
+                let Save { id, content } = params(args)?;
+                let note =
+                    crate::write_note(id.clone(), content, &app.state::<AppState>()).await?;
+                crate::note_saved(&app, id.as_deref(), &note);
+                json!(note)
+            }
+            "file.open" => {
+                let Paths { paths } = params(args)?;
+                let (files, missing): (Vec<PathBuf>, Vec<PathBuf>) = paths
+                    .into_iter()
+                    .partition(|path| path.is_file() && crate::is_markdown_file(path));
+                if let Some(path) = missing.first() {
+                    return Err(format!("{} isn't a markdown file", path.display()).into());
+                }
+                single_instance::open_files(&app, files);
+                Value::Null
+            }
+            "export.run" => {
+                let Export { paths, format, out } = params(args)?;
+                std::fs::create_dir_all(&out)
+                    .map_err(|e| format!("Failed to create export folder: {}", e))?;
+                let mut written = Vec::new();
+                for path in &paths {
+                    written.push(match format {
+                        ExportFormat::Html => {
+                            batch::export_html(path, &out, crate::extract_title).await?
+                        }
+                        ExportFormat::Markdown => batch::export_file(path, &out).await?,
+                    });
+                }
+                json!(written)
+            }
+            "windows.list" => json!(windows::list(&app)),
+            "events.subscribe" => {
+                let Events { events } = params(args)?;
+                let allowed = &preferences::current(&app).remote_control.events;
+                if let Some(event) = events.iter().find(|event| !allowed.contains(event)) {
+                    return Err(RpcError::new(
+                        INVALID_PARAMS,
+                        format!("Event {} isn't allowed", event),
+                    ));
+                }
+                for event in events {
+                    if self.subscriptions.contains_key(&event) {
+                        continue;
+                    }
+                    let out = self.out.clone();
+                    let name = event.clone();
+                    let id = app.listen_any(event.clone(), move |received| {
+                        let payload = serde_json::from_str::<Value>(received.payload())
+                            .unwrap_or(Value::Null);
+                        let notification = json!({
+                            "jsonrpc": "2.0",
+                            "method": "event",
+                            "params": { "event": name, "payload": payload },
+                        });
+                        let _ = out.send(notification.to_string());
+                    });
+                    self.subscriptions.insert(event, id);
+                }
+                json!(self.subscriptions.keys().collect::<Vec<_>>())
+            }
+            "events.unsubscribe" => {
+                let Events { events } = params(args)?;
+                for event in events {
+                    if let Some(id) = self.subscriptions.remove(&event) {
+                        app.unlisten(id);
+                    }
+                }
+                json!(self.subscriptions.keys().collect::<Vec<_>>())
+            }
+            _ => {
+                return Err(RpcError::new(
+                    METHOD_NOT_FOUND,
+                    format!("No method {}", method),
+                ))
+            }
+        };
+        Ok(result)
+    }
+}
+
+#[derive(Deserialize)]
+struct NoteId {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct Query {
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct Save {
+    /// A new note without one
+    id: Option<String>,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct Paths {
+    paths: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum ExportFormat {
+    Html,
+    Markdown,
+}
+
+#[derive(Deserialize)]
+struct Export {
+    paths: Vec<PathBuf>,
+    format: ExportFormat,
+    out: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Events {
+    events: Vec<String>,
+}
+
+/// Long enough for the running copy to start its socket after the launch
+/// is forwarded to it
+const CONNECT_ATTEMPTS: u32 = 50;
+const CONNECT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+#[cfg(unix)]
+mod platform {
+    use std::io::{self, Write};
+    use std::net::Shutdown;
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixStream;
+    use std::path::PathBuf;
+    use tauri::AppHandle;
+    use tokio::net::UnixListener;
+
+    use super::{session, CONNECT_ATTEMPTS, CONNECT_INTERVAL};
+
+    /// Next to the single-instance socket
+    fn socket_path(identifier: &str) -> PathBuf {
+        std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join(format!("{}.remote-control.sock", identifier))
+    }
+
+    pub async fn serve(app: &AppHandle, identifier: &str) -> Result<(), String> {
+        let path = socket_path(identifier);
+        // Only the single instance gets here, so one left there is stale
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).map_err(|e| e.to_string())?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+        loop {
+            let (stream, _) = listener.accept().await.map_err(|e| e.to_string())?;
+            let (reader, writer) = stream.into_split();
+            tauri::async_runtime::spawn(session(app.clone(), reader, writer));
+        }
+    }
+
+    pub fn relay(identifier: &str) -> Result<(), String> {
+        let path = socket_path(identifier);
+        let mut stream = None;
+        for _ in 0..CONNECT_ATTEMPTS {
+            if let Ok(connected) = UnixStream::connect(&path) {
+                stream = Some(connected);
+                break;
+            }
+            std::thread::sleep(CONNECT_INTERVAL);
+        }
+        let mut stream = stream.ok_or("it didn't open its socket")?;
+        let mut sending = stream.try_clone().map_err(|e| e.to_string())?;
+        std::thread::spawn(move || {
+            let _ = io::copy(&mut io::stdin().lock(), &mut sending);
+            // Replies to what was sent still arrive after this
+            let _ = sending.shutdown(Shutdown::Write);
+        });
+        io::copy(&mut stream, &mut io::stdout().lock()).map_err(|e| e.to_string())?;
+        io::stdout().flush().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::fs::OpenOptions;
+    use std::io::{self, Write};
+    use tauri::AppHandle;
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    use super::{session, CONNECT_ATTEMPTS, CONNECT_INTERVAL};
+
+    /// Pipe names are machine-wide, so they name the user too
+    fn pipe_name(identifier: &str) -> String {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        format!(r"\\.\pipe\{}.remote-control.{}", identifier, user)
+    }
+
+    pub async fn serve(app: &AppHandle, identifier: &str) -> Result<(), String> {
+        let name = pipe_name(identifier);
+        let options = || {
+            let mut options = ServerOptions::new();
+            options.reject_remote_clients(true);
+            options
+        };
+        let mut server = options()
+            .first_pipe_instance(true)
+            .create(&name)
+            .map_err(|e| e.to_string())?;
+        loop {
+            server.connect().await.map_err(|e| e.to_string())?;
+            // The next client needs an instance of its own waiting
+            let connected = std::mem::replace(
+                &mut server,
+                options().create(&name).map_err(|e| e.to_string())?,
+            );
+            let (reader, writer) = tokio::io::split(connected);
+            tauri::async_runtime::spawn(session(app.clone(), reader, writer));
+        }
+    }
+
+    /// Pipes can't be half closed, so this ends when stdin does; clients
+    /// should read their replies before closing it
+    pub fn relay(identifier: &str) -> Result<(), String> {
+        let name = pipe_name(identifier);
+        let mut pipe = None;
+        for _ in 0..CONNECT_ATTEMPTS {
+            if let Ok(opened) = OpenOptions::new().read(true).write(true).open(&name) {
+                pipe = Some(opened);
+                break;
+            }
+            std::thread::sleep(CONNECT_INTERVAL);
+        }
+        let mut pipe = pipe.ok_or("it didn't open its pipe")?;
+        let mut receiving = pipe.try_clone().map_err(|e| e.to_string())?;
+        std::thread::spawn(move || {
+            let _ = io::copy(&mut receiving, &mut io::stdout().lock());
+            let _ = io::stdout().flush();
+            std::process::exit(0);
+        });
+        io::copy(&mut io::stdin().lock(), &mut pipe).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,746 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tokio::sync::Notify;
+
+use crate::{app_icon, connectivity, credentials, db::Database, trash, tray, AppState};
+
+/// Sent to every window with the `SyncStatus` whenever it changes
+pub const PROGRESS_EVENT: &str = "sync-progress";
+/// Sent to every window with a `SyncConflict` for the user to resolve
+pub const CONFLICT_EVENT: &str = "sync-conflict";
+
+/// The server's bearer token, kept in the keychain
+pub const TOKEN_KEY: &str = "sync-token";
+
+/// `SyncConfig` and the server's change cursor
+const NAMESPACE: &str = "remote-sync";
+const CONFIG_KEY: &str = "config";
+const CURSOR_KEY: &str = "cursor";
+/// Each note as of its last sync, by note id: the base of three-way merges
+const BASES_NAMESPACE: &str = "remote-sync.bases";
+/// Notes changed here that the server hasn't taken yet, by note id. Kept
+/// in the database so changes made offline go out once it's back.
+const QUEUE_NAMESPACE: &str = "remote-sync.queue";
+/// Notes changed on both sides that didn't merge, by note id
+const CONFLICTS_NAMESPACE: &str = "remote-sync.conflicts";
+
+const MIN_INTERVAL_SECS: u64 = 30;
+/// The first retry after a failure; each one after waits twice as long
+const BACKOFF_START: Duration = Duration::from_secs(5);
+const BACKOFF_MAX: Duration = Duration::from_secs(15 * 60);
+/// Edits settle for this long before they're sent
+const CHANGE_DELAY: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where and how often to sync the notes folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConfig {
+    pub enabled: bool,
+    /// The sync API's base URL, e.g. `https://sync.example.com/v1`
+    pub server_url: String,
+    pub interval_secs: u64,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            server_url: String::new(),
+            interval_secs: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    /// Sync isn't set up
+    #[default]
+    Off,
+    Idle,
+    Downloading,
+    Uploading,
+    /// The server couldn't be reached; changes wait in the queue
+    Offline,
+    /// The server refused or answered with something unusable
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub phase: Phase,
+    /// Notes handled of the current phase's `total`
+    pub done: usize,
+    pub total: usize,
+    /// Local changes waiting to be sent
+    pub pending: usize,
+    pub conflicts: usize,
+    /// When the last sync finished, in milliseconds since the Unix epoch
+    pub last_synced: Option<u64>,
+    pub error: Option<String>,
+    /// When a failed sync is retried, in milliseconds since the Unix epoch
+    pub retry_at: Option<u64>,
+}
+
+/// A note changed on both sides whose changes overlap. It's left alone on
+/// both sides until `resolve` gets the content to keep.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    pub id: String,
+    /// The note as last synced, None if it's new on both sides
+    pub base: Option<String>,
+    /// None where the note was deleted
+    pub local: Option<String>,
+    pub remote: Option<String>,
+    pub remote_revision: u64,
+    /// Both sides with conflict markers around the overlaps, to start from
+    pub merged: String,
+}
+
+/// A note as last synced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Base {
+    revision: u64,
+    content: String,
+}
+
+/// A note as the server has it
+#[derive(Debug, Clone, Deserialize)]
+struct RemoteNote {
+    id: String,
+    revision: u64,
+    /// None once it's deleted
+    content: Option<String>,
+}
+
+/// `GET /changes?since=<cursor>`: every note changed since the cursor
+#[derive(Debug, Deserialize)]
+struct ChangeSet {
+    cursor: String,
+    changes: Vec<RemoteNote>,
+}
+
+/// `PUT /notes/<id>`. The server takes it if `base_revision` is still the
+/// note's revision, answering with the new `RemoteNote`; otherwise it
+/// answers 409 Conflict with the `RemoteNote` it has.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Upload<'a> {
+    base_revision: Option<u64>,
+    content: Option<&'a str>,
+}
+
+enum SyncError {
+    Offline(String),
+    Failed(String),
+}
+
+impl From<String> for SyncError {
+    fn from(message: String) -> Self {
+        SyncError::Failed(message)
+    }
+}
+
+impl From<reqwest::Error> for SyncError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_connect() || e.is_timeout() {
+            SyncError::Offline(e.to_string())
+        } else {
+            SyncError::Failed(e.to_string())
+        }
+    }
+}
+
+/// Mirrors the notes folder against a sync server from a loop on the async
+/// runtime, while it's enabled
+#[derive(Default)]
+pub struct RemoteSync {
+    status: Mutex<SyncStatus>,
+    wake: Notify,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn database(app: &AppHandle) -> Result<Database, String> {
+    app.try_state::<Database>()
+        .map(|db| db.inner().clone())
+        .ok_or_else(|| "The database isn't open".to_string())
+}
+
+fn notes_folder(app: &AppHandle) -> Result<PathBuf, String> {
+    let state = app.state::<AppState>();
+    let app_config = state.app_config.read().expect("app_config read lock");
+    app_config
+        .notes_folder
+        .clone()
+        .map(PathBuf::from)
+        .ok_or_else(|| "Notes folder not set".to_string())
+}
+
+fn note_path(folder: &Path, id: &str) -> PathBuf {
+    folder.join(format!("{}.md", id))
+}
+
+fn read_note(folder: &Path, id: &str) -> Option<String> {
+    std::fs::read_to_string(note_path(folder, id)).ok()
+}
+
+pub fn config(app: &AppHandle) -> SyncConfig {
+    database(app)
+        .and_then(|db| db.get(NAMESPACE, CONFIG_KEY))
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Save `config` and restart the loop with it
+pub fn configure(app: &AppHandle, config: SyncConfig) -> Result<(), String> {
+    if config.enabled {
+        let url = Url::parse(&config.server_url).map_err(|_| "Invalid server URL")?;
+        if !matches!(url.scheme(), "https" | "http") {
+            return Err("The server URL must be http or https".to_string());
+        }
+    }
+    if config.interval_secs < MIN_INTERVAL_SECS {
+        return Err(format!(
+            "Sync can run at most every {} seconds",
+            MIN_INTERVAL_SECS
+        ));
+    }
+    let db = database(app)?;
+    // A different server knows nothing of what was synced with this one
+    if config.server_url != self::config(app).server_url {
+        forget(&db)?;
+    }
+    db.put(NAMESPACE, CONFIG_KEY, &config)?;
+    start(app);
+    Ok(())
+}
+
+/// Drop everything known about past syncs, so the next one starts afresh
+fn forget(db: &Database) -> Result<(), String> {
+    db.delete(NAMESPACE, CURSOR_KEY)?;
+    for namespace in [BASES_NAMESPACE, QUEUE_NAMESPACE, CONFLICTS_NAMESPACE] {
+        db.replace(namespace, Vec::<(&str, &())>::new())?;
+    }
+    Ok(())
+}
+
+/// Start over with a different notes folder. What was synced from the last
+/// one isn't in this one, and mustn't be sent as deleted.
+pub fn folder_changed(app: &AppHandle) {
+    if let Err(e) = database(app).and_then(|db| forget(&db)) {
+        log::warn!(error:% = e; "sync state not cleared");
+    }
+    start(app);
+}
+
+/// Start the loop if sync is enabled, stopping any that's running
+pub fn start(app: &AppHandle) {
+    let sync = app.state::<RemoteSync>();
+    let running = sync.task.lock().expect("sync task mutex").take();
+    if let Some(task) = &running {
+        task.abort();
+    }
+    let config = config(app);
+    if !config.enabled || config.server_url.is_empty() {
+        if running.is_some() {
+            sync.update(app, |status| *status = SyncStatus::default());
+        }
+        return;
+    }
+    let conflicts = conflict_count(app);
+    sync.update(app, |status| {
+        *status = SyncStatus {
+            phase: Phase::Idle,
+            conflicts,
+            ..Default::default()
+        }
+    });
+    let handle = tauri::async_runtime::spawn(run_loop(app.clone(), config));
+    *sync.task.lock().expect("sync task mutex") = Some(handle);
+}
+
+/// Sync now, without waiting out the interval or a retry
+pub fn sync_now(app: &AppHandle) -> Result<(), String> {
+    let sync = app.state::<RemoteSync>();
+    if sync.task.lock().expect("sync task mutex").is_none() {
+        return Err("Sync isn't set up".to_string());
+    }
+    sync.wake.notify_one();
+    Ok(())
+}
+
+pub fn status(app: &AppHandle) -> SyncStatus {
+    app.state::<RemoteSync>()
+        .status
+        .lock()
+        .expect("sync status mutex")
+        .clone()
+}
+
+/// Queue the note for sending. The notes folder's watcher calls this for
+/// each changed note, including those sync itself wrote; a note that
+/// matches what was last synced is dropped from the queue unsent.
+pub fn note_changed(app: &AppHandle, id: &str) {
+    let Some(sync) = app.try_state::<RemoteSync>() else {
+        return;
+    };
+    if sync.task.lock().expect("sync task mutex").is_none() {
+        return;
+    }
+    let Ok(db) = database(app) else {
+        return;
+    };
+    if db.put(QUEUE_NAMESPACE, id, &now_ms()).is_err() {
+        return;
+    }
+    // While it's backing off, changes wait for the retry like the rest
+    let backing_off = sync
+        .status
+        .lock()
+        .expect("sync status mutex")
+        .retry_at
+        .is_some();
+    if !backing_off {
+        sync.wake.notify_one();
+    }
+}
+
+fn conflict_count(app: &AppHandle) -> usize {
+    database(app)
+        .and_then(|db| db.entries(CONFLICTS_NAMESPACE))
+        .map_or(0, |entries| entries.len())
+}
+
+pub fn conflicts(app: &AppHandle) -> Result<Vec<SyncConflict>, String> {
+    Ok(database(app)?
+        .list::<SyncConflict>(CONFLICTS_NAMESPACE)?
+        .into_iter()
+        .map(|(_, conflict)| conflict)
+        .collect())
+}
+
+/// Settle a conflict with `content`, or None to delete the note. It's sent
+/// as a change to the server's revision at the next sync.
+pub fn resolve(app: &AppHandle, id: &str, content: Option<&str>) -> Result<(), String> {
+    let db = database(app)?;
+    let conflict: SyncConflict = db
+        .get(CONFLICTS_NAMESPACE, id)?
+        .ok_or_else(|| format!("No sync conflict for {}", id))?;
+    let folder = notes_folder(app)?;
+    let path = note_path(&folder, id);
+    match content {
+        Some(content) => std::fs::write(&path, content).map_err(|e| e.to_string())?,
+        None if path.exists() => {
+            trash::move_to_trash(&path)?;
+        }
+        None => {}
+    }
+    match conflict.remote {
+        Some(remote) => db.put(
+            BASES_NAMESPACE,
+            id,
+            &Base {
+                revision: conflict.remote_revision,
+                content: remote,
+            },
+        )?,
+        None => db.delete(BASES_NAMESPACE, id)?,
+    }
+    db.delete(CONFLICTS_NAMESPACE, id)?;
+    db.put(QUEUE_NAMESPACE, id, &now_ms())?;
+    let conflicts = conflict_count(app);
+    let sync = app.state::<RemoteSync>();
+    sync.update(app, |status| status.conflicts = conflicts);
+    sync.wake.notify_one();
+    Ok(())
+}
+
+impl RemoteSync {
+    /// Change the status and show it: sent to the frontend, as progress on
+    /// the Dock icon or taskbar button, the number of conflicts on the Dock
+    /// badge and the state in the tray icon's tooltip. The progress and
+    /// badge are only touched as they change, as the frontend sets them too.
+    fn update(&self, app: &AppHandle, f: impl FnOnce(&mut SyncStatus)) {
+        let (previous, status) = {
+            let mut status = self.status.lock().expect("sync status mutex");
+            let previous = status.clone();
+            f(&mut status);
+            (previous, status.clone())
+        };
+        let _ = app.emit(PROGRESS_EVENT, &status);
+
+        let progress = |status: &SyncStatus| match status.phase {
+            Phase::Downloading | Phase::Uploading if status.total > 0 => {
+                Some(status.done as f64 / status.total as f64)
+            }
+            _ => None,
+        };
+        if progress(&status) != progress(&previous) {
+            let _ = app_icon::set_progress(app, progress(&status));
+        }
+        if status.conflicts != previous.conflicts {
+            let _ = app_icon::set_badge_label(
+                app,
+                (status.conflicts > 0).then(|| status.conflicts.to_string()),
+            );
+        }
+        let tooltip = match status.phase {
+            Phase::Off => "Smudge".to_string(),
+            Phase::Idle if status.conflicts > 0 => {
+                format!("Smudge — {} sync conflicts", status.conflicts)
+            }
+            Phase::Idle => "Smudge — Synced".to_string(),
+            Phase::Downloading | Phase::Uploading => "Smudge — Syncing…".to_string(),
+            Phase::Offline => "Smudge — Offline".to_string(),
+            Phase::Failed => "Smudge — Sync failed".to_string(),
+        };
+        let _ = tray::set_tooltip(app, &tooltip);
+    }
+}
+
+async fn run_loop(app: AppHandle, config: SyncConfig) {
+    let sync = app.state::<RemoteSync>();
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!(error:% = e; "sync not started");
+            return;
+        }
+    };
+    let interval = Duration::from_secs(config.interval_secs);
+    let mut failures: u32 = 0;
+    loop {
+        // Pause rather than fail request after request while offline
+        if !connectivity::is_online(&app) {
+            sync.update(&app, |status| {
+                status.phase = Phase::Offline;
+                status.done = 0;
+                status.total = 0;
+                status.error = Some("No internet connection".to_string());
+                status.retry_at = None;
+            });
+            connectivity::wait_for_online(&app).await;
+            failures = 0;
+        }
+        let delay = match sync_once(&app, &client, &config).await {
+            Ok(()) => {
+                failures = 0;
+                sync.update(&app, |status| {
+                    status.phase = Phase::Idle;
+                    status.done = 0;
+                    status.total = 0;
+                    status.error = None;
+                    status.retry_at = None;
+                    status.last_synced = Some(now_ms());
+                });
+                interval
+            }
+            Err(e) => {
+                failures += 1;
+                let delay = BACKOFF_START
+                    .saturating_mul(1 << (failures - 1).min(16))
+                    .min(BACKOFF_MAX);
+                let (phase, message) = match e {
+                    SyncError::Offline(message) => (Phase::Offline, message),
+                    SyncError::Failed(message) => (Phase::Failed, message),
+                };
+                log::warn!(failures, error:% = message; "sync failed");
+                sync.update(&app, |status| {
+                    status.phase = phase;
+                    status.done = 0;
+                    status.total = 0;
+                    status.error = Some(message);
+                    status.retry_at = Some(now_ms() + delay.as_millis() as u64);
+                });
+                delay
+            }
+        };
+        if tokio::time::timeout(delay, sync.wake.notified())
+            .await
+            .is_ok()
+        {
+            tokio::time::sleep(CHANGE_DELAY).await;
+        }
+    }
+}
+
+/// Download the server's changes, then send the queue
+async fn sync_once(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    config: &SyncConfig,
+) -> Result<(), SyncError> {
+    let sync = app.state::<RemoteSync>();
+    let db = database(app)?;
+    let folder = notes_folder(app)?;
+    let server = Url::parse(&config.server_url).map_err(|e| e.to_string())?;
+    let token = credentials::retrieve(app, TOKEN_KEY)?;
+    let request = |request: reqwest::RequestBuilder| match &token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    };
+
+    // Download
+    let cursor: Option<String> = db.get(NAMESPACE, CURSOR_KEY)?;
+    let mut url = endpoint(&server, &["changes"]);
+    if let Some(cursor) = &cursor {
+        url.query_pairs_mut().append_pair("since", cursor);
+    }
+    let response = request(client.get(url)).send().await?;
+    if !response.status().is_success() {
+        return Err(SyncError::Failed(format!(
+            "The sync server answered {}",
+            response.status()
+        )));
+    }
+    let changes: ChangeSet = response.json().await?;
+    let total = changes.changes.len();
+    sync.update(app, |status| {
+        status.phase = Phase::Downloading;
+        status.done = 0;
+        status.total = total;
+    });
+    for (done, remote) in changes.changes.iter().enumerate() {
+        apply_remote(app, &db, &folder, remote)?;
+        sync.update(app, |status| status.done = done + 1);
+    }
+    db.put(NAMESPACE, CURSOR_KEY, &changes.cursor)?;
+
+    // Upload
+    queue_local_changes(&db, &folder)?;
+    let queued: Vec<String> = db
+        .list::<u64>(QUEUE_NAMESPACE)?
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    let total = queued.len();
+    sync.update(app, |status| {
+        status.phase = Phase::Uploading;
+        status.done = 0;
+        status.total = total;
+        status.pending = total;
+    });
+    for (done, id) in queued.iter().enumerate() {
+        upload(app, client, &request, &server, &db, &folder, id).await?;
+        sync.update(app, |status| status.done = done + 1);
+    }
+    let pending = db.entries(QUEUE_NAMESPACE)?.len();
+    sync.update(app, |status| status.pending = pending);
+    Ok(())
+}
+
+/// `server` with `segments` appended to its path, each escaped
+fn endpoint(server: &Url, segments: &[&str]) -> Url {
+    let mut url = server.clone();
+    if let Ok(mut path) = url.path_segments_mut() {
+        path.pop_if_empty().extend(segments);
+    }
+    url
+}
+
+/// Queue every note that differs from what was last synced, so changes
+/// made while smudge wasn't running go out too
+fn queue_local_changes(db: &Database, folder: &Path) -> Result<(), String> {
+    let mut bases: std::collections::HashMap<String, Base> =
+        db.list(BASES_NAMESPACE)?.into_iter().collect();
+    let storage = crate::storage::detect(folder);
+    for entry in std::fs::read_dir(folder)
+        .map_err(|e| e.to_string())?
+        .flatten()
+    {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let base = bases.remove(id);
+        // Reading a placeholder would download it; it's unchanged anyway
+        if storage.is_placeholder(&path) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if base.is_none_or(|base| base.content != content) {
+            db.put(QUEUE_NAMESPACE, id, &now_ms())?;
+        }
+    }
+    // Synced notes that are gone were deleted here
+    for id in bases.keys() {
+        db.put(QUEUE_NAMESPACE, id, &now_ms())?;
+    }
+    Ok(())
+}
+
+/// Send a queued note, taking it off the queue once the server has it. A
+/// note in conflict stays queued until it's resolved.
+async fn upload(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    request: &impl Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+    server: &Url,
+    db: &Database,
+    folder: &Path,
+    id: &str,
+) -> Result<(), SyncError> {
+    if db.get::<SyncConflict>(CONFLICTS_NAMESPACE, id)?.is_some() {
+        return Ok(());
+    }
+    let base: Option<Base> = db.get(BASES_NAMESPACE, id)?;
+    let local = read_note(folder, id);
+    let unchanged = match (&base, &local) {
+        (Some(base), Some(local)) => base.content == *local,
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        db.delete(QUEUE_NAMESPACE, id)?;
+        return Ok(());
+    }
+
+    let body = Upload {
+        base_revision: base.as_ref().map(|base| base.revision),
+        content: local.as_deref(),
+    };
+    let response = request(client.put(endpoint(server, &["notes", id])))
+        .json(&body)
+        .send()
+        .await?;
+    let status = response.status();
+    if status == reqwest::StatusCode::CONFLICT {
+        // Changed there too since the base: merge as if it were downloaded
+        let remote: RemoteNote = response.json().await?;
+        apply_remote(app, db, folder, &remote)?;
+        return Ok(());
+    }
+    if !status.is_success() {
+        return Err(SyncError::Failed(format!(
+            "The sync server answered {} for {}",
+            status, id
+        )));
+    }
+    let remote: RemoteNote = response.json().await?;
+    match local {
+        Some(content) => db.put(
+            BASES_NAMESPACE,
+            id,
+            &Base {
+                revision: remote.revision,
+                content,
+            },
+        )?,
+        None => db.delete(BASES_NAMESPACE, id)?,
+    }
+    db.delete(QUEUE_NAMESPACE, id)?;
+    Ok(())
+}
+
+/// Bring the server's version of a note into the notes folder. A note
+/// changed here as well is merged with the last synced version as the base;
+/// if the changes overlap, or one side deleted what the other edited, it
+/// becomes a conflict.
+fn apply_remote(
+    app: &AppHandle,
+    db: &Database,
+    folder: &Path,
+    remote: &RemoteNote,
+) -> Result<(), String> {
+    let id = remote.id.as_str();
+    // The server's ids become file names
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        log::warn!(id; "sync skipped a note with an invalid id");
+        return Ok(());
+    }
+    let base: Option<Base> = db.get(BASES_NAMESPACE, id)?;
+    let local = read_note(folder, id);
+    let base_content = base.as_ref().map(|base| base.content.as_str());
+    let local_changed = local.as_deref() != base_content;
+    let settled = |content: Option<&str>| -> Result<(), String> {
+        match content {
+            Some(content) => db.put(
+                BASES_NAMESPACE,
+                id,
+                &Base {
+                    revision: remote.revision,
+                    content: content.to_string(),
+                },
+            ),
+            None => db.delete(BASES_NAMESPACE, id),
+        }
+    };
+
+    if !local_changed || local == remote.content {
+        write_note(folder, id, remote.content.as_deref())?;
+        settled(remote.content.as_deref())?;
+        db.delete(QUEUE_NAMESPACE, id)?;
+        db.delete(CONFLICTS_NAMESPACE, id)?;
+        return Ok(());
+    }
+
+    let merged = match (&local, &remote.content) {
+        (Some(local), Some(theirs)) => diffy::merge(base_content.unwrap_or(""), local, theirs),
+        // Deleted on one side and edited on the other
+        (Some(kept), None) | (None, Some(kept)) => Err(kept.clone()),
+        (None, None) => Ok(String::new()),
+    };
+    match merged {
+        Ok(merged) => {
+            if local.is_some() {
+                write_note(folder, id, Some(&merged))?;
+            }
+            // Based on the server's version now, so the merge is what's sent
+            settled(remote.content.as_deref())?;
+            db.put(QUEUE_NAMESPACE, id, &now_ms())?;
+            db.delete(CONFLICTS_NAMESPACE, id)?;
+            app.state::<RemoteSync>().wake.notify_one();
+            log::info!(id; "sync merged changes to a note");
+        }
+        Err(merged) => {
+            let conflict = SyncConflict {
+                id: id.to_string(),
+                base: base.map(|base| base.content),
+                local,
+                remote: remote.content.clone(),
+                remote_revision: remote.revision,
+                merged,
+            };
+            db.put(CONFLICTS_NAMESPACE, id, &conflict)?;
+            let conflicts = conflict_count(app);
+            app.state::<RemoteSync>()
+                .update(app, |status| status.conflicts = conflicts);
+            let _ = app.emit(CONFLICT_EVENT, &conflict);
+            log::info!(id; "sync conflict");
+        }
+    }
+    Ok(())
+}
+
+/// Write or delete a note as the server has it. Deleted notes go to the
+/// trash, where they can still be restored.
+fn write_note(folder: &Path, id: &str, content: Option<&str>) -> Result<(), String> {
+    let path = note_path(folder, id);
+    match content {
+        Some(content) => {
+            std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", id, e))
+        }
+        None if path.exists() => trash::move_to_trash(&path).map(|_| ()),
+        None => Ok(()),
+    }
+}
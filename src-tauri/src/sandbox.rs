@@ -0,0 +1,428 @@
+use base64::Engine;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Built with `--features sandbox` for the App Sandbox. Only macOS has one;
+/// the feature does nothing elsewhere.
+pub const SANDBOXED: bool = cfg!(all(feature = "sandbox", target_os = "macos"));
+
+/// Lets sandboxed builds keep files and folders the user picked
+const BOOKMARKS: &str = "com.apple.security.files.bookmarks.app-scope";
+const NETWORK_CLIENT: &str = "com.apple.security.network.client";
+/// Lets sandboxed builds listen for the browser extension and OAuth
+/// redirects to loopback
+const NETWORK_SERVER: &str = "com.apple.security.network.server";
+const ASSOCIATED_DOMAINS: &str = "com.apple.developer.associated-domains";
+
+/// Whether a feature works in this build, and why not if it doesn't
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capability {
+    pub id: &'static str,
+    pub available: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityReport {
+    pub sandboxed: bool,
+    pub capabilities: Vec<Capability>,
+}
+
+fn capability(id: &'static str, unavailable: Option<&str>) -> Capability {
+    Capability {
+        id,
+        available: unavailable.is_none(),
+        reason: unavailable.map(str::to_string),
+    }
+}
+
+fn missing(entitlement: &str) -> Option<String> {
+    (!platform::has_entitlement(entitlement))
+        .then(|| format!("This build lacks the {} entitlement", entitlement))
+}
+
+/// Every feature the sandbox can take away. Outside sandboxed builds
+/// everything is available.
+pub fn report() -> CapabilityReport {
+    let mut capabilities = vec![
+        // The sandbox only lets apps run helper tools inside their own
+        // bundle, and the Xcode git shim isn't one
+        capability(
+            "git",
+            SANDBOXED.then_some("Sandboxed builds can't run the git command line tools"),
+        ),
+        capability(
+            "aiAssistant",
+            SANDBOXED.then_some("Sandboxed builds can't run the Claude CLI"),
+        ),
+        capability(
+            "screenRecording",
+            SANDBOXED.then_some("Sandboxed builds can't run screencapture"),
+        ),
+        // Sandboxed copies are updated by whoever distributes them
+        capability(
+            "updates",
+            SANDBOXED.then_some("Sandboxed builds are updated by the App Store"),
+        ),
+        capability(
+            "stripQuarantine",
+            SANDBOXED.then_some("Sandboxed builds can't remove download warnings"),
+        ),
+        // Seeing through the page needs WebKit's private drawsBackground,
+        // which App Store review turns down
+        capability(
+            "windowMaterials",
+            SANDBOXED.then_some("Sandboxed builds can't make pages see-through"),
+        ),
+    ];
+    let entitlement_gated = [
+        ("rememberFolders", BOOKMARKS),
+        ("network", NETWORK_CLIENT),
+        ("extensionServer", NETWORK_SERVER),
+        ("loopbackRedirect", NETWORK_SERVER),
+        ("universalLinks", ASSOCIATED_DOMAINS),
+    ];
+    for (id, entitlement) in entitlement_gated {
+        let reason = if SANDBOXED {
+            missing(entitlement)
+        } else {
+            None
+        };
+        capabilities.push(Capability {
+            id,
+            available: reason.is_none(),
+            reason,
+        });
+    }
+    CapabilityReport {
+        sandboxed: SANDBOXED,
+        capabilities,
+    }
+}
+
+/// Fail with the reason `id` is unavailable in this build
+pub fn require(id: &str) -> Result<(), String> {
+    if !SANDBOXED {
+        return Ok(());
+    }
+    match report().capabilities.into_iter().find(|c| c.id == id) {
+        Some(Capability {
+            reason: Some(reason),
+            ..
+        }) => Err(reason),
+        _ => Ok(()),
+    }
+}
+
+/// Security-scoped bookmarks for everything the user picked, persisted to
+/// app data. Sandboxed builds lose access to those paths on quit and get it
+/// back by resolving these on launch.
+///
+/// Documents the OS opened are bookmarked separately and only accessed while
+/// they're open, since the sandbox limits how many items an app can access
+/// at once.
+pub struct Bookmarks {
+    path: Option<PathBuf>,
+    /// Base64 bookmark data per path
+    saved: Mutex<HashMap<PathBuf, String>>,
+    documents_path: Option<PathBuf>,
+    /// Base64 bookmark data per document
+    documents: Mutex<HashMap<PathBuf, String>>,
+    /// Documents being accessed now
+    open: Mutex<HashSet<PathBuf>>,
+}
+
+fn read_saved(path: &Option<PathBuf>) -> HashMap<PathBuf, String> {
+    path.as_ref()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_saved(
+    path: &Option<PathBuf>,
+    saved: &Mutex<HashMap<PathBuf, String>>,
+) -> Result<(), String> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let content = {
+        let saved = saved.lock().expect("bookmarks mutex");
+        serde_json::to_string_pretty(&*saved).map_err(|e| e.to_string())?
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, content).map_err(|e| e.to_string())
+}
+
+impl Bookmarks {
+    pub fn load(app: &AppHandle) -> Self {
+        let dir = app.path().app_data_dir().ok();
+        let path = dir.as_ref().map(|dir| dir.join("bookmarks.json"));
+        let documents_path = dir.as_ref().map(|dir| dir.join("document-bookmarks.json"));
+        Self {
+            saved: Mutex::new(read_saved(&path)),
+            path,
+            documents: Mutex::new(read_saved(&documents_path)),
+            documents_path,
+            open: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Start accessing every bookmarked path. Stale bookmarks are recreated
+    /// and ones that no longer resolve are dropped.
+    pub fn restore(&self) {
+        if !SANDBOXED {
+            return;
+        }
+        let engine = base64::engine::general_purpose::STANDARD;
+        {
+            let mut saved = self.saved.lock().expect("bookmarks mutex");
+            let resolved: HashMap<PathBuf, String> = saved
+                .drain()
+                .filter_map(|(_, data)| {
+                    let bytes = engine.decode(&data).ok()?;
+                    let (path, stale) = platform::start_accessing(&bytes).ok()?;
+                    let data = if stale {
+                        engine.encode(platform::bookmark(&path).ok()?)
+                    } else {
+                        data
+                    };
+                    Some((path, data))
+                })
+                .collect();
+            *saved = resolved;
+        }
+        let _ = self.persist();
+    }
+
+    /// Remember access to `path`, which the user just picked
+    pub fn grant(&self, path: &Path) -> Result<(), String> {
+        if !SANDBOXED {
+            return Ok(());
+        }
+        let data = platform::bookmark(path)?;
+        self.saved.lock().expect("bookmarks mutex").insert(
+            path.to_path_buf(),
+            base64::engine::general_purpose::STANDARD.encode(data),
+        );
+        self.persist()
+    }
+
+    /// Remember access to `document`, which the OS just opened the app with
+    /// and which is only accessible for this launch. It stays open until
+    /// `close_document`.
+    pub fn bookmark_document(&self, document: &Path) -> Result<(), String> {
+        if !SANDBOXED {
+            return Ok(());
+        }
+        let data = platform::bookmark(document)?;
+        self.documents.lock().expect("bookmarks mutex").insert(
+            document.to_path_buf(),
+            base64::engine::general_purpose::STANDARD.encode(data),
+        );
+        self.open
+            .lock()
+            .expect("bookmarks mutex")
+            .insert(document.to_path_buf());
+        write_saved(&self.documents_path, &self.documents)
+    }
+
+    /// Start accessing a bookmarked document, e.g. one reopened after a
+    /// restart. Documents that are already open, or were never bookmarked,
+    /// are left as they are.
+    pub fn open_document(&self, document: &Path) -> Result<(), String> {
+        let already_open = self
+            .open
+            .lock()
+            .expect("bookmarks mutex")
+            .contains(document);
+        if !SANDBOXED || already_open {
+            return Ok(());
+        }
+        let Some(data) = self
+            .documents
+            .lock()
+            .expect("bookmarks mutex")
+            .get(document)
+            .cloned()
+        else {
+            return Ok(());
+        };
+        let engine = base64::engine::general_purpose::STANDARD;
+        let bytes = engine.decode(&data).map_err(|e| e.to_string())?;
+        let (resolved, stale) = platform::start_accessing(&bytes)?;
+        if stale {
+            // Recreated where it is now, but kept under the path the
+            // frontend knows it by
+            let data = engine.encode(platform::bookmark(&resolved)?);
+            self.documents
+                .lock()
+                .expect("bookmarks mutex")
+                .insert(document.to_path_buf(), data);
+            write_saved(&self.documents_path, &self.documents)?;
+        }
+        self.open
+            .lock()
+            .expect("bookmarks mutex")
+            .insert(document.to_path_buf());
+        Ok(())
+    }
+
+    /// Stop accessing `document` until it's opened again
+    pub fn close_document(&self, document: &Path) -> Result<(), String> {
+        if !self.open.lock().expect("bookmarks mutex").remove(document) {
+            return Ok(());
+        }
+        let data = self
+            .documents
+            .lock()
+            .expect("bookmarks mutex")
+            .get(document)
+            .cloned();
+        match data {
+            Some(data) => {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(&data)
+                    .map_err(|e| e.to_string())?;
+                platform::stop_accessing(&bytes)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Forget the bookmark for `path`, so access to it isn't regained on
+    /// the next launch
+    pub fn revoke(&self, path: &Path) -> Result<(), String> {
+        self.close_document(path)?;
+        if self
+            .documents
+            .lock()
+            .expect("bookmarks mutex")
+            .remove(path)
+            .is_some()
+        {
+            write_saved(&self.documents_path, &self.documents)?;
+        }
+        let removed = self
+            .saved
+            .lock()
+            .expect("bookmarks mutex")
+            .remove(path)
+            .is_some();
+        if removed {
+            self.persist()?;
+        }
+        Ok(())
+    }
+
+    fn persist(&self) -> Result<(), String> {
+        write_saved(&self.path, &self.saved)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::{c_char, c_void, CString};
+    use std::path::{Path, PathBuf};
+    use tauri_runtime_wry::tao::platform::macos::{
+        security_scoped_bookmark, start_accessing_bookmark, stop_accessing_bookmark,
+    };
+
+    type CFTypeRef = *const c_void;
+
+    const UTF8: u32 = 0x0800_0100;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            alloc: *const c_void,
+            string: *const c_char,
+            encoding: u32,
+        ) -> CFTypeRef;
+        fn CFGetTypeID(cf: CFTypeRef) -> usize;
+        fn CFBooleanGetTypeID() -> usize;
+        fn CFBooleanGetValue(boolean: CFTypeRef) -> bool;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    #[link(name = "Security", kind = "framework")]
+    extern "C" {
+        fn SecTaskCreateFromSelf(alloc: *const c_void) -> CFTypeRef;
+        fn SecTaskCopyValueForEntitlement(
+            task: CFTypeRef,
+            entitlement: CFTypeRef,
+            error: *mut CFTypeRef,
+        ) -> CFTypeRef;
+    }
+
+    /// Whether the running binary was signed with `entitlement`. Boolean
+    /// entitlements must be true; any other value (e.g. the domain list of
+    /// associated-domains) counts as present.
+    pub fn has_entitlement(entitlement: &str) -> bool {
+        let Ok(name) = CString::new(entitlement) else {
+            return false;
+        };
+        unsafe {
+            let task = SecTaskCreateFromSelf(std::ptr::null());
+            if task.is_null() {
+                return false;
+            }
+            let key = CFStringCreateWithCString(std::ptr::null(), name.as_ptr(), UTF8);
+            let value = if key.is_null() {
+                std::ptr::null()
+            } else {
+                let value = SecTaskCopyValueForEntitlement(task, key, std::ptr::null_mut());
+                CFRelease(key);
+                value
+            };
+            CFRelease(task);
+            if value.is_null() {
+                return false;
+            }
+            let present = CFGetTypeID(value) != CFBooleanGetTypeID() || CFBooleanGetValue(value);
+            CFRelease(value);
+            present
+        }
+    }
+
+    pub fn bookmark(path: &Path) -> Result<Vec<u8>, String> {
+        security_scoped_bookmark(path).map_err(|e| e.to_string())
+    }
+
+    pub fn start_accessing(bookmark: &[u8]) -> Result<(PathBuf, bool), String> {
+        start_accessing_bookmark(bookmark).map_err(|e| e.to_string())
+    }
+
+    pub fn stop_accessing(bookmark: &[u8]) -> Result<(), String> {
+        stop_accessing_bookmark(bookmark).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+#[allow(dead_code)]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    pub fn has_entitlement(_entitlement: &str) -> bool {
+        false
+    }
+
+    pub fn bookmark(_path: &Path) -> Result<Vec<u8>, String> {
+        Err("Security-scoped bookmarks are macOS only".to_string())
+    }
+
+    pub fn start_accessing(_bookmark: &[u8]) -> Result<(PathBuf, bool), String> {
+        Err("Security-scoped bookmarks are macOS only".to_string())
+    }
+
+    pub fn stop_accessing(_bookmark: &[u8]) -> Result<(), String> {
+        Err("Security-scoped bookmarks are macOS only".to_string())
+    }
+}
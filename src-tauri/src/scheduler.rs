@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{diagnostics, executor, git, power, pressure, sandbox, AppState};
+
+/// Sent to every window with a `Completed` whenever a scheduled task
+/// finishes a run
+pub const EVENT: &str = "task-completed";
+
+/// Shortest interval a task can be scheduled at
+const MIN_INTERVAL: Duration = Duration::from_secs(60);
+/// How often deferred work checks whether it can run yet
+const DEFER_RECHECK: Duration = Duration::from_secs(60);
+/// Recordings the diagnostics bundle never picked up are deleted after this
+const RECORDING_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Work that can run periodically
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Task {
+    /// Commit the notes folder's changes and push them, if it's a git
+    /// repository
+    Sync,
+    /// Delete leftover diagnostics recordings
+    Cleanup,
+}
+
+impl Task {
+    fn name(self) -> &'static str {
+        match self {
+            Task::Sync => "sync",
+            Task::Cleanup => "cleanup",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Urgency {
+    /// Runs on time whatever the power source, and keeps App Nap and idle
+    /// sleep off until it's done
+    Critical,
+    /// Waits while the machine is on battery, asleep or locked
+    #[default]
+    Deferrable,
+}
+
+/// What `schedule_task` is asked to run
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    pub task: Task,
+    pub interval_secs: u64,
+    #[serde(default)]
+    pub urgency: Urgency,
+    /// Run once straight away instead of after the first interval
+    #[serde(default)]
+    pub immediately: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Status {
+    Waiting,
+    /// Due, but held back until the machine is plugged in or awake
+    Deferred,
+    Running,
+}
+
+/// A scheduled task, as `list_scheduled_tasks` reports it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTask {
+    pub id: String,
+    pub task: Task,
+    pub interval_secs: u64,
+    pub urgency: Urgency,
+    pub status: Status,
+    /// When the last run finished, in milliseconds since the Unix epoch
+    pub last_run: Option<u64>,
+    pub runs: u64,
+}
+
+/// How a run ended
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Outcome {
+    Succeeded { message: Option<String> },
+    Failed { message: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Completed {
+    id: String,
+    task: Task,
+    outcome: Outcome,
+    duration_ms: u64,
+}
+
+struct Entry {
+    info: ScheduledTask,
+    handle: JoinHandle<()>,
+}
+
+/// Periodic tasks, each looping on the async runtime until it's cancelled
+#[derive(Default)]
+pub struct Scheduler {
+    tasks: Mutex<HashMap<String, Entry>>,
+    next_id: AtomicU64,
+}
+
+impl Scheduler {
+    /// Start running `schedule` and return the id it's listed and
+    /// cancelled under
+    pub fn schedule(&self, app: &AppHandle, schedule: Schedule) -> Result<String, String> {
+        if Duration::from_secs(schedule.interval_secs) < MIN_INTERVAL {
+            return Err(format!(
+                "Tasks can run at most every {} seconds",
+                MIN_INTERVAL.as_secs()
+            ));
+        }
+        let id = format!(
+            "{}-{}",
+            schedule.task.name(),
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        );
+        let info = ScheduledTask {
+            id: id.clone(),
+            task: schedule.task,
+            interval_secs: schedule.interval_secs,
+            urgency: schedule.urgency,
+            status: Status::Waiting,
+            last_run: None,
+            runs: 0,
+        };
+        // Held while spawning so the loop can't look for its entry first
+        let mut tasks = self.tasks.lock().expect("scheduler mutex");
+        let handle =
+            tauri::async_runtime::spawn(run_periodically(app.clone(), id.clone(), schedule));
+        tasks.insert(id.clone(), Entry { info, handle });
+        log::debug!(id = id.as_str(); "task scheduled");
+        Ok(id)
+    }
+
+    /// Stop the task. A run in progress finishes in the background, but
+    /// nothing is reported for it.
+    pub fn cancel(&self, id: &str) -> Result<(), String> {
+        let entry = self
+            .tasks
+            .lock()
+            .expect("scheduler mutex")
+            .remove(id)
+            .ok_or_else(|| format!("No scheduled task {}", id))?;
+        entry.handle.abort();
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<ScheduledTask> {
+        let mut list: Vec<ScheduledTask> = self
+            .tasks
+            .lock()
+            .expect("scheduler mutex")
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect();
+        list.sort_by(|a, b| a.id.cmp(&b.id));
+        list
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut ScheduledTask)) {
+        if let Some(entry) = self.tasks.lock().expect("scheduler mutex").get_mut(id) {
+            f(&mut entry.info);
+        }
+    }
+}
+
+/// Whether deferrable work should wait: nobody's around, running it would
+/// cost battery, or the system is short of memory or running hot
+fn should_defer(app: &AppHandle) -> bool {
+    power::on_battery() || power::is_idle(app) || pressure::is_constrained(app)
+}
+
+async fn run_periodically(app: AppHandle, id: String, schedule: Schedule) {
+    let interval = Duration::from_secs(schedule.interval_secs);
+    if !schedule.immediately {
+        tokio::time::sleep(interval).await;
+    }
+    loop {
+        if schedule.urgency == Urgency::Deferrable {
+            while should_defer(&app) {
+                app.state::<Scheduler>()
+                    .update(&id, |task| task.status = Status::Deferred);
+                tokio::time::sleep(DEFER_RECHECK).await;
+            }
+        }
+        app.state::<Scheduler>()
+            .update(&id, |task| task.status = Status::Running);
+
+        let started = Instant::now();
+        let result = {
+            let _activity = activity::begin(schedule.urgency, schedule.task);
+            run(&app, schedule.task).await
+        };
+        let outcome = match result {
+            Ok(message) => Outcome::Succeeded { message },
+            Err(message) => {
+                log::warn!(id = id.as_str(), error:% = message; "scheduled task failed");
+                Outcome::Failed { message }
+            }
+        };
+        let finished = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .ok();
+        app.state::<Scheduler>().update(&id, |task| {
+            task.status = Status::Waiting;
+            task.last_run = finished;
+            task.runs += 1;
+        });
+        let _ = app.emit(
+            EVENT,
+            Completed {
+                id: id.clone(),
+                task: schedule.task,
+                outcome,
+                duration_ms: started.elapsed().as_millis() as u64,
+            },
+        );
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Run `task` once on the executor's background queue
+async fn run(app: &AppHandle, task: Task) -> Result<Option<String>, String> {
+    let handle = app.clone();
+    app.state::<executor::Executor>()
+        .run(executor::Priority::Background, move || match task {
+            Task::Sync => sync(&handle),
+            Task::Cleanup => cleanup(&handle),
+        })
+        .await?
+}
+
+fn sync(app: &AppHandle) -> Result<Option<String>, String> {
+    sandbox::require("git")?;
+    let folder = {
+        let state = app.state::<AppState>();
+        let app_config = state.app_config.read().expect("app_config read lock");
+        app_config
+            .notes_folder
+            .clone()
+            .map(PathBuf::from)
+            .ok_or("Notes folder not set")?
+    };
+    let status = git::get_status(&folder);
+    if !status.is_repo {
+        return Err("The notes folder isn't a git repository".to_string());
+    }
+    let mut done = Vec::new();
+    if status.changed_count > 0 {
+        let result = git::commit_all(&folder, "Automatic sync");
+        if !result.success {
+            return Err(result.error.unwrap_or_else(|| "Commit failed".to_string()));
+        }
+        done.push(format!("committed {} changes", status.changed_count));
+    }
+    if status.has_upstream {
+        let result = git::push(&folder);
+        if !result.success {
+            return Err(result.error.unwrap_or_else(|| "Push failed".to_string()));
+        }
+        done.push("pushed".to_string());
+    }
+    Ok((!done.is_empty()).then(|| done.join(", ")))
+}
+
+fn cleanup(app: &AppHandle) -> Result<Option<String>, String> {
+    let dir = diagnostics::recordings_dir(app)?;
+    let mut removed = 0;
+    for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let old = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > RECORDING_MAX_AGE);
+        if old && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok((removed > 0).then(|| format!("removed {} old recordings", removed)))
+}
+
+/// Holding off App Nap, which otherwise slows the timers and I/O of an app
+/// that isn't in front
+#[cfg(target_os = "macos")]
+mod activity {
+    use super::{Task, Urgency};
+    use tauri_runtime_wry::tao::platform::macos::{self, ActivityKind, ProcessActivity};
+
+    pub fn begin(urgency: Urgency, task: Task) -> ProcessActivity {
+        let kind = match urgency {
+            Urgency::Critical => ActivityKind::UserInitiated,
+            Urgency::Deferrable => ActivityKind::Background,
+        };
+        macos::begin_activity(kind, &format!("Scheduled {}", task.name()))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod activity {
+    use super::{Task, Urgency};
+
+    /// Nothing else throttles background work the way App Nap does
+    pub struct Activity;
+
+    pub fn begin(_urgency: Urgency, _task: Task) -> Activity {
+        Activity
+    }
+}
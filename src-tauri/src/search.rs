@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Bound;
+use std::path::Path;
+use tantivy::collector::{DocSetCollector, TopDocs};
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, QueryParser, RangeQuery};
+use tantivy::schema::Value;
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{doc, TantivyDocument, Term};
+
+use crate::{storage, SearchIndex};
+
+/// Results when `Filters` doesn't say
+const DEFAULT_LIMIT: usize = 20;
+/// Longest snippet, in characters
+const SNIPPET_CHARS: usize = 160;
+/// Title matches count for this much more than content matches
+const TITLE_BOOST: f32 = 2.0;
+
+/// What to narrow a search to. Everything is optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Filters {
+    /// Only notes modified at or after this, in seconds since the epoch
+    pub modified_after: Option<i64>,
+    /// Only notes modified at or before this, in seconds since the epoch
+    pub modified_before: Option<i64>,
+    /// Match titles, not content
+    pub title_only: bool,
+    pub limit: Option<usize>,
+}
+
+/// A ranked match with the part of the note that matched
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub id: String,
+    pub title: String,
+    pub modified: i64,
+    pub score: f32,
+    /// The best matching passage, or the note's preview if only its title
+    /// matched
+    pub snippet: String,
+    /// Matched words in `snippet` as `[start, end)` offsets in UTF-16 code
+    /// units, as JavaScript strings index
+    pub highlights: Vec<[usize; 2]>,
+}
+
+/// Seconds since the epoch `path` was last modified, as the index stores it
+fn modified_time(path: &Path) -> i64 {
+    std::fs::metadata(path)
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn utf16_offset(text: &str, byte_offset: usize) -> usize {
+    text[..byte_offset].encode_utf16().count()
+}
+
+impl SearchIndex {
+    /// Ranked search of `query`, narrowed by `filters`
+    pub fn search_filtered(
+        &self,
+        query: &str,
+        filters: &Filters,
+    ) -> tantivy::Result<Vec<SearchHit>> {
+        let searcher = self.reader.searcher();
+        let fields = if filters.title_only {
+            vec![self.title_field]
+        } else {
+            vec![self.title_field, self.content_field]
+        };
+        let mut query_parser = QueryParser::for_index(&self.index, fields);
+        query_parser.set_conjunction_by_default();
+        query_parser.set_field_boost(self.title_field, TITLE_BOOST);
+
+        // Parse query, fall back to prefix query if parsing fails
+        let text_query = query_parser
+            .parse_query(query)
+            .or_else(|_| query_parser.parse_query(&format!("{}*", query)))?;
+
+        let bounds = (
+            filters
+                .modified_after
+                .map_or(Bound::Unbounded, Bound::Included),
+            filters
+                .modified_before
+                .map_or(Bound::Unbounded, Bound::Included),
+        );
+        let full_query: Box<dyn Query> = match bounds {
+            (Bound::Unbounded, Bound::Unbounded) => text_query,
+            (lower, upper) => Box::new(BooleanQuery::new(vec![
+                (Occur::Must, text_query),
+                (
+                    Occur::Must,
+                    Box::new(RangeQuery::new_i64_bounds(
+                        "modified".to_string(),
+                        lower,
+                        upper,
+                    )),
+                ),
+            ])),
+        };
+
+        let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).max(1);
+        let top_docs = searcher.search(&*full_query, &TopDocs::with_limit(limit))?;
+
+        let mut snippets = SnippetGenerator::create(&searcher, &*full_query, self.content_field)?;
+        snippets.set_max_num_chars(SNIPPET_CHARS);
+
+        let mut hits = Vec::with_capacity(top_docs.len());
+        for (score, address) in top_docs {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let text = |field| {
+                doc.get_first(field)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string()
+            };
+            let content = text(self.content_field);
+
+            let snippet = snippets.snippet_from_doc(&doc);
+            let (snippet, highlights) = if snippet.is_empty() {
+                (crate::generate_preview(&content), Vec::new())
+            } else {
+                let fragment = snippet.fragment();
+                let highlights = snippet
+                    .highlighted()
+                    .iter()
+                    .map(|range| {
+                        [
+                            utf16_offset(fragment, range.start),
+                            utf16_offset(fragment, range.end),
+                        ]
+                    })
+                    .collect();
+                (fragment.to_string(), highlights)
+            };
+
+            hits.push(SearchHit {
+                id: text(self.id_field),
+                title: text(self.title_field),
+                modified: doc
+                    .get_first(self.modified_field)
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(0),
+                score,
+                snippet,
+                highlights,
+            });
+        }
+        Ok(hits)
+    }
+
+    /// Bring the index up to date with `notes_folder`, indexing only notes
+    /// modified since they were last indexed and dropping those that are
+    /// gone. Returns how many notes changed.
+    pub fn sync(
+        &self,
+        notes_folder: &Path,
+        storage: &dyn storage::StorageProvider,
+    ) -> tantivy::Result<usize> {
+        let searcher = self.reader.searcher();
+        let mut indexed: HashMap<String, i64> = HashMap::new();
+        for address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            if let Some(id) = doc.get_first(self.id_field).and_then(|v| v.as_str()) {
+                let modified = doc
+                    .get_first(self.modified_field)
+                    .and_then(|v| v.as_i64())
+                    .unwrap_or(0);
+                indexed.insert(id.to_string(), modified);
+            }
+        }
+
+        let mut writer = self.writer.lock().expect("search writer mutex");
+        let mut changed = 0;
+        if notes_folder.exists() {
+            for entry in std::fs::read_dir(notes_folder)?.flatten() {
+                let path = entry.path();
+                if path.extension().is_none_or(|ext| ext != "md") {
+                    continue;
+                }
+                let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                let previous = indexed.remove(id);
+                // Reading a placeholder would download it; keep what was
+                // indexed and let the file watcher catch its content
+                if storage.is_placeholder(&path) {
+                    continue;
+                }
+                let modified = modified_time(&path);
+                if previous == Some(modified) {
+                    continue;
+                }
+                let Ok(content) = std::fs::read_to_string(&path) else {
+                    continue;
+                };
+                writer.delete_term(Term::from_field_text(self.id_field, id));
+                writer.add_document(doc!(
+                    self.id_field => id,
+                    self.title_field => crate::extract_title(&content),
+                    self.content_field => content.as_str(),
+                    self.modified_field => modified,
+                ))?;
+                changed += 1;
+            }
+        }
+
+        // Whatever wasn't found in the folder is gone
+        for id in indexed.keys() {
+            writer.delete_term(Term::from_field_text(self.id_field, id));
+            changed += 1;
+        }
+
+        if changed > 0 {
+            writer.commit()?;
+        }
+        Ok(changed)
+    }
+
+    /// Let go of what searches have cached from the index's segments; the
+    /// next search reads what it needs from disk again
+    pub fn release_memory(&self) -> tantivy::Result<()> {
+        self.reader.reload()
+    }
+}
+
+/// Index the note at `path` as it is now, or drop it if it's gone. The
+/// notes folder's watcher calls this for each changed note.
+pub fn note_changed(search_index: &SearchIndex, path: &Path) {
+    let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+        return;
+    };
+    let result = match std::fs::read_to_string(path) {
+        Ok(content) => search_index.index_note(
+            id,
+            &crate::extract_title(&content),
+            &content,
+            modified_time(path),
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => search_index.delete_note(id),
+        Err(_) => return,
+    };
+    if let Err(e) = result {
+        log::warn!(id, error:% = e; "note not indexed");
+    }
+}
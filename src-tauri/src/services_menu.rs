@@ -0,0 +1,79 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sent to the main window with a `ServiceRequest` when another app sends
+/// smudge its selection
+pub const EVENT: &str = "service-request";
+
+/// The `NSMessage` of the "New Smudge Note" service in `Info.plist`
+const SEND_TO_SMUDGE: &str = "sendToSmudge";
+
+/// What the other app put on the pasteboard for the service
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceRequest {
+    pub text: Option<String>,
+    pub files: Vec<PathBuf>,
+}
+
+/// Start providing smudge's services to other apps' Services menus.
+/// macOS only; elsewhere this does nothing.
+pub fn register(app: &AppHandle) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    app.run_on_main_thread(|| {
+        tauri_runtime_wry::tao::platform::macos::register_service(SEND_TO_SMUDGE);
+    })
+    .map_err(|e| e.to_string())?;
+    #[cfg(not(target_os = "macos"))]
+    let _ = (app, SEND_TO_SMUDGE);
+    Ok(())
+}
+
+/// Bring up the main window and hand it what was sent, if it's for one of
+/// smudge's services and there is anything to act on
+pub fn handle(app: &AppHandle, message: &str, text: Option<String>, files: Vec<PathBuf>) {
+    if message != SEND_TO_SMUDGE {
+        return;
+    }
+    let text = text.filter(|text| !text.trim().is_empty());
+    if text.is_none() && files.is_empty() {
+        return;
+    }
+    log::debug!(text_len = text.as_ref().map_or(0, String::len), files = files.len(); "service request");
+    crate::quarantine::opened(app, &files);
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    let _ = window.emit(EVENT, ServiceRequest { text, files });
+}
+
+/// Run another app's service, named as it appears in the Services menu, on
+/// `text` and return the text it sent back, if any. macOS only; elsewhere
+/// there are no services.
+pub async fn perform(
+    app: &AppHandle,
+    menu_item: String,
+    text: String,
+) -> Result<Option<String>, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.run_on_main_thread(move || {
+            let _ = tx.send(tauri_runtime_wry::tao::platform::macos::perform_service(
+                &menu_item, &text,
+            ));
+        })
+        .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The service didn't finish".to_string())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (app, menu_item, text);
+        Err("Services are only available on macOS".to_string())
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, WebviewWindow};
+
+/// Sent to every window with a `Completed` when a share ends
+pub const EVENT: &str = "share-completed";
+
+/// Something handed to the share sheet
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Item {
+    Url {
+        url: String,
+    },
+    Text {
+        text: String,
+    },
+    /// A note file, or any other existing file
+    File {
+        path: String,
+    },
+}
+
+/// How a share ended
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Outcome {
+    /// `service` is the name of where the items went, when the OS says
+    Shared {
+        service: Option<String>,
+    },
+    Cancelled,
+    Failed {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Completed {
+    id: u64,
+    outcome: Outcome,
+}
+
+/// Show the share sheet for `items`, anchored at `position` in `window`'s
+/// content on macOS; Windows places its share UI itself. Returns the id the
+/// share's outcome is reported with.
+pub async fn share(
+    window: &WebviewWindow,
+    items: Vec<Item>,
+    position: tauri::LogicalPosition<f64>,
+) -> Result<u64, String> {
+    if items.is_empty() {
+        return Err("Nothing to share".to_string());
+    }
+    platform::share(window, items, position).await
+}
+
+/// Tell the windows how a share ended
+pub fn completed(app: &AppHandle, id: u64, outcome: Outcome) {
+    use tauri::Emitter;
+
+    log::debug!(id = id, outcome:? = outcome; "share completed");
+    let _ = app.emit(EVENT, Completed { id, outcome });
+}
+
+/// NSSharingServicePicker on macOS, DataTransferManager on Windows, through
+/// tao
+#[cfg(any(target_os = "macos", windows))]
+mod platform {
+    use super::Item;
+    use std::path::PathBuf;
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::share::ShareItem;
+
+    fn native_item(item: Item) -> ShareItem {
+        match item {
+            Item::Url { url } => ShareItem::Url(url),
+            Item::Text { text } => ShareItem::Text(text),
+            Item::File { path } => ShareItem::File(PathBuf::from(path)),
+        }
+    }
+
+    pub async fn share(
+        window: &WebviewWindow,
+        items: Vec<Item>,
+        position: tauri::LogicalPosition<f64>,
+    ) -> Result<u64, String> {
+        let items: Vec<ShareItem> = items.into_iter().map(native_item).collect();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        #[cfg(target_os = "macos")]
+        {
+            use tauri_runtime_wry::tao::{dpi::LogicalPosition, platform::macos};
+
+            let position = LogicalPosition::new(position.x, position.y);
+            crate::windows::with_ns_window(window, move |ns_window| {
+                let _ = tx.send(unsafe { macos::share_items(ns_window, &items, position) });
+            })?;
+        }
+        #[cfg(windows)]
+        {
+            use tauri_runtime_wry::tao::platform::windows;
+
+            let _ = position;
+            let hwnd = window.hwnd().map_err(|e| e.to_string())?.0 as isize;
+            window
+                .run_on_main_thread(move || {
+                    let _ = tx.send(windows::share_items(hwnd, &items));
+                })
+                .map_err(|e| e.to_string())?;
+        }
+        rx.await
+            .map_err(|_| "The share sheet wasn't shown".to_string())?
+            .map_err(|e| format!("Couldn't share: {}", e))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::Item;
+    use tauri::WebviewWindow;
+
+    pub async fn share(
+        _window: &WebviewWindow,
+        _items: Vec<Item>,
+        _position: tauri::LogicalPosition<f64>,
+    ) -> Result<u64, String> {
+        Err("Sharing is only available on macOS and Windows".to_string())
+    }
+}
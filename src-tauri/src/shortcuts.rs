@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Url};
+
+use crate::{deep_link, FrontendReady};
+
+/// Signals the main window that invocations are waiting in
+/// `get_pending_intents`
+pub const RECEIVED_EVENT: &str = "intent-received";
+
+/// Host of smudge's x-callback-url links, e.g.
+/// `smudge://x-callback-url/create-note?title=Groceries&x-success=...`.
+/// Shortcuts' Open X-Callback URL action runs these with parameters and
+/// reads the results back from the `x-success` link.
+const CALLBACK_HOST: &str = "x-callback-url";
+
+/// Prefix of the activities offered to Shortcuts, followed by the action's
+/// name. Each is listed under `NSUserActivityTypes` in Info.plist.
+const ACTIVITY_PREFIX: &str = "com.smudge.intent.";
+
+/// An action smudge offers to Shortcuts
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub struct Action {
+    /// The x-callback-url path and the activity type's suffix
+    pub name: &'static str,
+    pub title: &'static str,
+    pub invocation_phrase: &'static str,
+    /// Query parameters it takes, all optional
+    pub parameters: &'static [&'static str],
+}
+
+pub const ACTIONS: &[Action] = &[
+    Action {
+        name: "create-note",
+        title: "Create Note",
+        invocation_phrase: "New Smudge note",
+        parameters: &["title", "text"],
+    },
+    Action {
+        name: "open-project",
+        title: "Open Project",
+        invocation_phrase: "Open my notes folder",
+        parameters: &["folder"],
+    },
+    Action {
+        name: "export-selection",
+        title: "Export Selection",
+        invocation_phrase: "Export my selected notes",
+        parameters: &["format", "destination"],
+    },
+];
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Html,
+    Markdown,
+}
+
+impl ExportFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "html" => Some(Self::Html),
+            "markdown" | "md" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+}
+
+/// What a shortcut asks for. Anything left out is asked of the user, as the
+/// same action from the menus would.
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    tag = "action",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Intent {
+    CreateNote {
+        title: Option<String>,
+        text: Option<String>,
+    },
+    /// Switch to a notes folder
+    OpenProject { folder: Option<String> },
+    /// Export the notes selected in the note list
+    ExportSelection {
+        format: Option<ExportFormat>,
+        destination: Option<String>,
+    },
+}
+
+impl Intent {
+    /// The intent for the action `name`, taking only the parameters it
+    /// declares
+    fn parse(name: &str, parameters: &HashMap<String, String>) -> Option<Self> {
+        let action = ACTIONS.iter().find(|action| action.name == name)?;
+        let get = |key: &str| {
+            debug_assert!(action.parameters.contains(&key));
+            parameters.get(key).filter(|v| !v.is_empty()).cloned()
+        };
+        match action.name {
+            "create-note" => Some(Self::CreateNote {
+                title: get("title"),
+                text: get("text"),
+            }),
+            "open-project" => Some(Self::OpenProject {
+                folder: get("folder"),
+            }),
+            "export-selection" => Some(Self::ExportSelection {
+                format: get("format").as_deref().and_then(ExportFormat::parse),
+                destination: get("destination"),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// An intent waiting for the frontend, which answers it with
+/// `complete_intent` and its id
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Invocation {
+    pub id: u64,
+    pub intent: Intent,
+}
+
+/// How the frontend finished an invocation. `values` are handed back to the
+/// shortcut as the `x-success` link's query.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "outcome", rename_all = "camelCase")]
+pub enum Completion {
+    Success {
+        #[serde(default)]
+        values: HashMap<String, String>,
+    },
+    Error {
+        message: String,
+    },
+    Cancel,
+}
+
+/// Where an x-callback-url link wants its answer
+#[derive(Debug, Default)]
+struct Callback {
+    success: Option<Url>,
+    error: Option<Url>,
+    cancel: Option<Url>,
+}
+
+/// Invocations the frontend hasn't picked up, and the callbacks of those it
+/// hasn't finished
+#[derive(Default)]
+pub struct Intents {
+    next_id: AtomicU64,
+    pending: Mutex<Vec<Invocation>>,
+    callbacks: Mutex<HashMap<u64, Callback>>,
+}
+
+/// Whether `url` is an x-callback-url link for `received_url`
+pub fn is_callback_url(url: &Url) -> bool {
+    url.scheme() == deep_link::SCHEME && url.host_str() == Some(CALLBACK_HOST)
+}
+
+/// Run the action an x-callback-url link names, with its query as the
+/// parameters. An unknown action is answered on `x-error` straight away.
+pub fn received_url(app: &AppHandle, url: &Url) {
+    let mut parameters = HashMap::new();
+    let mut callback = Callback::default();
+    for (key, value) in url.query_pairs() {
+        let link = || Url::parse(&value).ok();
+        match key.as_ref() {
+            "x-success" => callback.success = link(),
+            "x-error" => callback.error = link(),
+            "x-cancel" => callback.cancel = link(),
+            _ => {
+                parameters.insert(key.into_owned(), value.into_owned());
+            }
+        }
+    }
+    let name = url.path().trim_matches('/');
+    match Intent::parse(name, &parameters) {
+        Some(intent) => invoke(app, intent, callback),
+        None => {
+            let message = format!("Smudge has no action \"{}\"", name);
+            answer(&callback, Completion::Error { message });
+        }
+    }
+}
+
+/// Run the action a continued activity was donated for. Returns false if
+/// it isn't one of `ACTIONS`'.
+pub fn continued(
+    app: &AppHandle,
+    activity_type: &str,
+    user_info: &HashMap<String, String>,
+) -> bool {
+    let Some(intent) = activity_type
+        .strip_prefix(ACTIVITY_PREFIX)
+        .and_then(|name| Intent::parse(name, user_info))
+    else {
+        return false;
+    };
+    invoke(app, intent, Callback::default());
+    true
+}
+
+/// Buffer the invocation for the frontend and signal it if it's listening.
+/// Before then it checks the buffer once it has loaded.
+fn invoke(app: &AppHandle, intent: Intent, callback: Callback) {
+    let Some(intents) = app.try_state::<Intents>() else {
+        return;
+    };
+    let id = intents.next_id.fetch_add(1, Ordering::Relaxed);
+    log::debug!(id, intent:? = intent; "shortcut intent");
+    intents
+        .callbacks
+        .lock()
+        .expect("intent callbacks mutex")
+        .insert(id, callback);
+    intents
+        .pending
+        .lock()
+        .expect("pending intents mutex")
+        .push(Invocation { id, intent });
+
+    let frontend_ready = app
+        .try_state::<FrontendReady>()
+        .map(|s| *s.0.lock().expect("frontend ready mutex"))
+        .unwrap_or(false);
+    if frontend_ready {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.unminimize();
+            let _ = window.show();
+            let _ = window.set_focus();
+            let _ = window.emit(RECEIVED_EVENT, ());
+        }
+    }
+}
+
+pub fn take_pending(intents: &Intents) -> Vec<Invocation> {
+    std::mem::take(&mut *intents.pending.lock().expect("pending intents mutex"))
+}
+
+/// Hand the outcome back to the shortcut that ran `id`, if it asked for it
+pub fn complete(intents: &Intents, id: u64, completion: Completion) -> Result<(), String> {
+    let callback = intents
+        .callbacks
+        .lock()
+        .expect("intent callbacks mutex")
+        .remove(&id)
+        .ok_or_else(|| format!("No intent {} is waiting", id))?;
+    answer(&callback, completion);
+    Ok(())
+}
+
+fn answer(callback: &Callback, completion: Completion) {
+    let url = match completion {
+        Completion::Success { values } => callback.success.clone().map(|mut url| {
+            url.query_pairs_mut().extend_pairs(values);
+            url
+        }),
+        Completion::Error { message } => callback.error.clone().map(|mut url| {
+            url.query_pairs_mut()
+                .append_pair("errorCode", "1")
+                .append_pair("errorMessage", &message);
+            url
+        }),
+        Completion::Cancel => callback.cancel.clone(),
+    };
+    let Some(url) = url else {
+        return;
+    };
+    if let Err(e) = open::that(url.as_str()) {
+        log::warn!(error:% = e; "couldn't answer the shortcut");
+    }
+}
+
+/// Offer `ACTIONS` to the Shortcuts app. macOS only; elsewhere shortcuts
+/// and scripts reach them through x-callback-url links alone.
+pub fn donate(app: &AppHandle) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    app.run_on_main_thread(|| {
+        use tauri_runtime_wry::tao::platform::macos::{self, UserActivity};
+
+        for action in ACTIONS {
+            let activity = UserActivity {
+                activity_type: format!("{}{}", ACTIVITY_PREFIX, action.name),
+                title: Some(action.title.to_string()),
+                ..Default::default()
+            };
+            macos::donate_user_activity(&activity, Some(action.invocation_phrase));
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    #[cfg(not(target_os = "macos"))]
+    let _ = app;
+    Ok(())
+}
@@ -0,0 +1,489 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::watch;
+
+/// Where helpers are bundled, under the app's resources
+const RESOURCES_DIR: &str = "sidecars";
+/// The first wait before restarting a helper that exited, doubled each time
+/// it exits again soon after
+const FIRST_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// A helper that ran this long before exiting starts over at `FIRST_BACKOFF`
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+/// Helpers that exit this many times in a row, each soon after starting,
+/// are given up on
+const MAX_QUICK_EXITS: u32 = 10;
+/// How long helpers get to exit after being asked when the app quits
+const EXIT_GRACE: Duration = Duration::from_secs(2);
+
+/// What a helper is doing, sent on `sidecar://<name>`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SidecarEvent {
+    Started {
+        pid: u32,
+    },
+    Stdout {
+        line: String,
+    },
+    Stderr {
+        line: String,
+    },
+    /// It exited; `code` is `None` when a signal ended it
+    Exited {
+        code: Option<i32>,
+    },
+    /// It's started again after `delay_ms`
+    #[serde(rename_all = "camelCase")]
+    Restarting {
+        delay_ms: u64,
+        attempt: u32,
+    },
+    /// It kept exiting, or couldn't be started, and won't be restarted
+    Failed {
+        message: String,
+    },
+    /// Stopped on request
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarStatus {
+    pub name: String,
+    /// The running process, if there is one between restarts
+    pub pid: Option<u32>,
+    /// Restarts since it was started
+    pub restarts: u32,
+}
+
+struct Sidecar {
+    name: String,
+    stdin: tokio::sync::Mutex<Option<ChildStdin>>,
+    /// 0 while no process is running
+    pid: AtomicU32,
+    restarts: AtomicU32,
+    stop: watch::Sender<bool>,
+}
+
+impl Sidecar {
+    fn status(&self) -> SidecarStatus {
+        let pid = self.pid.load(Ordering::SeqCst);
+        SidecarStatus {
+            name: self.name.clone(),
+            pid: (pid != 0).then_some(pid),
+            restarts: self.restarts.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// The helpers smudge runs, managed as Tauri state. Each is supervised:
+/// restarted with backoff when it exits, and ended when the app quits.
+#[derive(Default)]
+pub struct Sidecars {
+    running: Mutex<HashMap<String, Arc<Sidecar>>>,
+    quitting: AtomicBool,
+}
+
+fn event_name(name: &str) -> String {
+    format!("sidecar://{}", name)
+}
+
+fn emit(app: &AppHandle, name: &str, event: SidecarEvent) {
+    let _ = app.emit(&event_name(name), event);
+}
+
+/// The bundled executable for `name`, which can't reach outside the
+/// helpers' folder
+fn executable(app: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!("\"{}\" isn't a helper's name", name));
+    }
+    let path = app
+        .path()
+        .resource_dir()
+        .map_err(|e| e.to_string())?
+        .join(RESOURCES_DIR)
+        .join(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+    if !path.is_file() {
+        return Err(format!("smudge doesn't include a helper named {}", name));
+    }
+    Ok(path)
+}
+
+/// Start the bundled helper `name` with `args`, supervised until it's
+/// stopped or the app quits. Its output arrives line by line on
+/// `sidecar://<name>`.
+pub fn start(app: &AppHandle, name: &str, args: Vec<String>) -> Result<SidecarStatus, String> {
+    let path = executable(app, name)?;
+    let sidecars = app.state::<Sidecars>();
+    if sidecars.quitting.load(Ordering::SeqCst) {
+        return Err("smudge is quitting".to_string());
+    }
+    let mut running = sidecars.running.lock().expect("sidecars mutex");
+    if running.contains_key(name) {
+        return Err(format!("{} is already running", name));
+    }
+    let (stop, _) = watch::channel(false);
+    let sidecar = Arc::new(Sidecar {
+        name: name.to_string(),
+        stdin: tokio::sync::Mutex::new(None),
+        pid: AtomicU32::new(0),
+        restarts: AtomicU32::new(0),
+        stop,
+    });
+    running.insert(name.to_string(), Arc::clone(&sidecar));
+    let status = sidecar.status();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        supervise(&app, &sidecar, &path, &args).await;
+        let sidecars = app.state::<Sidecars>();
+        let mut running = sidecars.running.lock().expect("sidecars mutex");
+        if running
+            .get(&sidecar.name)
+            .is_some_and(|current| Arc::ptr_eq(current, &sidecar))
+        {
+            running.remove(&sidecar.name);
+        }
+    });
+    Ok(status)
+}
+
+/// Run the helper, and run it again each time it exits until it's stopped
+/// or gives up
+async fn supervise(app: &AppHandle, sidecar: &Sidecar, path: &PathBuf, args: &[String]) {
+    let mut stop = sidecar.stop.subscribe();
+    let mut backoff = FIRST_BACKOFF;
+    let mut quick_exits = 0;
+    loop {
+        let started = Instant::now();
+        let mut child = match spawn(path, args) {
+            Ok(child) => child,
+            Err(e) => {
+                log::error!(sidecar = sidecar.name.as_str(), error:% = e; "sidecar not started");
+                emit(app, &sidecar.name, SidecarEvent::Failed { message: e });
+                return;
+            }
+        };
+        let pid = child.id().unwrap_or(0);
+        platform::adopt(&child);
+        sidecar.pid.store(pid, Ordering::SeqCst);
+        *sidecar.stdin.lock().await = child.stdin.take();
+        log::info!(sidecar = sidecar.name.as_str(), pid = pid; "sidecar started");
+        emit(app, &sidecar.name, SidecarEvent::Started { pid });
+        let stdout = child.stdout.take().map(|stdout| {
+            forward(app, &sidecar.name, stdout, |line| SidecarEvent::Stdout {
+                line,
+            })
+        });
+        let stderr = child.stderr.take().map(|stderr| {
+            forward(app, &sidecar.name, stderr, |line| SidecarEvent::Stderr {
+                line,
+            })
+        });
+
+        let stopped = tokio::select! {
+            _ = stop.wait_for(|stop| *stop) => true,
+            _ = child.wait() => false,
+        };
+        if stopped {
+            // Closing stdin asks politely; well-behaved helpers exit on it
+            sidecar.stdin.lock().await.take();
+            if tokio::time::timeout(EXIT_GRACE, child.wait())
+                .await
+                .is_err()
+            {
+                let _ = child.kill().await;
+            }
+        }
+        let code = child
+            .try_wait()
+            .ok()
+            .flatten()
+            .and_then(|status| status.code());
+        sidecar.pid.store(0, Ordering::SeqCst);
+        sidecar.stdin.lock().await.take();
+        // Whatever it wrote before exiting is sent before it's said to have
+        for output in [stdout, stderr].into_iter().flatten() {
+            let _ = output.await;
+        }
+        if stopped {
+            log::info!(sidecar = sidecar.name.as_str(); "sidecar stopped");
+            emit(app, &sidecar.name, SidecarEvent::Stopped);
+            return;
+        }
+        log::warn!(sidecar = sidecar.name.as_str(), code:? = code; "sidecar exited");
+        emit(app, &sidecar.name, SidecarEvent::Exited { code });
+
+        if started.elapsed() >= HEALTHY_RUN {
+            backoff = FIRST_BACKOFF;
+            quick_exits = 0;
+        } else {
+            quick_exits += 1;
+        }
+        if quick_exits >= MAX_QUICK_EXITS {
+            emit(
+                app,
+                &sidecar.name,
+                SidecarEvent::Failed {
+                    message: format!("It exited {} times in a row", quick_exits),
+                },
+            );
+            return;
+        }
+        let attempt = sidecar.restarts.fetch_add(1, Ordering::SeqCst) + 1;
+        emit(
+            app,
+            &sidecar.name,
+            SidecarEvent::Restarting {
+                delay_ms: backoff.as_millis() as u64,
+                attempt,
+            },
+        );
+        tokio::select! {
+            _ = stop.wait_for(|stop| *stop) => {
+                emit(app, &sidecar.name, SidecarEvent::Stopped);
+                return;
+            }
+            _ = tokio::time::sleep(backoff) => {}
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+fn spawn(path: &PathBuf, args: &[String]) -> Result<Child, String> {
+    let mut command = Command::new(path);
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    platform::configure(&mut command);
+    command.spawn().map_err(|e| e.to_string())
+}
+
+/// Send what `output` writes as events, a line at a time
+fn forward(
+    app: &AppHandle,
+    name: &str,
+    output: impl AsyncRead + Unpin + Send + 'static,
+    event: fn(String) -> SidecarEvent,
+) -> tauri::async_runtime::JoinHandle<()> {
+    let app = app.clone();
+    let name = name.to_string();
+    tauri::async_runtime::spawn(async move {
+        let mut reader = BufReader::new(output);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {
+                    let text = String::from_utf8_lossy(&line);
+                    let text = text.trim_end_matches(['\n', '\r']).to_string();
+                    emit(&app, &name, event(text));
+                }
+            }
+        }
+    })
+}
+
+fn running(app: &AppHandle, name: &str) -> Result<Arc<Sidecar>, String> {
+    app.state::<Sidecars>()
+        .running
+        .lock()
+        .expect("sidecars mutex")
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("{} isn't running", name))
+}
+
+/// Write `data` to the helper's stdin
+pub async fn write(app: &AppHandle, name: &str, data: &[u8]) -> Result<(), String> {
+    let sidecar = running(app, name)?;
+    let mut stdin = sidecar.stdin.lock().await;
+    let stdin = stdin
+        .as_mut()
+        .ok_or_else(|| format!("{} is restarting", name))?;
+    stdin.write_all(data).await.map_err(|e| e.to_string())?;
+    stdin.flush().await.map_err(|e| e.to_string())
+}
+
+/// Stop the helper: its stdin is closed, and it's killed if it hasn't
+/// exited a moment later
+pub fn stop(app: &AppHandle, name: &str) -> Result<(), String> {
+    let sidecar = running(app, name)?;
+    sidecar.stop.send_replace(true);
+    Ok(())
+}
+
+pub fn list(app: &AppHandle) -> Vec<SidecarStatus> {
+    let mut statuses: Vec<SidecarStatus> = app
+        .state::<Sidecars>()
+        .running
+        .lock()
+        .expect("sidecars mutex")
+        .values()
+        .map(|sidecar| sidecar.status())
+        .collect();
+    statuses.sort_by(|a, b| a.name.cmp(&b.name));
+    statuses
+}
+
+/// End every helper before the app exits. Called from `RunEvent::Exit`,
+/// which on macOS is `applicationWillTerminate`, after which the process
+/// ends at once: so this blocks until they've exited or been killed.
+pub fn shutdown(app: &AppHandle) {
+    let Some(sidecars) = app.try_state::<Sidecars>() else {
+        return;
+    };
+    sidecars.quitting.store(true, Ordering::SeqCst);
+    let running: Vec<Arc<Sidecar>> = sidecars
+        .running
+        .lock()
+        .expect("sidecars mutex")
+        .values()
+        .cloned()
+        .collect();
+    if running.is_empty() {
+        return;
+    }
+    for sidecar in &running {
+        sidecar.stop.send_replace(true);
+    }
+    // The supervisors close stdin and kill what's left after the grace
+    // period; this waits a little longer for them, then kills it itself
+    let deadline = Instant::now() + EXIT_GRACE + Duration::from_millis(500);
+    while Instant::now() < deadline
+        && running
+            .iter()
+            .any(|sidecar| sidecar.pid.load(Ordering::SeqCst) != 0)
+    {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    for sidecar in &running {
+        let pid = sidecar.pid.load(Ordering::SeqCst);
+        if pid != 0 {
+            log::warn!(sidecar = sidecar.name.as_str(), pid = pid; "sidecar killed at exit");
+            platform::kill(pid);
+        }
+    }
+}
+
+/// Children get `SIGKILL` if smudge dies without running `shutdown`, and
+/// are killed by pid if they outlast it
+#[cfg(target_os = "linux")]
+mod platform {
+    use tokio::process::{Child, Command};
+
+    pub fn configure(command: &mut Command) {
+        unsafe {
+            command.pre_exec(|| {
+                if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    pub fn adopt(_child: &Child) {}
+
+    pub fn kill(pid: u32) {
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+}
+
+/// macOS has no way to have children end with their parent; a helper
+/// that outlives a crash sees its stdin close
+#[cfg(all(unix, not(target_os = "linux")))]
+mod platform {
+    use tokio::process::{Child, Command};
+
+    pub fn configure(_command: &mut Command) {}
+
+    pub fn adopt(_child: &Child) {}
+
+    pub fn kill(pid: u32) {
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+}
+
+/// Children join a job object that kills them when its last handle closes,
+/// which is when smudge exits however it exits
+#[cfg(windows)]
+mod platform {
+    use std::sync::OnceLock;
+    use tokio::process::{Child, Command};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+    use windows::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+    /// The job's handle, as an address so it can be shared between threads
+    static JOB: OnceLock<Option<isize>> = OnceLock::new();
+
+    fn job() -> Option<HANDLE> {
+        let job = JOB.get_or_init(|| unsafe {
+            let job = CreateJobObjectW(None, None).ok()?;
+            let mut limits = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+            limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            let set = SetInformationJobObject(
+                job,
+                JobObjectExtendedLimitInformation,
+                &limits as *const _ as *const _,
+                std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            );
+            if set.is_err() {
+                let _ = CloseHandle(job);
+                return None;
+            }
+            Some(job.0 as isize)
+        });
+        job.map(|job| HANDLE(job as *mut _))
+    }
+
+    pub fn configure(command: &mut Command) {
+        // No console window flashing up for console helpers
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    pub fn adopt(child: &Child) {
+        let (Some(job), Some(process)) = (job(), child.raw_handle()) else {
+            return;
+        };
+        if let Err(e) = unsafe { AssignProcessToJobObject(job, HANDLE(process)) } {
+            log::warn!(error:% = e; "sidecar not added to the job object");
+        }
+    }
+
+    pub fn kill(pid: u32) {
+        unsafe {
+            if let Ok(process) = OpenProcess(PROCESS_TERMINATE, false, pid) {
+                let _ = TerminateProcess(process, 1);
+                let _ = CloseHandle(process);
+            }
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{deep_link, projects, remote_control, FrontendReady, OpenedFiles};
+
+/// What a second copy of smudge was launched with, sent to the first
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Forwarded {
+    args: Vec<String>,
+    /// Relative paths in `args` are relative to this
+    cwd: Option<PathBuf>,
+}
+
+/// Held by the copy of smudge that owns the notes folder, search index and
+/// config. Later copies forward their arguments to it and exit.
+pub struct SingleInstance {
+    listener: Option<platform::Listener>,
+}
+
+/// Become the running instance, or hand this launch's arguments to the one
+/// that already is and return `None`, in which case the caller should exit.
+/// Runs before Tauri starts, so a second copy never touches shared state.
+pub fn acquire(identifier: &str) -> Option<SingleInstance> {
+    let forwarded = Forwarded {
+        args: std::env::args().collect(),
+        cwd: std::env::current_dir().ok(),
+    };
+    let message = serde_json::to_vec(&forwarded).unwrap_or_default();
+    match platform::acquire(identifier, &message) {
+        platform::Role::Primary(listener) => Some(SingleInstance { listener }),
+        platform::Role::Secondary => None,
+    }
+}
+
+impl SingleInstance {
+    /// Open what later copies forward. If setting up the channel failed, this
+    /// instance still runs, just without receiving them.
+    pub fn serve(self, app: &AppHandle) {
+        let Some(listener) = self.listener else {
+            return;
+        };
+        let app = app.clone();
+        std::thread::spawn(move || {
+            while let Some(bytes) = listener.accept() {
+                let Ok(forwarded) = serde_json::from_slice::<Forwarded>(&bytes) else {
+                    continue;
+                };
+                let handle = app.clone();
+                let _ = app.run_on_main_thread(move || open(&handle, forwarded));
+            }
+        });
+    }
+}
+
+/// Route forwarded arguments like the OS's own open requests, then bring the
+/// main window to the front
+fn open(app: &AppHandle, forwarded: Forwarded) {
+    deep_link::received(app, &deep_link::urls_from_args(&forwarded.args));
+    // A copy launched for remote control relays to this one's socket
+    if remote_control::requested(&forwarded.args).is_some() {
+        remote_control::start(app, remote_control::Transport::Socket);
+    }
+
+    let cwd = forwarded.cwd.unwrap_or_default();
+    let paths: Vec<PathBuf> = forwarded
+        .args
+        .iter()
+        .skip(1)
+        .map(|arg| cwd.join(arg))
+        .collect();
+    let files: Vec<PathBuf> = projects::open_bundles(app, paths)
+        .into_iter()
+        .filter(|path| path.is_file() && crate::is_markdown_file(path))
+        .collect();
+    open_files(app, files);
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Open markdown files in the main window, as if the OS asked to
+pub fn open_files(app: &AppHandle, files: Vec<PathBuf>) {
+    if files.is_empty() {
+        return;
+    }
+    if let Some(scope) = app.try_state::<crate::fs_scope::FsScope>() {
+        scope.opened(app, &files);
+    }
+    crate::quarantine::opened(app, &files);
+    if let Some(state) = app.try_state::<OpenedFiles>() {
+        state.0.lock().expect("opened files mutex").extend(files);
+    }
+    let frontend_ready = app
+        .try_state::<FrontendReady>()
+        .map(|s| *s.0.lock().expect("frontend ready mutex"))
+        .unwrap_or(false);
+    if frontend_ready {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.emit("file-opened", "check");
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use std::fs::{File, OpenOptions};
+    use std::io::{Read, Write};
+    use std::os::windows::io::FromRawHandle;
+    use std::time::Duration;
+
+    const ERROR_ALREADY_EXISTS: u32 = 183;
+    const ERROR_PIPE_CONNECTED: u32 = 535;
+    const PIPE_ACCESS_INBOUND: u32 = 0x1;
+    const PIPE_TYPE_BYTE_WAIT: u32 = 0x0;
+    const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+    const BUFFER_SIZE: u32 = 64 * 1024;
+    const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+
+    /// How long a second copy keeps trying to reach a first one that is
+    /// still starting up
+    const CONNECT_ATTEMPTS: u32 = 20;
+    const CONNECT_INTERVAL: Duration = Duration::from_millis(100);
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateMutexW(
+            attributes: *const c_void,
+            initial_owner: i32,
+            name: *const u16,
+        ) -> *mut c_void;
+        fn GetLastError() -> u32;
+        fn CreateNamedPipeW(
+            name: *const u16,
+            open_mode: u32,
+            pipe_mode: u32,
+            max_instances: u32,
+            out_buffer_size: u32,
+            in_buffer_size: u32,
+            default_timeout: u32,
+            attributes: *const c_void,
+        ) -> *mut c_void;
+        fn ConnectNamedPipe(pipe: *mut c_void, overlapped: *mut c_void) -> i32;
+    }
+
+    pub enum Role {
+        Primary(Option<Listener>),
+        Secondary,
+    }
+
+    /// Reads one message per connection to the instance's named pipe
+    pub struct Listener {
+        pipe_name: Vec<u16>,
+    }
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Pipe names are machine-wide, unlike `Local\` mutexes, so they name the
+    /// user too
+    fn pipe_name(identifier: &str) -> String {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        format!(r"\\.\pipe\{}.single-instance.{}", identifier, user)
+    }
+
+    pub fn acquire(identifier: &str, message: &[u8]) -> Role {
+        let mutex_name = wide(&format!(r"Local\{}.single-instance", identifier));
+        // Never closed: the mutex marks this process as the instance until it exits
+        let mutex = unsafe { CreateMutexW(std::ptr::null(), 0, mutex_name.as_ptr()) };
+        if mutex.is_null() {
+            return Role::Primary(None);
+        }
+        let pipe_name = pipe_name(identifier);
+        if unsafe { GetLastError() } != ERROR_ALREADY_EXISTS {
+            return Role::Primary(Some(Listener {
+                pipe_name: wide(&pipe_name),
+            }));
+        }
+
+        for _ in 0..CONNECT_ATTEMPTS {
+            if let Ok(mut pipe) = OpenOptions::new().write(true).open(&pipe_name) {
+                let _ = pipe.write_all(message);
+                break;
+            }
+            std::thread::sleep(CONNECT_INTERVAL);
+        }
+        Role::Secondary
+    }
+
+    impl Listener {
+        /// The next message, or `None` once the pipe can't be served
+        pub fn accept(&self) -> Option<Vec<u8>> {
+            let pipe = unsafe {
+                CreateNamedPipeW(
+                    self.pipe_name.as_ptr(),
+                    PIPE_ACCESS_INBOUND,
+                    PIPE_TYPE_BYTE_WAIT,
+                    PIPE_UNLIMITED_INSTANCES,
+                    0,
+                    BUFFER_SIZE,
+                    0,
+                    std::ptr::null(),
+                )
+            };
+            if pipe == INVALID_HANDLE_VALUE {
+                return None;
+            }
+            let connected = unsafe { ConnectNamedPipe(pipe, std::ptr::null_mut()) } != 0
+                || unsafe { GetLastError() } == ERROR_PIPE_CONNECTED;
+            // Closes the pipe instance when dropped
+            let mut pipe = unsafe { File::from_raw_handle(pipe) };
+            let mut message = Vec::new();
+            if connected {
+                let _ = pipe.read_to_end(&mut message);
+            }
+            Some(message)
+        }
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::io::{Read, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+
+    pub enum Role {
+        Primary(Option<Listener>),
+        Secondary,
+    }
+
+    /// Reads one message per connection to the instance's socket
+    pub struct Listener(UnixListener);
+
+    /// Per user: the runtime dir on Linux, the per-user temp dir on macOS
+    fn socket_path(identifier: &str) -> PathBuf {
+        std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join(format!("{}.sock", identifier))
+    }
+
+    pub fn acquire(identifier: &str, message: &[u8]) -> Role {
+        let path = socket_path(identifier);
+        if let Ok(mut stream) = UnixStream::connect(&path) {
+            let _ = stream.write_all(message);
+            // A background app can't bring itself forward on macOS, so the
+            // launching copy does it
+            #[cfg(target_os = "macos")]
+            tauri_runtime_wry::tao::platform::macos::activate_running_instance(identifier);
+            return Role::Secondary;
+        }
+        // Launch Services starts one copy per bundle by default; one without
+        // a socket is still starting up or predates it
+        #[cfg(target_os = "macos")]
+        if tauri_runtime_wry::tao::platform::macos::activate_running_instance(identifier) {
+            return Role::Secondary;
+        }
+
+        // Nothing answered, so a socket left there belonged to a copy that
+        // crashed
+        let _ = std::fs::remove_file(&path);
+        Role::Primary(UnixListener::bind(&path).ok().map(Listener))
+    }
+
+    impl Listener {
+        /// The next message, or `None` once the socket can't be served
+        pub fn accept(&self) -> Option<Vec<u8>> {
+            let (mut stream, _) = self.0.accept().ok()?;
+            let mut message = Vec::new();
+            let _ = stream.read_to_end(&mut message);
+            Some(message)
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use serde::Serialize;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{accessibility, sandbox, storage, titlebar, NoteMetadata, Settings};
+
+/// Notes shown before the full list has loaded
+const RECENT_NOTES: usize = 20;
+
+/// Everything the main window asks for while it starts up, injected before
+/// its scripts run so the first render doesn't wait on a dozen commands.
+/// Each value is what the command of the same name would return at launch.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupSnapshot {
+    /// Distinguishes this launch's snapshot from a reload of the same page,
+    /// which gets the same script but must not trust it
+    pub launch_id: String,
+    pub notes_folder: Option<String>,
+    pub settings: Settings,
+    /// Most recently modified notes, newest first
+    pub recent_notes: Vec<NoteMetadata>,
+    pub titlebar: titlebar::TitlebarInfo,
+    pub capabilities: sandbox::CapabilityReport,
+    pub high_contrast: bool,
+    pub reduce_motion: bool,
+    pub reduce_transparency: bool,
+    pub text_scale: f64,
+}
+
+impl StartupSnapshot {
+    pub fn new(
+        notes_folder: Option<String>,
+        settings: Settings,
+        titlebar: titlebar::TitlebarInfo,
+        accessibility: &accessibility::AccessibilityState,
+        storage: &dyn storage::StorageProvider,
+    ) -> Self {
+        let launch_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos().to_string())
+            .unwrap_or_default();
+        let recent_notes = notes_folder
+            .as_deref()
+            .map(|folder| recent_notes(Path::new(folder), storage))
+            .unwrap_or_default();
+        Self {
+            launch_id,
+            notes_folder,
+            settings,
+            recent_notes,
+            titlebar,
+            capabilities: sandbox::report(),
+            high_contrast: accessibility.high_contrast(),
+            reduce_motion: accessibility.reduce_motion(),
+            reduce_transparency: accessibility.reduce_transparency(),
+            text_scale: accessibility.text_scale(),
+        }
+    }
+
+    /// Initialization script defining `window.__SMUDGE_SNAPSHOT__`. Parsing
+    /// a JSON string is faster than evaluating the same data as a literal.
+    pub fn script(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_else(|_| "null".to_string());
+        let literal = serde_json::to_string(&json).unwrap_or_else(|_| "\"null\"".to_string());
+        format!("window.__SMUDGE_SNAPSHOT__ = JSON.parse({});", literal)
+    }
+}
+
+/// The newest notes by modification time. Only these are read, and cloud
+/// placeholders are skipped so nothing downloads during launch.
+fn recent_notes(folder: &Path, storage: &dyn storage::StorageProvider) -> Vec<NoteMetadata> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(i64, std::path::PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter(|path| !storage.is_placeholder(path))
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path)
+                .ok()?
+                .modified()
+                .ok()?
+                .duration_since(UNIX_EPOCH)
+                .ok()?
+                .as_secs() as i64;
+            Some((modified, path))
+        })
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    files
+        .into_iter()
+        .take(RECENT_NOTES)
+        .filter_map(|(modified, path)| {
+            let content = std::fs::read_to_string(&path).ok()?;
+            Some(NoteMetadata {
+                id: path.file_stem()?.to_str()?.to_string(),
+                title: crate::extract_title(&content),
+                preview: crate::generate_preview(&content),
+                modified,
+                is_placeholder: false,
+            })
+        })
+        .collect()
+}
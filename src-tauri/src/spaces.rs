@@ -0,0 +1,307 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, LogicalSize, Manager, PhysicalPosition, WebviewWindow};
+
+/// Sent to every window when one of them moves to another Space or virtual
+/// desktop, or the user switches to one it's on or off
+pub const SPACE_EVENT: &str = "space-changed";
+/// How often windows are checked for having moved; neither platform says
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How a window takes part in Spaces, Mission Control, Stage Manager and
+/// full screen. Of each group, at most one should be given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Behavior {
+    /// Shown on every Space
+    CanJoinAllSpaces,
+    /// Comes to the active Space when shown instead of switching Spaces
+    MoveToActiveSpace,
+    /// Moved around by Mission Control like a document window
+    Managed,
+    /// Floats in its Space and is hidden by Mission Control
+    Transient,
+    /// Stays put in Mission Control
+    Stationary,
+    /// Included in Cmd-` cycling and the Window menu
+    ParticipatesInCycle,
+    IgnoresCycle,
+    /// Can go full screen in its own Space
+    FullScreenPrimary,
+    /// Can be shown over another window's full screen Space
+    FullScreenAuxiliary,
+    FullScreenNone,
+    /// Gets its own stage in Stage Manager
+    Primary,
+    /// Joins the stage of the window it's shown with, like a palette
+    Auxiliary,
+    /// Shown with whichever app's stage is active
+    CanJoinAllApplications,
+}
+
+/// A document window: arranged by Mission Control and Stage Manager and
+/// cycled through with the app's other windows
+pub const DOCUMENT: &[Behavior] = &[
+    Behavior::Managed,
+    Behavior::ParticipatesInCycle,
+    Behavior::FullScreenPrimary,
+    Behavior::Primary,
+];
+
+/// A floating palette that follows the user to every Space, including full
+/// screen ones, and stays out of Mission Control, window cycling and Stage
+/// Manager's stages
+pub const PALETTE: &[Behavior] = &[
+    Behavior::CanJoinAllSpaces,
+    Behavior::Transient,
+    Behavior::IgnoresCycle,
+    Behavior::FullScreenAuxiliary,
+    Behavior::Auxiliary,
+];
+
+/// Replace how `window` takes part in Spaces, Mission Control, Stage Manager
+/// and full screen. Only macOS has these; elsewhere nothing changes.
+pub fn apply(window: &WebviewWindow, behaviors: &[Behavior]) -> Result<(), String> {
+    platform::apply(window, behaviors)
+}
+
+/// Where a window is among the Spaces (macOS) or virtual desktops (Windows)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Space {
+    /// On the Space or desktop being shown
+    pub on_active_space: bool,
+    /// The virtual desktop's id; Windows only, macOS has no public ids for
+    /// Spaces
+    pub desktop: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpaceChange {
+    pub label: String,
+    pub space: Space,
+}
+
+pub async fn space(window: &WebviewWindow) -> Result<Space, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let target = window.clone();
+    window
+        .run_on_main_thread(move || {
+            let _ = tx.send(platform::space(&target));
+        })
+        .map_err(|e| e.to_string())?;
+    rx.await
+        .map_err(|_| "The main thread dropped the call".to_string())?
+}
+
+/// Take `window` into or out of full screen; on macOS that's its own Space,
+/// as the green button does
+pub fn set_full_screen(window: &WebviewWindow, full_screen: bool) -> Result<(), String> {
+    window
+        .set_fullscreen(full_screen)
+        .map_err(|e| e.to_string())
+}
+
+/// Put `left` and `right` side by side, each filling half of the work area
+/// of the monitor `left` is on, and bring both to the front. On Windows
+/// `right` first moves to `left`'s virtual desktop. Neither platform lets
+/// an app start Split View or Snap Assist itself, so this is the layout
+/// they make without the shared full screen Space.
+pub fn tile(left: &WebviewWindow, right: &WebviewWindow) -> Result<(), String> {
+    if left.label() == right.label() {
+        return Err("A window can't be tiled with itself".to_string());
+    }
+    let monitor = left
+        .current_monitor()
+        .map_err(|e| e.to_string())?
+        .ok_or("No monitor available")?;
+    platform::join_space(left, right)?;
+    let area = monitor.work_area();
+    let half = area.size.width / 2;
+    for (window, x) in [
+        (left, area.position.x),
+        (right, area.position.x + half as i32),
+    ] {
+        window.set_fullscreen(false).map_err(|e| e.to_string())?;
+        let _ = window.unmaximize();
+        let _ = window.unminimize();
+        window.show().map_err(|e| e.to_string())?;
+        // Sizes are for the content, so the frame comes off the half
+        let outer = window.outer_size().map_err(|e| e.to_string())?;
+        let inner = window.inner_size().map_err(|e| e.to_string())?;
+        let frame_width = outer.width.saturating_sub(inner.width);
+        let frame_height = outer.height.saturating_sub(inner.height);
+        let scale = window.scale_factor().map_err(|e| e.to_string())?;
+        window
+            .set_size(LogicalSize::new(
+                half.saturating_sub(frame_width) as f64 / scale,
+                area.size.height.saturating_sub(frame_height) as f64 / scale,
+            ))
+            .map_err(|e| e.to_string())?;
+        window
+            .set_position(PhysicalPosition::new(x, area.position.y))
+            .map_err(|e| e.to_string())?;
+    }
+    right.set_focus().map_err(|e| e.to_string())?;
+    left.set_focus().map_err(|e| e.to_string())
+}
+
+/// Check every window's Space now and then, and say when one changed
+pub fn watch(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut last: HashMap<String, Space> = HashMap::new();
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let windows = app.webview_windows();
+            last.retain(|label, _| windows.contains_key(label));
+            for (label, window) in windows {
+                let Ok(space) = space(&window).await else {
+                    continue;
+                };
+                if last.get(&label) == Some(&space) {
+                    continue;
+                }
+                // The first look at a window isn't a change
+                if last.insert(label.clone(), space.clone()).is_some() {
+                    let _ = app.emit(SPACE_EVENT, SpaceChange { label, space });
+                }
+            }
+        }
+    });
+}
+
+/// NSWindowCollectionBehavior through tao, and the window's own Space
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Behavior, Space};
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::platform::macos::{self, CollectionBehavior};
+
+    fn flag(behavior: Behavior) -> CollectionBehavior {
+        match behavior {
+            Behavior::CanJoinAllSpaces => CollectionBehavior::CAN_JOIN_ALL_SPACES,
+            Behavior::MoveToActiveSpace => CollectionBehavior::MOVE_TO_ACTIVE_SPACE,
+            Behavior::Managed => CollectionBehavior::MANAGED,
+            Behavior::Transient => CollectionBehavior::TRANSIENT,
+            Behavior::Stationary => CollectionBehavior::STATIONARY,
+            Behavior::ParticipatesInCycle => CollectionBehavior::PARTICIPATES_IN_CYCLE,
+            Behavior::IgnoresCycle => CollectionBehavior::IGNORES_CYCLE,
+            Behavior::FullScreenPrimary => CollectionBehavior::FULL_SCREEN_PRIMARY,
+            Behavior::FullScreenAuxiliary => CollectionBehavior::FULL_SCREEN_AUXILIARY,
+            Behavior::FullScreenNone => CollectionBehavior::FULL_SCREEN_NONE,
+            Behavior::Primary => CollectionBehavior::PRIMARY,
+            Behavior::Auxiliary => CollectionBehavior::AUXILIARY,
+            Behavior::CanJoinAllApplications => CollectionBehavior::CAN_JOIN_ALL_APPLICATIONS,
+        }
+    }
+
+    pub fn apply(window: &WebviewWindow, behaviors: &[Behavior]) -> Result<(), String> {
+        let behavior = behaviors
+            .iter()
+            .fold(CollectionBehavior::empty(), |all, b| all | flag(*b));
+        crate::windows::with_ns_window(window, move |ns_window| unsafe {
+            macos::set_window_collection_behavior(ns_window, behavior)
+        })
+    }
+
+    /// Called on the main thread
+    pub fn space(window: &WebviewWindow) -> Result<Space, String> {
+        let ns_window = window.ns_window().map_err(|e| e.to_string())?;
+        let ns_window = unsafe { &*ns_window.cast::<objc2::runtime::AnyObject>() };
+        let on_active_space: bool = unsafe { objc2::msg_send![ns_window, isOnActiveSpace] };
+        Ok(Space {
+            on_active_space,
+            desktop: None,
+        })
+    }
+
+    /// Spaces can only be chosen by the user
+    pub fn join_space(_window: &WebviewWindow, _with: &WebviewWindow) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The shell's virtual desktop manager, which only moves the app's own
+/// windows
+#[cfg(windows)]
+mod platform {
+    use super::{Behavior, Space};
+    use tauri::WebviewWindow;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+    use windows::Win32::UI::Shell::{IVirtualDesktopManager, VirtualDesktopManager};
+
+    pub fn apply(_window: &WebviewWindow, _behaviors: &[Behavior]) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn manager() -> Result<IVirtualDesktopManager, String> {
+        unsafe { CoCreateInstance(&VirtualDesktopManager, None, CLSCTX_ALL) }
+            .map_err(|e| e.message())
+    }
+
+    fn hwnd(window: &WebviewWindow) -> Result<HWND, String> {
+        window.hwnd().map_err(|e| e.to_string())
+    }
+
+    /// Called on the main thread, where COM is initialized
+    pub fn space(window: &WebviewWindow) -> Result<Space, String> {
+        let manager = manager()?;
+        let hwnd = hwnd(window)?;
+        unsafe {
+            Ok(Space {
+                on_active_space: manager
+                    .IsWindowOnCurrentVirtualDesktop(hwnd)
+                    .map_err(|e| e.message())?
+                    .as_bool(),
+                desktop: Some(format!(
+                    "{:?}",
+                    manager.GetWindowDesktopId(hwnd).map_err(|e| e.message())?
+                )),
+            })
+        }
+    }
+
+    /// Move `window` to the desktop `with` is on
+    pub fn join_space(window: &WebviewWindow, with: &WebviewWindow) -> Result<(), String> {
+        let manager = manager()?;
+        unsafe {
+            let desktop = manager
+                .GetWindowDesktopId(hwnd(with)?)
+                .map_err(|e| e.message())?;
+            manager
+                .MoveWindowToDesktop(hwnd(window)?, &desktop)
+                .map_err(|e| {
+                    format!(
+                        "Couldn't move the window to the other's desktop: {}",
+                        e.message()
+                    )
+                })
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::{Behavior, Space};
+    use tauri::WebviewWindow;
+
+    pub fn apply(_window: &WebviewWindow, _behaviors: &[Behavior]) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn space(_window: &WebviewWindow) -> Result<Space, String> {
+        Ok(Space {
+            on_active_space: true,
+            desktop: None,
+        })
+    }
+
+    pub fn join_space(_window: &WebviewWindow, _with: &WebviewWindow) -> Result<(), String> {
+        Ok(())
+    }
+}
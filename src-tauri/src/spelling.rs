@@ -0,0 +1,189 @@
+use serde::Serialize;
+use tao::spelling::{self, SpellingError};
+use tauri::{AppHandle, Manager, Webview};
+
+use crate::preferences;
+
+/// A misspelled word, placed the way JavaScript indexes strings
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Misspelling {
+    /// Where the word starts, in UTF-16 code units
+    pub start: usize,
+    /// In UTF-16 code units
+    pub length: usize,
+    pub word: String,
+    /// The likeliest first
+    pub suggestions: Vec<String>,
+}
+
+fn message(e: SpellingError) -> String {
+    match e {
+        SpellingError::Unsupported => {
+            "Spell checking is only available on macOS and Windows".to_string()
+        }
+        e => format!("Couldn't check spelling: {}", e),
+    }
+}
+
+fn language(language: Option<String>) -> Option<String> {
+    language.filter(|language| !language.is_empty())
+}
+
+/// The misspelled words in `text`, in `language` or the preferred one
+pub async fn check(text: String, language: Option<String>) -> Result<Vec<Misspelling>, String> {
+    let language = self::language(language);
+    tauri::async_runtime::spawn_blocking(move || {
+        let units: Vec<u16> = text.encode_utf16().collect();
+        let misspellings = spelling::check(&text, language.as_deref()).map_err(message)?;
+        Ok(misspellings
+            .into_iter()
+            .map(|misspelling| Misspelling {
+                word: String::from_utf16_lossy(
+                    units
+                        .get(misspelling.start..misspelling.start + misspelling.length)
+                        .unwrap_or_default(),
+                ),
+                start: misspelling.start,
+                length: misspelling.length,
+                suggestions: misspelling.suggestions,
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Replacements for `word`, the likeliest first
+pub async fn suggestions(word: String, language: Option<String>) -> Result<Vec<String>, String> {
+    let language = self::language(language);
+    tauri::async_runtime::spawn_blocking(move || {
+        spelling::suggestions(&word, language.as_deref()).map_err(message)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The languages there are dictionaries for, sorted
+pub async fn languages() -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let mut languages = spelling::languages().map_err(message)?;
+        languages.sort();
+        Ok(languages)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Check in the preferred language, on the main thread where WebKit's
+/// checking as the user types reads it
+fn apply_language(app: &AppHandle, preferences: &preferences::Spelling) {
+    let language = language(Some(preferences.language.clone()));
+    let _ = app.run_on_main_thread(move || {
+        if let Err(e) = spelling::set_language(language.as_deref()) {
+            if e != SpellingError::Unsupported {
+                log::warn!(error:% = e; "spelling language not applied");
+            }
+        }
+    });
+}
+
+fn apply(webview: &Webview, preferences: &preferences::Spelling) {
+    if let Err(e) = platform::apply(webview, preferences) {
+        log::warn!(label = webview.label(), error:% = e; "text substitutions not applied");
+    }
+}
+
+/// Apply the preferences to a webview starting to load, when any are set
+pub fn page_load_started(webview: &Webview) {
+    let preferences = preferences::current(webview.app_handle());
+    if preferences.spelling != preferences::Spelling::default() {
+        apply(webview, &preferences.spelling);
+    }
+}
+
+/// Apply the language now, and the preferences to every web view again
+/// whenever they change
+pub fn watch(app: &AppHandle) {
+    let preferences = preferences::current(app);
+    if !preferences.spelling.language.is_empty() {
+        apply_language(app, &preferences.spelling);
+    }
+    preferences::on_change(app, |preferences| preferences.spelling.clone(), {
+        let app = app.clone();
+        move |preferences| {
+            apply_language(&app, &preferences.spelling);
+            for window in app.webview_windows().values() {
+                apply(window.as_ref(), &preferences.spelling);
+            }
+        }
+    });
+}
+
+/// WKWebView's checking and substitutions as the user types, which it keeps
+/// for every web view in the app. Their setters aren't in WebKit's headers
+/// but are the ones the Edit menu's Spelling and Substitutions items use, so
+/// each is only sent when the web view answers to it.
+#[cfg(target_os = "macos")]
+mod platform {
+    use crate::preferences;
+    use objc2::runtime::NSObjectProtocol;
+    use objc2::{msg_send, sel};
+    use objc2_web_kit::WKWebView;
+    use tauri::Webview;
+
+    macro_rules! set {
+        ($webview:ident, $setter:ident, $enabled:expr) => {
+            if let Some(enabled) = $enabled {
+                if $webview.respondsToSelector(sel!($setter:)) {
+                    let _: () = unsafe { msg_send![$webview, $setter: enabled] };
+                }
+            }
+        };
+    }
+
+    pub fn apply(webview: &Webview, preferences: &preferences::Spelling) -> Result<(), String> {
+        let preferences = preferences.clone();
+        webview
+            .with_webview(move |webview| {
+                let webview = unsafe { &*webview.inner().cast::<WKWebView>() };
+                set!(
+                    webview,
+                    setContinuousSpellCheckingEnabled,
+                    preferences.check_while_typing
+                );
+                set!(
+                    webview,
+                    setAutomaticSpellingCorrectionEnabled,
+                    preferences.correct_automatically
+                );
+                set!(
+                    webview,
+                    setAutomaticQuoteSubstitutionEnabled,
+                    preferences.smart_quotes
+                );
+                set!(
+                    webview,
+                    setAutomaticDashSubstitutionEnabled,
+                    preferences.smart_dashes
+                );
+                set!(
+                    webview,
+                    setAutomaticTextReplacementEnabled,
+                    preferences.text_replacement
+                );
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// WebView2 has no settings for checking or substituting as the user types
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use crate::preferences;
+    use tauri::Webview;
+
+    pub fn apply(_webview: &Webview, _preferences: &preferences::Spelling) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::Url;
+
+use crate::{deep_link, Note, NoteMetadata};
+
+/// Spotlight domain the notes folder's notes are indexed under, so they can
+/// be dropped together when the folder changes
+pub const NOTES_DOMAIN: &str = "notes";
+
+/// Something to find in Spotlight. Choosing it opens `url`, which should be
+/// a smudge:// or universal link so it reaches `deep_link`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub struct Item {
+    pub url: String,
+    pub title: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// An image file shown with the result; it has to stay where it is
+    pub thumbnail: Option<String>,
+    /// Groups items for `remove_domain`
+    pub domain: Option<String>,
+}
+
+/// The link a note is indexed under, `smudge://notes/<id>`
+pub fn note_url(id: &str) -> Url {
+    let mut url = Url::parse(&format!("{}://notes", deep_link::SCHEME)).expect("valid base URL");
+    url.path_segments_mut()
+        .expect("URL with a host has path segments")
+        .push(id);
+    url
+}
+
+fn note_item(id: &str, title: &str, preview: &str) -> Item {
+    Item {
+        url: note_url(id).to_string(),
+        title: title.to_string(),
+        description: (!preview.is_empty()).then(|| preview.to_string()),
+        keywords: Vec::new(),
+        thumbnail: None,
+        domain: Some(NOTES_DOMAIN.to_string()),
+    }
+}
+
+/// Which notes are in Spotlight and as of when they were modified, so
+/// listing the notes only reindexes what changed
+#[derive(Default)]
+pub struct NoteIndex(Mutex<HashMap<String, i64>>);
+
+impl NoteIndex {
+    /// Bring the index up to date with the notes folder's listing
+    pub fn sync(&self, notes: &[NoteMetadata]) {
+        let (changed, removed) = {
+            let mut indexed = self.0.lock().expect("spotlight index mutex");
+            let changed: Vec<Item> = notes
+                .iter()
+                .filter(|note| indexed.get(&note.id) != Some(&note.modified))
+                .map(|note| note_item(&note.id, &note.title, &note.preview))
+                .collect();
+            let listed: HashMap<String, i64> = notes
+                .iter()
+                .map(|note| (note.id.clone(), note.modified))
+                .collect();
+            let removed: Vec<String> = indexed
+                .keys()
+                .filter(|id| !listed.contains_key(*id))
+                .map(|id| note_url(id).to_string())
+                .collect();
+            *indexed = listed;
+            (changed, removed)
+        };
+        log_failure(index(changed));
+        log_failure(remove(removed));
+    }
+
+    /// Index a note that was just written, dropping the entry it was renamed
+    /// from
+    pub fn saved(&self, note: &Note, preview: &str, renamed_from: Option<&str>) {
+        {
+            let mut indexed = self.0.lock().expect("spotlight index mutex");
+            if let Some(old_id) = renamed_from {
+                indexed.remove(old_id);
+            }
+            indexed.insert(note.id.clone(), note.modified);
+        }
+        if let Some(old_id) = renamed_from {
+            log_failure(remove(vec![note_url(old_id).to_string()]));
+        }
+        log_failure(index(vec![note_item(&note.id, &note.title, preview)]));
+    }
+
+    pub fn deleted(&self, id: &str) {
+        self.0.lock().expect("spotlight index mutex").remove(id);
+        log_failure(remove(vec![note_url(id).to_string()]));
+    }
+
+    /// Forget the previous notes folder's notes
+    pub fn clear(&self) {
+        self.0.lock().expect("spotlight index mutex").clear();
+        log_failure(remove_domain(NOTES_DOMAIN));
+    }
+}
+
+fn log_failure(result: Result<(), String>) {
+    if let Err(e) = result {
+        log::debug!("Spotlight index not updated: {}", e);
+    }
+}
+
+/// Add `items` to Spotlight, replacing any with the same URLs. The index is
+/// written in the background. Does nothing off macOS.
+pub fn index(items: Vec<Item>) -> Result<(), String> {
+    platform::index(items)
+}
+
+/// Take the items with these URLs out of Spotlight
+pub fn remove(urls: Vec<String>) -> Result<(), String> {
+    platform::remove(urls)
+}
+
+/// Take every item in `domain` out of Spotlight
+pub fn remove_domain(domain: &str) -> Result<(), String> {
+    platform::remove_domain(domain)
+}
+
+/// Core Spotlight through tao; tao turns a chosen result back into an opened
+/// URL
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::Item;
+    use std::path::PathBuf;
+    use tauri::Url;
+    use tauri_runtime_wry::tao::{platform::macos, spotlight::SearchableItem};
+
+    fn native_item(item: Item) -> Result<SearchableItem, String> {
+        let url = Url::parse(&item.url).map_err(|e| format!("{}: {}", item.url, e))?;
+        let mut native = SearchableItem::new(url, item.title);
+        native.description = item.description;
+        native.keywords = item.keywords;
+        native.thumbnail = item.thumbnail.map(PathBuf::from);
+        native.domain = item.domain;
+        native.content_type = Some("net.daringfireball.markdown".to_string());
+        Ok(native)
+    }
+
+    pub fn index(items: Vec<Item>) -> Result<(), String> {
+        let items = items
+            .into_iter()
+            .map(native_item)
+            .collect::<Result<Vec<_>, _>>()?;
+        macos::index_searchable_items(&items).map_err(|e| e.to_string())
+    }
+
+    pub fn remove(urls: Vec<String>) -> Result<(), String> {
+        let urls = urls
+            .iter()
+            .map(|url| Url::parse(url).map_err(|e| format!("{}: {}", url, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        macos::delete_searchable_items(&urls).map_err(|e| e.to_string())
+    }
+
+    pub fn remove_domain(domain: &str) -> Result<(), String> {
+        macos::delete_searchable_domains(&[domain.to_string()]).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::Item;
+
+    pub fn index(_items: Vec<Item>) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn remove(_urls: Vec<String>) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn remove_domain(_domain: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Whole launch, from entering `run` to the frontend's first paint
+pub const LAUNCH: &str = "launch";
+/// Until the OS says the app finished launching (`applicationDidFinishLaunching`
+/// on macOS)
+pub const NATIVE: &str = "native";
+/// The setup hook: config, settings, search index, window placement
+pub const SETUP: &str = "setup";
+/// The main webview loading the frontend
+pub const WEBVIEW: &str = "webview";
+/// From the main webview starting to load to React's first frame on screen
+pub const FIRST_PAINT: &str = "firstPaint";
+
+/// Times since launch are milliseconds
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupSpan {
+    pub name: &'static str,
+    pub start_ms: f64,
+    /// None while the phase is still running
+    pub end_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupReport {
+    pub spans: Vec<StartupSpan>,
+    /// Launch to first paint, once the frontend has painted
+    pub total_ms: Option<f64>,
+}
+
+struct Span {
+    name: &'static str,
+    start: Duration,
+    end: Option<Duration>,
+}
+
+/// Spans recorded once per launch. Later reloads of the webview don't
+/// restart them.
+pub struct StartupTimeline {
+    origin: Instant,
+    spans: Mutex<Vec<Span>>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl StartupTimeline {
+    /// Call first thing in `run`; the launch and native spans start here
+    pub fn begin() -> Self {
+        let timeline = Self {
+            origin: Instant::now(),
+            spans: Mutex::new(Vec::new()),
+        };
+        timeline.start(LAUNCH);
+        timeline.start(NATIVE);
+        timeline
+    }
+
+    pub fn start(&self, name: &'static str) {
+        let mut spans = self.spans.lock().expect("startup timeline mutex");
+        if !spans.iter().any(|span| span.name == name) {
+            spans.push(Span {
+                name,
+                start: self.origin.elapsed(),
+                end: None,
+            });
+        }
+    }
+
+    pub fn finish(&self, name: &'static str) {
+        let mut spans = self.spans.lock().expect("startup timeline mutex");
+        if let Some(span) = spans.iter_mut().find(|s| s.name == name && s.end.is_none()) {
+            span.end = Some(self.origin.elapsed());
+        }
+    }
+
+    pub fn report(&self) -> StartupReport {
+        let spans = self.spans.lock().expect("startup timeline mutex");
+        let total_ms = spans
+            .iter()
+            .find(|span| span.name == LAUNCH)
+            .and_then(|span| span.end)
+            .map(millis);
+        StartupReport {
+            spans: spans
+                .iter()
+                .map(|span| StartupSpan {
+                    name: span.name,
+                    start_ms: millis(span.start),
+                    end_ms: span.end.map(millis),
+                })
+                .collect(),
+            total_ms,
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, LogicalPosition, LogicalSize, Manager, WebviewWindow};
+use tauri_runtime_wry::tao::event::RestorableState;
+
+use crate::{windows, FrontendReady};
+
+/// Signals the main window that the state it saved is waiting in
+/// `take_restored_state`
+pub const RESTORED_EVENT: &str = "state-restored";
+
+/// What the main window saved for itself when the app last quit, until it
+/// takes it
+#[derive(Default)]
+pub struct RestoredState(Mutex<Option<String>>);
+
+impl RestoredState {
+    pub fn take(&self) -> Option<String> {
+        self.0.lock().expect("restored state mutex").take()
+    }
+
+    /// What the main window saved in the window layout, for when AppKit
+    /// doesn't restore it; state AppKit restores later replaces it
+    pub fn seed(&self, state: Option<String>) {
+        *self.0.lock().expect("restored state mutex") = state;
+    }
+}
+
+/// Turn saving window state on or off, and make the restorable windows known
+/// to AppKit. No-op off macOS.
+pub fn apply(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    app.run_on_main_thread(move || {
+        tauri_runtime_wry::tao::platform::macos::set_state_restoration_enabled(enabled)
+    })
+    .map_err(|e| e.to_string())?;
+    #[cfg(not(target_os = "macos"))]
+    let _ = enabled;
+    for window in app.webview_windows().into_values() {
+        identify(&window)?;
+    }
+    Ok(())
+}
+
+/// Make `window` known to AppKit under its label, if it's one the window
+/// manager keeps. No-op off macOS.
+pub fn identify(window: &WebviewWindow) -> Result<(), String> {
+    if !windows::is_managed(window.label()) {
+        return Ok(());
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let label = window.label().to_string();
+        windows::with_ns_window(window, move |ns_window| unsafe {
+            tauri_runtime_wry::tao::platform::macos::set_window_restoration_identifier(
+                ns_window,
+                Some(&label),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Save `state` with `window` for the next launch, replacing what it saved
+/// before. No-op off macOS.
+pub fn save(window: &WebviewWindow, state: Option<String>) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        windows::with_ns_window(window, move |ns_window| unsafe {
+            tauri_runtime_wry::tao::platform::macos::set_window_restorable_state(
+                ns_window,
+                state.as_deref(),
+            )
+        })?;
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (window, state);
+    Ok(())
+}
+
+/// Put the window saved as `identifier` back where it was, opening it if
+/// it's a note window the saved layout lost, then hand its own state to its
+/// frontend, now if it's listening or once it has loaded
+pub fn restore(app: &AppHandle, identifier: &str, state: RestorableState) {
+    if !windows::is_managed(identifier) {
+        return;
+    }
+    let window = match app.get_webview_window(identifier) {
+        Some(window) => window,
+        None => match windows::reopen(app, identifier) {
+            Some(window) => window,
+            None => return,
+        },
+    };
+    let _ = window.set_position(LogicalPosition::new(state.position.x, state.position.y));
+    let _ = window.set_size(LogicalSize::new(
+        state.inner_size.width,
+        state.inner_size.height,
+    ));
+    if state.fullscreen {
+        let _ = window.set_fullscreen(true);
+    }
+
+    let Some(user_state) = state.user_state else {
+        return;
+    };
+    // Note windows read their state from the layout whenever they load
+    if identifier != "main" {
+        if let Some(windows) = app.try_state::<windows::Windows>() {
+            windows.set_state(identifier, Some(user_state));
+        }
+        let _ = window.emit_to(identifier, RESTORED_EVENT, ());
+        return;
+    }
+    if let Some(restored) = app.try_state::<RestoredState>() {
+        *restored.0.lock().expect("restored state mutex") = Some(user_state);
+    }
+    let frontend_ready = app
+        .try_state::<FrontendReady>()
+        .map(|s| *s.0.lock().expect("frontend ready mutex"))
+        .unwrap_or(false);
+    if frontend_ready {
+        let _ = window.emit(RESTORED_EVENT, ());
+    }
+}
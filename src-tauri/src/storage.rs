@@ -0,0 +1,459 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// How long opening a placeholder waits for its content before giving up
+pub const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+const DOWNLOAD_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Where the notes folder lives, as far as smudge can tell
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProviderKind {
+    Local,
+    ICloudDrive,
+    OneDrive,
+    WebDav,
+}
+
+/// Provider info reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageInfo {
+    pub kind: ProviderKind,
+    pub name: String,
+    pub supports_eviction: bool,
+}
+
+/// A synced folder backend. Cloud providers can replace file content with
+/// placeholders ("dataless" files) that must be downloaded before reading;
+/// reading one directly either blocks on the network or returns nothing.
+pub trait StorageProvider: Send + Sync {
+    fn kind(&self) -> ProviderKind;
+
+    fn name(&self) -> &'static str;
+
+    /// Whether the file's content is not on this device
+    fn is_placeholder(&self, path: &Path) -> bool;
+
+    /// Ask the provider to bring the file's content on-device. Returns
+    /// immediately; the file watcher sees the file change once it arrives.
+    fn request_download(&self, path: &Path) -> Result<(), String>;
+
+    /// Free local space for the file, keeping it in the cloud
+    fn evict(&self, path: &Path) -> Result<(), String>;
+
+    fn supports_eviction(&self) -> bool {
+        false
+    }
+
+    fn info(&self) -> StorageInfo {
+        StorageInfo {
+            kind: self.kind(),
+            name: self.name().to_string(),
+            supports_eviction: self.supports_eviction(),
+        }
+    }
+}
+
+/// Pick the provider for a notes folder. Falls back to plain local storage.
+pub fn detect(folder: &Path) -> Box<dyn StorageProvider> {
+    if ICloudDrive::contains(folder) {
+        Box::new(ICloudDrive)
+    } else if OneDrive::contains(folder) {
+        Box::new(OneDrive)
+    } else if WebDav::contains(folder) {
+        Box::new(WebDav)
+    } else {
+        Box::new(Local)
+    }
+}
+
+/// Progress of a placeholder download, emitted while opening it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub path: String,
+    pub downloaded: Option<u64>, // None where the OS doesn't expose partial content
+    pub total: u64,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// Whether any cloud provider left `path` as a placeholder, regardless of
+/// which folder it's in (APFS dataless files, OneDrive/Cloud Files
+/// placeholders, iCloud `.icloud` stubs).
+pub fn is_placeholder(path: &Path) -> bool {
+    is_dataless(path)
+        || (cfg!(target_os = "macos")
+            && !path.exists()
+            && ICloudDrive::stub_path(path).is_some_and(|stub| stub.exists()))
+}
+
+/// Make sure `path`'s content is on disk before it's read, so a placeholder
+/// never reads as an empty file. Reports progress until the content arrives
+/// and fails with a readable error after `timeout`.
+pub async fn materialize(
+    path: &Path,
+    timeout: Duration,
+    on_progress: impl Fn(DownloadProgress),
+) -> Result<(), String> {
+    if !is_placeholder(path) {
+        return Ok(());
+    }
+
+    // Providers with a CLI get an explicit request; anything else (Dropbox,
+    // Google Drive, ...) materializes the file when it's read.
+    let provider = detect(path);
+    if provider.kind() == ProviderKind::Local || provider.request_download(path).is_err() {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            use std::io::Read;
+            let _ = std::fs::File::open(&path).and_then(|mut file| file.read(&mut [0u8; 1]));
+        });
+    }
+
+    let started = Instant::now();
+    loop {
+        if !is_placeholder(path) {
+            on_progress(download_progress(path, true));
+            return Ok(());
+        }
+        if started.elapsed() >= timeout {
+            let file_name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let source = match provider.kind() {
+                ProviderKind::Local => "its cloud storage app".to_string(),
+                _ => provider.name().to_string(),
+            };
+            let error = format!(
+                "Timed out downloading \"{}\" from {}. Check your connection and try again.",
+                file_name, source
+            );
+            on_progress(DownloadProgress {
+                done: true,
+                error: Some(error.clone()),
+                ..download_progress(path, false)
+            });
+            return Err(error);
+        }
+        on_progress(download_progress(path, false));
+        tokio::time::sleep(DOWNLOAD_POLL_INTERVAL).await;
+    }
+}
+
+fn download_progress(path: &Path, done: bool) -> DownloadProgress {
+    let metadata = std::fs::symlink_metadata(path).ok();
+    let total = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+    DownloadProgress {
+        path: path.to_string_lossy().into_owned(),
+        downloaded: if done {
+            Some(total)
+        } else {
+            metadata.as_ref().and_then(allocated_bytes).map(|b| b.min(total))
+        },
+        total,
+        done,
+        error: None,
+    }
+}
+
+// Blocks allocated on disk grow as a dataless file is filled in
+#[cfg(unix)]
+fn allocated_bytes(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.blocks() * 512)
+}
+
+#[cfg(not(unix))]
+fn allocated_bytes(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Older iCloud Drive versions replace `note.md` with a hidden `.note.md.icloud`
+/// stub. Returns the path the stub stands in for.
+pub fn icloud_stub_target(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let real_name = name.strip_prefix('.')?.strip_suffix(".icloud")?;
+    Some(path.with_file_name(real_name))
+}
+
+fn run_tool(program: &str, args: &[&str], path: &Path) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+pub struct Local;
+
+impl StorageProvider for Local {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Local
+    }
+
+    fn name(&self) -> &'static str {
+        "Local"
+    }
+
+    // Folders synced by apps without a CLI (Dropbox, Google Drive, ...) can
+    // still contain dataless files
+    fn is_placeholder(&self, path: &Path) -> bool {
+        is_dataless(path)
+    }
+
+    fn request_download(&self, _path: &Path) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn evict(&self, _path: &Path) -> Result<(), String> {
+        Err("Local folders can't be evicted".to_string())
+    }
+}
+
+pub struct ICloudDrive;
+
+impl ICloudDrive {
+    fn contains(path: &Path) -> bool {
+        if !cfg!(target_os = "macos") {
+            return false;
+        }
+        std::env::var("HOME")
+            .map(|home| path.starts_with(Path::new(&home).join("Library/Mobile Documents")))
+            .unwrap_or(false)
+    }
+
+    fn stub_path(path: &Path) -> Option<PathBuf> {
+        let name = path.file_name()?.to_str()?;
+        Some(path.with_file_name(format!(".{}.icloud", name)))
+    }
+}
+
+impl StorageProvider for ICloudDrive {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::ICloudDrive
+    }
+
+    fn name(&self) -> &'static str {
+        "iCloud Drive"
+    }
+
+    fn is_placeholder(&self, path: &Path) -> bool {
+        if icloud_stub_target(path).is_some() {
+            return true;
+        }
+        if Self::stub_path(path).is_some_and(|stub| stub.exists()) {
+            return true;
+        }
+        is_dataless(path)
+    }
+
+    fn request_download(&self, path: &Path) -> Result<(), String> {
+        let target = icloud_stub_target(path).unwrap_or_else(|| path.to_path_buf());
+        ubiquitous_item(&target, UbiquitousAction::Download)
+    }
+
+    fn evict(&self, path: &Path) -> Result<(), String> {
+        ubiquitous_item(path, UbiquitousAction::Evict)
+    }
+
+    fn supports_eviction(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Copy)]
+enum UbiquitousAction {
+    Download,
+    Evict,
+}
+
+/// Asks iCloud Drive to download or evict the item at `path` through
+/// `NSFileManager`, which starts either and returns without waiting for it
+#[cfg(target_os = "macos")]
+fn ubiquitous_item(path: &Path, action: UbiquitousAction) -> Result<(), String> {
+    use objc2_foundation::{NSFileManager, NSURL};
+
+    let url = NSURL::from_file_path(path).ok_or("Not a file path")?;
+    let manager = NSFileManager::defaultManager();
+    let result = match action {
+        UbiquitousAction::Download => manager.startDownloadingUbiquitousItemAtURL_error(&url),
+        UbiquitousAction::Evict => manager.evictUbiquitousItemAtURL_error(&url),
+    };
+    result.map_err(|e| e.localizedDescription().to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn ubiquitous_item(_path: &Path, _action: UbiquitousAction) -> Result<(), String> {
+    Err("iCloud Drive is only available on macOS".to_string())
+}
+
+pub struct OneDrive;
+
+impl OneDrive {
+    fn contains(path: &Path) -> bool {
+        if !cfg!(target_os = "windows") {
+            return false;
+        }
+        ["OneDrive", "OneDriveConsumer", "OneDriveCommercial"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .any(|root| !root.is_empty() && path.starts_with(root))
+    }
+}
+
+impl StorageProvider for OneDrive {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::OneDrive
+    }
+
+    fn name(&self) -> &'static str {
+        "OneDrive"
+    }
+
+    fn is_placeholder(&self, path: &Path) -> bool {
+        is_dataless(path)
+    }
+
+    // Pinning ("Always keep on this device") makes OneDrive hydrate the file
+    fn request_download(&self, path: &Path) -> Result<(), String> {
+        run_tool("attrib", &["-U", "+P"], path)
+    }
+
+    fn evict(&self, path: &Path) -> Result<(), String> {
+        run_tool("attrib", &["+U", "-P"], path)
+    }
+
+    fn supports_eviction(&self) -> bool {
+        true
+    }
+}
+
+/// A WebDAV share mounted by the OS. There are no placeholders, but every
+/// read goes over the network, so callers should expect slow or failed I/O.
+pub struct WebDav;
+
+impl WebDav {
+    fn contains(path: &Path) -> bool {
+        #[cfg(target_os = "windows")]
+        {
+            // Mapped WebDAV shares resolve to \\host@SSL\DavWWWRoot\...
+            path.to_string_lossy().contains("DavWWWRoot")
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            webdav_mount_points()
+                .iter()
+                .any(|mount_point| path.starts_with(mount_point))
+        }
+    }
+}
+
+impl StorageProvider for WebDav {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::WebDav
+    }
+
+    fn name(&self) -> &'static str {
+        "WebDAV"
+    }
+
+    fn is_placeholder(&self, _path: &Path) -> bool {
+        false
+    }
+
+    fn request_download(&self, _path: &Path) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn evict(&self, _path: &Path) -> Result<(), String> {
+        Err("WebDAV folders can't be evicted".to_string())
+    }
+}
+
+// Parse `mount` output for WebDAV filesystems:
+//   macOS: "https://host/ on /Volumes/dav (webdav, nodev, ...)"
+//   Linux: "https://host/ on /mnt/dav type davfs (rw, ...)", plus gvfs mounts
+#[cfg(not(target_os = "windows"))]
+fn webdav_mount_points() -> Vec<PathBuf> {
+    let mut mount_points: Vec<PathBuf> = Command::new("mount")
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| {
+                    let (_, rest) = line.split_once(" on ")?;
+                    if let Some((mount_point, fs_type)) = rest.split_once(" type ") {
+                        fs_type
+                            .starts_with("davfs")
+                            .then(|| PathBuf::from(mount_point))
+                    } else {
+                        let (mount_point, options) = rest.split_once(" (")?;
+                        options
+                            .starts_with("webdav")
+                            .then(|| PathBuf::from(mount_point))
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // GNOME mounts WebDAV through gvfs rather than a kernel filesystem
+    if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+        if let Ok(entries) = std::fs::read_dir(Path::new(&runtime_dir).join("gvfs")) {
+            mount_points.extend(entries.flatten().map(|e| e.path()).filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("dav:") || n.starts_with("davs:"))
+            }));
+        }
+    }
+
+    mount_points
+}
+
+// APFS marks files whose content lives only in the cloud with SF_DATALESS
+#[cfg(target_os = "macos")]
+fn is_dataless(path: &Path) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    const SF_DATALESS: u32 = 0x4000_0000;
+
+    std::fs::symlink_metadata(path)
+        .map(|m| m.st_flags() & SF_DATALESS != 0)
+        .unwrap_or(false)
+}
+
+// Cloud Files placeholders carry recall attributes until hydrated
+#[cfg(target_os = "windows")]
+fn is_dataless(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x4_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x40_0000;
+
+    std::fs::symlink_metadata(path)
+        .map(|m| {
+            m.file_attributes()
+                & (FILE_ATTRIBUTE_OFFLINE
+                    | FILE_ATTRIBUTE_RECALL_ON_OPEN
+                    | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+                != 0
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn is_dataless(_path: &Path) -> bool {
+    false
+}
@@ -0,0 +1,322 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, UriSchemeResponder};
+use tokio::sync::mpsc;
+
+use crate::{frontend_assets, logging, note_path, AppState};
+
+/// Scheme the frontend pulls chunks from, `stream://localhost/<id>`
+/// (`http://stream.localhost/<id>` on Windows). Also in the CSP's
+/// connect-src.
+pub const SCHEME: &str = "stream";
+
+/// Bytes read per chunk
+const CHUNK: usize = 256 * 1024;
+/// Chunks read ahead of the frontend. Past this the reader waits, so a
+/// stream never holds more than `CHUNK * WINDOW` bytes the frontend hasn't
+/// taken.
+const WINDOW: usize = 4;
+/// How far back from the end of the log a tail starts
+const TAIL_BYTES: u64 = 64 * 1024;
+/// How often a followed log is checked for new lines
+const FOLLOW_POLL: Duration = Duration::from_millis(250);
+
+/// What to stream
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Source {
+    /// A note's markdown
+    Note { id: String },
+    /// A file in the notes folder, e.g. an image under `assets/`
+    File { path: String },
+    /// The end of the log file, and with `follow` the lines written after it
+    /// until the stream is closed
+    Log {
+        #[serde(default)]
+        follow: bool,
+    },
+}
+
+/// An open stream, as `open_stream` returns it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamInfo {
+    pub id: String,
+    /// Bytes the stream will deliver, when that's known up front
+    pub size: Option<u64>,
+    pub content_type: &'static str,
+}
+
+type Chunk = Result<Vec<u8>, String>;
+
+struct Stream {
+    /// Window that opened it, whose closing ends it
+    window: String,
+    receiver: Arc<tokio::sync::Mutex<mpsc::Receiver<Chunk>>>,
+}
+
+/// Streams the frontend is reading. Each has a reader thread filling a
+/// bounded channel that the `stream` scheme drains one chunk per request.
+#[derive(Default)]
+pub struct Streams {
+    streams: Mutex<HashMap<String, Stream>>,
+    next_id: AtomicU64,
+}
+
+impl Streams {
+    /// Start reading `source` for `window`
+    pub fn open(
+        &self,
+        app: &AppHandle,
+        window: &str,
+        source: Source,
+    ) -> Result<StreamInfo, String> {
+        let (reader, size, content_type) = match &source {
+            Source::Note { id } => {
+                let path = note_path(&app.state::<AppState>(), id)?;
+                let (file, size) = open_file(&path)?;
+                (
+                    Reader::File(file),
+                    Some(size),
+                    "text/markdown; charset=utf-8",
+                )
+            }
+            Source::File { path } => {
+                let path = notes_folder_file(app, path)?;
+                let (file, size) = open_file(&path)?;
+                (
+                    Reader::File(file),
+                    Some(size),
+                    frontend_assets::content_type(&path.to_string_lossy()),
+                )
+            }
+            Source::Log { follow } => {
+                let path = logging::path().ok_or("The log file isn't open")?;
+                let (mut file, size) = open_file(&path)?;
+                let start = size.saturating_sub(TAIL_BYTES);
+                file.seek(SeekFrom::Start(start))
+                    .map_err(|e| e.to_string())?;
+                let reader = Reader::Log {
+                    file,
+                    path,
+                    offset: start,
+                    // Starting mid-line, drop the partial line
+                    skip_partial: start > 0,
+                    follow: *follow,
+                };
+                (reader, None, "text/plain; charset=utf-8")
+            }
+        };
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed).to_string();
+        let (sender, receiver) = mpsc::channel(WINDOW);
+        std::thread::Builder::new()
+            .name(format!("smudge-stream-{}", id))
+            .spawn(move || reader.run(&sender))
+            .map_err(|e| e.to_string())?;
+        self.streams.lock().expect("streams mutex").insert(
+            id.clone(),
+            Stream {
+                window: window.to_string(),
+                receiver: Arc::new(tokio::sync::Mutex::new(receiver)),
+            },
+        );
+        log::debug!(id = id.as_str(), source:? = source; "stream opened");
+        Ok(StreamInfo {
+            id,
+            size,
+            content_type,
+        })
+    }
+
+    /// Stop the stream. Its reader notices and stops on its next chunk.
+    pub fn close(&self, id: &str) {
+        self.streams.lock().expect("streams mutex").remove(id);
+    }
+
+    /// Close the streams `window` opened
+    pub fn window_closed(&self, window: &str) {
+        self.streams
+            .lock()
+            .expect("streams mutex")
+            .retain(|_, stream| stream.window != window);
+    }
+
+    fn receiver(&self, id: &str) -> Option<Arc<tokio::sync::Mutex<mpsc::Receiver<Chunk>>>> {
+        let streams = self.streams.lock().expect("streams mutex");
+        streams.get(id).map(|stream| stream.receiver.clone())
+    }
+
+    /// The stream's next chunk: 200 with the bytes, 204 once it's finished,
+    /// 500 with the message if reading failed. Waits for the reader when it
+    /// hasn't caught up.
+    async fn next(&self, id: &str) -> (StatusCode, Vec<u8>) {
+        let Some(receiver) = self.receiver(id) else {
+            return (
+                StatusCode::NOT_FOUND,
+                format!("No stream {}", id).into_bytes(),
+            );
+        };
+        let chunk = receiver.lock().await.recv().await;
+        match chunk {
+            Some(Ok(bytes)) => (StatusCode::OK, bytes),
+            Some(Err(message)) => {
+                self.close(id);
+                (StatusCode::INTERNAL_SERVER_ERROR, message.into_bytes())
+            }
+            None => {
+                self.close(id);
+                (StatusCode::NO_CONTENT, Vec::new())
+            }
+        }
+    }
+}
+
+/// Protocol handler for the `stream` scheme
+pub fn serve(app: &AppHandle, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let id = request.uri().path().trim_matches('/').to_string();
+        let (status, body) = match app.try_state::<Streams>() {
+            Some(streams) => streams.next(&id).await,
+            None => (StatusCode::SERVICE_UNAVAILABLE, Vec::new()),
+        };
+        let origin = request
+            .headers()
+            .get(header::ORIGIN)
+            .and_then(|origin| origin.to_str().ok())
+            .unwrap_or("*")
+            .to_string();
+        let response = Response::builder()
+            .status(status)
+            .header(header::CONTENT_TYPE, "application/octet-stream")
+            .header(header::CACHE_CONTROL, "no-store")
+            .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin)
+            .body(body)
+            .unwrap_or_default();
+        responder.respond(response);
+    });
+}
+
+fn open_file(path: &Path) -> Result<(File, u64), String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    Ok((file, size))
+}
+
+/// `path` in the notes folder, refusing anything that would leave it
+fn notes_folder_file(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(path);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(format!("{} isn't in the notes folder", path));
+    }
+    let state = app.state::<AppState>();
+    let app_config = state.app_config.read().expect("app_config read lock");
+    let folder = app_config
+        .notes_folder
+        .as_ref()
+        .ok_or("Notes folder not set")?;
+    Ok(PathBuf::from(folder).join(relative))
+}
+
+enum Reader {
+    File(File),
+    Log {
+        file: File,
+        path: PathBuf,
+        offset: u64,
+        skip_partial: bool,
+        follow: bool,
+    },
+}
+
+impl Reader {
+    /// Read chunks into `sender` until the source ends or the stream is
+    /// closed. Runs on its own thread, blocking while the channel is full.
+    fn run(self, sender: &mpsc::Sender<Chunk>) {
+        match self {
+            Reader::File(mut file) => loop {
+                match read_chunk(&mut file) {
+                    Ok(chunk) if chunk.is_empty() => return,
+                    Ok(chunk) => {
+                        if sender.blocking_send(Ok(chunk)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = sender.blocking_send(Err(e));
+                        return;
+                    }
+                }
+            },
+            Reader::Log {
+                mut file,
+                path,
+                mut offset,
+                mut skip_partial,
+                follow,
+            } => loop {
+                let mut chunk = match read_chunk(&mut file) {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        let _ = sender.blocking_send(Err(e));
+                        return;
+                    }
+                };
+                offset += chunk.len() as u64;
+                if skip_partial {
+                    match chunk.iter().position(|&b| b == b'\n') {
+                        Some(newline) => {
+                            chunk.drain(..=newline);
+                            skip_partial = false;
+                        }
+                        None => chunk.clear(),
+                    }
+                }
+                if !chunk.is_empty() {
+                    if sender.blocking_send(Ok(chunk)).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                if !follow || sender.is_closed() {
+                    return;
+                }
+                std::thread::sleep(FOLLOW_POLL);
+                // Rotated: carry on from the start of the new file
+                let len = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                if len < offset {
+                    match File::open(&path) {
+                        Ok(reopened) => {
+                            file = reopened;
+                            offset = 0;
+                        }
+                        Err(e) => {
+                            let _ = sender.blocking_send(Err(e.to_string()));
+                            return;
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+fn read_chunk(file: &mut File) -> Result<Vec<u8>, String> {
+    let mut chunk = Vec::with_capacity(CHUNK);
+    file.by_ref()
+        .take(CHUNK as u64)
+        .read_to_end(&mut chunk)
+        .map_err(|e| e.to_string())?;
+    Ok(chunk)
+}
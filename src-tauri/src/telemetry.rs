@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::{preferences, profiling};
+
+/// Where the metrics are kept between launches, under the app data
+/// directory
+const FILE_NAME: &str = "telemetry.json";
+/// How often changed metrics are written out
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+/// Upper bounds of the histograms' buckets in milliseconds; a last bucket
+/// takes everything slower
+const BUCKETS_MS: [f64; 14] = [
+    1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 30000.0,
+];
+
+/// Timings, in milliseconds, counted into buckets so they take the same
+/// space however many there are
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Histogram {
+    count: u64,
+    sum_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+    /// One more than `BUCKETS_MS`
+    buckets: Vec<u64>,
+}
+
+impl Histogram {
+    fn record(&mut self, ms: f64) {
+        if self.buckets.len() != BUCKETS_MS.len() + 1 {
+            self.buckets = vec![0; BUCKETS_MS.len() + 1];
+        }
+        let bucket = BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+        self.min_ms = if self.count == 0 {
+            ms
+        } else {
+            self.min_ms.min(ms)
+        };
+        self.max_ms = self.max_ms.max(ms);
+        self.sum_ms += ms;
+        self.count += 1;
+    }
+
+    /// The bound of the bucket the `q`th quantile falls in, no more than the
+    /// slowest timing
+    fn quantile(&self, q: f64) -> f64 {
+        let rank = (q * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = BUCKETS_MS.get(bucket).copied().unwrap_or(self.max_ms);
+                return bound.min(self.max_ms);
+            }
+        }
+        self.max_ms
+    }
+
+    fn summary(&self) -> Summary {
+        Summary {
+            count: self.count,
+            mean_ms: if self.count == 0 {
+                0.0
+            } else {
+                self.sum_ms / self.count as f64
+            },
+            min_ms: self.min_ms,
+            max_ms: self.max_ms,
+            p50_ms: self.quantile(0.5),
+            p90_ms: self.quantile(0.9),
+            p99_ms: self.quantile(0.99),
+        }
+    }
+}
+
+/// What's kept: counts and timings by name, never anything about notes or
+/// the person using the app
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Metrics {
+    /// Milliseconds since the Unix epoch of when counting began
+    since: u64,
+    counters: BTreeMap<String, u64>,
+    histograms: BTreeMap<String, Histogram>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    pub count: u64,
+    pub mean_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    /// Percentiles are the bucket bounds they fall under
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+}
+
+/// The metrics for the diagnostics pane
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub enabled: bool,
+    /// When counting began, in milliseconds since the Unix epoch; 0 when
+    /// nothing has been counted
+    pub since: u64,
+    pub counters: BTreeMap<String, u64>,
+    pub timings: BTreeMap<String, Summary>,
+}
+
+/// An exported report, with only what identifies the build
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Export {
+    app_version: String,
+    os: &'static str,
+    arch: &'static str,
+    created_at: u64,
+    report: Report,
+}
+
+/// Counters and timings, managed as Tauri state. Nothing is recorded
+/// unless `telemetry.enabled` is on in the preferences, and nothing leaves
+/// the machine but through `export`.
+pub struct Telemetry {
+    path: Option<PathBuf>,
+    metrics: Mutex<Metrics>,
+    dirty: AtomicBool,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn enabled(app: &AppHandle) -> bool {
+    preferences::current(app).telemetry.enabled
+}
+
+impl Telemetry {
+    fn load(app: &AppHandle) -> Self {
+        let path = app
+            .path()
+            .app_data_dir()
+            .ok()
+            .map(|dir| dir.join(FILE_NAME));
+        let metrics = path
+            .as_deref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            metrics: Mutex::new(metrics),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Metrics)) {
+        let mut metrics = self.metrics.lock().expect("telemetry mutex");
+        if metrics.since == 0 {
+            metrics.since = now_ms();
+        }
+        change(&mut metrics);
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Write the metrics out if they changed since the last time
+    fn flush(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let text = {
+            let metrics = self.metrics.lock().expect("telemetry mutex");
+            serde_json::to_string(&*metrics)
+        };
+        let written = text.map_err(|e| e.to_string()).and_then(|text| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            let staged = path.with_extension("json.part");
+            std::fs::write(&staged, text).map_err(|e| e.to_string())?;
+            std::fs::rename(&staged, path).map_err(|e| e.to_string())
+        });
+        if let Err(e) = written {
+            log::warn!(error:% = e; "telemetry not saved");
+        }
+    }
+
+    /// Forget everything counted, on disk too
+    fn clear(&self) {
+        *self.metrics.lock().expect("telemetry mutex") = Metrics::default();
+        self.dirty.store(false, Ordering::Relaxed);
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Manage the metrics, write them out now and then, and throw them away
+/// when telemetry is turned off
+pub fn start(app: &AppHandle) {
+    app.manage(Telemetry::load(app));
+    if !enabled(app) {
+        app.state::<Telemetry>().clear();
+    }
+    preferences::on_change(app, |preferences| preferences.telemetry.enabled, {
+        let app = app.clone();
+        move |preferences| {
+            if !preferences.telemetry.enabled {
+                app.state::<Telemetry>().clear();
+            }
+        }
+    });
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            app.state::<Telemetry>().flush();
+        }
+    });
+}
+
+/// Add one to the counter `name`
+pub fn count(app: &AppHandle, name: &str) {
+    let Some(telemetry) = app.try_state::<Telemetry>() else {
+        return;
+    };
+    if enabled(app) {
+        telemetry.update(|metrics| *metrics.counters.entry(name.to_string()).or_default() += 1);
+    }
+}
+
+/// Add a timing to the histogram `name`
+pub fn record(app: &AppHandle, name: &str, duration: Duration) {
+    let Some(telemetry) = app.try_state::<Telemetry>() else {
+        return;
+    };
+    if enabled(app) {
+        let ms = duration.as_secs_f64() * 1000.0;
+        telemetry.update(|metrics| {
+            metrics
+                .histograms
+                .entry(name.to_string())
+                .or_default()
+                .record(ms)
+        });
+    }
+}
+
+/// Await `future`, timing it into the histogram `name` and as a profiling
+/// interval
+pub async fn timed<T>(app: &AppHandle, name: &str, future: impl Future<Output = T>) -> T {
+    let started = Instant::now();
+    let interval = profiling::interval(profiling::Category::Timed, name);
+    let output = future.await;
+    drop(interval);
+    record(app, name, started.elapsed());
+    output
+}
+
+pub fn report(app: &AppHandle) -> Report {
+    let enabled = enabled(app);
+    let Some(telemetry) = app.try_state::<Telemetry>() else {
+        return Report {
+            enabled,
+            since: 0,
+            counters: BTreeMap::new(),
+            timings: BTreeMap::new(),
+        };
+    };
+    let metrics = telemetry.metrics.lock().expect("telemetry mutex");
+    Report {
+        enabled,
+        since: metrics.since,
+        counters: metrics.counters.clone(),
+        timings: metrics
+            .histograms
+            .iter()
+            .map(|(name, histogram)| (name.clone(), histogram.summary()))
+            .collect(),
+    }
+}
+
+/// Start counting again from nothing
+pub fn reset(app: &AppHandle) {
+    if let Some(telemetry) = app.try_state::<Telemetry>() {
+        telemetry.clear();
+    }
+}
+
+/// The report as JSON for a bug report. Metric names are fixed in the code,
+/// so it holds no paths, note titles or ids, and names the build but not
+/// the machine or the person.
+pub fn export_json(app: &AppHandle) -> Result<String, String> {
+    let export = Export {
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        created_at: now_ms(),
+        report: report(app),
+    };
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+/// Write the report for a bug report to `path`
+pub fn export(app: &AppHandle, path: &Path) -> Result<(), String> {
+    std::fs::write(path, export_json(app)?).map_err(|e| e.to_string())
+}
@@ -0,0 +1,175 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::i18n;
+
+/// Where the built-in templates are bundled, under the app's resources
+const RESOURCES_DIR: &str = "templates";
+/// Where the user's own templates live, under app data
+const USER_DIR: &str = "templates";
+const EXTENSION: &str = "md";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Source {
+    BuiltIn,
+    /// In the templates folder. One named like a built-in replaces it.
+    User,
+}
+
+/// A document template as the frontend lists it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    /// The file's name without its extension
+    pub id: String,
+    pub name: String,
+    pub source: Source,
+    /// The `{{variable}}`s in its content, in the order they first appear
+    pub variables: Vec<String>,
+}
+
+/// The templates folder in app data, made if it isn't there yet
+pub fn user_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join(USER_DIR);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+fn builtin_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resource_dir()
+        .map(|dir| dir.join(RESOURCES_DIR))
+        .map_err(|e| e.to_string())
+}
+
+/// Template files in `dir` by id. A folder that isn't there has none.
+fn files(dir: &Path) -> impl Iterator<Item = (String, PathBuf)> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if !path.is_file() || path.extension()? != EXTENSION {
+                return None;
+            }
+            let id = path.file_stem()?.to_str()?.to_string();
+            is_valid_id(&id).then_some((id, path))
+        })
+}
+
+/// Ids name files, so they can't reach outside the templates' folders
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == ' ')
+}
+
+/// Every template's file by id, the user's over the built-ins
+fn paths(app: &AppHandle) -> BTreeMap<String, (Source, PathBuf)> {
+    let mut paths = BTreeMap::new();
+    if let Ok(dir) = builtin_dir(app) {
+        paths.extend(files(&dir).map(|(id, path)| (id, (Source::BuiltIn, path))));
+    }
+    if let Ok(dir) = user_dir(app) {
+        paths.extend(files(&dir).map(|(id, path)| (id, (Source::User, path))));
+    }
+    paths
+}
+
+/// A built-in's name in the current language; a user template's file name,
+/// as it is
+fn name(app: &AppHandle, id: &str, source: Source) -> String {
+    if source == Source::BuiltIn {
+        let key = format!("template-{}", id);
+        let name = i18n::text(app, &key);
+        if name != key {
+            return name;
+        }
+    }
+    id.to_string()
+}
+
+/// The names in `{{…}}` in `content`, once each, in order
+fn variables(content: &str) -> Vec<String> {
+    let mut variables: Vec<String> = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim();
+        if !name.is_empty() && !variables.iter().any(|variable| variable == name) {
+            variables.push(name.to_string());
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    variables
+}
+
+/// `content` with each `{{variable}}` replaced by its value, or by nothing
+/// when `values` doesn't have it
+fn render(content: &str, values: &HashMap<String, String>) -> String {
+    let mut rendered = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + end].trim();
+        if let Some(value) = values.get(name) {
+            rendered.push_str(value);
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// The built-in and user templates, by name
+pub fn list(app: &AppHandle) -> Vec<Template> {
+    let mut templates: Vec<Template> = paths(app)
+        .into_iter()
+        .filter_map(|(id, (source, path))| {
+            let content = std::fs::read_to_string(&path).ok()?;
+            Some(Template {
+                name: name(app, &id, source),
+                variables: variables(&content),
+                id,
+                source,
+            })
+        })
+        .collect();
+    templates.sort_by_key(|template| template.name.to_lowercase());
+    templates
+}
+
+/// The content of the template `id` with `values` filled in. A `title` left
+/// out is the localized "Untitled".
+pub fn instantiate(
+    app: &AppHandle,
+    id: &str,
+    values: &HashMap<String, String>,
+) -> Result<String, String> {
+    if !is_valid_id(id) {
+        return Err(format!("\"{}\" isn't a template's name", id));
+    }
+    let (_, path) = paths(app)
+        .remove(id)
+        .ok_or_else(|| format!("There's no template named {}", id))?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read the template: {}", e))?;
+    let mut values = values.clone();
+    values
+        .entry("title".to_string())
+        .or_insert_with(|| i18n::text(app, "note-untitled"));
+    Ok(render(&content, &values))
+}
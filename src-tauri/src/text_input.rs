@@ -0,0 +1,111 @@
+use serde::Serialize;
+#[cfg(target_os = "macos")]
+use tauri::Manager;
+use tauri::{AppHandle, Emitter, Webview, WebviewWindow};
+
+/// Sent to a window when an input method starts or finishes composing text
+/// in it
+pub const COMPOSITION_EVENT: &str = "composition-changed";
+
+/// Reports the page's composition events to `composition_changed`. Added
+/// once a page has loaded; composing can't start before there's a page to
+/// type in.
+const COMPOSITION_SCRIPT: &str = r#"(() => {
+  if (window.__smudgeComposition) return;
+  window.__smudgeComposition = true;
+  const report = (composing) =>
+    window.__TAURI_INTERNALS__.invoke("composition_changed", { composing });
+  addEventListener("compositionstart", () => report(true), true);
+  addEventListener("compositionend", () => report(false), true);
+})();"#;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Composition {
+    /// Marked text is being composed; the editor may want to keep what's
+    /// around it still until it's committed
+    pub composing: bool,
+}
+
+/// Run `f` on the main thread, which AppKit's text input needs
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+async fn on_main_thread<T: Send + 'static>(
+    app: &AppHandle,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.run_on_main_thread(move || {
+        let _ = tx.send(f());
+    })
+    .map_err(|e| e.to_string())?;
+    rx.await
+        .map_err(|_| "The main thread dropped the call".to_string())
+}
+
+/// Bring `window` to the front so what's typed lands in its focused field
+fn focus(window: &WebviewWindow) -> Result<(), String> {
+    let _ = window.unminimize();
+    window.show().map_err(|e| e.to_string())?;
+    window.set_focus().map_err(|e| e.to_string())
+}
+
+/// Open the system's emoji and symbols picker for `window`'s focused field
+pub async fn show_character_palette(window: &WebviewWindow) -> Result<(), String> {
+    focus(window)?;
+    #[cfg(target_os = "macos")]
+    {
+        on_main_thread(window.app_handle(), || {
+            tauri_runtime_wry::tao::platform::macos::show_character_palette()
+        })
+        .await
+    }
+    #[cfg(windows)]
+    {
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?.0 as isize;
+        tauri_runtime_wry::tao::platform::windows::show_character_palette(hwnd)
+            .map_err(|e| format!("Couldn't open the emoji panel: {}", e))
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        Err("The character palette is only available on macOS and Windows".to_string())
+    }
+}
+
+/// Start dictating into `window`'s focused field
+pub async fn start_dictation(window: &WebviewWindow) -> Result<(), String> {
+    focus(window)?;
+    #[cfg(target_os = "macos")]
+    {
+        let started = on_main_thread(window.app_handle(), || {
+            tauri_runtime_wry::tao::platform::macos::start_dictation()
+        })
+        .await?;
+        if started {
+            Ok(())
+        } else {
+            Err("Dictation is off, or nothing is focused to dictate into".to_string())
+        }
+    }
+    #[cfg(windows)]
+    {
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?.0 as isize;
+        tauri_runtime_wry::tao::platform::windows::start_dictation(hwnd)
+            .map_err(|e| format!("Couldn't start voice typing: {}", e))
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        Err("Dictation is only available on macOS and Windows".to_string())
+    }
+}
+
+/// Watch for composing in a page that finished loading
+pub fn page_load_finished(webview: &Webview) {
+    if let Err(e) = webview.eval(COMPOSITION_SCRIPT) {
+        log::warn!(label = webview.label(), error:% = e; "composition events not watched");
+    }
+}
+
+/// The page started or finished composing; tell its window
+pub fn composition_changed(window: &WebviewWindow, composing: bool) {
+    let _ = window.emit_to(window.label(), COMPOSITION_EVENT, Composition { composing });
+}
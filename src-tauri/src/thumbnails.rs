@@ -0,0 +1,209 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tao::thumbnail::ThumbnailError;
+use tauri::{AppHandle, Manager};
+
+/// Sizes thumbnails are drawn at, in pixels along the longer side. Asked-for
+/// sizes are rounded up to one of these so a few cached images serve every
+/// layout.
+const SIZES: [u32; 4] = [128, 256, 512, 1024];
+/// What a file's content hash was taken of, so it's only read again once it
+/// changes
+#[derive(Clone, PartialEq, Eq)]
+struct Stamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+/// A PNG thumbnail and the ETag it's served with
+pub struct Thumbnail {
+    pub png: Vec<u8>,
+    pub etag: String,
+}
+
+/// Thumbnails of images, PDFs and videos' first frames, drawn by the
+/// system's own generators (see `tao::thumbnail`) and cached on disk by the
+/// hash of the file's contents, so a renamed or copied file keeps its
+/// thumbnail and an edited one gets a new one. The webview loads them from
+/// `smudge://asset/thumbnail/<size>/<path>`.
+pub struct Thumbnails {
+    /// `<app cache>/thumbnails`, or `None` without a cache folder, when
+    /// thumbnails are drawn every time
+    dir: Option<PathBuf>,
+    /// Content hashes of files already read, until they change
+    hashes: Mutex<HashMap<PathBuf, (Stamp, String)>>,
+    /// Thumbnails that couldn't be drawn, so they aren't tried again this run
+    missing: Mutex<HashSet<String>>,
+    /// Names cache files are written under before they're complete
+    next_temp: AtomicU64,
+}
+
+impl Thumbnails {
+    /// Opens the cache and trims it in the background
+    pub fn open(app: &AppHandle) -> Self {
+        let dir = app
+            .path()
+            .app_cache_dir()
+            .map(|dir| dir.join("thumbnails"))
+            .ok()
+            .filter(|dir| match fs::create_dir_all(dir) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!(error:% = e; "thumbnail cache not created");
+                    false
+                }
+            });
+        if let Some(dir) = dir.clone() {
+            let max_bytes = crate::preferences::current(app).thumbnails.cache_mb * 1024 * 1024;
+            tauri::async_runtime::spawn_blocking(move || prune(&dir, max_bytes));
+        }
+        Self {
+            dir,
+            hashes: Mutex::new(HashMap::new()),
+            missing: Mutex::new(HashSet::new()),
+            next_temp: AtomicU64::new(0),
+        }
+    }
+
+    /// The thumbnail of the file at `path` fitting in `size` pixels, rounded
+    /// up to a size thumbnails are drawn at. `None` when there's no
+    /// thumbnail for its type. Drawing one can take a while, so this runs
+    /// off the main thread.
+    pub fn get(&self, path: &Path, size: u32) -> Result<Option<Thumbnail>, String> {
+        let size = SIZES
+            .iter()
+            .copied()
+            .find(|&bucket| bucket >= size)
+            .unwrap_or(SIZES[SIZES.len() - 1]);
+        let hash = self
+            .content_hash(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let key = format!("{}-{}", hash, size);
+        let etag = format!("\"{}\"", key);
+
+        if self
+            .missing
+            .lock()
+            .expect("missing thumbnails mutex")
+            .contains(&key)
+        {
+            return Ok(None);
+        }
+        let cached = self
+            .dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.png", key)));
+        if let Some(png) = cached.as_ref().and_then(|cached| fs::read(cached).ok()) {
+            return Ok(Some(Thumbnail { png, etag }));
+        }
+
+        let png = match tao::thumbnail::generate(path, size) {
+            Ok(png) => png,
+            Err(ThumbnailError::NoThumbnail | ThumbnailError::Unsupported) => {
+                self.missing
+                    .lock()
+                    .expect("missing thumbnails mutex")
+                    .insert(key);
+                return Ok(None);
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        if let Some(cached) = cached {
+            // Written aside and renamed, so a concurrent request never reads
+            // half a file
+            let temp = cached.with_extension(format!(
+                "{}.tmp",
+                self.next_temp.fetch_add(1, Ordering::Relaxed)
+            ));
+            if let Err(e) = fs::write(&temp, &png).and_then(|()| fs::rename(&temp, &cached)) {
+                log::warn!(error:% = e; "thumbnail not cached");
+                let _ = fs::remove_file(&temp);
+            }
+        }
+        Ok(Some(Thumbnail { png, etag }))
+    }
+
+    /// Forget the content hashes and missing thumbnails remembered this run.
+    /// The disk cache stays; files are hashed again as they're drawn.
+    pub fn release_memory(&self) {
+        self.hashes.lock().expect("thumbnail hashes mutex").clear();
+        self.missing
+            .lock()
+            .expect("missing thumbnails mutex")
+            .clear();
+    }
+
+    /// Hash of the file's contents and length, read only when it's changed
+    /// since the last time
+    fn content_hash(&self, path: &Path) -> io::Result<String> {
+        let metadata = fs::metadata(path)?;
+        let stamp = Stamp {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        };
+        if let Some((known, hash)) = self
+            .hashes
+            .lock()
+            .expect("thumbnail hashes mutex")
+            .get(path)
+        {
+            if *known == stamp {
+                return Ok(hash.clone());
+            }
+        }
+
+        let mut file = File::open(path)?;
+        let mut hasher = DefaultHasher::new();
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.write(&buffer[..read]);
+        }
+        let hash = format!("{:016x}{:x}", hasher.finish(), stamp.len);
+        self.hashes
+            .lock()
+            .expect("thumbnail hashes mutex")
+            .insert(path.to_path_buf(), (stamp, hash.clone()));
+        Ok(hash)
+    }
+}
+
+/// Deletes the least recently drawn thumbnails until the cache fits in
+/// `max_bytes`, and whatever a crash left half written
+fn prune(dir: &Path, max_bytes: u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "tmp") {
+            let _ = fs::remove_file(&path);
+            continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((modified, metadata.len(), path));
+        }
+    }
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort();
+    for (_, len, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
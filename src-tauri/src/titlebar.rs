@@ -0,0 +1,377 @@
+use serde::{Deserialize, Serialize};
+use tauri::{LogicalPosition, WebviewWindow};
+
+/// Sent to a window with a `ButtonPressed` when a button in one of its title
+/// bar accessories is clicked
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub const BUTTON_EVENT: &str = "titlebar-button";
+
+/// Height of the frontend-drawn titlebar strip, in logical pixels. Matches
+/// the `h-11` drag regions at the top of the sidebar and editor.
+pub const HEIGHT: f64 = 44.0;
+
+/// Traffic light position from tauri.conf.json, in logical pixels
+#[cfg(target_os = "macos")]
+const TRAFFIC_LIGHT_POSITION: (f64, f64) = (16.0, 24.0);
+
+/// Width reserved for the three traffic lights past their x position
+#[cfg(target_os = "macos")]
+const TRAFFIC_LIGHTS_WIDTH: f64 = 56.0;
+
+/// Width of each of the frontend-drawn minimize/maximize/close buttons
+#[cfg(not(target_os = "macos"))]
+const CAPTION_BUTTON_WIDTH: f64 = 46.0;
+#[cfg(not(target_os = "macos"))]
+const CAPTION_BUTTONS_WIDTH: f64 = 3.0 * CAPTION_BUTTON_WIDTH;
+
+/// Whether the OS or the frontend draws the titlebar
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TitlebarMode {
+    Native,
+    Custom,
+}
+
+impl Default for TitlebarMode {
+    // macOS has always shipped with the overlay titlebar
+    fn default() -> Self {
+        if cfg!(target_os = "macos") {
+            TitlebarMode::Custom
+        } else {
+            TitlebarMode::Native
+        }
+    }
+}
+
+/// What the frontend needs to lay out its titlebar
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TitlebarInfo {
+    pub mode: TitlebarMode,
+    pub height: f64,
+    /// Space to leave clear for the window controls, in logical pixels:
+    /// on the left for the traffic lights, on the right for caption
+    /// buttons. Zero when the OS draws the titlebar.
+    pub controls_inset: f64,
+    /// Whether the frontend has to draw its own caption buttons
+    pub draws_controls: bool,
+}
+
+pub fn info(mode: TitlebarMode) -> TitlebarInfo {
+    #[cfg(target_os = "macos")]
+    let (controls_inset, draws_controls) = (TRAFFIC_LIGHT_POSITION.0 + TRAFFIC_LIGHTS_WIDTH, false);
+    #[cfg(not(target_os = "macos"))]
+    let (controls_inset, draws_controls) = (CAPTION_BUTTONS_WIDTH, true);
+
+    match mode {
+        TitlebarMode::Custom => TitlebarInfo {
+            mode,
+            height: HEIGHT,
+            controls_inset,
+            draws_controls,
+        },
+        TitlebarMode::Native => TitlebarInfo {
+            mode,
+            height: HEIGHT,
+            controls_inset: 0.0,
+            draws_controls: false,
+        },
+    }
+}
+
+/// Switch `window` between native and frontend-drawn decorations.
+///
+/// On macOS the custom mode keeps the native traffic lights over a
+/// transparent titlebar. Elsewhere the decorations are removed entirely:
+/// the runtime keeps native resize borders on undecorated windows, and
+/// dragging from a drag region goes through the window manager, so
+/// Aero Snap and tiling still apply.
+pub fn apply(window: &WebviewWindow, mode: TitlebarMode) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        use tauri::TitleBarStyle;
+
+        let style = match mode {
+            TitlebarMode::Custom => TitleBarStyle::Overlay,
+            TitlebarMode::Native => TitleBarStyle::Visible,
+        };
+        window
+            .set_title_bar_style(style)
+            .map_err(|e| e.to_string())?;
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let custom = mode == TitlebarMode::Custom;
+        window.set_decorations(!custom).map_err(|e| e.to_string())?;
+        // Keeps the drop shadow and the 1px border Windows 11 draws
+        // around undecorated windows
+        #[cfg(windows)]
+        window.set_shadow(custom).map_err(|e| e.to_string())?;
+        // Hovering the frontend's maximize button, left of close, brings up
+        // Windows 11's snap layouts as the system's button would
+        #[cfg(windows)]
+        {
+            use tauri_runtime_wry::tao::platform::windows::{self, MaximizeButton};
+
+            let button = custom.then_some(MaximizeButton {
+                right: CAPTION_BUTTON_WIDTH,
+                width: CAPTION_BUTTON_WIDTH,
+                height: HEIGHT,
+            });
+            let hwnd = window.hwnd().map_err(|e| e.to_string())?.0 as isize;
+            window
+                .run_on_main_thread(move || {
+                    if let Err(e) = windows::set_window_maximize_button(hwnd, button) {
+                        log::warn!(error:% = e; "snap layouts not set up");
+                    }
+                })
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// What double-clicking the titlebar does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+enum DoubleClickAction {
+    Zoom,
+    Minimize,
+    None,
+}
+
+/// Reads the "Double-click a window's title bar to" preference on macOS,
+/// once per launch. Other platforms always maximize.
+fn double_click_action() -> DoubleClickAction {
+    #[cfg(target_os = "macos")]
+    {
+        use objc2_foundation::{NSString, NSUserDefaults};
+        use std::sync::OnceLock;
+
+        static ACTION: OnceLock<DoubleClickAction> = OnceLock::new();
+        *ACTION.get_or_init(|| {
+            let key = NSString::from_str("AppleActionOnDoubleClick");
+            let value = NSUserDefaults::standardUserDefaults().stringForKey(&key);
+            match value.map(|value| value.to_string()).as_deref() {
+                Some("Minimize") => DoubleClickAction::Minimize,
+                Some("None") => DoubleClickAction::None,
+                _ => DoubleClickAction::Zoom,
+            }
+        })
+    }
+    #[cfg(not(target_os = "macos"))]
+    DoubleClickAction::Zoom
+}
+
+/// Handle a double-click on a frontend drag region the way the native
+/// titlebar would
+pub fn double_click(window: &WebviewWindow) -> Result<(), String> {
+    match double_click_action() {
+        DoubleClickAction::Zoom => {
+            if !window.is_resizable().map_err(|e| e.to_string())? {
+                return Ok(());
+            }
+            if window.is_maximized().map_err(|e| e.to_string())? {
+                window.unmaximize()
+            } else {
+                window.maximize()
+            }
+        }
+        DoubleClickAction::Minimize => window.minimize(),
+        DoubleClickAction::None => Ok(()),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Where a title bar accessory goes
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessoryPlacement {
+    /// After the traffic lights
+    #[default]
+    Leading,
+    Trailing,
+    /// Under the title bar, across the window
+    Bottom,
+}
+
+/// A native button in a title bar accessory
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessoryButton {
+    pub id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// An SF Symbol name, shown before the title or in place of it
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub tool_tip: Option<String>,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Buttons hosted in the native title bar, beside smudge's own toolbar
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Accessory {
+    #[serde(default)]
+    pub placement: AccessoryPlacement,
+    pub buttons: Vec<AccessoryButton>,
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ButtonPressed {
+    pub id: String,
+}
+
+/// Move `window`'s traffic lights so the close button's top left corner is
+/// at `position`, or leave them to the OS again with `None`. Only macOS has
+/// them; elsewhere nothing changes.
+pub fn set_traffic_lights(
+    window: &WebviewWindow,
+    position: Option<LogicalPosition<f64>>,
+) -> Result<(), String> {
+    platform::set_traffic_lights(window, position)
+}
+
+/// Hide `window`'s title while keeping its title bar and traffic lights
+/// (macOS)
+pub fn set_title_hidden(window: &WebviewWindow, hidden: bool) -> Result<(), String> {
+    platform::set_title_hidden(window, hidden)
+}
+
+/// Replace the accessories smudge added to `window`'s title bar. No
+/// accessories take them away.
+pub async fn set_accessories(
+    window: &WebviewWindow,
+    accessories: Vec<Accessory>,
+) -> Result<(), String> {
+    platform::set_accessories(window, accessories).await
+}
+
+/// Tell the window tao reported the button `id` of
+#[cfg(target_os = "macos")]
+pub fn button_pressed(
+    app: &tauri::AppHandle,
+    window_id: tauri_runtime_wry::tao::window::WindowId,
+    id: String,
+) {
+    use tauri::{Emitter, Manager};
+    use tauri_runtime_wry::tao::platform::macos;
+
+    let window = app.webview_windows().into_values().find(|window| {
+        window
+            .ns_window()
+            .is_ok_and(|ns_window| macos::window_id_for_ns_window(ns_window) == window_id)
+    });
+    if let Some(window) = window {
+        let _ = window.emit_to(window.label(), BUTTON_EVENT, ButtonPressed { id });
+    }
+}
+
+/// The title bar through tao
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Accessory, AccessoryButton, AccessoryPlacement};
+    use crate::windows::with_ns_window;
+    use tauri::{LogicalPosition, WebviewWindow};
+    use tauri_runtime_wry::tao::dpi;
+    use tauri_runtime_wry::tao::platform::macos::{
+        self, TitlebarAccessory, TitlebarAccessoryPlacement, TitlebarButton,
+    };
+
+    fn native_accessory(accessory: Accessory) -> TitlebarAccessory {
+        let placement = match accessory.placement {
+            AccessoryPlacement::Leading => TitlebarAccessoryPlacement::Leading,
+            AccessoryPlacement::Trailing => TitlebarAccessoryPlacement::Trailing,
+            AccessoryPlacement::Bottom => TitlebarAccessoryPlacement::Bottom,
+        };
+        let buttons = accessory
+            .buttons
+            .into_iter()
+            .map(
+                |AccessoryButton {
+                     id,
+                     title,
+                     symbol,
+                     tool_tip,
+                     enabled,
+                 }| TitlebarButton {
+                    id,
+                    title,
+                    symbol,
+                    tool_tip,
+                    enabled,
+                },
+            )
+            .collect();
+        TitlebarAccessory { placement, buttons }
+    }
+
+    pub fn set_traffic_lights(
+        window: &WebviewWindow,
+        position: Option<LogicalPosition<f64>>,
+    ) -> Result<(), String> {
+        let position = position.map(|p| dpi::LogicalPosition::new(p.x, p.y));
+        with_ns_window(window, move |ns_window| unsafe {
+            macos::set_window_traffic_light_position(ns_window, position)
+        })
+    }
+
+    pub fn set_title_hidden(window: &WebviewWindow, hidden: bool) -> Result<(), String> {
+        with_ns_window(window, move |ns_window| unsafe {
+            macos::set_window_title_hidden(ns_window, hidden)
+        })
+    }
+
+    pub async fn set_accessories(
+        window: &WebviewWindow,
+        accessories: Vec<Accessory>,
+    ) -> Result<(), String> {
+        let accessories: Vec<TitlebarAccessory> =
+            accessories.into_iter().map(native_accessory).collect();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        with_ns_window(window, move |ns_window| {
+            let _ =
+                tx.send(unsafe { macos::set_window_titlebar_accessories(ns_window, &accessories) });
+        })?;
+        rx.await
+            .map_err(|_| "The title bar accessories weren't set".to_string())?
+            .map_err(|e| format!("Couldn't set the title bar accessories: {}", e))
+    }
+}
+
+/// There are no traffic lights or accessories; succeeding keeps callers
+/// simple
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::Accessory;
+    use tauri::{LogicalPosition, WebviewWindow};
+
+    pub fn set_traffic_lights(
+        _window: &WebviewWindow,
+        _position: Option<LogicalPosition<f64>>,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn set_title_hidden(_window: &WebviewWindow, _hidden: bool) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub async fn set_accessories(
+        _window: &WebviewWindow,
+        _accessories: Vec<Accessory>,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use tauri::WebviewWindow;
+
+/// Sent to a window with the `Action` when a control on its Touch Bar is
+/// used
+pub const EVENT: &str = "touch-bar";
+
+/// A Touch Bar control as the frontend describes it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TouchBarItem {
+    Button {
+        id: String,
+        title: String,
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+    },
+    Slider {
+        id: String,
+        #[serde(default)]
+        label: Option<String>,
+        value: f64,
+        #[serde(default)]
+        min: f64,
+        #[serde(default = "slider_max")]
+        max: f64,
+    },
+    /// Buttons of which one is selected at a time
+    Segmented {
+        id: String,
+        labels: Vec<String>,
+        #[serde(default)]
+        selected: Option<usize>,
+    },
+    /// Words to pick from, like text input suggestions
+    Candidates { id: String, candidates: Vec<String> },
+    /// Pushes the items after it to the end
+    FlexibleSpace,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+fn slider_max() -> f64 {
+    1.0
+}
+
+/// A control on the Touch Bar was used
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Action {
+    ButtonPressed { id: String },
+    SliderChanged { id: String, value: f64 },
+    SegmentSelected { id: String, index: usize },
+    CandidateSelected { id: String, index: usize },
+}
+
+/// Show `items` on the Touch Bar while `window` is key, in place of what it
+/// showed, including the webview's own text controls. No items take the
+/// window's away.
+pub async fn set(window: &WebviewWindow, items: Vec<TouchBarItem>) -> Result<(), String> {
+    platform::set(window, items).await
+}
+
+/// Tell the window tao reported `action` for
+#[cfg(target_os = "macos")]
+pub fn used(
+    app: &tauri::AppHandle,
+    window_id: tauri_runtime_wry::tao::window::WindowId,
+    action: Action,
+) {
+    use tauri::{Emitter, Manager};
+    use tauri_runtime_wry::tao::platform::macos;
+
+    let window = app.webview_windows().into_values().find(|window| {
+        window
+            .ns_window()
+            .is_ok_and(|ns_window| macos::window_id_for_ns_window(ns_window) == window_id)
+    });
+    if let Some(window) = window {
+        let _ = window.emit_to(window.label(), EVENT, action);
+    }
+}
+
+/// NSTouchBar through tao
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::TouchBarItem;
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::platform::macos;
+    use tauri_runtime_wry::tao::touch_bar::TouchBarItem as NativeItem;
+
+    fn native_item(item: TouchBarItem) -> NativeItem {
+        match item {
+            TouchBarItem::Button { id, title, enabled } => {
+                NativeItem::Button { id, title, enabled }
+            }
+            TouchBarItem::Slider {
+                id,
+                label,
+                value,
+                min,
+                max,
+            } => NativeItem::Slider {
+                id,
+                label,
+                value,
+                min,
+                max,
+            },
+            TouchBarItem::Segmented {
+                id,
+                labels,
+                selected,
+            } => NativeItem::Segmented {
+                id,
+                labels,
+                selected,
+            },
+            TouchBarItem::Candidates { id, candidates } => {
+                NativeItem::Candidates { id, candidates }
+            }
+            TouchBarItem::FlexibleSpace => NativeItem::FlexibleSpace,
+        }
+    }
+
+    pub async fn set(window: &WebviewWindow, items: Vec<TouchBarItem>) -> Result<(), String> {
+        let items: Vec<NativeItem> = items.into_iter().map(native_item).collect();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        crate::windows::with_ns_window(window, move |ns_window| {
+            let _ = tx.send(unsafe { macos::set_window_touch_bar(ns_window, &items) });
+        })?;
+        rx.await
+            .map_err(|_| "The Touch Bar wasn't set".to_string())?
+            .map_err(|e| format!("Couldn't set the Touch Bar: {}", e))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::TouchBarItem;
+    use tauri::WebviewWindow;
+
+    /// There's nothing to show items on; succeeding keeps callers simple
+    pub async fn set(_window: &WebviewWindow, _items: Vec<TouchBarItem>) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Where a trashed item came from and where it went. The frontend keeps
+/// this to offer Undo and hands it back to `restore`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashHandle {
+    pub original: PathBuf,
+    /// None when the platform moved the item somewhere smudge can't find,
+    /// e.g. the trash folder of another drive on Linux
+    pub trashed: Option<PathBuf>,
+}
+
+/// Move `path` to the OS trash (Trash on macOS, Recycle Bin on Windows, the
+/// freedesktop trash through gio on Linux)
+pub fn move_to_trash(path: &Path) -> Result<TrashHandle, String> {
+    let original = std::path::absolute(path).map_err(|e| e.to_string())?;
+    if !original.exists() {
+        return Err(format!("{} does not exist", original.display()));
+    }
+    let trashed = platform::trash(&original)?;
+    Ok(TrashHandle { original, trashed })
+}
+
+/// Move a trashed item back to where it was. Refuses to overwrite anything
+/// created there since.
+pub fn restore(handle: &TrashHandle) -> Result<PathBuf, String> {
+    let Some(trashed) = &handle.trashed else {
+        return Err(format!(
+            "Couldn't find {} in the trash. Restore it from your file manager instead.",
+            handle.original.display()
+        ));
+    };
+    if !trashed.exists() {
+        return Err(format!(
+            "{} is no longer in the trash",
+            handle.original.display()
+        ));
+    }
+    if handle.original.exists() {
+        return Err(format!("{} already exists", handle.original.display()));
+    }
+    if let Some(parent) = handle.original.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::rename(trashed, &handle.original).map_err(|e| e.to_string())?;
+    platform::forget(trashed);
+    Ok(handle.original.clone())
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::{Path, PathBuf};
+    use tauri_runtime_wry::tao::platform::macos::trash_item;
+
+    pub fn trash(path: &Path) -> Result<Option<PathBuf>, String> {
+        trash_item(path).map(Some).map_err(|e| e.to_string())
+    }
+
+    /// The Trash keeps no metadata of its own that needs cleaning up
+    pub fn forget(_trashed: &Path) {}
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+    use tauri_runtime_wry::tao::platform::windows::recycle_item;
+
+    /// How recently an `$I` file must have been written to be considered
+    const RECENT: Duration = Duration::from_secs(60);
+
+    pub fn trash(path: &Path) -> Result<Option<PathBuf>, String> {
+        recycle_item(path).map_err(|e| e.to_string())?;
+        Ok(find_recycled(path))
+    }
+
+    /// The Recycle Bin stores each item as `$R<id>` next to an `$I<id>` file
+    /// recording the original path. Find the newest `$I` file naming `path`.
+    fn find_recycled(path: &Path) -> Option<PathBuf> {
+        let root = path.ancestors().last()?.join("$Recycle.Bin");
+        let original = path.to_string_lossy().to_lowercase();
+        let now = SystemTime::now();
+        std::fs::read_dir(root)
+            .ok()?
+            .flatten()
+            .filter_map(|user| std::fs::read_dir(user.path()).ok())
+            .flat_map(|entries| entries.flatten())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("$I"))
+            .filter_map(|entry| {
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                if now.duration_since(modified).unwrap_or_default() > RECENT {
+                    return None;
+                }
+                let recorded = read_info(&entry.path())?;
+                (recorded.to_lowercase() == original).then_some((modified, entry.path()))
+            })
+            .max_by_key(|(modified, _)| *modified)
+            .map(|(_, info)| sibling(&info, "$R"))
+    }
+
+    /// Original path from an `$I` file: version 1 holds a fixed 260-character
+    /// path at offset 24, version 2 a length-prefixed one at offset 28
+    fn read_info(info: &Path) -> Option<String> {
+        let bytes = std::fs::read(info).ok()?;
+        let version = u64::from_le_bytes(bytes.get(0..8)?.try_into().ok()?);
+        let name = match version {
+            1 => bytes.get(24..24 + 520)?,
+            2 => {
+                let len = u32::from_le_bytes(bytes.get(24..28)?.try_into().ok()?) as usize;
+                bytes.get(28..28 + len * 2)?
+            }
+            _ => return None,
+        };
+        let wide: Vec<u16> = name
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&c| c != 0)
+            .collect();
+        Some(String::from_utf16_lossy(&wide))
+    }
+
+    fn sibling(path: &Path, prefix: &str) -> PathBuf {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!("{}{}", prefix, &name[2..]))
+    }
+
+    /// Drop the `$I` record so the Recycle Bin stops listing the item
+    pub fn forget(trashed: &Path) {
+        let _ = std::fs::remove_file(sibling(trashed, "$I"));
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use percent_encoding::percent_decode_str;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    pub fn trash(path: &Path) -> Result<Option<PathBuf>, String> {
+        let output = Command::new("gio")
+            .arg("trash")
+            .arg(path)
+            .output()
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    "gio is not installed".to_string()
+                } else {
+                    format!("Failed to run gio: {}", e)
+                }
+            })?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(find_trashed(path))
+    }
+
+    fn home_trash() -> Option<PathBuf> {
+        let data = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share"))
+            })?;
+        Some(data.join("Trash"))
+    }
+
+    /// gio records each item in `info/<name>.trashinfo` with a percent-encoded
+    /// `Path=` line. Find the newest record naming `path`.
+    fn find_trashed(path: &Path) -> Option<PathBuf> {
+        let trash = home_trash()?;
+        std::fs::read_dir(trash.join("info"))
+            .ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let content = std::fs::read_to_string(entry.path()).ok()?;
+                let recorded = content.lines().find_map(|l| l.strip_prefix("Path="))?;
+                let recorded = percent_decode_str(recorded).decode_utf8_lossy();
+                if Path::new(&*recorded) != path {
+                    return None;
+                }
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                let name = entry.path().file_stem()?.to_os_string();
+                Some((modified, trash.join("files").join(name)))
+            })
+            .max_by_key(|(modified, _)| *modified)
+            .map(|(_, trashed)| trashed)
+    }
+
+    /// Drop the `.trashinfo` record so file managers stop listing the item
+    pub fn forget(trashed: &Path) {
+        let (Some(files), Some(name)) = (trashed.parent(), trashed.file_name()) else {
+            return;
+        };
+        let mut info = name.to_os_string();
+        info.push(".trashinfo");
+        if let Some(trash) = files.parent() {
+            let _ = std::fs::remove_file(trash.join("info").join(info));
+        }
+    }
+}
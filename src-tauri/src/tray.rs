@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Sent to the main window with the `TrayButton` that clicked the icon
+pub const CLICK_EVENT: &str = "tray-click";
+
+/// Sent to the main window with the frontend's id of the chosen menu item
+pub const MENU_EVENT: &str = "tray-menu";
+
+const TRAY_ID: &str = "smudge";
+
+/// The tray menu's items share Tauri's menu event channel with every other
+/// menu, so their ids are prefixed
+const ITEM_ID_PREFIX: &str = "tray:";
+
+/// Drawn in black and tinted by AppKit to match the menu bar (macOS)
+#[cfg(target_os = "macos")]
+const TEMPLATE_ICON: &[u8] = include_bytes!("../icons/tray-template.png");
+
+/// A menu item as the frontend describes it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TrayMenuItem {
+    Item {
+        id: String,
+        title: String,
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+        /// Shown with a checkmark column when set
+        #[serde(default)]
+        checked: Option<bool>,
+    },
+    Separator,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TrayButton {
+    Left,
+    Right,
+}
+
+/// Put smudge's icon in the menu bar (macOS) or notification area, or take
+/// it away. Its menu starts empty until `set_menu`.
+pub fn set_visible(app: &AppHandle, visible: bool) -> Result<(), String> {
+    if !visible {
+        app.remove_tray_by_id(TRAY_ID);
+        return Ok(());
+    }
+    if app.tray_by_id(TRAY_ID).is_some() {
+        return Ok(());
+    }
+    build(app).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn build(app: &AppHandle) -> tauri::Result<TrayIcon> {
+    #[cfg(target_os = "macos")]
+    let builder = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(tauri::image::Image::from_bytes(TEMPLATE_ICON)?)
+        .icon_as_template(true);
+    #[cfg(not(target_os = "macos"))]
+    let builder = match app.default_window_icon() {
+        Some(icon) => TrayIconBuilder::with_id(TRAY_ID).icon(icon.clone()),
+        None => TrayIconBuilder::with_id(TRAY_ID),
+    };
+    builder
+        .tooltip("Smudge")
+        // A left click is the app's to handle; the menu is for right clicks
+        .show_menu_on_left_click(false)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                clicked(tray.app_handle(), button);
+            }
+        })
+        .build(app)
+}
+
+/// Change the text shown when hovering over the icon. Does nothing while
+/// the icon isn't shown.
+pub fn set_tooltip(app: &AppHandle, tooltip: &str) -> Result<(), String> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    tray.set_tooltip(Some(tooltip)).map_err(|e| e.to_string())
+}
+
+/// Replace the icon's menu with `items`, in order. Does nothing while the
+/// icon isn't shown.
+pub fn set_menu(app: &AppHandle, items: Vec<TrayMenuItem>) -> Result<(), String> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let menu = build_menu(app, &items).map_err(|e| e.to_string())?;
+    tray.set_menu(Some(menu)).map_err(|e| e.to_string())
+}
+
+fn build_menu(app: &AppHandle, items: &[TrayMenuItem]) -> tauri::Result<Menu<tauri::Wry>> {
+    let menu = Menu::new(app)?;
+    for item in items {
+        match item {
+            TrayMenuItem::Item {
+                id,
+                title,
+                enabled,
+                checked: Some(checked),
+            } => {
+                let id = format!("{}{}", ITEM_ID_PREFIX, id);
+                menu.append(&CheckMenuItem::with_id(
+                    app,
+                    id,
+                    title,
+                    *enabled,
+                    *checked,
+                    None::<&str>,
+                )?)?;
+            }
+            TrayMenuItem::Item {
+                id,
+                title,
+                enabled,
+                checked: None,
+            } => {
+                let id = format!("{}{}", ITEM_ID_PREFIX, id);
+                menu.append(&MenuItem::with_id(app, id, title, *enabled, None::<&str>)?)?;
+            }
+            TrayMenuItem::Separator => menu.append(&PredefinedMenuItem::separator(app)?)?,
+        }
+    }
+    Ok(menu)
+}
+
+/// A left click brings up the main window; both buttons are reported to it
+fn clicked(app: &AppHandle, button: MouseButton) {
+    let button = match button {
+        MouseButton::Left => TrayButton::Left,
+        MouseButton::Right => TrayButton::Right,
+        _ => return,
+    };
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if let TrayButton::Left = button {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = window.emit(CLICK_EVENT, button);
+}
+
+/// Handle a menu event if it came from the tray menu: bring up the main
+/// window and pass it the item's id
+pub fn handle_menu(app: &AppHandle, id: &str) {
+    let Some(id) = id.strip_prefix(ITEM_ID_PREFIX) else {
+        return;
+    };
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    let _ = window.emit(MENU_EVENT, id);
+}
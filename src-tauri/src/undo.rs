@@ -0,0 +1,416 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, WebviewWindow};
+
+use crate::{app_menu, i18n};
+
+/// Sent to a window with an `Apply` when one of its actions is undone or
+/// redone. The frontend applies the patches; the backend only keeps them.
+pub const APPLY_EVENT: &str = "undo-apply";
+/// Sent to a window with its `UndoState` whenever it changes
+pub const EVENT: &str = "undo-changed";
+
+/// Actions kept per window; older ones can't be undone
+const MAX_LEVELS: usize = 100;
+
+/// One change an action made, as the frontend describes it: a patch that
+/// reverts it and one that makes it again
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    pub undo: serde_json::Value,
+    pub redo: serde_json::Value,
+}
+
+/// What the user did, undone and redone as one
+struct Action {
+    id: u64,
+    /// Shown in the Edit menu, e.g. "Move Card"
+    name: String,
+    changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    Undo,
+    Redo,
+}
+
+/// The patches to apply for an undo or redo, in the order to apply them:
+/// an undo's are its changes' `undo` patches last first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Apply {
+    pub name: String,
+    pub direction: Direction,
+    pub patches: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoState {
+    pub undo_name: Option<String>,
+    pub redo_name: Option<String>,
+    /// Whether what's there differs from what was last marked saved
+    pub edited: bool,
+}
+
+#[derive(Default)]
+struct History {
+    undo: Vec<Action>,
+    redo: Vec<Action>,
+    /// The group being built, and how deeply groups are nested in it
+    open: Option<(Action, usize)>,
+    /// The action on top of the undo stack when the document was saved;
+    /// `None` for an empty stack
+    saved: Option<u64>,
+    next_id: u64,
+}
+
+impl History {
+    fn state(&self) -> UndoState {
+        UndoState {
+            undo_name: self.undo.last().map(|action| action.name.clone()),
+            redo_name: self.redo.last().map(|action| action.name.clone()),
+            edited: self.undo.last().map(|action| action.id) != self.saved,
+        }
+    }
+
+    fn new_action(&mut self, name: String) -> Action {
+        self.next_id += 1;
+        Action {
+            id: self.next_id,
+            name,
+            changes: Vec::new(),
+        }
+    }
+}
+
+/// Each window's undo history by label, managed as Tauri state. It outlives
+/// the window's web view, so a reload can pick it up again.
+#[derive(Default)]
+pub struct Undo {
+    histories: Mutex<HashMap<String, History>>,
+}
+
+impl Undo {
+    pub fn window_closed(&self, window: &str) {
+        self.histories.lock().expect("undo mutex").remove(window);
+    }
+
+    fn with<T>(&self, window: &str, f: impl FnOnce(&mut History) -> T) -> T {
+        let mut histories = self.histories.lock().expect("undo mutex");
+        f(histories.entry(window.to_string()).or_default())
+    }
+}
+
+/// Tell `window` about its state, and show it in the Edit menu and, on
+/// macOS, the window's close button
+fn changed(window: &WebviewWindow, state: UndoState) {
+    let app = window.app_handle();
+    let title = |key: &str, name: &Option<String>| match name {
+        Some(name) => i18n::text_with(app, &format!("{}-action", key), &[("action", name)]),
+        None => i18n::text(app, key),
+    };
+    app_menu::set_undo_titles(
+        app,
+        &title("menu-undo", &state.undo_name),
+        &title("menu-redo", &state.redo_name),
+    );
+    if let Err(e) = platform::set_edited(window, state.edited) {
+        log::warn!(window = window.label(), error:% = e; "edited state not shown");
+    }
+    let _ = window.emit_to(window.label(), EVENT, state);
+}
+
+/// Put a finished action on top of `window`'s history, dropping what could
+/// be redone
+fn push(window: &WebviewWindow, action: Action) {
+    let undo = window.state::<Undo>();
+    let name = action.name.clone();
+    let state = undo.with(window.label(), |history| {
+        history.undo.push(action);
+        if history.undo.len() > MAX_LEVELS {
+            history.undo.remove(0);
+        }
+        history.redo.clear();
+        history.state()
+    });
+    if let Err(e) = platform::registered(window, &name) {
+        log::warn!(window = window.label(), error:% = e; "undo not registered natively");
+    }
+    changed(window, state);
+}
+
+/// Start an action that the changes registered until `end_group` make
+/// together. Groups nest; only the outermost one's name is kept.
+pub fn begin_group(window: &WebviewWindow, name: String) {
+    let undo = window.state::<Undo>();
+    undo.with(window.label(), |history| match history.open.as_mut() {
+        Some((_, depth)) => *depth += 1,
+        None => history.open = Some((history.new_action(name), 1)),
+    });
+}
+
+pub fn end_group(window: &WebviewWindow) -> Result<(), String> {
+    let undo = window.state::<Undo>();
+    let finished = undo.with(window.label(), |history| -> Result<_, String> {
+        let (_, depth) = history.open.as_mut().ok_or("No undo group is open")?;
+        *depth -= 1;
+        if *depth > 0 {
+            return Ok(None);
+        }
+        Ok(history.open.take().map(|(action, _)| action))
+    })?;
+    // A group nothing was registered in isn't worth undoing
+    if let Some(action) = finished.filter(|action| !action.changes.is_empty()) {
+        push(window, action);
+    }
+    Ok(())
+}
+
+/// Record a change the frontend just made: into the open group, or as an
+/// action of its own named `name`
+pub fn register(window: &WebviewWindow, name: String, change: Change) {
+    let undo = window.state::<Undo>();
+    let action = undo.with(window.label(), |history| match history.open.as_mut() {
+        Some((action, _)) => {
+            action.changes.push(change);
+            None
+        }
+        None => {
+            let mut action = history.new_action(name);
+            action.changes.push(change);
+            Some(action)
+        }
+    });
+    if let Some(action) = action {
+        push(window, action);
+    }
+}
+
+/// Move the top action across from one stack to the other and have `window`
+/// apply its patches. Returns its name, or `None` with nothing to move.
+fn step(window: &WebviewWindow, direction: Direction) -> Option<String> {
+    let undo = window.state::<Undo>();
+    let (apply, state) = undo.with(window.label(), |history| {
+        let (from, to) = match direction {
+            Direction::Undo => (&mut history.undo, &mut history.redo),
+            Direction::Redo => (&mut history.redo, &mut history.undo),
+        };
+        let action = from.pop()?;
+        let patches = match direction {
+            Direction::Undo => action
+                .changes
+                .iter()
+                .rev()
+                .map(|change| change.undo.clone())
+                .collect(),
+            Direction::Redo => action
+                .changes
+                .iter()
+                .map(|change| change.redo.clone())
+                .collect(),
+        };
+        let apply = Apply {
+            name: action.name.clone(),
+            direction,
+            patches,
+        };
+        to.push(action);
+        Some((apply, history.state()))
+    })?;
+    let name = apply.name.clone();
+    let _ = window.emit_to(window.label(), APPLY_EVENT, apply);
+    changed(window, state);
+    Some(name)
+}
+
+/// Undo the last action, through the native undo manager where there is
+/// one so text edits in the web view take their turn
+pub fn undo(window: &WebviewWindow) -> Result<(), String> {
+    platform::perform(window, Direction::Undo)
+}
+
+pub fn redo(window: &WebviewWindow) -> Result<(), String> {
+    platform::perform(window, Direction::Redo)
+}
+
+/// The document was saved as it is now
+pub fn mark_saved(window: &WebviewWindow) {
+    let undo = window.state::<Undo>();
+    let state = undo.with(window.label(), |history| {
+        history.saved = history.undo.last().map(|action| action.id);
+        history.state()
+    });
+    changed(window, state);
+}
+
+/// Forget `window`'s history, e.g. when it opens another document
+pub fn clear(window: &WebviewWindow) -> Result<(), String> {
+    let undo = window.state::<Undo>();
+    let state = undo.with(window.label(), |history| {
+        *history = History::default();
+        history.state()
+    });
+    platform::cleared(window)?;
+    changed(window, state);
+    Ok(())
+}
+
+/// `window`'s state for a web view that just loaded, with what can be undone
+/// handed to the native undo manager again, which forgets it as the page
+/// reloads
+pub fn restore(window: &WebviewWindow) -> Result<UndoState, String> {
+    let undo = window.state::<Undo>();
+    let (names, state) = undo.with(window.label(), |history| {
+        // A group the old page left open will never be ended
+        history.open = None;
+        let names: Vec<String> = history
+            .undo
+            .iter()
+            .map(|action| action.name.clone())
+            .collect();
+        (names, history.state())
+    });
+    platform::cleared(window)?;
+    for name in &names {
+        platform::registered(window, name)?;
+    }
+    changed(window, state.clone());
+    Ok(state)
+}
+
+/// Each action is mirrored into the web view's `NSUndoManager`, which the
+/// Edit menu's Undo and Redo and Cmd+Z go through. Its entries don't carry
+/// the action; undoing one steps the window's history, and registers the
+/// entry that will step it back.
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::Direction;
+    use block2::RcBlock;
+    use objc2::msg_send;
+    use objc2::rc::{Retained, Weak};
+    use objc2::runtime::{AnyObject, NSObject};
+    use objc2_foundation::{NSString, NSUndoManager};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::ptr::NonNull;
+    use tauri::{Manager, WebviewWindow};
+
+    thread_local! {
+        /// The target each window's entries are registered with, so they
+        /// can be removed without touching the web view's own
+        static TARGETS: RefCell<HashMap<String, Retained<NSObject>>> = RefCell::default();
+    }
+
+    fn target(label: &str) -> Retained<NSObject> {
+        TARGETS.with_borrow_mut(|targets| {
+            targets
+                .entry(label.to_string())
+                .or_insert_with(NSObject::new)
+                .clone()
+        })
+    }
+
+    fn undo_manager(webview: *mut std::ffi::c_void) -> Option<Retained<NSUndoManager>> {
+        let webview = unsafe { &*webview.cast::<AnyObject>() };
+        unsafe { msg_send![webview, undoManager] }
+    }
+
+    /// Register an entry in `manager` that steps `window`'s history when
+    /// it's undone or redone, and registers its own reverse as it does
+    fn register(window: &WebviewWindow, manager: &NSUndoManager, name: &str) {
+        let label = window.label().to_string();
+        let app = window.app_handle().clone();
+        let weak = Weak::from(manager);
+        let handler = RcBlock::new(move |_target: NonNull<AnyObject>| {
+            let (Some(window), Some(manager)) = (app.get_webview_window(&label), weak.load())
+            else {
+                return;
+            };
+            let direction = if manager.isUndoing() {
+                Direction::Undo
+            } else {
+                Direction::Redo
+            };
+            if let Some(name) = super::step(&window, direction) {
+                register(&window, &manager, &name);
+            }
+        });
+        unsafe { manager.registerUndoWithTarget_handler(&target(window.label()), &handler) };
+        manager.setActionName(&NSString::from_str(name));
+    }
+
+    pub fn registered(window: &WebviewWindow, name: &str) -> Result<(), String> {
+        let handle = window.clone();
+        let name = name.to_string();
+        window
+            .with_webview(move |webview| {
+                if let Some(manager) = undo_manager(webview.inner()) {
+                    register(&handle, &manager, &name);
+                }
+            })
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn perform(window: &WebviewWindow, direction: Direction) -> Result<(), String> {
+        window
+            .with_webview(move |webview| {
+                if let Some(manager) = undo_manager(webview.inner()) {
+                    match direction {
+                        Direction::Undo if manager.canUndo() => manager.undo(),
+                        Direction::Redo if manager.canRedo() => manager.redo(),
+                        _ => {}
+                    }
+                }
+            })
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn cleared(window: &WebviewWindow) -> Result<(), String> {
+        let label = window.label().to_string();
+        window
+            .with_webview(move |webview| {
+                if let Some(manager) = undo_manager(webview.inner()) {
+                    unsafe { manager.removeAllActionsWithTarget(&target(&label)) };
+                }
+            })
+            .map_err(|e| e.to_string())
+    }
+
+    /// The dot in the close button
+    pub fn set_edited(window: &WebviewWindow, edited: bool) -> Result<(), String> {
+        crate::windows::with_ns_window(window, move |ns_window| {
+            let ns_window = unsafe { &*(ns_window as *const AnyObject) };
+            let _: () = unsafe { msg_send![ns_window, setDocumentEdited: edited] };
+        })
+    }
+}
+
+/// Without a native undo manager the history is stepped directly, and the
+/// frontend shows whether the document is edited
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::Direction;
+    use tauri::WebviewWindow;
+
+    pub fn registered(_window: &WebviewWindow, _name: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn perform(window: &WebviewWindow, direction: Direction) -> Result<(), String> {
+        super::step(window, direction);
+        Ok(())
+    }
+
+    pub fn cleared(_window: &WebviewWindow) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn set_edited(_window: &WebviewWindow, _edited: bool) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+
+/// Domain-to-route mapping bundled with the app (see `universal-links.json`).
+/// Adding a linkable page only requires a new entry here, not native changes.
+const BUNDLED_CONFIG: &str = include_str!("../universal-links.json");
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct UniversalLinksConfig {
+    #[serde(default)]
+    pub applinks: Vec<AppLinkGroup>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AppLinkGroup {
+    pub domains: Vec<String>,
+    pub routes: Vec<RoutePattern>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoutePattern {
+    pub path: String, // "/notes/:id" or "/files/*path"
+    pub route: String,
+}
+
+/// A URL resolved to an app route, emitted to the frontend as-is
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkRoute {
+    pub route: String,
+    pub params: HashMap<String, String>,
+    pub url: String,
+}
+
+/// Load the bundled mapping. A malformed file disables universal links
+/// rather than failing app startup.
+pub fn load_config() -> UniversalLinksConfig {
+    serde_json::from_str(BUNDLED_CONFIG).unwrap_or_default()
+}
+
+impl UniversalLinksConfig {
+    /// Resolve an https URL against the configured domains and path patterns.
+    /// Query parameters are merged into the route params (path captures win).
+    pub fn resolve(&self, url: &Url) -> Option<DeepLinkRoute> {
+        if url.scheme() != "https" {
+            return None;
+        }
+        let host = url.host_str()?.to_ascii_lowercase();
+
+        let group = self
+            .applinks
+            .iter()
+            .find(|g| g.domains.iter().any(|d| d.eq_ignore_ascii_case(&host)))?;
+
+        resolve_routes(&group.routes, url, url.path())
+    }
+
+    /// Resolve a link in the app's own scheme. Its host is the first path
+    /// segment (`smudge://notes/abc` matches `/notes/abc`), and with no
+    /// domain to pick a group, every group's routes apply.
+    pub fn resolve_app_url(&self, url: &Url) -> Option<DeepLinkRoute> {
+        let path = format!("/{}{}", url.host_str().unwrap_or(""), url.path());
+        self.applinks
+            .iter()
+            .find_map(|group| resolve_routes(&group.routes, url, &path))
+    }
+}
+
+fn resolve_routes(routes: &[RoutePattern], url: &Url, path: &str) -> Option<DeepLinkRoute> {
+    routes.iter().find_map(|pattern| {
+        let mut params = match_path(&pattern.path, path)?;
+        for (key, value) in url.query_pairs() {
+            params.entry(key.into_owned()).or_insert_with(|| value.into_owned());
+        }
+        Some(DeepLinkRoute {
+            route: pattern.route.clone(),
+            params,
+            url: url.to_string(),
+        })
+    })
+}
+
+// Match a path against a pattern with `:name` segment captures and an
+// optional trailing `*name` capture for the remainder of the path.
+fn match_path(pattern: &str, path: &str) -> Option<HashMap<String, String>> {
+    let pattern_segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path_segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let mut params = HashMap::new();
+
+    for (i, segment) in pattern_segments.iter().enumerate() {
+        if let Some(name) = segment.strip_prefix('*') {
+            let rest = path_segments.get(i..)?.join("/");
+            params.insert(name.to_string(), percent_decode(&rest));
+            return Some(params);
+        }
+
+        let actual = path_segments.get(i)?;
+        if let Some(name) = segment.strip_prefix(':') {
+            params.insert(name.to_string(), percent_decode(actual));
+        } else if segment != actual {
+            return None;
+        }
+    }
+
+    if path_segments.len() == pattern_segments.len() {
+        Some(params)
+    } else {
+        None
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    percent_encoding::percent_decode_str(s)
+        .decode_utf8_lossy()
+        .into_owned()
+}
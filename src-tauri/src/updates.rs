@@ -0,0 +1,877 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+/// Sent to every window with a `Progress` while an update downloads
+pub const PROGRESS_EVENT: &str = "update-progress";
+
+/// Sent to every window with a `Ready` once an update is downloaded,
+/// verified and staged. Installing it and restarting is left to the user.
+pub const READY_EVENT: &str = "update-ready";
+
+/// Launches of a newly installed version that never got as far as a ready
+/// frontend, after which the previous version is put back
+const MAX_FAILED_LAUNCHES: u32 = 2;
+
+/// Developer ID team that signs releases, from `SMUDGE_TEAM_ID` at build
+/// time. Updates signed by anyone else aren't installed.
+#[cfg(target_os = "macos")]
+const TEAM_ID: Option<&str> = option_env!("SMUDGE_TEAM_ID");
+
+/// Certificate subject that signs releases, from
+/// `SMUDGE_AUTHENTICODE_SIGNER` at build time, e.g.
+/// `CN=Runpoint Partners, O=Runpoint Partners, C=US`
+#[cfg(windows)]
+const AUTHENTICODE_SIGNER: Option<&str> = option_env!("SMUDGE_AUTHENTICODE_SIGNER");
+
+/// An available update, as shown to the user
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub body: Option<String>,
+    /// Older than the running version; only offered when forced
+    pub downgrade: bool,
+}
+
+/// Outcome of checking a downloaded package's platform signature, on top
+/// of the updater's own minisign check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Verification {
+    pub version: String,
+    pub verified: bool,
+    /// Team ID (macOS) or certificate subject (Windows) that signed it
+    pub signer: Option<String>,
+    /// Whether Gatekeeper accepts it as notarized (macOS only)
+    pub notarized: Option<bool>,
+    pub detail: String,
+}
+
+/// How far a download has got
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+    pub version: String,
+    pub downloaded: u64,
+    /// Unknown when the server doesn't say
+    pub total: Option<u64>,
+    /// Downloading a patch against the installed version rather than the
+    /// whole package
+    pub delta: bool,
+}
+
+/// A staged update, ready to install
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ready {
+    pub version: String,
+    pub verification: Verification,
+}
+
+/// The update found by the last check, waiting to be installed
+#[derive(Default)]
+pub struct PendingUpdate(Mutex<Option<(Update, bool)>>);
+
+/// A patch from one version's package to the announced one, listed in the
+/// feed under `platforms.<target>.deltas.<from version>`. It's a zstd
+/// frame made with `zstd --patch-from=<old package> --long=31`.
+#[derive(Debug, Deserialize)]
+struct Delta {
+    url: url::Url,
+    signature: String,
+}
+
+/// What's kept next to a package under the updates directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageInfo {
+    version: String,
+    /// The feed's minisign signature, checked again before installing
+    signature: String,
+    verification: Verification,
+}
+
+/// An installed update on probation until its frontend comes up
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Probation {
+    from_version: String,
+    to_version: String,
+    launches: u32,
+}
+
+fn parse_version(version: &str) -> Result<semver::Version, String> {
+    semver::Version::parse(version.trim_start_matches('v')).map_err(|e| e.to_string())
+}
+
+/// Check the update endpoint. Older releases are ignored unless
+/// `allow_downgrade` is set.
+pub async fn check(app: &AppHandle, allow_downgrade: bool) -> Result<Option<UpdateInfo>, String> {
+    let update = app
+        .updater_builder()
+        .version_comparator(move |current, release| {
+            if allow_downgrade {
+                release.version != current
+            } else {
+                release.version > current
+            }
+        })
+        .build()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let state = app.state::<PendingUpdate>();
+    let mut pending = state.0.lock().expect("pending update mutex");
+    let Some(update) = update else {
+        *pending = None;
+        return Ok(None);
+    };
+    let downgrade = parse_version(&update.version)? < parse_version(&update.current_version)?;
+    let info = UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        body: update.body.clone(),
+        downgrade,
+    };
+    *pending = Some((update, allow_downgrade));
+    Ok(Some(info))
+}
+
+fn pending(app: &AppHandle) -> Result<(Update, bool), String> {
+    app.state::<PendingUpdate>()
+        .0
+        .lock()
+        .expect("pending update mutex")
+        .clone()
+        .ok_or_else(|| "No update is pending. Check for updates first.".to_string())
+}
+
+/// Download the pending update, verify its signature against the signer
+/// built into the app, and stage it for `install`. A patch against
+/// the installed version's package is tried before the whole package.
+pub async fn download(app: &AppHandle) -> Result<Verification, String> {
+    let (update, allow_downgrade) = pending(app)?;
+
+    if !allow_downgrade
+        && parse_version(&update.version)? <= parse_version(&update.current_version)?
+    {
+        let message = format!(
+            "Refusing to install {} over {}",
+            update.version, update.current_version
+        );
+        log(app, &message);
+        return Err(message);
+    }
+
+    let dir = updates_dir(app)?;
+    if let Some(staged) = read_package(&dir.join("staged"))
+        .filter(|(info, _)| info.version == update.version && info.signature == update.signature)
+    {
+        return Ok(staged.0.verification);
+    }
+
+    let bytes = match download_delta(app, &update, &dir).await {
+        Ok(Some(bytes)) => bytes,
+        result => {
+            if let Err(e) = result {
+                log(
+                    app,
+                    &format!(
+                        "{}: delta failed, downloading it all: {}",
+                        update.version, e
+                    ),
+                );
+            }
+            // download() also checks the minisign signature from tauri.conf.json
+            let progress = progress_emitter(app, &update.version, false);
+            update.download(progress, || {}).await.map_err(|e| {
+                log(app, &format!("{}: download failed: {}", update.version, e));
+                e.to_string()
+            })?
+        }
+    };
+
+    let version = update.version.clone();
+    let verification =
+        tauri::async_runtime::spawn_blocking(move || verify(&version, &bytes).map(|v| (v, bytes)))
+            .await
+            .map_err(|e| e.to_string())?;
+    let (verification, bytes) = match verification {
+        Ok(result) => result,
+        Err(e) => {
+            log(
+                app,
+                &format!("{}: verification failed: {}", update.version, e),
+            );
+            return Err(e);
+        }
+    };
+    log(
+        app,
+        &format!(
+            "{}: verified={} signer={} notarized={} ({})",
+            verification.version,
+            verification.verified,
+            verification.signer.as_deref().unwrap_or("-"),
+            verification
+                .notarized
+                .map_or("-".to_string(), |n| n.to_string()),
+            verification.detail
+        ),
+    );
+    if !verification.verified {
+        return Err(verification.detail);
+    }
+
+    let info = PackageInfo {
+        version: update.version.clone(),
+        signature: update.signature.clone(),
+        verification: verification.clone(),
+    };
+    write_package(&dir.join("staged"), &info, &bytes)?;
+    log(app, &format!("{}: staged", update.version));
+    let _ = app.emit(
+        READY_EVENT,
+        Ready {
+            version: update.version.clone(),
+            verification: verification.clone(),
+        },
+    );
+    Ok(verification)
+}
+
+/// Install the staged update, downloading it first if it isn't staged yet.
+/// The current version is backed up first, and put back if the new one
+/// fails to launch. Restarting is left to the caller.
+pub async fn install(app: &AppHandle) -> Result<Verification, String> {
+    let (update, _) = pending(app)?;
+    download(app).await?;
+
+    let dir = updates_dir(app)?;
+    let (info, bytes) = read_package(&dir.join("staged")).ok_or("The staged update is gone")?;
+    // It sat on disk since it was checked
+    if let Err(e) = verify_minisign(app, &bytes, &info.signature) {
+        let _ = std::fs::remove_dir_all(dir.join("staged"));
+        log(
+            app,
+            &format!("{}: staged package rejected: {}", info.version, e),
+        );
+        return Err(e);
+    }
+
+    let rollback = dir.join("rollback");
+    let _ = std::fs::remove_dir_all(&rollback);
+    match back_up(&dir, &rollback, &update.current_version) {
+        Ok(()) => write_json(
+            &rollback.join("probation.json"),
+            &Probation {
+                from_version: update.current_version.clone(),
+                to_version: info.version.clone(),
+                launches: 0,
+            },
+        )?,
+        Err(e) => log(
+            app,
+            &format!("{}: no rollback available: {}", update.current_version, e),
+        ),
+    }
+
+    update.install(&bytes).map_err(|e| {
+        log(app, &format!("{}: install failed: {}", update.version, e));
+        e.to_string()
+    })?;
+
+    // The installed package is what the next delta patches
+    let installed = dir.join("installed");
+    let _ = std::fs::remove_dir_all(&installed);
+    if let Err(e) = std::fs::rename(dir.join("staged"), &installed) {
+        log(app, &format!("{}: package not kept: {}", info.version, e));
+    }
+    *app.state::<PendingUpdate>()
+        .0
+        .lock()
+        .expect("pending update mutex") = None;
+    log(app, &format!("{}: installed", info.version));
+    Ok(info.verification)
+}
+
+/// The patched package, or None when the feed has no patch from the
+/// installed version or its package wasn't kept
+async fn download_delta(
+    app: &AppHandle,
+    update: &Update,
+    dir: &Path,
+) -> Result<Option<Vec<u8>>, String> {
+    let Some(delta) = find_delta(update) else {
+        return Ok(None);
+    };
+    let Some((base_info, base)) = read_package(&dir.join("installed")) else {
+        return Ok(None);
+    };
+    if base_info.version != update.current_version {
+        return Ok(None);
+    }
+
+    // The updater downloads anything it's pointed at and checks its
+    // minisign signature
+    let mut patch_update = update.clone();
+    patch_update.download_url = delta.url;
+    patch_update.signature = delta.signature;
+    let progress = progress_emitter(app, &update.version, true);
+    let patch = patch_update
+        .download(progress, || {})
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let package = tauri::async_runtime::spawn_blocking(move || apply_delta(&base, &patch))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("The patch doesn't apply: {}", e))?;
+    // The result has to be exactly the package the feed signed
+    verify_minisign(app, &package, &update.signature)?;
+    log(
+        app,
+        &format!(
+            "{}: patched from {}",
+            update.version, update.current_version
+        ),
+    );
+    Ok(Some(package))
+}
+
+fn find_delta(update: &Update) -> Option<Delta> {
+    let json = &update.raw_json;
+    let deltas = json
+        .get("platforms")
+        .and_then(|platforms| platforms.get(&update.target))
+        .and_then(|platform| platform.get("deltas"))
+        // Dynamic feeds answer for one platform
+        .or_else(|| json.get("deltas"))?;
+    serde_json::from_value(deltas.get(&update.current_version)?.clone()).ok()
+}
+
+fn apply_delta(base: &[u8], patch: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decoder = zstd::stream::read::Decoder::with_ref_prefix(patch, base)?;
+    // Patches are made with --long=31, so matches reach across the whole base
+    decoder.window_log_max(31)?;
+    let mut package = Vec::new();
+    decoder.read_to_end(&mut package)?;
+    Ok(package)
+}
+
+/// Check `bytes` against a minisign signature from the feed, with the key in
+/// tauri.conf.json, the way the updater checks what it downloads
+fn verify_minisign(app: &AppHandle, bytes: &[u8], signature: &str) -> Result<(), String> {
+    let decode = |value: &str| {
+        base64::engine::general_purpose::STANDARD
+            .decode(value)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+    };
+    let pubkey = app
+        .config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|pubkey| pubkey.as_str())
+        .and_then(decode)
+        .ok_or("The updater has no public key")?;
+    let pubkey = minisign_verify::PublicKey::decode(&pubkey).map_err(|e| e.to_string())?;
+    let signature = decode(signature).ok_or("The signature isn't valid base64")?;
+    let signature = minisign_verify::Signature::decode(&signature).map_err(|e| e.to_string())?;
+    pubkey
+        .verify(bytes, &signature, true)
+        .map_err(|e| format!("The update's signature doesn't match: {}", e))
+}
+
+/// A download progress callback that tells every window as each percent (or
+/// megabyte, when the size is unknown) comes in
+fn progress_emitter(app: &AppHandle, version: &str, delta: bool) -> impl FnMut(usize, Option<u64>) {
+    let app = app.clone();
+    let version = version.to_string();
+    let mut downloaded = 0u64;
+    let mut reported = 0u64;
+    move |chunk, total| {
+        downloaded += chunk as u64;
+        let step = total.map_or(1 << 20, |total| (total / 100).max(1));
+        if downloaded - reported < step && Some(downloaded) != total {
+            return;
+        }
+        reported = downloaded;
+        let _ = app.emit(
+            PROGRESS_EVENT,
+            Progress {
+                version: version.clone(),
+                downloaded,
+                total,
+                delta,
+            },
+        );
+    }
+}
+
+fn updates_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("updates"))
+        .map_err(|e| e.to_string())
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+/// Keep a package in `dir`, replacing what was there
+fn write_package(dir: &Path, info: &PackageInfo, bytes: &[u8]) -> Result<(), String> {
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join("package"), bytes).map_err(|e| e.to_string())?;
+    // Written last, so a package without it is never trusted
+    write_json(&dir.join("package.json"), info)
+}
+
+fn read_package(dir: &Path) -> Option<(PackageInfo, Vec<u8>)> {
+    let info = read_json(&dir.join("package.json"))?;
+    Some((info, std::fs::read(dir.join("package")).ok()?))
+}
+
+/// Append a line to updates.log in the app log directory
+fn log(app: &AppHandle, message: &str) {
+    let Ok(dir) = app.path().app_log_dir() else {
+        return;
+    };
+    if std::fs::create_dir_all(&dir).is_err() {
+        return;
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("updates.log"))
+    {
+        let _ = writeln!(file, "{} {}", timestamp, message);
+    }
+}
+
+/// Scratch directory for unpacking a package, removed when dropped
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+struct Scratch(PathBuf);
+
+impl Scratch {
+    #[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+    fn new() -> Result<Self, String> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!("smudge-update-{}", nanos));
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(Self(dir))
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run a tool, returning its stdout and stderr when it succeeds
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+fn run(command: &mut Command, tool: &str) -> Result<(String, String), String> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", tool, e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    if !output.status.success() {
+        return Err(format!("{} failed: {}", tool, stderr.trim()));
+    }
+    Ok((stdout, stderr))
+}
+
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+fn find_file(dir: &Path, extensions: &[&str]) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| extensions.iter().any(|x| e.eq_ignore_ascii_case(x)))
+        })
+}
+
+/// What to make of an update when no signer was built in: development
+/// builds settle for the updater signature, release builds refuse it
+#[cfg(any(target_os = "macos", windows))]
+fn unpinned(version: &str) -> Verification {
+    Verification {
+        version: version.to_string(),
+        verified: cfg!(debug_assertions),
+        signer: None,
+        notarized: None,
+        detail: if cfg!(debug_assertions) {
+            "This build has no pinned signer, so only the updater signature was checked"
+        } else {
+            "This build has no pinned signer, so the update can't be verified"
+        }
+        .to_string(),
+    }
+}
+
+/// The bundle's Developer ID team, from `codesign -dv`
+#[cfg(target_os = "macos")]
+fn team_identifier(bundle: &Path) -> Option<String> {
+    let (_, details) = run(
+        Command::new("codesign")
+            .args(["-dv", "--verbose=2"])
+            .arg(bundle),
+        "codesign",
+    )
+    .ok()?;
+    details
+        .lines()
+        .find_map(|line| line.strip_prefix("TeamIdentifier="))
+        .map(str::to_string)
+        .filter(|team| team != "not set")
+}
+
+#[cfg(target_os = "macos")]
+fn verify(version: &str, bytes: &[u8]) -> Result<Verification, String> {
+    let Some(pinned) = TEAM_ID.filter(|team| !team.is_empty()) else {
+        return Ok(unpinned(version));
+    };
+
+    let scratch = Scratch::new()?;
+    let archive = scratch.0.join("update.app.tar.gz");
+    std::fs::write(&archive, bytes).map_err(|e| e.to_string())?;
+    run(
+        Command::new("tar")
+            .arg("-xzf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&scratch.0),
+        "tar",
+    )?;
+    let bundle = find_file(&scratch.0, &["app"]).ok_or("The update has no app bundle")?;
+
+    let mut verification = Verification {
+        version: version.to_string(),
+        verified: false,
+        signer: None,
+        notarized: None,
+        detail: String::new(),
+    };
+    if let Err(e) = run(
+        Command::new("codesign")
+            .args(["--verify", "--deep", "--strict"])
+            .arg(&bundle),
+        "codesign",
+    ) {
+        verification.detail = format!("The code signature is invalid: {}", e);
+        return Ok(verification);
+    }
+    verification.signer = team_identifier(&bundle);
+    if verification.signer.as_deref() != Some(pinned) {
+        verification.detail = format!(
+            "Signed by {} instead of {}",
+            verification.signer.as_deref().unwrap_or("nobody"),
+            pinned
+        );
+        return Ok(verification);
+    }
+    let notarized = run(
+        Command::new("spctl")
+            .args(["--assess", "--type", "execute", "-vv"])
+            .arg(&bundle),
+        "spctl",
+    )
+    .is_ok_and(|(_, assessment)| assessment.contains("Notarized"));
+    verification.notarized = Some(notarized);
+    if !notarized {
+        verification.detail = "Gatekeeper doesn't accept the update as notarized".to_string();
+        return Ok(verification);
+    }
+    verification.verified = true;
+    verification.detail = format!("Signed by team {} and notarized", pinned);
+    Ok(verification)
+}
+
+/// Authenticode status and signer subject, from PowerShell
+#[cfg(windows)]
+fn authenticode(path: &Path) -> Result<(String, Option<String>), String> {
+    let script = format!(
+        "$s = Get-AuthenticodeSignature -LiteralPath '{}'; $s.Status; $s.SignerCertificate.Subject",
+        path.to_string_lossy().replace('\'', "''")
+    );
+    let (output, _) = run(
+        Command::new("powershell").args(["-NoProfile", "-NonInteractive", "-Command", &script]),
+        "powershell",
+    )?;
+    let mut lines = output.lines().map(str::trim);
+    let status = lines.next().unwrap_or_default().to_string();
+    let subject = lines.next().filter(|s| !s.is_empty()).map(str::to_string);
+    Ok((status, subject))
+}
+
+#[cfg(windows)]
+fn verify(version: &str, bytes: &[u8]) -> Result<Verification, String> {
+    let Some(pinned) = AUTHENTICODE_SIGNER.filter(|signer| !signer.is_empty()) else {
+        return Ok(unpinned(version));
+    };
+
+    let scratch = Scratch::new()?;
+    let installer = if bytes.starts_with(b"MZ") {
+        let path = scratch.0.join("update.exe");
+        std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+        path
+    } else if bytes.starts_with(&[0xD0, 0xCF, 0x11, 0xE0]) {
+        let path = scratch.0.join("update.msi");
+        std::fs::write(&path, bytes).map_err(|e| e.to_string())?;
+        path
+    } else {
+        // Older releases zip the installer; Windows' bsdtar reads zips
+        let archive = scratch.0.join("update.zip");
+        std::fs::write(&archive, bytes).map_err(|e| e.to_string())?;
+        run(
+            Command::new("tar")
+                .arg("-xf")
+                .arg(&archive)
+                .arg("-C")
+                .arg(&scratch.0),
+            "tar",
+        )?;
+        find_file(&scratch.0, &["exe", "msi"]).ok_or("The update has no installer")?
+    };
+
+    let (status, signer) = authenticode(&installer)?;
+    let mut verification = Verification {
+        version: version.to_string(),
+        verified: false,
+        signer,
+        notarized: None,
+        detail: String::new(),
+    };
+    if status != "Valid" {
+        verification.detail = format!("The Authenticode signature is {}", status);
+    } else if verification.signer.as_deref() != Some(pinned) {
+        verification.detail = format!(
+            "Signed by {} instead of {}",
+            verification.signer.as_deref().unwrap_or("nobody"),
+            pinned
+        );
+    } else {
+        verification.verified = true;
+        verification.detail = format!("Authenticode signed by {}", pinned);
+    }
+    Ok(verification)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn verify(version: &str, _bytes: &[u8]) -> Result<Verification, String> {
+    Ok(Verification {
+        version: version.to_string(),
+        verified: true,
+        signer: None,
+        notarized: None,
+        detail: "Linux packages carry no platform signature; the updater signature was checked"
+            .to_string(),
+    })
+}
+
+/// Where the running version is installed: the app bundle on macOS, the
+/// AppImage on Linux. Windows installers are reinstalled instead.
+#[cfg(target_os = "macos")]
+fn installation() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()?
+        .ancestors()
+        .nth(3)
+        .map(Path::to_path_buf)
+        .filter(|bundle| bundle.extension().is_some_and(|e| e == "app"))
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn installation() -> Option<PathBuf> {
+    std::env::var_os("APPIMAGE").map(PathBuf::from)
+}
+
+/// Copy the running version into `rollback` before an update replaces it
+#[cfg(not(windows))]
+fn back_up(_dir: &Path, rollback: &Path, _version: &str) -> Result<(), String> {
+    let installation = installation().ok_or("Not installed from a bundle or AppImage")?;
+    std::fs::create_dir_all(rollback).map_err(|e| e.to_string())?;
+    copy_installation(&installation, &rollback.join("previous"))
+}
+
+/// Windows can only go back by reinstalling the previous package, kept when
+/// it was installed by an update
+#[cfg(windows)]
+fn back_up(dir: &Path, rollback: &Path, version: &str) -> Result<(), String> {
+    let (info, bytes) =
+        read_package(&dir.join("installed")).ok_or("The installed package wasn't kept")?;
+    if info.version != version {
+        return Err(format!("The kept package is {}", info.version));
+    }
+    write_package(&rollback.join("previous"), &info, &bytes)
+}
+
+#[cfg(target_os = "macos")]
+fn copy_installation(from: &Path, to: &Path) -> Result<(), String> {
+    // ditto keeps the bundle's signature, symlinks and extended attributes
+    run(Command::new("ditto").arg(from).arg(to), "ditto").map(|_| ())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn copy_installation(from: &Path, to: &Path) -> Result<(), String> {
+    std::fs::copy(from, to)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Count this launch against a freshly installed version. After
+/// `MAX_FAILED_LAUNCHES` launches that never reached `confirm_launch`, the
+/// previous version is put back and started instead. Called early in setup.
+pub fn launched(app: &AppHandle) {
+    let Ok(dir) = updates_dir(app) else {
+        return;
+    };
+    let rollback = dir.join("rollback");
+    let Some(mut probation) = read_json::<Probation>(&rollback.join("probation.json")) else {
+        return;
+    };
+    let current = app.package_info().version.to_string();
+    if current != probation.to_version {
+        // The install never took, or a rollback already happened
+        let _ = std::fs::remove_dir_all(&rollback);
+        return;
+    }
+    if probation.launches < MAX_FAILED_LAUNCHES {
+        probation.launches += 1;
+        let _ = write_json(&rollback.join("probation.json"), &probation);
+        return;
+    }
+
+    log(
+        app,
+        &format!(
+            "{}: failed to launch {} times, rolling back to {}",
+            probation.to_version, probation.launches, probation.from_version
+        ),
+    );
+    match restore(&rollback) {
+        Ok(()) => {
+            log(app, &format!("{}: restored", probation.from_version));
+            let _ = std::fs::remove_dir_all(dir.join("installed"));
+            let _ = std::fs::remove_dir_all(&rollback);
+            // On Windows the installer launches the restored version itself
+            #[cfg(windows)]
+            app.exit(0);
+            #[cfg(not(windows))]
+            app.restart();
+        }
+        Err(e) => {
+            log(
+                app,
+                &format!("{}: rollback failed: {}", probation.from_version, e),
+            );
+            let _ = std::fs::remove_dir_all(&rollback);
+        }
+    }
+}
+
+/// The new version got its frontend up, so it stays. Called when the
+/// frontend reports ready.
+pub fn confirm_launch(app: &AppHandle) {
+    let Ok(dir) = updates_dir(app) else {
+        return;
+    };
+    let rollback = dir.join("rollback");
+    if let Some(probation) = read_json::<Probation>(&rollback.join("probation.json")) {
+        log(app, &format!("{}: launched", probation.to_version));
+        let _ = std::fs::remove_dir_all(&rollback);
+    }
+}
+
+#[cfg(not(windows))]
+fn restore(rollback: &Path) -> Result<(), String> {
+    let installation = installation().ok_or("Not running from a bundle or AppImage")?;
+    // Copied next to it first, so a failed copy leaves the new version be
+    let mut restoring = installation.clone().into_os_string();
+    restoring.push(".rollback");
+    let restoring = PathBuf::from(restoring);
+    let _ = remove(&restoring);
+    copy_installation(&rollback.join("previous"), &restoring)?;
+    remove(&installation)?;
+    std::fs::rename(&restoring, &installation).map_err(|e| e.to_string())
+}
+
+#[cfg(not(windows))]
+fn remove(path: &Path) -> Result<(), String> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Start the previous version's installer, the way the updater starts one:
+/// it replaces this version and launches the result
+#[cfg(windows)]
+fn restore(rollback: &Path) -> Result<(), String> {
+    let (_, bytes) =
+        read_package(&rollback.join("previous")).ok_or("The previous package is gone")?;
+    let mut command = if bytes.starts_with(b"MZ") {
+        let installer = std::env::temp_dir().join("smudge-rollback-setup.exe");
+        std::fs::write(&installer, &bytes).map_err(|e| e.to_string())?;
+        let mut command = Command::new(installer);
+        command.args(["/P", "/R", "/UPDATE"]);
+        command
+    } else if bytes.starts_with(&[0xD0, 0xCF, 0x11, 0xE0]) {
+        let installer = std::env::temp_dir().join("smudge-rollback.msi");
+        std::fs::write(&installer, &bytes).map_err(|e| e.to_string())?;
+        let mut command = Command::new("msiexec");
+        command
+            .arg("/i")
+            .arg(installer)
+            .args(["/passive", "AUTOLAUNCHAPP=True"]);
+        command
+    } else {
+        return Err("The previous package is zipped".to_string());
+    };
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Couldn't start the installer: {}", e))
+}
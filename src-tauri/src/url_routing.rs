@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+/// Sent to a window with the `OpenedPath`s routed to it
+#[cfg(target_os = "macos")]
+pub const EVENT: &str = "paths-opened";
+
+/// The folder or file a window owns. Files opened from Finder inside it go
+/// to that window rather than the main window's usual handling.
+struct Scope {
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    root: PathBuf,
+    /// The window's `NSWindow`, which is what tao identifies it by
+    #[cfg(target_os = "macos")]
+    ns_window: usize,
+}
+
+/// Every window's scope by label, shared with the router tao calls from
+/// AppKit's callbacks
+#[derive(Default, Clone)]
+pub struct WindowScopes(Arc<Mutex<HashMap<String, Scope>>>);
+
+/// A file routed to a window, with its note id if it's in the notes folder
+#[cfg(target_os = "macos")]
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedPath {
+    pub path: PathBuf,
+    pub note_id: Option<String>,
+}
+
+/// Start routing opened files to the window whose scope holds them. macOS
+/// only; elsewhere files arrive as launch arguments and go to the main
+/// window.
+pub fn install(app: &AppHandle) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        use tauri_runtime_wry::tao::platform::macos::{self, OpenUrlTarget};
+
+        let scopes = app.state::<WindowScopes>().inner().clone();
+        app.run_on_main_thread(move || {
+            macos::set_open_url_router(move |url| {
+                let Ok(path) = url.to_file_path() else {
+                    return OpenUrlTarget::App;
+                };
+                // The window whose scope holds the file most closely
+                let scopes = scopes.0.lock().expect("window scopes mutex");
+                scopes
+                    .values()
+                    .filter(|scope| path.starts_with(&scope.root))
+                    .max_by_key(|scope| scope.root.components().count())
+                    .map_or(OpenUrlTarget::App, |scope| {
+                        OpenUrlTarget::Window(macos::window_id_for_ns_window(
+                            scope.ns_window as *mut std::ffi::c_void,
+                        ))
+                    })
+            });
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = app;
+    Ok(())
+}
+
+/// Set the folder or file `window` owns; None gives it up
+pub fn set_scope(window: &WebviewWindow, root: Option<PathBuf>) -> Result<(), String> {
+    let scopes = window.state::<WindowScopes>();
+    let mut scopes = scopes.0.lock().expect("window scopes mutex");
+    let Some(root) = root else {
+        scopes.remove(window.label());
+        return Ok(());
+    };
+    let scope = Scope {
+        root,
+        #[cfg(target_os = "macos")]
+        ns_window: window.ns_window().map_err(|e| e.to_string())? as usize,
+    };
+    scopes.insert(window.label().to_string(), scope);
+    Ok(())
+}
+
+/// A window closed; it no longer owns anything
+pub fn forget(app: &AppHandle, label: &str) {
+    if let Some(scopes) = app.try_state::<WindowScopes>() {
+        scopes.0.lock().expect("window scopes mutex").remove(label);
+    }
+}
+
+/// Bring up the window tao routed `urls` to and hand it the markdown files
+/// among them
+#[cfg(target_os = "macos")]
+pub fn deliver(
+    app: &AppHandle,
+    window_id: tauri_runtime_wry::tao::window::WindowId,
+    urls: Vec<tauri::Url>,
+) {
+    use tauri::Emitter;
+    use tauri_runtime_wry::tao::platform::macos;
+
+    let label = {
+        let scopes = app.state::<WindowScopes>();
+        let scopes = scopes.0.lock().expect("window scopes mutex");
+        scopes
+            .iter()
+            .find(|(_, scope)| {
+                macos::window_id_for_ns_window(scope.ns_window as *mut std::ffi::c_void)
+                    == window_id
+            })
+            .map(|(label, _)| label.clone())
+    };
+    let Some(window) = label.and_then(|label| app.get_webview_window(&label)) else {
+        // The window closed before the event arrived
+        crate::handle_opened_urls(app, urls);
+        return;
+    };
+    let notes_folder = app
+        .try_state::<crate::AppState>()
+        .and_then(|state| {
+            state
+                .app_config
+                .read()
+                .expect("app_config read lock")
+                .notes_folder
+                .clone()
+        })
+        .map(PathBuf::from);
+    let files: Vec<PathBuf> = urls
+        .iter()
+        .filter_map(|url| url.to_file_path().ok())
+        .filter(|path| crate::is_markdown_file(path))
+        .collect();
+    crate::quarantine::opened(app, &files);
+    let paths: Vec<OpenedPath> = files
+        .into_iter()
+        .map(|path| {
+            let note_id = notes_folder
+                .as_ref()
+                .and_then(|folder| path.strip_prefix(folder).ok())
+                .map(|relative| relative.with_extension(""))
+                .and_then(|relative| relative.to_str().map(str::to_string));
+            OpenedPath { path, note_id }
+        })
+        .collect();
+    log::debug!(window = window.label(), count = paths.len(); "files routed to window");
+    if paths.is_empty() {
+        return;
+    }
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    let _ = window.emit_to(window.label(), EVENT, paths);
+}
@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::{db::Database, AppState};
+
+/// Each note's versions, newest first, by note id
+const NAMESPACE: &str = "versions";
+/// Where the contents are kept under the app data directory, one zstd file
+/// per distinct content, named by its SHA-256
+const OBJECTS_DIR: &str = "versions";
+const COMPRESSION_LEVEL: i32 = 3;
+/// Versions kept per note once thinned
+const MAX_VERSIONS: usize = 500;
+/// Every version younger than this is kept, so whatever a save replaced can
+/// be had back. Older ones are thinned to one each five minutes for the
+/// first hour, one an hour for the first day, and one a day after that.
+const KEEP_ALL_MS: u64 = 10 * 60 * 1000;
+const MINUTES_MS: u64 = 5 * 60 * 1000;
+const HOUR_MS: u64 = 60 * 60 * 1000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+/// Version ids that name a version the system keeps rather than smudge
+const SYSTEM_PREFIX: &str = "system:";
+
+/// Serializes snapshots, so two saves of a note don't drop each other's
+/// version
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Source {
+    /// Snapshotted by smudge as the note was saved or changed on disk
+    App,
+    /// Kept by macOS in the volume's version store, from apps that save
+    /// through `NSDocument` or from iCloud Drive
+    System,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Version {
+    /// The content's SHA-256 for smudge's versions, so saves of the same
+    /// content share an id. The system's are prefixed with `system:`.
+    pub id: String,
+    /// Milliseconds since the Unix epoch
+    pub saved_at: u64,
+    /// Bytes of content
+    pub size: u64,
+    pub source: Source,
+    /// The computer a system version was saved on, when it was another one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub computer: Option<String>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn database(app: &AppHandle) -> Result<Database, String> {
+    app.try_state::<Database>()
+        .map(|db| db.inner().clone())
+        .ok_or_else(|| "The database isn't open".to_string())
+}
+
+fn note_path(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    let state = app.state::<AppState>();
+    let app_config = state.app_config.read().expect("app_config read lock");
+    let folder = app_config
+        .notes_folder
+        .as_ref()
+        .ok_or("Notes folder not set")?;
+    Ok(Path::new(folder).join(format!("{}.md", id)))
+}
+
+fn objects_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(OBJECTS_DIR))
+}
+
+fn object_path(objects: &Path, hash: &str) -> PathBuf {
+    objects.join(&hash[..2]).join(format!("{}.zst", hash))
+}
+
+fn hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Store `content` unless the same content already is
+fn store(objects: &Path, hash: &str, content: &str) -> Result<(), String> {
+    let path = object_path(objects, hash);
+    if path.exists() {
+        return Ok(());
+    }
+    let dir = path.parent().expect("objects have a parent directory");
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let compressed =
+        zstd::encode_all(content.as_bytes(), COMPRESSION_LEVEL).map_err(|e| e.to_string())?;
+    // Written aside and renamed, so a crash never leaves half a version
+    let staged = path.with_extension("zst.part");
+    fs::write(&staged, compressed).map_err(|e| e.to_string())?;
+    fs::rename(&staged, &path).map_err(|e| e.to_string())
+}
+
+fn load(objects: &Path, hash: &str) -> Result<String, String> {
+    let valid = hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit());
+    if !valid {
+        return Err(format!("\"{}\" isn't a version", hash));
+    }
+    let compressed = fs::read(object_path(objects, hash))
+        .map_err(|_| "The version's content is missing".to_string())?;
+    let content = zstd::decode_all(compressed.as_slice()).map_err(|e| e.to_string())?;
+    String::from_utf8(content).map_err(|e| e.to_string())
+}
+
+/// Keep fewer versions as they age, like Time Machine: the newest in each
+/// period stands for it
+fn thin(versions: &mut Vec<Version>, now: u64) {
+    let mut periods = HashSet::new();
+    versions.retain(|version| {
+        let age = now.saturating_sub(version.saved_at);
+        let width = if age < KEEP_ALL_MS {
+            return true;
+        } else if age < HOUR_MS {
+            MINUTES_MS
+        } else if age < DAY_MS {
+            HOUR_MS
+        } else {
+            DAY_MS
+        };
+        periods.insert((width, version.saved_at / width))
+    });
+    versions.truncate(MAX_VERSIONS);
+}
+
+/// Remove the contents in `hashes` no note's history refers to any more
+fn collect_garbage(db: &Database, objects: &Path, hashes: HashSet<String>) -> Result<(), String> {
+    if hashes.is_empty() {
+        return Ok(());
+    }
+    let referenced: HashSet<String> = db
+        .list::<Vec<Version>>(NAMESPACE)?
+        .into_iter()
+        .flat_map(|(_, versions)| versions.into_iter().map(|version| version.id))
+        .collect();
+    for hash in hashes.difference(&referenced) {
+        let _ = fs::remove_file(object_path(objects, hash));
+    }
+    Ok(())
+}
+
+/// Record `content` as the note's newest version, unless it's what the
+/// newest already has. Older versions are thinned out as this one joins them.
+pub fn snapshot(app: &AppHandle, id: &str, content: &str) -> Result<(), String> {
+    let db = database(app)?;
+    let objects = objects_dir(app)?;
+    let hash = hash(content);
+    let _lock = LOCK.lock().expect("versions mutex");
+    let mut versions: Vec<Version> = db.get(NAMESPACE, id)?.unwrap_or_default();
+    if versions.first().is_some_and(|newest| newest.id == hash) {
+        return Ok(());
+    }
+    store(&objects, &hash, content)?;
+    let now = now_ms();
+    versions.insert(
+        0,
+        Version {
+            id: hash,
+            saved_at: now,
+            size: content.len() as u64,
+            source: Source::App,
+            computer: None,
+        },
+    );
+    let before: HashSet<String> = versions.iter().map(|v| v.id.clone()).collect();
+    thin(&mut versions, now);
+    db.put(NAMESPACE, id, &versions)?;
+    let after: HashSet<String> = versions.into_iter().map(|v| v.id).collect();
+    collect_garbage(&db, &objects, before.difference(&after).cloned().collect())
+}
+
+/// Snapshot a note whose file changed on disk, such as by sync or another
+/// app. Saves from smudge come through here too and are skipped, since
+/// they're already the newest version.
+pub fn file_changed(app: &AppHandle, id: &str, path: &Path) {
+    let Ok(content) = fs::read_to_string(path) else {
+        return;
+    };
+    if let Err(e) = snapshot(app, id, &content) {
+        log::warn!(note = id, error:% = e; "version not kept");
+    }
+}
+
+/// Carry the history over when a new title renames the note
+pub fn renamed(app: &AppHandle, old_id: &str, new_id: &str) -> Result<(), String> {
+    let db = database(app)?;
+    let _lock = LOCK.lock().expect("versions mutex");
+    let Some(mut old) = db.get::<Vec<Version>>(NAMESPACE, old_id)? else {
+        return Ok(());
+    };
+    let new: Vec<Version> = db.get(NAMESPACE, new_id)?.unwrap_or_default();
+    old.extend(new);
+    old.sort_by_key(|version| std::cmp::Reverse(version.saved_at));
+    old.dedup_by(|a, b| a.id == b.id && a.saved_at == b.saved_at);
+    db.put(NAMESPACE, new_id, &old)?;
+    db.delete(NAMESPACE, old_id)
+}
+
+/// The note's versions, smudge's and the system's, newest first. Kept after
+/// the note is deleted, so it can be brought back.
+pub fn history(app: &AppHandle, id: &str) -> Result<Vec<Version>, String> {
+    let mut versions: Vec<Version> = database(app)?.get(NAMESPACE, id)?.unwrap_or_default();
+    let path = note_path(app, id)?;
+    versions.extend(
+        platform::system_versions(&path)
+            .into_iter()
+            .map(|version| Version {
+                id: format!("{}{}", SYSTEM_PREFIX, version.path.display()),
+                saved_at: version.saved_at,
+                size: fs::metadata(&version.path).map_or(0, |m| m.len()),
+                source: Source::System,
+                computer: version.computer,
+            }),
+    );
+    versions.sort_by_key(|version| std::cmp::Reverse(version.saved_at));
+    Ok(versions)
+}
+
+/// The content of one of the note's versions
+pub fn content(app: &AppHandle, id: &str, version: &str) -> Result<String, String> {
+    let Some(system_path) = version.strip_prefix(SYSTEM_PREFIX) else {
+        return load(&objects_dir(app)?, version);
+    };
+    // Only versions of this note, so the id can't name any other file
+    let path = note_path(app, id)?;
+    let known = platform::system_versions(&path)
+        .into_iter()
+        .any(|known| known.path == Path::new(system_path));
+    if !known {
+        return Err("The system no longer has that version".to_string());
+    }
+    fs::read_to_string(system_path).map_err(|e| e.to_string())
+}
+
+/// A unified diff from version `from` to version `to`, or to the note as it
+/// is on disk when `to` is `None`
+pub fn diff(app: &AppHandle, id: &str, from: &str, to: Option<&str>) -> Result<String, String> {
+    let old = content(app, id, from)?;
+    let new = match to {
+        Some(to) => content(app, id, to)?,
+        None => fs::read_to_string(note_path(app, id)?).map_err(|e| e.to_string())?,
+    };
+    Ok(diffy::create_patch(&old, &new).to_string())
+}
+
+struct SystemVersion {
+    path: PathBuf,
+    saved_at: u64,
+    computer: Option<String>,
+}
+
+/// The versions `NSFileVersion` lists, which the "Browse All Versions"
+/// browser of document-based apps shows. They're only read: smudge doesn't
+/// save through `NSDocument`, so adding its own would show them nowhere.
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::SystemVersion;
+    use objc2_foundation::{NSFileVersion, NSURL};
+    use std::path::Path;
+
+    pub fn system_versions(path: &Path) -> Vec<SystemVersion> {
+        let Some(url) = NSURL::from_file_path(path) else {
+            return Vec::new();
+        };
+        let Some(versions) = NSFileVersion::otherVersionsOfItemAtURL(&url) else {
+            return Vec::new();
+        };
+        versions
+            .iter()
+            .filter_map(|version| {
+                let path = version.URL().to_file_path()?;
+                let saved_at = version.modificationDate()?.timeIntervalSince1970();
+                Some(SystemVersion {
+                    path,
+                    saved_at: (saved_at * 1000.0) as u64,
+                    computer: version
+                        .localizedNameOfSavingComputer()
+                        .map(|name| name.to_string()),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Only macOS keeps versions of its own
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::SystemVersion;
+    use std::path::Path;
+
+    pub fn system_versions(_path: &Path) -> Vec<SystemVersion> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,117 @@
+use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What happens to a source file once it's imported
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportAction {
+    /// Delete the source after importing
+    Move,
+    /// Keep the source; later edits to it are not re-imported
+    #[default]
+    Copy,
+    /// Keep the source and re-import it whenever it changes
+    Leave,
+}
+
+/// A folder whose new files are imported into the notes folder automatically
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchFolder {
+    pub path: String,
+    #[serde(default)]
+    pub action: ImportAction,
+    /// Appended to imported notes as `#tag`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// File extensions to import, without the dot. Empty means markdown only.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+}
+
+impl WatchFolder {
+    /// Whether `path` is a file this folder's rule applies to
+    pub fn matches(&self, path: &Path) -> bool {
+        let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+            return false;
+        };
+        // Skip hidden and temporary files (editors' swap files, partial downloads)
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.') || n.starts_with('~'));
+        if hidden {
+            return false;
+        }
+        if self.extensions.is_empty() {
+            ["md", "markdown", "mdown", "mkd"]
+                .iter()
+                .any(|e| ext.eq_ignore_ascii_case(e))
+        } else {
+            self.extensions
+                .iter()
+                .any(|e| ext.eq_ignore_ascii_case(e.trim_start_matches('.')))
+        }
+    }
+}
+
+/// Add `#tag`s for the folder's tags that the note doesn't already have
+pub fn apply_tags(content: &str, tags: &[String]) -> String {
+    let missing: Vec<String> = tags
+        .iter()
+        .map(|t| t.trim().trim_start_matches('#').replace(' ', "-"))
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("#{}", t))
+        .filter(|tag| {
+            !content
+                .split_whitespace()
+                .any(|word| word.eq_ignore_ascii_case(tag))
+        })
+        .collect();
+
+    if missing.is_empty() {
+        return content.to_string();
+    }
+    format!("{}\n\n{}\n", content.trim_end(), missing.join(" "))
+}
+
+/// Watch every folder for new or changed files, calling `on_file` with the
+/// matching rule. Folders that don't exist are skipped.
+pub fn watch<F>(folders: Vec<WatchFolder>, on_file: F) -> Result<RecommendedWatcher, String>
+where
+    F: Fn(&WatchFolder, PathBuf) + Send + 'static,
+{
+    let rules = folders.clone();
+    let mut watcher = RecommendedWatcher::new(
+        move |res: Result<notify::Event, notify::Error>| {
+            let Ok(event) = res else {
+                return;
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                return;
+            }
+            for path in event.paths {
+                let rule = rules
+                    .iter()
+                    .find(|f| path.parent() == Some(Path::new(&f.path)) && f.matches(&path));
+                if let Some(rule) = rule {
+                    on_file(rule, path);
+                }
+            }
+        },
+        Config::default(),
+    )
+    .map_err(|e| e.to_string())?;
+
+    for folder in &folders {
+        let path = Path::new(&folder.path);
+        if path.is_dir() {
+            watcher
+                .watch(path, RecursiveMode::NonRecursive)
+                .map_err(|e| format!("Failed to watch {}: {}", folder.path, e))?;
+        }
+    }
+
+    Ok(watcher)
+}
@@ -0,0 +1,427 @@
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, watch};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+
+use crate::preferences;
+
+/// Received messages kept per connection for a reloaded page to catch up on
+const REPLAY_MESSAGES: usize = 256;
+/// Sends queued while a connection is down. Past this, sending fails.
+const SEND_QUEUE: usize = 256;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Reconnecting waits this long after the first failure, doubling with each
+/// one after up to the `websocket.maxBackoffSecs` preference. How often
+/// connections are pinged and how long they may be quiet are preferences
+/// too.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionState {
+    Connecting,
+    Open,
+    /// Lost, and waiting to try again
+    Reconnecting,
+    /// Closed by `close`; it won't reconnect
+    Closed,
+}
+
+/// A message's data: text as is, binary as base64
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+pub enum Payload {
+    Text(String),
+    Binary(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceivedMessage {
+    /// Counts up from 1 over the connection's life, across reconnects
+    pub seq: u64,
+    pub payload: Payload,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketStatus {
+    pub id: String,
+    pub url: String,
+    pub state: ConnectionState,
+    /// Failed attempts since the connection was last open
+    pub attempt: u32,
+    /// Why the last attempt failed or the connection was lost
+    pub error: Option<String>,
+    /// `seq` of the last message received, 0 before the first
+    pub last_seq: u64,
+}
+
+/// What's emitted on `websocket://<id>`
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+enum WebSocketEvent<'a> {
+    Message(&'a ReceivedMessage),
+    Status(&'a WebSocketStatus),
+}
+
+struct Connection {
+    status: Mutex<WebSocketStatus>,
+    replay: Mutex<VecDeque<ReceivedMessage>>,
+    /// Messages to send, which wait here while the connection is down
+    outgoing: mpsc::Sender<Message>,
+    closed: watch::Sender<bool>,
+}
+
+impl Connection {
+    fn status(&self) -> WebSocketStatus {
+        self.status.lock().expect("websocket status mutex").clone()
+    }
+
+    fn update(&self, app: &AppHandle, update: impl FnOnce(&mut WebSocketStatus)) {
+        let status = {
+            let mut status = self.status.lock().expect("websocket status mutex");
+            update(&mut status);
+            status.clone()
+        };
+        let _ = app.emit(
+            &format!("websocket://{}", status.id),
+            WebSocketEvent::Status(&status),
+        );
+    }
+
+    fn received(&self, app: &AppHandle, payload: Payload) {
+        let (id, message) = {
+            let mut status = self.status.lock().expect("websocket status mutex");
+            status.last_seq += 1;
+            let message = ReceivedMessage {
+                seq: status.last_seq,
+                payload,
+            };
+            (status.id.clone(), message)
+        };
+        let _ = app.emit(
+            &format!("websocket://{}", id),
+            WebSocketEvent::Message(&message),
+        );
+        let mut replay = self.replay.lock().expect("websocket replay mutex");
+        if replay.len() == REPLAY_MESSAGES {
+            replay.pop_front();
+        }
+        replay.push_back(message);
+    }
+}
+
+/// WebSocket connections the backend keeps open for the frontend, managed
+/// as Tauri state. They belong to the app rather than a page, so they
+/// survive reloads: a reloaded page calls `connect` with the same ID to get
+/// the connection back, then `replay` for what it missed. Lost connections
+/// are retried with backoff until closed.
+#[derive(Default)]
+pub struct WebSockets {
+    connections: Mutex<HashMap<String, Arc<Connection>>>,
+    next_id: AtomicU64,
+}
+
+impl WebSockets {
+    /// Open a connection to `url`, or with the ID of one already open,
+    /// return that one's status. `headers` go with every attempt, e.g.
+    /// `Sec-WebSocket-Protocol`.
+    pub fn connect(
+        &self,
+        app: &AppHandle,
+        url: &str,
+        headers: HashMap<String, String>,
+        id: Option<String>,
+    ) -> Result<WebSocketStatus, String> {
+        let mut connections = self.connections.lock().expect("websockets mutex");
+        if let Some(connection) = id.as_ref().and_then(|id| connections.get(id)) {
+            return Ok(connection.status());
+        }
+        let url = crate::http::client(app)?.check(url, &["ws", "wss"])?;
+        let mut request = url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| e.to_string())?;
+        for (name, value) in &headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| format!("Invalid header {}: {}", name, e))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| format!("Invalid value for header {}: {}", name, e))?;
+            request.headers_mut().append(name, value);
+        }
+
+        let id = id.unwrap_or_else(|| {
+            format!("websocket-{}", self.next_id.fetch_add(1, Ordering::Relaxed))
+        });
+        let (outgoing, receiver) = mpsc::channel(SEND_QUEUE);
+        let status = WebSocketStatus {
+            id: id.clone(),
+            url: url.to_string(),
+            state: ConnectionState::Connecting,
+            attempt: 0,
+            error: None,
+            last_seq: 0,
+        };
+        let connection = Arc::new(Connection {
+            status: Mutex::new(status.clone()),
+            replay: Mutex::new(VecDeque::new()),
+            outgoing,
+            closed: watch::channel(false).0,
+        });
+        connections.insert(id, Arc::clone(&connection));
+        tauri::async_runtime::spawn(maintain(app.clone(), connection, request, receiver));
+        Ok(status)
+    }
+
+    fn get(&self, id: &str) -> Result<Arc<Connection>, String> {
+        self.connections
+            .lock()
+            .expect("websockets mutex")
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("No WebSocket {}", id))
+    }
+
+    /// Send `payload`, or queue it until the connection is back
+    pub fn send(&self, id: &str, payload: Payload) -> Result<(), String> {
+        let message = match payload {
+            Payload::Text(text) => Message::text(text),
+            Payload::Binary(data) => Message::binary(
+                base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .map_err(|e| format!("Invalid base64 data: {}", e))?,
+            ),
+        };
+        self.get(id)?
+            .outgoing
+            .try_send(message)
+            .map_err(|e| match e {
+                mpsc::error::TrySendError::Full(_) => {
+                    "Too many messages are waiting to be sent".to_string()
+                }
+                mpsc::error::TrySendError::Closed(_) => format!("WebSocket {} is closed", id),
+            })
+    }
+
+    /// Close the connection for good
+    pub fn close(&self, id: &str) -> Result<(), String> {
+        let connection = self
+            .connections
+            .lock()
+            .expect("websockets mutex")
+            .remove(id)
+            .ok_or_else(|| format!("No WebSocket {}", id))?;
+        connection.closed.send_replace(true);
+        Ok(())
+    }
+
+    /// Messages received after `after_seq` that are still kept
+    pub fn replay(&self, id: &str, after_seq: u64) -> Result<Vec<ReceivedMessage>, String> {
+        Ok(self
+            .get(id)?
+            .replay
+            .lock()
+            .expect("websocket replay mutex")
+            .iter()
+            .filter(|message| message.seq > after_seq)
+            .cloned()
+            .collect())
+    }
+
+    pub fn list(&self) -> Vec<WebSocketStatus> {
+        self.connections
+            .lock()
+            .expect("websockets mutex")
+            .values()
+            .map(|connection| connection.status())
+            .collect()
+    }
+}
+
+/// TLS that trusts what the system trusts, built once
+fn connector() -> Option<Connector> {
+    static CONFIG: OnceLock<Option<Arc<rustls::ClientConfig>>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            use rustls_platform_verifier::ConfigVerifierExt;
+            rustls::ClientConfig::with_platform_verifier()
+                .map(Arc::new)
+                .map_err(|e| log::warn!(error:% = e; "system TLS verifier unavailable"))
+                .ok()
+        })
+        .clone()
+        .map(Connector::Rustls)
+}
+
+/// Why an open connection ended
+enum Ended {
+    Closed,
+    Lost(String),
+}
+
+/// Keeps `connection` open until it's closed, reconnecting when it's lost
+async fn maintain(
+    app: AppHandle,
+    connection: Arc<Connection>,
+    request: Request,
+    mut outgoing: mpsc::Receiver<Message>,
+) {
+    let mut closed = connection.closed.subscribe();
+    // What was being sent when the connection was lost, sent first after
+    // it's back
+    let mut unsent = None;
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let attempt = tokio::select! {
+            attempt = tokio::time::timeout(
+                CONNECT_TIMEOUT,
+                tokio_tungstenite::connect_async_tls_with_config(
+                    request.clone(),
+                    None,
+                    false,
+                    connector(),
+                ),
+            ) => attempt,
+            () = until_closed(&mut closed) => break,
+        };
+        let error = match attempt {
+            Ok(Ok((stream, _))) => {
+                backoff = MIN_BACKOFF;
+                connection.update(&app, |status| {
+                    status.state = ConnectionState::Open;
+                    status.attempt = 0;
+                    status.error = None;
+                });
+                let relay = Relay {
+                    app: &app,
+                    connection: &connection,
+                    outgoing: &mut outgoing,
+                    closed: &mut closed,
+                    unsent: &mut unsent,
+                };
+                match relay.run(stream).await {
+                    Ended::Closed => break,
+                    Ended::Lost(error) => error,
+                }
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "Timed out connecting".to_string(),
+        };
+
+        connection.update(&app, |status| {
+            status.state = ConnectionState::Reconnecting;
+            status.attempt += 1;
+            status.error = Some(error);
+        });
+        tokio::select! {
+            () = tokio::time::sleep(backoff + jitter()) => {}
+            () = until_closed(&mut closed) => break,
+        }
+        let max_backoff = preferences::current(&app).websocket.max_backoff_secs;
+        backoff = (backoff * 2).min(Duration::from_secs(max_backoff));
+        connection.update(&app, |status| status.state = ConnectionState::Connecting);
+    }
+    connection.update(&app, |status| status.state = ConnectionState::Closed);
+}
+
+/// Waits for `close`. The borrow `wait_for` returns isn't `Send`, so it's
+/// dropped here rather than held in a `select!`.
+async fn until_closed(closed: &mut watch::Receiver<bool>) {
+    let _ = closed.wait_for(|closed| *closed).await;
+}
+
+/// Up to a second, so clients dropped together don't all come back at once
+fn jitter() -> Duration {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |now| now.subsec_nanos());
+    Duration::from_millis(u64::from(nanos) % 1000)
+}
+
+/// One open connection's traffic
+struct Relay<'a> {
+    app: &'a AppHandle,
+    connection: &'a Connection,
+    outgoing: &'a mut mpsc::Receiver<Message>,
+    closed: &'a mut watch::Receiver<bool>,
+    unsent: &'a mut Option<Message>,
+}
+
+impl Relay<'_> {
+    /// Relays `stream` until it's closed or lost, pinging it to notice when
+    /// it goes quiet
+    async fn run(self, stream: WebSocketStream<MaybeTlsStream<TcpStream>>) -> Ended {
+        let (mut sink, mut stream) = stream.split();
+        if let Some(message) = self.unsent.take() {
+            if let Err(e) = sink.send(message.clone()).await {
+                *self.unsent = Some(message);
+                return Ended::Lost(e.to_string());
+            }
+        }
+        let preferences = preferences::current(self.app);
+        let idle_timeout = Duration::from_secs(preferences.websocket.idle_timeout_secs);
+        let mut ping = tokio::time::interval(Duration::from_secs(
+            preferences.websocket.ping_interval_secs,
+        ));
+        ping.tick().await;
+        let mut last_seen = Instant::now();
+        loop {
+            tokio::select! {
+                Some(message) = self.outgoing.recv() => {
+                    if let Err(e) = sink.send(message.clone()).await {
+                        *self.unsent = Some(message);
+                        return Ended::Lost(e.to_string());
+                    }
+                }
+                () = until_closed(self.closed) => {
+                    let _ = sink.send(Message::Close(None)).await;
+                    let _ = sink.close().await;
+                    return Ended::Closed;
+                }
+                message = stream.next() => {
+                    last_seen = Instant::now();
+                    match message {
+                        Some(Ok(Message::Text(text))) => {
+                            self.connection
+                                .received(self.app, Payload::Text(text.to_string()));
+                        }
+                        Some(Ok(Message::Binary(data))) => {
+                            let data = base64::engine::general_purpose::STANDARD.encode(data);
+                            self.connection.received(self.app, Payload::Binary(data));
+                        }
+                        // Pings are answered by tungstenite as the stream is read
+                        Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => {}
+                        Some(Ok(Message::Close(frame))) => {
+                            return Ended::Lost(frame.map_or_else(
+                                || "Closed by the server".to_string(),
+                                |frame| format!("Closed by the server: {} {}", frame.code, frame.reason),
+                            ));
+                        }
+                        Some(Err(e)) => return Ended::Lost(e.to_string()),
+                        None => return Ended::Lost("Connection closed".to_string()),
+                    }
+                }
+                _ = ping.tick() => {
+                    if last_seen.elapsed() >= idle_timeout {
+                        return Ended::Lost("The server stopped responding".to_string());
+                    }
+                    if let Err(e) = sink.send(Message::Ping(Default::default())).await {
+                        return Ended::Lost(e.to_string());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,605 @@
+use crate::preferences;
+use cookie::time::OffsetDateTime;
+use cookie::{Cookie, SameSite};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{AppHandle, Manager, Webview, WebviewWindow};
+
+/// A kind of data sites keep in the web view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Kind {
+    Cookies,
+    /// Local and session storage
+    LocalStorage,
+    IndexedDb,
+    /// The HTTP cache and the Cache API's storage
+    Cache,
+    /// Service workers, WebSQL, file systems and the like
+    Other,
+}
+
+#[cfg(not(windows))]
+impl Kind {
+    pub const ALL: [Kind; 5] = [
+        Kind::Cookies,
+        Kind::LocalStorage,
+        Kind::IndexedDb,
+        Kind::Cache,
+        Kind::Other,
+    ];
+}
+
+/// What one site keeps in the web view
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteData {
+    /// The site's domain, such as "example.com"
+    pub site: String,
+    pub kinds: Vec<Kind>,
+}
+
+/// A cookie as it's exported and imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedCookie {
+    name: String,
+    value: String,
+    domain: Option<String>,
+    path: Option<String>,
+    /// Seconds since the Unix epoch; session cookies have none
+    expires: Option<i64>,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    /// "strict", "lax" or "none"
+    same_site: Option<String>,
+}
+
+impl From<&Cookie<'_>> for ExportedCookie {
+    fn from(cookie: &Cookie<'_>) -> Self {
+        Self {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain: cookie.domain().map(str::to_string),
+            path: cookie.path().map(str::to_string),
+            expires: cookie
+                .expires_datetime()
+                .map(OffsetDateTime::unix_timestamp),
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            same_site: cookie.same_site().map(|same_site| match same_site {
+                SameSite::Strict => "strict".to_string(),
+                SameSite::Lax => "lax".to_string(),
+                SameSite::None => "none".to_string(),
+            }),
+        }
+    }
+}
+
+impl ExportedCookie {
+    fn into_cookie(self) -> Result<Cookie<'static>, String> {
+        let mut cookie = Cookie::new(self.name, self.value);
+        if let Some(domain) = self.domain {
+            cookie.set_domain(domain);
+        }
+        if let Some(path) = self.path {
+            cookie.set_path(path);
+        }
+        if let Some(expires) = self.expires {
+            let expires = OffsetDateTime::from_unix_timestamp(expires)
+                .map_err(|e| format!("Cookie {} expires at no time: {}", cookie.name(), e))?;
+            cookie.set_expires(expires);
+        }
+        cookie.set_secure(self.secure);
+        cookie.set_http_only(self.http_only);
+        if let Some(same_site) = self.same_site {
+            cookie.set_same_site(match same_site.as_str() {
+                "strict" => SameSite::Strict,
+                "lax" => SameSite::Lax,
+                "none" => SameSite::None,
+                other => {
+                    return Err(format!(
+                        "Cookie {} has an unknown sameSite {}",
+                        cookie.name(),
+                        other
+                    ))
+                }
+            });
+        }
+        Ok(cookie)
+    }
+}
+
+/// A cookie's domain as a site, without the leading dot that lets
+/// subdomains see it
+#[cfg(not(target_os = "macos"))]
+fn cookie_site(cookie: &Cookie<'_>) -> Option<String> {
+    cookie
+        .domain()
+        .map(|domain| domain.trim_start_matches('.').to_ascii_lowercase())
+}
+
+/// The sites with cookies, from the cookie store Tauri exposes, for where
+/// the web view can't list its other data
+#[cfg(not(target_os = "macos"))]
+fn cookie_sites(window: &WebviewWindow) -> Result<Vec<SiteData>, String> {
+    let mut sites: std::collections::BTreeMap<String, Vec<Kind>> = Default::default();
+    for cookie in window.cookies().map_err(|e| e.to_string())? {
+        if let Some(site) = cookie_site(&cookie) {
+            sites.insert(site, vec![Kind::Cookies]);
+        }
+    }
+    Ok(sites
+        .into_iter()
+        .map(|(site, kinds)| SiteData { site, kinds })
+        .collect())
+}
+
+/// Every site keeping data in the web views, which share one store. On
+/// Windows and Linux only sites with cookies are listed.
+pub async fn sites(window: &WebviewWindow) -> Result<Vec<SiteData>, String> {
+    platform::sites(window).await
+}
+
+/// Remove `kinds` of data for `sites`, or for every site. The app's own
+/// pages keep their web storage under their origin too, so clearing every
+/// site resets it.
+pub async fn clear(
+    window: &WebviewWindow,
+    sites: Option<Vec<String>>,
+    kinds: Vec<Kind>,
+) -> Result<(), String> {
+    if kinds.is_empty() {
+        return Ok(());
+    }
+    let sites = sites.map(|sites| {
+        sites
+            .into_iter()
+            .map(|site| site.trim_start_matches('.').to_ascii_lowercase())
+            .collect::<Vec<_>>()
+    });
+    log::info!(sites:? = sites, kinds:? = kinds; "clearing web view data");
+    platform::clear(window, sites, kinds).await
+}
+
+/// Write every cookie in the web views to `path` as JSON. Returns how many
+/// there were.
+pub fn export_cookies(window: &WebviewWindow, path: &Path) -> Result<usize, String> {
+    let cookies: Vec<ExportedCookie> = window
+        .cookies()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(ExportedCookie::from)
+        .collect();
+    let json = serde_json::to_string_pretty(&cookies).map_err(|e| e.to_string())?;
+    std::fs::write(path, json)
+        .map_err(|e| format!("Couldn't save the cookies to {}: {}", path.display(), e))?;
+    Ok(cookies.len())
+}
+
+/// Set every cookie in the JSON file at `path`, as `export_cookies` writes
+/// it, in the web views. Returns how many there were.
+pub fn import_cookies(window: &WebviewWindow, path: &Path) -> Result<usize, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    let cookies: Vec<ExportedCookie> = serde_json::from_str(&json)
+        .map_err(|e| format!("{} isn't a cookie export: {}", path.display(), e))?;
+    let count = cookies.len();
+    for cookie in cookies {
+        window
+            .set_cookie(cookie.into_cookie()?)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(count)
+}
+
+/// Give `webview` the user agent and languages from the preferences
+pub fn apply(webview: &Webview, preferences: &preferences::Webview) {
+    let webview = webview.clone();
+    let preferences = preferences.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = platform::apply(&webview, &preferences).await {
+            log::warn!(label = webview.label(), error:% = e; "web view identity not applied");
+        }
+    });
+}
+
+/// Apply the preferences to a webview starting to load, when they differ
+/// from the web view's own
+pub fn page_load_started(webview: &Webview) {
+    let preferences = preferences::current(webview.app_handle());
+    if preferences.webview != preferences::Webview::default() {
+        apply(webview, &preferences.webview);
+    }
+}
+
+/// Apply the preferences to every web view again whenever they change
+pub fn watch(app: &AppHandle) {
+    preferences::on_change(app, |preferences| preferences.webview.clone(), {
+        let app = app.clone();
+        move |preferences| {
+            for window in app.webview_windows().values() {
+                apply(window.as_ref(), &preferences.webview);
+            }
+        }
+    });
+}
+
+/// The web view's WKWebsiteDataStore, which lists its records by domain
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Kind, SiteData};
+    use crate::preferences;
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::MainThreadMarker;
+    use objc2_foundation::{NSArray, NSSet, NSString};
+    use objc2_web_kit::{
+        WKWebView, WKWebsiteDataRecord, WKWebsiteDataStore, WKWebsiteDataTypeCookies,
+        WKWebsiteDataTypeDiskCache, WKWebsiteDataTypeFetchCache,
+        WKWebsiteDataTypeIndexedDBDatabases, WKWebsiteDataTypeLocalStorage,
+        WKWebsiteDataTypeMemoryCache, WKWebsiteDataTypeOfflineWebApplicationCache,
+        WKWebsiteDataTypeSessionStorage,
+    };
+    use std::cell::Cell;
+    use std::collections::BTreeSet;
+    use std::ptr::NonNull;
+    use tauri::webview::PlatformWebview;
+    use tauri::{Webview, WebviewWindow};
+
+    fn kind(data_type: &NSString) -> Kind {
+        let is = |types: &[&NSString]| types.contains(&data_type);
+        unsafe {
+            if is(&[WKWebsiteDataTypeCookies]) {
+                Kind::Cookies
+            } else if is(&[
+                WKWebsiteDataTypeLocalStorage,
+                WKWebsiteDataTypeSessionStorage,
+            ]) {
+                Kind::LocalStorage
+            } else if is(&[WKWebsiteDataTypeIndexedDBDatabases]) {
+                Kind::IndexedDb
+            } else if is(&[
+                WKWebsiteDataTypeDiskCache,
+                WKWebsiteDataTypeMemoryCache,
+                WKWebsiteDataTypeFetchCache,
+                WKWebsiteDataTypeOfflineWebApplicationCache,
+            ]) {
+                Kind::Cache
+            } else {
+                Kind::Other
+            }
+        }
+    }
+
+    /// The data types WebKit has that are one of `kinds`
+    fn data_types(kinds: &[Kind], mtm: MainThreadMarker) -> Retained<NSSet<NSString>> {
+        let types: Vec<Retained<NSString>> =
+            unsafe { WKWebsiteDataStore::allWebsiteDataTypes(mtm) }
+                .to_vec()
+                .into_iter()
+                .filter(|data_type| kinds.contains(&kind(data_type)))
+                .collect();
+        NSSet::from_retained_slice(&types)
+    }
+
+    fn data_store(webview: &PlatformWebview) -> Retained<WKWebsiteDataStore> {
+        let webview = unsafe { &*webview.inner().cast::<WKWebView>() };
+        unsafe { webview.configuration().websiteDataStore() }
+    }
+
+    /// Fetch the records holding `kinds` on the main thread, and hand them to
+    /// `then` there
+    async fn with_records<T: Send + 'static>(
+        window: &WebviewWindow,
+        kinds: Vec<Kind>,
+        then: impl FnOnce(
+                &WKWebsiteDataStore,
+                &NSSet<NSString>,
+                &NSArray<WKWebsiteDataRecord>,
+                tokio::sync::oneshot::Sender<T>,
+            ) + Send
+            + 'static,
+    ) -> Result<T, String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        window
+            .with_webview(move |webview| {
+                let mtm = MainThreadMarker::new().expect("web views are used on the main thread");
+                let store = data_store(&webview);
+                let types = data_types(&kinds, mtm);
+                let then = Cell::new(Some((then, tx)));
+                let fetched = {
+                    let store = store.clone();
+                    let types = types.clone();
+                    RcBlock::new(move |records: NonNull<NSArray<WKWebsiteDataRecord>>| {
+                        if let Some((then, tx)) = then.take() {
+                            then(&store, &types, unsafe { records.as_ref() }, tx);
+                        }
+                    })
+                };
+                unsafe { store.fetchDataRecordsOfTypes_completionHandler(&types, &fetched) };
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The web view didn't answer".to_string())
+    }
+
+    pub async fn sites(window: &WebviewWindow) -> Result<Vec<SiteData>, String> {
+        with_records(window, Kind::ALL.to_vec(), |_, _, records, tx| {
+            let mut sites: Vec<SiteData> = records
+                .iter()
+                .map(|record| {
+                    let kinds: BTreeSet<Kind> = unsafe { record.dataTypes() }
+                        .iter()
+                        .map(|data_type| kind(&data_type))
+                        .collect();
+                    SiteData {
+                        site: unsafe { record.displayName() }.to_string(),
+                        kinds: kinds.into_iter().collect(),
+                    }
+                })
+                .collect();
+            sites.sort_by(|a, b| a.site.cmp(&b.site));
+            let _ = tx.send(sites);
+        })
+        .await
+    }
+
+    pub async fn clear(
+        window: &WebviewWindow,
+        sites: Option<Vec<String>>,
+        kinds: Vec<Kind>,
+    ) -> Result<(), String> {
+        with_records(window, kinds, move |store, types, records, tx| {
+            let records: Vec<Retained<WKWebsiteDataRecord>> = records
+                .iter()
+                .filter(|record| match &sites {
+                    Some(sites) => {
+                        let site = unsafe { record.displayName() }.to_string();
+                        sites.contains(&site)
+                    }
+                    None => true,
+                })
+                .collect();
+            let tx = Cell::new(Some(tx));
+            let done = RcBlock::new(move || {
+                if let Some(tx) = tx.take() {
+                    let _ = tx.send(());
+                }
+            });
+            unsafe {
+                store.removeDataOfTypes_forDataRecords_completionHandler(
+                    types,
+                    &NSArray::from_retained_slice(&records),
+                    &done,
+                )
+            };
+        })
+        .await
+    }
+
+    pub async fn apply(
+        webview: &Webview,
+        preferences: &preferences::Webview,
+    ) -> Result<(), String> {
+        let user_agent = preferences.user_agent.clone();
+        webview
+            .with_webview(move |webview| {
+                let webview = unsafe { &*webview.inner().cast::<WKWebView>() };
+                let user_agent = (!user_agent.is_empty()).then(|| NSString::from_str(&user_agent));
+                unsafe { webview.setCustomUserAgent(user_agent.as_deref()) };
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// WebView2, through the DevTools protocol for single sites and the profile
+/// for every site. It has no way to list what sites keep but cookies, and
+/// its HTTP cache can only be cleared for every site at once.
+#[cfg(windows)]
+mod platform {
+    use super::{Kind, SiteData};
+    use crate::preferences;
+    use serde_json::json;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use tauri::{Webview, WebviewWindow};
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        ICoreWebView2, ICoreWebView2Profile2, ICoreWebView2_13, COREWEBVIEW2_BROWSING_DATA_KINDS,
+        COREWEBVIEW2_BROWSING_DATA_KINDS_CACHE_STORAGE, COREWEBVIEW2_BROWSING_DATA_KINDS_COOKIES,
+        COREWEBVIEW2_BROWSING_DATA_KINDS_DISK_CACHE, COREWEBVIEW2_BROWSING_DATA_KINDS_FILE_SYSTEMS,
+        COREWEBVIEW2_BROWSING_DATA_KINDS_INDEXED_DB,
+        COREWEBVIEW2_BROWSING_DATA_KINDS_LOCAL_STORAGE,
+        COREWEBVIEW2_BROWSING_DATA_KINDS_SERVICE_WORKERS, COREWEBVIEW2_BROWSING_DATA_KINDS_WEB_SQL,
+    };
+    use webview2_com::{
+        CallDevToolsProtocolMethodCompletedHandler, ClearBrowsingDataCompletedHandler,
+    };
+    use windows::core::{Interface, HSTRING};
+
+    type Done = Box<dyn FnOnce(windows::core::Result<()>)>;
+
+    /// Start an operation with the web view on the main thread, and wait for
+    /// it to finish with the `Done` it's given
+    async fn run(
+        webview: &Webview,
+        start: impl FnOnce(&ICoreWebView2, Done) -> windows::core::Result<()> + Send + 'static,
+    ) -> Result<(), String> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        webview
+            .with_webview(move |webview| {
+                let tx = Rc::new(Cell::new(Some(tx)));
+                let done: Done = {
+                    let tx = Rc::clone(&tx);
+                    Box::new(move |result| {
+                        if let Some(tx) = tx.take() {
+                            let _ = tx.send(result.map_err(|e| e.message()));
+                        }
+                    })
+                };
+                let started = unsafe { webview.controller().CoreWebView2() }
+                    .and_then(|core| start(&core, done));
+                if let Err(e) = started {
+                    if let Some(tx) = tx.take() {
+                        let _ = tx.send(Err(e.message()));
+                    }
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await
+            .map_err(|_| "The web view didn't answer".to_string())?
+    }
+
+    async fn devtools(
+        webview: &Webview,
+        method: &'static str,
+        params: serde_json::Value,
+    ) -> Result<(), String> {
+        run(webview, move |core, done| unsafe {
+            core.CallDevToolsProtocolMethod(
+                &HSTRING::from(method),
+                &HSTRING::from(params.to_string()),
+                &CallDevToolsProtocolMethodCompletedHandler::create(Box::new(
+                    move |result, _json| {
+                        done(result);
+                        Ok(())
+                    },
+                )),
+            )
+        })
+        .await
+    }
+
+    fn browsing_data_kinds(kinds: &[Kind]) -> COREWEBVIEW2_BROWSING_DATA_KINDS {
+        let bits = kinds
+            .iter()
+            .flat_map(|kind| match kind {
+                Kind::Cookies => vec![COREWEBVIEW2_BROWSING_DATA_KINDS_COOKIES],
+                Kind::LocalStorage => vec![COREWEBVIEW2_BROWSING_DATA_KINDS_LOCAL_STORAGE],
+                Kind::IndexedDb => vec![COREWEBVIEW2_BROWSING_DATA_KINDS_INDEXED_DB],
+                Kind::Cache => vec![
+                    COREWEBVIEW2_BROWSING_DATA_KINDS_DISK_CACHE,
+                    COREWEBVIEW2_BROWSING_DATA_KINDS_CACHE_STORAGE,
+                ],
+                Kind::Other => vec![
+                    COREWEBVIEW2_BROWSING_DATA_KINDS_FILE_SYSTEMS,
+                    COREWEBVIEW2_BROWSING_DATA_KINDS_WEB_SQL,
+                    COREWEBVIEW2_BROWSING_DATA_KINDS_SERVICE_WORKERS,
+                ],
+            })
+            .fold(0, |bits, kind| bits | kind.0);
+        COREWEBVIEW2_BROWSING_DATA_KINDS(bits)
+    }
+
+    /// The `storageTypes` of `Storage.clearDataForOrigin`
+    fn storage_types(kinds: &[Kind]) -> String {
+        kinds
+            .iter()
+            .map(|kind| match kind {
+                Kind::Cookies => "cookies",
+                Kind::LocalStorage => "local_storage",
+                Kind::IndexedDb => "indexeddb",
+                Kind::Cache => "cache_storage",
+                Kind::Other => "file_systems,websql,service_workers",
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub async fn sites(window: &WebviewWindow) -> Result<Vec<SiteData>, String> {
+        super::cookie_sites(window)
+    }
+
+    pub async fn clear(
+        window: &WebviewWindow,
+        sites: Option<Vec<String>>,
+        kinds: Vec<Kind>,
+    ) -> Result<(), String> {
+        let Some(sites) = sites else {
+            let kinds = browsing_data_kinds(&kinds);
+            return run(window.as_ref(), move |core, done| unsafe {
+                core.cast::<ICoreWebView2_13>()?
+                    .Profile()?
+                    .cast::<ICoreWebView2Profile2>()?
+                    .ClearBrowsingData(
+                        kinds,
+                        &ClearBrowsingDataCompletedHandler::create(Box::new(move |result| {
+                            done(result);
+                            Ok(())
+                        })),
+                    )
+            })
+            .await;
+        };
+        let storage_types = storage_types(&kinds);
+        for site in sites {
+            for scheme in ["https", "http"] {
+                let params = json!({
+                    "origin": format!("{}://{}", scheme, site),
+                    "storageTypes": storage_types,
+                });
+                devtools(window.as_ref(), "Storage.clearDataForOrigin", params).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// An empty user agent or language list leaves the web view's own
+    pub async fn apply(
+        webview: &Webview,
+        preferences: &preferences::Webview,
+    ) -> Result<(), String> {
+        let params = json!({
+            "userAgent": preferences.user_agent,
+            "acceptLanguage": preferences.accept_languages.join(","),
+        });
+        devtools(webview, "Emulation.setUserAgentOverride", params).await
+    }
+}
+
+/// Cookies through the store Tauri exposes; clearing anything else is only
+/// possible for every kind of data of every site at once
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::{Kind, SiteData};
+    use crate::preferences;
+    use tauri::{Webview, WebviewWindow};
+
+    pub async fn sites(window: &WebviewWindow) -> Result<Vec<SiteData>, String> {
+        super::cookie_sites(window)
+    }
+
+    pub async fn clear(
+        window: &WebviewWindow,
+        sites: Option<Vec<String>>,
+        kinds: Vec<Kind>,
+    ) -> Result<(), String> {
+        if sites.is_none() && Kind::ALL.iter().all(|kind| kinds.contains(kind)) {
+            return window.clear_all_browsing_data().map_err(|e| e.to_string());
+        }
+        if kinds.iter().any(|kind| *kind != Kind::Cookies) {
+            return Err("Only cookies can be cleared on their own here".to_string());
+        }
+        for cookie in window.cookies().map_err(|e| e.to_string())? {
+            let matches = match (&sites, super::cookie_site(&cookie)) {
+                (None, _) => true,
+                (Some(sites), Some(site)) => sites.contains(&site),
+                (Some(_), None) => false,
+            };
+            if matches {
+                window.delete_cookie(cookie).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// WebKitGTK's settings aren't reachable without its bindings
+    pub async fn apply(
+        _webview: &Webview,
+        _preferences: &preferences::Webview,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
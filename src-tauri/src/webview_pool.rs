@@ -0,0 +1,236 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::{activation, mini_window, power};
+
+/// Emitted to a warm window with the note ID it should show
+pub const CLAIM_EVENT: &str = "warm-claim";
+
+pub const DEFAULT_SIZE: usize = 1;
+pub const MAX_SIZE: usize = 3;
+
+/// How long after the main window's first paint the pool starts filling, so
+/// warming never competes with launch
+const IDLE_DELAY: Duration = Duration::from_secs(3);
+/// How often memory pressure is checked while the pool is running
+const PRESSURE_INTERVAL: Duration = Duration::from_secs(30);
+/// Wait before replacing a claimed window, so the claim gets the CPU first
+const REFILL_DELAY: Duration = Duration::from_secs(2);
+
+struct Warm {
+    window: WebviewWindow,
+    /// Set once the frontend is listening for its claim
+    ready: bool,
+}
+
+/// Hidden mini windows with the frontend already loaded. Opening the mini
+/// window takes one of these instead of paying for a new webview's startup.
+pub struct WebviewPool {
+    warm: Mutex<Vec<Warm>>,
+    size: AtomicUsize,
+    started: AtomicBool,
+    next_id: AtomicU32,
+}
+
+impl WebviewPool {
+    pub fn new(size: usize) -> Self {
+        Self {
+            warm: Mutex::new(Vec::new()),
+            size: AtomicUsize::new(size.min(MAX_SIZE)),
+            started: AtomicBool::new(false),
+            next_id: AtomicU32::new(1),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    pub fn is_warm(&self, label: &str) -> bool {
+        self.warm
+            .lock()
+            .expect("webview pool mutex")
+            .iter()
+            .any(|w| w.window.label() == label)
+    }
+
+    /// Called by a warm window's frontend once it can be claimed
+    pub fn mark_ready(&self, label: &str) {
+        let mut warm = self.warm.lock().expect("webview pool mutex");
+        if let Some(entry) = warm.iter_mut().find(|w| w.window.label() == label) {
+            entry.ready = true;
+        }
+    }
+
+    /// Take a ready window out of the pool, and schedule its replacement
+    pub fn take(&self, app: &AppHandle) -> Option<WebviewWindow> {
+        let window = {
+            let mut warm = self.warm.lock().expect("webview pool mutex");
+            let index = warm.iter().position(|w| w.ready)?;
+            warm.remove(index).window
+        };
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(REFILL_DELAY).await;
+            if let Some(pool) = app.try_state::<WebviewPool>() {
+                pool.fill(&app);
+            }
+        });
+        Some(window)
+    }
+
+    /// Change the pool size, closing or creating windows to match once the
+    /// pool has started
+    pub fn resize(&self, app: &AppHandle, size: usize) {
+        self.size.store(size.min(MAX_SIZE), Ordering::Relaxed);
+        if self.started.load(Ordering::Relaxed) {
+            self.trim(self.size());
+            self.fill(app);
+        }
+    }
+
+    fn fill(&self, app: &AppHandle) {
+        // Webview startup is the expensive part; a backgrounded app, or one on
+        // a locked or sleeping machine, catches up on the next pressure check
+        // after it's back
+        if memory::under_pressure() || activation::is_backgrounded(app) || power::is_idle(app) {
+            return;
+        }
+        let mut warm = self.warm.lock().expect("webview pool mutex");
+        while warm.len() < self.size() {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let label = format!("{}{}", mini_window::POOLED_PREFIX, id);
+            match mini_window::builder(app, &label, "index.html?warm".to_string()).build() {
+                Ok(window) => warm.push(Warm {
+                    window,
+                    ready: false,
+                }),
+                // Opening the mini window falls back to a cold webview
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// Close every warm window and stop refilling. Called when the main
+    /// window goes away, since hidden windows would keep the app running.
+    pub fn shutdown(&self) {
+        self.size.store(0, Ordering::Relaxed);
+        self.trim(0);
+    }
+
+    /// Close warm windows beyond `size`
+    fn trim(&self, size: usize) {
+        let mut warm = self.warm.lock().expect("webview pool mutex");
+        while warm.len() > size {
+            if let Some(entry) = warm.pop() {
+                let _ = entry.window.destroy();
+            }
+        }
+    }
+}
+
+/// Start filling the pool once the app is idle, and keep it filled, or
+/// empty while the system is short of memory. Runs once.
+pub fn start(app: &AppHandle) {
+    let Some(pool) = app.try_state::<WebviewPool>() else {
+        return;
+    };
+    if pool.started.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(IDLE_DELAY).await;
+        loop {
+            let pool = app.state::<WebviewPool>();
+            if memory::under_pressure() {
+                pool.trim(0);
+            } else {
+                pool.fill(&app);
+            }
+            tokio::time::sleep(PRESSURE_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+mod memory {
+    use std::ffi::{c_char, c_int, c_void};
+
+    /// Below this share of memory available, warm windows aren't worth it
+    const MIN_AVAILABLE_PERCENT: c_int = 25;
+
+    extern "C" {
+        fn sysctlbyname(
+            name: *const c_char,
+            oldp: *mut c_void,
+            oldlenp: *mut usize,
+            newp: *mut c_void,
+            newlen: usize,
+        ) -> c_int;
+    }
+
+    pub fn under_pressure() -> bool {
+        let mut level: c_int = 0;
+        let mut size = std::mem::size_of::<c_int>();
+        let result = unsafe {
+            sysctlbyname(
+                c"kern.memorystatus_level".as_ptr(),
+                (&mut level as *mut c_int).cast(),
+                &mut size,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        result == 0 && level < MIN_AVAILABLE_PERCENT
+    }
+}
+
+#[cfg(windows)]
+mod memory {
+    /// Above this share of memory in use, warm windows aren't worth it
+    const MAX_LOAD_PERCENT: u32 = 85;
+
+    #[repr(C)]
+    struct MemoryStatusEx {
+        length: u32,
+        memory_load: u32,
+        total_phys: u64,
+        avail_phys: u64,
+        total_page_file: u64,
+        avail_page_file: u64,
+        total_virtual: u64,
+        avail_virtual: u64,
+        avail_extended_virtual: u64,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GlobalMemoryStatusEx(buffer: *mut MemoryStatusEx) -> i32;
+    }
+
+    pub fn under_pressure() -> bool {
+        let mut status = MemoryStatusEx {
+            length: std::mem::size_of::<MemoryStatusEx>() as u32,
+            memory_load: 0,
+            total_phys: 0,
+            avail_phys: 0,
+            total_page_file: 0,
+            avail_page_file: 0,
+            total_virtual: 0,
+            avail_virtual: 0,
+            avail_extended_virtual: 0,
+        };
+        let ok = unsafe { GlobalMemoryStatusEx(&mut status) } != 0;
+        ok && status.memory_load > MAX_LOAD_PERCENT
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod memory {
+    pub fn under_pressure() -> bool {
+        false
+    }
+}
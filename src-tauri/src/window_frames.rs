@@ -0,0 +1,115 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, Window};
+
+use crate::{placement, windows};
+
+/// At most one `window-frame` event per window per frame
+const FRAME: Duration = Duration::from_millis(16);
+/// Quiet time after the last move or resize before `window-settled`
+const SETTLE: Duration = Duration::from_millis(150);
+
+/// Sent as `window-frame` at most once a frame while a window moves or
+/// resizes, and as `window-settled` once it stops. Position and size are in
+/// physical pixels.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowFrame {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+    pub maximized: bool,
+}
+
+impl WindowFrame {
+    fn of(window: &Window) -> Option<Self> {
+        let position = window.outer_position().ok()?;
+        let size = window.inner_size().ok()?;
+        Some(Self {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            scale_factor: window.scale_factor().unwrap_or(1.0),
+            maximized: window.is_maximized().unwrap_or(false),
+        })
+    }
+}
+
+struct Pending {
+    changed: bool,
+    last_change: Instant,
+}
+
+/// Coalesces moves and resizes so the frontend hears about each window's
+/// latest frame once per frame, instead of once per native event
+#[derive(Default)]
+pub struct WindowFrames {
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+impl WindowFrames {
+    /// Note that `window` moved or resized. The first change starts a
+    /// flusher for the window that runs until it settles.
+    pub fn changed(&self, window: &Window) {
+        let mut pending = self.pending.lock().expect("window frames mutex");
+        let label = window.label().to_string();
+        let flushing = pending.contains_key(&label);
+        pending.insert(
+            label.clone(),
+            Pending {
+                changed: true,
+                last_change: Instant::now(),
+            },
+        );
+        if !flushing {
+            tauri::async_runtime::spawn(flush(window.clone(), label));
+        }
+    }
+}
+
+async fn flush(window: Window, label: String) {
+    loop {
+        tokio::time::sleep(FRAME).await;
+        let frames = window.state::<WindowFrames>();
+        let settled = {
+            let mut pending = frames.pending.lock().expect("window frames mutex");
+            let Some(entry) = pending.get_mut(&label) else {
+                return;
+            };
+            if entry.changed {
+                entry.changed = false;
+                false
+            } else if entry.last_change.elapsed() >= SETTLE {
+                pending.remove(&label);
+                true
+            } else {
+                continue;
+            }
+        };
+
+        if settled {
+            // Placements only need the frame the window ends up with
+            if let Some(placements) = window.try_state::<placement::Placements>() {
+                placements.record(&window);
+                if let Err(e) = placements.persist() {
+                    log::warn!(error:% = e; "window placements not saved");
+                }
+            }
+            if let Some(windows) = window.try_state::<windows::Windows>() {
+                windows.frame_changed(&window);
+            }
+            if let Some(frame) = WindowFrame::of(&window) {
+                let _ = window.emit_to(label.as_str(), "window-settled", frame);
+            }
+            return;
+        }
+        if let Some(frame) = WindowFrame::of(&window) {
+            let _ = window.emit_to(label.as_str(), "window-frame", frame);
+        }
+    }
+}
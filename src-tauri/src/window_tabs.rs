@@ -0,0 +1,104 @@
+use serde::Serialize;
+use tauri::{Manager, WebviewWindow};
+
+/// Sent to a window with the `Change` when it becomes the selected tab of
+/// its group or closes as one of several tabs
+pub const EVENT: &str = "window-tab";
+
+/// Windows with this tabbing identifier tab together, Finder-style. The
+/// mini window floats and is kept out of them.
+pub const TABBING_IDENTIFIER: &str = "smudge.note";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Change {
+    Selected,
+    Closed,
+}
+
+/// Add the window labelled `tab` to `window`'s tab group and select it
+pub fn add(window: &WebviewWindow, tab: &str) -> Result<(), String> {
+    let tab = window
+        .get_webview_window(tab)
+        .ok_or_else(|| format!("No window labelled {}", tab))?;
+    platform::add(window, &tab)
+}
+
+/// Select the tab after `window`'s selected one, wrapping around
+pub fn select_next(window: &WebviewWindow) -> Result<(), String> {
+    platform::select_next(window)
+}
+
+/// Gather every note window into `window`'s tab group
+pub fn merge_all(window: &WebviewWindow) -> Result<(), String> {
+    platform::merge_all(window)
+}
+
+/// Tell the window tao reported `change` for
+#[cfg(target_os = "macos")]
+pub fn changed(
+    app: &tauri::AppHandle,
+    window_id: tauri_runtime_wry::tao::window::WindowId,
+    change: Change,
+) {
+    use tauri::Emitter;
+    use tauri_runtime_wry::tao::platform::macos;
+
+    let window = app.webview_windows().into_values().find(|window| {
+        window
+            .ns_window()
+            .is_ok_and(|ns_window| macos::window_id_for_ns_window(ns_window) == window_id)
+    });
+    let Some(window) = window else {
+        return;
+    };
+    log::debug!(window = window.label(), change:? = change; "window tab changed");
+    let _ = window.emit_to(window.label(), EVENT, change);
+}
+
+/// NSWindow tabbing, through tao
+#[cfg(target_os = "macos")]
+mod platform {
+    use crate::windows::with_ns_window;
+    use std::ffi::c_void;
+    use tauri::WebviewWindow;
+    use tauri_runtime_wry::tao::platform::macos;
+
+    pub fn add(window: &WebviewWindow, tab: &WebviewWindow) -> Result<(), String> {
+        let tab = tab.ns_window().map_err(|e| e.to_string())? as usize;
+        with_ns_window(window, move |ns_window| unsafe {
+            macos::add_tabbed_window(ns_window, tab as *mut c_void)
+        })
+    }
+
+    pub fn select_next(window: &WebviewWindow) -> Result<(), String> {
+        with_ns_window(window, |ns_window| unsafe {
+            macos::select_next_tab(ns_window)
+        })
+    }
+
+    pub fn merge_all(window: &WebviewWindow) -> Result<(), String> {
+        with_ns_window(window, |ns_window| unsafe {
+            macos::merge_all_windows(ns_window)
+        })
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use tauri::WebviewWindow;
+
+    const UNSUPPORTED: &str = "Window tabs are only available on macOS";
+
+    pub fn add(_window: &WebviewWindow, _tab: &WebviewWindow) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn select_next(_window: &WebviewWindow) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn merge_all(_window: &WebviewWindow) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
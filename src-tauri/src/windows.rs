@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime, WebviewWindow, Window};
+
+use crate::db::Database;
+use crate::{main_window, placement, spaces, state_restoration, titlebar, AppState};
+
+/// Prefix of note windows' labels, followed by a number the window keeps
+/// from one launch to the next. Also listed in capabilities/default.json.
+pub const PREFIX: &str = "note-";
+
+/// Sent to every window with the `WindowInfo` list when a window opens or
+/// closes
+pub const EVENT: &str = "windows-changed";
+
+/// Whether `label` is one of the windows whose layout is kept: the main
+/// window and note windows, not the mini window
+pub fn is_managed(label: &str) -> bool {
+    label == "main" || id(label).is_some()
+}
+
+fn id(label: &str) -> Option<u32> {
+    label.strip_prefix(PREFIX)?.parse().ok()
+}
+
+/// Run `f` with `window`'s NSWindow on the main thread, where AppKit wants
+/// it used
+#[cfg(target_os = "macos")]
+pub fn with_ns_window(
+    window: &WebviewWindow,
+    f: impl FnOnce(*mut std::ffi::c_void) + Send + 'static,
+) -> Result<(), String> {
+    // Raw pointers aren't Send; only the address crosses threads
+    let ns_window = window.ns_window().map_err(|e| e.to_string())? as usize;
+    window
+        .run_on_main_thread(move || f(ns_window as *mut std::ffi::c_void))
+        .map_err(|e| e.to_string())
+}
+
+/// Sent to a window with its zoom when it changes
+pub const ZOOM_EVENT: &str = "zoom-changed";
+
+/// The range a window can be zoomed in
+pub const MIN_ZOOM: f64 = 0.25;
+pub const MAX_ZOOM: f64 = 5.0;
+/// What zooming in and out steps through, like browsers
+const ZOOM_STEPS: [f64; 15] = [
+    0.25, 0.33, 0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 3.0, 5.0,
+];
+
+fn default_zoom() -> f64 {
+    1.0
+}
+
+/// What's kept of a window to open it again the way it was
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowLayout {
+    pub label: String,
+    /// Its frame and monitor once it last settled
+    pub frame: Option<placement::SavedPlacement>,
+    #[serde(default = "default_zoom")]
+    pub zoom: f64,
+    /// The window whose tab group it's in (macOS)
+    pub tab_group: Option<String>,
+    /// What its frontend saved for itself, e.g. the open note
+    pub state: Option<String>,
+}
+
+impl WindowLayout {
+    fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            frame: None,
+            zoom: default_zoom(),
+            tab_group: None,
+            state: None,
+        }
+    }
+}
+
+/// An open window, as `list_windows` reports it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowInfo {
+    pub label: String,
+    pub focused: bool,
+    pub visible: bool,
+    pub zoom: f64,
+    pub tab_group: Option<String>,
+}
+
+/// Database namespace and key of the layout, kept whole so windows reopen
+/// in order
+pub const NAMESPACE: &str = "windows";
+pub const LAYOUT_KEY: &str = "layout";
+
+/// The layout of every open window, persisted to the database whenever it
+/// changes so the next launch can open them all again
+pub struct Windows {
+    db: Option<Database>,
+    layout: Mutex<Vec<WindowLayout>>,
+    /// The zoom of windows whose layout isn't kept, like the mini window,
+    /// until they close
+    zooms: Mutex<HashMap<String, f64>>,
+    next_id: AtomicU32,
+}
+
+impl Windows {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let db = app.try_state::<Database>().map(|db| db.inner().clone());
+        let layout: Vec<WindowLayout> = db
+            .as_ref()
+            .and_then(|db| db.get(NAMESPACE, LAYOUT_KEY).ok().flatten())
+            .unwrap_or_default();
+        let next_id = layout
+            .iter()
+            .filter_map(|w| id(&w.label))
+            .max()
+            .unwrap_or(0)
+            + 1;
+        Self {
+            db,
+            layout: Mutex::new(layout),
+            zooms: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(next_id),
+        }
+    }
+
+    pub fn get(&self, label: &str) -> Option<WindowLayout> {
+        self.layout
+            .lock()
+            .expect("window layout mutex")
+            .iter()
+            .find(|w| w.label == label)
+            .cloned()
+    }
+
+    fn labels(&self) -> Vec<String> {
+        let layout = self.layout.lock().expect("window layout mutex");
+        layout.iter().map(|w| w.label.clone()).collect()
+    }
+
+    fn next_label(&self) -> String {
+        format!("{}{}", PREFIX, self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Change the layout of the window labelled `label`, adding it if it's
+    /// new, and save the layout
+    fn update(&self, label: &str, f: impl FnOnce(&mut WindowLayout)) {
+        if !is_managed(label) {
+            return;
+        }
+        {
+            let mut layout = self.layout.lock().expect("window layout mutex");
+            match layout.iter_mut().find(|w| w.label == label) {
+                Some(window) => f(window),
+                None => {
+                    let mut window = WindowLayout::new(label);
+                    f(&mut window);
+                    layout.push(window);
+                }
+            }
+        }
+        if let Err(e) = self.persist() {
+            log::warn!(error:% = e; "window layout not saved");
+        }
+    }
+
+    /// Record `window`'s frame as placement last recorded it
+    pub fn frame_changed<R: Runtime>(&self, window: &Window<R>) {
+        let frame = window
+            .try_state::<placement::Placements>()
+            .and_then(|placements| placements.get(window.label()));
+        if frame.is_some() {
+            self.update(window.label(), |layout| layout.frame = frame);
+        }
+    }
+
+    pub fn set_zoom(&self, label: &str, zoom: f64) {
+        if !is_managed(label) {
+            self.zooms
+                .lock()
+                .expect("window zooms mutex")
+                .insert(label.to_string(), zoom);
+            return;
+        }
+        self.update(label, |layout| layout.zoom = zoom);
+    }
+
+    pub fn zoom(&self, label: &str) -> f64 {
+        if let Some(layout) = self.get(label) {
+            return layout.zoom;
+        }
+        let zooms = self.zooms.lock().expect("window zooms mutex");
+        zooms.get(label).copied().unwrap_or_else(default_zoom)
+    }
+
+    pub fn set_state(&self, label: &str, state: Option<String>) {
+        self.update(label, |layout| layout.state = state);
+    }
+
+    /// Put the window labelled `tab` in `window`'s tab group
+    pub fn tabbed(&self, window: &str, tab: &str) {
+        let group = self
+            .get(window)
+            .and_then(|layout| layout.tab_group)
+            .unwrap_or_else(|| window.to_string());
+        self.update(tab, |layout| layout.tab_group = Some(group));
+    }
+
+    /// Put every other window in `window`'s tab group
+    pub fn merged(&self, window: &str) {
+        for label in self.labels() {
+            let group = (label != window).then(|| window.to_string());
+            self.update(&label, |layout| layout.tab_group = group);
+        }
+    }
+
+    /// Drop a window the user closed. Its tabs stay together, grouped
+    /// around the first of them.
+    pub fn forget(&self, label: &str) {
+        self.zooms.lock().expect("window zooms mutex").remove(label);
+        {
+            let mut layout = self.layout.lock().expect("window layout mutex");
+            layout.retain(|w| w.label != label);
+            let mut leader: Option<String> = None;
+            for window in layout.iter_mut() {
+                if window.tab_group.as_deref() != Some(label) {
+                    continue;
+                }
+                match &leader {
+                    Some(leader) => window.tab_group = Some(leader.clone()),
+                    None => {
+                        leader = Some(window.label.clone());
+                        window.tab_group = None;
+                    }
+                }
+            }
+        }
+        if let Err(e) = self.persist() {
+            log::warn!(error:% = e; "window layout not saved");
+        }
+    }
+
+    fn persist(&self) -> Result<(), String> {
+        let Some(db) = &self.db else {
+            return Ok(());
+        };
+        let layout = self.layout.lock().expect("window layout mutex");
+        db.put(NAMESPACE, LAYOUT_KEY, &*layout)
+    }
+}
+
+/// Every open main or note window
+pub fn list(app: &AppHandle) -> Vec<WindowInfo> {
+    let windows = app.state::<Windows>();
+    let mut list: Vec<WindowInfo> = app
+        .webview_windows()
+        .into_values()
+        .filter(|window| is_managed(window.label()))
+        .map(|window| {
+            let layout = windows.get(window.label());
+            WindowInfo {
+                label: window.label().to_string(),
+                focused: window.is_focused().unwrap_or(false),
+                visible: window.is_visible().unwrap_or(false),
+                zoom: layout.as_ref().map_or(default_zoom(), |l| l.zoom),
+                tab_group: layout.and_then(|l| l.tab_group),
+            }
+        })
+        .collect();
+    // The main window first, then note windows in the order they opened
+    list.sort_by_key(|window| id(&window.label).unwrap_or(0));
+    list
+}
+
+/// Tell every window which windows are open now
+pub fn changed(app: &AppHandle) {
+    let _ = app.emit(EVENT, list(app));
+}
+
+/// Open a new note window whose frontend starts from `state`
+pub fn open(app: &AppHandle, state: Option<String>) -> Result<WebviewWindow, String> {
+    let windows = app.state::<Windows>();
+    let label = windows.next_label();
+    windows.set_state(&label, state);
+    let window = build(app, &label)?;
+    changed(app);
+    Ok(window)
+}
+
+/// Create the note window labelled `label` the way its layout says, or
+/// placed by the placement policy if it has none
+fn build(app: &AppHandle, label: &str) -> Result<WebviewWindow, String> {
+    let snapshot = main_window::current_snapshot(app);
+    let window = main_window::builder(app, label, &snapshot)
+        .ok_or("The main window's config is missing")?
+        .build()
+        .map_err(|e| e.to_string())?;
+    let (titlebar_mode, policy) = {
+        let state = app.state::<AppState>();
+        let app_config = state.app_config.read().expect("app_config read lock");
+        (app_config.titlebar, app_config.placement_policy)
+    };
+    let _ = titlebar::apply(&window, titlebar_mode);
+    let _ = spaces::apply(&window, spaces::DOCUMENT);
+    let layout = app.state::<Windows>().get(label);
+    let frame = layout.as_ref().and_then(|layout| layout.frame.as_ref());
+    let _ = match frame {
+        Some(frame) => placement::restore(&window.as_ref().window(), frame),
+        None => placement::place(&window.as_ref().window(), policy),
+    };
+    let zoom = layout.map_or(default_zoom(), |layout| layout.zoom);
+    if zoom != default_zoom() {
+        let _ = window.set_zoom(zoom);
+    }
+    let _ = state_restoration::identify(&window);
+    window.show().map_err(|e| e.to_string())?;
+    Ok(window)
+}
+
+/// Open the note window `label` if it isn't open, for AppKit restoring a
+/// window the saved layout doesn't have
+pub fn reopen(app: &AppHandle, label: &str) -> Option<WebviewWindow> {
+    id(label)?;
+    if let Some(window) = app.get_webview_window(label) {
+        return Some(window);
+    }
+    let window = build(app, label).ok()?;
+    changed(app);
+    Some(window)
+}
+
+/// Open every note window that was open when the app last quit, and put
+/// them back in their tab groups. The main window is already open.
+pub fn restore_layout(app: &AppHandle) {
+    let windows = app.state::<Windows>();
+    let layout = windows.layout.lock().expect("window layout mutex").clone();
+    for window in layout.iter().filter(|w| id(&w.label).is_some()) {
+        if let Err(e) = build(app, &window.label) {
+            log::warn!(window = window.label.as_str(), error:% = e; "window not restored");
+        }
+    }
+    if let Some(main) = app.get_webview_window("main") {
+        let zoom = windows.get("main").map_or(default_zoom(), |l| l.zoom);
+        if zoom != default_zoom() {
+            let _ = main.set_zoom(zoom);
+        }
+    }
+    #[cfg(target_os = "macos")]
+    for window in &layout {
+        let Some(group) = &window.tab_group else {
+            continue;
+        };
+        if let Some(group) = app.get_webview_window(group) {
+            let _ = crate::window_tabs::add(&group, &window.label);
+        }
+    }
+    changed(app);
+}
+
+/// Forget windows that didn't make it back: the layout only keeps the
+/// windows open now. Called after the layout is restored.
+pub fn prune(app: &AppHandle) {
+    let windows = app.state::<Windows>();
+    for label in windows.labels() {
+        if app.get_webview_window(&label).is_none() {
+            windows.forget(&label);
+        }
+    }
+}
+
+/// Zoom `window`'s content, 1 being actual size, and keep it for the next
+/// time the window opens. Returns the zoom, and tells the window.
+pub fn set_zoom(window: &WebviewWindow, zoom: f64) -> Result<f64, String> {
+    if !(MIN_ZOOM..=MAX_ZOOM).contains(&zoom) {
+        return Err(format!("Zoom {} is out of range", zoom));
+    }
+    window.set_zoom(zoom).map_err(|e| e.to_string())?;
+    window.state::<Windows>().set_zoom(window.label(), zoom);
+    let _ = window.emit_to(window.label(), ZOOM_EVENT, zoom);
+    Ok(zoom)
+}
+
+pub fn zoom(window: &WebviewWindow) -> f64 {
+    window.state::<Windows>().zoom(window.label())
+}
+
+/// Zoom to the next step up from where `window` is
+pub fn zoom_in(window: &WebviewWindow) -> Result<f64, String> {
+    let current = zoom(window);
+    let next = ZOOM_STEPS
+        .iter()
+        .copied()
+        .find(|step| *step > current + 0.001)
+        .unwrap_or(MAX_ZOOM);
+    set_zoom(window, next)
+}
+
+/// Zoom to the next step down from where `window` is
+pub fn zoom_out(window: &WebviewWindow) -> Result<f64, String> {
+    let current = zoom(window);
+    let next = ZOOM_STEPS
+        .iter()
+        .rev()
+        .copied()
+        .find(|step| *step < current - 0.001)
+        .unwrap_or(MIN_ZOOM);
+    set_zoom(window, next)
+}
+
+pub fn reset_zoom(window: &WebviewWindow) -> Result<f64, String> {
+    set_zoom(window, default_zoom())
+}
@@ -4,31 +4,94 @@ import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import { toast } from "sonner";
 import { NotesProvider, useNotes } from "./context/NotesContext";
-import { ThemeProvider } from "./context/ThemeContext";
+import { ThemeProvider, useTheme } from "./context/ThemeContext";
 import { GitProvider } from "./context/GitContext";
 import { TooltipProvider, Toaster } from "./components/ui";
 import { Sidebar } from "./components/layout/Sidebar";
 import { Editor } from "./components/editor/Editor";
 import { FolderPicker } from "./components/layout/FolderPicker";
+import { WindowControls } from "./components/layout/WindowControls";
 import { CommandPalette } from "./components/command-palette/CommandPalette";
 import { SettingsPage } from "./components/settings";
 import { SpinnerIcon, ClaudeIcon } from "./components/icons";
 import { AiEditModal } from "./components/ai/AiEditModal";
 import { AiResponseToast } from "./components/ai/AiResponseToast";
-import {
-  check as checkForUpdate,
-  type Update,
-} from "@tauri-apps/plugin-updater";
+import * as accessibilityService from "./services/accessibility";
+import * as activationService from "./services/activation";
+import * as occlusionService from "./services/occlusion";
 import * as aiService from "./services/ai";
+import * as appleEventsService from "./services/appleEvents";
+import * as appMenuService from "./services/appMenu";
+import * as crashService from "./services/crash";
+import * as dockService from "./services/dock";
+import * as fsScopeService from "./services/fsScope";
+import * as globalShortcutsService from "./services/globalShortcuts";
+import * as handoffService from "./services/handoff";
+import * as notesService from "./services/notes";
+import * as titlebarService from "./services/titlebar";
+import * as presentationService from "./services/presentation";
+import * as projectsService from "./services/projects";
+import * as quitService from "./services/quit";
+import * as restorationService from "./services/restoration";
+import * as servicesMenuService from "./services/servicesMenu";
+import * as shortcutsService from "./services/shortcuts";
+import * as trayService from "./services/tray";
+import * as updatesService from "./services/updates";
+import * as windowScopeService from "./services/windowScope";
+import { dialogLabels, pickFolder, pickSaveFile } from "./services/dialogs";
+import { translate } from "./services/i18n";
+import type { DownloadProgress } from "./services/storage";
 
 type ViewState = "notes" | "settings";
+
+interface DeepLinkRoute {
+  route: string;
+  params: Record<string, string>;
+  url: string;
+}
 const LAST_EXTERNAL_FILE_SESSION_KEY = "smudge:last-external-file-path";
 
+// Pick a folder and open it as a project, in a window of its own
+async function pickAndOpenProject() {
+  const folder = await pickFolder({
+    purpose: "notes-folder",
+    ...(await dialogLabels("open-project")),
+  });
+  if (!folder) return;
+  try {
+    await projectsService.openProject(folder);
+  } catch (err) {
+    toast.error(err instanceof Error ? err.message : String(err));
+  }
+}
+
+// Ask where to put a new project bundle, then open it
+async function pickAndCreateProject() {
+  const path = await pickSaveFile({
+    purpose: "notes-folder",
+    ...(await dialogLabels("new-project")),
+    defaultName: "Untitled.smudge",
+    filters: [
+      {
+        name: await translate("dialog-filter-project"),
+        extensions: ["smudge"],
+      },
+    ],
+  });
+  if (!path) return;
+  try {
+    await projectsService.createProject(path);
+  } catch (err) {
+    toast.error(err instanceof Error ? err.message : String(err));
+  }
+}
+
 function AppContent() {
   const {
     notesFolder,
     isLoading,
     createNote,
+    createNoteFromTemplate,
     notes,
     selectedNoteId,
     selectNote,
@@ -38,7 +101,10 @@ function AppContent() {
     currentNote,
     externalFile,
     openExternalFile,
+    search,
+    setNotesFolder,
   } = useNotes();
+  const { reloadSettings } = useTheme();
   const [paletteOpen, setPaletteOpen] = useState(false);
   const [view, setView] = useState<ViewState>("notes");
   const [sidebarVisible, setSidebarVisible] = useState(true);
@@ -84,18 +150,521 @@ function AppContent() {
     }
   }, [openExternalFile, persistExternalFilePath]);
 
+  // smudge:// and universal links resolved by the backend (see src-tauri/universal-links.json)
+  const checkDeepLinks = useCallback(async () => {
+    try {
+      const links = await invoke<DeepLinkRoute[]>("get_pending_deep_links");
+      for (const link of links) {
+        if (link.route === "note" && link.params.id) {
+          setView("notes");
+          await selectNote(link.params.id);
+        } else if (link.route === "search" && link.params.q) {
+          setView("notes");
+          await search(link.params.q);
+        } else if (link.route === "clip" && link.params.url) {
+          // From the browser extension: a page, with the text selected on it
+          const { url, title, selection } = link.params;
+          const content = [
+            title && `# ${title}`,
+            selection,
+            `Source: <${url}>`,
+          ]
+            .filter(Boolean)
+            .join("\n\n");
+          setView("notes");
+          const note = await notesService.saveNote(null, content);
+          await selectNote(note.id);
+        }
+      }
+    } catch (err) {
+      console.error("Failed to check deep links:", err);
+    }
+  }, [selectNote, search]);
+
+  // Handoff continued from another device: open the note, the editor restores the position
+  const checkHandoff = useCallback(async () => {
+    try {
+      const document = await handoffService.getPendingHandoff();
+      if (document) {
+        handoffService.setPendingHandoffPosition(document);
+        setView("notes");
+        await selectNote(document.noteId);
+        window.dispatchEvent(
+          new CustomEvent("handoff-position", { detail: document.noteId }),
+        );
+      }
+    } catch (err) {
+      console.error("Failed to check handoff:", err);
+    }
+  }, [selectNote]);
+
+  // Actions run from the Shortcuts app or x-callback-url links. Each is
+  // completed so the shortcut gets its result.
+  const checkIntents = useCallback(async () => {
+    let invocations: shortcutsService.IntentInvocation[];
+    try {
+      invocations = await shortcutsService.getPendingIntents();
+    } catch (err) {
+      console.error("Failed to check intents:", err);
+      return;
+    }
+    for (const { id, intent } of invocations) {
+      const complete = (completion: shortcutsService.IntentCompletion) =>
+        shortcutsService.completeIntent(id, completion).catch(() => {});
+      try {
+        if (intent.action === "createNote") {
+          const content = [intent.title && `# ${intent.title}`, intent.text]
+            .filter(Boolean)
+            .join("\n\n");
+          setView("notes");
+          if (content) {
+            const note = await notesService.saveNote(null, content);
+            await selectNote(note.id);
+            await complete({
+              outcome: "success",
+              values: { noteId: note.id },
+            });
+          } else {
+            await createNote();
+            await complete({ outcome: "success" });
+          }
+        } else if (intent.action === "openProject") {
+          // The notes folder has to be picked in a dialog, so a folder
+          // the intent names is only where the dialog starts
+          const folder = await pickFolder({
+            purpose: "notes-folder",
+            ...(await dialogLabels("choose-notes-folder")),
+            defaultPath: intent.folder,
+          });
+          if (!folder) {
+            await complete({ outcome: "cancel" });
+            continue;
+          }
+          await setNotesFolder(folder);
+          await reloadSettings();
+          setView("notes");
+          await complete({ outcome: "success", values: { folder } });
+        } else if (!notesFolder) {
+          await complete({
+            outcome: "error",
+            message: "No notes folder is open",
+          });
+        } else {
+          setView("notes");
+          window.dispatchEvent(
+            new CustomEvent(shortcutsService.EXPORT_SELECTION_EVENT, {
+              detail: { id, intent },
+            }),
+          );
+        }
+      } catch (err) {
+        const message = err instanceof Error ? err.message : String(err);
+        await complete({ outcome: "error", message });
+      }
+    }
+  }, [createNote, notesFolder, reloadSettings, selectNote, setNotesFolder]);
+
+  // Relaunch: reopen the note the window showed when the app last quit
+  const checkRestoredState = useCallback(async () => {
+    try {
+      const state = await restorationService.takeRestoredState();
+      if (state?.noteId) {
+        setView("notes");
+        await selectNote(state.noteId);
+      }
+    } catch (err) {
+      console.error("Failed to restore state:", err);
+    }
+  }, [selectNote]);
+
   // Cold start: check for buffered files and mark frontend ready
   useEffect(() => {
     async function init() {
+      // A file the app was launched to open wins over the one restored from
+      // this session, so it's the first document shown
+      const launchFiles = await invoke<string[]>("get_launch_files").catch(
+        () => [],
+      );
       const persistedFilePath = readPersistedExternalFilePath();
-      if (persistedFilePath) {
+      if (persistedFilePath && launchFiles.length === 0) {
         await openExternalFile(persistedFilePath);
       }
+      await checkRestoredState();
       await checkOpenedFiles();
+      await checkDeepLinks();
+      await checkHandoff();
+      await checkIntents();
       await invoke("mark_frontend_ready");
     }
     init();
-  }, [checkOpenedFiles, openExternalFile, readPersistedExternalFilePath]);
+  }, [
+    checkOpenedFiles,
+    checkDeepLinks,
+    checkHandoff,
+    checkIntents,
+    checkRestoredState,
+    openExternalFile,
+    readPersistedExternalFilePath,
+  ]);
+
+  // Save what's pending before the app quits
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    quitService.listenForQuit().then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // Screen reader announcements the backend couldn't make natively
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    accessibilityService.listenForAnnouncements().then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // Warm start: smudge:// and universal links arriving while the app runs
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen("deep-link-received", () => {
+      checkDeepLinks();
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [checkDeepLinks]);
+
+  // Warm start: intents arriving while the app runs
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    shortcutsService
+      .onIntentReceived(() => {
+        checkIntents();
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [checkIntents]);
+
+  // Warm start: the window was restored after the frontend had loaded
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    restorationService
+      .onStateRestored(() => {
+        checkRestoredState();
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [checkRestoredState]);
+
+  // Keep what the next launch restores in step with the open note
+  useEffect(() => {
+    restorationService
+      .setRestorableState({ noteId: selectedNoteId })
+      .catch(() => {});
+  }, [selectedNoteId]);
+
+  // Warm start: listen for handoff signal from backend
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen<string>("handoff", () => {
+      checkHandoff();
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [checkHandoff]);
+
+  // A note coming back from the mini window
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen<string>("mini-mode-exit", (event) => {
+      setView("notes");
+      selectNote(event.payload);
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [selectNote]);
+
+  // Recent notes in the Dock icon's menu
+  useEffect(() => {
+    dockService
+      .setDockMenu(notes.slice(0, 10).map(({ id, title }) => ({ id, title })))
+      .catch(() => {});
+  }, [notes]);
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    dockService
+      .onDockMenuAction((action) => {
+        setView("notes");
+        if (action.action === "newNote") {
+          createNote();
+        } else if (action.action === "newFromTemplate") {
+          createNoteFromTemplate(action.templateId);
+        } else {
+          selectNote(action.noteId);
+        }
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [createNote, createNoteFromTemplate, selectNote]);
+
+  // The quick capture shortcut, pressed from anywhere
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    globalShortcutsService
+      .onQuickCapture(() => {
+        setView("notes");
+        createNote();
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [createNote]);
+
+  // "New Note" and recent notes in the tray icon's menu, rebuilt whenever
+  // the icon is shown again
+  const [trayVisible, setTrayVisible] = useState(false);
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    trayService
+      .isTrayVisible()
+      .then(setTrayVisible)
+      .catch(() => {});
+    trayService
+      .onTrayVisibilityChange(setTrayVisible)
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!trayVisible) return;
+    const recent = notes.slice(0, 5).map(
+      ({ id, title }): trayService.TrayMenuItem => ({
+        kind: "item",
+        id: `note:${id}`,
+        title: title.trim() || "Untitled",
+      }),
+    );
+    trayService
+      .setTrayMenu([
+        { kind: "item", id: "new-note", title: "New Note" },
+        ...(recent.length > 0
+          ? [{ kind: "separator" } as const, ...recent]
+          : []),
+      ])
+      .catch(() => {});
+  }, [notes, trayVisible]);
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    trayService
+      .onTrayMenuAction((id) => {
+        setView("notes");
+        if (id === "new-note") {
+          createNote();
+        } else if (id.startsWith("note:")) {
+          selectNote(id.slice("note:".length));
+        }
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [createNote, selectNote]);
+
+  // The main window owns the notes folder: files opened from Finder inside
+  // it open as the notes they are, unless a mini window shows one of them
+  useEffect(() => {
+    windowScopeService.setWindowScope(notesFolder).catch(() => {});
+  }, [notesFolder]);
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    windowScopeService
+      .onPathsOpened(async (paths) => {
+        setView("notes");
+        for (const { path, noteId } of paths) {
+          try {
+            if (noteId) {
+              await selectNote(noteId);
+            } else {
+              await openExternalFile(path);
+            }
+          } catch (err) {
+            console.error("Failed to open routed file:", err);
+          }
+        }
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [openExternalFile, selectNote]);
+
+  // AppleScript's «event SmdgNewN» and «event SmdgOpnN»
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    appleEventsService
+      .onAppleEventAction((action) => {
+        setView("notes");
+        if (action.action === "newNote") {
+          createNote();
+        } else {
+          selectNote(action.noteId);
+        }
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [createNote, selectNote]);
+
+  // "New Smudge Note" from another app's Services menu: the selected text
+  // becomes a note, a selected markdown file is opened
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    servicesMenuService
+      .onServiceRequest(async ({ text, files }) => {
+        setView("notes");
+        const mdFile = files.find((p) => /\.(md|markdown|mdown|mkd)$/i.test(p));
+        try {
+          if (text) {
+            const note = await notesService.saveNote(null, text);
+            await selectNote(note.id);
+          } else if (mdFile) {
+            await openExternalFile(mdFile);
+          }
+        } catch (err) {
+          toast.error(err instanceof Error ? err.message : "Failed to create note");
+        }
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [openExternalFile, selectNote]);
+
+  // Cloud placeholders being downloaded before they're opened
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen<DownloadProgress>("download-progress", (event) => {
+      const { path, downloaded, total, done, error } = event.payload;
+      const toastId = `download:${path}`;
+      const name = path.split(/[\\/]/).pop() || path;
+      if (error) {
+        toast.error(error, { id: toastId });
+      } else if (done) {
+        toast.dismiss(toastId);
+      } else {
+        const percent =
+          downloaded !== null && total > 0
+            ? ` ${Math.round((downloaded / total) * 100)}%`
+            : "";
+        toast.loading(`Downloading ${name}...${percent}`, { id: toastId });
+      }
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // Files picked up from watched import folders
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen<{ sourcePath: string; noteId: string | null; error: string | null }>(
+      "watch-folder-import",
+      (event) => {
+        const { sourcePath, error } = event.payload;
+        const name = sourcePath.split(/[\\/]/).pop() || sourcePath;
+        if (error) {
+          toast.error(`Failed to import ${name}: ${error}`);
+        } else {
+          toast.success(`Imported ${name}`);
+        }
+      },
+    ).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
 
   // Warm start: listen for file-opened signal from backend, then poll for files
   useEffect(() => {
@@ -154,17 +723,59 @@ function AppContent() {
     };
   }, [checkOpenedFiles]);
 
+  // Backgrounded (inactive or hidden) the app stops polling; coming back
+  // checks once straight away
+  const [backgrounded, setBackgrounded] = useState(false);
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    activationService
+      .onActivationChange((state) => {
+        setBackgrounded(activationService.isBackgrounded(state));
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // With render throttling on, every window being hidden behind others
+  // pauses the loop too
+  const [throttled, setThrottled] = useState(false);
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    occlusionService
+      .isRenderThrottled()
+      .then(setThrottled)
+      .catch(() => {});
+    occlusionService
+      .onRenderThrottlingChange(setThrottled)
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
   // Last-resort fallback: periodically poll backend's opened-file buffer.
   // This guarantees OS-opened files are eventually picked up even if signals
   // or focus ordering fail on some macOS flows.
   useEffect(() => {
+    if (backgrounded || throttled) return;
+    checkOpenedFiles();
     const interval = window.setInterval(() => {
       checkOpenedFiles();
     }, 1500);
     return () => {
       clearInterval(interval);
     };
-  }, [checkOpenedFiles]);
+  }, [backgrounded, throttled, checkOpenedFiles]);
 
   // Drag-and-drop: open .md files dropped onto the app window
   useEffect(() => {
@@ -195,6 +806,16 @@ function AppContent() {
     persistExternalFilePath(externalFile?.path ?? null);
   }, [externalFile?.path, persistExternalFilePath]);
 
+  // Let go of an external file once another replaces it or it's closed
+  useEffect(() => {
+    const path = externalFile?.path;
+    return () => {
+      if (path) {
+        fsScopeService.closeDocument(path).catch(() => {});
+      }
+    };
+  }, [externalFile?.path]);
+
   const toggleSidebar = useCallback(() => {
     setSidebarVisible((prev) => !prev);
   }, []);
@@ -397,6 +1018,88 @@ function AppContent() {
     view,
   ]);
 
+  // Items chosen from the app menu, or by their key equivalents when the
+  // webview didn't handle the keys itself
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    appMenuService
+      .onAppMenuAction((id) => {
+        if (id === "settings") {
+          toggleSettings();
+          return;
+        }
+        if (view === "settings") return;
+        if (id.startsWith("newFromTemplate:")) {
+          createNoteFromTemplate(id.slice("newFromTemplate:".length));
+          return;
+        }
+        switch (id) {
+          case "newNote":
+            createNote();
+            break;
+          case "reloadNote":
+            reloadCurrentNote();
+            break;
+          case "toggleSidebar":
+            toggleSidebar();
+            break;
+          case "commandPalette":
+            setPaletteOpen(true);
+            break;
+          case "openProject":
+            pickAndOpenProject();
+            break;
+          case "newProject":
+            pickAndCreateProject();
+            break;
+        }
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [
+    createNote,
+    createNoteFromTemplate,
+    reloadCurrentNote,
+    toggleSettings,
+    toggleSidebar,
+    view,
+  ]);
+
+  // The active project's settings, such as its theme, apply once it's
+  // switched to
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    projectsService
+      .onProjectsChange(() => {
+        reloadSettings();
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [reloadSettings]);
+
+  // Note commands are off in settings, as their shortcuts are
+  useEffect(() => {
+    const inNotes = view !== "settings";
+    appMenuService
+      .updateAppMenu([
+        { id: "newNote", enabled: inNotes },
+        { id: "reloadNote", enabled: inNotes && selectedNoteId !== null },
+        { id: "toggleSidebar", enabled: inNotes },
+        { id: "commandPalette", enabled: inNotes },
+      ])
+      .catch(() => {});
+  }, [view, selectedNoteId]);
+
   const handleClosePalette = useCallback(() => {
     setPaletteOpen(false);
   }, []);
@@ -484,7 +1187,7 @@ function AppContent() {
 // Shared update check — used by startup and manual "Check for Updates"
 async function showUpdateToast(): Promise<"update" | "no-update" | "error"> {
   try {
-    const update = await checkForUpdate();
+    const update = await updatesService.checkForUpdate();
     if (update) {
       toast(<UpdateToast update={update} toastId="update-toast" />, {
         id: "update-toast",
@@ -515,27 +1218,67 @@ function UpdateToast({
   update,
   toastId,
 }: {
-  update: Update;
+  update: updatesService.UpdateInfo;
   toastId: string | number;
 }) {
-  const [installing, setInstalling] = useState(false);
+  const [stage, setStage] = useState<
+    "available" | "downloading" | "ready" | "installing"
+  >("available");
+  const [percent, setPercent] = useState<number | null>(null);
 
-  const handleUpdate = async () => {
-    setInstalling(true);
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    updatesService
+      .onUpdateProgress((progress) => {
+        if (progress.total) {
+          setPercent(Math.round((progress.downloaded / progress.total) * 100));
+        }
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  const handleDownload = async () => {
+    setStage("downloading");
     try {
-      await update.downloadAndInstall();
-      toast.dismiss(toastId);
-      toast.success("Update installed! Restart Smudge to apply.", {
-        duration: Infinity,
-        closeButton: true,
+      await updatesService.downloadUpdate();
+      setStage("ready");
+    } catch (err) {
+      console.error("Update download failed:", err);
+      toast.error("Update failed. Please try again later.", {
+        description: typeof err === "string" ? err : undefined,
       });
+      setStage("available");
+      setPercent(null);
+    }
+  };
+
+  const handleRestart = async () => {
+    setStage("installing");
+    try {
+      await updatesService.installUpdate();
+      toast.dismiss(toastId);
+      await updatesService.restartAfterUpdate();
     } catch (err) {
       console.error("Update failed:", err);
-      toast.error("Update failed. Please try again later.");
-      setInstalling(false);
+      toast.error("Update failed. Please try again later.", {
+        description: typeof err === "string" ? err : undefined,
+      });
+      setStage("ready");
     }
   };
 
+  const buttonLabel = {
+    available: "Update Now",
+    downloading: percent === null ? "Downloading..." : `Downloading ${percent}%`,
+    ready: "Restart to Update",
+    installing: "Installing...",
+  }[stage];
+
   return (
     <div className="flex flex-col gap-1">
       <div className="font-medium text-sm">
@@ -547,16 +1290,72 @@ function UpdateToast({
         </div>
       )}
       <button
-        onClick={handleUpdate}
-        disabled={installing}
+        onClick={stage === "ready" ? handleRestart : handleDownload}
+        disabled={stage === "downloading" || stage === "installing"}
         className="self-start mt-1 text-xs font-medium px-3 py-1.5 rounded-md bg-text text-bg hover:opacity-90 disabled:opacity-50 transition-opacity"
       >
-        {installing ? "Installing..." : "Update Now"}
+        {buttonLabel}
       </button>
     </div>
   );
 }
 
+function CrashToast({
+  report,
+  toastId,
+}: {
+  report: crashService.CrashReport;
+  toastId: string | number;
+}) {
+  const [sending, setSending] = useState(false);
+
+  const handleSend = async () => {
+    setSending(true);
+    try {
+      await crashService.uploadCrashReport(report.id);
+      toast.dismiss(toastId);
+      toast.success("Crash report sent. Thank you!");
+    } catch (err) {
+      console.error("Crash report upload failed:", err);
+      toast.error("Couldn't send the crash report.", {
+        description: typeof err === "string" ? err : undefined,
+      });
+      setSending(false);
+    }
+  };
+
+  const handleDiscard = () => {
+    toast.dismiss(toastId);
+    crashService.discardCrashReport(report.id).catch(console.error);
+  };
+
+  return (
+    <div className="flex flex-col gap-1">
+      <div className="font-medium text-sm">Smudge quit unexpectedly</div>
+      <div className="text-xs text-text-muted line-clamp-3">
+        Send a crash report to help fix it? It includes the error and the
+        app's recent log lines.
+      </div>
+      <div className="flex gap-2 mt-1">
+        <button
+          onClick={handleSend}
+          disabled={sending}
+          className="text-xs font-medium px-3 py-1.5 rounded-md bg-text text-bg hover:opacity-90 disabled:opacity-50 transition-opacity"
+        >
+          {sending ? "Sending..." : "Send Report"}
+        </button>
+        <button
+          onClick={handleDiscard}
+          disabled={sending}
+          className="text-xs font-medium px-3 py-1.5 rounded-md text-text-muted hover:text-text disabled:opacity-50 transition-colors"
+        >
+          Don't Send
+        </button>
+      </div>
+    </div>
+  );
+}
+
 function App() {
   // Add platform class for OS-specific styling (e.g., keyboard shortcuts)
   useEffect(() => {
@@ -566,6 +1365,77 @@ function App() {
     );
   }, []);
 
+  // Lay out around the window controls and take over titlebar double-clicks
+  const [titlebar, setTitlebar] = useState<titlebarService.TitlebarInfo | null>(
+    null,
+  );
+  useEffect(() => {
+    titlebarService
+      .getTitlebarInfo()
+      .then((info) => {
+        titlebarService.applyTitlebarInfo(info);
+        setTitlebar(info);
+      })
+      .catch(() => {});
+
+    const handleChanged = (e: Event) =>
+      setTitlebar((e as CustomEvent<titlebarService.TitlebarInfo>).detail);
+    window.addEventListener("titlebar-changed", handleChanged);
+    const uninstall = titlebarService.installTitlebarDoubleClick();
+    return () => {
+      window.removeEventListener("titlebar-changed", handleChanged);
+      uninstall();
+    };
+  }, []);
+
+  // Presentation mode: no toasts while presenting, Escape to leave
+  const [presenting, setPresenting] = useState(false);
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    listen<boolean>("presentation", (event) => {
+      if (event.payload) toast.dismiss();
+      setPresenting(event.payload);
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!presenting) return;
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") {
+        presentationService.stopPresentation().catch(console.error);
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    return () => window.removeEventListener("keydown", handleKeyDown);
+  }, [presenting]);
+
+  // Offer to send a report when the previous run crashed
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    crashService
+      .onCrashDetected((report) => {
+        toast(<CrashToast report={report} toastId="crash-toast" />, {
+          id: "crash-toast",
+          duration: Infinity,
+        });
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
   // Check for app updates on startup
   useEffect(() => {
     const timer = setTimeout(() => showUpdateToast(), 3000);
@@ -574,7 +1444,10 @@ function App() {
 
   return (
     <ThemeProvider>
-      <Toaster />
+      {!presenting && <Toaster />}
+      {titlebar?.mode === "custom" && titlebar.drawsControls && (
+        <WindowControls />
+      )}
       <TooltipProvider>
         <NotesProvider>
           <GitProvider>
@@ -0,0 +1,75 @@
+import { useEffect } from "react";
+import { NotesProvider, useNotes } from "./context/NotesContext";
+import { ThemeProvider } from "./context/ThemeContext";
+import { TooltipProvider, Toaster } from "./components/ui";
+import { Editor } from "./components/editor/Editor";
+import { isMac } from "./lib/platform";
+import * as titlebarService from "./services/titlebar";
+import * as windowScopeService from "./services/windowScope";
+
+// Compact, always-on-top window showing a single note
+function MiniContent({ noteId }: { noteId: string }) {
+  const { notesFolder, selectNote } = useNotes();
+
+  useEffect(() => {
+    if (notesFolder) selectNote(noteId);
+  }, [notesFolder, noteId, selectNote]);
+
+  // Opening this note's file from Finder brings this window forward
+  useEffect(() => {
+    if (!notesFolder) return;
+    windowScopeService
+      .setWindowScope(`${notesFolder}/${noteId}.md`)
+      .catch(() => {});
+  }, [notesFolder, noteId]);
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    windowScopeService
+      .onPathsOpened((paths) => {
+        const routed = paths.find((p) => p.noteId);
+        if (routed?.noteId) selectNote(routed.noteId);
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [selectNote]);
+
+  return (
+    <div className="h-screen flex bg-bg overflow-hidden">
+      <Editor />
+    </div>
+  );
+}
+
+function MiniApp({ noteId }: { noteId: string }) {
+  useEffect(() => {
+    document.documentElement.classList.add(
+      isMac ? "platform-mac" : "platform-other",
+    );
+    // Only macOS overlays the controls here; elsewhere the mini window keeps
+    // its native titlebar
+    if (!isMac) {
+      document.documentElement.style.setProperty("--titlebar-inset-left", "0px");
+    }
+    return titlebarService.installTitlebarDoubleClick();
+  }, []);
+
+  return (
+    <ThemeProvider>
+      <Toaster />
+      <TooltipProvider>
+        <NotesProvider>
+          <MiniContent noteId={noteId} />
+        </NotesProvider>
+      </TooltipProvider>
+    </ThemeProvider>
+  );
+}
+
+export default MiniApp;
@@ -0,0 +1,25 @@
+import { useEffect, useState } from "react";
+import MiniApp from "./MiniApp";
+import { onWarmClaim } from "./services/miniWindow";
+
+// A pooled window with the frontend loaded but nothing rendered, until it's
+// claimed to show a note as the mini window
+function WarmWindow() {
+  const [noteId, setNoteId] = useState<string | null>(null);
+
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    onWarmClaim(setNoteId)
+      .then((fn) => {
+        unlisten = fn;
+      })
+      .catch((err) => console.error("Failed to register warm window:", err));
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  return noteId === null ? null : <MiniApp noteId={noteId} />;
+}
+
+export default WarmWindow;
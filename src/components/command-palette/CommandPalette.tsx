@@ -13,6 +13,14 @@ import { useNotes } from "../../context/NotesContext";
 import { useTheme } from "../../context/ThemeContext";
 import { useGit } from "../../context/GitContext";
 import * as notesService from "../../services/notes";
+import * as presentationService from "../../services/presentation";
+import * as printService from "../../services/print";
+import * as diagnosticsService from "../../services/diagnostics";
+import * as recentDocumentsService from "../../services/recentDocuments";
+import { dialogLabels, pickSaveFile } from "../../services/dialogs";
+import { translate } from "../../services/i18n";
+import { scrollBehavior } from "../../services/accessibility";
+import { trashName } from "../../services/trash";
 import type { Settings } from "../../types/note";
 import {
   CommandItem,
@@ -37,8 +45,10 @@ import {
   TrashIcon,
   PinIcon,
   ClaudeIcon,
+  EyeIcon,
+  NoteIcon,
 } from "../icons";
-import { mod } from "../../lib/platform";
+import { isMac, isWindows, mod } from "../../lib/platform";
 
 interface Command {
   id: string;
@@ -66,6 +76,7 @@ export function CommandPalette({
     selectNote,
     createNote,
     deleteNote,
+    restoreNote,
     currentNote,
     refreshNotes,
     pinNote,
@@ -81,6 +92,7 @@ export function CommandPalette({
     { id: string; title: string; preview: string; modified: number }[]
   >([]);
   const [settings, setSettings] = useState<Settings | null>(null);
+  const [displays, setDisplays] = useState<presentationService.Display[]>([]);
   const inputRef = useRef<HTMLInputElement>(null);
   const listRef = useRef<HTMLDivElement>(null);
 
@@ -91,6 +103,16 @@ export function CommandPalette({
     }
   }, [open, currentNote?.id]);
 
+  // Displays can be plugged in while the app runs
+  useEffect(() => {
+    if (open) {
+      presentationService
+        .listDisplays()
+        .then(setDisplays)
+        .catch(() => {});
+    }
+  }, [open]);
+
   // Memoize commands array
   const commands = useMemo<Command[]>(() => {
     const baseCommands: Command[] = [
@@ -204,6 +226,34 @@ export function CommandPalette({
           },
         },
       );
+
+      // Prints the window as it shows the note
+      if (isMac || isWindows) {
+        baseCommands.push({
+          id: "print-note",
+          label: "Print Current Note...",
+          icon: <NoteIcon className="w-4.5 h-4.5 stroke-[1.5]" />,
+          action: async () => {
+            onClose();
+            // Let the palette close before the window is drawn for printing
+            await new Promise((resolve) => setTimeout(resolve, 100));
+            try {
+              const outcome = await printService.print(
+                { kind: "window" },
+                { jobName: cleanTitle(currentNote.title) },
+              );
+              if (outcome.kind === "failed") {
+                toast.error(`Failed to print: ${outcome.message}`);
+              }
+            } catch (error) {
+              console.error("Failed to print note:", error);
+              toast.error(
+                typeof error === "string" ? error : "Failed to print",
+              );
+            }
+          },
+        });
+      }
     }
 
     // Add git commands if git is available and initialized
@@ -250,6 +300,120 @@ export function CommandPalette({
       }
     }
 
+    // Presentation mode, on the current display or any other connected one
+    const startPresentation = async (display?: string) => {
+      onClose();
+      try {
+        await presentationService.startPresentation(display);
+      } catch (error) {
+        console.error("Failed to start presentation:", error);
+        toast.error("Failed to start presentation");
+      }
+    };
+    baseCommands.push({
+      id: "present",
+      label: "Start Presentation",
+      icon: <EyeIcon className="w-4.5 h-4.5 stroke-[1.5]" />,
+      action: () => startPresentation(),
+    });
+    if (displays.length > 1) {
+      for (const display of displays) {
+        baseCommands.push({
+          id: `present-${display.name}`,
+          label: `Start Presentation on ${display.name}`,
+          icon: <EyeIcon className="w-4.5 h-4.5 stroke-[1.5]" />,
+          action: () => startPresentation(display.name),
+        });
+      }
+    }
+
+    // The OS's recent files: "Open Recent" and the Dock menu, or the jump list
+    if (isMac || isWindows) {
+      baseCommands.push({
+        id: "clear-recent-documents",
+        label: "Clear Recent Documents",
+        icon: <TrashIcon className="w-4.5 h-4.5 stroke-[1.5]" />,
+        action: async () => {
+          onClose();
+          try {
+            await recentDocumentsService.clearRecentDocuments();
+          } catch (error) {
+            console.error("Failed to clear recent documents:", error);
+            toast.error("Failed to clear recent documents");
+          }
+        },
+      });
+    }
+
+    // Bug reports
+    baseCommands.push(
+      {
+        id: "record-window",
+        label: "Record Window for Bug Report (15s)",
+        icon: <EyeIcon className="w-4.5 h-4.5 stroke-[1.5]" />,
+        action: async () => {
+          onClose();
+          const toastId = toast.loading("Recording window for 15 seconds...");
+          try {
+            await diagnosticsService.recordWindow(15);
+            toast.success("Recording added to the next diagnostics bundle", {
+              id: toastId,
+            });
+          } catch (error) {
+            console.error("Failed to record window:", error);
+            toast.error(
+              typeof error === "string" ? error : "Failed to record window",
+              { id: toastId },
+            );
+          }
+        },
+      },
+      {
+        id: "diagnostics-bundle",
+        label: "Create Diagnostics Bundle",
+        icon: <CopyIcon className="w-4.5 h-4.5 stroke-[1.5]" />,
+        action: async () => {
+          onClose();
+          try {
+            const bundle = await diagnosticsService.createDiagnosticsBundle();
+            await invoke("reveal_in_file_manager", { path: bundle });
+          } catch (error) {
+            console.error("Failed to create diagnostics bundle:", error);
+            toast.error("Failed to create diagnostics bundle");
+          }
+        },
+      },
+      {
+        id: "export-logs",
+        label: "Export Logs...",
+        icon: <CopyIcon className="w-4.5 h-4.5 stroke-[1.5]" />,
+        action: async () => {
+          onClose();
+          const path = await pickSaveFile({
+            purpose: "export",
+            ...(await dialogLabels("export-logs")),
+            defaultName: "smudge.log",
+            filters: [
+              {
+                name: await translate("dialog-filter-log"),
+                extensions: ["log"],
+              },
+            ],
+          });
+          if (!path) return;
+          try {
+            await diagnosticsService.exportLogs(path);
+            toast.success("Logs exported");
+          } catch (error) {
+            console.error("Failed to export logs:", error);
+            toast.error(
+              typeof error === "string" ? error : "Failed to export logs",
+            );
+          }
+        },
+      },
+    );
+
     // Settings and theme commands at the bottom
     baseCommands.push(
       {
@@ -310,6 +474,7 @@ export function CommandPalette({
     settings,
     pinNote,
     unpinNote,
+    displays,
   ]);
 
   // Debounced search using Tantivy (local state, doesn't affect sidebar)
@@ -410,23 +575,37 @@ export function CommandPalette({
       const selectedItem = listRef.current.querySelector(
         `[data-index="${selectedIndex}"]`,
       );
-      selectedItem?.scrollIntoView({ block: "center", behavior: "smooth" });
+      selectedItem?.scrollIntoView({ block: "center", behavior: scrollBehavior() });
     }
   }, [selectedIndex]);
 
   const handleDeleteConfirm = useCallback(async () => {
     if (noteToDelete) {
       try {
-        await deleteNote(noteToDelete);
+        const handle = await deleteNote(noteToDelete);
         setNoteToDelete(null);
         setDeleteDialogOpen(false);
         onClose();
+        if (handle) {
+          toast(`Moved note to ${trashName}`, {
+            action: {
+              label: "Undo",
+              onClick: () => {
+                restoreNote(handle).catch((err) =>
+                  toast.error(
+                    typeof err === "string" ? err : "Failed to restore note",
+                  ),
+                );
+              },
+            },
+          });
+        }
       } catch (error) {
         console.error("Failed to delete note:", error);
         toast.error("Failed to delete note");
       }
     }
-  }, [noteToDelete, deleteNote, onClose]);
+  }, [noteToDelete, deleteNote, restoreNote, onClose]);
 
   const handleKeyDown = useCallback(
     (e: KeyboardEvent) => {
@@ -554,8 +733,8 @@ export function CommandPalette({
           <AlertDialogHeader>
             <AlertDialogTitle>Delete note?</AlertDialogTitle>
             <AlertDialogDescription>
-              This will permanently delete the note and all its content. This
-              action cannot be undone.
+              This will move the note to the {trashName}. You can restore it
+              from there.
             </AlertDialogDescription>
           </AlertDialogHeader>
           <AlertDialogFooter>
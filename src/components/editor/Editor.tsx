@@ -15,11 +15,10 @@ import { TableKit } from "@tiptap/extension-table";
 import { Markdown } from "@tiptap/markdown";
 import { Extension } from "@tiptap/core";
 import { Decoration, DecorationSet } from "@tiptap/pm/view";
-import { Plugin, PluginKey } from "@tiptap/pm/state";
+import { NodeSelection, Plugin, PluginKey } from "@tiptap/pm/state";
 import tippy, { type Instance as TippyInstance } from "tippy.js";
-import { open as openDialog } from "@tauri-apps/plugin-dialog";
 import { openUrl } from "@tauri-apps/plugin-opener";
-import { invoke, convertFileSrc } from "@tauri-apps/api/core";
+import { invoke } from "@tauri-apps/api/core";
 import { join } from "@tauri-apps/api/path";
 import { toast } from "sonner";
 import { mod, alt, shift, isMac } from "../../lib/platform";
@@ -34,13 +33,24 @@ function isAllowedUrlScheme(url: string): boolean {
   }
 }
 import * as DropdownMenu from "@radix-ui/react-dropdown-menu";
-import { Menu, MenuItem, PredefinedMenuItem } from "@tauri-apps/api/menu";
+import { LogicalPosition } from "@tauri-apps/api/dpi";
 import { useNotes } from "../../context/NotesContext";
 import { LinkEditor } from "./LinkEditor";
 import { SearchToolbar } from "./SearchToolbar";
 import { cn } from "../../lib/utils";
 import { Button, IconButton, ToolbarButton, Tooltip } from "../ui";
 import * as notesService from "../../services/notes";
+import * as handoffService from "../../services/handoff";
+import * as quitService from "../../services/quit";
+import * as miniWindowService from "../../services/miniWindow";
+import * as assetsService from "../../services/assets";
+import * as quickLookService from "../../services/quickLook";
+import * as clipboardService from "../../services/clipboard";
+import * as touchBarService from "../../services/touchBar";
+import { isContextMenuShortcut, popupMenu } from "../../services/menu";
+import { scrollBehavior } from "../../services/accessibility";
+import { dialogLabels, pickFile } from "../../services/dialogs";
+import { translate } from "../../services/i18n";
 import type { Settings } from "../../types/note";
 import {
   BoldIcon,
@@ -67,6 +77,7 @@ import {
   RefreshCwIcon,
   PinIcon,
   SearchIcon,
+  PictureInPictureIcon,
   XIcon,
 } from "../icons";
 
@@ -501,7 +512,7 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
               : node.parentElement;
 
           if (element) {
-            element.scrollIntoView({ behavior: "smooth", block: "center" });
+            element.scrollIntoView({ behavior: scrollBehavior(), block: "center" });
           }
         }
       } catch (error) {
@@ -593,6 +604,76 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
     }, 500);
   }, [saveImmediately, getMarkdown, currentNote?.id, externalFile, saveExternalFile]);
 
+  // What the system clipboard holds, kept current so paste can tell
+  // synchronously whether it has images or files the webview can't see
+  const clipboardFormatsRef = useRef<clipboardService.ClipboardFormat[]>([]);
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    clipboardService
+      .getClipboardFormats()
+      .then((formats) => {
+        clipboardFormatsRef.current = formats;
+      })
+      .catch(() => {});
+    clipboardService
+      .onClipboardChanged(({ formats }) => {
+        clipboardFormatsRef.current = formats;
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+    return () => unlisten?.();
+  }, []);
+
+  // Paste copied files and images from the system clipboard: images go into
+  // the assets folder, other files become links
+  const pasteFromSystemClipboard = async () => {
+    try {
+      const data = await clipboardService.readClipboard();
+      const notesFolder = await invoke<string>("get_notes_folder");
+      const insertImage = async (relativePath: string) => {
+        const absolutePath = await join(notesFolder, relativePath);
+        editorRef.current
+          ?.chain()
+          .focus()
+          .setImage({ src: assetsService.assetUrl(absolutePath) })
+          .run();
+      };
+
+      if (data.files?.length) {
+        for (const path of data.files) {
+          if (/\.(png|jpe?g|gif|webp|svg)$/i.test(path)) {
+            await insertImage(
+              await invoke<string>("copy_image_to_assets", { sourcePath: path }),
+            );
+          } else {
+            const name = path.split(/[\\/]/).pop() || path;
+            const href = encodeURI(
+              `file://${path.startsWith("/") ? "" : "/"}${path.replace(/\\/g, "/")}`,
+            );
+            editorRef.current
+              ?.chain()
+              .focus()
+              .insertContent({
+                type: "text",
+                text: name,
+                marks: [{ type: "link", attrs: { href } }],
+              })
+              .insertContent(" ")
+              .run();
+          }
+        }
+      } else if (data.png) {
+        await insertImage(
+          await invoke<string>("save_clipboard_image", { base64Data: data.png }),
+        );
+      }
+    } catch (error) {
+      console.error("Failed to paste from the clipboard:", error);
+      toast.error("Failed to paste");
+    }
+  };
+
   const editor = useEditor({
     extensions: [
       StarterKit.configure({
@@ -637,7 +718,24 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
           "prose prose-lg dark:prose-invert max-w-3xl mx-auto focus:outline-none min-h-full px-6 pt-8 pb-24",
       },
       // Trap Tab key inside the editor
-      handleKeyDown: (_view, event) => {
+      handleKeyDown: (view, event) => {
+        // Space on a selected image previews it, as in the Finder
+        const { selection } = view.state;
+        if (
+          event.key === " " &&
+          selection instanceof NodeSelection &&
+          selection.node.type.name === "image"
+        ) {
+          const path = quickLookService.pathFromAssetUrl(
+            selection.node.attrs.src,
+          );
+          if (path) {
+            quickLookService.previewFiles([path]).catch((error) => {
+              console.error("Failed to preview image:", error);
+            });
+            return true;
+          }
+        }
         if (event.key === "Tab") {
           // Allow default tab behavior (indent in lists, etc.)
           // but prevent focus from leaving the editor
@@ -673,8 +771,8 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
                 const notesFolder = await invoke<string>("get_notes_folder");
                 const absolutePath = await join(notesFolder, relativePath);
 
-                // Convert to Tauri asset URL
-                const assetUrl = convertFileSrc(absolutePath);
+                // Convert to a smudge asset URL
+                const assetUrl = assetsService.assetUrl(absolutePath);
 
                 // Insert image
                 editorRef.current
@@ -696,6 +794,19 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
           }
         }
 
+        // Copied files, and images the webview didn't get, e.g. TIFF
+        // screenshots. Images that come with text, as Office copies a
+        // picture of the selection, are left to the text paste.
+        const formats = clipboardFormatsRef.current;
+        const hasText = clipboardData.types.includes("text/plain");
+        if (
+          clipboardData.files.length === 0 &&
+          (formats.includes("files") || (formats.includes("png") && !hasText))
+        ) {
+          void pasteFromSystemClipboard();
+          return true;
+        }
+
         // Handle markdown text paste
         const text = clipboardData.getData("text/plain");
         if (!text) return false;
@@ -752,6 +863,26 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
   // Track reloadVersion to detect manual refreshes
   const lastReloadVersionRef = useRef(0);
 
+  // Restore the cursor and scroll position of a note continued from another device
+  const restoreHandoffPosition = useCallback(
+    (noteId: string) => {
+      if (!editor) return;
+      const handoff = handoffService.takePendingHandoffPosition(noteId);
+      if (!handoff) return;
+      if (handoff.position !== null) {
+        const position = Math.min(
+          handoff.position,
+          editor.state.doc.content.size,
+        );
+        editor.chain().focus().setTextSelection(position).run();
+      }
+      if (handoff.scrollTop !== null) {
+        scrollContainerRef.current?.scrollTo(0, handoff.scrollTop);
+      }
+    },
+    [editor],
+  );
+
   // Search navigation functions (defined after editor is created)
   const goToNextMatch = useCallback(() => {
     if (searchMatches.length === 0 || !editor) return;
@@ -930,8 +1061,68 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
         editor.commands.selectAll();
       }
       // For existing notes, don't auto-focus - let user click where they want
+
+      restoreHandoffPosition(loadingNoteId);
     });
-  }, [currentNote, editor, flushPendingSave, reloadVersion]);
+  }, [currentNote, editor, flushPendingSave, reloadVersion, restoreHandoffPosition]);
+
+  // A handoff for the note that's already open doesn't trigger a reload
+  useEffect(() => {
+    const handleHandoffPosition = (e: Event) => {
+      const noteId = (e as CustomEvent<string>).detail;
+      if (loadedNoteIdRef.current === noteId && !isLoadingRef.current) {
+        restoreHandoffPosition(noteId);
+      }
+    };
+    window.addEventListener("handoff-position", handleHandoffPosition);
+    return () => {
+      window.removeEventListener("handoff-position", handleHandoffPosition);
+    };
+  }, [restoreHandoffPosition]);
+
+  // Advertise the focused note for Handoff, with the cursor and scroll position
+  useEffect(() => {
+    if (!editor || !currentNote || externalFile || !settings?.handoffEnabled) {
+      return;
+    }
+    const noteId = currentNote.id;
+    const title = currentNote.title;
+    const scrollContainer = scrollContainerRef.current;
+    let timer: number | null = null;
+
+    const advertise = () => {
+      if (timer) clearTimeout(timer);
+      timer = window.setTimeout(() => {
+        handoffService
+          .advertiseHandoff({
+            noteId,
+            title,
+            position: editor.state.selection.from,
+            scrollTop: scrollContainer?.scrollTop ?? null,
+          })
+          .catch((error) => {
+            console.error("Failed to advertise handoff:", error);
+          });
+      }, 500);
+    };
+
+    advertise();
+    editor.on("selectionUpdate", advertise);
+    scrollContainer?.addEventListener("scroll", advertise, { passive: true });
+
+    return () => {
+      if (timer) clearTimeout(timer);
+      editor.off("selectionUpdate", advertise);
+      scrollContainer?.removeEventListener("scroll", advertise);
+      handoffService.clearHandoff().catch(() => {});
+    };
+  }, [
+    editor,
+    currentNote?.id,
+    currentNote?.title,
+    externalFile,
+    settings?.handoffEnabled,
+  ]);
 
   // Load external file content when externalFile changes
   useEffect(() => {
@@ -969,6 +1160,9 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
     });
   }, [externalFile?.path, externalFile?.content, editor, flushPendingSave]);
 
+  // Quitting waits for the pending save
+  useEffect(() => quitService.beforeQuit(flushPendingSave), [flushPendingSave]);
+
   // Scroll to top on mount (e.g., when returning from settings)
   useEffect(() => {
     scrollContainerRef.current?.scrollTo(0, 0);
@@ -997,6 +1191,144 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []); // Only run cleanup on unmount, not when saveNote changes
 
+  // Table context menu, opened at (left, top) in window coordinates by a
+  // right-click or from the keyboard
+  const openTableMenu = async (left: number, top: number) => {
+    if (!editor) return;
+    try {
+      // Get the position at the click coordinates
+      const clickPos = editor.view.posAtCoords({ left, top });
+
+      if (!clickPos) return;
+
+      // Set the selection to the clicked position
+      editor.chain().focus().setTextSelection(clickPos.pos).run();
+
+      // Now work with the updated selection
+      const { state } = editor;
+      const { selection } = state;
+      const { $anchor } = selection;
+
+      // Find the table cell/header node
+      let cellDepth = $anchor.depth;
+      while (
+        cellDepth > 0 &&
+        state.doc.resolve($anchor.pos).node(cellDepth).type.name !==
+          "tableCell" &&
+        state.doc.resolve($anchor.pos).node(cellDepth).type.name !==
+          "tableHeader"
+      ) {
+        cellDepth--;
+      }
+
+      // Guard: if we didn't find a table cell, bail out
+      if (cellDepth <= 0) return;
+
+      const resolvedNode = state.doc.resolve($anchor.pos).node(cellDepth);
+      if (
+        resolvedNode.type.name !== "tableCell" &&
+        resolvedNode.type.name !== "tableHeader"
+      ) {
+        return;
+      }
+
+      // Get the cell position
+      const cellPos = $anchor.before(cellDepth);
+
+      // Check if we're in the first column (index 0 in parent row)
+      const rowNode = state.doc.resolve(cellPos).node(cellDepth - 1);
+      let cellIndex = 0;
+      rowNode.forEach((_node, offset) => {
+        if (offset < cellPos - $anchor.before(cellDepth - 1) - 1) {
+          cellIndex++;
+        }
+      });
+      const isFirstColumn = cellIndex === 0;
+
+      // Check if we're in the first row (index 0 in parent table)
+      const tableNode = state.doc.resolve(cellPos).node(cellDepth - 2);
+      let rowIndex = 0;
+      tableNode.forEach((_node, offset) => {
+        if (
+          offset <
+          $anchor.before(cellDepth - 1) -
+            $anchor.before(cellDepth - 2) -
+            1
+        ) {
+          rowIndex++;
+        }
+      });
+      const isFirstRow = rowIndex === 0;
+
+      await popupMenu(
+        [
+          // Only show "Add Column Before" if not in first column
+          ...(isFirstColumn
+            ? []
+            : [
+                {
+                  text: "Add Column &Before",
+                  help: "Insert a column to the left of this cell",
+                  action: () => editor.chain().focus().addColumnBefore().run(),
+                },
+              ]),
+          {
+            text: "Add Column &After",
+            help: "Insert a column to the right of this cell",
+            action: () => editor.chain().focus().addColumnAfter().run(),
+          },
+          {
+            text: "Delete &Column",
+            help: "Delete the column containing this cell",
+            action: () => editor.chain().focus().deleteColumn().run(),
+          },
+          "separator",
+          // Only show "Add Row Above" if not in first row
+          ...(isFirstRow
+            ? []
+            : [
+                {
+                  text: "Add Row Ab&ove",
+                  help: "Insert a row above this cell",
+                  action: () => editor.chain().focus().addRowBefore().run(),
+                },
+              ]),
+          {
+            text: "Add Row Be&low",
+            help: "Insert a row below this cell",
+            action: () => editor.chain().focus().addRowAfter().run(),
+          },
+          {
+            text: "Delete &Row",
+            help: "Delete the row containing this cell",
+            action: () => editor.chain().focus().deleteRow().run(),
+          },
+          "separator",
+          {
+            text: "Toggle &Header Row",
+            help: "Turn the first row into headers, or back into cells",
+            action: () => editor.chain().focus().toggleHeaderRow().run(),
+          },
+          {
+            text: "Toggle Header Col&umn",
+            help: "Turn the first column into headers, or back into cells",
+            action: () => editor.chain().focus().toggleHeaderColumn().run(),
+          },
+          "separator",
+          {
+            text: "Delete &Table",
+            help: "Delete the whole table",
+            action: () => editor.chain().focus().deleteTable().run(),
+          },
+        ],
+        new LogicalPosition(left, top),
+      );
+    } catch (err) {
+      console.error("Table context menu error:", err);
+    }
+
+  };
+
   // Link handlers - show inline popup at cursor position
   const handleAddLink = useCallback(() => {
     if (!editor) return;
@@ -1123,11 +1455,12 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
   // Image handler
   const handleAddImage = useCallback(async () => {
     if (!editor) return;
-    const selected = await openDialog({
-      multiple: false,
+    const selected = await pickFile({
+      purpose: "import",
+      ...(await dialogLabels("insert-image")),
       filters: [
         {
-          name: "Images",
+          name: await translate("dialog-filter-images"),
           extensions: ["png", "jpg", "jpeg", "gif", "webp", "svg"],
         },
       ],
@@ -1143,8 +1476,8 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
         const notesFolder = await invoke<string>("get_notes_folder");
         const absolutePath = await join(notesFolder, relativePath);
 
-        // Convert to Tauri asset URL
-        const assetUrl = convertFileSrc(absolutePath);
+        // Convert to a smudge asset URL
+        const assetUrl = assetsService.assetUrl(absolutePath);
 
         // Insert image with asset URL
         editor.chain().focus().setImage({ src: assetUrl }).run();
@@ -1171,6 +1504,70 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
     return () => document.removeEventListener("keydown", handleKeyDown);
   }, [handleAddLink, editor]);
 
+  // Formatting on the Touch Bar while a note is open; the settings view,
+  // which replaces the editor, shows none
+  const hasNote = !!currentNote;
+  useEffect(() => {
+    if (!editor || !hasNote) return;
+    touchBarService
+      .setTouchBar([
+        { kind: "button", id: "bold", title: "B" },
+        { kind: "button", id: "italic", title: "I" },
+        { kind: "button", id: "strike", title: "S" },
+        {
+          kind: "segmented",
+          id: "heading",
+          labels: ["Text", "H1", "H2", "H3"],
+        },
+        { kind: "button", id: "taskList", title: "Checklist" },
+        { kind: "button", id: "link", title: "Link" },
+        { kind: "flexibleSpace" },
+        { kind: "button", id: "find", title: "Find" },
+      ])
+      .catch(() => {});
+
+    let unlisten: (() => void) | undefined;
+    touchBarService
+      .onTouchBarAction((action) => {
+        const chain = () => editor.chain().focus();
+        switch (action.id) {
+          case "bold":
+            chain().toggleBold().run();
+            break;
+          case "italic":
+            chain().toggleItalic().run();
+            break;
+          case "strike":
+            chain().toggleStrike().run();
+            break;
+          case "heading":
+            if (action.kind !== "segmentSelected") break;
+            if (action.index === 0) {
+              chain().setParagraph().run();
+            } else {
+              chain().setHeading({ level: action.index as 1 | 2 | 3 }).run();
+            }
+            break;
+          case "taskList":
+            chain().toggleTaskList().run();
+            break;
+          case "link":
+            handleAddLink();
+            break;
+          case "find":
+            setSearchOpen(true);
+            break;
+        }
+      })
+      .then((fn) => {
+        unlisten = fn;
+      });
+    return () => {
+      unlisten?.();
+      touchBarService.setTouchBar([]).catch(() => {});
+    };
+  }, [editor, hasNote, handleAddLink]);
+
   // Keyboard shortcut for Cmd+Shift+C to open copy menu
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
@@ -1309,9 +1706,8 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
       {/* Drag region with sidebar toggle, date and save status */}
       <div
         className={cn(
-          "h-11 shrink-0 flex items-center justify-between px-3",
-          !sidebarVisible && !externalFile && "pl-22",
-          externalFile && !sidebarVisible && "pl-22",
+          "h-11 shrink-0 flex items-center justify-between px-3 pr-[calc(0.75rem+var(--titlebar-inset-right))]",
+          !sidebarVisible && "pl-[calc(1rem+var(--titlebar-inset-left))]",
         )}
         data-tauri-drag-region
       >
@@ -1407,6 +1803,28 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
               </IconButton>
             </Tooltip>
           )}
+          {currentNote && !externalFile && (
+            <Tooltip
+              content={
+                miniWindowService.isMiniWindow()
+                  ? "Back to main window"
+                  : "Open in mini window"
+              }
+            >
+              <IconButton
+                onClick={() =>
+                  miniWindowService
+                    .swapMiniMode(currentNote.id)
+                    .catch((error) => {
+                      console.error("Failed to swap mini window:", error);
+                      toast.error("Failed to open mini window");
+                    })
+                }
+              >
+                <PictureInPictureIcon className="w-4.25 h-4.25 stroke-[1.6]" />
+              </IconButton>
+            </Tooltip>
+          )}
           {externalFile && (
             <Tooltip content="Close external file">
               <IconButton onClick={closeExternalFile}>
@@ -1505,152 +1923,18 @@ export function Editor({ onToggleSidebar, sidebarVisible }: EditorProps) {
         )}
         <div
           className="h-full"
-          onContextMenu={async (e) => {
+          onContextMenu={(e) => {
             if (!editor?.isActive("table")) return;
-
             e.preventDefault();
-
-            try {
-              // Get the position at the click coordinates
-              const clickPos = editor.view.posAtCoords({
-                left: e.clientX,
-                top: e.clientY,
-              });
-
-              if (!clickPos) return;
-
-              // Set the selection to the clicked position
-              editor.chain().focus().setTextSelection(clickPos.pos).run();
-
-              // Now work with the updated selection
-              const { state } = editor;
-              const { selection } = state;
-              const { $anchor } = selection;
-
-              // Find the table cell/header node
-              let cellDepth = $anchor.depth;
-              while (
-                cellDepth > 0 &&
-                state.doc.resolve($anchor.pos).node(cellDepth).type.name !==
-                  "tableCell" &&
-                state.doc.resolve($anchor.pos).node(cellDepth).type.name !==
-                  "tableHeader"
-              ) {
-                cellDepth--;
-              }
-
-              // Guard: if we didn't find a table cell, bail out
-              if (cellDepth <= 0) return;
-
-              const resolvedNode = state.doc.resolve($anchor.pos).node(cellDepth);
-              if (
-                resolvedNode.type.name !== "tableCell" &&
-                resolvedNode.type.name !== "tableHeader"
-              ) {
-                return;
-              }
-
-              // Get the cell position
-              const cellPos = $anchor.before(cellDepth);
-
-              // Check if we're in the first column (index 0 in parent row)
-              const rowNode = state.doc.resolve(cellPos).node(cellDepth - 1);
-              let cellIndex = 0;
-              rowNode.forEach((_node, offset) => {
-                if (offset < cellPos - $anchor.before(cellDepth - 1) - 1) {
-                  cellIndex++;
-                }
-              });
-              const isFirstColumn = cellIndex === 0;
-
-              // Check if we're in the first row (index 0 in parent table)
-              const tableNode = state.doc.resolve(cellPos).node(cellDepth - 2);
-              let rowIndex = 0;
-              tableNode.forEach((_node, offset) => {
-                if (
-                  offset <
-                  $anchor.before(cellDepth - 1) -
-                    $anchor.before(cellDepth - 2) -
-                    1
-                ) {
-                  rowIndex++;
-                }
-              });
-              const isFirstRow = rowIndex === 0;
-
-              const menuItems = [];
-
-              // Only show "Add Column Before" if not in first column
-              if (!isFirstColumn) {
-                menuItems.push(
-                  await MenuItem.new({
-                    text: "Add Column Before",
-                    action: () => editor.chain().focus().addColumnBefore().run(),
-                  }),
-                );
-              }
-              menuItems.push(
-                await MenuItem.new({
-                  text: "Add Column After",
-                  action: () => editor.chain().focus().addColumnAfter().run(),
-                }),
-              );
-              menuItems.push(
-                await MenuItem.new({
-                  text: "Delete Column",
-                  action: () => editor.chain().focus().deleteColumn().run(),
-                }),
-              );
-              menuItems.push(await PredefinedMenuItem.new({ item: "Separator" }));
-
-              // Only show "Add Row Above" if not in first row
-              if (!isFirstRow) {
-                menuItems.push(
-                  await MenuItem.new({
-                    text: "Add Row Above",
-                    action: () => editor.chain().focus().addRowBefore().run(),
-                  }),
-                );
-              }
-              menuItems.push(
-                await MenuItem.new({
-                  text: "Add Row Below",
-                  action: () => editor.chain().focus().addRowAfter().run(),
-                }),
-              );
-              menuItems.push(
-                await MenuItem.new({
-                  text: "Delete Row",
-                  action: () => editor.chain().focus().deleteRow().run(),
-                }),
-              );
-              menuItems.push(await PredefinedMenuItem.new({ item: "Separator" }));
-              menuItems.push(
-                await MenuItem.new({
-                  text: "Toggle Header Row",
-                  action: () => editor.chain().focus().toggleHeaderRow().run(),
-                }),
-              );
-              menuItems.push(
-                await MenuItem.new({
-                  text: "Toggle Header Column",
-                  action: () => editor.chain().focus().toggleHeaderColumn().run(),
-                }),
-              );
-              menuItems.push(await PredefinedMenuItem.new({ item: "Separator" }));
-              menuItems.push(
-                await MenuItem.new({
-                  text: "Delete Table",
-                  action: () => editor.chain().focus().deleteTable().run(),
-                }),
-              );
-
-              const menu = await Menu.new({ items: menuItems });
-
-              await menu.popup();
-            } catch (err) {
-              console.error("Table context menu error:", err);
-            }
+            void openTableMenu(e.clientX, e.clientY);
+          }}
+          onKeyDown={(e) => {
+            if (!isContextMenuShortcut(e) || !editor?.isActive("table")) return;
+            e.preventDefault();
+            const { left, top, bottom } = editor.view.coordsAtPos(
+              editor.state.selection.from,
+            );
+            void openTableMenu(left, (top + bottom) / 2);
           }}
         >
           <EditorContent editor={editor} className="h-full text-text" />
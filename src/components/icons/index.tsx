@@ -1149,6 +1149,75 @@ export function FoldersIcon({ className = "w-4.5 h-4.5" }: IconProps) {
   );
 }
 
+export function MinimizeIcon({ className = "w-4.5 h-4.5" }: IconProps) {
+  return (
+    <svg
+      className={className}
+      fill="none"
+      stroke="currentColor"
+      viewBox="0 0 24 24"
+      strokeWidth={2}
+      strokeLinecap="round"
+      strokeLinejoin="round"
+    >
+      <path stroke="none" d="M0 0h24v24H0z" fill="none" />
+      <path d="M5 12l14 0" />
+    </svg>
+  );
+}
+
+export function MaximizeIcon({ className = "w-4.5 h-4.5" }: IconProps) {
+  return (
+    <svg
+      className={className}
+      fill="none"
+      stroke="currentColor"
+      viewBox="0 0 24 24"
+      strokeWidth={2}
+      strokeLinecap="round"
+      strokeLinejoin="round"
+    >
+      <path stroke="none" d="M0 0h24v24H0z" fill="none" />
+      <path d="M5 5h14v14h-14z" />
+    </svg>
+  );
+}
+
+export function RestoreIcon({ className = "w-4.5 h-4.5" }: IconProps) {
+  return (
+    <svg
+      className={className}
+      fill="none"
+      stroke="currentColor"
+      viewBox="0 0 24 24"
+      strokeWidth={2}
+      strokeLinecap="round"
+      strokeLinejoin="round"
+    >
+      <path stroke="none" d="M0 0h24v24H0z" fill="none" />
+      <path d="M8 8h11v11h-11z" />
+      <path d="M5 16v-11h11" />
+    </svg>
+  );
+}
+
+export function PictureInPictureIcon({ className = "w-4.5 h-4.5" }: IconProps) {
+  return (
+    <svg
+      className={className}
+      fill="none"
+      stroke="currentColor"
+      viewBox="0 0 24 24"
+      strokeWidth={2}
+      strokeLinecap="round"
+      strokeLinejoin="round"
+    >
+      <path d="M21 9v-3a2 2 0 0 0 -2 -2h-15a2 2 0 0 0 -2 2v10a2 2 0 0 0 2 2h4" />
+      <rect x="12" y="13" width="10" height="7" rx="2" />
+    </svg>
+  );
+}
+
 export function ClaudeIcon({
   className = "w-4.5 h-4.5 text-text-muted",
 }: IconProps) {
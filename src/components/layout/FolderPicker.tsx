@@ -1,7 +1,7 @@
-import { open } from "@tauri-apps/plugin-dialog";
 import { useNotes } from "../../context/NotesContext";
 import { useTheme } from "../../context/ThemeContext";
 import { Button } from "../ui";
+import { dialogLabels, pickFolder } from "../../services/dialogs";
 
 export function FolderPicker() {
   const { setNotesFolder } = useNotes();
@@ -9,13 +9,12 @@ export function FolderPicker() {
 
   const handleSelectFolder = async () => {
     try {
-      const selected = await open({
-        directory: true,
-        multiple: false,
-        title: "Choose Notes Folder",
+      const selected = await pickFolder({
+        purpose: "notes-folder",
+        ...(await dialogLabels("choose-notes-folder")),
       });
 
-      if (selected && typeof selected === "string") {
+      if (selected) {
         await setNotesFolder(selected);
         // Reload theme/font settings from the new folder's .smudge/settings.json
         await reloadSettings();
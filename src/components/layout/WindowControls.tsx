@@ -0,0 +1,61 @@
+import { useEffect, useState } from "react";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { MinimizeIcon, MaximizeIcon, RestoreIcon, XIcon } from "../icons";
+import { onWindowFrame } from "../../services/windowFrames";
+
+// Caption buttons for the custom titlebar on Windows and Linux. macOS keeps
+// its native traffic lights, so this is never rendered there.
+export function WindowControls() {
+  const [maximized, setMaximized] = useState(false);
+
+  useEffect(() => {
+    const appWindow = getCurrentWindow();
+    let unlisten: (() => void) | undefined;
+
+    appWindow.isMaximized().then(setMaximized).catch(() => {});
+    // Snapping and double-clicks change the state too, not just the button
+    onWindowFrame((frame) => setMaximized(frame.maximized)).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  const buttonClass =
+    "h-full w-11.5 flex items-center justify-center text-text-muted hover:text-text hover:bg-bg-muted transition-colors";
+
+  return (
+    <div className="titlebar-no-drag fixed top-0 right-0 z-30 h-(--titlebar-height) flex">
+      <button
+        className={buttonClass}
+        onClick={() => getCurrentWindow().minimize()}
+        title="Minimize"
+        tabIndex={-1}
+      >
+        <MinimizeIcon className="w-4 h-4 stroke-[1.5]" />
+      </button>
+      <button
+        className={buttonClass}
+        onClick={() => getCurrentWindow().toggleMaximize()}
+        title={maximized ? "Restore" : "Maximize"}
+        tabIndex={-1}
+      >
+        {maximized ? (
+          <RestoreIcon className="w-3.5 h-3.5 stroke-[1.5]" />
+        ) : (
+          <MaximizeIcon className="w-3.5 h-3.5 stroke-[1.5]" />
+        )}
+      </button>
+      <button
+        className="h-full w-11.5 flex items-center justify-center text-text-muted hover:text-white hover:bg-red-600 transition-colors"
+        onClick={() => getCurrentWindow().close()}
+        title="Close"
+        tabIndex={-1}
+      >
+        <XIcon className="w-4 h-4 stroke-[1.5]" />
+      </button>
+    </div>
+  );
+}
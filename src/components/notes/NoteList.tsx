@@ -1,5 +1,7 @@
 import { useCallback, useMemo, memo, useEffect, useRef, useState } from "react";
-import { Menu, MenuItem } from "@tauri-apps/api/menu";
+import { LogicalPosition } from "@tauri-apps/api/dpi";
+import { join } from "@tauri-apps/api/path";
+import { toast } from "sonner";
 import { useNotes } from "../../context/NotesContext";
 import {
   ListItem,
@@ -14,6 +16,23 @@ import {
 } from "../ui";
 import { cleanTitle } from "../../lib/utils";
 import * as notesService from "../../services/notes";
+import * as storageService from "../../services/storage";
+import * as jobsService from "../../services/jobs";
+import * as dockService from "../../services/dock";
+import * as dragService from "../../services/drag";
+import * as shareService from "../../services/share";
+import * as shortcutsService from "../../services/shortcuts";
+import { dialogLabels, showDialog } from "../../services/dialogs";
+import { translate } from "../../services/i18n";
+import { trashName, type TrashHandle } from "../../services/trash";
+import { fileManagerName, revealFiles } from "../../services/fileMetadata";
+import {
+  isContextMenuShortcut,
+  menuPositionFor,
+  popupMenu,
+  type AccessibleMenuEntry,
+} from "../../services/menu";
+import type { StorageInfo } from "../../services/storage";
 import type { Settings } from "../../types/note";
 
 function formatDate(timestamp: number): string {
@@ -69,6 +88,7 @@ interface NoteItemProps {
   isSelected: boolean;
   isPinned: boolean;
   onSelect: (id: string, e: React.MouseEvent) => void;
+  onDragStart: (e: React.DragEvent, id: string) => void;
   onContextMenu: (e: React.MouseEvent, id: string) => void;
 }
 
@@ -80,12 +100,19 @@ const NoteItem = memo(function NoteItem({
   isSelected,
   isPinned,
   onSelect,
+  onDragStart,
   onContextMenu,
 }: NoteItemProps) {
   const handleClick = useCallback(
     (e: React.MouseEvent) => onSelect(id, e),
     [onSelect, id]
   );
+
+  const handleDragStart = useCallback(
+    (e: React.DragEvent) => onDragStart(e, id),
+    [onDragStart, id]
+  );
+
   const handleContextMenu = useCallback(
     (e: React.MouseEvent) => onContextMenu(e, id),
     [onContextMenu, id]
@@ -99,6 +126,7 @@ const NoteItem = memo(function NoteItem({
       isSelected={isSelected}
       isPinned={isPinned}
       onClick={handleClick}
+      onDragStart={handleDragStart}
       onContextMenu={handleContextMenu}
     />
   );
@@ -110,18 +138,21 @@ export function NoteList() {
     selectedNoteId,
     selectNote,
     deleteNote,
+    restoreNote,
     duplicateNote,
     pinNote,
     unpinNote,
     isLoading,
     searchQuery,
     searchResults,
+    notesFolder,
   } = useNotes();
 
   const [deleteDialogOpen, setDeleteDialogOpen] = useState(false);
   const [notesToDelete, setNotesToDelete] = useState<string[]>([]);
   const [multiSelectedIds, setMultiSelectedIds] = useState<Set<string>>(new Set());
   const [settings, setSettings] = useState<Settings | null>(null);
+  const [storageInfo, setStorageInfo] = useState<StorageInfo | null>(null);
   const containerRef = useRef<HTMLDivElement>(null);
   const lastSelectedIdRef = useRef<string | null>(null);
 
@@ -135,6 +166,16 @@ export function NoteList() {
       });
   }, [notes]);
 
+  // Cloud provider backing the notes folder, for download/evict actions
+  useEffect(() => {
+    storageService
+      .getStorageInfo()
+      .then(setStorageInfo)
+      .catch((error) => {
+        console.error("Failed to load storage info:", error);
+      });
+  }, [notesFolder]);
+
   // Calculate pinned IDs set for efficient lookup
   const pinnedIds = useMemo(
     () => new Set(settings?.pinnedNoteIds || []),
@@ -144,31 +185,218 @@ export function NoteList() {
   const handleDeleteConfirm = useCallback(async () => {
     if (notesToDelete.length > 0) {
       try {
+        const handles: TrashHandle[] = [];
         for (const noteId of notesToDelete) {
-          await deleteNote(noteId);
+          const handle = await deleteNote(noteId);
+          if (handle) handles.push(handle);
         }
         setNotesToDelete([]);
         setMultiSelectedIds(new Set());
         setDeleteDialogOpen(false);
+        if (handles.length > 0) {
+          toast(
+            handles.length === 1
+              ? `Moved note to ${trashName}`
+              : `Moved ${handles.length} notes to ${trashName}`,
+            {
+              action: {
+                label: "Undo",
+                onClick: () => {
+                  Promise.all(handles.map(restoreNote)).catch((err) =>
+                    toast.error(
+                      typeof err === "string" ? err : "Failed to restore notes",
+                    ),
+                  );
+                },
+              },
+            },
+          );
+        }
       } catch (error) {
         console.error("Failed to delete note:", error);
       }
     }
-  }, [notesToDelete, deleteNote]);
+  }, [notesToDelete, deleteNote, restoreNote]);
+
+  // Export notes as a cancellable batch job, to HTML or Markdown files as
+  // picked in the folder dialog, or straight to `to` when a shortcut says
+  // where. Resolves with the report, or null if the dialog was cancelled.
+  const exportNotes = useCallback(
+    async (
+      ids: string[],
+      to?: { destination: string; asMarkdown: boolean },
+    ): Promise<jobsService.BatchReport | null> => {
+      if (!notesFolder) return null;
+      let destination = to?.destination;
+      let asMarkdown = to?.asMarkdown ?? false;
+      if (!destination) {
+        const picked = await showDialog("pickFolder", {
+          purpose: "export",
+          ...(await dialogLabels("export-notes", { count: ids.length })),
+          accessory: [
+            {
+              kind: "popup",
+              label: await translate("dialog-export-format"),
+              options: ["HTML", "Markdown"],
+            },
+          ],
+        });
+        if (!picked) return null;
+        destination = picked.path;
+        asMarkdown = picked.accessory[0] === 1;
+      }
 
-  const handleContextMenu = useCallback(
-    async (e: React.MouseEvent, noteId: string) => {
+      const jobId = `export-${Date.now()}`;
+      const unlisten = await jobsService.onJobProgress(jobId, (progress) => {
+        if (progress.state !== "running" && progress.state !== "paused") return;
+        if (progress.total > 0) {
+          dockService
+            .setDockProgress(progress.completed / progress.total)
+            .catch(() => {});
+        }
+        toast.loading(
+          `Exporting ${progress.completed} of ${progress.total}...`,
+          {
+            id: jobId,
+            action: {
+              label: "Cancel",
+              onClick: () => jobsService.cancelJob(jobId),
+            },
+          },
+        );
+      });
+
+      try {
+        const paths = await Promise.all(
+          ids.map((id) => join(notesFolder, `${id}.md`)),
+        );
+        const report = await jobsService.batchProcess(
+          paths,
+          { type: asMarkdown ? "export" : "exportHtml", destination },
+          jobId,
+        );
+        const exported = `Exported ${report.succeeded} note${report.succeeded === 1 ? "" : "s"}`;
+        if (report.failed > 0) {
+          toast.error(`${exported}, ${report.failed} failed`, { id: jobId });
+        } else if (report.cancelled) {
+          toast(`${exported} before cancelling`, { id: jobId });
+        } else {
+          toast.success(exported, { id: jobId });
+        }
+        return report;
+      } catch (error) {
+        console.error("Failed to export notes:", error);
+        toast.error("Failed to export notes", { id: jobId });
+        throw error;
+      } finally {
+        unlisten();
+        dockService.setDockProgress(null).catch(() => {});
+      }
+    },
+    [notesFolder],
+  );
+
+  const revealNotes = useCallback(
+    async (ids: string[]) => {
+      if (!notesFolder) return;
+      try {
+        const paths = await Promise.all(
+          ids.map((id) => join(notesFolder, `${id}.md`)),
+        );
+        await revealFiles(paths);
+      } catch (error) {
+        console.error("Failed to reveal notes:", error);
+        toast.error(`Failed to show in ${fileManagerName}`);
+      }
+    },
+    [notesFolder],
+  );
+
+  // Share note files through the OS share sheet, anchored where the menu
+  // was opened
+  const shareNotes = useCallback(
+    async (ids: string[], anchor: LogicalPosition) => {
+      if (!notesFolder) return;
+      try {
+        const paths = await Promise.all(
+          ids.map((id) => join(notesFolder, `${id}.md`)),
+        );
+        const outcome = await shareService.share(
+          paths.map((path) => ({ kind: "file" as const, path })),
+          anchor,
+        );
+        if (outcome.kind === "failed") {
+          toast.error(`Failed to share: ${outcome.message}`);
+        }
+      } catch (error) {
+        console.error("Failed to share notes:", error);
+        toast.error(typeof error === "string" ? error : "Failed to share");
+      }
+    },
+    [notesFolder],
+  );
+
+  // Drag the note, or the selection it's part of, out as files; with Alt
+  // (Option) held, as HTML pages instead
+  const handleDragStart = useCallback(
+    async (e: React.DragEvent, noteId: string) => {
       e.preventDefault();
+      if (!notesFolder) return;
+      const ids =
+        multiSelectedIds.size > 1 && multiSelectedIds.has(noteId)
+          ? Array.from(multiSelectedIds)
+          : [noteId];
+      const kind = e.altKey ? "html" : "file";
+      try {
+        const paths = await Promise.all(
+          ids.map((id) => join(notesFolder, `${id}.md`)),
+        );
+        await dragService.startDrag(paths.map((path) => ({ kind, path })));
+      } catch (error) {
+        console.error("Failed to drag notes:", error);
+      }
+    },
+    [notesFolder, multiSelectedIds],
+  );
+
+  const openContextMenu = useCallback(
+    async (noteId: string, at: LogicalPosition) => {
       const isPinned = pinnedIds.has(noteId);
       const noteIdsForDelete =
         multiSelectedIds.size > 1 && multiSelectedIds.has(noteId)
           ? Array.from(multiSelectedIds)
           : [noteId];
-
-      const menu = await Menu.new({
-        items: [
-          await MenuItem.new({
-            text: isPinned ? "Unpin" : "Pin",
+      const count = noteIdsForDelete.length;
+
+      const isPlaceholder =
+        notes.find((note) => note.id === noteId)?.isPlaceholder ?? false;
+      const storageItems: AccessibleMenuEntry[] = storageInfo?.supportsEviction
+        ? [
+            {
+              text: isPlaceholder ? "Download &Now" : "&Remove Download",
+              help: isPlaceholder
+                ? "Download this note from cloud storage"
+                : "Free up space by keeping this note only in cloud storage",
+              action: async () => {
+                try {
+                  await (isPlaceholder
+                    ? storageService.downloadNote(noteId)
+                    : storageService.evictNote(noteId));
+                } catch (error) {
+                  console.error("Failed to update download:", error);
+                }
+              },
+            },
+          ]
+        : [];
+
+      await popupMenu(
+        [
+          {
+            text: isPinned ? "Un&pin" : "&Pin",
+            help: isPinned
+              ? "Stop keeping this note at the top of the list"
+              : "Keep this note at the top of the list",
             action: async () => {
               try {
                 await (isPinned ? unpinNote(noteId) : pinNote(noteId));
@@ -179,24 +407,70 @@ export function NoteList() {
                 console.error("Failed to pin/unpin note:", error);
               }
             },
-          }),
-          await MenuItem.new({
-            text: "Duplicate",
+          },
+          {
+            text: "&Duplicate",
+            help: "Create a copy of this note",
             action: () => duplicateNote(noteId),
-          }),
-          await MenuItem.new({
-            text: "Delete",
+          },
+          {
+            text: count > 1 ? `&Export ${count} Notes...` : "&Export...",
+            help:
+              count > 1
+                ? `Save the ${count} selected notes as HTML or Markdown files`
+                : "Save this note as an HTML or Markdown file",
+            action: () => exportNotes(noteIdsForDelete).catch(() => {}),
+          },
+          {
+            text: "Sh&are...",
+            help:
+              count > 1
+                ? `Send the ${count} selected notes to another app or person`
+                : "Send this note to another app or person",
+            action: () => shareNotes(noteIdsForDelete, at),
+          },
+          ...storageItems,
+          {
+            text: `S&how in ${fileManagerName}`,
+            help:
+              count > 1
+                ? `Select the ${count} note files in ${fileManagerName}`
+                : `Select the note file in ${fileManagerName}`,
+            action: () => revealNotes(noteIdsForDelete),
+          },
+          {
+            text: "De&lete",
+            accessibilityTitle: count > 1 ? `Delete ${count} Notes` : undefined,
+            help: `Move to the ${trashName} after confirming`,
             action: () => {
               setNotesToDelete(noteIdsForDelete);
               setDeleteDialogOpen(true);
             },
-          }),
+          },
         ],
-      });
+        at,
+      );
+    },
+    [
+      pinnedIds,
+      pinNote,
+      unpinNote,
+      duplicateNote,
+      multiSelectedIds,
+      notes,
+      storageInfo,
+      exportNotes,
+      shareNotes,
+      revealNotes,
+    ]
+  );
 
-      await menu.popup();
+  const handleContextMenu = useCallback(
+    (e: React.MouseEvent, noteId: string) => {
+      e.preventDefault();
+      void openContextMenu(noteId, new LogicalPosition(e.clientX, e.clientY));
     },
-    [pinnedIds, pinNote, unpinNote, duplicateNote, multiSelectedIds]
+    [openContextMenu]
   );
 
   // Memoize display items to prevent recalculation on every render
@@ -238,6 +512,59 @@ export function NoteList() {
     setDeleteDialogOpen(true);
   }, [multiSelectedIds, selectedNoteId]);
 
+  // "Export Selection" from the Shortcuts app, answered with the exported
+  // files' paths, one per line
+  useEffect(() => {
+    const handler = async (event: Event) => {
+      const { id, intent } = (
+        event as CustomEvent<shortcutsService.IntentInvocation>
+      ).detail;
+      if (intent.action !== "exportSelection") return;
+      const ids =
+        multiSelectedIds.size > 0
+          ? Array.from(multiSelectedIds)
+          : selectedNoteId
+            ? [selectedNoteId]
+            : [];
+      try {
+        if (ids.length === 0) {
+          throw new Error("No notes are selected");
+        }
+        const report = await exportNotes(
+          ids,
+          intent.destination
+            ? {
+                destination: intent.destination,
+                asMarkdown: intent.format === "markdown",
+              }
+            : undefined,
+        );
+        if (!report) {
+          await shortcutsService.completeIntent(id, { outcome: "cancel" });
+          return;
+        }
+        const paths = report.results
+          .map((result) => result.output)
+          .filter((output): output is string => output !== null);
+        await shortcutsService.completeIntent(id, {
+          outcome: "success",
+          values: { paths: paths.join("\n") },
+        });
+      } catch (error) {
+        const message = error instanceof Error ? error.message : String(error);
+        await shortcutsService
+          .completeIntent(id, { outcome: "error", message })
+          .catch(() => {});
+      }
+    };
+    window.addEventListener(shortcutsService.EXPORT_SELECTION_EVENT, handler);
+    return () =>
+      window.removeEventListener(
+        shortcutsService.EXPORT_SELECTION_EVENT,
+        handler,
+      );
+  }, [exportNotes, multiSelectedIds, selectedNoteId]);
+
   const handleSelect = useCallback(
     (id: string, e: React.MouseEvent) => {
       containerRef.current?.focus();
@@ -284,13 +611,37 @@ export function NoteList() {
     [displayItems, selectNote, selectedNoteId]
   );
 
+  // Open the selected note's menu below it, for Shift+F10 and the Menu key
+  const openSelectedContextMenu = useCallback(() => {
+    if (!selectedNoteId) return;
+    const index = displayItems.findIndex((item) => item.id === selectedNoteId);
+    const element = containerRef.current?.children[index];
+    if (!element) return;
+    void openContextMenu(selectedNoteId, menuPositionFor(element));
+  }, [selectedNoteId, displayItems, openContextMenu]);
+
   const handleListKeyDown = useCallback(
     (e: React.KeyboardEvent<HTMLDivElement>) => {
+      if (isContextMenuShortcut(e)) {
+        e.preventDefault();
+        openSelectedContextMenu();
+        return;
+      }
       if (e.key !== "Delete" && e.key !== "Backspace") return;
       e.preventDefault();
       queueDeleteSelection();
     },
-    [queueDeleteSelection]
+    [queueDeleteSelection, openSelectedContextMenu]
+  );
+
+  // Keyboard-opened context menus target the focused list, not an item
+  const handleListContextMenu = useCallback(
+    (e: React.MouseEvent<HTMLDivElement>) => {
+      if (e.target !== e.currentTarget) return;
+      e.preventDefault();
+      openSelectedContextMenu();
+    },
+    [openSelectedContextMenu]
   );
 
   // Listen for focus request from editor (when Escape is pressed)
@@ -334,7 +685,9 @@ export function NoteList() {
         ref={containerRef}
         tabIndex={0}
         data-note-list
+        aria-label="Notes"
         onKeyDown={handleListKeyDown}
+        onContextMenu={handleListContextMenu}
         className="flex flex-col gap-1 p-1.5 outline-none"
       >
         {displayItems.map((item) => (
@@ -347,6 +700,7 @@ export function NoteList() {
             isSelected={selectedNoteId === item.id || multiSelectedIds.has(item.id)}
             isPinned={pinnedIds.has(item.id)}
             onSelect={handleSelect}
+            onDragStart={handleDragStart}
             onContextMenu={handleContextMenu}
           />
         ))}
@@ -361,8 +715,8 @@ export function NoteList() {
             </AlertDialogTitle>
             <AlertDialogDescription>
               {notesToDelete.length > 1
-                ? `This will permanently delete ${notesToDelete.length} notes and all their content. This action cannot be undone.`
-                : "This will permanently delete the note and all its content. This action cannot be undone."}
+                ? `This will move ${notesToDelete.length} notes to the ${trashName}. You can restore them from there.`
+                : `This will move the note to the ${trashName}. You can restore it from there.`}
             </AlertDialogDescription>
           </AlertDialogHeader>
           <AlertDialogFooter>
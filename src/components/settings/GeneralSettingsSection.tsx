@@ -6,8 +6,25 @@ import { useNotes } from "../../context/NotesContext";
 import { useTheme } from "../../context/ThemeContext";
 import { useGit } from "../../context/GitContext";
 import { showUpdateToast } from "../../App";
+import { WatchFoldersSection } from "./WatchFoldersSection";
+import { isMac } from "../../lib/platform";
+import * as notesService from "../../services/notes";
+import * as titlebarService from "../../services/titlebar";
+import * as placementService from "../../services/placement";
+import * as restorationService from "../../services/restoration";
+import * as globalShortcutsService from "../../services/globalShortcuts";
+import * as trayService from "../../services/tray";
+import * as loginItemService from "../../services/loginItem";
+import * as occlusionService from "../../services/occlusion";
+import * as dockService from "../../services/dock";
+import * as fileAssociationsService from "../../services/fileAssociations";
+import * as quarantineService from "../../services/quarantine";
+import * as capabilitiesService from "../../services/capabilities";
+import * as i18nService from "../../services/i18n";
+import { dialogLabels, pickFolder } from "../../services/dialogs";
 import { Button } from "../ui";
 import { Input } from "../ui";
+import { Select } from "../ui";
 import {
   FolderIcon,
   FoldersIcon,
@@ -17,6 +34,15 @@ import {
   CloudPlusIcon,
 } from "../icons";
 
+// A language's name in that language, e.g. "Deutsch" for "de"
+function languageName(tag: string): string {
+  try {
+    return new Intl.DisplayNames([tag], { type: "language" }).of(tag) ?? tag;
+  } catch {
+    return tag;
+  }
+}
+
 // Format remote URL for display - extract user/repo from full URL
 function formatRemoteUrl(url: string | null): string {
   if (!url) return "Connected";
@@ -64,6 +90,30 @@ export function GeneralSettingsSection() {
   const [showRemoteInput, setShowRemoteInput] = useState(false);
   const [appVersion, setAppVersion] = useState<string>("");
   const [checkingUpdate, setCheckingUpdate] = useState(false);
+  const [handoffEnabled, setHandoffEnabled] = useState(false);
+  const [titlebarMode, setTitlebarMode] =
+    useState<titlebarService.TitlebarMode | null>(null);
+  const [placementPolicy, setPlacementPolicy] =
+    useState<placementService.PlacementPolicy | null>(null);
+  const [restoreWindows, setRestoreWindows] = useState<boolean | null>(null);
+  const [trayVisible, setTrayVisible] = useState<boolean | null>(null);
+  const [launchAtLogin, setLaunchAtLogin] = useState<boolean | null>(null);
+  const [renderThrottling, setRenderThrottling] = useState<boolean | null>(
+    null,
+  );
+  const [dockVisible, setDockVisible] = useState(true);
+  // undefined until loaded; null when quick capture is off
+  const [quickCaptureShortcut, setQuickCaptureShortcut] = useState<
+    string | null | undefined
+  >(undefined);
+  const [quarantinePolicy, setQuarantinePolicy] =
+    useState<quarantineService.QuarantinePolicy | null>(null);
+  const [locale, setLocale] = useState<i18nService.LocaleInfo | null>(null);
+  const [capabilities, setCapabilities] =
+    useState<capabilitiesService.CapabilityReport | null>(null);
+  const [fileAssociations, setFileAssociations] = useState<
+    fileAssociationsService.FileAssociation[]
+  >([]);
 
   useEffect(() => {
     getVersion()
@@ -71,6 +121,230 @@ export function GeneralSettingsSection() {
       .catch(() => {});
   }, []);
 
+  useEffect(() => {
+    titlebarService
+      .getTitlebarInfo()
+      .then((info) => setTitlebarMode(info.mode))
+      .catch(() => {});
+    placementService
+      .getPlacementPolicy()
+      .then(setPlacementPolicy)
+      .catch(() => {});
+    restorationService
+      .getRestoreWindows()
+      .then(setRestoreWindows)
+      .catch(() => {});
+    trayService
+      .isTrayVisible()
+      .then(setTrayVisible)
+      .catch(() => {});
+    loginItemService
+      .launchAtLogin()
+      .then(setLaunchAtLogin)
+      .catch(() => {});
+    occlusionService
+      .getRenderThrottling()
+      .then(setRenderThrottling)
+      .catch(() => {});
+    dockService
+      .isDockVisible()
+      .then(setDockVisible)
+      .catch(() => {});
+    globalShortcutsService
+      .getQuickCaptureShortcut()
+      .then(setQuickCaptureShortcut)
+      .catch(() => {});
+    fileAssociationsService
+      .listFileAssociations()
+      .then(setFileAssociations)
+      .catch(() => {});
+    quarantineService
+      .getQuarantinePolicy()
+      .then(setQuarantinePolicy)
+      .catch(() => {});
+    capabilitiesService
+      .getCapabilities()
+      .then(setCapabilities)
+      .catch(() => {});
+  }, []);
+
+  // The language can also change with the system's, or in another window
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    i18nService
+      .getLocale()
+      .then(setLocale)
+      .catch(() => {});
+    i18nService
+      .onLocaleChanged(setLocale)
+      .then((fn) => {
+        unlisten = fn;
+      });
+    return () => unlisten?.();
+  }, []);
+
+  useEffect(() => {
+    if (!notesFolder) return;
+    notesService
+      .getSettings()
+      .then((settings) => setHandoffEnabled(settings.handoffEnabled ?? false))
+      .catch(() => {});
+  }, [notesFolder]);
+
+  const handleToggleHandoff = async () => {
+    const enabled = !handoffEnabled;
+    try {
+      const settings = await notesService.getSettings();
+      await notesService.updateSettings({ ...settings, handoffEnabled: enabled });
+      setHandoffEnabled(enabled);
+    } catch (err) {
+      console.error("Failed to update Handoff setting:", err);
+      toast.error("Failed to update Handoff setting");
+    }
+  };
+
+  const handleToggleTitlebar = async () => {
+    const mode = titlebarMode === "custom" ? "native" : "custom";
+    try {
+      await titlebarService.setTitlebarMode(mode);
+      setTitlebarMode(mode);
+    } catch (err) {
+      console.error("Failed to change title bar:", err);
+      toast.error("Failed to change title bar");
+    }
+  };
+
+  const handlePlacementChange = async (
+    policy: placementService.PlacementPolicy,
+  ) => {
+    try {
+      await placementService.setPlacementPolicy(policy);
+      setPlacementPolicy(policy);
+    } catch (err) {
+      console.error("Failed to update window placement:", err);
+      toast.error("Failed to update window placement");
+    }
+  };
+
+  const handleToggleRestoreWindows = async () => {
+    const enabled = !restoreWindows;
+    try {
+      await restorationService.setRestoreWindows(enabled);
+      setRestoreWindows(enabled);
+    } catch (err) {
+      console.error("Failed to update window restoration:", err);
+      toast.error("Failed to update window restoration");
+    }
+  };
+
+  const handleToggleLaunchAtLogin = async () => {
+    const enabled = !launchAtLogin;
+    try {
+      await loginItemService.setLaunchAtLogin(enabled);
+      setLaunchAtLogin(enabled);
+    } catch (err) {
+      console.error("Failed to update launch at login:", err);
+      toast.error(
+        typeof err === "string" ? err : "Failed to update launch at login",
+      );
+    }
+  };
+
+  const handleToggleRenderThrottling = async () => {
+    const enabled = !renderThrottling;
+    try {
+      await occlusionService.setRenderThrottling(enabled);
+      setRenderThrottling(enabled);
+    } catch (err) {
+      console.error("Failed to update render throttling:", err);
+      toast.error("Failed to update render throttling");
+    }
+  };
+
+  const handleToggleTray = async () => {
+    const visible = !trayVisible;
+    try {
+      await trayService.setTrayVisible(visible);
+      setTrayVisible(visible);
+      // Without either icon there would be no way back to Smudge
+      if (!visible && !dockVisible) {
+        await dockService.setDockVisible(true);
+        setDockVisible(true);
+      }
+    } catch (err) {
+      console.error("Failed to update tray icon:", err);
+      toast.error("Failed to update tray icon");
+    }
+  };
+
+  const handleToggleDock = async () => {
+    const visible = !dockVisible;
+    try {
+      await dockService.setDockVisible(visible);
+      setDockVisible(visible);
+    } catch (err) {
+      console.error("Failed to update Dock icon:", err);
+      toast.error("Failed to update Dock icon");
+    }
+  };
+
+  const handleQuickCaptureShortcutChange = async (shortcut: string | null) => {
+    try {
+      await globalShortcutsService.setQuickCaptureShortcut(shortcut);
+      setQuickCaptureShortcut(shortcut);
+    } catch (err) {
+      console.error("Failed to update quick capture shortcut:", err);
+      toast.error(
+        typeof err === "string" ? err : "Failed to update quick capture shortcut",
+      );
+    }
+  };
+
+  // An empty choice follows the system's languages
+  const handleLanguageChange = async (language: string) => {
+    try {
+      setLocale(await i18nService.setLocale(language || null));
+    } catch (err) {
+      console.error("Failed to change language:", err);
+      toast.error("Failed to change language");
+    }
+  };
+
+  const handleQuarantinePolicyChange = async (
+    policy: quarantineService.QuarantinePolicy,
+  ) => {
+    try {
+      await quarantineService.setQuarantinePolicy(policy);
+      setQuarantinePolicy(policy);
+    } catch (err) {
+      console.error("Failed to update download warnings:", err);
+      toast.error("Failed to update download warnings");
+    }
+  };
+
+  const handleToggleFileAssociation = async (
+    association: fileAssociationsService.FileAssociation,
+  ) => {
+    try {
+      const change = association.owned
+        ? await fileAssociationsService.releaseFileAssociation(association.id)
+        : await fileAssociationsService.claimFileAssociation(association.id);
+      setFileAssociations((current) =>
+        current.map((a) =>
+          a.id === change.association.id ? change.association : a,
+        ),
+      );
+      if (change.needsConfirmation) {
+        toast(`Choose Smudge for ${association.name} files in Default Apps`);
+      }
+    } catch (err) {
+      console.error("Failed to change default app:", err);
+      toast.error(
+        typeof err === "string" ? err : "Failed to change default app",
+      );
+    }
+  };
+
   const handleCheckForUpdates = async () => {
     setCheckingUpdate(true);
     const result = await showUpdateToast();
@@ -84,8 +358,10 @@ export function GeneralSettingsSection() {
 
   const handleChangeFolder = async () => {
     try {
-      const selected = await invoke<string | null>("open_folder_dialog", {
-        defaultPath: notesFolder || null,
+      const selected = await pickFolder({
+        purpose: "notes-folder",
+        ...(await dialogLabels("choose-notes-folder")),
+        defaultPath: notesFolder,
       });
 
       if (selected) {
@@ -196,6 +472,16 @@ export function GeneralSettingsSection() {
       {/* Divider */}
       <div className="border-t border-border border-dashed" />
 
+      {/* Watch Folders */}
+      {notesFolder && (
+        <>
+          <WatchFoldersSection />
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
       {/* Git Section */}
       <section>
         <h2 className="text-xl font-medium mb-0.5">Version Control</h2>
@@ -449,7 +735,354 @@ export function GeneralSettingsSection() {
       {/* Divider */}
       <div className="border-t border-border border-dashed" />
 
-      {/* About */}
+      {/* Language */}
+      {locale && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Language</h2>
+            <p className="text-sm text-text-muted mb-4">
+              Choose the language of Smudge's menus and file dialogs, or
+              follow your system's
+            </p>
+            <Select
+              value={locale.chosen ?? ""}
+              onChange={(e) => handleLanguageChange(e.target.value)}
+              className="w-64"
+            >
+              <option value="">
+                System Language
+                {locale.chosen ? "" : ` (${languageName(locale.locale)})`}
+              </option>
+              {locale.available.map((tag) => (
+                <option key={tag} value={tag}>
+                  {languageName(tag)}
+                </option>
+              ))}
+            </Select>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Title bar */}
+      {titlebarMode && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Title Bar</h2>
+            <p className="text-sm text-text-muted mb-4">
+              Draw the title bar inside the window for a seamless look, or
+              use the system's
+            </p>
+            <Button onClick={handleToggleTitlebar} variant="outline" size="md">
+              {titlebarMode === "custom"
+                ? "Use System Title Bar"
+                : "Use Custom Title Bar"}
+            </Button>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Window placement */}
+      {placementPolicy && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Window Placement</h2>
+            <p className="text-sm text-text-muted mb-4">
+              Choose which display new windows open on
+            </p>
+            <Select
+              value={placementPolicy}
+              onChange={(e) =>
+                handlePlacementChange(
+                  e.target.value as placementService.PlacementPolicy,
+                )
+              }
+              className="w-64"
+            >
+              <option value="remembered">Where it was last closed</option>
+              <option value="cursorMonitor">Display with the pointer</option>
+              <option value="focusedWindowMonitor">
+                Display of the focused window
+              </option>
+            </Select>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Window restoration */}
+      {restoreWindows !== null && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Restore Windows</h2>
+            <p className="text-sm text-text-muted mb-4">
+              Reopen every window where it was, with the same note and zoom,
+              when Smudge starts again
+            </p>
+            <Button
+              onClick={handleToggleRestoreWindows}
+              variant="outline"
+              size="md"
+            >
+              {restoreWindows ? "Stop Restoring Windows" : "Restore Windows"}
+            </Button>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Start at login */}
+      {launchAtLogin !== null && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Start at Login</h2>
+            <p className="text-sm text-text-muted mb-4">
+              Open Smudge automatically when you log in
+            </p>
+            <Button
+              onClick={handleToggleLaunchAtLogin}
+              variant="outline"
+              size="md"
+            >
+              {launchAtLogin ? "Don't Start at Login" : "Start at Login"}
+            </Button>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Render throttling */}
+      {renderThrottling !== null && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Pause When Hidden</h2>
+            <p className="text-sm text-text-muted mb-4">
+              Stop background work while every Smudge window is covered by
+              other windows, to save battery
+            </p>
+            <Button
+              onClick={handleToggleRenderThrottling}
+              variant="outline"
+              size="md"
+            >
+              {renderThrottling
+                ? "Keep Running When Hidden"
+                : "Pause When Hidden"}
+            </Button>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Tray icon */}
+      {trayVisible !== null && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">
+              {isMac ? "Menu Bar Icon" : "Tray Icon"}
+            </h2>
+            <p className="text-sm text-text-muted mb-4">
+              {isMac
+                ? "Keep Smudge in the menu bar for new and recent notes. Hide the Dock icon too to run Smudge from the menu bar alone."
+                : "Keep Smudge in the notification area for new and recent notes"}
+            </p>
+            <div className="flex items-center gap-2">
+              <Button onClick={handleToggleTray} variant="outline" size="md">
+                {trayVisible
+                  ? isMac
+                    ? "Remove from Menu Bar"
+                    : "Remove from Notification Area"
+                  : isMac
+                    ? "Show in Menu Bar"
+                    : "Show in Notification Area"}
+              </Button>
+              {isMac && trayVisible && (
+                <Button onClick={handleToggleDock} variant="outline" size="md">
+                  {dockVisible ? "Hide Dock Icon" : "Show Dock Icon"}
+                </Button>
+              )}
+            </div>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Quick capture */}
+      {quickCaptureShortcut !== undefined && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Quick Capture</h2>
+            <p className="text-sm text-text-muted mb-4">
+              A shortcut that brings Smudge to the front with a new note, even
+              while you're in another app
+            </p>
+            <div className="flex items-center gap-2">
+              <Input
+                type="text"
+                readOnly
+                value={
+                  quickCaptureShortcut
+                    ? globalShortcutsService.formatAccelerator(
+                        quickCaptureShortcut,
+                      )
+                    : ""
+                }
+                onKeyDown={(e) => {
+                  if (e.key === "Tab") return;
+                  e.preventDefault();
+                  const accelerator =
+                    globalShortcutsService.acceleratorFromEvent(e.nativeEvent);
+                  if (accelerator) handleQuickCaptureShortcutChange(accelerator);
+                }}
+                placeholder="Click and press a shortcut"
+                className="w-64"
+              />
+              {quickCaptureShortcut && (
+                <Button
+                  onClick={() => handleQuickCaptureShortcutChange(null)}
+                  variant="outline"
+                  size="md"
+                >
+                  Turn Off
+                </Button>
+              )}
+            </div>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Default apps */}
+      {fileAssociations.length > 0 && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Default Apps</h2>
+            <p className="text-sm text-text-muted mb-4">
+              Open these file types in Smudge when you double-click them
+            </p>
+            <div className="space-y-2">
+              {fileAssociations.map((association) => (
+                <div
+                  key={association.id}
+                  className="flex items-center justify-between gap-4"
+                >
+                  <div className="min-w-0">
+                    <div className="text-sm font-medium">{association.name}</div>
+                    <div className="text-xs text-text-muted truncate">
+                      {association.extensions.map((ext) => `.${ext}`).join(", ")}
+                      {" · "}
+                      {association.owned
+                        ? "Opens in Smudge"
+                        : association.handler
+                          ? `Opens in ${association.handler}`
+                          : "No default app"}
+                    </div>
+                  </div>
+                  <Button
+                    onClick={() => handleToggleFileAssociation(association)}
+                    variant="outline"
+                    size="md"
+                  >
+                    {association.owned ? "Release" : "Make Default"}
+                  </Button>
+                </div>
+              ))}
+            </div>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Downloaded files */}
+      {quarantinePolicy && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Downloaded Files</h2>
+            <p className="text-sm text-text-muted mb-4">
+              Choose whether downloaded files you open or import in Smudge
+              keep the warning your system shows before opening them
+            </p>
+            <Select
+              value={quarantinePolicy}
+              onChange={(e) =>
+                handleQuarantinePolicyChange(
+                  e.target.value as quarantineService.QuarantinePolicy,
+                )
+              }
+              className="w-64"
+            >
+              <option value="preserve">Keep download warnings</option>
+              <option value="clear">Remove download warnings</option>
+            </Select>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Handoff */}
+      {isMac && notesFolder && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Handoff</h2>
+            <p className="text-sm text-text-muted mb-4">
+              Continue editing the note you have open on your other devices,
+              right where you left off
+            </p>
+            <Button onClick={handleToggleHandoff} variant="outline" size="md">
+              {handoffEnabled ? "Disable Handoff" : "Enable Handoff"}
+            </Button>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+      {/* Sandboxed build */}
+      {capabilities?.sandboxed && (
+        <>
+          <section>
+            <h2 className="text-xl font-medium mb-0.5">Sandboxed Build</h2>
+            <p className="text-sm text-text-muted mb-4">
+              This copy of Smudge runs in the App Sandbox, which turns off
+              some features
+            </p>
+            <ul className="text-sm text-text-muted space-y-1 list-disc list-inside">
+              {capabilities.capabilities
+                .filter((capability) => !capability.available)
+                .map((capability) => (
+                  <li key={capability.id}>{capability.reason}</li>
+                ))}
+            </ul>
+          </section>
+
+          {/* Divider */}
+          <div className="border-t border-border border-dashed" />
+        </>
+      )}
+
+            {/* About */}
       <section>
         <h2 className="text-xl font-medium mb-0.5">About Smudge</h2>
         <p className="text-sm text-text-muted mb-3">
@@ -464,25 +1097,27 @@ export function GeneralSettingsSection() {
           </a>
           .
         </p>
-        <Button
-          onClick={handleCheckForUpdates}
-          disabled={checkingUpdate}
-          variant="outline"
-          size="md"
-          className="gap-1.25"
-        >
-          {checkingUpdate ? (
-            <>
-              <SpinnerIcon className="w-4.5 h-4.5 stroke-[1.5] animate-spin" />
-              Checking...
-            </>
-          ) : (
-            <>
-              <RefreshCwIcon className="w-4.5 h-4.5 stroke-[1.5]" />
-              Check for Updates
-            </>
-          )}
-        </Button>
+        {capabilitiesService.isAvailable(capabilities, "updates") && (
+          <Button
+            onClick={handleCheckForUpdates}
+            disabled={checkingUpdate}
+            variant="outline"
+            size="md"
+            className="gap-1.25"
+          >
+            {checkingUpdate ? (
+              <>
+                <SpinnerIcon className="w-4.5 h-4.5 stroke-[1.5] animate-spin" />
+                Checking...
+              </>
+            ) : (
+              <>
+                <RefreshCwIcon className="w-4.5 h-4.5 stroke-[1.5]" />
+                Check for Updates
+              </>
+            )}
+          </Button>
+        )}
       </section>
     </div>
   );
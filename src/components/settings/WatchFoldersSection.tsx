@@ -0,0 +1,164 @@
+import { useState, useEffect, useCallback } from "react";
+import { toast } from "sonner";
+import { useNotes } from "../../context/NotesContext";
+import { Button, Input, Select } from "../ui";
+import { FoldersIcon } from "../icons";
+import * as notesService from "../../services/notes";
+import { dialogLabels, pickFolder } from "../../services/dialogs";
+import type { ImportAction, WatchFolder } from "../../types/note";
+
+const actionOptions: { value: ImportAction; label: string }[] = [
+  { value: "copy", label: "Copy" },
+  { value: "move", label: "Move" },
+  { value: "leave", label: "Keep in sync" },
+];
+
+export function WatchFoldersSection() {
+  const { notesFolder } = useNotes();
+  const [folders, setFolders] = useState<WatchFolder[]>([]);
+
+  useEffect(() => {
+    if (!notesFolder) return;
+    notesService
+      .getSettings()
+      .then((settings) => setFolders(settings.watchFolders ?? []))
+      .catch(() => {});
+  }, [notesFolder]);
+
+  // Persist the whole list; the backend restarts its watchers when it changes
+  const saveFolders = useCallback(async (next: WatchFolder[]) => {
+    try {
+      const settings = await notesService.getSettings();
+      await notesService.updateSettings({ ...settings, watchFolders: next });
+      setFolders(next);
+    } catch (err) {
+      console.error("Failed to update watch folders:", err);
+      toast.error(
+        typeof err === "string" ? err : "Failed to update watch folders",
+      );
+    }
+  }, []);
+
+  const handleAddFolder = async () => {
+    try {
+      const selected = await pickFolder({
+        purpose: "watch-folder",
+        ...(await dialogLabels("add-watch-folder")),
+      });
+      if (!selected || folders.some((f) => f.path === selected)) return;
+      await saveFolders([
+        ...folders,
+        { path: selected, action: "copy", tags: [], extensions: [] },
+      ]);
+    } catch (err) {
+      console.error("Failed to select folder:", err);
+      toast.error("Failed to select folder");
+    }
+  };
+
+  const updateFolder = (path: string, changes: Partial<WatchFolder>) => {
+    saveFolders(
+      folders.map((f) => (f.path === path ? { ...f, ...changes } : f)),
+    );
+  };
+
+  const removeFolder = (path: string) => {
+    saveFolders(folders.filter((f) => f.path !== path));
+  };
+
+  if (!notesFolder) return null;
+
+  return (
+    <section>
+      <h2 className="text-xl font-medium mb-0.5">Watch Folders</h2>
+      <p className="text-sm text-text-muted mb-4">
+        New markdown files in these folders are imported into your notes
+        automatically
+      </p>
+      {folders.length > 0 && (
+        <div className="rounded-[10px] border border-border p-4 space-y-4 mb-2.5">
+          {folders.map((folder) => (
+            <WatchFolderRow
+              key={folder.path}
+              folder={folder}
+              onChange={(changes) => updateFolder(folder.path, changes)}
+              onRemove={() => removeFolder(folder.path)}
+            />
+          ))}
+        </div>
+      )}
+      <Button
+        onClick={handleAddFolder}
+        variant="outline"
+        size="md"
+        className="gap-1.25"
+      >
+        <FoldersIcon className="w-4.5 h-4.5 stroke-[1.5]" />
+        Add Folder
+      </Button>
+    </section>
+  );
+}
+
+function WatchFolderRow({
+  folder,
+  onChange,
+  onRemove,
+}: {
+  folder: WatchFolder;
+  onChange: (changes: Partial<WatchFolder>) => void;
+  onRemove: () => void;
+}) {
+  const [tags, setTags] = useState(folder.tags.join(", "));
+
+  const commitTags = () => {
+    const next = tags
+      .split(",")
+      .map((t) => t.trim().replace(/^#/, ""))
+      .filter(Boolean);
+    if (next.join(",") !== folder.tags.join(",")) {
+      onChange({ tags: next });
+    }
+  };
+
+  return (
+    <div className="space-y-2 not-first:pt-4 not-first:border-t not-first:border-border not-first:border-dashed">
+      <div className="flex items-center justify-between gap-2">
+        <p className="text-sm text-text truncate" title={folder.path}>
+          {folder.path}
+        </p>
+        <Button variant="ghost" size="sm" onClick={onRemove}>
+          Remove
+        </Button>
+      </div>
+      <div className="flex items-center justify-between">
+        <label className="text-sm text-text font-medium">After import</label>
+        <Select
+          value={folder.action}
+          onChange={(e) => onChange({ action: e.target.value as ImportAction })}
+          className="w-40"
+        >
+          {actionOptions.map((opt) => (
+            <option key={opt.value} value={opt.value}>
+              {opt.label}
+            </option>
+          ))}
+        </Select>
+      </div>
+      <div className="flex items-center justify-between gap-4">
+        <label className="text-sm text-text font-medium">Tags</label>
+        <Input
+          type="text"
+          value={tags}
+          onChange={(e) => setTags(e.target.value)}
+          onBlur={commitTags}
+          onKeyDown={(e) => {
+            if (e.key === "Enter") commitTags();
+          }}
+          placeholder="inbox, reading"
+          className="w-40"
+        />
+      </div>
+    </div>
+  );
+}
@@ -135,6 +135,8 @@ interface ListItemProps {
   isSelected?: boolean;
   isPinned?: boolean;
   onClick?: (e: React.MouseEvent<HTMLDivElement>) => void;
+  /** Makes the item draggable */
+  onDragStart?: (e: React.DragEvent<HTMLDivElement>) => void;
   /** Optional status icon to display next to meta */
 }
 
@@ -145,6 +147,7 @@ export function ListItem({
   isSelected = false,
   isPinned = false,
   onClick,
+  onDragStart,
   onContextMenu,
 }: ListItemProps & { onContextMenu?: (e: React.MouseEvent) => void }) {
   // Clean subtitle: treat whitespace-only or &nbsp; as empty
@@ -158,8 +161,12 @@ export function ListItem({
     <div
       onClick={onClick}
       onContextMenu={onContextMenu}
+      draggable={onDragStart ? true : undefined}
+      onDragStart={onDragStart}
       role="button"
       tabIndex={-1}
+      aria-current={isSelected || undefined}
+      aria-haspopup={onContextMenu ? "menu" : undefined}
       className={cn(
         "w-full text-left px-2.5 py-2.25 transition-colors cursor-pointer select-none rounded-md",
         "focus:outline-none focus-visible:outline-none",
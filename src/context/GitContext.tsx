@@ -11,6 +11,7 @@ import {
 import { listen } from "@tauri-apps/api/event";
 import * as gitService from "../services/git";
 import type { GitStatus } from "../services/git";
+import { onPowerChange } from "../services/power";
 import { useNotesData } from "./NotesContext";
 
 interface GitContextValue {
@@ -206,6 +207,24 @@ export function GitProvider({ children }: { children: ReactNode }) {
     };
   }, []);
 
+  // The remote may have moved on while the machine slept or sat locked, and
+  // connections made before then are gone, so check again once it's back
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    onPowerChange((change) => {
+      if (change === "didWake" || change === "screenUnlocked") {
+        refreshStatusRef.current();
+      }
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
   const value = useMemo<GitContextValue>(
     () => ({
       status,
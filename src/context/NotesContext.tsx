@@ -10,8 +10,16 @@ import {
 } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { toast } from "sonner";
 import type { Note, NoteMetadata } from "../types/note";
 import * as notesService from "../services/notes";
+import * as trashService from "../services/trash";
+import * as templatesService from "../services/templates";
+import * as quarantineService from "../services/quarantine";
+import * as directoryWatchService from "../services/directoryWatch";
+import * as projectsService from "../services/projects";
+import type { TrashHandle } from "../services/trash";
 import type { SearchResult } from "../services/notes";
 
 // External file opened via OS file association
@@ -43,8 +51,10 @@ interface NotesDataContextValue {
 interface NotesActionsContextValue {
   selectNote: (id: string) => Promise<void>;
   createNote: () => Promise<void>;
+  createNoteFromTemplate: (templateId: string) => Promise<void>;
   saveNote: (content: string, noteId?: string) => Promise<void>;
-  deleteNote: (id: string) => Promise<void>;
+  deleteNote: (id: string) => Promise<TrashHandle | null>;
+  restoreNote: (handle: TrashHandle) => Promise<void>;
   duplicateNote: (id: string) => Promise<void>;
   refreshNotes: () => Promise<void>;
   reloadCurrentNote: () => Promise<void>;
@@ -61,6 +71,31 @@ interface NotesActionsContextValue {
 const NotesDataContext = createContext<NotesDataContextValue | null>(null);
 const NotesActionsContext = createContext<NotesActionsContextValue | null>(null);
 
+// A downloaded file opened in place keeps triggering OS warnings in other
+// apps; offer to clear them, but only with the user's say-so
+async function offerToStripQuarantine(path: string, name: string) {
+  const info = await quarantineService.getQuarantineInfo(path).catch(() => null);
+  if (!info?.quarantined) return;
+  let source = "the internet";
+  try {
+    if (info.originUrl) source = new URL(info.originUrl).host || source;
+  } catch {
+    // Keep the generic description for unparseable URLs
+  }
+  toast(`"${name}" was downloaded from ${source}`, {
+    description: "Your system may warn before opening it in other apps.",
+    duration: 10000,
+    action: {
+      label: "Remove Warning",
+      onClick: () => {
+        quarantineService
+          .stripQuarantine(path)
+          .catch(() => toast.error("Failed to remove the download warning"));
+      },
+    },
+  });
+}
+
 export function NotesProvider({ children }: { children: ReactNode }) {
   const [notes, setNotes] = useState<NoteMetadata[]>([]);
   const [selectedNoteId, setSelectedNoteId] = useState<string | null>(null);
@@ -85,8 +120,12 @@ export function NotesProvider({ children }: { children: ReactNode }) {
   const selectedNoteIdRef = useRef<string | null>(null);
   selectedNoteIdRef.current = selectedNoteId;
 
+  // Whether the backend serves this window's project. While another
+  // project's window is focused, this one keeps the notes it has.
+  const servedRef = useRef(true);
+
   const refreshNotes = useCallback(async () => {
-    if (!notesFolder) return;
+    if (!notesFolder || !servedRef.current) return;
     try {
       const notesList = await notesService.listNotes();
       setNotes(notesList);
@@ -148,6 +187,23 @@ export function NotesProvider({ children }: { children: ReactNode }) {
     }
   }, [refreshNotes]);
 
+  const createNoteFromTemplate = useCallback(
+    async (templateId: string) => {
+      try {
+        const note = await templatesService.createNoteFromTemplate(templateId);
+        await refreshNotes();
+        setCurrentNote(note);
+        setSelectedNoteId(note.id);
+        setExternalFile(null);
+        setSearchQuery("");
+        setSearchResults([]);
+      } catch (err) {
+        setError(err instanceof Error ? err.message : "Failed to create note");
+      }
+    },
+    [refreshNotes]
+  );
+
   const saveNote = useCallback(
     async (content: string, noteId?: string) => {
       // Use provided noteId (for flush saves) or fall back to currentNote.id
@@ -217,7 +273,7 @@ export function NotesProvider({ children }: { children: ReactNode }) {
   const deleteNote = useCallback(
     async (id: string) => {
       try {
-        await notesService.deleteNote(id);
+        const handle = await notesService.deleteNote(id);
 
         // Clean up pinned status for deleted note
         const currentSettings = await notesService.getSettings();
@@ -239,13 +295,24 @@ export function NotesProvider({ children }: { children: ReactNode }) {
           return prevId;
         });
         await refreshNotes();
+        return handle;
       } catch (err) {
         setError(err instanceof Error ? err.message : "Failed to delete note");
+        return null;
       }
     },
     [refreshNotes]
   );
 
+  // Undo a delete by moving the note back out of the trash
+  const restoreNote = useCallback(
+    async (handle: TrashHandle) => {
+      await trashService.restoreFromTrash(handle);
+      await refreshNotes();
+    },
+    [refreshNotes]
+  );
+
   const duplicateNote = useCallback(
     async (id: string) => {
       try {
@@ -361,6 +428,7 @@ export function NotesProvider({ children }: { children: ReactNode }) {
       // Fallback: no notes folder configured or import failed — open in place
       const content = await invoke<string>("read_external_file", { path: filePath });
       const name = filePath.split("/").pop()?.replace(/\.md$/, "") || "Untitled";
+      void offerToStripQuarantine(filePath, name);
       setExternalFile({
         path: filePath,
         content,
@@ -398,6 +466,12 @@ export function NotesProvider({ children }: { children: ReactNode }) {
         const folder = await notesService.getNotesFolder();
         setNotesFolderState(folder);
         if (folder) {
+          // Show the launch snapshot's recent notes while the full list loads
+          const recent = notesService.takeRecentNotes();
+          if (recent) {
+            setNotes(recent);
+            setIsLoading(false);
+          }
           const notesList = await notesService.listNotes();
           setNotes(notesList);
           // Start file watcher
@@ -462,6 +536,110 @@ export function NotesProvider({ children }: { children: ReactNode }) {
     }
   }, [notesFolder, refreshNotes]);
 
+  // Follow this window's project. Focusing a window makes its project the
+  // active one; coming back to this one, its notes may have changed since.
+  const notesFolderRef = useRef<string | null>(null);
+  notesFolderRef.current = notesFolder;
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+    projectsService
+      .onProjectsChange(async (status) => {
+        const label = getCurrentWindow().label;
+        const own = status.open.find((project) =>
+          project.windows.includes(label),
+        );
+        const served = !own || own.active;
+        const wasServed = servedRef.current;
+        servedRef.current = served;
+        if (!served) return;
+        const active = status.open.find((project) => project.active);
+        const folder = active?.path ?? null;
+        if (folder === notesFolderRef.current) {
+          if (folder && !wasServed) {
+            try {
+              await notesService.startFileWatcher();
+              setNotes(await notesService.listNotes());
+            } catch (err) {
+              console.error("Failed to reload the project:", err);
+            }
+          }
+          return;
+        }
+        setNotesFolderState(folder);
+        setSelectedNoteId(null);
+        setCurrentNote(null);
+        if (!folder) {
+          setNotes([]);
+          return;
+        }
+        try {
+          await notesService.startFileWatcher();
+        } catch (err) {
+          console.error("Failed to watch the project:", err);
+        }
+      })
+      .then((fn) => {
+        if (cancelled) fn();
+        else unlisten = fn;
+      });
+    return () => {
+      cancelled = true;
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  // Reload an external file when another app edits it. Our own saves leave
+  // it matching originalContent, so they don't reload.
+  const externalOriginalRef = useRef<string | null>(null);
+  externalOriginalRef.current = externalFile?.originalContent ?? null;
+  const externalPath = externalFile?.path;
+  useEffect(() => {
+    if (!externalPath) return;
+    const separator = Math.max(
+      externalPath.lastIndexOf("/"),
+      externalPath.lastIndexOf("\\"),
+    );
+    if (separator < 0) return;
+    const folder = externalPath.slice(0, separator) || "/";
+    const name = externalPath.slice(separator + 1);
+    let stop: (() => void) | null = null;
+    let cancelled = false;
+
+    directoryWatchService
+      .watchDirectory(folder, async (changes) => {
+        if (!directoryWatchService.changesFileNamed(changes, name)) return;
+        try {
+          const content = await invoke<string>("read_external_file", {
+            path: externalPath,
+          });
+          if (cancelled || content === externalOriginalRef.current) return;
+          setExternalFile((prev) =>
+            prev?.path === externalPath
+              ? { ...prev, content, originalContent: content }
+              : prev
+          );
+        } catch {
+          // Removed or moved away: keep what's in the editor
+        }
+      })
+      .then((unwatch) => {
+        if (cancelled) {
+          unwatch();
+        } else {
+          stop = unwatch;
+        }
+      })
+      .catch((err) => {
+        console.error("Failed to watch external file:", err);
+      });
+
+    return () => {
+      cancelled = true;
+      stop?.();
+    };
+  }, [externalPath]);
+
   // Memoize data context value to prevent unnecessary re-renders
   const dataValue = useMemo<NotesDataContextValue>(
     () => ({
@@ -499,8 +677,10 @@ export function NotesProvider({ children }: { children: ReactNode }) {
     () => ({
       selectNote,
       createNote,
+      createNoteFromTemplate,
       saveNote,
       deleteNote,
+      restoreNote,
       duplicateNote,
       refreshNotes,
       reloadCurrentNote,
@@ -516,8 +696,10 @@ export function NotesProvider({ children }: { children: ReactNode }) {
     [
       selectNote,
       createNote,
+      createNoteFromTemplate,
       saveNote,
       deleteNote,
+      restoreNote,
       duplicateNote,
       refreshNotes,
       reloadCurrentNote,
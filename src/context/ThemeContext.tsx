@@ -7,6 +7,17 @@ import {
   type ReactNode,
 } from "react";
 import { getSettings, updateSettings } from "../services/notes";
+import {
+  getHighContrast,
+  getReduceMotion,
+  getReduceTransparency,
+  getTextScale,
+  onHighContrastChange,
+  onReduceMotionChange,
+  onReduceTransparencyChange,
+  onTextScaleChange,
+} from "../services/accessibility";
+import { onAppearanceChange } from "../services/appearance";
 import type {
   ThemeSettings,
   EditorFontSettings,
@@ -35,6 +46,10 @@ const defaultEditorFontSettings: Required<EditorFontSettings> = {
 interface ThemeContextType {
   theme: ThemeMode;
   resolvedTheme: "light" | "dark";
+  highContrast: boolean;
+  reduceMotion: boolean;
+  reduceTransparency: boolean;
+  textScale: number;
   setTheme: (theme: ThemeMode) => void;
   cycleTheme: () => void;
   editorFontSettings: Required<EditorFontSettings>;
@@ -60,11 +75,15 @@ interface ThemeProviderProps {
   children: ReactNode;
 }
 
-// Apply editor font CSS variables (with computed values)
-function applyFontCSSVariables(fonts: Required<EditorFontSettings>) {
+// Apply editor font CSS variables (with computed values), scaled by the OS
+// text size
+function applyFontCSSVariables(
+  fonts: Required<EditorFontSettings>,
+  textScale: number
+) {
   const root = document.documentElement;
   const fontFamily = fontFamilyMap[fonts.baseFontFamily];
-  const baseSize = fonts.baseFontSize;
+  const baseSize = fonts.baseFontSize * textScale;
   const boldWeight = fonts.boldWeight;
   const lineHeight = fonts.lineHeight;
 
@@ -146,6 +165,102 @@ export function ThemeProvider({ children }: ThemeProviderProps) {
     return () => mediaQuery.removeEventListener("change", handler);
   }, []);
 
+  // The native appearance changes too, sometimes before the webview's media
+  // query does. While a theme is forced it reports that theme, not the OS's.
+  useEffect(() => {
+    if (theme !== "system") return;
+    let unlisten: (() => void) | undefined;
+
+    onAppearanceChange(setSystemTheme).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [theme]);
+
+  // Follow the OS high-contrast setting
+  const [highContrast, setHighContrast] = useState(false);
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    getHighContrast().then(setHighContrast).catch(console.error);
+    onHighContrastChange(setHighContrast).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  useEffect(() => {
+    document.documentElement.classList.toggle("high-contrast", highContrast);
+  }, [highContrast]);
+
+  // Follow the OS reduce-motion setting
+  const [reduceMotion, setReduceMotion] = useState(false);
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    getReduceMotion().then(setReduceMotion).catch(console.error);
+    onReduceMotionChange(setReduceMotion).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  useEffect(() => {
+    document.documentElement.classList.toggle("reduce-motion", reduceMotion);
+  }, [reduceMotion]);
+
+  // Follow the OS reduce-transparency setting
+  const [reduceTransparency, setReduceTransparency] = useState(false);
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    getReduceTransparency().then(setReduceTransparency).catch(console.error);
+    onReduceTransparencyChange(setReduceTransparency).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  useEffect(() => {
+    document.documentElement.classList.toggle(
+      "reduce-transparency",
+      reduceTransparency,
+    );
+  }, [reduceTransparency]);
+
+  // Follow the OS text size. The UI is sized in rem, so scaling the root
+  // font size scales it along with the editor.
+  const [textScale, setTextScale] = useState(1);
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+
+    getTextScale().then(setTextScale).catch(console.error);
+    onTextScaleChange(setTextScale).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  useEffect(() => {
+    document.documentElement.style.fontSize =
+      textScale === 1 ? "" : `${16 * textScale}px`;
+  }, [textScale]);
+
   // Resolve the actual theme to use
   const resolvedTheme = theme === "system" ? systemTheme : theme;
 
@@ -190,10 +305,10 @@ export function ThemeProvider({ children }: ThemeProviderProps) {
     setTheme(order[nextIndex]);
   }, [theme, setTheme]);
 
-  // Apply font CSS variables whenever font settings change
+  // Apply font CSS variables whenever font settings or the OS text size change
   useEffect(() => {
-    applyFontCSSVariables(editorFontSettings);
-  }, [editorFontSettings]);
+    applyFontCSSVariables(editorFontSettings, textScale);
+  }, [editorFontSettings, textScale]);
 
   // Save font settings to backend
   const saveFontSettings = useCallback(
@@ -242,6 +357,10 @@ export function ThemeProvider({ children }: ThemeProviderProps) {
       value={{
         theme,
         resolvedTheme,
+        highContrast,
+        reduceMotion,
+        reduceTransparency,
+        textScale,
         setTheme,
         cycleTheme,
         editorFontSettings,
@@ -0,0 +1,42 @@
+import { invoke, type InvokeArgs } from "@tauri-apps/api/core";
+import { decode, encode } from "./msgpack";
+
+// Large payloads cross IPC as MessagePack: JSON stringifying and parsing
+// whole notes dominated save and load times. The backend says which commands
+// take it and from what size (ipc_binary_codec); everything else stays JSON.
+
+interface BinaryCodec {
+  threshold: number;
+  commands: string[];
+}
+
+let codec: Promise<BinaryCodec> | null = null;
+
+function negotiate(): Promise<BinaryCodec> {
+  codec ??= invoke<BinaryCodec>("ipc_binary_codec").catch(() => ({
+    threshold: Infinity,
+    commands: [],
+  }));
+  return codec;
+}
+
+// Invoke a command whose arguments or reply can be large. `size` is the rough
+// size of the arguments, e.g. the length of the note content being sent.
+export async function invokeLarge<T>(
+  cmd: string,
+  args: Record<string, unknown>,
+  size = 0,
+): Promise<T> {
+  const { threshold, commands } = await negotiate();
+  if (!commands.includes(cmd)) {
+    return invoke<T>(cmd, args as InvokeArgs);
+  }
+  const headers: Record<string, string> = { "X-Smudge-Accept": "msgpack" };
+  let body: InvokeArgs = args as InvokeArgs;
+  if (size >= threshold) {
+    body = encode(args);
+    headers["X-Smudge-Codec"] = "msgpack";
+  }
+  const reply = await invoke<T | ArrayBuffer>(cmd, body, { headers });
+  return reply instanceof ArrayBuffer ? (decode(new Uint8Array(reply)) as T) : reply;
+}
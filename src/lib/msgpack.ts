@@ -0,0 +1,246 @@
+// Minimal MessagePack codec for large IPC payloads (see lib/ipc.ts). Covers
+// what JSON can carry: null, booleans, numbers, strings, arrays and objects.
+
+const textEncoder = new TextEncoder();
+const textDecoder = new TextDecoder();
+
+class Writer {
+  private bytes = new Uint8Array(1024);
+  private view = new DataView(this.bytes.buffer);
+  private length = 0;
+
+  private reserve(size: number) {
+    if (this.length + size <= this.bytes.length) return;
+    let capacity = this.bytes.length * 2;
+    while (capacity < this.length + size) capacity *= 2;
+    const bytes = new Uint8Array(capacity);
+    bytes.set(this.bytes.subarray(0, this.length));
+    this.bytes = bytes;
+    this.view = new DataView(bytes.buffer);
+  }
+
+  u8(value: number) {
+    this.reserve(1);
+    this.view.setUint8(this.length, value);
+    this.length += 1;
+  }
+
+  u16(value: number) {
+    this.reserve(2);
+    this.view.setUint16(this.length, value);
+    this.length += 2;
+  }
+
+  u32(value: number) {
+    this.reserve(4);
+    this.view.setUint32(this.length, value);
+    this.length += 4;
+  }
+
+  f64(value: number) {
+    this.reserve(8);
+    this.view.setFloat64(this.length, value);
+    this.length += 8;
+  }
+
+  raw(bytes: Uint8Array) {
+    this.reserve(bytes.length);
+    this.bytes.set(bytes, this.length);
+    this.length += bytes.length;
+  }
+
+  finish(): Uint8Array {
+    return this.bytes.subarray(0, this.length);
+  }
+}
+
+function writeLength(writer: Writer, length: number, fixTag: number, fixMax: number, tags: number[]) {
+  if (length <= fixMax) {
+    writer.u8(fixTag | length);
+  } else if (length <= 0xff && tags[0]) {
+    writer.u8(tags[0]);
+    writer.u8(length);
+  } else if (length <= 0xffff) {
+    writer.u8(tags[1]);
+    writer.u16(length);
+  } else {
+    writer.u8(tags[2]);
+    writer.u32(length);
+  }
+}
+
+function writeNumber(writer: Writer, value: number) {
+  if (!Number.isInteger(value) || Math.abs(value) > 0xffffffff) {
+    writer.u8(0xcb);
+    writer.f64(value);
+  } else if (value >= 0) {
+    if (value <= 0x7f) {
+      writer.u8(value);
+    } else if (value <= 0xff) {
+      writer.u8(0xcc);
+      writer.u8(value);
+    } else if (value <= 0xffff) {
+      writer.u8(0xcd);
+      writer.u16(value);
+    } else {
+      writer.u8(0xce);
+      writer.u32(value);
+    }
+  } else if (value >= -32) {
+    writer.u8(value & 0xff);
+  } else if (value >= -0x80000000) {
+    writer.u8(0xd2);
+    writer.u32(value >>> 0);
+  } else {
+    writer.u8(0xcb);
+    writer.f64(value);
+  }
+}
+
+function writeString(writer: Writer, value: string) {
+  const bytes = textEncoder.encode(value);
+  writeLength(writer, bytes.length, 0xa0, 31, [0xd9, 0xda, 0xdb]);
+  writer.raw(bytes);
+}
+
+function writeValue(writer: Writer, value: unknown) {
+  if (value === null || value === undefined) {
+    writer.u8(0xc0);
+  } else if (typeof value === "boolean") {
+    writer.u8(value ? 0xc3 : 0xc2);
+  } else if (typeof value === "number") {
+    writeNumber(writer, value);
+  } else if (typeof value === "string") {
+    writeString(writer, value);
+  } else if (Array.isArray(value)) {
+    writeLength(writer, value.length, 0x90, 15, [0, 0xdc, 0xdd]);
+    for (const item of value) writeValue(writer, item);
+  } else if (typeof value === "object") {
+    // Skip undefined properties, as JSON.stringify does
+    const entries = Object.entries(value).filter(([, item]) => item !== undefined);
+    writeLength(writer, entries.length, 0x80, 15, [0, 0xde, 0xdf]);
+    for (const [key, item] of entries) {
+      writeString(writer, key);
+      writeValue(writer, item);
+    }
+  } else {
+    throw new TypeError(`Can't encode ${typeof value} as MessagePack`);
+  }
+}
+
+export function encode(value: unknown): Uint8Array {
+  const writer = new Writer();
+  writeValue(writer, value);
+  return writer.finish();
+}
+
+class Reader {
+  private view: DataView;
+  private offset = 0;
+
+  constructor(private bytes: Uint8Array) {
+    this.view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  }
+
+  get done() {
+    return this.offset === this.bytes.length;
+  }
+
+  private advance(size: number): number {
+    if (this.offset + size > this.bytes.length) {
+      throw new RangeError("Truncated MessagePack payload");
+    }
+    const offset = this.offset;
+    this.offset += size;
+    return offset;
+  }
+
+  private length(width: number): number {
+    if (width === 1) return this.view.getUint8(this.advance(1));
+    if (width === 2) return this.view.getUint16(this.advance(2));
+    return this.view.getUint32(this.advance(4));
+  }
+
+  private string(length: number): string {
+    const start = this.advance(length);
+    return textDecoder.decode(this.bytes.subarray(start, start + length));
+  }
+
+  private array(length: number): unknown[] {
+    const items = new Array(length);
+    for (let i = 0; i < length; i++) items[i] = this.value();
+    return items;
+  }
+
+  private map(length: number): Record<string, unknown> {
+    const map: Record<string, unknown> = {};
+    for (let i = 0; i < length; i++) {
+      const key = String(this.value());
+      map[key] = this.value();
+    }
+    return map;
+  }
+
+  value(): unknown {
+    const tag = this.view.getUint8(this.advance(1));
+    if (tag <= 0x7f) return tag;
+    if (tag <= 0x8f) return this.map(tag & 0x0f);
+    if (tag <= 0x9f) return this.array(tag & 0x0f);
+    if (tag <= 0xbf) return this.string(tag & 0x1f);
+    if (tag >= 0xe0) return tag - 0x100;
+    switch (tag) {
+      case 0xc0:
+        return null;
+      case 0xc2:
+        return false;
+      case 0xc3:
+        return true;
+      case 0xc4:
+      case 0xc5:
+      case 0xc6: {
+        const length = this.length(1 << (tag - 0xc4));
+        const start = this.advance(length);
+        return this.bytes.slice(start, start + length);
+      }
+      case 0xca:
+        return this.view.getFloat32(this.advance(4));
+      case 0xcb:
+        return this.view.getFloat64(this.advance(8));
+      case 0xcc:
+        return this.view.getUint8(this.advance(1));
+      case 0xcd:
+        return this.view.getUint16(this.advance(2));
+      case 0xce:
+        return this.view.getUint32(this.advance(4));
+      case 0xcf:
+        return Number(this.view.getBigUint64(this.advance(8)));
+      case 0xd0:
+        return this.view.getInt8(this.advance(1));
+      case 0xd1:
+        return this.view.getInt16(this.advance(2));
+      case 0xd2:
+        return this.view.getInt32(this.advance(4));
+      case 0xd3:
+        return Number(this.view.getBigInt64(this.advance(8)));
+      case 0xd9:
+      case 0xda:
+      case 0xdb:
+        return this.string(this.length(1 << (tag - 0xd9)));
+      case 0xdc:
+      case 0xdd:
+        return this.array(this.length(2 << (tag - 0xdc)));
+      case 0xde:
+      case 0xdf:
+        return this.map(this.length(2 << (tag - 0xde)));
+      default:
+        throw new TypeError(`Unsupported MessagePack type 0x${tag.toString(16)}`);
+    }
+  }
+}
+
+export function decode(bytes: Uint8Array): unknown {
+  const reader = new Reader(bytes);
+  const value = reader.value();
+  if (!reader.done) throw new RangeError("Trailing bytes after MessagePack payload");
+  return value;
+}
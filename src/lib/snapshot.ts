@@ -0,0 +1,65 @@
+import type { NoteMetadata, Settings } from "../types/note";
+import type { TitlebarInfo } from "../services/titlebar";
+import type { CapabilityReport } from "../services/capabilities";
+
+// State the backend injects into the main window before its scripts run
+// (snapshot.rs), so the first render doesn't wait on a round of commands.
+// Each value is handed out once: later calls go to the backend, which by then
+// may know better.
+
+export interface StartupSnapshot {
+  launchId: string;
+  notesFolder: string | null;
+  settings: Settings;
+  recentNotes: NoteMetadata[];
+  titlebar: TitlebarInfo;
+  capabilities: CapabilityReport;
+  highContrast: boolean;
+  reduceMotion: boolean;
+  reduceTransparency: boolean;
+  textScale: number;
+}
+
+declare global {
+  interface Window {
+    __SMUDGE_SNAPSHOT__?: StartupSnapshot | null;
+  }
+}
+
+const SEEN_KEY = "smudge-snapshot";
+
+type Remaining = Partial<Omit<StartupSnapshot, "launchId">>;
+
+// A reload runs the same injected script, but the state has moved on since
+// launch, so a snapshot this session has already seen is ignored
+function load(): Remaining {
+  const snapshot = window.__SMUDGE_SNAPSHOT__;
+  delete window.__SMUDGE_SNAPSHOT__;
+  if (!snapshot) return {};
+  try {
+    if (sessionStorage.getItem(SEEN_KEY) === snapshot.launchId) return {};
+    sessionStorage.setItem(SEEN_KEY, snapshot.launchId);
+  } catch {
+    // Without session storage, trust it; reloads are rare outside dev
+  }
+  const { launchId: _, ...remaining } = snapshot;
+  return remaining;
+}
+
+let remaining: Remaining = load();
+
+// The snapshot's value for `key`, or undefined if there is none or it was
+// already taken
+export function takeSnapshot<K extends keyof Remaining>(
+  key: K,
+): Remaining[K] | undefined {
+  const value = remaining[key];
+  delete remaining[key];
+  return value;
+}
+
+// Drop whatever hasn't been taken, e.g. once settings or the notes folder
+// change and the snapshot no longer describes them
+export function expireSnapshot() {
+  remaining = {};
+}
@@ -1,10 +1,34 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import MiniApp from "./MiniApp";
+import WarmWindow from "./WarmWindow";
+import { getMiniNoteId, isWarmWindow } from "./services/miniWindow";
+import { recordFirstPaint } from "./services/startup";
 import "./App.css";
 
+const miniNoteId = getMiniNoteId();
+const warm = isWarmWindow();
+
+function Root() {
+  if (miniNoteId !== null) return <MiniApp noteId={miniNoteId} />;
+  if (warm) return <WarmWindow />;
+  return <App />;
+}
+
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    <Root />
   </React.StrictMode>,
 );
+
+// The second frame callback runs once the first frame has been presented
+if (miniNoteId === null && !warm) {
+  requestAnimationFrame(() =>
+    requestAnimationFrame(() => {
+      recordFirstPaint().catch((err) =>
+        console.error("Failed to record first paint:", err),
+      );
+    }),
+  );
+}
@@ -0,0 +1,110 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { takeSnapshot } from "../lib/snapshot";
+
+// Whether the OS asks for high contrast (Increase contrast on macOS, a High
+// Contrast theme on Windows, the GNOME high-contrast setting on Linux)
+export async function getHighContrast(): Promise<boolean> {
+  return takeSnapshot("highContrast") ?? invoke<boolean>("get_high_contrast");
+}
+
+export function onHighContrastChange(
+  handler: (highContrast: boolean) => void,
+): Promise<UnlistenFn> {
+  return listen<boolean>("high-contrast-changed", (event) =>
+    handler(event.payload),
+  );
+}
+
+export async function getReduceMotion(): Promise<boolean> {
+  return takeSnapshot("reduceMotion") ?? invoke<boolean>("get_reduce_motion");
+}
+
+export function onReduceMotionChange(
+  handler: (reduceMotion: boolean) => void,
+): Promise<UnlistenFn> {
+  return listen<boolean>("reduce-motion-changed", (event) =>
+    handler(event.payload),
+  );
+}
+
+// Whether the OS asks for solid backgrounds instead of blurred, translucent
+// ones (Reduce transparency on macOS, Transparency effects off on Windows)
+export async function getReduceTransparency(): Promise<boolean> {
+  return (
+    takeSnapshot("reduceTransparency") ??
+    invoke<boolean>("get_reduce_transparency")
+  );
+}
+
+export function onReduceTransparencyChange(
+  handler: (reduceTransparency: boolean) => void,
+): Promise<UnlistenFn> {
+  return listen<boolean>("reduce-transparency-changed", (event) =>
+    handler(event.payload),
+  );
+}
+
+// For scrollIntoView calls, which ignore the CSS override
+export function scrollBehavior(): ScrollBehavior {
+  return document.documentElement.classList.contains("reduce-motion")
+    ? "auto"
+    : "smooth";
+}
+
+// OS text size multiplier, 1 when unchanged
+export async function getTextScale(): Promise<number> {
+  return takeSnapshot("textScale") ?? invoke<number>("get_text_scale");
+}
+
+export function onTextScaleChange(
+  handler: (textScale: number) => void,
+): Promise<UnlistenFn> {
+  return listen<number>("text-scale-changed", (event) =>
+    handler(event.payload),
+  );
+}
+
+// Has the screen reader speak `message` (VoiceOver on macOS, Narrator on
+// Windows). With `interrupt` it cuts off what's being spoken, like an
+// assertive live region.
+export async function announce(
+  message: string,
+  interrupt = false,
+): Promise<void> {
+  return invoke("announce", { message, interrupt });
+}
+
+interface Announcement {
+  message: string;
+  interrupt: boolean;
+}
+
+// Where there's no native announcement API (Linux), the backend sends
+// announcements to the focused window instead; this speaks them through
+// visually hidden live regions
+export async function listenForAnnouncements(): Promise<UnlistenFn> {
+  return listen<Announcement>("accessibility-announcement", (event) => {
+    const { message, interrupt } = event.payload;
+    const region = liveRegion(interrupt ? "assertive" : "polite");
+    // Screen readers only speak changes, so the same message twice in a row
+    // has to be cleared first
+    region.textContent = "";
+    requestAnimationFrame(() => {
+      region.textContent = message;
+    });
+  });
+}
+
+function liveRegion(politeness: "polite" | "assertive"): HTMLElement {
+  const id = `announcements-${politeness}`;
+  const existing = document.getElementById(id);
+  if (existing) return existing;
+  const region = document.createElement("div");
+  region.id = id;
+  region.setAttribute("aria-live", politeness);
+  region.setAttribute("aria-atomic", "true");
+  region.className = "sr-only";
+  document.body.appendChild(region);
+  return region;
+}
@@ -0,0 +1,21 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export interface ActivationState {
+  active: boolean;
+  hidden: boolean;
+}
+
+// The app was activated, deactivated, hidden (Cmd+H) or shown again (macOS).
+// Sent to every window.
+export function onActivationChange(
+  handler: (state: ActivationState) => void,
+): Promise<UnlistenFn> {
+  return listen<ActivationState>("app-activation", (event) =>
+    handler(event.payload),
+  );
+}
+
+// Whether work the user isn't waiting on should hold off
+export function isBackgrounded(state: ActivationState): boolean {
+  return !state.active || state.hidden;
+}
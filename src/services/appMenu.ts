@@ -0,0 +1,79 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// Items the OS implements; the ones only macOS has are left out elsewhere
+export type PredefinedMenuItem =
+  | "about"
+  | "services"
+  | "hide"
+  | "hideOthers"
+  | "showAll"
+  | "quit"
+  | "undo"
+  | "redo"
+  | "cut"
+  | "copy"
+  | "paste"
+  | "selectAll"
+  | "minimize"
+  | "zoom"
+  | "fullscreen"
+  | "closeWindow";
+
+// An entry of the menu bar (macOS) or the main window's menu. The top level
+// holds submenus.
+export type AppMenuItem =
+  | {
+      kind: "item";
+      id: string;
+      title: string;
+      enabled?: boolean;
+      // Only items created with a checkmark can be checked later
+      checked?: boolean;
+      // Key equivalent, e.g. "CmdOrCtrl+N"
+      accelerator?: string;
+      accessibilityTitle?: string;
+      help?: string;
+    }
+  | {
+      kind: "submenu";
+      // Needed to enable or re-title the submenu later
+      id?: string;
+      title: string;
+      enabled?: boolean;
+      // Lets macOS add window switching or a search field
+      role?: "window" | "help";
+      items: AppMenuItem[];
+    }
+  | { kind: "predefined"; item: PredefinedMenuItem; title?: string }
+  | { kind: "separator" };
+
+export interface AppMenuItemUpdate {
+  id: string;
+  enabled?: boolean;
+  checked?: boolean;
+  title?: string;
+}
+
+// Replaces smudge's default menu
+export async function setAppMenu(items: AppMenuItem[]): Promise<void> {
+  return invoke("set_app_menu", { items });
+}
+
+// Fields left out stay as they are
+export async function updateAppMenu(
+  updates: AppMenuItemUpdate[],
+): Promise<void> {
+  return invoke("update_app_menu", { updates });
+}
+
+// The id of the chosen item, sent to the focused window. The default menu's
+// ids are "newNote", "reloadNote", "settings", "toggleSidebar",
+// "commandPalette", "newProject" and "openProject", and "newFromTemplate:"
+// followed by a template's id. The backend carries out the recent project
+// items and "closeProject" itself.
+export function onAppMenuAction(
+  handler: (id: string) => void,
+): Promise<UnlistenFn> {
+  return listen<string>("app-menu", (event) => handler(event.payload));
+}
@@ -0,0 +1,27 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type Appearance = "light" | "dark";
+
+// Force every window's native chrome light or dark; null follows the OS
+export async function setAppearance(mode: Appearance | null): Promise<void> {
+  return invoke("set_appearance", { mode });
+}
+
+// Force one window light or dark; null follows the app again
+export async function setWindowAppearance(
+  label: string,
+  mode: Appearance | null,
+): Promise<void> {
+  return invoke("set_window_appearance", { label, mode });
+}
+
+// The app's effective appearance changed, because the user toggled dark mode
+// or one was forced. Sent to every window.
+export function onAppearanceChange(
+  handler: (appearance: Appearance) => void,
+): Promise<UnlistenFn> {
+  return listen<Appearance>("appearance-changed", (event) =>
+    handler(event.payload),
+  );
+}
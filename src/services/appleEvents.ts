@@ -0,0 +1,16 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// What an AppleScript `new note` or `open note "<note id>"` asks the main
+// window for (macOS). `export note` and `window count` are answered by the
+// backend.
+export type AppleEventAction =
+  | { action: "newNote" }
+  | { action: "openNote"; noteId: string };
+
+export function onAppleEventAction(
+  handler: (action: AppleEventAction) => void,
+): Promise<UnlistenFn> {
+  return listen<AppleEventAction>("apple-event", (event) =>
+    handler(event.payload),
+  );
+}
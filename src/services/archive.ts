@@ -0,0 +1,45 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Zip and tar.gz archives, for exporting and importing projects of several
+// files. Both run as jobs: progress in bytes arrives through
+// jobs.onJobProgress, and cancelJob stops them.
+
+export type ArchiveFormat = "zip" | "tarGz";
+
+export interface ArchiveSummary {
+  files: number;
+  bytes: number;
+}
+
+// Packs files and folders, each folder under its own name. The format comes
+// from the destination's extension unless given. A password, zip only,
+// encrypts each file with AES-256.
+export async function packArchive(
+  sources: string[],
+  destination: string,
+  options: { format?: ArchiveFormat; password?: string; jobId?: string } = {},
+): Promise<ArchiveSummary> {
+  return invoke("pack_archive", {
+    sources,
+    destination,
+    format: options.format ?? null,
+    password: options.password ?? null,
+    jobId: options.jobId,
+  });
+}
+
+// Extracts into a folder, replacing files already there. Rejects archives
+// with entries that would land outside it; links are skipped. What was
+// extracted before a cancel stays.
+export async function extractArchive(
+  archive: string,
+  destination: string,
+  options: { password?: string; jobId?: string } = {},
+): Promise<ArchiveSummary> {
+  return invoke("extract_archive", {
+    archive,
+    destination,
+    password: options.password ?? null,
+    jobId: options.jobId,
+  });
+}
@@ -0,0 +1,52 @@
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+
+// Custom schemes are served over http on Windows
+const BASE = convertFileSrc("", "smudge").startsWith("http")
+  ? "http://smudge.localhost/asset"
+  : "smudge://asset";
+
+// URL the webview can load a file in the notes folder or a watch folder
+// from, with seeking for audio and video. Files elsewhere are refused.
+export function assetUrl(path: string): string {
+  return `${BASE}/file/${encodeURIComponent(path)}`;
+}
+
+// URL of a PNG thumbnail of an image, PDF or video in the same folders,
+// drawn and cached by the backend. size is in CSS pixels along the longer
+// side. Files without a thumbnail load as a 404, so fall back on onerror.
+export function thumbnailUrl(path: string, size: number): string {
+  const pixels = Math.ceil(size * window.devicePixelRatio);
+  return `${BASE}/thumbnail/${pixels}/${encodeURIComponent(path)}`;
+}
+
+// URL of data put with putAssetData
+export function memoryAssetUrl(name: string): string {
+  return `${BASE}/memory/${encodeURIComponent(name)}`;
+}
+
+// Serve bytes from memory under a name, replacing what it had; resolves to
+// the URL. The content type comes from the name's extension unless given.
+export async function putAssetData(
+  name: string,
+  data: Uint8Array,
+  contentType?: string,
+): Promise<string> {
+  const headers: Record<string, string> = { "x-smudge-asset-name": name };
+  if (contentType) {
+    headers["x-smudge-asset-type"] = contentType;
+  }
+  await invoke("put_asset_data", data, { headers });
+  return memoryAssetUrl(name);
+}
+
+export async function removeAssetData(name: string): Promise<boolean> {
+  return invoke("remove_asset_data", { name });
+}
+
+// The file behind an assetUrl URL, or null for other URLs
+export function pathFromAssetUrl(src: string): string | null {
+  const prefix = `${BASE}/file/`;
+  return src.startsWith(prefix)
+    ? decodeURIComponent(src.slice(prefix.length))
+    : null;
+}
@@ -0,0 +1,62 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// Interface sounds, smudge's own volume, and the media keys for the media
+// preview. macOS and Windows only; elsewhere these reject.
+
+export type Sound =
+  | { kind: "system"; name: string }
+  | { kind: "file"; path: string };
+
+// Times are in seconds
+export interface NowPlaying {
+  title: string;
+  artist?: string;
+  duration?: number;
+  position?: number;
+  playing: boolean;
+}
+
+export type MediaCommand =
+  | { kind: "play" }
+  | { kind: "pause" }
+  | { kind: "togglePlayPause" }
+  | { kind: "stop" }
+  | { kind: "nextTrack" }
+  | { kind: "previousTrack" }
+  | { kind: "seek"; position: number };
+
+// Play a system sound ("Glass" or "Pop" on macOS, "SystemAsterisk" or
+// "SystemNotification" on Windows) or a sound file, without waiting for it
+export async function playSound(sound: Sound): Promise<void> {
+  return invoke("play_sound", { sound });
+}
+
+// From 0 to 1. On Windows this is smudge's volume in the volume mixer; on
+// macOS it's the volume playSound plays at.
+export async function setVolume(volume: number): Promise<void> {
+  return invoke("set_app_volume", { volume });
+}
+
+export async function getVolume(): Promise<number> {
+  return invoke("get_app_volume");
+}
+
+// Show what's playing in Control Center or the volume flyout and start
+// receiving the media keys, or with null clear it and stop. Call again as
+// playback starts, pauses or seeks.
+export async function setNowPlaying(
+  nowPlaying: NowPlaying | null,
+): Promise<void> {
+  return invoke("set_now_playing", { nowPlaying });
+}
+
+// A media key or control was pressed while something is playing. Sent to
+// every window.
+export function onMediaCommand(
+  handler: (command: MediaCommand) => void,
+): Promise<UnlistenFn> {
+  return listen<MediaCommand>("media-command", (event) =>
+    handler(event.payload),
+  );
+}
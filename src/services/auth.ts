@@ -0,0 +1,50 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Signing in to OAuth 2 providers in the system browser, as a public client
+// with PKCE. Tokens stay in the keychain; ask for an access token before each
+// request and it's refreshed when about to expire.
+
+export interface OAuthProvider {
+  // Names the provider's tokens, e.g. "github"
+  id: string;
+  clientId: string;
+  authorizationUrl: string;
+  // Has to be on an allowed HTTP host (setAllowedHosts in http.ts)
+  tokenUrl: string;
+  scopes?: string[];
+  // "loopback" redirects to http://127.0.0.1:<port>/callback, "scheme" to
+  // smudge://oauth/callback for providers that need a fixed redirect URI
+  redirect?: "loopback" | "scheme";
+  // More authorization parameters, such as prompt or audience
+  extraParams?: Record<string, string>;
+}
+
+export interface OAuthSession {
+  provider: string;
+  // Seconds since the Unix epoch, or null when the provider didn't say
+  expiresAt: number | null;
+  scopes: string[];
+  refreshable: boolean;
+}
+
+// Open the provider's sign-in page and wait, up to ten minutes, for the
+// browser to come back. Signing in again replaces the tokens.
+export async function signIn(provider: OAuthProvider): Promise<OAuthSession> {
+  return invoke("oauth_sign_in", { provider });
+}
+
+// Rejects when signed out or the session can't be refreshed, which needs
+// signIn again
+export async function accessToken(provider: string): Promise<string> {
+  return invoke("oauth_access_token", { provider });
+}
+
+export async function getSession(
+  provider: string,
+): Promise<OAuthSession | null> {
+  return invoke("oauth_session", { provider });
+}
+
+export async function signOut(provider: string): Promise<void> {
+  return invoke("oauth_sign_out", { provider });
+}
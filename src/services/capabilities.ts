@@ -0,0 +1,38 @@
+import { invoke } from "@tauri-apps/api/core";
+import { takeSnapshot } from "../lib/snapshot";
+
+export type CapabilityId =
+  | "git"
+  | "aiAssistant"
+  | "screenRecording"
+  | "updates"
+  | "stripQuarantine"
+  | "rememberFolders"
+  | "network"
+  | "extensionServer"
+  | "loopbackRedirect"
+  | "universalLinks";
+
+export interface Capability {
+  id: CapabilityId;
+  available: boolean;
+  // Why it's unavailable in this build
+  reason: string | null;
+}
+
+export interface CapabilityReport {
+  // Built for the macOS App Sandbox
+  sandboxed: boolean;
+  capabilities: Capability[];
+}
+
+export async function getCapabilities(): Promise<CapabilityReport> {
+  return takeSnapshot("capabilities") ?? invoke<CapabilityReport>("get_capabilities");
+}
+
+export function isAvailable(
+  report: CapabilityReport | null,
+  id: CapabilityId,
+): boolean {
+  return report?.capabilities.find((c) => c.id === id)?.available ?? true;
+}
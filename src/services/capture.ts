@@ -0,0 +1,25 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Screenshots of windows and monitors, as PNGs. Each returns a
+// `data:image/png;base64,...` URL, usable as an image's src, or the path the
+// PNG was saved at when given one. On macOS, capturing a monitor needs the
+// Screen Recording permission; without it the call fails and System Settings
+// opens at its pane.
+
+// The window `label`, or this one, as it's drawn even where other windows
+// cover it. On Linux it's the part of the screen the window covers.
+export async function captureWindow(
+  label?: string,
+  path?: string,
+): Promise<string> {
+  return invoke("capture_window", { label, path });
+}
+
+// Everything on the monitor named `monitor`, as `availableMonitors` names
+// it, or on this window's monitor. Unavailable in sandboxed builds.
+export async function captureScreen(
+  monitor?: string,
+  path?: string,
+): Promise<string> {
+  return invoke("capture_screen", { monitor, path });
+}
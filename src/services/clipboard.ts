@@ -0,0 +1,45 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type ClipboardFormat = "text" | "html" | "rtf" | "png" | "files";
+
+// What the clipboard holds, in each format it holds it in. Linux only has
+// text.
+export interface ClipboardData {
+  text?: string | null;
+  html?: string | null;
+  rtf?: string | null;
+  // Base64-encoded PNG
+  png?: string | null;
+  files?: string[];
+}
+
+export interface ClipboardChanged {
+  changeCount: number;
+  formats: ClipboardFormat[];
+}
+
+// Everything on the clipboard, including images and copied files the
+// webview's paste event doesn't see. Copied files become readable by other
+// commands.
+export async function readClipboard(): Promise<ClipboardData> {
+  return invoke("read_clipboard");
+}
+
+// Replace what's on the clipboard with data, in every format given
+export async function writeClipboard(data: ClipboardData): Promise<void> {
+  return invoke("write_clipboard", { data });
+}
+
+export async function getClipboardFormats(): Promise<ClipboardFormat[]> {
+  return invoke("get_clipboard_formats");
+}
+
+// Called whenever the clipboard changes, in smudge or another app
+export async function onClipboardChanged(
+  handler: (change: ClipboardChanged) => void,
+): Promise<UnlistenFn> {
+  return listen<ClipboardChanged>("clipboard-changed", (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,33 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type InterfaceType = "wifi" | "wired" | "cellular" | "other";
+
+export interface NetworkStatus {
+  // A route to the internet and no captive portal in the way
+  online: boolean;
+  interfaceType: InterfaceType | null;
+  // Metered, or Low Data Mode on macOS: hold off on large transfers
+  expensive: boolean;
+  // The network wants its sign-in page filled in first
+  captivePortal: boolean;
+}
+
+// Online until the system has reported otherwise
+export async function getNetworkStatus(): Promise<NetworkStatus> {
+  return invoke("get_network_status");
+}
+
+// Resolves once the internet can be reached, right away if it can now
+export async function waitForOnline(): Promise<void> {
+  return invoke("wait_for_online");
+}
+
+// Sent to every window whenever the status changes
+export function onNetworkChanged(
+  handler: (status: NetworkStatus) => void,
+): Promise<UnlistenFn> {
+  return listen<NetworkStatus>("network-changed", (event) =>
+    handler(event.payload),
+  );
+}
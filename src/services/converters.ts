@@ -0,0 +1,39 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { BatchReport } from "./jobs";
+
+export type ConvertFormat = "markdown" | "html" | "csv" | "json" | "pdf";
+
+export interface ConverterInfo {
+  format: ConvertFormat;
+  extensions: string[];
+  canImport: boolean;
+  canExport: boolean;
+}
+
+export interface ConvertOptions {
+  // Folder the converted files are written to
+  destination: string;
+  // CSV: whether the first row names the columns (default true)
+  header?: boolean;
+  // CSV: the separator between fields, a comma by default
+  delimiter?: string;
+  // PDF: the paper's orientation
+  orientation?: "portrait" | "landscape";
+}
+
+export async function listConverters(): Promise<ConverterInfo[]> {
+  return invoke("list_converters");
+}
+
+// Converts files as a cancellable job; follow it with onJobProgress and
+// control it with the job functions in jobs.ts. Each file's format is taken
+// from its extension unless from is given.
+export async function convert(
+  input: string[],
+  to: ConvertFormat,
+  options: ConvertOptions,
+  from?: ConvertFormat,
+  jobId?: string,
+): Promise<BatchReport> {
+  return invoke("convert", { input, from, to, options, jobId });
+}
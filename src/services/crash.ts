@@ -0,0 +1,41 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// What the backend kept about a crash of the previous run. Nothing leaves
+// the machine unless the user chooses to send it with uploadCrashReport.
+export interface CrashReport {
+  id: string;
+  appVersion: string;
+  os: string;
+  arch: string;
+  crashedAt: number;
+  message: string;
+  location: string | null;
+  thread: string | null;
+  backtrace: string | null;
+  // The fatal signal or Windows exception, when it wasn't just a panic
+  signal: string | null;
+  // The last log lines before the crash
+  breadcrumbs: string[];
+  minidump: boolean;
+  // The report macOS wrote for the same crash
+  systemReport: string | null;
+  uploaded: boolean;
+}
+
+// Sent once the frontend is ready, if the previous run crashed
+export function onCrashDetected(
+  handler: (report: CrashReport) => void,
+): Promise<UnlistenFn> {
+  return listen<CrashReport>("crash-detected", (event) =>
+    handler(event.payload),
+  );
+}
+
+export async function uploadCrashReport(id: string): Promise<void> {
+  return invoke("upload_crash_report", { id });
+}
+
+export async function discardCrashReport(id: string): Promise<void> {
+  return invoke("discard_crash_report", { id });
+}
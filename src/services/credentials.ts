@@ -0,0 +1,28 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Secrets smudge keeps in the OS keychain: the macOS Keychain, Windows
+// Credential Manager, or the Secret Service through libsecret on Linux.
+// The backend refuses any other key.
+export type CredentialKey =
+  | "ai-api-key"
+  | "extension-token"
+  | "git-token"
+  | "sync-token";
+
+export async function storeCredential(
+  key: CredentialKey,
+  secret: string,
+): Promise<void> {
+  return invoke("store_credential", { key, secret });
+}
+
+// Null when nothing is saved under `key`
+export async function retrieveCredential(
+  key: CredentialKey,
+): Promise<string | null> {
+  return invoke("retrieve_credential", { key });
+}
+
+export async function deleteCredential(key: CredentialKey): Promise<void> {
+  return invoke("delete_credential", { key });
+}
@@ -0,0 +1,70 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Structured data kept in the app's database as JSON values by namespace
+// and key. Namespaces here are the frontend's own; the backend's aren't
+// reachable from them.
+export interface DbEntry<T = unknown> {
+  key: string;
+  value: T;
+  // Seconds since the epoch
+  updated: number;
+}
+
+export async function dbGet<T>(
+  namespace: string,
+  key: string,
+): Promise<T | null> {
+  return invoke("db_get", { namespace, key });
+}
+
+export async function dbPut<T>(
+  namespace: string,
+  key: string,
+  value: T,
+): Promise<void> {
+  return invoke("db_put", { namespace, key, value });
+}
+
+export async function dbDelete(namespace: string, key: string): Promise<void> {
+  return invoke("db_delete", { namespace, key });
+}
+
+// Every entry in the namespace, by key
+export async function dbList<T>(namespace: string): Promise<DbEntry<T>[]> {
+  return invoke("db_list", { namespace });
+}
+
+// Writes the whole database to one backup file
+export async function exportBackup(path: string): Promise<void> {
+  return invoke("export_backup", { path });
+}
+
+// Restores a backup from exportBackup. The app restarts to swap it in, so
+// this only returns if the backup can't be used.
+export async function importBackup(path: string): Promise<void> {
+  return invoke("import_backup", { path });
+}
+
+export interface DbEncryption {
+  encrypted: boolean;
+  // Nothing can be read or written while an encrypted database is locked
+  locked: boolean;
+}
+
+export async function databaseEncryption(): Promise<DbEncryption> {
+  return invoke("database_encryption");
+}
+
+// Encrypts the database in place. Its key is wrapped by one kept in the
+// keychain, and it locks and unlocks along with the screen.
+export async function encryptDatabase(): Promise<void> {
+  return invoke("encrypt_database");
+}
+
+export async function lockDatabase(): Promise<void> {
+  return invoke("lock_database");
+}
+
+export async function unlockDatabase(): Promise<void> {
+  return invoke("unlock_database");
+}
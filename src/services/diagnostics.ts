@@ -0,0 +1,56 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Record this window for `seconds`; resolves with the mp4 path once done
+export async function recordWindow(seconds: number): Promise<string> {
+  return invoke("record_window", { seconds });
+}
+
+// Screenshot the window `label`, or this one, for the next bundle; resolves
+// with the png path
+export async function attachScreenshot(label?: string): Promise<string> {
+  return invoke("attach_screenshot", { label });
+}
+
+// Gather pending recordings, screenshots and system details; resolves with
+// the folder
+export async function createDiagnosticsBundle(): Promise<string> {
+  return invoke("create_diagnostics_bundle");
+}
+
+// Recent messages from the windowing layer, e.g. files the OS asked to open
+export async function getPlatformDiagnostics(): Promise<string[]> {
+  return invoke("get_platform_diagnostics");
+}
+
+export async function setPlatformDiagnostics(enabled: boolean): Promise<void> {
+  return invoke("set_platform_diagnostics", { enabled });
+}
+
+// The app's most recent log lines, oldest first
+export async function getRecentLogs(): Promise<string[]> {
+  return invoke("get_recent_logs");
+}
+
+// Copy the whole log, rotated files included, to `path`
+export async function exportLogs(path: string): Promise<void> {
+  return invoke("export_logs", { path });
+}
+
+// Write the recent command, job and timed intervals to `path` as a Chrome
+// trace, which chrome://tracing, Perfetto and speedscope open as a flame chart.
+// Instruments and Windows Performance Recorder see the same intervals live.
+export async function exportProfile(path: string): Promise<void> {
+  return invoke("export_profile", { path });
+}
+
+// Start the next export from here
+export async function clearProfile(): Promise<void> {
+  return invoke("clear_profile");
+}
+
+export type LogLevel = "error" | "warn" | "info" | "debug" | "trace";
+
+// Until the app quits; SMUDGE_LOG sets it at launch
+export async function setLogLevel(level: LogLevel): Promise<void> {
+  return invoke("set_log_level", { level });
+}
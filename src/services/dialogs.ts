@@ -0,0 +1,84 @@
+import { invoke } from "@tauri-apps/api/core";
+import { translate } from "./i18n";
+
+export interface FileFilter {
+  name: string;
+  extensions: string[];
+}
+
+// Labels are shown as given; take them from smudge's catalogs with
+// `dialogLabels` or `translate`
+export interface DialogOptions {
+  // Dialogs with the same purpose reopen in the directory last picked there
+  purpose?: "notes-folder" | "watch-folder" | "export" | "import";
+  title?: string;
+  buttonLabel?: string;
+  filters?: FileFilter[];
+  defaultName?: string;
+  // Starting directory, instead of the remembered one
+  defaultPath?: string | null;
+  // Uniform type identifiers allowed alongside the filters. macOS only.
+  contentTypes?: string[];
+  // Browse into packages like .app bundles. macOS only.
+  treatPackagesAsDirectories?: boolean;
+  showHiddenFiles?: boolean;
+  // Tags the save panel starts with. macOS only.
+  tags?: string[];
+  // Controls shown in the dialog; their values come back with the pick
+  accessory?: AccessoryControl[];
+}
+
+export type AccessoryControl =
+  | { kind: "checkbox"; label: string; checked?: boolean }
+  | { kind: "popup"; label: string; options: string[]; selected?: number };
+
+export type DialogKind = "openFile" | "pickFolder" | "saveFile";
+
+export interface Picked {
+  path: string;
+  // Per accessory control, in order: whether a checkbox is checked, or the
+  // index of a popup's option
+  accessory: (boolean | number)[];
+  // Tags the user gave a saved file, for the caller to apply once it exists
+  tags: string[];
+}
+
+// The title and accept button of `dialog` in the current language: the
+// catalogs' dialog-<dialog>-title and dialog-<dialog>-button
+export async function dialogLabels(
+  dialog: string,
+  args?: Record<string, string | number | null>,
+): Promise<Pick<DialogOptions, "title" | "buttonLabel">> {
+  const [title, buttonLabel] = await Promise.all([
+    translate(`dialog-${dialog}-title`, args),
+    translate(`dialog-${dialog}-button`, args),
+  ]);
+  return { title, buttonLabel };
+}
+
+export async function pickFolder(
+  options: DialogOptions = {},
+): Promise<string | null> {
+  return invoke("open_folder_dialog", { options });
+}
+
+export async function pickFile(
+  options: DialogOptions = {},
+): Promise<string | null> {
+  return invoke("open_file_dialog", { options });
+}
+
+export async function pickSaveFile(
+  options: DialogOptions = {},
+): Promise<string | null> {
+  return invoke("save_file_dialog", { options });
+}
+
+// Like the pickers above, with the accessory controls' values alongside the
+// path
+export async function showDialog(
+  kind: DialogKind,
+  options: DialogOptions = {},
+): Promise<Picked | null> {
+  return invoke("show_file_dialog", { kind, options });
+}
@@ -0,0 +1,53 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// What happened to one file since the last batch. A file created and then
+// removed within a batch isn't reported at all.
+export type DirectoryChange =
+  | { kind: "created"; path: string }
+  | { kind: "modified"; path: string }
+  | { kind: "removed"; path: string }
+  | { kind: "renamed"; from: string; to: string };
+
+interface DirectoryChanged {
+  id: string;
+  changes: DirectoryChange[];
+}
+
+// Call onChange with each batch of changes in a directory, once it's been
+// quiet for a moment. The directory has to be in the notes folder or granted.
+// Resolves to a function that stops watching.
+export async function watchDirectory(
+  path: string,
+  onChange: (changes: DirectoryChange[]) => void,
+  recursive = false,
+): Promise<UnlistenFn> {
+  const id = await invoke<string>("watch_directory", { path, recursive });
+  const unlisten = await listen<DirectoryChanged>(
+    "directory-changed",
+    (event) => {
+      if (event.payload.id === id) {
+        onChange(event.payload.changes);
+      }
+    },
+  );
+  return () => {
+    unlisten();
+    invoke("unwatch_directory", { id }).catch(() => {});
+  };
+}
+
+// Whether a batch touched a file called `name` in the watched directory,
+// including moving another file onto it. Compares names because the backend
+// reports resolved paths, which can differ from the one the file was opened
+// with (e.g. /private/var for /var).
+export function changesFileNamed(
+  changes: DirectoryChange[],
+  name: string,
+): boolean {
+  const baseName = (path: string) => path.split(/[\\/]/).pop();
+  return changes.some(
+    (change) =>
+      baseName(change.kind === "renamed" ? change.to : change.path) === name,
+  );
+}
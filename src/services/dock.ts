@@ -0,0 +1,55 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// macOS only: without its Dock icon the app keeps running from its windows
+// and the menu bar. Launching it again brings the icon back.
+export async function setDockVisible(visible: boolean): Promise<void> {
+  return invoke("set_dock_visible", { visible });
+}
+
+export async function isDockVisible(): Promise<boolean> {
+  return invoke("is_dock_visible");
+}
+
+export function onDockVisibilityChange(
+  handler: (visible: boolean) => void,
+): Promise<UnlistenFn> {
+  return listen<boolean>("dock-visibility-changed", (event) =>
+    handler(event.payload),
+  );
+}
+
+export interface DockMenuNote {
+  id: string;
+  title: string;
+}
+
+export type DockMenuAction =
+  | { action: "newNote" }
+  | { action: "newFromTemplate"; templateId: string }
+  | { action: "openNote"; noteId: string };
+
+// "New Note", "New from Template", the recent projects and these notes, in
+// order, at the top of the Dock icon's menu (macOS). The backend lists the
+// first ten notes.
+export async function setDockMenu(notes: DockMenuNote[]): Promise<void> {
+  return invoke("set_dock_menu", { notes });
+}
+
+// An item chosen from the Dock menu; the main window is already in front
+export function onDockMenuAction(
+  handler: (action: DockMenuAction) => void,
+): Promise<UnlistenFn> {
+  return listen<DockMenuAction>("dock-menu", (event) => handler(event.payload));
+}
+
+// Text on the Dock icon, e.g. an unread count (macOS); null removes it
+export async function setBadgeLabel(label: string | null): Promise<void> {
+  return invoke("set_badge_label", { label });
+}
+
+// Progress from 0 to 1 on the Dock icon (macOS) or the taskbar button
+// (Windows); null removes it
+export async function setDockProgress(progress: number | null): Promise<void> {
+  return invoke("set_dock_progress", { progress });
+}
@@ -0,0 +1,29 @@
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+
+// A note file dragged as itself, or rendered to HTML where it's dropped
+export type DragItem =
+  | { kind: "file"; path: string }
+  | { kind: "html"; path: string };
+
+export type DragOperation = "none" | "copy" | "move" | "link";
+
+// Start a native drag out to the Finder or Explorer. Call it from dragstart
+// after preventing the webview's own drag, while the button is still down.
+// `image` is a base64 PNG shown under the pointer in place of file icons.
+export async function startDrag(
+  items: DragItem[],
+  image?: string,
+): Promise<void> {
+  return invoke("start_drag", { items, image: image ?? null });
+}
+
+// A drag begun in this window ended
+export function onDragEnded(
+  handler: (operation: DragOperation) => void,
+): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<DragOperation>("drag-ended", (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,27 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// The loopback server the browser extension sends clips to. Clips arrive
+// as "clip" deep links with url, title and selection params.
+
+export interface ExtensionServerStatus {
+  running: boolean;
+  port: number;
+  // What to paste into the extension to pair it; null while stopped
+  token: string | null;
+}
+
+export async function getExtensionServer(): Promise<ExtensionServerStatus> {
+  return invoke("get_extension_server");
+}
+
+// Also whether it starts at launch
+export async function setExtensionServerEnabled(
+  enabled: boolean,
+): Promise<ExtensionServerStatus> {
+  return invoke("set_extension_server_enabled", { enabled });
+}
+
+// The extension has to be paired again with the new token
+export async function resetExtensionToken(): Promise<ExtensionServerStatus> {
+  return invoke("reset_extension_token");
+}
@@ -0,0 +1,46 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface FileAssociation {
+  id: string;
+  name: string;
+  extensions: string[];
+  // Smudge is the default app for this type
+  owned: boolean;
+  // Bundle ID, ProgID or desktop file of the current default app
+  handler: string | null;
+}
+
+export interface AssociationChange {
+  association: FileAssociation;
+  // Windows only lets the user pick defaults, so claiming opens its
+  // Default Apps settings instead
+  needsConfirmation: boolean;
+}
+
+export async function listFileAssociations(): Promise<FileAssociation[]> {
+  return invoke("list_file_associations");
+}
+
+export async function claimFileAssociation(
+  id: string,
+): Promise<AssociationChange> {
+  return invoke("claim_file_association", { id });
+}
+
+export async function releaseFileAssociation(
+  id: string,
+): Promise<AssociationChange> {
+  return invoke("release_file_association", { id });
+}
+
+// Whether Smudge opens files with this extension by default
+export async function isDefaultHandler(ext: string): Promise<boolean> {
+  return invoke("is_default_handler", { ext });
+}
+
+// Make Smudge the default app for the file type this extension belongs to
+export async function setDefaultHandler(
+  ext: string,
+): Promise<AssociationChange> {
+  return invoke("set_default_handler", { ext });
+}
@@ -0,0 +1,54 @@
+import { invoke } from "@tauri-apps/api/core";
+import { isMac } from "../lib/platform";
+
+export type TagColor =
+  | "gray"
+  | "green"
+  | "purple"
+  | "blue"
+  | "yellow"
+  | "red"
+  | "orange";
+
+export interface FileTag {
+  name: string;
+  // Only Finder shows label colors
+  color: TagColor | null;
+}
+
+// Timestamps are seconds since the epoch
+export interface FileMetadata {
+  path: string;
+  size: number;
+  created: number | null;
+  modified: number | null;
+  tags: FileTag[];
+}
+
+export const fileManagerName = isMac
+  ? "Finder"
+  : /Windows/.test(navigator.userAgent)
+    ? "Explorer"
+    : "File Manager";
+
+export async function getFileMetadata(path: string): Promise<FileMetadata> {
+  return invoke("get_file_metadata", { path });
+}
+
+// Replaces all tags: Finder tags on macOS, user.xdg.tags on Linux, an NTFS
+// stream on Windows
+export async function setFileTags(path: string, tags: FileTag[]): Promise<void> {
+  return invoke("set_file_tags", { path, tags });
+}
+
+export async function setFileDates(
+  path: string,
+  dates: { created?: number; modified?: number },
+): Promise<void> {
+  return invoke("set_file_dates", { path, ...dates });
+}
+
+// Show the files in the system file manager with them selected
+export async function revealFiles(paths: string[]): Promise<void> {
+  return invoke("reveal_files", { paths });
+}
@@ -0,0 +1,47 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export interface FindOptions {
+  caseSensitive?: boolean;
+  // Windows only
+  wholeWord?: boolean;
+  // Highlight every match, not just the active one; on by default,
+  // Windows only
+  highlightAll?: boolean;
+}
+
+export interface FindMatches {
+  query: string;
+  // null until the page is counted. On macOS, a match split across
+  // elements is found but not counted.
+  matchCount: number | null;
+  // Counting from 1; null when nothing is selected
+  activeMatch: number | null;
+}
+
+// Find `query` in this window's page with the web view's own find,
+// selecting the first match. An empty query stops finding.
+export async function findInPage(
+  query: string,
+  options?: FindOptions,
+): Promise<FindMatches> {
+  return invoke("find_in_page", { query, options });
+}
+
+// Move to the next match, or the previous one, wrapping around the page
+export async function findNext(backwards = false): Promise<FindMatches> {
+  return invoke("find_next", { backwards });
+}
+
+export async function stopFind(): Promise<void> {
+  return invoke("stop_find");
+}
+
+// The match count or the active match changed in this window
+export function onFindChange(
+  handler: (matches: FindMatches) => void,
+): Promise<UnlistenFn> {
+  return listen<FindMatches>("find-changed", (event) =>
+    handler(event.payload),
+  );
+}
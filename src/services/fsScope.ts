@@ -0,0 +1,34 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type FsAccess = "read" | "readWrite";
+
+export type GrantSource = "dialog" | "opened" | "dropped" | "pasted";
+
+// A path the user picked, opened or dropped. Commands that take a path
+// refuse anything outside these and the notes folder.
+export interface FsGrant {
+  path: string;
+  // Grants on a folder cover everything in it
+  folder: boolean;
+  access: FsAccess;
+  source: GrantSource;
+  // Milliseconds since the epoch
+  grantedAt: number;
+}
+
+export async function listFsGrants(): Promise<FsGrant[]> {
+  return invoke("list_fs_grants");
+}
+
+// Sandboxed macOS builds keep access until the app quits, but commands
+// refuse the path straight away
+export async function revokeFsGrant(path: string): Promise<void> {
+  return invoke("revoke_fs_grant", { path });
+}
+
+// Let go of a document opened from outside the notes folder once it's
+// closed. Sandboxed builds can only keep so many open at once; commands
+// open it again when they need it.
+export async function closeDocument(path: string): Promise<void> {
+  return invoke("close_document", { path });
+}
@@ -0,0 +1,25 @@
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+
+export type GesturePhase = "started" | "moved" | "ended" | "cancelled";
+
+export type TrackpadGesture =
+  // Zoom in by a factor of 1 + delta
+  | { kind: "magnify"; delta: number; phase: GesturePhase }
+  // Degrees counterclockwise since the last one
+  | { kind: "rotate"; delta: number; phase: GesturePhase }
+  // Two-finger double tap: zoom to fit what's under the pointer, or back out
+  | { kind: "smartMagnify" }
+  // Pressure from 0 to 1; stage 2 is a force click
+  | { kind: "pressure"; pressure: number; stage: number; phase: GesturePhase };
+
+// The trackpad was pinched, rotated, double-tapped or pressed over this
+// window. macOS only; nothing arrives elsewhere.
+export function onTrackpadGesture(
+  handler: (gesture: TrackpadGesture) => void,
+): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<TrackpadGesture>(
+    "trackpad-gesture",
+    (event) => handler(event.payload),
+  );
+}
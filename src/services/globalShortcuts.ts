@@ -0,0 +1,76 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { isMac, isWindows } from "../lib/platform";
+
+// Accelerators look like "CmdOrCtrl+Shift+Space": modifiers, then one key
+// named by its KeyboardEvent.code
+
+export async function getQuickCaptureShortcut(): Promise<string | null> {
+  return invoke("get_quick_capture_shortcut");
+}
+
+// Null turns quick capture off. Rejects, keeping the old shortcut, if the
+// new one is taken by smudge or another app.
+export async function setQuickCaptureShortcut(
+  shortcut: string | null,
+): Promise<void> {
+  return invoke("set_quick_capture_shortcut", { shortcut });
+}
+
+// Registers an accelerator that works while other apps are in front;
+// presses arrive through onGlobalShortcut with the same name
+export async function registerGlobalShortcut(
+  name: string,
+  accelerator: string,
+): Promise<void> {
+  return invoke("register_global_shortcut", { name, accelerator });
+}
+
+export async function unregisterGlobalShortcut(name: string): Promise<void> {
+  return invoke("unregister_global_shortcut", { name });
+}
+
+// The quick capture shortcut was pressed and the main window brought up
+export function onQuickCapture(handler: () => void): Promise<UnlistenFn> {
+  return listen("quick-capture", () => handler());
+}
+
+export function onGlobalShortcut(
+  handler: (name: string) => void,
+): Promise<UnlistenFn> {
+  return listen<string>("global-shortcut", (event) => handler(event.payload));
+}
+
+// The accelerator for a key press, or null while only modifiers are held
+export function acceleratorFromEvent(event: KeyboardEvent): string | null {
+  if (/^(Meta|Control|Alt|Shift|OS)/.test(event.code)) return null;
+  const parts: string[] = [];
+  if (isMac ? event.metaKey : event.ctrlKey) parts.push("CmdOrCtrl");
+  if (isMac && event.ctrlKey) parts.push("Ctrl");
+  if (!isMac && event.metaKey) parts.push("Super");
+  if (event.altKey) parts.push("Alt");
+  if (event.shiftKey) parts.push("Shift");
+  parts.push(event.code);
+  return parts.join("+");
+}
+
+// "CmdOrCtrl+Shift+KeyN" as the platform writes it, e.g. "⌘⇧N"
+export function formatAccelerator(accelerator: string): string {
+  const labels = accelerator.split("+").map((part) => {
+    switch (part) {
+      case "CmdOrCtrl":
+        return isMac ? "⌘" : "Ctrl";
+      case "Ctrl":
+        return isMac ? "⌃" : "Ctrl";
+      case "Alt":
+        return isMac ? "⌥" : "Alt";
+      case "Shift":
+        return isMac ? "⇧" : "Shift";
+      case "Super":
+        return isWindows ? "Win" : "Super";
+      default:
+        return part.replace(/^(Key|Digit)/, "");
+    }
+  });
+  return labels.join(isMac ? "" : "+");
+}
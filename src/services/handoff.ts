@@ -0,0 +1,34 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface HandoffDocument {
+  noteId: string;
+  title: string;
+  position: number | null;
+  scrollTop: number | null;
+}
+
+export async function advertiseHandoff(document: HandoffDocument): Promise<void> {
+  return invoke("handoff_advertise", { document });
+}
+
+export async function clearHandoff(): Promise<void> {
+  return invoke("handoff_clear");
+}
+
+export async function getPendingHandoff(): Promise<HandoffDocument | null> {
+  return invoke("get_pending_handoff");
+}
+
+// Position to restore once the continued note has loaded in the editor
+let pendingPosition: HandoffDocument | null = null;
+
+export function setPendingHandoffPosition(document: HandoffDocument) {
+  pendingPosition = document;
+}
+
+export function takePendingHandoffPosition(noteId: string): HandoffDocument | null {
+  if (pendingPosition?.noteId !== noteId) return null;
+  const document = pendingPosition;
+  pendingPosition = null;
+  return document;
+}
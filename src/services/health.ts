@@ -0,0 +1,26 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface QueueMetrics {
+  // Tasks waiting for a worker
+  queued: number;
+  peakQueued: number;
+  running: number;
+  completed: number;
+  panicked: number;
+  // Mean time from submission to a worker picking the task up
+  averageWaitMs: number;
+}
+
+export interface Health {
+  executor: {
+    workers: number;
+    interactive: QueueMetrics;
+    background: QueueMetrics;
+  };
+  searchIndexReady: boolean;
+}
+
+// Queue depths of the backend's command executor, and whether search is ready
+export async function getHealth(): Promise<Health> {
+  return invoke("get_health");
+}
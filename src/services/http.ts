@@ -0,0 +1,69 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Requests made by the backend, which shares one cookie jar across them,
+// goes through the system proxy and can write straight to disk. Only hosts
+// in the allowlist can be reached.
+
+export interface HttpRequest {
+  method?: string;
+  url: string;
+  headers?: Record<string, string>;
+  body?: string;
+  // body is base64, for binary bodies
+  bodyBase64?: boolean;
+  timeoutMs?: number;
+  // "base64" for binary responses
+  responseType?: "text" | "base64";
+}
+
+export interface HttpResponse {
+  status: number;
+  // Where the request ended up after redirects
+  url: string;
+  // Repeated headers are joined with ", "
+  headers: Record<string, string>;
+  body: string;
+}
+
+export interface DownloadRequest {
+  url: string;
+  path: string;
+  headers?: Record<string, string>;
+}
+
+export interface Download {
+  path: string;
+  bytes: number;
+  // Continued from what an earlier attempt left
+  resumed: boolean;
+  // False when the job was cancelled; downloading again resumes
+  complete: boolean;
+}
+
+export async function httpRequest(request: HttpRequest): Promise<HttpResponse> {
+  return invoke("http_request", { request });
+}
+
+// Download to a file as a job; progress in bytes arrives through
+// jobs.onJobProgress. Downloading to the same path after a failure or
+// cancellation resumes if the server still has the same file.
+export async function download(
+  request: DownloadRequest,
+  jobId?: string,
+): Promise<Download> {
+  return invoke("http_download", { request, jobId });
+}
+
+export async function getAllowedHosts(): Promise<string[]> {
+  return invoke("get_http_allowed_hosts");
+}
+
+// Names like "example.com", or "*.example.com" for its subdomains. The user
+// is asked to allow any that are new; false if they didn't.
+export async function setAllowedHosts(hosts: string[]): Promise<boolean> {
+  return invoke("set_http_allowed_hosts", { hosts });
+}
+
+export async function clearCookies(): Promise<void> {
+  return invoke("clear_http_cookies");
+}
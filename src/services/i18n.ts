@@ -0,0 +1,41 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// smudge's translations, from the Fluent catalogs bundled with the app. The
+// catalog follows the system's languages unless one is chosen in smudge.
+
+export interface LocaleInfo {
+  // The catalog in use, one of `available`
+  locale: string;
+  // Most preferred first, as BCP 47 tags
+  systemLanguages: string[];
+  chosen: string | null;
+  available: string[];
+}
+
+export async function getLocale(): Promise<LocaleInfo> {
+  return invoke("get_locale");
+}
+
+// null follows the system's languages again
+export async function setLocale(language: string | null): Promise<LocaleInfo> {
+  return invoke("set_locale", { language });
+}
+
+// Numbers in `args` are formatted for the language and pick plural forms
+export async function translate(
+  key: string,
+  args?: Record<string, string | number | null>,
+): Promise<string> {
+  return invoke("translate", { key, args });
+}
+
+// Sent once the native menus are in the new language; re-render, and set
+// any menus of your own again
+export async function onLocaleChanged(
+  handler: (info: LocaleInfo) => void,
+): Promise<UnlistenFn> {
+  return listen<LocaleInfo>("locale-changed", (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,122 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type JobState =
+  | "running"
+  | "paused"
+  | "cancelled"
+  | "completed"
+  | "failed";
+
+export interface JobProgress {
+  id: string;
+  name: string;
+  state: JobState;
+  completed: number;
+  total: number;
+  message: string | null;
+  // What a completed job returned, on its last event
+  result: unknown;
+  // Why a failed job failed, on its last event
+  error: string | null;
+}
+
+// Jobs submitJob runs, with the arguments each takes
+export type JobRequest =
+  | { name: "rebuildSearchIndex" }
+  | { name: "exportBackup"; args: { path: string } }
+  | { name: "exportLogs"; args: { path: string } }
+  // Resolves to the bundle's path
+  | { name: "diagnosticsBundle" };
+
+export type BatchOperation =
+  | { type: "import"; tags?: string[] }
+  | { type: "export"; destination: string }
+  | { type: "exportHtml"; destination: string };
+
+export interface BatchItemResult {
+  path: string;
+  success: boolean;
+  output: string | null;
+  error: string | null;
+}
+
+export interface BatchReport {
+  jobId: string;
+  succeeded: number;
+  failed: number;
+  skipped: string[];
+  cancelled: boolean;
+  results: BatchItemResult[];
+}
+
+export async function batchProcess(
+  paths: string[],
+  operation: BatchOperation,
+  jobId?: string,
+): Promise<BatchReport> {
+  return invoke("batch_process", { paths, operation, jobId });
+}
+
+// Start a job in the background and resolve to its ID at once. Its progress,
+// then its result or error, arrive through onJobProgress.
+export async function submitJob(
+  request: JobRequest,
+  jobId?: string,
+): Promise<string> {
+  const args = "args" in request ? request.args : undefined;
+  return invoke("submit_job", { name: request.name, args, jobId });
+}
+
+// Submit a job and resolve to its result once it completes, calling
+// onProgress along the way. Rejects if it fails or is cancelled.
+export async function runJob(
+  request: JobRequest,
+  onProgress?: (progress: JobProgress) => void,
+): Promise<unknown> {
+  const jobId = `${request.name}-${crypto.randomUUID()}`;
+  let settle: (progress: JobProgress) => void = () => {};
+  const finished = new Promise<JobProgress>((resolve) => {
+    settle = resolve;
+  });
+  // Listening before submitting, so the last event can't be missed
+  const unlisten = await onJobProgress(jobId, (progress) => {
+    onProgress?.(progress);
+    if (progress.state !== "running" && progress.state !== "paused") {
+      settle(progress);
+    }
+  });
+  try {
+    await submitJob(request, jobId);
+    const last = await finished;
+    if (last.state !== "completed") {
+      throw new Error(last.error ?? "Cancelled");
+    }
+    return last.result;
+  } finally {
+    unlisten();
+  }
+}
+
+export async function listJobs(): Promise<JobProgress[]> {
+  return invoke("list_jobs");
+}
+
+export async function pauseJob(id: string): Promise<void> {
+  return invoke("pause_job", { id });
+}
+
+export async function resumeJob(id: string): Promise<void> {
+  return invoke("resume_job", { id });
+}
+
+export async function cancelJob(id: string): Promise<void> {
+  return invoke("cancel_job", { id });
+}
+
+export async function onJobProgress(
+  id: string,
+  handler: (progress: JobProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<JobProgress>(`job://${id}`, (event) => handler(event.payload));
+}
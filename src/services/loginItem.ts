@@ -0,0 +1,13 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Whether Smudge starts when the user logs in: a login item on macOS, the
+// Run key on Windows, an autostart entry on Linux
+export async function launchAtLogin(): Promise<boolean> {
+  return invoke("launch_at_login");
+}
+
+// On macOS, turning it on after the user turned Smudge off in System
+// Settings opens Login Items there and rejects
+export async function setLaunchAtLogin(enabled: boolean): Promise<void> {
+  return invoke("set_launch_at_login", { enabled });
+}
@@ -0,0 +1,61 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Translucent materials behind the window's page: NSVisualEffectView on
+// macOS and Windows 11's Mica and Acrylic backdrops, each standing in for
+// the other's nearest match. They show through wherever the page's CSS
+// leaves the background transparent. Unavailable in sandboxed builds.
+
+export type Material =
+  | "sidebar"
+  | "headerView"
+  | "hud"
+  | "underWindow"
+  | "titlebar"
+  | "menu"
+  | "popover"
+  | "sheet"
+  | "windowBackground"
+  | "contentBackground"
+  | "mica"
+  | "acrylic"
+  | "tabbed";
+
+// In CSS pixels from the window's top left
+export interface MaterialRegion {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  material: Material;
+}
+
+// Puts `material` behind the whole window, or makes the page opaque again
+// with null
+export async function setWindowMaterial(
+  material: Material | null,
+): Promise<void> {
+  return invoke("set_window_material", { material });
+}
+
+// Replaces the window's regions. Windows can only have one backdrop, so it
+// uses the first region's material for the whole window.
+export async function setMaterialRegions(
+  regions: MaterialRegion[],
+): Promise<void> {
+  return invoke("set_material_regions", { regions });
+}
+
+// A region covering `element`, e.g. a sidebar, where it is now
+export function regionFor(
+  element: Element,
+  material: Material,
+): MaterialRegion {
+  const rect = element.getBoundingClientRect();
+  return {
+    x: rect.left,
+    y: rect.top,
+    width: rect.width,
+    height: rect.height,
+    material,
+  };
+}
@@ -0,0 +1,116 @@
+import { invoke } from "@tauri-apps/api/core";
+import { LogicalPosition } from "@tauri-apps/api/dpi";
+import { isMac } from "../lib/platform";
+
+export interface MenuItemAccessibility {
+  id: string;
+  // Announced instead of the item text
+  title?: string;
+  help?: string;
+}
+
+// An entry of a native context menu, as showContextMenu takes it
+export type ContextMenuItem =
+  | {
+      kind: "item";
+      id: string;
+      title: string;
+      enabled?: boolean;
+      // Shown with a checkmark column when set
+      checked?: boolean;
+      // Shortcut hint shown next to the title, e.g. "CmdOrCtrl+D"
+      accelerator?: string;
+      // Announced instead of the title
+      accessibilityTitle?: string;
+      help?: string;
+    }
+  | {
+      kind: "submenu";
+      title: string;
+      enabled?: boolean;
+      items: ContextMenuItem[];
+    }
+  | { kind: "separator" };
+
+export type AccessibleMenuEntry =
+  | {
+      text: string;
+      action?: () => void | Promise<void>;
+      enabled?: boolean;
+      checked?: boolean;
+      accelerator?: string;
+      accessibilityTitle?: string;
+      help?: string;
+    }
+  | { text: string; enabled?: boolean; submenu: AccessibleMenuEntry[] }
+  | "separator";
+
+// Descriptions are read when the native item is created, so they have to be
+// registered before the item goes into a menu
+export async function setMenuAccessibility(
+  items: MenuItemAccessibility[],
+): Promise<void> {
+  return invoke("set_menu_accessibility", { items });
+}
+
+export async function clearMenuAccessibility(ids: string[]): Promise<void> {
+  return invoke("clear_menu_accessibility", { ids });
+}
+
+// Pop up a native menu over the current window at `at`, measured from the
+// top left of the window's content (the mouse cursor if omitted). Resolves
+// to the id of the item picked, or null if the menu was dismissed. Unlike an
+// HTML menu it can extend past the window.
+export async function showContextMenu(
+  items: ContextMenuItem[],
+  at?: LogicalPosition,
+): Promise<string | null> {
+  const position = at ? { x: at.x, y: at.y } : null;
+  return invoke("show_context_menu", { items, position });
+}
+
+// Pop up a native context menu whose items carry screen reader descriptions
+// and run the action of the one picked. Use "&" in item text to mark the
+// access key on Windows and Linux; macOS drops it.
+export async function popupMenu(
+  entries: AccessibleMenuEntry[],
+  at?: LogicalPosition,
+): Promise<void> {
+  const actions = new Map<string, () => void | Promise<void>>();
+  const toItems = (entries: AccessibleMenuEntry[]): ContextMenuItem[] =>
+    entries.map((entry): ContextMenuItem => {
+      if (entry === "separator") {
+        return { kind: "separator" };
+      }
+      if ("submenu" in entry) {
+        return {
+          kind: "submenu",
+          title: entry.text,
+          enabled: entry.enabled,
+          items: toItems(entry.submenu),
+        };
+      }
+      const { text, action, ...options } = entry;
+      const id = String(actions.size);
+      actions.set(id, action ?? (() => {}));
+      return { kind: "item", id, title: text, ...options };
+    });
+
+  const chosen = await showContextMenu(toItems(entries), at);
+  if (chosen !== null) {
+    await actions.get(chosen)?.();
+  }
+}
+
+// Position for a keyboard-opened menu: just below the element
+export function menuPositionFor(element: Element): LogicalPosition {
+  const rect = element.getBoundingClientRect();
+  return new LogicalPosition(rect.left + 8, rect.bottom);
+}
+
+// Windows and Linux webviews turn Shift+F10 and the Menu key into a
+// contextmenu event on the focused element. WebKit on macOS doesn't, so
+// Shift+F10 is handled by hand there.
+export function isContextMenuShortcut(e: KeyboardEvent | React.KeyboardEvent) {
+  return isMac && e.shiftKey && e.key === "F10";
+}
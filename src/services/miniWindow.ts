@@ -0,0 +1,44 @@
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+
+// The mini window loads index.html?mini=<note id>
+export function getMiniNoteId(): string | null {
+  return new URLSearchParams(window.location.search).get("mini");
+}
+
+export function isMiniWindow(): boolean {
+  return getMiniNoteId() !== null;
+}
+
+// Move a note between the main window and the always-on-top mini window
+export async function swapMiniMode(noteId: string): Promise<void> {
+  return invoke("swap_mini_mode", { noteId });
+}
+
+// Warm windows load index.html?warm and wait, hidden, to become the mini
+// window. Opening the mini window claims one instead of starting a webview.
+export function isWarmWindow(): boolean {
+  return new URLSearchParams(window.location.search).has("warm");
+}
+
+// Called with the note ID once this warm window is claimed. The backend only
+// claims windows that have reported ready.
+export async function onWarmClaim(handler: (noteId: string) => void): Promise<UnlistenFn> {
+  const unlisten = await getCurrentWindow().listen<string>("warm-claim", (event) => {
+    // From here on this is the mini window for that note
+    window.history.replaceState(null, "", `?mini=${encodeURIComponent(event.payload)}`);
+    handler(event.payload);
+  });
+  await invoke("webview_pool_ready");
+  return unlisten;
+}
+
+// Warm windows kept ready; 0 turns the pool off
+export async function getWebviewPoolSize(): Promise<number> {
+  return invoke("get_webview_pool_size");
+}
+
+export async function setWebviewPoolSize(size: number): Promise<void> {
+  return invoke("set_webview_pool_size", { size });
+}
@@ -0,0 +1,19 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// Monitors are named the way availableMonitors() names them. "changed"
+// covers resolution, scale factor and arrangement changes.
+export type MonitorChange =
+  | { kind: "added"; name: string | null }
+  | { kind: "removed"; name: string | null }
+  | { kind: "changed"; name: string | null };
+
+// A display was connected, disconnected or rearranged, e.g. by docking or
+// undocking a laptop. Windows left off-screen have already been pulled back
+// by the time this arrives. Sent to every window.
+export function onMonitorsChanged(
+  handler: (change: MonitorChange) => void,
+): Promise<UnlistenFn> {
+  return listen<MonitorChange>("monitors-changed", (event) =>
+    handler(event.payload),
+  );
+}
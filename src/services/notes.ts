@@ -1,11 +1,17 @@
 import { invoke } from "@tauri-apps/api/core";
+import { invokeLarge } from "../lib/ipc";
+import { expireSnapshot, takeSnapshot } from "../lib/snapshot";
 import type { Note, NoteMetadata, Settings } from "../types/note";
+import type { TrashHandle } from "./trash";
 
 export async function getNotesFolder(): Promise<string | null> {
+  const folder = takeSnapshot("notesFolder");
+  if (folder !== undefined) return folder;
   return invoke("get_notes_folder");
 }
 
 export async function setNotesFolder(path: string): Promise<void> {
+  expireSnapshot();
   return invoke("set_notes_folder", { path });
 }
 
@@ -13,15 +19,22 @@ export async function listNotes(): Promise<NoteMetadata[]> {
   return invoke("list_notes");
 }
 
+// The most recently modified notes as of launch, to show while listNotes
+// runs; null once taken
+export function takeRecentNotes(): NoteMetadata[] | null {
+  return takeSnapshot("recentNotes") ?? null;
+}
+
 export async function readNote(id: string): Promise<Note> {
-  return invoke("read_note", { id });
+  return invokeLarge("read_note", { id });
 }
 
 export async function saveNote(id: string | null, content: string): Promise<Note> {
-  return invoke("save_note", { id, content });
+  return invokeLarge("save_note", { id, content }, content.length);
 }
 
-export async function deleteNote(id: string): Promise<void> {
+// Moves the note to the OS trash; the handle undoes it
+export async function deleteNote(id: string): Promise<TrashHandle | null> {
   return invoke("delete_note", { id });
 }
 
@@ -39,10 +52,13 @@ export async function duplicateNote(id: string): Promise<Note> {
 }
 
 export async function getSettings(): Promise<Settings> {
+  const settings = takeSnapshot("settings");
+  if (settings) return settings;
   return invoke("get_settings");
 }
 
 export async function updateSettings(settings: Settings): Promise<void> {
+  expireSnapshot();
   return invoke("update_settings", { newSettings: settings });
 }
 
@@ -0,0 +1,63 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export interface NotificationAction {
+  id: string;
+  title: string;
+  // Shown in red on macOS
+  destructive?: boolean;
+}
+
+// A text field with the button that sends it. Not available on Linux.
+export interface ReplyField {
+  id: string;
+  title: string;
+  placeholder?: string;
+}
+
+export interface NativeNotification {
+  // Showing another notification with the same id replaces it
+  id: string;
+  title: string;
+  subtitle?: string;
+  body?: string;
+  actions?: NotificationAction[];
+  reply?: ReplyField;
+  // Image paths; Windows and Linux show only the first
+  attachments?: string[];
+}
+
+export type NotificationResponseKind =
+  | "activated"
+  | "action"
+  | "reply"
+  | "dismissed";
+
+// Notifications about finished background jobs have ids starting with
+// "job:" followed by the job id
+export interface NotificationResponse {
+  id: string;
+  kind: NotificationResponseKind;
+  // The action or reply field's id
+  actionId: string | null;
+  text: string | null;
+}
+
+// On macOS the user is asked to allow notifications the first time
+export async function showNotification(
+  notification: NativeNotification,
+): Promise<void> {
+  return invoke("show_notification", { notification });
+}
+
+export async function removeNotification(id: string): Promise<void> {
+  return invoke("remove_notification", { id });
+}
+
+export function onNotificationResponse(
+  handler: (response: NotificationResponse) => void,
+): Promise<UnlistenFn> {
+  return listen<NotificationResponse>("notification-response", (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,42 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+
+interface Throttling {
+  throttled: boolean;
+}
+
+// Whether animation pauses while every window is hidden behind others;
+// null where occlusion isn't reported (everywhere but macOS)
+export async function getRenderThrottling(): Promise<boolean | null> {
+  return invoke("get_render_throttling");
+}
+
+export async function setRenderThrottling(enabled: boolean): Promise<void> {
+  return invoke("set_render_throttling", { enabled });
+}
+
+// Whether animation should be paused right now
+export async function isRenderThrottled(): Promise<boolean> {
+  return invoke("is_render_throttled");
+}
+
+// Rendering should pause or pick up again. Sent to every window.
+export function onRenderThrottlingChange(
+  handler: (throttled: boolean) => void,
+): Promise<UnlistenFn> {
+  return listen<Throttling>("render-throttling", (event) =>
+    handler(event.payload.throttled),
+  );
+}
+
+// This window stopped showing, behind other windows, minimized or on
+// another space, or shows again (macOS)
+export function onWindowOcclusionChange(
+  handler: (occluded: boolean) => void,
+): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<boolean>(
+    "window-occlusion",
+    (event) => handler(event.payload),
+  );
+}
@@ -0,0 +1,55 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// PDFs of a window or of HTML, paginated without any dialog. macOS and
+// Windows only; elsewhere these reject.
+
+export type PdfSource =
+  // A window's whole page, this one's unless labelled
+  | { kind: "window"; label?: string }
+  // Loaded in a hidden window that's closed afterwards
+  | { kind: "html"; html: string };
+
+// Sizes are in points, 1/72 inch
+export interface PaperSize {
+  width: number;
+  height: number;
+}
+
+export const PAPER_SIZES = {
+  letter: { width: 612, height: 792 },
+  legal: { width: 612, height: 1008 },
+  a3: { width: 841.89, height: 1190.55 },
+  a4: { width: 595.28, height: 841.89 },
+  a5: { width: 419.53, height: 595.28 },
+} satisfies Record<string, PaperSize>;
+
+export interface PdfOptions {
+  // The system's paper and margins when not given
+  paperSize?: PaperSize;
+  margins?: { top: number; right: number; bottom: number; left: number };
+  orientation?: "portrait" | "landscape";
+  // The title and date above each page, and its number below
+  headerAndFooter?: boolean;
+  // The title in the header, the page's own if not given
+  title?: string;
+}
+
+// Saves the PDF at `output` and resolves with its path
+export async function renderToPdf(
+  source: PdfSource,
+  output: string,
+  options: PdfOptions = {},
+): Promise<string> {
+  return invoke("render_to_pdf", { source, options, output });
+}
+
+export async function renderPdfBytes(
+  source: PdfSource,
+  options: PdfOptions = {},
+): Promise<Uint8Array> {
+  const bytes = await invoke<ArrayBuffer>("render_pdf_bytes", {
+    source,
+    options,
+  });
+  return new Uint8Array(bytes);
+}
@@ -0,0 +1,43 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type PermissionKind =
+  | "fullDiskAccess"
+  | "screenRecording"
+  | "accessibility"
+  | "camera"
+  | "microphone";
+
+// Denied permissions can only be turned on by the user, in System Settings
+export type PermissionStatus =
+  | "granted"
+  | "denied"
+  | "notDetermined"
+  | "restricted";
+
+export type PermissionStatuses = Record<PermissionKind, PermissionStatus>;
+
+// Every permission's status. Only macOS asks for these; elsewhere they're
+// all granted.
+export async function getPermissions(): Promise<PermissionStatuses> {
+  return invoke("get_permissions");
+}
+
+// Asks for a permission: the system's prompt for the camera and microphone
+// the first time, otherwise its pane in System Settings. Returns the status
+// before asking; the answer arrives through onPermissionsChange.
+export async function requestPermission(
+  kind: PermissionKind,
+): Promise<PermissionStatus> {
+  return invoke("request_permission", { kind });
+}
+
+// A permission's status changed, noticed when the app is activated or the
+// user answers a prompt. Sent to every window with every status.
+export function onPermissionsChange(
+  handler: (statuses: PermissionStatuses) => void,
+): Promise<UnlistenFn> {
+  return listen<PermissionStatuses>("permissions-changed", (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,26 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type PlacementPolicy =
+  | "cursorMonitor"
+  | "focusedWindowMonitor"
+  | "remembered";
+
+export async function getPlacementPolicy(): Promise<PlacementPolicy> {
+  return invoke("get_placement_policy");
+}
+
+export async function setPlacementPolicy(policy: PlacementPolicy): Promise<void> {
+  return invoke("set_placement_policy", { policy });
+}
+
+export type MoveMode = "keepRelative" | "center" | "maximize" | "fullscreen";
+
+// Move this window onto the monitor with `name`, as availableMonitors()
+// names it. keepRelative keeps the window's offset and its maximized or
+// fullscreen state.
+export async function moveToMonitor(
+  name: string,
+  mode: MoveMode = "keepRelative",
+): Promise<void> {
+  return invoke("move_to_monitor", { name, mode });
+}
@@ -0,0 +1,80 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// WebAssembly plugins from the plugins folder in app data. Each sits in a
+// folder of its own with a plugin.json manifest, and can use only what the
+// capabilities in it allow.
+
+export type PluginCapability = "storage" | "events" | "emit";
+
+export type PluginEvent = "note-saved" | "note-deleted";
+
+export interface PluginCommand {
+  name: string;
+  title: string | null;
+}
+
+export interface PluginManifest {
+  id: string;
+  name: string;
+  version: string;
+  description: string | null;
+  abiVersion: number;
+  // A semver requirement on smudge's version
+  smudgeVersion: string | null;
+  module: string;
+  capabilities: PluginCapability[];
+  commands: PluginCommand[];
+  events: PluginEvent[];
+}
+
+export interface PluginInfo {
+  // The folder's name when the manifest couldn't be read
+  id: string;
+  path: string;
+  manifest: PluginManifest | null;
+  enabled: boolean;
+  loaded: boolean;
+  // Why it isn't loaded, or why its last call failed
+  error: string | null;
+}
+
+export async function listPlugins(): Promise<PluginInfo[]> {
+  return invoke("list_plugins");
+}
+
+// Runs one of the commands the plugin's manifest declares
+export async function invokePlugin<T = unknown>(
+  id: string,
+  command: string,
+  args?: unknown,
+): Promise<T> {
+  return invoke("plugin_invoke", { id, command, args });
+}
+
+export async function setPluginEnabled(
+  id: string,
+  enabled: boolean,
+): Promise<PluginInfo[]> {
+  return invoke("set_plugin_enabled", { id, enabled });
+}
+
+// Picks up plugins added or changed on disk
+export async function reloadPlugins(): Promise<PluginInfo[]> {
+  return invoke("reload_plugins");
+}
+
+export interface PluginEmitted<T = unknown> {
+  plugin: string;
+  name: string;
+  payload: T;
+}
+
+// Events plugins with the emit capability send
+export async function onPluginEvent<T = unknown>(
+  handler: (event: PluginEmitted<T>) => void,
+): Promise<UnlistenFn> {
+  return listen<PluginEmitted<T>>("plugin-event", (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,15 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type PowerChange =
+  | "willSleep"
+  | "didWake"
+  | "screenLocked"
+  | "screenUnlocked";
+
+// The system is about to sleep or woke, or the screen was locked or unlocked.
+// Sent to every window.
+export function onPowerChange(
+  handler: (change: PowerChange) => void,
+): Promise<UnlistenFn> {
+  return listen<PowerChange>("power-event", (event) => handler(event.payload));
+}
@@ -0,0 +1,84 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// App-wide tuning from the preferences file (preferences.toml in the app's
+// config folder), which can also be edited by hand. Notes folders keep
+// their own settings.
+
+export interface Preferences {
+  network: {
+    // "system", "direct", or a proxy URL
+    proxy: string;
+    connectTimeoutSecs: number;
+    readTimeoutSecs: number;
+    requestTimeoutSecs: number;
+    maxResponseMb: number;
+  };
+  websocket: {
+    pingIntervalSecs: number;
+    idleTimeoutSecs: number;
+    maxBackoffSecs: number;
+  };
+  thumbnails: {
+    // Applies at the next launch
+    cacheMb: number;
+  };
+  // What clients of --remote-control may call and subscribe to
+  remoteControl: {
+    methods: string[];
+    events: string[];
+  };
+  // How the web views present themselves to sites
+  webview: {
+    // Replaces the web view's own user agent when not empty
+    userAgent: string;
+    // Language tags, most preferred first; empty follows the system.
+    // Windows only.
+    acceptLanguages: string[];
+  };
+  // Counters and timings kept on this machine for the diagnostics pane;
+  // turning it off throws them away
+  telemetry: {
+    enabled: boolean;
+  };
+  // How editable fields check and substitute text as it's typed; null
+  // follows System Settings
+  spelling: {
+    // "en_GB" on macOS, "en-GB" on Windows; empty is automatic
+    language: string;
+    // macOS only, as are the rest
+    checkWhileTyping: boolean | null;
+    correctAutomatically: boolean | null;
+    smartQuotes: boolean | null;
+    smartDashes: boolean | null;
+    textReplacement: boolean | null;
+  };
+}
+
+export interface PreferencesStatus {
+  values: Preferences;
+  path: string | null;
+  // Why the file couldn't be used; the last good values stay in effect
+  error: string | null;
+}
+
+export async function getPreferences(): Promise<PreferencesStatus> {
+  return invoke("get_preferences");
+}
+
+// Set one value by its dotted key, e.g. "network.proxy", and save the file.
+// Fails while the file has an error.
+export async function setPreference(
+  key: string,
+  value: unknown,
+): Promise<PreferencesStatus> {
+  return invoke("set_preference", { key, value });
+}
+
+export async function onPreferencesChanged(
+  handler: (status: PreferencesStatus) => void,
+): Promise<UnlistenFn> {
+  return listen<PreferencesStatus>("preferences-changed", (event) =>
+    handler(event.payload),
+  );
+}
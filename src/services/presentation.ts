@@ -0,0 +1,25 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface Display {
+  name: string;
+  width: number;
+  height: number;
+  primary: boolean;
+}
+
+export async function listDisplays(): Promise<Display[]> {
+  return invoke("list_displays");
+}
+
+// Present the current window full-screen, on `display` or where it is now
+export async function startPresentation(display?: string): Promise<void> {
+  return invoke("start_presentation", { display: display ?? null });
+}
+
+export async function stopPresentation(): Promise<void> {
+  return invoke("stop_presentation");
+}
+
+export async function isPresenting(): Promise<boolean> {
+  return invoke("is_presenting");
+}
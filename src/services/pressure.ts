@@ -0,0 +1,28 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// Caches are let go of on warning; at critical the system starts ending apps
+export type MemoryPressure = "normal" | "warning" | "critical";
+
+// Background work waits from serious up
+export type ThermalState = "nominal" | "fair" | "serious" | "critical";
+
+export interface Pressure {
+  memory: MemoryPressure;
+  // Always nominal on Windows and Linux, which don't report it
+  thermal: ThermalState;
+}
+
+// Normal until the system has reported otherwise
+export async function currentPressure(): Promise<Pressure> {
+  return invoke("current_pressure");
+}
+
+// Sent to every window whenever memory or thermal pressure changes
+export function onPressureChanged(
+  handler: (pressure: Pressure) => void,
+): Promise<UnlistenFn> {
+  return listen<Pressure>("pressure-changed", (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,76 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type PrintSource = { kind: "window" } | { kind: "pdf"; path: string };
+
+export interface PrintOptions {
+  // Counting from 1, inclusive
+  pages?: { first: number; last: number };
+  orientation?: "portrait" | "landscape";
+  // A printer by name instead of the default one
+  printer?: string;
+  // Print straight to the printer, without a dialog
+  silent?: boolean;
+  jobName?: string;
+  // macOS only: paper and margins in points (1/72 inch), and the job's name
+  // and date above each page with its number below
+  paperSize?: { width: number; height: number };
+  margins?: { top: number; right: number; bottom: number; left: number };
+  headerAndFooter?: boolean;
+}
+
+export type PrintOutcome =
+  | { kind: "printed" }
+  | { kind: "cancelled" }
+  | { kind: "failed"; message: string };
+
+interface PrintCompleted {
+  id: number;
+  outcome: PrintOutcome;
+}
+
+// macOS and Windows only; elsewhere these reject
+
+// Start printing `source` from this window, through the print dialog
+// unless `options.silent`. Resolves with the id onPrintCompleted reports
+// the outcome under.
+export async function startPrint(
+  source: PrintSource,
+  options: PrintOptions = {},
+): Promise<number> {
+  return invoke("print", { source, options });
+}
+
+// A print job from any window ended
+export function onPrintCompleted(
+  handler: (id: number, outcome: PrintOutcome) => void,
+): Promise<UnlistenFn> {
+  return listen<PrintCompleted>("print-completed", (event) =>
+    handler(event.payload.id, event.payload.outcome),
+  );
+}
+
+// Print `source` and resolve once the job was spooled, cancelled or failed
+export async function print(
+  source: PrintSource,
+  options: PrintOptions = {},
+): Promise<PrintOutcome> {
+  // Outcomes can arrive before the id does, so they're kept until it's known
+  const outcomes = new Map<number, PrintOutcome>();
+  let resolve: ((outcome: PrintOutcome) => void) | null = null;
+  let printId: number | null = null;
+  const unlisten = await onPrintCompleted((id, outcome) => {
+    if (id === printId && resolve) resolve(outcome);
+    else outcomes.set(id, outcome);
+  });
+  try {
+    printId = await startPrint(source, options);
+    const early = outcomes.get(printId);
+    if (early) return early;
+    return await new Promise<PrintOutcome>((r) => {
+      resolve = r;
+    });
+  } finally {
+    unlisten();
+  }
+}
@@ -0,0 +1,72 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { Settings } from "../types/note";
+
+// A project is a notes folder, or one packaged as a .smudge bundle, and the
+// windows showing it. The backend serves one project's notes at a time, the
+// focused window's; the other projects' windows keep showing their own.
+export interface ProjectInfo {
+  path: string;
+  name: string;
+  // Where it keeps its settings and other data of its own
+  dataDir: string;
+  bundle: boolean;
+  active: boolean;
+  // Labels of the windows showing it
+  windows: string[];
+}
+
+export interface RecentProject {
+  path: string;
+  name: string;
+}
+
+export interface ProjectsStatus {
+  open: ProjectInfo[];
+  // Most recent first
+  recent: RecentProject[];
+}
+
+export async function listProjects(): Promise<ProjectsStatus> {
+  return invoke("list_projects");
+}
+
+// Makes an empty project at `path`, a bundle if it ends in ".smudge", and
+// opens it in a new window
+export async function createProject(path: string): Promise<ProjectInfo> {
+  return invoke("create_project", { path });
+}
+
+// Opens the project in a window of its own, or switches to it if it's open
+export async function openProject(path: string): Promise<ProjectInfo> {
+  return invoke("open_project", { path });
+}
+
+export async function switchProject(path: string): Promise<ProjectInfo> {
+  return invoke("switch_project", { path });
+}
+
+// Closes the project at `path`, or this window's, and its windows
+export async function closeProject(path?: string): Promise<void> {
+  return invoke("close_project", { path: path ?? null });
+}
+
+// An open project's settings, whether or not it's the active one
+export async function getProjectSettings(path: string): Promise<Settings> {
+  return invoke("get_project_settings", { path });
+}
+
+export async function clearRecentProjects(): Promise<void> {
+  return invoke("clear_recent_projects");
+}
+
+// A project opened, closed or became the active one. A window whose
+// project became active again should reload its notes; one whose project
+// isn't active should leave them be.
+export function onProjectsChange(
+  handler: (status: ProjectsStatus) => void,
+): Promise<UnlistenFn> {
+  return listen<ProjectsStatus>("projects-changed", (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,32 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// What importing a file does with its "downloaded from the internet" marks
+export type QuarantinePolicy = "preserve" | "clear";
+
+export interface QuarantineInfo {
+  // The OS warns before opening it (never set on Linux)
+  quarantined: boolean;
+  agent: string | null;
+  originUrl: string | null;
+  // Windows security zone, 3 being the internet
+  zone: number | null;
+}
+
+export async function getQuarantinePolicy(): Promise<QuarantinePolicy> {
+  return invoke("get_quarantine_policy");
+}
+
+export async function setQuarantinePolicy(
+  policy: QuarantinePolicy,
+): Promise<void> {
+  return invoke("set_quarantine_policy", { policy });
+}
+
+export async function getQuarantineInfo(path: string): Promise<QuarantineInfo> {
+  return invoke("get_quarantine_info", { path });
+}
+
+// Only call after the user agrees; the OS stops warning about the file
+export async function stripQuarantine(path: string): Promise<void> {
+  return invoke("strip_quarantine", { path });
+}
@@ -0,0 +1,44 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import * as assetsService from "./assets";
+
+export type QuickLookChange = "opened" | "closed";
+
+// macOS and Windows only; elsewhere these reject
+
+// Preview files the way the Finder does on space, starting with the first.
+// Replaces what an open preview shows.
+export async function previewFiles(paths: string[]): Promise<void> {
+  return invoke("preview_file", { paths });
+}
+
+export async function closePreview(): Promise<void> {
+  return invoke("close_preview");
+}
+
+// The preview opened or closed
+export function onQuickLookChange(
+  handler: (change: QuickLookChange) => void,
+): Promise<UnlistenFn> {
+  return listen<QuickLookChange>("quick-look", (event) =>
+    handler(event.payload),
+  );
+}
+
+// The file behind an image's asset URL, or null for other images. Older
+// notes have Tauri's asset: URLs.
+export function pathFromAssetUrl(src: string): string | null {
+  const path = assetsService.pathFromAssetUrl(src);
+  if (path !== null) {
+    return path;
+  }
+  let url: URL;
+  try {
+    url = new URL(src);
+  } catch {
+    return null;
+  }
+  const isAsset =
+    url.protocol === "asset:" || url.hostname === "asset.localhost";
+  return isAsset ? decodeURIComponent(url.pathname.slice(1)) : null;
+}
@@ -0,0 +1,25 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// Work that has to finish before the app quits, e.g. the editor's pending
+// save. A flusher that throws means something may be lost.
+type Flusher = () => Promise<void>;
+
+const flushers = new Set<Flusher>();
+
+export function beforeQuit(flush: Flusher): () => void {
+  flushers.add(flush);
+  return () => {
+    flushers.delete(flush);
+  };
+}
+
+// Answer the backend's "quit-requested" (Quit, Cmd+Q, logout) once every
+// flusher has run. If any failed, the backend asks the user before quitting.
+export function listenForQuit(): Promise<UnlistenFn> {
+  return listen("quit-requested", async () => {
+    const results = await Promise.allSettled([...flushers].map((flush) => flush()));
+    const saved = results.every((result) => result.status === "fulfilled");
+    await invoke("reply_to_quit", { saved });
+  });
+}
@@ -0,0 +1,8 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Files opened from outside the notes folder are listed in "Open Recent" and
+// the Dock menu (macOS) or the jump list (Windows) as they're opened; this
+// empties those lists
+export async function clearRecentDocuments(): Promise<void> {
+  return invoke("clear_recent_documents");
+}
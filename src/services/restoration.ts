@@ -0,0 +1,35 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// What a window saves to come back to after a relaunch
+export interface RestorableState {
+  noteId: string | null;
+}
+
+export async function getRestoreWindows(): Promise<boolean> {
+  return invoke("get_restore_windows");
+}
+
+export async function setRestoreWindows(enabled: boolean): Promise<void> {
+  return invoke("set_restore_windows", { enabled });
+}
+
+export async function setRestorableState(state: RestorableState): Promise<void> {
+  return invoke("set_restorable_state", { state: JSON.stringify(state) });
+}
+
+// The state saved when the app last quit, once; null if there is none
+export async function takeRestoredState(): Promise<RestorableState | null> {
+  const state = await invoke<string | null>("take_restored_state");
+  if (!state) return null;
+  try {
+    return JSON.parse(state) as RestorableState;
+  } catch {
+    return null;
+  }
+}
+
+// The window was restored after the frontend had loaded
+export function onStateRestored(handler: () => void): Promise<UnlistenFn> {
+  return listen("state-restored", () => handler());
+}
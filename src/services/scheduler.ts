@@ -0,0 +1,60 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export type ScheduledTaskKind = "sync" | "cleanup";
+
+// Critical tasks run on time and keep the app awake; deferrable ones wait
+// while on battery, asleep or locked
+export type Urgency = "critical" | "deferrable";
+
+export interface Schedule {
+  task: ScheduledTaskKind;
+  intervalSecs: number;
+  urgency?: Urgency;
+  // Run once straight away instead of after the first interval
+  immediately?: boolean;
+}
+
+export interface ScheduledTask {
+  id: string;
+  task: ScheduledTaskKind;
+  intervalSecs: number;
+  urgency: Urgency;
+  status: "waiting" | "deferred" | "running";
+  // Milliseconds since the epoch
+  lastRun: number | null;
+  runs: number;
+}
+
+export type TaskOutcome =
+  | { kind: "succeeded"; message: string | null }
+  | { kind: "failed"; message: string };
+
+export interface TaskCompletion {
+  id: string;
+  task: ScheduledTaskKind;
+  outcome: TaskOutcome;
+  durationMs: number;
+}
+
+// Run a task periodically, at most once a minute; resolves to its id
+export async function scheduleTask(schedule: Schedule): Promise<string> {
+  return invoke("schedule_task", { schedule });
+}
+
+export async function cancelScheduledTask(id: string): Promise<void> {
+  return invoke("cancel_scheduled_task", { id });
+}
+
+export async function listScheduledTasks(): Promise<ScheduledTask[]> {
+  return invoke("list_scheduled_tasks");
+}
+
+// A scheduled task finished a run. Sent to every window.
+export function onTaskCompleted(
+  handler: (completion: TaskCompletion) => void,
+): Promise<UnlistenFn> {
+  return listen<TaskCompletion>("task-completed", (event) =>
+    handler(event.payload),
+  );
+}
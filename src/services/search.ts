@@ -0,0 +1,49 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Everything is optional; times are seconds since the epoch, inclusive
+export interface SearchFilters {
+  modifiedAfter?: number;
+  modifiedBefore?: number;
+  titleOnly?: boolean;
+  limit?: number;
+}
+
+export interface SearchHit {
+  id: string;
+  title: string;
+  modified: number;
+  score: number;
+  // The best matching passage, or the note's preview if only its title
+  // matched
+  snippet: string;
+  // [start, end) of each matched word in snippet, as string indices
+  highlights: [number, number][];
+}
+
+// Ranked search of the notes folder's index. Fails while the index isn't
+// ready, unlike notesService.searchNotes.
+export async function search(
+  query: string,
+  filters?: SearchFilters,
+): Promise<SearchHit[]> {
+  return invoke("search", { query, filters });
+}
+
+// Splits a hit's snippet into runs, marking the matched ones
+export function highlightRuns(
+  hit: SearchHit,
+): { text: string; matched: boolean }[] {
+  const runs: { text: string; matched: boolean }[] = [];
+  let at = 0;
+  for (const [start, end] of hit.highlights) {
+    if (start > at) {
+      runs.push({ text: hit.snippet.slice(at, start), matched: false });
+    }
+    runs.push({ text: hit.snippet.slice(start, end), matched: true });
+    at = end;
+  }
+  if (at < hit.snippet.length) {
+    runs.push({ text: hit.snippet.slice(at), matched: false });
+  }
+  return runs;
+}
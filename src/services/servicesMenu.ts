@@ -0,0 +1,25 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// What another app sent with "New Smudge Note" in its Services menu (macOS)
+export interface ServiceRequest {
+  text: string | null;
+  files: string[];
+}
+
+export function onServiceRequest(
+  handler: (request: ServiceRequest) => void,
+): Promise<UnlistenFn> {
+  return listen<ServiceRequest>("service-request", (event) =>
+    handler(event.payload),
+  );
+}
+
+// Runs another app's service, named as in the Services menu, on text and
+// returns the text it sent back (macOS)
+export async function performService(
+  menuItem: string,
+  text: string,
+): Promise<string | null> {
+  return invoke("perform_service", { menuItem, text });
+}
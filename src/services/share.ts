@@ -0,0 +1,68 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { LogicalPosition } from "@tauri-apps/api/dpi";
+
+export type ShareItem =
+  | { kind: "url"; url: string }
+  | { kind: "text"; text: string }
+  | { kind: "file"; path: string };
+
+export type ShareOutcome =
+  | { kind: "shared"; service: string | null }
+  | { kind: "cancelled" }
+  | { kind: "failed"; message: string };
+
+interface ShareCompleted {
+  id: number;
+  outcome: ShareOutcome;
+}
+
+// macOS and Windows only; elsewhere these reject
+
+// Show the OS share sheet for `items`, next to `position` in the window on
+// macOS; Windows places its own. Resolves with the id onShareCompleted
+// reports the outcome under.
+export async function shareItems(
+  items: ShareItem[],
+  position: LogicalPosition,
+): Promise<number> {
+  return invoke("share_items", {
+    items,
+    position: { x: position.x, y: position.y },
+  });
+}
+
+// A share from any window ended
+export function onShareCompleted(
+  handler: (id: number, outcome: ShareOutcome) => void,
+): Promise<UnlistenFn> {
+  return listen<ShareCompleted>("share-completed", (event) =>
+    handler(event.payload.id, event.payload.outcome),
+  );
+}
+
+// Share `items` and resolve once the user has shared or dismissed them.
+// Windows before 10 2004 doesn't report dismissal, so it may never resolve.
+export async function share(
+  items: ShareItem[],
+  position: LogicalPosition,
+): Promise<ShareOutcome> {
+  // Outcomes can arrive before the id does, so they're kept until it's known
+  const outcomes = new Map<number, ShareOutcome>();
+  let resolve: ((outcome: ShareOutcome) => void) | null = null;
+  let shareId: number | null = null;
+  const unlisten = await onShareCompleted((id, outcome) => {
+    if (id === shareId && resolve) resolve(outcome);
+    else outcomes.set(id, outcome);
+  });
+  try {
+    shareId = await shareItems(items, position);
+    const early = outcomes.get(shareId);
+    if (early) return early;
+    return await new Promise<ShareOutcome>((r) => {
+      resolve = r;
+    });
+  } finally {
+    unlisten();
+  }
+}
@@ -0,0 +1,47 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// What a shortcut asks for, from the Shortcuts app (macOS) or an
+// x-callback-url link such as
+// smudge://x-callback-url/create-note?title=Groceries. Anything left out is
+// asked of the user.
+export type Intent =
+  | { action: "createNote"; title: string | null; text: string | null }
+  | { action: "openProject"; folder: string | null }
+  | {
+      action: "exportSelection";
+      format: "html" | "markdown" | null;
+      destination: string | null;
+    };
+
+export interface IntentInvocation {
+  id: number;
+  intent: Intent;
+}
+
+// How an intent ended. Values are handed back to the shortcut that ran it.
+export type IntentCompletion =
+  | { outcome: "success"; values?: Record<string, string> }
+  | { outcome: "error"; message: string }
+  | { outcome: "cancel" };
+
+// Intents that arrived before the frontend was listening, or since
+// onIntentReceived last fired. Each is handed out once.
+export async function getPendingIntents(): Promise<IntentInvocation[]> {
+  return invoke("get_pending_intents");
+}
+
+// Every invocation must be completed, even if only as cancelled
+export async function completeIntent(
+  id: number,
+  completion: IntentCompletion,
+): Promise<void> {
+  return invoke("complete_intent", { id, completion });
+}
+
+export function onIntentReceived(handler: () => void): Promise<UnlistenFn> {
+  return listen("intent-received", () => handler());
+}
+
+// The note list exports its own selection, so App hands it these
+export const EXPORT_SELECTION_EVENT = "export-selection-intent";
@@ -0,0 +1,55 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// Helper executables bundled in the app's resources, such as the language
+// tool. Each is supervised: restarted with backoff when it exits, given up
+// on after exiting ten times in a row soon after starting, and ended when
+// the app quits.
+
+export interface SidecarStatus {
+  name: string;
+  // null between restarts
+  pid: number | null;
+  restarts: number;
+}
+
+export type SidecarEvent =
+  | { kind: "started"; pid: number }
+  | { kind: "stdout"; line: string }
+  | { kind: "stderr"; line: string }
+  // code is null when a signal ended it
+  | { kind: "exited"; code: number | null }
+  | { kind: "restarting"; delayMs: number; attempt: number }
+  | { kind: "failed"; message: string }
+  | { kind: "stopped" };
+
+export async function startSidecar(
+  name: string,
+  args: string[] = [],
+): Promise<SidecarStatus> {
+  return invoke("start_sidecar", { name, args });
+}
+
+// Closes its stdin, and kills it if it hasn't exited a moment later
+export async function stopSidecar(name: string): Promise<void> {
+  return invoke("stop_sidecar", { name });
+}
+
+// Fails while it's restarting; add the newline a line-based helper expects
+export async function writeSidecar(name: string, data: string): Promise<void> {
+  return invoke("write_sidecar_stdin", { name, data });
+}
+
+export async function listSidecars(): Promise<SidecarStatus[]> {
+  return invoke("list_sidecars");
+}
+
+// Output arrives a line at a time, without the line ending
+export function onSidecarEvent(
+  name: string,
+  handler: (event: SidecarEvent) => void,
+): Promise<UnlistenFn> {
+  return listen<SidecarEvent>(`sidecar://${name}`, (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,61 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// How a window takes part in Spaces, Mission Control, Stage Manager and full
+// screen. Of each group, at most one should be given.
+export type CollectionBehavior =
+  | "canJoinAllSpaces"
+  | "moveToActiveSpace"
+  | "managed"
+  | "transient"
+  | "stationary"
+  | "participatesInCycle"
+  | "ignoresCycle"
+  | "fullScreenPrimary"
+  | "fullScreenAuxiliary"
+  | "fullScreenNone"
+  // Stage Manager
+  | "primary"
+  | "auxiliary"
+  | "canJoinAllApplications";
+
+// Replaces this window's behavior. Note windows are managed document windows
+// and the mini window a palette on every Space by default. macOS only.
+export async function setCollectionBehavior(
+  behaviors: CollectionBehavior[],
+): Promise<void> {
+  return invoke("set_collection_behavior", { behaviors });
+}
+
+export interface Space {
+  // On the Space or virtual desktop being shown
+  onActiveSpace: boolean;
+  // The virtual desktop's id; Windows only
+  desktop: string | null;
+}
+
+export async function getWindowSpace(): Promise<Space> {
+  return invoke("get_window_space");
+}
+
+// Full screen in its own Space on macOS, as the green button does
+export async function setFullScreen(fullScreen: boolean): Promise<void> {
+  return invoke("set_window_full_screen", { fullScreen });
+}
+
+// Put two windows side by side, each filling half of the screen `left` is
+// on, e.g. for the compare view. Apps can't start Split View or Snap Assist
+// themselves, so this is their layout without the shared full screen Space.
+export async function tileWindows(left: string, right: string): Promise<void> {
+  return invoke("tile_windows", { left, right });
+}
+
+// A window moved to another Space or desktop, or the user switched to one
+// it's on or off. Sent to every window.
+export function onSpaceChange(
+  handler: (label: string, space: Space) => void,
+): Promise<UnlistenFn> {
+  return listen<{ label: string; space: Space }>("space-changed", (event) =>
+    handler(event.payload.label, event.payload.space),
+  );
+}
@@ -0,0 +1,36 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// The checker's language, whether editable fields underline misspellings
+// and which substitutions they make as the user types are preferences
+// (the spelling section in preferences.ts).
+
+export interface Misspelling {
+  // In UTF-16 code units, so text.slice(start, start + length) is the word
+  start: number;
+  length: number;
+  word: string;
+  // The likeliest first
+  suggestions: string[];
+}
+
+// The misspelled words in `text`, from the system's spell checker with the
+// words the user taught it. `language` defaults to the preferred one.
+export async function checkSpelling(
+  text: string,
+  language?: string,
+): Promise<Misspelling[]> {
+  return invoke("check_spelling", { text, language });
+}
+
+export async function spellingSuggestions(
+  word: string,
+  language?: string,
+): Promise<string[]> {
+  return invoke("spelling_suggestions", { word, language });
+}
+
+// The languages there are dictionaries for: "en_GB" on macOS, "en-GB" on
+// Windows
+export async function spellingLanguages(): Promise<string[]> {
+  return invoke("spelling_languages");
+}
@@ -0,0 +1,25 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Notes are indexed by the backend as they're listed and saved; these are
+// for anything else worth finding. Choosing a result opens its url, so it
+// should be a smudge:// or universal link. No-ops off macOS.
+
+export interface SearchableItem {
+  url: string;
+  title: string;
+  description?: string;
+  keywords?: string[];
+  // An image file shown with the result; it has to stay where it is
+  thumbnail?: string;
+  domain?: string;
+}
+
+export async function indexSearchableItems(
+  items: SearchableItem[],
+): Promise<void> {
+  return invoke("index_searchable_items", { items });
+}
+
+export async function removeSearchableItems(urls: string[]): Promise<void> {
+  return invoke("remove_searchable_items", { urls });
+}
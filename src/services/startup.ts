@@ -0,0 +1,24 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface StartupSpan {
+  name: string;
+  startMs: number;
+  // null while the phase is still running
+  endMs: number | null;
+}
+
+export interface StartupReport {
+  spans: StartupSpan[];
+  // Launch to first paint; null until the main window has painted
+  totalMs: number | null;
+}
+
+// Mark the main window's first frame, ending the launch timeline
+export async function recordFirstPaint(): Promise<void> {
+  return invoke("record_first_paint");
+}
+
+// Where the time went during this launch
+export async function getStartupReport(): Promise<StartupReport> {
+  return invoke("startup_report");
+}
@@ -0,0 +1,29 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type StorageProviderKind = "local" | "iCloudDrive" | "oneDrive" | "webDav";
+
+export interface StorageInfo {
+  kind: StorageProviderKind;
+  name: string;
+  supportsEviction: boolean;
+}
+
+export interface DownloadProgress {
+  path: string;
+  downloaded: number | null;
+  total: number;
+  done: boolean;
+  error: string | null;
+}
+
+export async function getStorageInfo(): Promise<StorageInfo> {
+  return invoke("get_storage_info");
+}
+
+export async function downloadNote(id: string): Promise<void> {
+  return invoke("download_note", { id });
+}
+
+export async function evictNote(id: string): Promise<void> {
+  return invoke("evict_note", { id });
+}
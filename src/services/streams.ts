@@ -0,0 +1,80 @@
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
+
+export type StreamSource =
+  | { kind: "note"; id: string }
+  // A file in the notes folder, e.g. "assets/diagram.png"
+  | { kind: "file"; path: string }
+  // The end of the log, and with follow every line written after it
+  | { kind: "log"; follow?: boolean };
+
+export interface StreamInfo {
+  id: string;
+  // Bytes the stream will deliver, when that's known up front
+  size: number | null;
+  contentType: string;
+}
+
+// Start streaming a source; read it with readStream
+export async function openStream(source: StreamSource): Promise<StreamInfo> {
+  return invoke("open_stream", { source });
+}
+
+// Stop a stream early. Streams close themselves once read to the end, and
+// when the window that opened them closes.
+export async function closeStream(id: string): Promise<void> {
+  return invoke("close_stream", { id });
+}
+
+// Hand each chunk to onChunk as it arrives, until the stream ends or the
+// signal aborts. The next chunk isn't asked for until onChunk returns, so a
+// slow reader holds the backend back rather than piling up data.
+export async function readStream(
+  stream: StreamInfo,
+  onChunk: (chunk: Uint8Array) => void | Promise<void>,
+  signal?: AbortSignal,
+): Promise<void> {
+  const url = convertFileSrc(stream.id, "stream");
+  try {
+    for (;;) {
+      const response = await fetch(url, { signal, cache: "no-store" });
+      if (response.status === 204) {
+        return;
+      }
+      if (!response.ok) {
+        throw new Error(await response.text());
+      }
+      await onChunk(new Uint8Array(await response.arrayBuffer()));
+    }
+  } catch (error) {
+    // Aborted, or onChunk gave up: let the backend stop reading
+    await closeStream(stream.id).catch(() => {});
+    throw error;
+  }
+}
+
+// Read a whole stream into a Blob, e.g. for an image
+export async function readStreamAsBlob(
+  stream: StreamInfo,
+  signal?: AbortSignal,
+): Promise<Blob> {
+  const chunks: Uint8Array[] = [];
+  await readStream(stream, (chunk) => void chunks.push(chunk), signal);
+  return new Blob(chunks, { type: stream.contentType });
+}
+
+// Read a whole stream as text, e.g. a large note
+export async function readStreamAsText(
+  stream: StreamInfo,
+  signal?: AbortSignal,
+): Promise<string> {
+  const decoder = new TextDecoder();
+  let text = "";
+  await readStream(
+    stream,
+    (chunk) => {
+      text += decoder.decode(chunk, { stream: true });
+    },
+    signal,
+  );
+  return text + decoder.decode();
+}
@@ -0,0 +1,89 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// The notes folder mirrored against a sync server. The server's token is
+// the "sync-token" credential.
+export interface SyncConfig {
+  enabled: boolean;
+  // The sync API's base URL, e.g. https://sync.example.com/v1
+  serverUrl: string;
+  intervalSecs: number;
+}
+
+export type SyncPhase =
+  | "off"
+  | "idle"
+  | "downloading"
+  | "uploading"
+  | "offline"
+  | "failed";
+
+export interface SyncStatus {
+  phase: SyncPhase;
+  // Notes handled of the current phase's total
+  done: number;
+  total: number;
+  // Local changes waiting to be sent
+  pending: number;
+  conflicts: number;
+  // Milliseconds since the epoch
+  lastSynced: number | null;
+  error: string | null;
+  // When a failed sync is retried, in milliseconds since the epoch
+  retryAt: number | null;
+}
+
+// A note changed on both sides whose changes overlap. Nulls are where it
+// was deleted, or for base, new on both sides.
+export interface SyncConflict {
+  id: string;
+  base: string | null;
+  local: string | null;
+  remote: string | null;
+  remoteRevision: number;
+  // Both sides with conflict markers around the overlaps, to start from
+  merged: string;
+}
+
+export async function getSyncConfig(): Promise<SyncConfig> {
+  return invoke("get_sync_config");
+}
+
+// Changing the server starts over with it
+export async function setSyncConfig(config: SyncConfig): Promise<void> {
+  return invoke("set_sync_config", { config });
+}
+
+export async function getSyncStatus(): Promise<SyncStatus> {
+  return invoke("get_sync_status");
+}
+
+export async function syncNow(): Promise<void> {
+  return invoke("sync_now");
+}
+
+export async function getSyncConflicts(): Promise<SyncConflict[]> {
+  return invoke("get_sync_conflicts");
+}
+
+// Keep content for the note, or delete it with null
+export async function resolveSyncConflict(
+  id: string,
+  content: string | null,
+): Promise<void> {
+  return invoke("resolve_sync_conflict", { id, content });
+}
+
+export function onSyncProgress(
+  handler: (status: SyncStatus) => void,
+): Promise<UnlistenFn> {
+  return listen<SyncStatus>("sync-progress", (event) => handler(event.payload));
+}
+
+export function onSyncConflict(
+  handler: (conflict: SyncConflict) => void,
+): Promise<UnlistenFn> {
+  return listen<SyncConflict>("sync-conflict", (event) =>
+    handler(event.payload),
+  );
+}
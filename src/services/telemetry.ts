@@ -0,0 +1,45 @@
+import { invoke } from "@tauri-apps/api/core";
+import { setPreference } from "./preferences";
+
+// Anonymous counters and timings for About → Diagnostics: launch phases,
+// how long note commands take, and how jobs end. Off unless turned on, and
+// kept on this machine; nothing leaves it but an export the user saves.
+
+export interface TimingSummary {
+  count: number;
+  meanMs: number;
+  minMs: number;
+  maxMs: number;
+  // Percentiles are the bounds of the buckets they fall in
+  p50Ms: number;
+  p90Ms: number;
+  p99Ms: number;
+}
+
+export interface TelemetryReport {
+  enabled: boolean;
+  // Milliseconds since the Unix epoch; 0 when nothing has been counted
+  since: number;
+  // e.g. "job.convert.failed"
+  counters: Record<string, number>;
+  // e.g. "startup.launch", "command.saveNote", "job.batch"
+  timings: Record<string, TimingSummary>;
+}
+
+export async function getTelemetry(): Promise<TelemetryReport> {
+  return invoke("get_telemetry");
+}
+
+export async function setTelemetryEnabled(enabled: boolean): Promise<void> {
+  await setPreference("telemetry.enabled", enabled);
+}
+
+export async function resetTelemetry(): Promise<void> {
+  return invoke("reset_telemetry");
+}
+
+// Saves the report as JSON for a bug report. It names the app's version and
+// the OS, but nothing about notes, the machine or the user.
+export async function exportTelemetry(path: string): Promise<void> {
+  return invoke("export_telemetry", { path });
+}
@@ -0,0 +1,44 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { Note } from "../types/note";
+
+// Document templates: the ones Smudge ships with, and the user's own in the
+// templates folder. A user template named like a built-in replaces it.
+export interface Template {
+  // The file's name without ".md"
+  id: string;
+  name: string;
+  source: "builtIn" | "user";
+  // The {{variables}} its content uses, in order
+  variables: string[];
+}
+
+export async function listTemplates(): Promise<Template[]> {
+  return invoke("list_templates");
+}
+
+// Where the user's templates go, created if it isn't there yet
+export async function templatesFolder(): Promise<string> {
+  return invoke("templates_folder");
+}
+
+function pad(value: number): string {
+  return String(value).padStart(2, "0");
+}
+
+// Creates a note from the template with its {{variables}} filled in.
+// "date" and "time" default to now, in local time, and "title" to Untitled;
+// variables left out are removed.
+export async function createNoteFromTemplate(
+  template: string,
+  variables: Record<string, string> = {},
+): Promise<Note> {
+  const now = new Date();
+  const defaults = {
+    date: `${now.getFullYear()}-${pad(now.getMonth() + 1)}-${pad(now.getDate())}`,
+    time: `${pad(now.getHours())}:${pad(now.getMinutes())}`,
+  };
+  return invoke("create_note_from_template", {
+    template,
+    variables: { ...defaults, ...variables },
+  });
+}
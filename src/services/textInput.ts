@@ -0,0 +1,23 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// Open the system's emoji and symbols picker for the focused field. On
+// Windows this is the emoji panel (Windows+.).
+export async function showCharacterPalette(): Promise<void> {
+  return invoke("show_character_palette");
+}
+
+// Start dictating into the focused field. On Windows this is voice typing
+// (Windows+H). Fails on macOS when dictation is off in System Settings.
+export async function startDictation(): Promise<void> {
+  return invoke("start_dictation");
+}
+
+// An input method started or finished composing text in this window
+export function onCompositionChange(
+  handler: (composing: boolean) => void,
+): Promise<UnlistenFn> {
+  return listen<{ composing: boolean }>("composition-changed", (event) =>
+    handler(event.payload.composing),
+  );
+}
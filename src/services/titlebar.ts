@@ -0,0 +1,127 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { isMac } from "../lib/platform";
+import { takeSnapshot } from "../lib/snapshot";
+
+export type TitlebarMode = "native" | "custom";
+
+export interface TitlebarInfo {
+  mode: TitlebarMode;
+  height: number;
+  // Space kept clear for the traffic lights (macOS, left) or the caption
+  // buttons (Windows/Linux, right)
+  controlsInset: number;
+  drawsControls: boolean;
+}
+
+const DRAG_REGION_ATTR = "data-tauri-drag-region";
+
+export async function getTitlebarInfo(): Promise<TitlebarInfo> {
+  return takeSnapshot("titlebar") ?? invoke<TitlebarInfo>("get_titlebar_info");
+}
+
+export async function setTitlebarMode(mode: TitlebarMode): Promise<TitlebarInfo> {
+  const info = await invoke<TitlebarInfo>("set_titlebar_mode", { mode });
+  applyTitlebarInfo(info);
+  window.dispatchEvent(new CustomEvent("titlebar-changed", { detail: info }));
+  return info;
+}
+
+// Expose the controls inset to CSS so headers can pad around the controls
+export function applyTitlebarInfo(info: TitlebarInfo) {
+  const inset = info.mode === "custom" ? info.controlsInset : 0;
+  const root = document.documentElement.style;
+  root.setProperty("--titlebar-height", `${info.height}px`);
+  root.setProperty("--titlebar-inset-left", `${isMac ? inset : 0}px`);
+  root.setProperty("--titlebar-inset-right", `${isMac ? 0 : inset}px`);
+}
+
+function isDragRegion(target: EventTarget | null): boolean {
+  if (!(target instanceof Element)) return false;
+  const attr = target.getAttribute(DRAG_REGION_ATTR);
+  return attr !== null && attr !== "false";
+}
+
+// Tauri's drag script always toggles maximize on a double-click. Intercept
+// it first (window capture runs before its document listeners) so the
+// backend can honour the OS preference instead, e.g. "Minimize" on macOS.
+export function installTitlebarDoubleClick(): () => void {
+  let downX = 0;
+  let downY = 0;
+
+  const onMouseDown = (e: MouseEvent) => {
+    if (e.button !== 0 || e.detail !== 2 || !isDragRegion(e.target)) return;
+    e.preventDefault();
+    e.stopImmediatePropagation();
+    if (isMac) {
+      // macOS acts on mouseup, and only if the pointer didn't move
+      downX = e.clientX;
+      downY = e.clientY;
+      return;
+    }
+    invoke("titlebar_double_click").catch(console.error);
+  };
+
+  const onMouseUp = (e: MouseEvent) => {
+    if (e.button !== 0 || e.detail !== 2 || !isDragRegion(e.target)) return;
+    e.stopImmediatePropagation();
+    if (e.clientX === downX && e.clientY === downY) {
+      invoke("titlebar_double_click").catch(console.error);
+    }
+  };
+
+  window.addEventListener("mousedown", onMouseDown, true);
+  if (isMac) window.addEventListener("mouseup", onMouseUp, true);
+  return () => {
+    window.removeEventListener("mousedown", onMouseDown, true);
+    window.removeEventListener("mouseup", onMouseUp, true);
+  };
+}
+
+// Where the close button's top left corner goes, in CSS pixels from the
+// window's; null gives the traffic lights back to macOS. macOS only.
+export async function setTrafficLightPosition(
+  position: { x: number; y: number } | null,
+): Promise<void> {
+  return invoke("set_traffic_light_position", { position });
+}
+
+// Hides the window title but keeps the traffic lights. macOS only.
+export async function setTitleHidden(hidden: boolean): Promise<void> {
+  return invoke("set_title_hidden", { hidden });
+}
+
+export interface TitlebarButton {
+  id: string;
+  title?: string;
+  // An SF Symbol name, e.g. "sidebar.left"
+  symbol?: string;
+  toolTip?: string;
+  enabled?: boolean;
+}
+
+export interface TitlebarAccessory {
+  // "leading" (after the traffic lights) by default; "bottom" spans the
+  // window under the title bar
+  placement?: "leading" | "trailing" | "bottom";
+  buttons: TitlebarButton[];
+}
+
+// Native buttons in this window's title bar, replacing the ones it had; no
+// accessories take them away. Does nothing outside macOS.
+export async function setTitlebarAccessories(
+  accessories: TitlebarAccessory[],
+): Promise<void> {
+  return invoke("set_titlebar_accessories", { accessories });
+}
+
+// A title bar accessory button in this window was clicked. macOS only.
+export function onTitlebarButton(
+  handler: (id: string) => void,
+): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<{ id: string }>(
+    "titlebar-button",
+    (event) => handler(event.payload.id),
+  );
+}
@@ -0,0 +1,41 @@
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+
+export type TouchBarItem =
+  | { kind: "button"; id: string; title: string; enabled?: boolean }
+  // min and max default to 0 and 1
+  | {
+      kind: "slider";
+      id: string;
+      label?: string;
+      value: number;
+      min?: number;
+      max?: number;
+    }
+  // Buttons of which one is selected at a time
+  | { kind: "segmented"; id: string; labels: string[]; selected?: number }
+  | { kind: "candidates"; id: string; candidates: string[] }
+  // Pushes the items after it to the end
+  | { kind: "flexibleSpace" };
+
+export type TouchBarAction =
+  | { kind: "buttonPressed"; id: string }
+  | { kind: "sliderChanged"; id: string; value: number }
+  | { kind: "segmentSelected"; id: string; index: number }
+  | { kind: "candidateSelected"; id: string; index: number };
+
+// Shows items on the Touch Bar while this window is key, in place of the
+// webview's own; no items take them away. Does nothing without a Touch Bar.
+export async function setTouchBar(items: TouchBarItem[]): Promise<void> {
+  return invoke("set_touch_bar", { items });
+}
+
+// A control on this window's Touch Bar was used. macOS only.
+export function onTouchBarAction(
+  handler: (action: TouchBarAction) => void,
+): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<TouchBarAction>("touch-bar", (event) =>
+    handler(event.payload),
+  );
+}
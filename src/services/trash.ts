@@ -0,0 +1,24 @@
+import { invoke } from "@tauri-apps/api/core";
+import { isMac } from "../lib/platform";
+
+// What the OS calls its trash, for toasts and menu labels
+export const trashName =
+  !isMac && /Windows/.test(navigator.userAgent) ? "Recycle Bin" : "Trash";
+
+// Where a trashed item came from and where it went, for undoing the move
+export interface TrashHandle {
+  original: string;
+  // Null when the OS put it somewhere Smudge can't find
+  trashed: string | null;
+}
+
+// Move files or folders to the OS trash (Trash, Recycle Bin or the
+// freedesktop trash) instead of deleting them
+export async function moveToTrash(paths: string[]): Promise<TrashHandle[]> {
+  return invoke("move_to_trash", { paths });
+}
+
+// Put a trashed item back, returning its restored path
+export async function restoreFromTrash(handle: TrashHandle): Promise<string> {
+  return invoke("restore_from_trash", { handle });
+}
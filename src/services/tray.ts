@@ -0,0 +1,53 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// smudge's icon in the menu bar (macOS) or notification area
+export async function isTrayVisible(): Promise<boolean> {
+  return invoke("get_tray_visible");
+}
+
+// The icon's menu starts empty; set it again once the icon is shown
+export async function setTrayVisible(visible: boolean): Promise<void> {
+  return invoke("set_tray_visible", { visible });
+}
+
+export function onTrayVisibilityChange(
+  handler: (visible: boolean) => void,
+): Promise<UnlistenFn> {
+  return listen<boolean>("tray-visibility-changed", (event) =>
+    handler(event.payload),
+  );
+}
+
+// Items with `checked` set get a checkmark column
+export type TrayMenuItem =
+  | {
+      kind: "item";
+      id: string;
+      title: string;
+      enabled?: boolean;
+      checked?: boolean;
+    }
+  | { kind: "separator" };
+
+// Replaces the icon's menu; does nothing while the icon is hidden
+export async function setTrayMenu(items: TrayMenuItem[]): Promise<void> {
+  return invoke("set_tray_menu", { items });
+}
+
+export type TrayButton = "left" | "right";
+
+// The icon was clicked. A left click has already brought up the main window;
+// a right click opens the menu.
+export function onTrayClick(
+  handler: (button: TrayButton) => void,
+): Promise<UnlistenFn> {
+  return listen<TrayButton>("tray-click", (event) => handler(event.payload));
+}
+
+// The id of the chosen menu item; the main window is already in front
+export function onTrayMenuAction(
+  handler: (id: string) => void,
+): Promise<UnlistenFn> {
+  return listen<string>("tray-menu", (event) => handler(event.payload));
+}
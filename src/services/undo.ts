@@ -0,0 +1,95 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// A patch the caller knows how to apply; the backend only keeps it
+export type UndoPatch = unknown;
+
+export interface UndoChange {
+  // Reverts the change
+  undo: UndoPatch;
+  // Makes it again
+  redo: UndoPatch;
+}
+
+export interface UndoState {
+  // What Undo and Redo would undo and redo, e.g. "Move Card"
+  undoName: string | null;
+  redoName: string | null;
+  // Whether the document differs from when it was last marked saved
+  edited: boolean;
+}
+
+export interface UndoApply {
+  name: string;
+  direction: "undo" | "redo";
+  // In the order to apply them
+  patches: UndoPatch[];
+}
+
+// Record a change this window just made. Outside a group it's undone on
+// its own, as `name`.
+export async function registerUndo(
+  name: string,
+  change: UndoChange,
+): Promise<void> {
+  return invoke("register_undo", { name, change });
+}
+
+export async function beginUndoGroup(name: string): Promise<void> {
+  return invoke("begin_undo_group", { name });
+}
+
+export async function endUndoGroup(): Promise<void> {
+  return invoke("end_undo_group");
+}
+
+// Run `action` as one undoable `name`, whatever it registers
+export async function undoGroup<T>(
+  name: string,
+  action: () => Promise<T>,
+): Promise<T> {
+  await beginUndoGroup(name);
+  try {
+    return await action();
+  } finally {
+    await endUndoGroup();
+  }
+}
+
+// Undo and redo as the Edit menu and Cmd+Z do; the patches arrive with
+// onUndoApply
+export async function undo(): Promise<void> {
+  return invoke("undo");
+}
+
+export async function redo(): Promise<void> {
+  return invoke("redo");
+}
+
+export async function markUndoSaved(): Promise<void> {
+  return invoke("mark_undo_saved");
+}
+
+// Forget this window's history, e.g. when it opens another document
+export async function clearUndo(): Promise<void> {
+  return invoke("clear_undo");
+}
+
+// This window's undo state, kept while the page reloads. Call once as the
+// page loads, so Cmd+Z reaches what was done before.
+export async function restoreUndo(): Promise<UndoState> {
+  return invoke("restore_undo");
+}
+
+// Apply these patches: an action in this window was undone or redone
+export function onUndoApply(
+  handler: (apply: UndoApply) => void,
+): Promise<UnlistenFn> {
+  return listen<UndoApply>("undo-apply", (event) => handler(event.payload));
+}
+
+export function onUndoChange(
+  handler: (state: UndoState) => void,
+): Promise<UnlistenFn> {
+  return listen<UndoState>("undo-changed", (event) => handler(event.payload));
+}
@@ -0,0 +1,71 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+export interface UpdateInfo {
+  version: string;
+  currentVersion: string;
+  body: string | null;
+  // Older than the running version; only returned when forced
+  downgrade: boolean;
+}
+
+// Result of checking the downloaded package's code signature
+export interface Verification {
+  version: string;
+  verified: boolean;
+  signer: string | null;
+  notarized: boolean | null;
+  detail: string;
+}
+
+// Older releases are ignored unless allowDowngrade is set
+export async function checkForUpdate(
+  allowDowngrade = false,
+): Promise<UpdateInfo | null> {
+  return invoke("check_for_update", { allowDowngrade });
+}
+
+export interface UpdateProgress {
+  version: string;
+  downloaded: number;
+  // Null when the server doesn't send a size
+  total: number | null;
+  // A patch against the installed version rather than the whole package
+  delta: boolean;
+}
+
+// Download and verify the update found by the last check, and stage it.
+// Rejects when the package's signature doesn't check out.
+export async function downloadUpdate(): Promise<Verification> {
+  return invoke("download_update");
+}
+
+// Install the staged update, downloading it first if needed. The current
+// version comes back if the new one fails to launch twice.
+export async function installUpdate(): Promise<Verification> {
+  return invoke("install_update");
+}
+
+// Start the installed update; Windows installers restart Smudge themselves
+export async function restartAfterUpdate(): Promise<void> {
+  return invoke("restart_after_update");
+}
+
+// Sent to every window while an update downloads
+export function onUpdateProgress(
+  handler: (progress: UpdateProgress) => void,
+): Promise<UnlistenFn> {
+  return listen<UpdateProgress>("update-progress", (event) =>
+    handler(event.payload),
+  );
+}
+
+// Sent to every window once an update is staged and ready to install
+export function onUpdateReady(
+  handler: (ready: { version: string; verification: Verification }) => void,
+): Promise<UnlistenFn> {
+  return listen<{ version: string; verification: Verification }>(
+    "update-ready",
+    (event) => handler(event.payload),
+  );
+}
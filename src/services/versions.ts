@@ -0,0 +1,48 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { Note } from "../types/note";
+
+// Each note's history: kept as it's saved or changes on disk, thinned as it
+// ages, and on macOS joined by the versions the system keeps of the file
+
+export type VersionSource = "app" | "system";
+
+export interface NoteVersion {
+  // Saves with the same content share an id
+  id: string;
+  // Milliseconds since the Unix epoch
+  savedAt: number;
+  size: number;
+  source: VersionSource;
+  // The computer a system version was saved on, when it was another one
+  computer?: string;
+}
+
+// Newest first. A deleted note's history stays, to bring it back from.
+export async function listVersions(noteId: string): Promise<NoteVersion[]> {
+  return invoke("list_note_versions", { id: noteId });
+}
+
+export async function getVersion(
+  noteId: string,
+  version: string,
+): Promise<string> {
+  return invoke("get_note_version", { id: noteId, version });
+}
+
+// A unified diff from one version to another, or to the note as it is now
+export async function diffVersions(
+  noteId: string,
+  from: string,
+  to: string | null = null,
+): Promise<string> {
+  return invoke("diff_note_versions", { id: noteId, from, to });
+}
+
+// Saves the version's content as the note's; what it replaces becomes a
+// version too, so restoring can be undone
+export async function restoreVersion(
+  noteId: string,
+  version: string,
+): Promise<Note> {
+  return invoke("restore_note_version", { id: noteId, version });
+}
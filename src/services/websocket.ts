@@ -0,0 +1,80 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+
+// WebSocket connections held by the backend, so they survive reloads and
+// reconnect on their own. Give a connection a stable id: after a reload,
+// connecting with it returns the open connection, and replay fills in
+// what arrived in between.
+
+export type ConnectionState = "connecting" | "open" | "reconnecting" | "closed";
+
+// Text as is, binary as base64
+export type Payload =
+  | { type: "text"; data: string }
+  | { type: "binary"; data: string };
+
+export interface ReceivedMessage {
+  // Counts up from 1 over the connection's life, across reconnects
+  seq: number;
+  payload: Payload;
+}
+
+export interface WebSocketStatus {
+  id: string;
+  url: string;
+  state: ConnectionState;
+  // Failed attempts since the connection was last open
+  attempt: number;
+  error: string | null;
+  // seq of the last message received, 0 before the first
+  lastSeq: number;
+}
+
+export type WebSocketEvent =
+  | ({ event: "message" } & ReceivedMessage)
+  | ({ event: "status" } & WebSocketStatus);
+
+// The URL must be ws or wss to a host allowed in http.setAllowedHosts
+export async function connect(
+  url: string,
+  options: { id?: string; headers?: Record<string, string> } = {},
+): Promise<WebSocketStatus> {
+  return invoke("websocket_connect", {
+    url,
+    headers: options.headers,
+    id: options.id,
+  });
+}
+
+// Queued while the connection is down
+export async function send(id: string, payload: Payload): Promise<void> {
+  return invoke("websocket_send", { id, payload });
+}
+
+export async function sendText(id: string, text: string): Promise<void> {
+  return send(id, { type: "text", data: text });
+}
+
+export async function close(id: string): Promise<void> {
+  return invoke("websocket_close", { id });
+}
+
+export async function replay(
+  id: string,
+  afterSeq: number,
+): Promise<ReceivedMessage[]> {
+  return invoke("websocket_replay", { id, afterSeq });
+}
+
+export async function listWebSockets(): Promise<WebSocketStatus[]> {
+  return invoke("list_websockets");
+}
+
+export async function onWebSocketEvent(
+  id: string,
+  handler: (event: WebSocketEvent) => void,
+): Promise<UnlistenFn> {
+  return listen<WebSocketEvent>(`websocket://${id}`, (event) =>
+    handler(event.payload),
+  );
+}
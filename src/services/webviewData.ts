@@ -0,0 +1,69 @@
+import { invoke } from "@tauri-apps/api/core";
+import { setPreference } from "./preferences";
+
+// What sites keep in the web views, which share one store, for "sign out
+// everywhere" and resetting data. The backend's HTTP client keeps its own
+// cookies (clearCookies in http.ts).
+
+export type WebviewDataKind =
+  | "cookies"
+  | "localStorage"
+  | "indexedDb"
+  | "cache"
+  | "other";
+
+export interface SiteData {
+  // The site's domain, e.g. "example.com"
+  site: string;
+  kinds: WebviewDataKind[];
+}
+
+const ALL_KINDS: WebviewDataKind[] = [
+  "cookies",
+  "localStorage",
+  "indexedDb",
+  "cache",
+  "other",
+];
+
+// On Windows and Linux only sites with cookies are listed
+export async function listWebviewData(): Promise<SiteData[]> {
+  return invoke("list_webview_data");
+}
+
+// Remove `kinds` of data for `sites`, or for every site when null. Windows
+// clears a single site's HTTP cache only with every site's; Linux clears
+// only cookies on their own.
+export async function clearWebviewData(
+  sites: string[] | null,
+  kinds: WebviewDataKind[] = ALL_KINDS,
+): Promise<void> {
+  return invoke("clear_webview_data", { sites, kinds });
+}
+
+// Every site's cookies, which signs the web views out everywhere
+export async function signOutEverywhere(): Promise<void> {
+  return clearWebviewData(null, ["cookies"]);
+}
+
+// Write every cookie to `path` as JSON. Resolves with how many there were.
+export async function exportWebviewCookies(path: string): Promise<number> {
+  return invoke("export_webview_cookies", { path });
+}
+
+// Set the cookies in a file exportWebviewCookies wrote
+export async function importWebviewCookies(path: string): Promise<number> {
+  return invoke("import_webview_cookies", { path });
+}
+
+// Replace the web views' user agent, or with "" go back to their own. Not
+// applied on Linux.
+export async function setWebviewUserAgent(userAgent: string): Promise<void> {
+  await setPreference("webview.userAgent", userAgent);
+}
+
+// Language tags for Accept-Language, most preferred first; [] follows the
+// system. Windows only.
+export async function setWebviewLanguages(languages: string[]): Promise<void> {
+  await setPreference("webview.acceptLanguages", languages);
+}
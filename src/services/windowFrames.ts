@@ -0,0 +1,26 @@
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+
+// Latest frame of this window, in physical pixels. The backend coalesces
+// native move and resize events, so these arrive at most once per frame
+// instead of Tauri's per-event onResized/onMoved.
+export interface WindowFrame {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  scaleFactor: number;
+  maximized: boolean;
+}
+
+// While the window is moving or resizing
+export function onWindowFrame(handler: (frame: WindowFrame) => void): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<WindowFrame>("window-frame", (event) => handler(event.payload));
+}
+
+// Once it has stopped, with its final frame
+export function onWindowSettled(handler: (frame: WindowFrame) => void): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<WindowFrame>("window-settled", (event) =>
+    handler(event.payload),
+  );
+}
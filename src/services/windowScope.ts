@@ -0,0 +1,26 @@
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+
+// A file opened from Finder inside this window's scope; noteId is set when
+// it's in the notes folder
+export interface OpenedPath {
+  path: string;
+  noteId: string | null;
+}
+
+// Claims a folder or file for this window, so files opened inside it come
+// here instead of through the usual opened-file handling (macOS). Null gives
+// it up.
+export async function setWindowScope(path: string | null): Promise<void> {
+  return invoke("set_window_scope", { path });
+}
+
+// Files routed to this window; it has already been brought to the front
+export function onPathsOpened(
+  handler: (paths: OpenedPath[]) => void,
+): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<OpenedPath[]>("paths-opened", (event) =>
+    handler(event.payload),
+  );
+}
@@ -0,0 +1,31 @@
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+
+export type WindowTabChange = "selected" | "closed";
+
+// Native window tabs are macOS only; elsewhere these reject
+
+// Add the window labelled `tab` to this window's tab group and select it
+export async function addTabbedWindow(tab: string): Promise<void> {
+  return invoke("add_tabbed_window", { tab });
+}
+
+// Window > Show Next Tab
+export async function selectNextTab(): Promise<void> {
+  return invoke("select_next_tab");
+}
+
+// Window > Merge All Windows, into this window's tab group
+export async function mergeAllWindows(): Promise<void> {
+  return invoke("merge_all_windows");
+}
+
+// This window became the selected tab, or is closing as one of several tabs
+export function onWindowTabChange(
+  handler: (change: WindowTabChange) => void,
+): Promise<UnlistenFn> {
+  return getCurrentWindow().listen<WindowTabChange>("window-tab", (event) =>
+    handler(event.payload),
+  );
+}
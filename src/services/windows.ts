@@ -0,0 +1,77 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { RestorableState } from "./restoration";
+
+// The main window ("main") or a note window ("note-<n>")
+export interface WindowInfo {
+  label: string;
+  focused: boolean;
+  visible: boolean;
+  zoom: number;
+  // The window whose tab group this one is in (macOS)
+  tabGroup: string | null;
+}
+
+// The main window first, then note windows in the order they opened
+export async function listWindows(): Promise<WindowInfo[]> {
+  return invoke("list_windows");
+}
+
+// Open a note window that starts out showing `state`. Resolves with its
+// label.
+export async function openWindow(
+  state: RestorableState | null = null,
+): Promise<string> {
+  return invoke("open_window", {
+    state: state ? JSON.stringify(state) : null,
+  });
+}
+
+export async function closeWindow(label: string): Promise<void> {
+  return invoke("close_window", { label });
+}
+
+export async function focusWindow(label: string): Promise<void> {
+  return invoke("focus_window", { label });
+}
+
+// Zoom this window's content, 1 being actual size, from 0.25 to 5; kept
+// for the next time the window opens
+export async function setWindowZoom(zoom: number): Promise<void> {
+  return invoke("set_window_zoom", { zoom });
+}
+
+export async function getWindowZoom(): Promise<number> {
+  return invoke("get_window_zoom");
+}
+
+// Step through the zoom levels browsers use; these resolve with the new
+// zoom. The View menu's Zoom In, Zoom Out and Actual Size items
+// (CmdOrCtrl+=, CmdOrCtrl+- and CmdOrCtrl+0) do the same.
+export async function zoomIn(): Promise<number> {
+  return invoke("zoom_window_in");
+}
+
+export async function zoomOut(): Promise<number> {
+  return invoke("zoom_window_out");
+}
+
+export async function resetZoom(): Promise<number> {
+  return invoke("reset_window_zoom");
+}
+
+// This window's zoom changed, from the menu or a command
+export function onZoomChange(
+  handler: (zoom: number) => void,
+): Promise<UnlistenFn> {
+  return listen<number>("zoom-changed", (event) => handler(event.payload));
+}
+
+// A window opened or closed. Sent to every window.
+export function onWindowsChange(
+  handler: (windows: WindowInfo[]) => void,
+): Promise<UnlistenFn> {
+  return listen<WindowInfo[]>("windows-changed", (event) =>
+    handler(event.payload),
+  );
+}
@@ -3,6 +3,7 @@ export interface NoteMetadata {
   title: string;
   preview: string;
   modified: number;
+  isPlaceholder?: boolean;
 }
 
 export interface Note {
@@ -32,4 +33,15 @@ export interface Settings {
   editorFont?: EditorFontSettings;
   gitEnabled?: boolean;
   pinnedNoteIds?: string[];
+  handoffEnabled?: boolean;
+  watchFolders?: WatchFolder[];
+}
+
+export type ImportAction = "move" | "copy" | "leave";
+
+export interface WatchFolder {
+  path: string;
+  action: ImportAction;
+  tags: string[];
+  extensions: string[];
 }